@@ -1218,6 +1218,7 @@ def external_crates_repository(name, cargo_lockfile, lockfile, sanitizers_enable
                     "net",
                     "rt",
                     "sync",
+                    "test-util",
                     "time",
                 ],
             ),
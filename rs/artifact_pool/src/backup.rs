@@ -13,8 +13,13 @@
 //! and since we backup all artifacts instantly after the pool update, there is
 //! no possibility to inject purging (or any other deletion) of artifacts
 //! between the pool update and the backup.
+//!
+//! Depending on the configured [`BackupFormat`], every artifact is either
+//! written into its own file, or appended to a packed segment (see
+//! [`crate::packed_backup`]).
 
-use ic_config::artifact_pool::BACKUP_GROUP_SIZE;
+use crate::packed_backup::{self, PackedBackup};
+use ic_config::artifact_pool::{BackupFormat, BACKUP_GROUP_SIZE};
 use ic_interfaces::{
     consensus_pool::{ConsensusPool, HeightRange},
     time_source::TimeSource,
@@ -81,18 +86,46 @@ enum BackupRequest {
     Shutdown,
 }
 
-struct BackupThread {
+/// The destination of the backed up artifacts.
+enum BackupStore {
     // Path pointing to <backup_dir>/<subnet_id>/<replica_version>. It contains all artifacts
-    // backed up by the current replica version.
-    version_path: PathBuf,
+    // backed up by the current replica version, one file per artifact.
+    Directory(PathBuf),
+    // The packed segments in <backup_dir>/<subnet_id>/<replica_version>.
+    Packed(PackedBackup),
+}
+
+impl BackupStore {
+    fn new(version_path: PathBuf, format: BackupFormat) -> Result<Self, io::Error> {
+        match format {
+            BackupFormat::Directory => Ok(Self::Directory(version_path)),
+            BackupFormat::Packed => PackedBackup::open(&version_path).map(Self::Packed),
+        }
+    }
+
+    // Write all backup artifacts to the disk. For the sake of simplicity, we write all
+    // artifacts sequentially.
+    fn store(&mut self, artifacts: Vec<ConsensusMessage>) -> Result<(), io::Error> {
+        backup_artifacts(artifacts).try_for_each(|artifact| match self {
+            Self::Directory(path) => artifact.write_to_disk(path),
+            Self::Packed(packed) => {
+                let (height, file_name) = artifact.height_and_file_name();
+                packed.write(height, file_name, &artifact.serialize()?)
+            }
+        })
+    }
+}
+
+struct BackupThread {
+    store: BackupStore,
     metrics: Metrics,
     log: ReplicaLogger,
 }
 
 impl BackupThread {
-    fn new(version_path: PathBuf, metrics: Metrics, log: ReplicaLogger) -> Self {
+    fn new(store: BackupStore, metrics: Metrics, log: ReplicaLogger) -> Self {
         BackupThread {
-            version_path,
+            store,
             metrics,
             log,
         }
@@ -111,7 +144,7 @@ impl BackupThread {
         loop {
             match rx.recv() {
                 Ok(BackupRequest::Backup(artifacts)) => {
-                    if let Err(err) = store_artifacts(artifacts, &self.version_path) {
+                    if let Err(err) = self.store.store(artifacts) {
                         error!(self.log, "Backup storing failed: {:?}", err);
                         self.metrics.io_errors.inc();
                     }
@@ -249,6 +282,7 @@ impl Backup {
         pool: &dyn ConsensusPool,
         backup_path: PathBuf,
         version_path: PathBuf,
+        format: BackupFormat,
         age_threshold: Duration,
         purge_interval: Duration,
         metrics_registry: MetricsRegistry,
@@ -257,8 +291,34 @@ impl Backup {
         time_source: Arc<dyn TimeSource>,
     ) -> Self {
         let metrics = Metrics::new(&metrics_registry);
+        let mut store = BackupStore::new(version_path.clone(), format).unwrap_or_else(|err| {
+            // The directory format can always be written to. Since readers of the backup
+            // support mixed formats, we can safely fall back to it.
+            error!(
+                log,
+                "Failed to open the packed backup at {:?}, falling back to the directory format: {:?}",
+                version_path,
+                err
+            );
+            metrics.io_errors.inc();
+            BackupStore::Directory(version_path)
+        });
+
+        // Due to the fact that the backup is synced to the disk completely
+        // independently of the consensus pool and always after the consensus pool was
+        // mutated, we might run into an inconsistent state between the pool and the
+        // backup data if the replica gets killed by the orchestrator. To avoid this
+        // situation, on the instantiation of the consensus pool and the backup
+        // component, we need to synchronize the backup with the pool in a blocking
+        // manner.
+        let artifacts = get_all_persisted_artifacts(pool);
+        if let Err(err) = store.store(artifacts) {
+            error!(log, "Backup storing failed: {:?}", err);
+            metrics.io_errors.inc();
+        }
+
         let (backup_queue, backup_thread) =
-            BackupThread::new(version_path.clone(), metrics.clone(), log.clone()).start();
+            BackupThread::new(store, metrics.clone(), log.clone()).start();
         let (purging_queue, purging_thread) = PurgingThread::new(
             backup_path,
             age_threshold,
@@ -267,7 +327,7 @@ impl Backup {
             age,
         )
         .start();
-        let backup = Self {
+        Self {
             time_of_last_purge: RwLock::new(UNIX_EPOCH),
             backup_queue,
             backup_thread: Some(backup_thread),
@@ -277,27 +337,14 @@ impl Backup {
             metrics,
             log,
             time_source,
-        };
-
-        // Due to the fact that the backup is synced to the disk completely
-        // independently of the consensus pool and always after the consensus pool was
-        // mutated, we might run into an inconsistent state between the pool and the
-        // backup data if the replica gets killed by the orchestrator. To avoid this
-        // situation, on the instantiation of the consensus pool and the backup
-        // component, we need to synchronize the backup with the pool in a blocking
-        // manner.
-        let artifacts = get_all_persisted_artifacts(pool);
-        if let Err(err) = store_artifacts(artifacts, &version_path) {
-            error!(backup.log, "Backup storing failed: {:?}", err);
-            backup.metrics.io_errors.inc();
         }
-        backup
     }
 
     pub fn new(
         pool: &dyn ConsensusPool,
         backup_path: PathBuf,
         version_path: PathBuf,
+        format: BackupFormat,
         age_threshold: Duration,
         purge_interval: Duration,
         metrics_registry: MetricsRegistry,
@@ -308,6 +355,7 @@ impl Backup {
             pool,
             backup_path,
             version_path,
+            format,
             age_threshold,
             purge_interval,
             metrics_registry,
@@ -383,34 +431,32 @@ impl Backup {
     }
 }
 
-// Write all backup files to the disk. For the sake of simplicity, we write all
-// artifacts sequentially.
-fn store_artifacts(artifacts: Vec<ConsensusMessage>, path: &Path) -> Result<(), io::Error> {
+// Selects the artifacts that need to be backed up.
+fn backup_artifacts(artifacts: Vec<ConsensusMessage>) -> impl Iterator<Item = BackupArtifact> {
     use ConsensusMessage::*;
-    artifacts
-        .into_iter()
-        .filter_map(|artifact| match artifact {
-            Finalization(artifact) => Some(BackupArtifact::Finalization(Box::new(artifact))),
-            Notarization(artifact) => Some(BackupArtifact::Notarization(Box::new(artifact))),
-            BlockProposal(artifact) => Some(BackupArtifact::BlockProposal(Box::new(artifact))),
-            RandomTape(artifact) => Some(BackupArtifact::RandomTape(Box::new(artifact))),
-            RandomBeacon(artifact) => Some(BackupArtifact::RandomBeacon(Box::new(artifact))),
-            CatchUpPackage(artifact) => Some(BackupArtifact::CatchUpPackage(Box::new(artifact))),
-            // Do not replace by a `_` so that we evaluate at this place if we want to
-            // backup a new artifact!
-            RandomBeaconShare(_)
-            | NotarizationShare(_)
-            | FinalizationShare(_)
-            | RandomTapeShare(_)
-            | CatchUpPackageShare(_) => None,
-        })
-        .try_for_each(|artifact| artifact.write_to_disk(path))
+    artifacts.into_iter().filter_map(|artifact| match artifact {
+        Finalization(artifact) => Some(BackupArtifact::Finalization(Box::new(artifact))),
+        Notarization(artifact) => Some(BackupArtifact::Notarization(Box::new(artifact))),
+        BlockProposal(artifact) => Some(BackupArtifact::BlockProposal(Box::new(artifact))),
+        RandomTape(artifact) => Some(BackupArtifact::RandomTape(Box::new(artifact))),
+        RandomBeacon(artifact) => Some(BackupArtifact::RandomBeacon(Box::new(artifact))),
+        CatchUpPackage(artifact) => Some(BackupArtifact::CatchUpPackage(Box::new(artifact))),
+        // Do not replace by a `_` so that we evaluate at this place if we want to
+        // backup a new artifact!
+        RandomBeaconShare(_)
+        | NotarizationShare(_)
+        | FinalizationShare(_)
+        | RandomTapeShare(_)
+        | CatchUpPackageShare(_) => None,
+    })
 }
 
 /// Traverses the whole backup directory and finds all leaf directories
-/// (containing no other directories). Then it purges all leaves older than the
-/// specified retention time. Age of a leave is determined by calling the given
-/// implementation of [`BackupAge`]
+/// (containing no other directories or packed segments) and all packed
+/// segments. Then it purges all leaves and segments older than the specified
+/// retention time. Age of a leave is determined by calling the given
+/// implementation of [`BackupAge`], the age of a segment by calling it on the
+/// segment index, which is updated with every artifact added to the segment.
 fn purge(
     age_threshold: Duration,
     path: &Path,
@@ -418,35 +464,68 @@ fn purge(
     age: &dyn BackupAge,
 ) -> Result<(), io::Error> {
     let mut leaves = Vec::new();
-    get_leaves(path, &mut leaves)?;
-    for path in leaves {
-        let age = match age.get_elapsed_time(&path) {
-            Ok(time) => time,
-            // According to the documentation of `elapsed` this function may fail as
-            // "the underlying system clock is susceptible to drift and updates". Those
-            // errors are transient and safe to ignore. As they are very rare it's ok to
-            // log a warning.
-            Err(PurgingError::Transient(err)) => {
-                warn!(
-                    log,
-                    "Skipping {:?}, because the modified timestamp couldn't be computed: {:?}",
-                    &path,
-                    err
-                );
-                continue;
+    let mut segments = Vec::new();
+    get_leaves(path, &mut leaves, &mut segments)?;
+    for (dir, key) in segments {
+        if is_expired(
+            age_threshold,
+            &packed_backup::index_path(&dir, key),
+            &log,
+            age,
+        )? {
+            // Remove the segment before its index, so that an interrupted purge leaves
+            // behind an index which is purged the next time.
+            if let Err(err) = fs::remove_file(packed_backup::segment_path(&dir, key)) {
+                if err.kind() != io::ErrorKind::NotFound {
+                    return Err(err);
+                }
             }
-
-            Err(PurgingError::Permanent(err)) => return Err(err),
-        };
-        if age > age_threshold {
+            fs::remove_file(packed_backup::index_path(&dir, key))?;
+        }
+    }
+    for path in leaves {
+        if is_expired(age_threshold, &path, &log, age)? {
             fs::remove_dir_all(path)?;
         }
     }
     Ok(())
 }
 
-// Traverses the given path and returns a list of all leaf directories.
-fn get_leaves(dir: &Path, leaves: &mut Vec<PathBuf>) -> std::io::Result<()> {
+// Returns true if the given path is older than the age threshold.
+fn is_expired(
+    age_threshold: Duration,
+    path: &Path,
+    log: &ReplicaLogger,
+    age: &dyn BackupAge,
+) -> Result<bool, io::Error> {
+    let age = match age.get_elapsed_time(path) {
+        Ok(time) => time,
+        // According to the documentation of `elapsed` this function may fail as
+        // "the underlying system clock is susceptible to drift and updates". Those
+        // errors are transient and safe to ignore. As they are very rare it's ok to
+        // log a warning.
+        Err(PurgingError::Transient(err)) => {
+            warn!(
+                log,
+                "Skipping {:?}, because the modified timestamp couldn't be computed: {:?}",
+                path,
+                err
+            );
+            return Ok(false);
+        }
+
+        Err(PurgingError::Permanent(err)) => return Err(err),
+    };
+    Ok(age > age_threshold)
+}
+
+// Traverses the given path and returns a list of all leaf directories, as well
+// as the directories and keys of all packed segments.
+fn get_leaves(
+    dir: &Path,
+    leaves: &mut Vec<PathBuf>,
+    segments: &mut Vec<(PathBuf, u64)>,
+) -> std::io::Result<()> {
     if !dir.is_dir() {
         return Ok(());
     }
@@ -455,9 +534,14 @@ fn get_leaves(dir: &Path, leaves: &mut Vec<PathBuf>) -> std::io::Result<()> {
         let path = entry?.path();
         if path.is_dir() {
             sub_directory_found = true;
-            get_leaves(&path, leaves)?;
+            get_leaves(&path, leaves, segments)?;
         }
     }
+    // A directory with packed segments is never purged as a whole, since adding
+    // artifacts to a segment doesn't update the modification time of the directory.
+    let segment_keys = packed_backup::segment_keys(dir)?;
+    sub_directory_found |= !segment_keys.is_empty();
+    segments.extend(segment_keys.into_iter().map(|key| (dir.to_path_buf(), key)));
     if !sub_directory_found {
         if let Some(path_name) = dir.to_str() {
             // We skip the folder lost+found, which is currently present on the backup
@@ -607,14 +691,7 @@ impl BackupArtifact {
     /// name.
     pub fn file_location(&self, path: &Path) -> (PathBuf, String) {
         // Create a subdirectory for the height
-        let (height, file_name) = match self {
-            BackupArtifact::Finalization(artifact) => (artifact.height(), "finalization.bin"),
-            BackupArtifact::Notarization(artifact) => (artifact.height(), "notarization.bin"),
-            BackupArtifact::BlockProposal(artifact) => (artifact.height(), "block_proposal.bin"),
-            BackupArtifact::RandomTape(artifact) => (artifact.height(), "random_tape.bin"),
-            BackupArtifact::RandomBeacon(artifact) => (artifact.height(), "random_beacon.bin"),
-            BackupArtifact::CatchUpPackage(artifact) => (artifact.height(), "catch_up_package.bin"),
-        };
+        let (height, file_name) = self.height_and_file_name();
         // We group heights by directories to avoid running into any kind of unexpected
         // FS inode limitations. Each group directory will contain at most
         // [BACKUP_GROUP_SIZE] heights.
@@ -622,6 +699,18 @@ impl BackupArtifact {
         let path_with_height = path.join(group_key.to_string()).join(height.to_string());
         (path_with_height, file_name.to_string())
     }
+
+    /// Returns the height of the artifact and the name of its file.
+    pub fn height_and_file_name(&self) -> (Height, &'static str) {
+        match self {
+            BackupArtifact::Finalization(artifact) => (artifact.height(), "finalization.bin"),
+            BackupArtifact::Notarization(artifact) => (artifact.height(), "notarization.bin"),
+            BackupArtifact::BlockProposal(artifact) => (artifact.height(), "block_proposal.bin"),
+            BackupArtifact::RandomTape(artifact) => (artifact.height(), "random_tape.bin"),
+            BackupArtifact::RandomBeacon(artifact) => (artifact.height(), "random_beacon.bin"),
+            BackupArtifact::CatchUpPackage(artifact) => (artifact.height(), "catch_up_package.bin"),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -2,6 +2,7 @@ use clap::{arg, Arg, Command};
 use ic_artifact_pool::{
     certification_pool::CertificationPoolImpl,
     consensus_pool::{PoolSectionOps, UncachedConsensusPoolImpl},
+    pool_inspection::{inspectable_height_range, PoolInspection},
 };
 use ic_config::artifact_pool::ArtifactPoolConfig;
 use ic_interfaces::consensus_pool::*;
@@ -10,16 +11,18 @@ use ic_metrics::MetricsRegistry;
 use ic_types::{
     consensus::{certification::CertificationMessage, CatchUpPackage, ConsensusMessageHashable},
     time::current_time,
-    NodeId, PrincipalId,
+    Height, NodeId, PrincipalId,
 };
 use prost::Message;
 use serde::{Deserialize, Serialize};
 use serde_bytes_repr::{ByteFmtDeserializer, ByteFmtSerializer};
 use serde_json::{Deserializer, Serializer};
+use std::collections::BTreeSet;
 use std::convert::TryFrom;
 use std::io::BufRead;
 use std::io::Write;
 use std::path::PathBuf;
+use std::str::FromStr;
 
 fn main() {
     let mut app = Command::new("ic-consensus-pool-util")
@@ -51,6 +54,52 @@ fn main() {
                         .takes_value(true),
                 ),
         )
+        .subcommand(
+            Command::new("inspect")
+                .about(
+                    "Print the block tree, random beacon and CatchUpPackage chains, and the \
+                     shares missing from each node",
+                )
+                .arg(
+                    Arg::new("format")
+                        .short('f')
+                        .long("format")
+                        .value_name("FORMAT")
+                        .help("Output format")
+                        .possible_values(["json", "dot"])
+                        .default_value("json")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::new("from-height")
+                        .long("from-height")
+                        .value_name("HEIGHT")
+                        .help("Lowest height to inspect, defaults to the lowest height in the pool")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::new("to-height")
+                        .long("to-height")
+                        .value_name("HEIGHT")
+                        .help(
+                            "Highest height to inspect, defaults to the highest height in the pool",
+                        )
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::new("node")
+                        .short('n')
+                        .long("node")
+                        .value_name("NODE_ID")
+                        .help(
+                            "Node expected to contribute shares, defaults to the subnet \
+                             members recorded in the DKG summary blocks of the pool",
+                        )
+                        .multiple_occurrences(true)
+                        .multiple_values(true)
+                        .takes_value(true),
+                ),
+        )
         .arg(arg!(<PATH>       "PATH to the consensus pool directory"));
     let mut help = Vec::new();
     app.write_help(&mut help)
@@ -65,6 +114,8 @@ fn main() {
         import(path)
     } else if let Some(matches) = matches.subcommand_matches("export-cup-proto") {
         export_cup_proto(path, matches)
+    } else if let Some(matches) = matches.subcommand_matches("inspect") {
+        inspect(path, matches)
     } else {
         eprintln!(
             "{}",
@@ -274,3 +325,53 @@ fn export_cup_proto(path: &str, matches: &clap::ArgMatches) {
     file.write_all(&buf)
         .unwrap_or_else(|err| panic!("Cannot write to file {}: {:?}", filename, err));
 }
+
+fn parse_height(matches: &clap::ArgMatches, name: &str) -> Option<Height> {
+    matches.value_of(name).map(|height| {
+        Height::from(
+            height
+                .parse::<u64>()
+                .unwrap_or_else(|err| panic!("Invalid {} '{}': {}", name, height, err)),
+        )
+    })
+}
+
+fn inspect(path: &str, matches: &clap::ArgMatches) {
+    let nodes = matches.values_of("node").map(|nodes| {
+        nodes
+            .map(|node| {
+                NodeId::from(
+                    PrincipalId::from_str(node)
+                        .unwrap_or_else(|err| panic!("Invalid node id '{}': {}", node, err)),
+                )
+            })
+            .collect::<BTreeSet<_>>()
+    });
+
+    let consensus_pool = open_consensus_pool(path, true);
+    let pool = consensus_pool.validated();
+    let range = match inspectable_height_range(pool) {
+        Some(range) => range,
+        None => {
+            eprintln!("The consensus pool is empty");
+            return;
+        }
+    };
+    let from_height = parse_height(matches, "from-height").unwrap_or(range.min);
+    let to_height = parse_height(matches, "to-height").unwrap_or(range.max);
+    if from_height > to_height {
+        panic!(
+            "from-height {} is above to-height {}",
+            from_height, to_height
+        );
+    }
+
+    let inspection = PoolInspection::new(pool, HeightRange::new(from_height, to_height), nodes);
+    match matches.value_of("format") {
+        Some("dot") => print!("{}", inspection.to_dot()),
+        _ => println!(
+            "{}",
+            serde_json::to_string_pretty(&inspection).expect("Failed to serialize to JSON")
+        ),
+    }
+}
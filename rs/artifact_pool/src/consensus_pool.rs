@@ -380,6 +380,7 @@ impl ConsensusPoolImpl {
                     .spool_path
                     .join(subnet_id.to_string())
                     .join(ic_types::ReplicaVersion::default().to_string()),
+                config.format,
                 Duration::from_secs(config.retention_time_secs),
                 Duration::from_secs(config.purging_interval_secs),
                 registry,
@@ -896,6 +897,7 @@ pub fn build_consensus_block_chain(
 #[cfg(test)]
 mod tests {
     use crate::backup::{BackupAge, PurgingError};
+    use ic_config::artifact_pool::BackupFormat;
 
     use super::*;
     use ic_interfaces::p2p::consensus::UnvalidatedArtifact;
@@ -1518,6 +1520,7 @@ mod tests {
                 &pool,
                 backup_dir.path().into(),
                 root_path.clone(),
+                BackupFormat::Directory,
                 // We purge all artifacts older than 5ms millisecond.
                 Duration::from_millis(100),
                 // We purge every 5 milliseconds.
@@ -1832,6 +1835,106 @@ mod tests {
         })
     }
 
+    #[test]
+    // We create artifacts with the packed backup format, check that they are written
+    // to segments, can be restored and are eventually purged.
+    fn test_packed_backup() {
+        ic_test_utilities::artifact_pool_config::with_test_pool_config(|pool_config| {
+            let time_source = FastForwardTimeSource::new();
+            let backup_dir = tempfile::Builder::new().tempdir().unwrap();
+            let subnet_id = subnet_test_id(0);
+            let root_path = backup_dir
+                .path()
+                .join(subnet_id.to_string())
+                .join(ic_types::ReplicaVersion::default().to_string());
+            let mut pool = new_from_cup_without_bytes(
+                node_test_id(0),
+                subnet_id,
+                make_genesis(ic_types::consensus::dkg::Summary::fake()),
+                pool_config,
+                ic_metrics::MetricsRegistry::new(),
+                no_op_logger(),
+            );
+
+            let purging_interval = Duration::from_millis(100);
+            pool.backup = Some(Backup::new(
+                &pool,
+                backup_dir.path().into(),
+                root_path.clone(),
+                BackupFormat::Packed,
+                Duration::from_millis(100),
+                purging_interval,
+                MetricsRegistry::new(),
+                no_op_logger(),
+                time_source.clone(),
+            ));
+
+            let random_beacon = RandomBeacon::fake(RandomBeaconContent::new(
+                Height::from(1),
+                CryptoHashOf::from(CryptoHash(Vec::new())),
+            ));
+            let random_tape = RandomTape::fake(RandomTapeContent::new(Height::from(1)));
+            let changeset = vec![
+                random_beacon.clone().into_message(),
+                random_tape.clone().into_message(),
+            ]
+            .into_iter()
+            .map(|msg| {
+                ChangeAction::AddToValidated(ValidatedConsensusArtifact {
+                    msg,
+                    timestamp: time_source.get_relative_time(),
+                })
+            })
+            .collect();
+            pool.apply_changes(changeset);
+            pool.backup.as_ref().unwrap().sync_backup();
+
+            // All artifacts are in a single segment, no height directories are created.
+            assert_eq!(
+                crate::packed_backup::segment_keys(&root_path).unwrap(),
+                vec![0]
+            );
+            assert!(!root_path.join("0").exists());
+
+            let packed = crate::packed_backup::PackedBackup::open(&root_path).unwrap();
+            assert_eq!(
+                packed.file_names(Height::from(0)),
+                vec!["catch_up_package.bin", "random_beacon.bin"]
+            );
+            assert_eq!(
+                packed.file_names(Height::from(1)),
+                vec!["random_beacon.bin", "random_tape.bin"]
+            );
+            let buffer = packed
+                .read(Height::from(1), "random_beacon.bin")
+                .unwrap()
+                .unwrap();
+            let restored =
+                RandomBeacon::try_from(pb::RandomBeacon::decode(buffer.as_slice()).unwrap())
+                    .unwrap();
+            assert_eq!(random_beacon, restored);
+            let buffer = packed
+                .read(Height::from(1), "random_tape.bin")
+                .unwrap()
+                .unwrap();
+            let restored =
+                RandomTape::try_from(pb::RandomTape::decode(buffer.as_slice()).unwrap()).unwrap();
+            assert_eq!(random_tape, restored);
+
+            // Once purging is overdue, the whole segment is purged.
+            std::thread::sleep(purging_interval);
+            time_source
+                .set_time(time_source.get_relative_time() + purging_interval)
+                .unwrap();
+            pool.apply_changes(Vec::new());
+            pool.backup.as_ref().unwrap().sync_purging();
+            assert!(crate::packed_backup::segment_keys(&root_path)
+                .unwrap()
+                .is_empty());
+            assert_eq!(fs::read_dir(&root_path).unwrap().count(), 0);
+        })
+    }
+
     #[test]
     fn test_backup_purging() {
         struct FakeAge {
@@ -1891,6 +1994,7 @@ mod tests {
                 &pool,
                 backup_dir.path().into(),
                 backup_dir.path().join(format!("{:?}", subnet_id)),
+                BackupFormat::Directory,
                 // Artifact retention time
                 Duration::from_millis(2700),
                 purging_interval,
@@ -9,12 +9,14 @@ pub mod ingress_pool;
 mod inmemory_pool;
 mod metrics;
 mod pool_common;
+pub mod pool_inspection;
 #[cfg(test)]
 mod test_utils;
 
 pub mod backup;
 mod lmdb_iterator;
 mod lmdb_pool;
+pub mod packed_backup;
 
 #[cfg(feature = "rocksdb_backend")]
 mod rocksdb_iterator;
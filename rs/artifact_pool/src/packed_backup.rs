@@ -0,0 +1,532 @@
+//! A packed on-disk format for the consensus artifact backup.
+//!
+//! Instead of writing every artifact into its own file, the artifacts of
+//! [`BACKUP_GROUP_SIZE`] consecutive heights are appended to a single segment
+//! file, next to an index that locates them:
+//!
+//! ```text
+//! <subnet_id>/<replica_version>/<(height / N) * N>.segment
+//! <subnet_id>/<replica_version>/<(height / N) * N>.index
+//! ```
+//!
+//! A segment is a sequence of independently zstd compressed artifacts, so that
+//! any artifact can be read without decompressing the rest of the segment. The
+//! index is a text file with one entry per line:
+//!
+//! ```text
+//! <height> <file_name> <offset> <length>
+//! ```
+//!
+//! where `file_name` is the name of the artifact in the directory layout (see
+//! [`crate::backup::BackupArtifact::file_location`]), and `offset` and
+//! `length` locate its compressed bytes in the segment. Both files are only
+//! ever appended to. A later entry for the same height and file name replaces
+//! an earlier one, and an entry with length 0 removes it. Entries which are
+//! incomplete or point past the end of the segment, e.g. because the replica
+//! was killed while writing them, are ignored.
+
+use ic_config::artifact_pool::BACKUP_GROUP_SIZE;
+use ic_types::Height;
+use std::{
+    collections::BTreeMap,
+    fs::{self, File, OpenOptions},
+    io::{self, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+
+pub const SEGMENT_EXTENSION: &str = "segment";
+pub const INDEX_EXTENSION: &str = "index";
+
+// The zstd compression level of the artifacts in a segment.
+const COMPRESSION_LEVEL: i32 = 3;
+
+/// The location of the compressed bytes of an artifact in its segment.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Entry {
+    offset: u64,
+    length: u64,
+}
+
+/// The in-memory index of a segment.
+#[derive(Default)]
+struct Segment {
+    entries: BTreeMap<Height, BTreeMap<String, Entry>>,
+    // True if the last line of the index file is incomplete, in which case the
+    // next entry has to start on a new line.
+    needs_newline: bool,
+}
+
+impl Segment {
+    fn load(path: &Path, key: u64) -> io::Result<Self> {
+        let index = fs::read_to_string(index_path(path, key))?;
+        let segment_len = match fs::metadata(segment_path(path, key)) {
+            Ok(metadata) => metadata.len(),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => 0,
+            Err(err) => return Err(err),
+        };
+
+        let mut segment = Segment {
+            needs_newline: !index.is_empty() && !index.ends_with('\n'),
+            ..Default::default()
+        };
+        for line in index.lines() {
+            let Some((height, file_name, entry)) = parse_entry(line) else {
+                continue;
+            };
+            if entry.length == 0 {
+                segment.remove(height, &file_name);
+            } else if entry.offset.saturating_add(entry.length) <= segment_len {
+                segment.insert(height, file_name, entry);
+            }
+        }
+        Ok(segment)
+    }
+
+    fn get(&self, height: Height, file_name: &str) -> Option<Entry> {
+        self.entries.get(&height)?.get(file_name).copied()
+    }
+
+    fn insert(&mut self, height: Height, file_name: String, entry: Entry) {
+        self.entries
+            .entry(height)
+            .or_default()
+            .insert(file_name, entry);
+    }
+
+    fn remove(&mut self, height: Height, file_name: &str) {
+        if let Some(names) = self.entries.get_mut(&height) {
+            names.remove(file_name);
+            if names.is_empty() {
+                self.entries.remove(&height);
+            }
+        }
+    }
+}
+
+fn parse_entry(line: &str) -> Option<(Height, String, Entry)> {
+    let mut fields = line.split(' ');
+    let height = fields.next()?.parse::<u64>().ok()?;
+    let file_name = fields.next().filter(|name| !name.is_empty())?;
+    let offset = fields.next()?.parse::<u64>().ok()?;
+    let length = fields.next()?.parse::<u64>().ok()?;
+    if fields.next().is_some() {
+        return None;
+    }
+    Some((
+        Height::from(height),
+        file_name.to_string(),
+        Entry { offset, length },
+    ))
+}
+
+/// The packed backup of a single replica version.
+pub struct PackedBackup {
+    path: PathBuf,
+    segments: BTreeMap<u64, Segment>,
+}
+
+impl PackedBackup {
+    /// Opens the packed backup in the directory `path` and loads the indices of
+    /// all its segments. The directory does not need to exist.
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let mut segments = BTreeMap::new();
+        for key in segment_keys(path)? {
+            segments.insert(key, Segment::load(path, key)?);
+        }
+        Ok(Self {
+            path: path.to_path_buf(),
+            segments,
+        })
+    }
+
+    /// Returns all heights with at least one artifact, in ascending order.
+    pub fn heights(&self) -> impl Iterator<Item = Height> + '_ {
+        self.segments
+            .values()
+            .flat_map(|segment| segment.entries.keys().copied())
+    }
+
+    /// Returns the highest height with at least one artifact.
+    pub fn top_height(&self) -> Option<Height> {
+        self.segments
+            .values()
+            .rev()
+            .find_map(|segment| segment.entries.keys().next_back().copied())
+    }
+
+    /// Returns the file names of all artifacts at `height`.
+    pub fn file_names(&self, height: Height) -> Vec<String> {
+        self.segments
+            .get(&group_key(height))
+            .and_then(|segment| segment.entries.get(&height))
+            .map(|names| names.keys().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Returns true if the backup contains an artifact with `file_name` at
+    /// `height`.
+    pub fn contains(&self, height: Height, file_name: &str) -> bool {
+        self.entry(height, file_name).is_some()
+    }
+
+    /// Reads and decompresses the artifact with `file_name` at `height`.
+    pub fn read(&self, height: Height, file_name: &str) -> io::Result<Option<Vec<u8>>> {
+        let Some(entry) = self.entry(height, file_name) else {
+            return Ok(None);
+        };
+        let mut file = File::open(segment_path(&self.path, group_key(height)))?;
+        file.seek(SeekFrom::Start(entry.offset))?;
+        let mut compressed = vec![0; entry.length as usize];
+        file.read_exact(&mut compressed)?;
+        zstd::decode_all(compressed.as_slice()).map(Some)
+    }
+
+    /// Compresses and appends the artifact with `file_name` at `height` to its
+    /// segment. Does nothing if the backup already contains the artifact.
+    pub fn write(&mut self, height: Height, file_name: &str, bytes: &[u8]) -> io::Result<()> {
+        if self.contains(height, file_name) {
+            return Ok(());
+        }
+        let compressed = zstd::encode_all(bytes, COMPRESSION_LEVEL)?;
+
+        fs::create_dir_all(&self.path)?;
+        let mut segment_file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(segment_path(&self.path, group_key(height)))?;
+        let offset = segment_file.metadata()?.len();
+        segment_file.write_all(&compressed)?;
+        // The segment has to be persisted before the index entry pointing into it.
+        segment_file.sync_data()?;
+
+        self.append_entry(
+            height,
+            file_name,
+            Entry {
+                offset,
+                length: compressed.len() as u64,
+            },
+        )
+    }
+
+    /// Renames the artifact with `file_name` at `height` to `new_file_name`.
+    pub fn rename(
+        &mut self,
+        height: Height,
+        file_name: &str,
+        new_file_name: &str,
+    ) -> io::Result<()> {
+        let entry = self.entry(height, file_name).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("No artifact {} at height {}", file_name, height),
+            )
+        })?;
+        self.append_entry(height, new_file_name, entry)?;
+        self.append_entry(
+            height,
+            file_name,
+            Entry {
+                offset: 0,
+                length: 0,
+            },
+        )
+    }
+
+    fn entry(&self, height: Height, file_name: &str) -> Option<Entry> {
+        self.segments
+            .get(&group_key(height))?
+            .get(height, file_name)
+    }
+
+    fn append_entry(&mut self, height: Height, file_name: &str, entry: Entry) -> io::Result<()> {
+        if file_name.is_empty() || file_name.contains(char::is_whitespace) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Invalid artifact file name {:?}", file_name),
+            ));
+        }
+        let key = group_key(height);
+        let segment = self.segments.entry(key).or_default();
+
+        let mut line = String::new();
+        if segment.needs_newline {
+            line.push('\n');
+        }
+        line.push_str(&format!(
+            "{} {} {} {}\n",
+            height, file_name, entry.offset, entry.length
+        ));
+        let mut index_file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(index_path(&self.path, key))?;
+        index_file.write_all(line.as_bytes())?;
+        index_file.sync_data()?;
+
+        segment.needs_newline = false;
+        if entry.length == 0 {
+            segment.remove(height, file_name);
+        } else {
+            segment.insert(height, file_name.to_string(), entry);
+        }
+        Ok(())
+    }
+}
+
+/// Returns the key of the segment containing the artifacts at `height`.
+fn group_key(height: Height) -> u64 {
+    (height.get() / BACKUP_GROUP_SIZE) * BACKUP_GROUP_SIZE
+}
+
+/// Returns the path of the segment file with the given key.
+pub fn segment_path(path: &Path, key: u64) -> PathBuf {
+    path.join(format!("{}.{}", key, SEGMENT_EXTENSION))
+}
+
+/// Returns the path of the index file of the segment with the given key.
+pub fn index_path(path: &Path, key: u64) -> PathBuf {
+    path.join(format!("{}.{}", key, INDEX_EXTENSION))
+}
+
+/// Returns the keys of all segments with an index in the directory `path`, in
+/// ascending order.
+pub fn segment_keys(path: &Path) -> io::Result<Vec<u64>> {
+    let entries = match fs::read_dir(path) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err),
+    };
+    let mut keys = Vec::new();
+    for entry in entries {
+        let file = entry?.path();
+        if file.extension().and_then(|ext| ext.to_str()) != Some(INDEX_EXTENSION) {
+            continue;
+        }
+        if let Some(key) = file
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .and_then(|stem| stem.parse::<u64>().ok())
+        {
+            keys.push(key);
+        }
+    }
+    keys.sort_unstable();
+    Ok(keys)
+}
+
+/// Returns true if the directory `path` contains packed segments.
+pub fn is_packed(path: &Path) -> bool {
+    segment_keys(path)
+        .map(|keys| !keys.is_empty())
+        .unwrap_or(false)
+}
+
+/// Converts the directory backup of a replica version in `path` into packed
+/// segments, in place. Each group directory is removed once all of its
+/// artifacts have been packed, so an interrupted conversion can be resumed.
+/// Returns the number of packed artifacts.
+pub fn pack_directory_backup(path: &Path) -> io::Result<usize> {
+    let mut packed = PackedBackup::open(path)?;
+    let mut group_dirs = Vec::new();
+    for entry in fs::read_dir(path)? {
+        let group_dir = entry?.path();
+        if group_dir.is_dir() {
+            group_dirs.push(group_dir);
+        }
+    }
+    group_dirs.sort();
+
+    let mut packed_artifacts = 0;
+    for group_dir in group_dirs {
+        for entry in fs::read_dir(&group_dir)? {
+            let height_dir = entry?.path();
+            let height = parse_file_name::<u64>(&height_dir)?;
+            for entry in fs::read_dir(&height_dir)? {
+                let file = entry?.path();
+                let file_name = parse_file_name::<String>(&file)?;
+                packed.write(Height::from(height), &file_name, &fs::read(&file)?)?;
+                packed_artifacts += 1;
+            }
+        }
+        fs::remove_dir_all(&group_dir)?;
+    }
+    Ok(packed_artifacts)
+}
+
+fn parse_file_name<T: std::str::FromStr>(path: &Path) -> io::Result<T> {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .and_then(|name| name.parse::<T>().ok())
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Unexpected entry in the backup: {:?}", path),
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_and_read() {
+        let dir = tempfile::Builder::new().tempdir().unwrap();
+        let path = dir.path().join("version");
+        let mut packed = PackedBackup::open(&path).unwrap();
+        packed
+            .write(Height::from(1), "random_beacon.bin", b"beacon")
+            .unwrap();
+        packed
+            .write(Height::from(1), "random_tape.bin", b"tape")
+            .unwrap();
+        packed
+            .write(Height::from(BACKUP_GROUP_SIZE + 2), "notarization.bin", b"")
+            .unwrap();
+        // Writing an artifact again is a no-op.
+        packed
+            .write(Height::from(1), "random_beacon.bin", b"other")
+            .unwrap();
+
+        for packed in [packed, PackedBackup::open(&path).unwrap()] {
+            assert_eq!(
+                packed.heights().collect::<Vec<_>>(),
+                vec![Height::from(1), Height::from(BACKUP_GROUP_SIZE + 2)]
+            );
+            assert_eq!(
+                packed.top_height(),
+                Some(Height::from(BACKUP_GROUP_SIZE + 2))
+            );
+            assert_eq!(
+                packed.file_names(Height::from(1)),
+                vec!["random_beacon.bin", "random_tape.bin"]
+            );
+            assert_eq!(
+                packed.read(Height::from(1), "random_beacon.bin").unwrap(),
+                Some(b"beacon".to_vec())
+            );
+            assert_eq!(
+                packed.read(Height::from(1), "random_tape.bin").unwrap(),
+                Some(b"tape".to_vec())
+            );
+            assert_eq!(
+                packed
+                    .read(Height::from(BACKUP_GROUP_SIZE + 2), "notarization.bin")
+                    .unwrap(),
+                Some(Vec::new())
+            );
+            assert_eq!(
+                packed.read(Height::from(2), "random_tape.bin").unwrap(),
+                None
+            );
+        }
+        assert_eq!(segment_keys(&path).unwrap(), vec![0, BACKUP_GROUP_SIZE]);
+    }
+
+    #[test]
+    fn test_rename() {
+        let dir = tempfile::Builder::new().tempdir().unwrap();
+        let mut packed = PackedBackup::open(dir.path()).unwrap();
+        packed
+            .write(Height::from(7), "finalization.bin", b"finalization")
+            .unwrap();
+        packed
+            .rename(
+                Height::from(7),
+                "finalization.bin",
+                "invalid_finalization.bin",
+            )
+            .unwrap();
+        assert!(packed
+            .rename(
+                Height::from(7),
+                "finalization.bin",
+                "invalid_finalization.bin"
+            )
+            .is_err());
+
+        let packed = PackedBackup::open(dir.path()).unwrap();
+        assert!(!packed.contains(Height::from(7), "finalization.bin"));
+        assert_eq!(
+            packed
+                .read(Height::from(7), "invalid_finalization.bin")
+                .unwrap(),
+            Some(b"finalization".to_vec())
+        );
+    }
+
+    #[test]
+    fn test_incomplete_entries_are_ignored() {
+        let dir = tempfile::Builder::new().tempdir().unwrap();
+        let mut packed = PackedBackup::open(dir.path()).unwrap();
+        packed
+            .write(Height::from(3), "random_beacon.bin", b"beacon")
+            .unwrap();
+
+        // Simulate a crash while writing an artifact and its index entry.
+        let mut segment_file = OpenOptions::new()
+            .append(true)
+            .open(segment_path(dir.path(), 0))
+            .unwrap();
+        segment_file.write_all(b"garbage").unwrap();
+        let mut index_file = OpenOptions::new()
+            .append(true)
+            .open(index_path(dir.path(), 0))
+            .unwrap();
+        index_file.write_all(b"3 random_tape.bin 1000 10").unwrap();
+
+        let mut packed = PackedBackup::open(dir.path()).unwrap();
+        assert_eq!(
+            packed.file_names(Height::from(3)),
+            vec!["random_beacon.bin"]
+        );
+        packed
+            .write(Height::from(3), "random_tape.bin", b"tape")
+            .unwrap();
+
+        let packed = PackedBackup::open(dir.path()).unwrap();
+        assert_eq!(
+            packed.read(Height::from(3), "random_tape.bin").unwrap(),
+            Some(b"tape".to_vec())
+        );
+        assert_eq!(
+            packed.read(Height::from(3), "random_beacon.bin").unwrap(),
+            Some(b"beacon".to_vec())
+        );
+    }
+
+    #[test]
+    fn test_pack_directory_backup() {
+        let dir = tempfile::Builder::new().tempdir().unwrap();
+        let height_dir = dir.path().join("0").join("5");
+        fs::create_dir_all(&height_dir).unwrap();
+        fs::write(height_dir.join("random_beacon.bin"), b"beacon").unwrap();
+        fs::write(height_dir.join("catch_up_package.bin"), b"cup").unwrap();
+        let height_dir = dir
+            .path()
+            .join(BACKUP_GROUP_SIZE.to_string())
+            .join((BACKUP_GROUP_SIZE + 1).to_string());
+        fs::create_dir_all(&height_dir).unwrap();
+        fs::write(height_dir.join("random_tape.bin"), b"tape").unwrap();
+
+        assert_eq!(pack_directory_backup(dir.path()).unwrap(), 3);
+        assert!(is_packed(dir.path()));
+        assert!(!dir.path().join("0").exists());
+        assert!(!dir.path().join(BACKUP_GROUP_SIZE.to_string()).exists());
+
+        let packed = PackedBackup::open(dir.path()).unwrap();
+        assert_eq!(
+            packed
+                .read(Height::from(5), "catch_up_package.bin")
+                .unwrap(),
+            Some(b"cup".to_vec())
+        );
+        assert_eq!(
+            packed
+                .read(Height::from(BACKUP_GROUP_SIZE + 1), "random_tape.bin")
+                .unwrap(),
+            Some(b"tape".to_vec())
+        );
+    }
+}
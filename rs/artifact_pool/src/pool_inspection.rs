@@ -0,0 +1,737 @@
+//! Structured, read-only views of a validated consensus pool.
+//!
+//! A [`PoolInspection`] summarizes the block tree, the random beacon and
+//! catch-up package chains and the shares contributed by every node over a
+//! range of heights. It is meant for diagnosing stalled subnets from a copy of
+//! their consensus pool, without replaying any state.
+use ic_interfaces::consensus_pool::{HeightRange, PoolSection, ValidatedConsensusArtifact};
+use ic_types::{
+    consensus::Block,
+    crypto::{threshold_sig::ni_dkg::NiDkgTag, CryptoHash},
+    Height, NodeId,
+};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+
+/// A block proposal together with the notarization and finalization state of
+/// its block.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct InspectedBlock {
+    pub hash: String,
+    pub parent: String,
+    pub rank: u64,
+    pub proposer: String,
+    pub notarized: bool,
+    pub finalized: bool,
+    /// Nodes that signed a notarization share for this block.
+    pub notarization_shares: Vec<String>,
+    /// Nodes that signed a finalization share for this block.
+    pub finalization_shares: Vec<String>,
+}
+
+/// A random beacon, identified by its hash and the hash of its parent.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct InspectedRandomBeacon {
+    pub hash: String,
+    pub parent: String,
+}
+
+/// Everything the pool holds at a single height.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct InspectedHeight {
+    pub height: u64,
+    pub blocks: Vec<InspectedBlock>,
+    pub random_beacon: Option<InspectedRandomBeacon>,
+    /// Nodes that signed a random beacon share.
+    pub random_beacon_shares: Vec<String>,
+    /// Expected nodes that did not contribute to the random beacon, although
+    /// others did.
+    pub missing_random_beacon_shares: Vec<String>,
+    /// Expected nodes that did not contribute to the notarization of any
+    /// block, although others did.
+    pub missing_notarization_shares: Vec<String>,
+    /// Expected nodes that did not contribute to the finalization of any
+    /// block, although others did.
+    pub missing_finalization_shares: Vec<String>,
+}
+
+/// A catch-up package, identified by the hashes of its contents.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct InspectedCatchUpPackage {
+    pub height: u64,
+    pub block: String,
+    pub random_beacon: String,
+    pub state_hash: String,
+}
+
+/// The number of heights at which a node proposed blocks or failed to
+/// contribute shares.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct NodeSummary {
+    pub node: String,
+    pub block_proposals: u64,
+    pub missing_random_beacon_shares: u64,
+    pub missing_notarization_shares: u64,
+    pub missing_finalization_shares: u64,
+}
+
+/// A structured view of the validated section of a consensus pool.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct PoolInspection {
+    pub from_height: u64,
+    pub to_height: u64,
+    pub heights: Vec<InspectedHeight>,
+    pub catch_up_packages: Vec<InspectedCatchUpPackage>,
+    pub nodes: Vec<NodeSummary>,
+}
+
+/// Returns the range of heights at which the pool holds any blocks, random
+/// beacons, catch-up packages or shares for them.
+pub fn inspectable_height_range(
+    pool: &dyn PoolSection<ValidatedConsensusArtifact>,
+) -> Option<HeightRange> {
+    [
+        pool.block_proposal().height_range(),
+        pool.notarization().height_range(),
+        pool.finalization().height_range(),
+        pool.random_beacon().height_range(),
+        pool.notarization_share().height_range(),
+        pool.finalization_share().height_range(),
+        pool.random_beacon_share().height_range(),
+        pool.catch_up_package().height_range(),
+    ]
+    .into_iter()
+    .flatten()
+    .reduce(|a, b| HeightRange::new(a.min.min(b.min), a.max.max(b.max)))
+}
+
+impl PoolInspection {
+    /// Inspects the heights in `range` of the given pool.
+    ///
+    /// Shares are expected from all `nodes`, or, if none are given, from the
+    /// members of the subnet at each height, as recorded in the latest DKG
+    /// summary block at or below that height held by the pool, e.g. in a
+    /// catch-up package. Only at heights below any such summary block are
+    /// shares expected from the nodes that proposed a block or signed anything
+    /// in `range`. A node is only reported missing for a kind of share at a
+    /// height where some other node contributed one, because the pool purges
+    /// shares once they are no longer needed.
+    pub fn new(
+        pool: &dyn PoolSection<ValidatedConsensusArtifact>,
+        range: HeightRange,
+        nodes: Option<BTreeSet<NodeId>>,
+    ) -> Self {
+        let mut observed = BTreeSet::new();
+        let mut contributions = Vec::new();
+        let mut heights = Vec::new();
+        for h in range.min.get()..=range.max.get() {
+            let (inspected, contribution) = inspect_height(pool, Height::from(h));
+            observed.extend(contribution.proposers.iter().cloned());
+            observed.extend(contribution.random_beacon.iter().cloned());
+            observed.extend(contribution.notarization.iter().cloned());
+            observed.extend(contribution.finalization.iter().cloned());
+            heights.push(inspected);
+            contributions.push(contribution);
+        }
+        let members = match &nodes {
+            Some(_) => BTreeMap::new(),
+            None => subnet_members(pool, range.max),
+        };
+        let expected: Vec<BTreeSet<NodeId>> = (range.min.get()..=range.max.get())
+            .map(|h| match &nodes {
+                Some(nodes) => nodes.clone(),
+                None => members
+                    .range(..=Height::from(h))
+                    .next_back()
+                    .map_or_else(|| observed.clone(), |(_, members)| members.clone()),
+            })
+            .collect();
+
+        let mut summaries: BTreeMap<NodeId, NodeSummary> = expected
+            .iter()
+            .flatten()
+            .map(|node| {
+                let summary = NodeSummary {
+                    node: node_to_string(node),
+                    ..Default::default()
+                };
+                (*node, summary)
+            })
+            .collect();
+        for ((inspected, contribution), expected) in
+            heights.iter_mut().zip(contributions).zip(&expected)
+        {
+            for node in &contribution.proposers {
+                if let Some(summary) = summaries.get_mut(node) {
+                    summary.block_proposals += 1;
+                }
+            }
+            inspected.missing_random_beacon_shares = missing(expected, &contribution.random_beacon);
+            inspected.missing_notarization_shares = missing(expected, &contribution.notarization);
+            inspected.missing_finalization_shares = missing(expected, &contribution.finalization);
+            for node in expected {
+                let summary = summaries
+                    .get_mut(node)
+                    .expect("Every expected node has a summary");
+                let absent = |contributors: &BTreeSet<NodeId>| {
+                    u64::from(!contributors.is_empty() && !contributors.contains(node))
+                };
+                summary.missing_random_beacon_shares += absent(&contribution.random_beacon);
+                summary.missing_notarization_shares += absent(&contribution.notarization);
+                summary.missing_finalization_shares += absent(&contribution.finalization);
+            }
+        }
+
+        let mut catch_up_packages: Vec<_> = pool
+            .catch_up_package()
+            .get_all()
+            .map(|cup| InspectedCatchUpPackage {
+                height: cup.content.block.as_ref().height.get(),
+                block: hash_to_string(cup.content.block.get_hash().get_ref()),
+                random_beacon: hash_to_string(cup.content.random_beacon.get_hash().get_ref()),
+                state_hash: hash_to_string(cup.content.state_hash.get_ref()),
+            })
+            .collect();
+        catch_up_packages.sort_by_key(|cup| cup.height);
+
+        PoolInspection {
+            from_height: range.min.get(),
+            to_height: range.max.get(),
+            heights,
+            catch_up_packages,
+            nodes: summaries.into_values().collect(),
+        }
+    }
+
+    /// Renders the block tree and the random beacon chain in the DOT
+    /// language.
+    ///
+    /// Notarized blocks are drawn with a bold outline and finalized blocks are
+    /// filled.
+    pub fn to_dot(&self) -> String {
+        let mut known_blocks = BTreeSet::new();
+        let mut known_beacons = BTreeSet::new();
+        for height in &self.heights {
+            known_blocks.extend(height.blocks.iter().map(|block| block.hash.as_str()));
+            known_beacons.extend(
+                height
+                    .random_beacon
+                    .iter()
+                    .map(|beacon| beacon.hash.as_str()),
+            );
+        }
+
+        let mut out = String::new();
+        writeln!(out, "digraph consensus_pool {{").unwrap();
+        writeln!(out, "  rankdir=BT;").unwrap();
+        writeln!(out, "  node [shape=box];").unwrap();
+        writeln!(out, "  subgraph cluster_blocks {{").unwrap();
+        writeln!(out, "    label=\"blocks\";").unwrap();
+        for height in self.heights.iter().filter(|h| !h.blocks.is_empty()) {
+            writeln!(out, "    {{ rank=same;").unwrap();
+            for block in &height.blocks {
+                let mut style = Vec::new();
+                if block.notarized {
+                    style.push("bold");
+                }
+                if block.finalized {
+                    style.push("filled");
+                }
+                writeln!(
+                    out,
+                    "      \"b_{}\" [label=\"h={} r={}\\n{}\\nproposer {}\\nnotarization shares {}\\nfinalization shares {}\", style=\"{}\"];",
+                    block.hash,
+                    height.height,
+                    block.rank,
+                    short(&block.hash),
+                    short(&block.proposer),
+                    block.notarization_shares.len(),
+                    block.finalization_shares.len(),
+                    style.join(","),
+                )
+                .unwrap();
+            }
+            writeln!(out, "    }}").unwrap();
+        }
+        for block in self.heights.iter().flat_map(|h| h.blocks.iter()) {
+            if known_blocks.contains(block.parent.as_str()) {
+                writeln!(out, "    \"b_{}\" -> \"b_{}\";", block.hash, block.parent).unwrap();
+            }
+        }
+        writeln!(out, "  }}").unwrap();
+
+        writeln!(out, "  subgraph cluster_random_beacons {{").unwrap();
+        writeln!(out, "    label=\"random beacons\";").unwrap();
+        for height in &self.heights {
+            if let Some(beacon) = &height.random_beacon {
+                writeln!(
+                    out,
+                    "    \"rb_{}\" [label=\"h={}\\n{}\", shape=ellipse];",
+                    beacon.hash,
+                    height.height,
+                    short(&beacon.hash),
+                )
+                .unwrap();
+                if known_beacons.contains(beacon.parent.as_str()) {
+                    writeln!(
+                        out,
+                        "    \"rb_{}\" -> \"rb_{}\";",
+                        beacon.hash, beacon.parent
+                    )
+                    .unwrap();
+                }
+            }
+        }
+        writeln!(out, "  }}").unwrap();
+
+        writeln!(out, "  subgraph cluster_catch_up_packages {{").unwrap();
+        writeln!(out, "    label=\"catch-up packages\";").unwrap();
+        let mut previous = None;
+        for cup in &self.catch_up_packages {
+            writeln!(
+                out,
+                "    \"cup_{}\" [label=\"CUP h={}\\nstate {}\", shape=hexagon];",
+                cup.height,
+                cup.height,
+                short(&cup.state_hash),
+            )
+            .unwrap();
+            if let Some(previous) = previous {
+                writeln!(out, "    \"cup_{}\" -> \"cup_{}\";", cup.height, previous).unwrap();
+            }
+            previous = Some(cup.height);
+        }
+        writeln!(out, "  }}").unwrap();
+        for cup in &self.catch_up_packages {
+            if known_blocks.contains(cup.block.as_str()) {
+                writeln!(
+                    out,
+                    "  \"cup_{}\" -> \"b_{}\" [style=dashed];",
+                    cup.height, cup.block
+                )
+                .unwrap();
+            }
+        }
+        writeln!(out, "}}").unwrap();
+        out
+    }
+}
+
+/// The nodes that contributed to each kind of artifact at a single height.
+#[derive(Default)]
+struct Contribution {
+    proposers: BTreeSet<NodeId>,
+    random_beacon: BTreeSet<NodeId>,
+    notarization: BTreeSet<NodeId>,
+    finalization: BTreeSet<NodeId>,
+}
+
+/// Returns the members of the subnet in every DKG interval whose summary block
+/// is held by the pool up to `max_height`, by the height of the summary block.
+///
+/// The members are the receivers of the current low-threshold transcript of
+/// the summary, which are the nodes of the subnet at the registry version the
+/// interval uses.
+fn subnet_members(
+    pool: &dyn PoolSection<ValidatedConsensusArtifact>,
+    max_height: Height,
+) -> BTreeMap<Height, BTreeSet<NodeId>> {
+    let members = |block: &Block| {
+        let payload = block.payload.as_ref();
+        payload.is_summary().then(|| {
+            let committee = &payload
+                .as_summary()
+                .dkg
+                .current_transcript(&NiDkgTag::LowThreshold)
+                .committee;
+            (block.height, committee.get().clone())
+        })
+    };
+    let mut members_by_height: BTreeMap<_, _> = pool
+        .catch_up_package()
+        .get_all()
+        .filter_map(|cup| members(cup.content.block.as_ref()))
+        .collect();
+    if let Some(range) = pool.block_proposal().height_range() {
+        members_by_height.extend(
+            pool.block_proposal()
+                .get_by_height_range(HeightRange::new(range.min, max_height))
+                .filter_map(|proposal| members(proposal.content.as_ref())),
+        );
+    }
+    members_by_height
+}
+
+fn inspect_height(
+    pool: &dyn PoolSection<ValidatedConsensusArtifact>,
+    height: Height,
+) -> (InspectedHeight, Contribution) {
+    let mut contribution = Contribution::default();
+
+    let mut notarizations = BTreeMap::<CryptoHash, Vec<NodeId>>::new();
+    for notarization in pool.notarization().get_by_height(height) {
+        contribution
+            .notarization
+            .extend(notarization.signature.signers.iter().cloned());
+        notarizations.insert(
+            notarization.content.block.get_ref().clone(),
+            notarization.signature.signers,
+        );
+    }
+    let mut finalizations = BTreeMap::<CryptoHash, Vec<NodeId>>::new();
+    for finalization in pool.finalization().get_by_height(height) {
+        contribution
+            .finalization
+            .extend(finalization.signature.signers.iter().cloned());
+        finalizations.insert(
+            finalization.content.block.get_ref().clone(),
+            finalization.signature.signers,
+        );
+    }
+    let mut notarization_shares = BTreeMap::<CryptoHash, BTreeSet<NodeId>>::new();
+    for share in pool.notarization_share().get_by_height(height) {
+        contribution.notarization.insert(share.signature.signer);
+        notarization_shares
+            .entry(share.content.block.get_ref().clone())
+            .or_default()
+            .insert(share.signature.signer);
+    }
+    let mut finalization_shares = BTreeMap::<CryptoHash, BTreeSet<NodeId>>::new();
+    for share in pool.finalization_share().get_by_height(height) {
+        contribution.finalization.insert(share.signature.signer);
+        finalization_shares
+            .entry(share.content.block.get_ref().clone())
+            .or_default()
+            .insert(share.signature.signer);
+    }
+
+    let mut blocks: Vec<_> = pool
+        .block_proposal()
+        .get_by_height(height)
+        .map(|proposal| {
+            let hash = proposal.content.get_hash().get_ref();
+            let block = proposal.content.as_ref();
+            contribution.proposers.insert(proposal.signature.signer);
+            InspectedBlock {
+                hash: hash_to_string(hash),
+                parent: hash_to_string(block.parent.get_ref()),
+                rank: block.rank.0,
+                proposer: node_to_string(&proposal.signature.signer),
+                notarized: notarizations.contains_key(hash),
+                finalized: finalizations.contains_key(hash),
+                notarization_shares: nodes_to_strings(notarization_shares.get(hash)),
+                finalization_shares: nodes_to_strings(finalization_shares.get(hash)),
+            }
+        })
+        .collect();
+    blocks.sort_by(|a, b| (a.rank, &a.hash).cmp(&(b.rank, &b.hash)));
+
+    let random_beacon = pool
+        .random_beacon()
+        .get_by_height(height)
+        .next()
+        .map(|beacon| InspectedRandomBeacon {
+            hash: hash_to_string(ic_types::crypto::crypto_hash(&beacon).get_ref()),
+            parent: hash_to_string(beacon.content.parent.get_ref()),
+        });
+    let random_beacon_signers: BTreeSet<NodeId> = pool
+        .random_beacon_share()
+        .get_by_height(height)
+        .map(|share| share.signature.signer)
+        .collect();
+    contribution
+        .random_beacon
+        .extend(random_beacon_signers.iter().cloned());
+
+    let inspected = InspectedHeight {
+        height: height.get(),
+        blocks,
+        random_beacon,
+        random_beacon_shares: nodes_to_strings(Some(&random_beacon_signers)),
+        missing_random_beacon_shares: Vec::new(),
+        missing_notarization_shares: Vec::new(),
+        missing_finalization_shares: Vec::new(),
+    };
+    (inspected, contribution)
+}
+
+/// Returns the expected nodes that are not among the `contributors`, or no
+/// nodes at all if nobody contributed.
+fn missing(expected: &BTreeSet<NodeId>, contributors: &BTreeSet<NodeId>) -> Vec<String> {
+    if contributors.is_empty() {
+        return Vec::new();
+    }
+    nodes_to_strings(Some(&expected.difference(contributors).cloned().collect()))
+}
+
+fn nodes_to_strings(nodes: Option<&BTreeSet<NodeId>>) -> Vec<String> {
+    nodes
+        .map(|nodes| nodes.iter().map(node_to_string).collect())
+        .unwrap_or_default()
+}
+
+fn node_to_string(node: &NodeId) -> String {
+    node.get().to_string()
+}
+
+fn hash_to_string(hash: &CryptoHash) -> String {
+    hash.0.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Returns a prefix of an identifier that is long enough to tell it apart in
+/// a rendered graph.
+fn short(id: &str) -> &str {
+    &id[..id.len().min(8)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        consensus_pool::{MutablePoolSection, PoolSectionOps},
+        inmemory_pool::InMemoryPoolSection,
+    };
+    use ic_test_utilities::{
+        consensus::{fake::*, make_genesis},
+        types::ids::node_test_id,
+    };
+    use ic_test_utilities_time::mock_time;
+    use ic_types::consensus::{
+        dkg::Summary, Block, BlockProposal, ConsensusMessage, ConsensusMessageHashable,
+        Finalization, FinalizationContent, FinalizationShare, Notarization, NotarizationContent,
+        NotarizationShare, RandomBeacon, RandomBeaconShare,
+    };
+    use ic_types::crypto::threshold_sig::ni_dkg::NiDkgReceivers;
+
+    fn insert(pool: &mut InMemoryPoolSection<ValidatedConsensusArtifact>, msg: ConsensusMessage) {
+        let mut ops = PoolSectionOps::new();
+        ops.insert(ValidatedConsensusArtifact {
+            msg,
+            timestamp: mock_time(),
+        });
+        pool.mutate(ops);
+    }
+
+    // Returns a DKG summary for a subnet made of the given nodes.
+    fn summary_with_members(members: &[u64]) -> Summary {
+        let summary = Summary::fake();
+        let mut transcripts = summary.current_transcripts().clone();
+        for transcript in transcripts.values_mut() {
+            transcript.committee =
+                NiDkgReceivers::new(members.iter().map(|id| node_test_id(*id)).collect()).unwrap();
+        }
+        summary.with_current_transcripts(transcripts)
+    }
+
+    // Builds a pool holding the genesis CUP of a subnet made of the given
+    // nodes and two heights on top of it. At height 1, nodes 1 and 2 notarize
+    // and finalize the block of node 1. At height 2, node 2 proposes a block
+    // that only node 2 notarizes, while node 3 proposes a block that nobody
+    // signs.
+    fn make_pool(
+        members: &[u64],
+    ) -> (
+        InMemoryPoolSection<ValidatedConsensusArtifact>,
+        Block,
+        Block,
+    ) {
+        let mut pool = InMemoryPoolSection::new();
+        let cup = make_genesis(summary_with_members(members));
+        let genesis_block = cup.content.block.as_ref().clone();
+        let genesis_beacon = cup.content.random_beacon.as_ref().clone();
+        insert(&mut pool, cup.into_message());
+
+        let block_1 = Block::from_parent(&genesis_block);
+        let beacon_1 = RandomBeacon::from_parent(&genesis_beacon);
+        insert(
+            &mut pool,
+            BlockProposal::fake(block_1.clone(), node_test_id(1)).into_message(),
+        );
+        insert(&mut pool, beacon_1.clone().into_message());
+        for node in [1, 2] {
+            insert(
+                &mut pool,
+                RandomBeaconShare::fake(&genesis_beacon, node_test_id(node)).into_message(),
+            );
+            insert(
+                &mut pool,
+                NotarizationShare::fake(&block_1, node_test_id(node)).into_message(),
+            );
+            insert(
+                &mut pool,
+                FinalizationShare::fake(&block_1, node_test_id(node)).into_message(),
+            );
+        }
+        insert(
+            &mut pool,
+            Notarization::fake(NotarizationContent::new(
+                block_1.height,
+                ic_types::crypto::crypto_hash(&block_1),
+            ))
+            .into_message(),
+        );
+        insert(
+            &mut pool,
+            Finalization::fake(FinalizationContent::new(
+                block_1.height,
+                ic_types::crypto::crypto_hash(&block_1),
+            ))
+            .into_message(),
+        );
+
+        let block_2 = Block::from_parent(&block_1);
+        let mut block_2_rank_1 = Block::from_parent(&block_1);
+        block_2_rank_1.rank = ic_types::consensus::Rank(1);
+        insert(
+            &mut pool,
+            BlockProposal::fake(block_2.clone(), node_test_id(2)).into_message(),
+        );
+        insert(
+            &mut pool,
+            BlockProposal::fake(block_2_rank_1, node_test_id(3)).into_message(),
+        );
+        insert(
+            &mut pool,
+            RandomBeaconShare::fake(&beacon_1, node_test_id(2)).into_message(),
+        );
+        insert(
+            &mut pool,
+            NotarizationShare::fake(&block_2, node_test_id(2)).into_message(),
+        );
+        (pool, block_1, block_2)
+    }
+
+    #[test]
+    fn test_inspect_pool() {
+        let (pool, block_1, block_2) = make_pool(&[1, 2, 3]);
+        let range = inspectable_height_range(&pool).unwrap();
+        assert_eq!(range, HeightRange::new(Height::from(0), Height::from(2)));
+
+        let inspection = PoolInspection::new(&pool, range, None);
+        let node = |id| node_to_string(&node_test_id(id));
+        let hash = |block: &Block| hash_to_string(ic_types::crypto::crypto_hash(block).get_ref());
+
+        assert_eq!(inspection.heights.len(), 3);
+        assert_eq!(inspection.catch_up_packages.len(), 1);
+        assert_eq!(inspection.catch_up_packages[0].height, 0);
+
+        let height_1 = &inspection.heights[1];
+        assert_eq!(height_1.blocks.len(), 1);
+        assert_eq!(height_1.blocks[0].hash, hash(&block_1));
+        assert_eq!(height_1.blocks[0].proposer, node(1));
+        assert!(height_1.blocks[0].notarized);
+        assert!(height_1.blocks[0].finalized);
+        assert_eq!(
+            height_1.blocks[0].notarization_shares,
+            vec![node(1), node(2)]
+        );
+        assert!(height_1.random_beacon.is_some());
+        assert_eq!(height_1.missing_notarization_shares, vec![node(3)]);
+        assert_eq!(height_1.missing_finalization_shares, vec![node(3)]);
+        assert_eq!(height_1.missing_random_beacon_shares, vec![node(3)]);
+
+        let height_2 = &inspection.heights[2];
+        assert_eq!(height_2.blocks.len(), 2);
+        assert_eq!(height_2.blocks[0].hash, hash(&block_2));
+        assert_eq!(height_2.blocks[0].parent, hash(&block_1));
+        assert!(!height_2.blocks[0].notarized);
+        assert_eq!(height_2.blocks[1].rank, 1);
+        assert_eq!(height_2.blocks[1].proposer, node(3));
+        assert!(height_2.random_beacon.is_none());
+        assert_eq!(height_2.random_beacon_shares, vec![node(2)]);
+        assert_eq!(height_2.missing_notarization_shares, vec![node(1), node(3)]);
+        // Nobody contributed to the finalization at height 2 yet.
+        assert!(height_2.missing_finalization_shares.is_empty());
+
+        let summary = |id| {
+            inspection
+                .nodes
+                .iter()
+                .find(|summary| summary.node == node(id))
+                .unwrap()
+                .clone()
+        };
+        assert_eq!(inspection.nodes.len(), 3);
+        assert_eq!(
+            summary(3),
+            NodeSummary {
+                node: node(3),
+                block_proposals: 1,
+                missing_random_beacon_shares: 2,
+                missing_notarization_shares: 2,
+                missing_finalization_shares: 1,
+            }
+        );
+        assert_eq!(summary(1).missing_notarization_shares, 1);
+        assert_eq!(summary(2).missing_notarization_shares, 0);
+    }
+
+    #[test]
+    fn test_inspect_pool_with_expected_nodes() {
+        let (pool, _, _) = make_pool(&[1, 2, 3]);
+        let nodes = [1, 4].into_iter().map(node_test_id).collect();
+        let inspection = PoolInspection::new(
+            &pool,
+            HeightRange::new(Height::from(2), Height::from(2)),
+            Some(nodes),
+        );
+
+        let node = |id| node_to_string(&node_test_id(id));
+        assert_eq!(inspection.heights.len(), 1);
+        assert_eq!(
+            inspection.heights[0].missing_notarization_shares,
+            vec![node(1), node(4)]
+        );
+        let nodes: Vec<_> = inspection.nodes.iter().map(|s| s.node.clone()).collect();
+        assert_eq!(nodes, vec![node(1), node(4)]);
+        assert_eq!(inspection.nodes[1].missing_random_beacon_shares, 1);
+    }
+
+    #[test]
+    fn test_inspect_pool_reports_silent_subnet_members() {
+        // Node 4 is a member of the subnet, but sent nothing at all.
+        let (pool, _, _) = make_pool(&[1, 2, 3, 4]);
+        let range = inspectable_height_range(&pool).unwrap();
+        let inspection = PoolInspection::new(&pool, range, None);
+
+        let node = |id| node_to_string(&node_test_id(id));
+        assert_eq!(
+            inspection.heights[1].missing_notarization_shares,
+            vec![node(3), node(4)]
+        );
+        assert_eq!(
+            inspection.heights[2].missing_random_beacon_shares,
+            vec![node(1), node(3), node(4)]
+        );
+        let nodes: Vec<_> = inspection.nodes.iter().map(|s| s.node.clone()).collect();
+        assert_eq!(nodes, vec![node(1), node(2), node(3), node(4)]);
+        assert_eq!(
+            inspection.nodes[3],
+            NodeSummary {
+                node: node(4),
+                block_proposals: 0,
+                missing_random_beacon_shares: 2,
+                missing_notarization_shares: 2,
+                missing_finalization_shares: 1,
+            }
+        );
+    }
+
+    #[test]
+    fn test_render_dot() {
+        let (pool, block_1, block_2) = make_pool(&[1, 2, 3]);
+        let range = inspectable_height_range(&pool).unwrap();
+        let dot = PoolInspection::new(&pool, range, None).to_dot();
+        let hash = |block: &Block| hash_to_string(ic_types::crypto::crypto_hash(block).get_ref());
+
+        assert!(dot.starts_with("digraph consensus_pool {"));
+        assert!(dot.contains(&format!(
+            "\"b_{}\" -> \"b_{}\";",
+            hash(&block_2),
+            hash(&block_1)
+        )));
+        assert!(dot.contains("style=\"bold,filled\""));
+        assert!(dot.contains("\"cup_0\""));
+    }
+}
@@ -3,12 +3,13 @@ use crate::{
     util::{block_on, sleep_secs},
 };
 use chrono::{DateTime, Utc};
+use ic_artifact_pool::packed_backup::PackedBackup;
 use ic_recovery::{
     command_helper::exec_cmd, error::RecoveryError, file_sync_helper::download_binary,
 };
 use ic_registry_client::client::{RegistryClient, RegistryClientImpl};
 use ic_registry_client_helpers::{node::NodeRegistry, subnet::SubnetRegistry};
-use ic_types::{ReplicaVersion, SubnetId};
+use ic_types::{Height, ReplicaVersion, SubnetId};
 use rand::{seq::SliceRandom, thread_rng};
 use slog::{debug, error, info, warn, Logger};
 use std::{
@@ -142,9 +143,9 @@ impl BackupHelper {
             "[#{}] Check if there are new artifacts.", self.thread_id
         );
 
-        let cup_file = self.spool_dir().join(format!(
-            "{}/{}/{}/catch_up_package.bin",
-            replica_version,
+        let replica_version_dir = self.spool_dir().join(replica_version.to_string());
+        let cup_file = replica_version_dir.join(format!(
+            "{}/{}/catch_up_package.bin",
             start_height - start_height % 10000,
             start_height
         ));
@@ -152,7 +153,9 @@ impl BackupHelper {
         // already synced from the node.
         // That way it is guaranteed that the node is running the new replica version and
         // has the latest version of the ic.json5 file.
-        while !cup_file.exists() {
+        while !cup_file.exists()
+            && !is_packed_in_spool(&replica_version_dir, start_height, "catch_up_package.bin")
+        {
             debug!(self.log, "CUP file {} not yet present", cup_file.display());
             sleep_secs(30);
         }
@@ -941,7 +944,11 @@ fn fetch_top_height(replica_version_dir: &DirEntry) -> (u64, PathBuf) {
     let replica_version_path = replica_version_dir.path();
     let height_bucket = last_dir_height(&replica_version_path, 10);
     let top_height = last_dir_height(&replica_version_path.join(format!("{}", height_bucket)), 10);
-    (top_height, replica_version_path)
+    let packed_top_height = PackedBackup::open(&replica_version_path)
+        .ok()
+        .and_then(|packed| packed.top_height())
+        .map_or(0, |height| height.get());
+    (top_height.max(packed_top_height), replica_version_path)
 }
 
 fn is_height_in_spool(replica_version_dir: &DirEntry, height: u64) -> bool {
@@ -949,6 +956,17 @@ fn is_height_in_spool(replica_version_dir: &DirEntry, height: u64) -> bool {
     let height_bucket = height / BUCKET_SIZE * BUCKET_SIZE;
     let path = replica_version_path.join(format!("{}/{}", height_bucket, height));
     path.exists()
+        || PackedBackup::open(&replica_version_path)
+            .map(|packed| !packed.file_names(Height::from(height)).is_empty())
+            .unwrap_or(false)
+}
+
+/// Returns true if the packed segments of the given replica version contain the
+/// artifact with `file_name` at `height`.
+fn is_packed_in_spool(replica_version_path: &Path, height: u64, file_name: &str) -> bool {
+    PackedBackup::open(replica_version_path)
+        .map(|packed| packed.contains(Height::from(height), file_name))
+        .unwrap_or(false)
 }
 
 fn height_from_dir_entry_radix(filename: &DirEntry, radix: u32) -> u64 {
@@ -1255,6 +1273,30 @@ mod tests {
 
     // Utility functions below
 
+    #[test]
+    fn fetch_top_height_with_packed_artifacts_test() {
+        let dir = tmpdir("test_dir");
+        let spool_dir = dir.as_ref().join("spool");
+        let replica_version_path = spool_dir.join("replica_version_1");
+
+        create_artifacts_dir_with_heights(&replica_version_path, vec![0, 50]);
+        PackedBackup::open(&replica_version_path)
+            .unwrap()
+            .write(Height::from(120), "catch_up_package.bin", b"cup")
+            .unwrap();
+
+        let replica_version_dir = read_dir(&spool_dir).unwrap().flatten().next().unwrap();
+        assert_eq!(fetch_top_height(&replica_version_dir).0, 120);
+        assert!(is_height_in_spool(&replica_version_dir, 50));
+        assert!(is_height_in_spool(&replica_version_dir, 120));
+        assert!(!is_height_in_spool(&replica_version_dir, 100));
+        assert!(is_packed_in_spool(
+            &replica_version_path,
+            120,
+            "catch_up_package.bin"
+        ));
+    }
+
     fn create_artifacts_dir_with_heights(replica_version_dir: &Path, heights: Vec<u64>) {
         for height in heights {
             let shard = 100 * (height / 100);
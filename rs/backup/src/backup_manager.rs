@@ -10,6 +10,7 @@ use std::{
     time::{Duration, Instant},
 };
 
+use ic_artifact_pool::packed_backup::pack_directory_backup;
 use ic_crypto_utils_threshold_sig_der::parse_threshold_sig_key;
 use ic_logger::ReplicaLogger;
 use ic_recovery::command_helper::exec_cmd;
@@ -178,6 +179,28 @@ impl BackupManager {
         println!("{}", replica_version)
     }
 
+    /// Converts the artifacts of all replica versions of the given subnet in the
+    /// spool into packed segments, in place.
+    pub fn pack_spool(log: Logger, config_file: PathBuf, subnet_id: SubnetId) {
+        let config = Config::load_config(config_file).expect("Config file can't be loaded");
+        let spool_dir = config.root_dir.join("spool").join(subnet_id.to_string());
+        let replica_version_dirs = fs::read_dir(&spool_dir)
+            .expect("Spool directory can't be read")
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.is_dir());
+        for replica_version_dir in replica_version_dirs {
+            info!(log, "Packing artifacts in {:?}", replica_version_dir);
+            match pack_directory_backup(&replica_version_dir) {
+                Ok(count) => info!(log, "Packed {} artifacts", count),
+                Err(err) => error!(
+                    log,
+                    "Failed to pack artifacts in {:?}: {:?}", replica_version_dir, err
+                ),
+            }
+        }
+    }
+
     pub fn upgrade(log: Logger, config_file: PathBuf) {
         let config = Config::load_config(config_file.clone()).expect("Config file can't be loaded");
         config
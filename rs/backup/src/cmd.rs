@@ -42,4 +42,10 @@ pub enum SubCommand {
         /// The ID of the target subnet
         subnet_id: ClapSubnetId,
     },
+    /// Convert the artifacts of a subnet in the spool from the directory backup
+    /// format into packed segments. Should not run concurrently with the backup.
+    PackSpool {
+        /// The ID of the target subnet
+        subnet_id: ClapSubnetId,
+    },
 }
@@ -80,6 +80,9 @@ async fn main() {
             Some(SubCommand::GetReplicaVersion { subnet_id }) => {
                 BackupManager::get_version(log, args.config_file, subnet_id.0)
             }
+            Some(SubCommand::PackSpool { subnet_id }) => {
+                BackupManager::pack_spool(log, args.config_file, subnet_id.0)
+            }
             _ => {
                 let bm = BackupManager::new(log, args, &rt);
                 Arc::new(bm).do_backups();
@@ -5,7 +5,9 @@ use std::{
 };
 
 use arc_swap::ArcSwapOption;
-use ic_crypto_test_utils_keys::public_keys::valid_tls_certificate_and_validation_time;
+use ic_crypto_test_utils_keys::public_keys::{
+    valid_node_signing_public_key, valid_tls_certificate_and_validation_time,
+};
 use ic_registry_subnet_type::SubnetType;
 use ic_test_utilities::types::ids::{node_test_id, subnet_test_id};
 use mockall::{predicate::*, *};
@@ -56,6 +58,7 @@ pub fn generate_custom_registry_snapshot(
                 tls_certificate: valid_tls_certificate_and_validation_time()
                     .0
                     .certificate_der,
+                signing_public_key: Some(valid_node_signing_public_key().key_value),
                 replica_version: "7742d96ddd30aa6b607c9d2d4093a7b714f5b25b".to_string(),
             };
             let node = Arc::new(node);
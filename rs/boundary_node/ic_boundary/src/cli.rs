@@ -36,6 +36,9 @@ pub struct Cli {
 
     #[command(flatten, next_help_heading = "retry")]
     pub retry: RetryConfig,
+
+    #[command(flatten, next_help_heading = "verification")]
+    pub verification: VerificationConfig,
 }
 
 #[derive(Args)]
@@ -240,3 +243,11 @@ pub struct RetryConfig {
     #[clap(long, default_value = "false")]
     pub retry_update_call: bool,
 }
+
+#[derive(Args)]
+pub struct VerificationConfig {
+    /// Whether to verify the node signatures of query responses.
+    /// Responses that fail verification are retried on other nodes according to the retry settings.
+    #[clap(long, default_value = "false")]
+    pub verify_query_signatures: bool,
+}
@@ -277,6 +277,7 @@ pub async fn main(cli: Cli) -> Result<(), Error> {
                 registry_client.clone(),
                 Duration::from_secs(cli.registry.min_version_age),
             );
+            snapshotter.set_require_node_signing_keys(cli.verification.verify_query_signatures);
 
             if let Some(v) = &cli.firewall.nftables_system_replicas_path {
                 let fw_reloader = SystemdReloader::new(SYSTEMCTL_BIN.into(), "nftables", "reload");
@@ -438,6 +439,7 @@ pub fn setup_router(
         http_client.clone(),
         Arc::clone(&routing_table),
         Arc::clone(&registry_snapshot),
+        cli.verification.verify_query_signatures,
     );
 
     let proxy_router = Arc::new(proxy_router);
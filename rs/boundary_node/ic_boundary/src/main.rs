@@ -16,6 +16,7 @@ mod http;
 mod management;
 mod metrics;
 mod persist;
+mod query_verify;
 mod rate_limiting;
 mod retry;
 mod routes;
@@ -9,7 +9,9 @@ use anyhow::Error;
 use arc_swap::ArcSwapOption;
 use candid::Principal;
 use ethnum::u256;
-use ic_crypto_test_utils_keys::public_keys::valid_tls_certificate_and_validation_time;
+use ic_crypto_test_utils_keys::public_keys::{
+    valid_node_signing_public_key, valid_tls_certificate_and_validation_time,
+};
 use ic_registry_subnet_type::SubnetType;
 use ic_test_utilities::types::ids::node_test_id;
 
@@ -68,6 +70,7 @@ pub fn node(i: u64, subnet_id: Principal) -> Arc<Node> {
         tls_certificate: valid_tls_certificate_and_validation_time()
             .0
             .certificate_der,
+        signing_public_key: Some(valid_node_signing_public_key().key_value),
         replica_version: "7742d96ddd30aa6b607c9d2d4093a7b714f5b25b".to_string(),
     })
 }
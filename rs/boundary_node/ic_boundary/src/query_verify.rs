@@ -0,0 +1,85 @@
+use ic_crypto_internal_basic_sig_ed25519::types::{
+    PublicKeyBytes as BasicSigEd25519PublicKeyBytes,
+    SignatureBytes as BasicSigEd25519SignatureBytes,
+};
+use ic_types::{
+    crypto::Signable,
+    messages::{
+        HttpQueryContent, HttpQueryResponse, HttpRequestEnvelope, NodeSignature, QueryResponseHash,
+    },
+};
+use serde::Deserialize;
+
+use crate::{routes::ErrorCause, snapshot::Node};
+
+// Signatures of a query response as they are sent by the replica over the wire, next to the response.
+// The signatures are a list according to the spec, though the replica currently always sends exactly one.
+// They are decoded separately from the response, so that a reply without valid signatures is reported
+// as such (and retried on another node), rather than as a malformed response.
+#[derive(Deserialize)]
+struct QueryResponseSignatures {
+    #[serde(default)]
+    signatures: Vec<NodeSignature>,
+}
+
+/// Verifies that the query response was signed by the node that we've sent the request to.
+/// Both request and response are expected to be raw CBOR-encoded bodies.
+pub fn verify_query_response(
+    request: &[u8],
+    response: &[u8],
+    node: &Node,
+) -> Result<(), ErrorCause> {
+    // Request id is a hash of the request content, so we need to decode it fully
+    let envelope: HttpRequestEnvelope<HttpQueryContent> = serde_cbor::from_slice(request)
+        .map_err(|e| ErrorCause::UnableToParseCBOR(e.to_string()))?;
+    let request_id = envelope.content.id();
+
+    let query_response: HttpQueryResponse = serde_cbor::from_slice(response).map_err(|e| {
+        ErrorCause::MalformedResponse(format!("unable to decode query response: {e}"))
+    })?;
+
+    let signatures: QueryResponseSignatures = serde_cbor::from_slice(response).map_err(|e| {
+        ErrorCause::ReplicaSignatureInvalid(format!("unable to decode signatures: {e}"))
+    })?;
+
+    let signature = match signatures.signatures.as_slice() {
+        [v] => v,
+        v => {
+            return Err(ErrorCause::ReplicaSignatureInvalid(format!(
+                "expected exactly one signature, got {}",
+                v.len()
+            )))
+        }
+    };
+
+    if signature.identity.get().0 != node.id {
+        return Err(ErrorCause::ReplicaSignatureInvalid(format!(
+            "response signed by {} instead of {}",
+            signature.identity, node.id
+        )));
+    }
+
+    let signing_public_key = node.signing_public_key.clone().ok_or_else(|| {
+        ErrorCause::ReplicaSignatureInvalid("node signing key not available".into())
+    })?;
+
+    let public_key = BasicSigEd25519PublicKeyBytes::try_from(signing_public_key).map_err(|e| {
+        ErrorCause::ReplicaSignatureInvalid(format!("invalid node signing key: {e}"))
+    })?;
+
+    let signature_bytes = BasicSigEd25519SignatureBytes::try_from(signature.signature.0.clone())
+        .map_err(|e| ErrorCause::ReplicaSignatureInvalid(format!("invalid signature: {e}")))?;
+
+    let hash =
+        QueryResponseHash::from_request_id(&query_response, &request_id, signature.timestamp);
+
+    ic_crypto_internal_basic_sig_ed25519::verify(
+        &signature_bytes,
+        &hash.as_signed_bytes(),
+        &public_key,
+    )
+    .map_err(|e| ErrorCause::ReplicaSignatureInvalid(format!("verification failed: {e}")))
+}
+
+#[cfg(test)]
+pub mod test;
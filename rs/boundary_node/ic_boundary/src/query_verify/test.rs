@@ -0,0 +1,209 @@
+use super::*;
+
+use std::{
+    collections::BTreeMap,
+    net::{IpAddr, Ipv4Addr},
+};
+
+use candid::Principal;
+use ic_crypto_internal_basic_sig_ed25519::types::SecretKeyBytes as BasicSigEd25519SecretKeyBytes;
+use ic_registry_subnet_type::SubnetType;
+use ic_test_utilities::types::ids::{node_test_id, subnet_test_id};
+use ic_types::{
+    messages::{Blob, HttpQueryResponseReply, HttpSignedQueryResponse, HttpUserQuery, MessageId},
+    time::Time,
+    NodeId,
+};
+use rand::thread_rng;
+
+pub fn test_node(node_id: NodeId, signing_public_key: Vec<u8>) -> Node {
+    Node {
+        id: node_id.get().0,
+        subnet_id: subnet_test_id(1).get().0,
+        subnet_type: SubnetType::Application,
+        addr: IpAddr::V4(Ipv4Addr::new(192, 168, 0, 1)),
+        port: 8080,
+        tls_certificate: vec![],
+        signing_public_key: Some(signing_public_key),
+        replica_version: "7742d96ddd30aa6b607c9d2d4093a7b714f5b25b".to_string(),
+    }
+}
+
+pub fn test_request() -> (Vec<u8>, MessageId) {
+    let content = HttpQueryContent::Query {
+        query: HttpUserQuery {
+            canister_id: Blob(
+                Principal::from_text("f7crg-kabae")
+                    .unwrap()
+                    .as_slice()
+                    .to_vec(),
+            ),
+            method_name: "foo".into(),
+            arg: Blob(vec![1, 2, 3, 4]),
+            sender: Blob(Principal::anonymous().as_slice().to_vec()),
+            ingress_expiry: 1234,
+            nonce: None,
+        },
+    };
+    let request_id = content.id();
+
+    let envelope = HttpRequestEnvelope {
+        content,
+        sender_pubkey: None,
+        sender_sig: None,
+        sender_delegation: None,
+    };
+
+    (serde_cbor::to_vec(&envelope).unwrap(), request_id)
+}
+
+fn test_query_response() -> HttpQueryResponse {
+    HttpQueryResponse::Replied {
+        reply: HttpQueryResponseReply {
+            arg: Blob(b"foobar".to_vec()),
+        },
+    }
+}
+
+pub fn test_response(
+    request_id: &MessageId,
+    node_id: NodeId,
+    secret_key: &BasicSigEd25519SecretKeyBytes,
+) -> Vec<u8> {
+    let response = test_query_response();
+    let timestamp = Time::from_nanos_since_unix_epoch(1_000_000);
+
+    let hash = QueryResponseHash::from_request_id(&response, request_id, timestamp);
+    let signature =
+        ic_crypto_internal_basic_sig_ed25519::sign(&hash.as_signed_bytes(), secret_key).unwrap();
+
+    let response = HttpSignedQueryResponse {
+        response,
+        node_signature: NodeSignature {
+            timestamp,
+            signature: Blob(signature.0.to_vec()),
+            identity: node_id,
+        },
+    };
+
+    serde_cbor::to_vec(&response).unwrap()
+}
+
+/// A query response as sent by a replica that does not sign its responses.
+pub fn test_unsigned_response() -> Vec<u8> {
+    serde_cbor::to_vec(&test_query_response()).unwrap()
+}
+
+#[test]
+fn test_verify_query_response() {
+    let (secret_key, public_key) =
+        ic_crypto_internal_basic_sig_ed25519::keypair_from_rng(&mut thread_rng());
+    let node_id = node_test_id(1001);
+    let node = test_node(node_id, public_key.0.to_vec());
+
+    let (request, request_id) = test_request();
+    let response = test_response(&request_id, node_id, &secret_key);
+
+    assert!(verify_query_response(&request, &response, &node).is_ok());
+}
+
+#[test]
+fn test_verify_query_response_wrong_key() {
+    let (secret_key, _) = ic_crypto_internal_basic_sig_ed25519::keypair_from_rng(&mut thread_rng());
+    let (_, public_key) = ic_crypto_internal_basic_sig_ed25519::keypair_from_rng(&mut thread_rng());
+    let node_id = node_test_id(1001);
+    let node = test_node(node_id, public_key.0.to_vec());
+
+    let (request, request_id) = test_request();
+    let response = test_response(&request_id, node_id, &secret_key);
+
+    let err = verify_query_response(&request, &response, &node).unwrap_err();
+    assert!(matches!(err, ErrorCause::ReplicaSignatureInvalid(_)));
+    assert!(err.retriable());
+}
+
+#[test]
+fn test_verify_query_response_missing_key() {
+    let (secret_key, public_key) =
+        ic_crypto_internal_basic_sig_ed25519::keypair_from_rng(&mut thread_rng());
+    let node_id = node_test_id(1001);
+    let node = Node {
+        signing_public_key: None,
+        ..test_node(node_id, public_key.0.to_vec())
+    };
+
+    let (request, request_id) = test_request();
+    let response = test_response(&request_id, node_id, &secret_key);
+
+    let err = verify_query_response(&request, &response, &node).unwrap_err();
+    assert!(matches!(err, ErrorCause::ReplicaSignatureInvalid(_)));
+}
+
+#[test]
+fn test_verify_query_response_wrong_node() {
+    let (secret_key, public_key) =
+        ic_crypto_internal_basic_sig_ed25519::keypair_from_rng(&mut thread_rng());
+    let node = test_node(node_test_id(1001), public_key.0.to_vec());
+
+    let (request, request_id) = test_request();
+    let response = test_response(&request_id, node_test_id(1002), &secret_key);
+
+    let err = verify_query_response(&request, &response, &node).unwrap_err();
+    assert!(matches!(err, ErrorCause::ReplicaSignatureInvalid(_)));
+}
+
+#[test]
+fn test_verify_query_response_wrong_request() {
+    let (secret_key, public_key) =
+        ic_crypto_internal_basic_sig_ed25519::keypair_from_rng(&mut thread_rng());
+    let node_id = node_test_id(1001);
+    let node = test_node(node_id, public_key.0.to_vec());
+
+    let (request, _) = test_request();
+    let response = test_response(&MessageId::from([0; 32]), node_id, &secret_key);
+
+    let err = verify_query_response(&request, &response, &node).unwrap_err();
+    assert!(matches!(err, ErrorCause::ReplicaSignatureInvalid(_)));
+}
+
+#[test]
+fn test_verify_query_response_malformed() {
+    let (_, public_key) = ic_crypto_internal_basic_sig_ed25519::keypair_from_rng(&mut thread_rng());
+    let node = test_node(node_test_id(1001), public_key.0.to_vec());
+
+    let (request, _) = test_request();
+
+    let err = verify_query_response(&request, b"foobar", &node).unwrap_err();
+    assert!(matches!(err, ErrorCause::MalformedResponse(_)));
+}
+
+#[test]
+fn test_verify_query_response_unsigned() {
+    let (_, public_key) = ic_crypto_internal_basic_sig_ed25519::keypair_from_rng(&mut thread_rng());
+    let node = test_node(node_test_id(1001), public_key.0.to_vec());
+
+    let (request, _) = test_request();
+
+    let err = verify_query_response(&request, &test_unsigned_response(), &node).unwrap_err();
+    assert!(matches!(err, ErrorCause::ReplicaSignatureInvalid(_)));
+    assert!(err.retriable());
+}
+
+#[test]
+fn test_verify_query_response_undecodable_signature() {
+    let (_, public_key) = ic_crypto_internal_basic_sig_ed25519::keypair_from_rng(&mut thread_rng());
+    let node = test_node(node_test_id(1001), public_key.0.to_vec());
+
+    let (request, _) = test_request();
+    let mut response: BTreeMap<String, serde_cbor::Value> =
+        serde_cbor::from_slice(&test_unsigned_response()).unwrap();
+    response.insert(
+        "signatures".into(),
+        serde_cbor::Value::Text("foobar".into()),
+    );
+    let response = serde_cbor::to_vec(&response).unwrap();
+
+    let err = verify_query_response(&request, &response, &node).unwrap_err();
+    assert!(matches!(err, ErrorCause::ReplicaSignatureInvalid(_)));
+    assert!(err.retriable());
+}
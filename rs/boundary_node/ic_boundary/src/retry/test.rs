@@ -7,15 +7,28 @@ use std::{
 
 use anyhow::Error;
 use axum::{
-    body::Body, extract::State, http::Request, middleware, response::IntoResponse,
-    routing::method_routing::post, Router,
+    body::{Body, Bytes},
+    extract::State,
+    http::Request,
+    middleware,
+    response::{IntoResponse, Response},
+    routing::method_routing::post,
+    Router,
 };
 use candid::Principal;
 use http::StatusCode;
+use ic_test_utilities::types::ids::node_test_id;
 use ic_types::CanisterId;
+use rand::thread_rng;
 use tower::Service;
 
-use crate::routes::test::test_route_subnet;
+use crate::{
+    query_verify::{
+        test::{test_node, test_request, test_response, test_unsigned_response},
+        verify_query_response,
+    },
+    routes::test::test_route_subnet,
+};
 
 struct TestState {
     failures: u8,
@@ -63,6 +76,26 @@ async fn handler(State(state): State<Arc<RwLock<TestState>>>) -> impl IntoRespon
     resp
 }
 
+struct VerifyingState {
+    node: Node,
+    // Replica responses to return, in order
+    responses: Vec<Vec<u8>>,
+}
+
+// Generate a response that is verified like a query response from a replica
+async fn verifying_handler(
+    State(state): State<Arc<RwLock<VerifyingState>>>,
+    body: Bytes,
+) -> Response {
+    let mut s = state.write().unwrap();
+    let response = s.responses.remove(0);
+
+    match verify_query_response(&body, &response, &s.node) {
+        Ok(()) => response.into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
 #[tokio::test]
 async fn test_request_clone() -> Result<(), Error> {
     let mut req = gen_request(RequestType::Query);
@@ -183,3 +216,42 @@ async fn test_retry() -> Result<(), Error> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_retry_unsigned_query_response() -> Result<(), Error> {
+    let (secret_key, public_key) =
+        ic_crypto_internal_basic_sig_ed25519::keypair_from_rng(&mut thread_rng());
+    let node_id = node_test_id(1001);
+    let (request, request_id) = test_request();
+
+    // The first replica replies without a signature, the second one with a valid one
+    let state = Arc::new(RwLock::new(VerifyingState {
+        node: test_node(node_id, public_key.0.to_vec()),
+        responses: vec![
+            test_unsigned_response(),
+            test_response(&request_id, node_id, &secret_key),
+        ],
+    }));
+
+    let mut app = Router::new()
+        .route("/", post(verifying_handler).with_state(Arc::clone(&state)))
+        .layer(middleware::from_fn_with_state(
+            RetryParams {
+                retry_count: 3,
+                retry_update_call: false,
+            },
+            retry_request,
+        ));
+
+    let (parts, _) = gen_request(RequestType::Query).into_parts();
+    let req = Request::from_parts(parts, Body::from(request));
+    let res = app.call(req).await.unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let retry_result = res.extensions().get::<RetryResult>().unwrap();
+    assert_eq!(retry_result.retries, 1);
+    assert!(retry_result.success);
+    assert!(state.read().unwrap().responses.is_empty());
+
+    Ok(())
+}
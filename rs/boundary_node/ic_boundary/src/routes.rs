@@ -39,6 +39,7 @@ use crate::{
     core::MAX_REQUEST_BODY_SIZE,
     http::{read_streaming_body, reqwest_error_infer, HttpClient},
     persist::{RouteSubnet, Routes},
+    query_verify::verify_query_response,
     retry::RetryResult,
     snapshot::{Node, RegistrySnapshot},
 };
@@ -140,6 +141,7 @@ pub enum ErrorCause {
     ReplicaTLSErrorOther(String),
     ReplicaTLSErrorCert(String),
     ReplicaErrorOther(String),
+    ReplicaSignatureInvalid(String),
     RateLimited(RateLimitCause),
     Other(String),
 }
@@ -164,6 +166,7 @@ impl ErrorCause {
             Self::ReplicaTLSErrorOther(_) => StatusCode::SERVICE_UNAVAILABLE,
             Self::ReplicaTLSErrorCert(_) => StatusCode::SERVICE_UNAVAILABLE,
             Self::ReplicaErrorOther(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::ReplicaSignatureInvalid(_) => StatusCode::SERVICE_UNAVAILABLE,
             Self::RateLimited(_) => StatusCode::TOO_MANY_REQUESTS,
         }
     }
@@ -182,6 +185,7 @@ impl ErrorCause {
             Self::ReplicaTLSErrorOther(x) => Some(x.clone()),
             Self::ReplicaTLSErrorCert(x) => Some(x.clone()),
             Self::ReplicaErrorOther(x) => Some(x.clone()),
+            Self::ReplicaSignatureInvalid(x) => Some(x.clone()),
             _ => None,
         }
     }
@@ -193,6 +197,7 @@ impl ErrorCause {
                 | Self::ReplicaErrorConnect
                 | Self::ReplicaTLSErrorOther(_)
                 | Self::ReplicaTLSErrorCert(_)
+                | Self::ReplicaSignatureInvalid(_)
         )
     }
 }
@@ -217,6 +222,7 @@ impl fmt::Display for ErrorCause {
             Self::ReplicaTLSErrorOther(_) => write!(f, "replica_tls_error"),
             Self::ReplicaTLSErrorCert(_) => write!(f, "replica_tls_error_cert"),
             Self::ReplicaErrorOther(_) => write!(f, "replica_error_other"),
+            Self::ReplicaSignatureInvalid(_) => write!(f, "replica_signature_invalid"),
             Self::RateLimited(x) => write!(f, "rate_limited_{x}"),
         }
     }
@@ -356,6 +362,7 @@ pub struct ProxyRouter {
     http_client: Arc<dyn HttpClient>,
     published_routes: Arc<ArcSwapOption<Routes>>,
     published_registry_snapshot: Arc<ArcSwapOption<RegistrySnapshot>>,
+    verify_query_signatures: bool,
 }
 
 impl ProxyRouter {
@@ -363,11 +370,13 @@ impl ProxyRouter {
         http_client: Arc<dyn HttpClient>,
         published_routes: Arc<ArcSwapOption<Routes>>,
         published_registry_snapshot: Arc<ArcSwapOption<RegistrySnapshot>>,
+        verify_query_signatures: bool,
     ) -> Self {
         Self {
             http_client,
             published_routes,
             published_registry_snapshot,
+            verify_query_signatures,
         }
     }
 }
@@ -391,9 +400,17 @@ impl Proxy for ProxyRouter {
         ))
         .map_err(|e| ErrorCause::Other(format!("failed to build request url: {e}")))?;
 
+        let verify = self.verify_query_signatures && request_type == RequestType::Query;
+
+        // The body is already buffered in memory by now, so this does not block.
+        // We need to keep a copy of it to be able to verify the response signature.
+        let body = hyper::body::to_bytes(body)
+            .await
+            .map_err(|e| ErrorCause::UnableToReadBody(e.to_string()))?;
+
         let mut request = reqwest::Request::new(Method::POST, u);
         *request.headers_mut() = parts.headers;
-        *request.body_mut() = Some(body.into());
+        *request.body_mut() = Some(body.clone().into());
 
         // Execute request
         let response = self
@@ -402,10 +419,22 @@ impl Proxy for ProxyRouter {
             .await
             .map_err(reqwest_error_infer)?;
 
-        // Convert Reqwest response into Axum one with body streaming
         let status = response.status();
         let headers = response.headers().clone();
 
+        // Only successful replies are signed by the replica
+        if verify && status == StatusCode::OK {
+            let response_body = response.bytes().await.map_err(reqwest_error_infer)?;
+            verify_query_response(&body, &response_body, &node)?;
+
+            let mut response = response_body.into_response();
+            *response.status_mut() = status;
+            *response.headers_mut() = headers;
+
+            return Ok(response);
+        }
+
+        // Convert Reqwest response into Axum one with body streaming
         let mut response = StreamBody::new(response.bytes_stream()).into_response();
         *response.status_mut() = status;
         *response.headers_mut() = headers;
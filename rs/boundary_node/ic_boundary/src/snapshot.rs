@@ -7,7 +7,7 @@ use std::{
     time::{Duration, Instant},
 };
 
-use anyhow::{Context, Error};
+use anyhow::{anyhow, Context, Error};
 use arc_swap::ArcSwapOption;
 use async_trait::async_trait;
 use candid::Principal;
@@ -19,7 +19,7 @@ use ic_registry_client_helpers::{
     subnet::{SubnetListRegistry, SubnetRegistry},
 };
 use ic_registry_subnet_type::SubnetType;
-use ic_types::RegistryVersion;
+use ic_types::{crypto::KeyPurpose, RegistryVersion};
 use tracing::info;
 use x509_parser::{certificate::X509Certificate, prelude::FromDer};
 
@@ -40,6 +40,9 @@ pub struct Node {
     pub addr: IpAddr,
     pub port: u16,
     pub tls_certificate: Vec<u8>,
+    // Raw Ed25519 node signing public key, used to verify signed query responses.
+    // Only required to be present when the verification is enabled.
+    pub signing_public_key: Option<Vec<u8>>,
     pub replica_version: String,
 }
 
@@ -113,6 +116,7 @@ pub struct Snapshotter {
     last_version_change: Instant,
     min_version_age: Duration,
     persister: Option<SnapshotPersister>,
+    require_node_signing_keys: bool,
 }
 
 pub struct SnapshotInfo {
@@ -147,6 +151,7 @@ impl Snapshotter {
             last_version_change: Instant::now(),
             min_version_age,
             persister: None,
+            require_node_signing_keys: false,
         }
     }
 
@@ -154,6 +159,12 @@ impl Snapshotter {
         self.persister = Some(persister);
     }
 
+    // Makes snapshots fail if a node's signing key is not available.
+    // Needed when the query response signatures are verified.
+    pub fn set_require_node_signing_keys(&mut self, require: bool) {
+        self.require_node_signing_keys = require;
+    }
+
     // Creates a snapshot of the registry for given version
     fn get_snapshot(&self, version: RegistryVersion) -> Result<RegistrySnapshot, Error> {
         // Get routing table with canister ranges
@@ -251,6 +262,16 @@ impl Snapshotter {
                         X509Certificate::from_der(cert.certificate_der.as_slice())
                             .context("Unable to parse TLS certificate")?;
 
+                        let signing_public_key = self
+                            .registry_client
+                            .get_crypto_key_for_node(node_id, KeyPurpose::NodeSigning, version)
+                            .context("failed to get node signing key")? // Result
+                            .map(|v| v.key_value);
+
+                        if self.require_node_signing_keys && signing_public_key.is_none() {
+                            return Err(anyhow!("node signing key not available"));
+                        }
+
                         let node = Node {
                             id: node_id.as_ref().0,
                             subnet_id: subnet_id.as_ref().0,
@@ -259,6 +280,7 @@ impl Snapshotter {
                                 .context("unable to parse IP address")?,
                             port: http_endpoint.port as u16, // Port is u16 anyway
                             tls_certificate: cert.certificate_der,
+                            signing_public_key,
                             replica_version: replica_version.to_string(),
                         };
                         let node = Arc::new(node);
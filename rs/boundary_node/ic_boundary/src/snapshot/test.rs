@@ -2,7 +2,11 @@ use super::*;
 
 use ic_crypto_test_utils_keys::public_keys::valid_tls_certificate_and_validation_time;
 
-use crate::test_utils::create_fake_registry_client;
+use ic_protobuf::registry::crypto::v1::PublicKey as PublicKeyProto;
+use ic_registry_client_fake::FakeRegistryClient;
+use ic_registry_keys::make_crypto_node_key;
+
+use crate::test_utils::{create_fake_registry_client, create_fake_registry_data_provider};
 
 #[tokio::test]
 async fn test_routing_table() -> Result<(), Error> {
@@ -41,3 +45,44 @@ async fn test_routing_table() -> Result<(), Error> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_node_without_signing_key() -> Result<(), Error> {
+    let (data_provider, nodes, _) = create_fake_registry_data_provider(2, 2, None);
+
+    // Remove the signing key of one node
+    data_provider
+        .add(
+            &make_crypto_node_key(nodes[0].0, KeyPurpose::NodeSigning),
+            RegistryVersion::new(2),
+            None::<PublicKeyProto>,
+        )
+        .unwrap();
+
+    let reg = FakeRegistryClient::new(Arc::new(data_provider));
+    reg.update_to_latest_version();
+    let reg = Arc::new(reg);
+
+    // Without verification the key is not needed
+    let snapshot = Arc::new(ArcSwapOption::empty());
+    let mut snapshotter = Snapshotter::new(Arc::clone(&snapshot), reg.clone(), Duration::ZERO);
+    snapshotter.snapshot()?;
+    let snapshot = snapshot.load_full().unwrap();
+
+    assert_eq!(snapshot.nodes.len(), 4);
+    for (id, node) in snapshot.nodes.iter() {
+        assert_eq!(
+            node.signing_public_key.is_none(),
+            *id == nodes[0].0.to_string()
+        );
+    }
+
+    // With verification the snapshot fails
+    let snapshot = Arc::new(ArcSwapOption::empty());
+    let mut snapshotter = Snapshotter::new(Arc::clone(&snapshot), reg, Duration::ZERO);
+    snapshotter.set_require_node_signing_keys(true);
+    assert!(snapshotter.snapshot().is_err());
+    assert!(snapshot.load_full().is_none());
+
+    Ok(())
+}
@@ -11,7 +11,9 @@ use http;
 use ic_base_types::NodeId;
 use ic_certification_test_utils::CertificateBuilder;
 use ic_certification_test_utils::CertificateData::*;
-use ic_crypto_test_utils_keys::public_keys::valid_tls_certificate_and_validation_time;
+use ic_crypto_test_utils_keys::public_keys::{
+    valid_node_signing_public_key, valid_tls_certificate_and_validation_time,
+};
 use ic_crypto_tree_hash::Digest;
 use ic_protobuf::registry::{
     crypto::v1::PublicKey as PublicKeyProto,
@@ -21,16 +23,17 @@ use ic_protobuf::registry::{
 };
 use ic_registry_client_fake::FakeRegistryClient;
 use ic_registry_keys::{
-    make_crypto_threshold_signing_pubkey_key, make_crypto_tls_cert_key, make_node_record_key,
-    make_routing_table_record_key, make_subnet_list_record_key, make_subnet_record_key,
-    ROOT_SUBNET_ID_KEY,
+    make_crypto_node_key, make_crypto_threshold_signing_pubkey_key, make_crypto_tls_cert_key,
+    make_node_record_key, make_routing_table_record_key, make_subnet_list_record_key,
+    make_subnet_record_key, ROOT_SUBNET_ID_KEY,
 };
 use ic_registry_proto_data_provider::ProtoRegistryDataProvider;
 use ic_registry_routing_table::{CanisterIdRange, RoutingTable as RoutingTableIC};
 use ic_test_utilities::types::ids::{node_test_id, subnet_test_id};
 use ic_test_utilities_registry::test_subnet_record;
 use ic_types::{
-    crypto::threshold_sig::ThresholdSigPublicKey, CanisterId, RegistryVersion, SubnetId,
+    crypto::{threshold_sig::ThresholdSigPublicKey, KeyPurpose},
+    CanisterId, RegistryVersion, SubnetId,
 };
 use prometheus::Registry;
 use rand::Rng;
@@ -80,14 +83,14 @@ pub fn new_threshold_key() -> ThresholdSigPublicKey {
     pk
 }
 
-// Generate a fake registry client with some data
+// Generate a fake registry data provider with some data
 #[allow(clippy::type_complexity)]
-pub fn create_fake_registry_client(
+pub fn create_fake_registry_data_provider(
     subnet_count: usize,
     nodes_per_subnet: usize,
     force_node_id: Option<NodeId>,
 ) -> (
-    FakeRegistryClient,
+    ProtoRegistryDataProvider,
     Vec<(NodeId, String)>,
     Vec<(SubnetId, CanisterIdRange)>,
 ) {
@@ -157,6 +160,15 @@ pub fn create_fake_registry_client(
                     Some(valid_tls_certificate_and_validation_time().0),
                 )
                 .expect("failed to add TLS certificate to registry");
+
+            // Add node signing key
+            data_provider
+                .add(
+                    &make_crypto_node_key(node_id, KeyPurpose::NodeSigning),
+                    reg_ver,
+                    Some(valid_node_signing_public_key()),
+                )
+                .expect("failed to add node signing key to registry");
         }
 
         // Add subnet
@@ -196,6 +208,23 @@ pub fn create_fake_registry_client(
         )
         .expect("could not add routing table");
 
+    (data_provider, nodes, ranges)
+}
+
+// Generate a fake registry client with some data
+#[allow(clippy::type_complexity)]
+pub fn create_fake_registry_client(
+    subnet_count: usize,
+    nodes_per_subnet: usize,
+    force_node_id: Option<NodeId>,
+) -> (
+    FakeRegistryClient,
+    Vec<(NodeId, String)>,
+    Vec<(SubnetId, CanisterIdRange)>,
+) {
+    let (data_provider, nodes, ranges) =
+        create_fake_registry_data_provider(subnet_count, nodes_per_subnet, force_node_id);
+
     let registry_client = FakeRegistryClient::new(Arc::new(data_provider));
     registry_client.update_to_latest_version();
 
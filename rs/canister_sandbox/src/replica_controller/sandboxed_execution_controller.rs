@@ -12,7 +12,8 @@ use ic_embedders::wasm_executor::{
     SliceExecutionOutput, WasmExecutionResult, WasmExecutor,
 };
 use ic_embedders::{
-    wasm_utils::WasmImportsDetails, CompilationCache, CompilationResult, WasmExecutionInput,
+    wasm_utils::{WasmImportsDetails, WasmMemoryDeclaration},
+    CompilationCache, CompilationResult, WasmExecutionInput,
 };
 use ic_interfaces::execution_environment::{HypervisorError, HypervisorResult};
 #[cfg(target_os = "linux")]
@@ -810,7 +811,12 @@ impl WasmExecutor for SandboxedExecutionController {
         canister_root: PathBuf,
         canister_id: CanisterId,
         compilation_cache: Arc<CompilationCache>,
-    ) -> HypervisorResult<(ExecutionState, NumInstructions, Option<CompilationResult>)> {
+    ) -> HypervisorResult<(
+        ExecutionState,
+        NumInstructions,
+        Option<CompilationResult>,
+        Option<WasmMemoryDeclaration>,
+    )> {
         let _create_exe_state_timer = self
             .metrics
             .sandboxed_execution_replica_create_exe_state_duration
@@ -964,6 +970,7 @@ impl WasmExecutor for SandboxedExecutionController {
             execution_state,
             serialized_module.compilation_cost,
             compilation_result,
+            serialized_module.main_memory,
         ))
     }
 }
@@ -68,7 +68,7 @@ mod tests {
         types::ids::{canister_test_id, node_test_id, subnet_test_id, user_test_id},
     };
     use ic_test_utilities_time::mock_time;
-    use ic_types::{xnet::StreamHeader, CanisterId, Cycles, ExecutionRound};
+    use ic_types::{xnet::StreamHeader, CanisterId, Cycles, ExecutionRound, ReplicaVersion};
     use ic_wasm_types::CanisterModule;
     use maplit::{btreemap, btreeset};
     use std::collections::{BTreeSet, VecDeque};
@@ -651,6 +651,7 @@ mod tests {
                 subnet_type: SubnetType::Application,
                 subnet_features: SubnetFeatures::default(),
                 ecdsa_keys_held: BTreeSet::new(),
+                replica_version: ReplicaVersion::default(),
             },
             subnet_test_id(1) => SubnetTopology {
                 public_key: vec![5, 6, 7, 8],
@@ -658,6 +659,7 @@ mod tests {
                 subnet_type: SubnetType::Application,
                 subnet_features: SubnetFeatures::default(),
                 ecdsa_keys_held: BTreeSet::new(),
+                replica_version: ReplicaVersion::default(),
             }
         };
         fn id_range(from: u64, to: u64) -> CanisterIdRange {
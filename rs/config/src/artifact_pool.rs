@@ -53,6 +53,22 @@ pub struct BackupConfig {
     pub retention_time_secs: u64,
     /// Time interval between purges.
     pub purging_interval_secs: u64,
+    /// The on-disk format of the backup.
+    #[serde(default)]
+    pub format: BackupFormat,
+}
+
+/// The on-disk format of the consensus artifact backup.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BackupFormat {
+    /// Every artifact is stored in its own protobuf file, in a directory per
+    /// height.
+    #[default]
+    Directory,
+    /// The artifacts are stored zstd compressed in segment files, each covering
+    /// [`BACKUP_GROUP_SIZE`] heights and indexed by a separate file.
+    Packed,
 }
 
 /// The configuration for the ingress and consensus artifact pools, both the
@@ -120,7 +120,11 @@ pub const SAMPLE_CONFIG: &str = r#"
             // How long the backup artifact stay on the disk before they get purged.
             retention_time_secs: 3600,
             // How often we purge.
-            purging_interval_secs: 3600
+            purging_interval_secs: 3600,
+            // The on-disk format of the backup: "directory" stores one file per artifact,
+            // "packed" stores compressed artifacts in indexed segment files. Defaults to
+            // "directory".
+            format: "directory"
         }
     },
     // ============================================
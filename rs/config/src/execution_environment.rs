@@ -5,7 +5,7 @@ use ic_types::{
     Cycles, NumBytes, NumInstructions, MAX_STABLE_MEMORY_IN_BYTES, MAX_WASM_MEMORY_IN_BYTES,
 };
 use serde::{Deserialize, Serialize};
-use std::{str::FromStr, time::Duration};
+use std::{path::PathBuf, str::FromStr, time::Duration};
 
 const MIB: u64 = 1024 * 1024;
 const GIB: u64 = MIB * 1024;
@@ -79,6 +79,11 @@ pub const INSTRUCTION_OVERHEAD_PER_QUERY_CALL: u64 = 50_000_000;
 /// See also `QUERY_EXECUTION_THREADS_PER_CANISTER`.
 pub(crate) const QUERY_EXECUTION_THREADS_TOTAL: usize = 4;
 
+/// The maximum number of query calls to canisters on other subnets that may be
+/// outstanding at the same time. Query execution threads wait for the response
+/// of such calls, so this keeps half of them available for other queries.
+pub(crate) const MAX_OUTSTANDING_REMOTE_QUERY_CALLS: usize = QUERY_EXECUTION_THREADS_TOTAL / 2;
+
 /// When a canister is scheduled for query execution, it is allowed to run for
 /// this amount of time. This limit controls how many queries the canister
 /// executes when it is scheduled. The limit does not control the duration of an
@@ -129,9 +134,15 @@ const BITCOIN_MAINNET_SOFT_LAUNCH_CANISTER_ID: &str = "gsvzx-syaaa-aaaan-aaabq-c
 /// The capacity of the Wasm compilation cache.
 pub const MAX_COMPILATION_CACHE_SIZE: NumBytes = NumBytes::new(10 * GIB);
 
+/// The capacity of the on-disk tier of the Wasm compilation cache.
+pub const MAX_COMPILATION_CACHE_DISK_SIZE: NumBytes = NumBytes::new(20 * GIB);
+
 /// Maximum number of controllers allowed in a request (specified in the interface spec).
 pub const MAX_ALLOWED_CONTROLLERS_COUNT: usize = 10;
 
+/// Maximum number of snapshots a canister can have at any time.
+pub const MAX_NUMBER_OF_SNAPSHOTS_PER_CANISTER: usize = 1;
+
 #[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
 #[serde(default)]
 pub struct Config {
@@ -210,6 +221,10 @@ pub struct Config {
     /// the actual call.
     pub instruction_overhead_per_query_call: NumInstructions,
 
+    /// The maximum number of query calls to canisters on other subnets that may
+    /// be outstanding at the same time. Further calls are rejected.
+    pub max_outstanding_remote_query_calls: usize,
+
     /// If this flag is enabled, then message execution of canisters will be
     /// rate limited based on the amount of modified memory.
     pub rate_limiting_of_heap_delta: FlagStatus,
@@ -246,6 +261,14 @@ pub struct Config {
     /// The capacity of the Wasm compilation cache.
     pub max_compilation_cache_size: NumBytes,
 
+    /// The directory in which compiled Wasm modules are persisted across
+    /// restarts. The on-disk tier of the compilation cache is disabled if
+    /// this is `None`.
+    pub compilation_cache_dir: Option<PathBuf>,
+
+    /// The capacity of the on-disk tier of the Wasm compilation cache.
+    pub max_compilation_cache_disk_size: NumBytes,
+
     /// Indicate whether query stats should be collected or not.
     pub query_stats_aggregation: FlagStatus,
 
@@ -261,6 +284,15 @@ pub struct Config {
     /// Indicates whether canister backup and restore feature is enabled or not.
     pub canister_snapshots: FlagStatus,
 
+    /// Indicates whether canister snapshots can be downloaded and uploaded.
+    ///
+    /// Must stay disabled until snapshots are persisted in checkpoints and
+    /// charged for.
+    pub canister_snapshot_data_transfer: FlagStatus,
+
+    /// Maximum number of snapshots a canister can have at any time.
+    pub max_number_of_snapshots_per_canister: usize,
+
     // TODO(IC-272): remove this flag once the feature is enabled by default.
     /// Indicates whether canister logging feature is enabled or not.
     pub canister_logging: FlagStatus,
@@ -311,6 +343,7 @@ impl Default for Config {
             instruction_overhead_per_query_call: NumInstructions::from(
                 INSTRUCTION_OVERHEAD_PER_QUERY_CALL,
             ),
+            max_outstanding_remote_query_calls: MAX_OUTSTANDING_REMOTE_QUERY_CALLS,
             rate_limiting_of_heap_delta: FlagStatus::Enabled,
             rate_limiting_of_instructions: FlagStatus::Enabled,
             // The allocatable compute capacity is capped at 50% to ensure that
@@ -332,11 +365,15 @@ impl Default for Config {
             query_cache_max_expiry_time: QUERY_CACHE_MAX_EXPIRY_TIME,
             query_cache_data_certificate_expiry_time: QUERY_CACHE_DATA_CERTIFICATE_EXPIRY_TIME,
             max_compilation_cache_size: MAX_COMPILATION_CACHE_SIZE,
+            compilation_cache_dir: None,
+            max_compilation_cache_disk_size: MAX_COMPILATION_CACHE_DISK_SIZE,
             query_stats_aggregation: FlagStatus::Disabled,
             query_stats_epoch_length: QUERY_STATS_EPOCH_LENGTH,
             wasm_chunk_store: FlagStatus::Enabled,
             stop_canister_timeout_duration: STOP_CANISTER_TIMEOUT_DURATION,
             canister_snapshots: FlagStatus::Disabled,
+            canister_snapshot_data_transfer: FlagStatus::Disabled,
+            max_number_of_snapshots_per_canister: MAX_NUMBER_OF_SNAPSHOTS_PER_CANISTER,
             canister_logging: FlagStatus::Disabled,
             dirty_page_logging: FlagStatus::Disabled,
         }
@@ -4,7 +4,7 @@
 use std::time::Duration;
 
 use crate::execution_environment::SUBNET_HEAP_DELTA_CAPACITY;
-use ic_base_types::NumBytes;
+use ic_base_types::{NumBytes, NumSeconds};
 use ic_registry_subnet_type::SubnetType;
 use ic_types::{Cycles, ExecutionRound, NumInstructions};
 use serde::{Deserialize, Serialize};
@@ -149,6 +149,10 @@ const DEFAULT_RESERVED_BALANCE_LIMIT: Cycles = Cycles::new(5 * T);
 /// 1/10th of a round.
 pub const DEFAULT_UPLOAD_CHUNK_INSTRUCTIONS: NumInstructions = NumInstructions::new(200_000_000);
 
+/// The `canister_on_low_cycles` hook is run once an idle canister would reach
+/// its freezing threshold within 7 days.
+const LOW_CYCLES_THRESHOLD: NumSeconds = NumSeconds::new(7 * 24 * 3600);
+
 /// The per subnet type configuration for the scheduler component
 #[derive(Clone, Serialize, Deserialize)]
 pub struct SchedulerConfig {
@@ -242,6 +246,11 @@ pub struct SchedulerConfig {
 
     /// Number of instructions to count when uploading a chunk to the wasm store.
     pub upload_wasm_chunk_instructions: NumInstructions,
+
+    /// The scheduler runs the `canister_on_low_cycles` hook of a canister once
+    /// its cycles balance would fall below the freezing threshold within this
+    /// duration if the canister stayed idle.
+    pub low_cycles_threshold: NumSeconds,
 }
 
 impl SchedulerConfig {
@@ -268,6 +277,7 @@ impl SchedulerConfig {
             dirty_page_overhead: DEFAULT_DIRTY_PAGE_OVERHEAD,
             accumulated_priority_reset_interval: ACCUMULATED_PRIORITY_RESET_INTERVAL,
             upload_wasm_chunk_instructions: DEFAULT_UPLOAD_CHUNK_INSTRUCTIONS,
+            low_cycles_threshold: LOW_CYCLES_THRESHOLD,
         }
     }
 
@@ -304,6 +314,7 @@ impl SchedulerConfig {
             dirty_page_overhead: SYSTEM_SUBNET_DIRTY_PAGE_OVERHEAD,
             accumulated_priority_reset_interval: ACCUMULATED_PRIORITY_RESET_INTERVAL,
             upload_wasm_chunk_instructions: NumInstructions::from(0),
+            low_cycles_threshold: LOW_CYCLES_THRESHOLD,
         }
     }
 
@@ -333,6 +344,7 @@ impl SchedulerConfig {
             dirty_page_overhead: DEFAULT_DIRTY_PAGE_OVERHEAD,
             accumulated_priority_reset_interval: ACCUMULATED_PRIORITY_RESET_INTERVAL,
             upload_wasm_chunk_instructions: DEFAULT_UPLOAD_CHUNK_INSTRUCTIONS,
+            low_cycles_threshold: LOW_CYCLES_THRESHOLD,
         }
     }
 
@@ -225,6 +225,7 @@ pub fn run_drun(uo: DrunOptions) -> Result<(), String> {
             Arc::clone(&cycles_account_manager),
             Arc::clone(&state_manager) as Arc<_>,
             state_manager.get_fd_factory(),
+            None,
         )
         .into_parts();
 
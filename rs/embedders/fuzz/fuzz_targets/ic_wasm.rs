@@ -154,6 +154,8 @@ impl Config for ICWasmConfig {
             "canister_post_upgrade".to_string(),
             "canister_heartbeat".to_string(),
             "canister_global_timer".to_string(),
+            "canister_on_low_wasm_memory".to_string(),
+            "canister_on_low_cycles".to_string(),
         ]))
     }
 
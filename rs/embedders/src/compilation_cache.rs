@@ -1,44 +1,354 @@
-use std::sync::{Arc, Mutex};
+use std::{
+    collections::hash_map::DefaultHasher,
+    fs::{self, File},
+    hash::{Hash, Hasher},
+    io::Write,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::SystemTime,
+};
 
-use crate::SerializedModule;
+use crate::{SerializedModule, WasmtimeEmbedder};
+use ic_config::embedders::Config as EmbeddersConfig;
+use ic_crypto_sha2::Sha256;
 use ic_interfaces::execution_environment::HypervisorResult;
-use ic_types::NumBytes;
+use ic_logger::{info, warn, ReplicaLogger};
+use ic_metrics::MetricsRegistry;
+use ic_types::{replica_version::REPLICA_BINARY_HASH, NumBytes, ReplicaVersion};
 use ic_utils_lru_cache::LruCache;
 use ic_wasm_types::{CanisterModule, WasmHash};
+use prometheus::IntCounter;
+
+/// The version of the on-disk format of the compilation cache. It has to be
+/// increased whenever the instrumentation or the layout of `SerializedModule`
+/// changes in a way that is not reflected in the `EmbeddersConfig`.
+const COMPILATION_CACHE_VERSION: u32 = 2;
+
+/// Every file on disk starts with the version key followed by the checksum of
+/// the serialized module.
+const DIGEST_LENGTH: usize = 32;
+const HEADER_LENGTH: usize = 2 * DIGEST_LENGTH;
+
+/// Extension of the files that are still being written.
+const TMP_EXTENSION: &str = "tmp";
+
+/// The file in the cache directory that holds the version key of its entries.
+const VERSION_KEY_FILE: &str = "version_key";
+
+/// Computes the key that identifies the replica build, the Wasmtime compiler
+/// and the instrumentation settings used to produce a serialized module. A
+/// cache directory written with a different key is wiped.
+pub fn compilation_cache_version_key(
+    config: &EmbeddersConfig,
+    replica_version: &ReplicaVersion,
+) -> [u8; DIGEST_LENGTH] {
+    let mut hasher = Sha256::new();
+    hasher.write(&COMPILATION_CACHE_VERSION.to_le_bytes());
+    hasher.write(replica_version.as_ref().as_bytes());
+    // Distinguishes builds of the same version, e.g. during development.
+    if let Some(binary_hash) = REPLICA_BINARY_HASH.get() {
+        hasher.write(binary_hash.as_bytes());
+    }
+    // Covers the Wasmtime version and the settings of its compiler.
+    let engine_config = WasmtimeEmbedder::wasmtime_execution_config(config);
+    if let Ok(engine) = wasmtime::Engine::new(&engine_config) {
+        let mut engine_hasher = DefaultHasher::new();
+        engine
+            .precompile_compatibility_hash()
+            .hash(&mut engine_hasher);
+        hasher.write(&engine_hasher.finish().to_le_bytes());
+    }
+    hasher.write(&bincode::serialize(config).expect("Failed to serialize embedders config"));
+    hasher.finish()
+}
+
+/// Prepares `dir` to hold entries written with `version_key`. If the
+/// directory holds entries written with another key, they are all removed.
+fn init_cache_dir(
+    dir: &Path,
+    version_key: &[u8; DIGEST_LENGTH],
+    log: &ReplicaLogger,
+) -> std::io::Result<()> {
+    fs::create_dir_all(dir)?;
+    let version_key_path = dir.join(VERSION_KEY_FILE);
+    let version_key = hex::encode(version_key);
+    match fs::read_to_string(&version_key_path) {
+        Ok(existing_key) if existing_key == version_key => {
+            // Leftovers of interrupted writes.
+            for entry in fs::read_dir(dir)? {
+                let path = entry?.path();
+                if path.extension().map_or(false, |ext| ext == TMP_EXTENSION) {
+                    fs::remove_file(&path)?;
+                }
+            }
+        }
+        _ => {
+            info!(
+                log,
+                "Resetting the compilation cache in {} for a new version key",
+                dir.display()
+            );
+            fs::remove_dir_all(dir)?;
+            fs::create_dir_all(dir)?;
+            fs::write(&version_key_path, version_key)?;
+        }
+    }
+    Ok(())
+}
+
+struct DiskCacheMetrics {
+    hits: IntCounter,
+    misses: IntCounter,
+    insertions: IntCounter,
+    evictions: IntCounter,
+    invalid_entries: IntCounter,
+}
+
+impl DiskCacheMetrics {
+    fn new(metrics_registry: &MetricsRegistry) -> Self {
+        Self {
+            hits: metrics_registry.int_counter(
+                "compilation_cache_disk_hits_total",
+                "Number of lookups in the on-disk compilation cache that found a module.",
+            ),
+            misses: metrics_registry.int_counter(
+                "compilation_cache_disk_misses_total",
+                "Number of lookups in the on-disk compilation cache that found no module.",
+            ),
+            insertions: metrics_registry.int_counter(
+                "compilation_cache_disk_insertions_total",
+                "Number of modules written to the on-disk compilation cache.",
+            ),
+            evictions: metrics_registry.int_counter(
+                "compilation_cache_disk_evictions_total",
+                "Number of modules evicted from the on-disk compilation cache.",
+            ),
+            invalid_entries: metrics_registry.int_counter(
+                "compilation_cache_disk_invalid_entries_total",
+                "Number of entries in the on-disk compilation cache that failed validation.",
+            ),
+        }
+    }
+}
+
+/// Keeps serialized modules in files named after the hash of their Wasm.
+/// Only successfully compiled modules are stored. The total size of the files
+/// is bounded by `max_size`; when it is exceeded, the least recently used
+/// files are removed.
+struct DiskCache {
+    dir: PathBuf,
+    max_size: NumBytes,
+    version_key: [u8; DIGEST_LENGTH],
+    // Serializes writes and evictions.
+    write_lock: Mutex<()>,
+    metrics: DiskCacheMetrics,
+    log: ReplicaLogger,
+}
+
+impl DiskCache {
+    fn path(&self, wasm_hash: &WasmHash) -> PathBuf {
+        self.dir.join(hex::encode(wasm_hash.to_slice()))
+    }
+
+    fn get(&self, wasm_hash: &WasmHash) -> Option<Arc<SerializedModule>> {
+        let path = self.path(wasm_hash);
+        let contents = match fs::read(&path) {
+            Ok(contents) => contents,
+            Err(_) => {
+                self.metrics.misses.inc();
+                return None;
+            }
+        };
+        match self.decode(&contents) {
+            Some(serialized_module) => {
+                self.metrics.hits.inc();
+                // Refresh the modification time so that eviction removes the
+                // least recently used entries first.
+                if let Ok(file) = File::options().write(true).open(&path) {
+                    let _ = file.set_modified(SystemTime::now());
+                }
+                Some(Arc::new(serialized_module))
+            }
+            None => {
+                self.metrics.invalid_entries.inc();
+                self.metrics.misses.inc();
+                warn!(
+                    self.log,
+                    "Removing invalid compilation cache entry {}",
+                    path.display()
+                );
+                let _ = fs::remove_file(&path);
+                None
+            }
+        }
+    }
+
+    fn decode(&self, contents: &[u8]) -> Option<SerializedModule> {
+        if contents.len() < HEADER_LENGTH {
+            return None;
+        }
+        let (header, payload) = contents.split_at(HEADER_LENGTH);
+        let (version_key, checksum) = header.split_at(DIGEST_LENGTH);
+        if version_key != self.version_key || checksum != Sha256::hash(payload) {
+            return None;
+        }
+        bincode::deserialize(payload).ok()
+    }
+
+    fn insert(&self, wasm_hash: &WasmHash, serialized_module: &SerializedModule) {
+        let payload = match bincode::serialize(serialized_module) {
+            Ok(payload) => payload,
+            Err(err) => {
+                warn!(
+                    self.log,
+                    "Failed to serialize module for the compilation cache: {}", err
+                );
+                return;
+            }
+        };
+        let _guard = self.write_lock.lock().unwrap();
+        let path = self.path(wasm_hash);
+        if let Err(err) = self.write_atomically(&path, &payload) {
+            warn!(
+                self.log,
+                "Failed to write compilation cache entry {}: {}",
+                path.display(),
+                err
+            );
+            return;
+        }
+        self.metrics.insertions.inc();
+        self.evict();
+    }
+
+    fn write_atomically(&self, path: &Path, payload: &[u8]) -> std::io::Result<()> {
+        let tmp_path = path.with_extension(TMP_EXTENSION);
+        let result = (|| {
+            let mut file = File::create(&tmp_path)?;
+            file.write_all(&self.version_key)?;
+            file.write_all(&Sha256::hash(payload))?;
+            file.write_all(payload)?;
+            file.sync_data()?;
+            fs::rename(&tmp_path, path)
+        })();
+        if result.is_err() {
+            let _ = fs::remove_file(&tmp_path);
+        }
+        result
+    }
+
+    /// Removes the least recently used entries until the total size of the
+    /// cache directory fits into `max_size`.
+    fn evict(&self) {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(_) => return,
+        };
+        let mut files: Vec<(SystemTime, u64, PathBuf)> = entries
+            .filter_map(|entry| {
+                let entry = entry.ok()?;
+                let metadata = entry.metadata().ok()?;
+                if !metadata.is_file() || entry.file_name() == VERSION_KEY_FILE {
+                    return None;
+                }
+                Some((metadata.modified().ok()?, metadata.len(), entry.path()))
+            })
+            .collect();
+        let mut total_size: u64 = files.iter().map(|(_, size, _)| size).sum();
+        if total_size <= self.max_size.get() {
+            return;
+        }
+        files.sort();
+        for (_, size, path) in files {
+            if total_size <= self.max_size.get() {
+                break;
+            }
+            if fs::remove_file(&path).is_ok() {
+                total_size -= size;
+                self.metrics.evictions.inc();
+            }
+        }
+    }
+}
 
 /// Stores the serialized modules of wasm code that has already been compiled so
 /// that it can be used again without recompiling.
+///
+/// Optionally, successfully compiled modules are also persisted on disk, so
+/// that they survive restarts of the replica and evictions from memory.
 pub struct CompilationCache {
     cache: Mutex<LruCache<WasmHash, HypervisorResult<Arc<SerializedModule>>>>,
+    disk_cache: Option<DiskCache>,
 }
 
 impl CompilationCache {
     pub fn new(capacity: NumBytes) -> Self {
         Self {
             cache: Mutex::new(LruCache::new(capacity)),
+            disk_cache: None,
         }
     }
 
+    /// Creates a cache that is backed by files in `dir`. If the directory was
+    /// written with a different `version_key` (see
+    /// [`compilation_cache_version_key`]), its contents are removed.
+    pub fn new_with_disk_cache(
+        capacity: NumBytes,
+        dir: PathBuf,
+        max_disk_size: NumBytes,
+        version_key: [u8; DIGEST_LENGTH],
+        metrics_registry: &MetricsRegistry,
+        log: ReplicaLogger,
+    ) -> std::io::Result<Self> {
+        init_cache_dir(&dir, &version_key, &log)?;
+        Ok(Self {
+            cache: Mutex::new(LruCache::new(capacity)),
+            disk_cache: Some(DiskCache {
+                dir,
+                max_size: max_disk_size,
+                version_key,
+                write_lock: Mutex::new(()),
+                metrics: DiskCacheMetrics::new(metrics_registry),
+                log,
+            }),
+        })
+    }
+
     pub fn insert(
         &self,
         canister_module: &CanisterModule,
         serialized_module: HypervisorResult<Arc<SerializedModule>>,
     ) {
+        let wasm_hash = WasmHash::from(canister_module);
+        if let (Some(disk_cache), Ok(serialized_module)) = (&self.disk_cache, &serialized_module) {
+            disk_cache.insert(&wasm_hash, serialized_module);
+        }
         self.cache
             .lock()
             .unwrap()
-            .push(WasmHash::from(canister_module), serialized_module);
+            .push(wasm_hash, serialized_module);
     }
 
     pub fn get(
         &self,
         canister_module: &CanisterModule,
     ) -> Option<HypervisorResult<Arc<SerializedModule>>> {
-        self.cache
+        let wasm_hash = WasmHash::from(canister_module);
+        if let Some(result) = self
+            .cache
             .lock()
             .unwrap()
-            .get(&WasmHash::from(canister_module))
+            .get(&wasm_hash)
             .map(|o| o.as_ref().map(Arc::clone).map_err(|e| e.clone()))
+        {
+            return Some(result);
+        }
+        let serialized_module = self.disk_cache.as_ref()?.get(&wasm_hash)?;
+        self.cache
+            .lock()
+            .unwrap()
+            .push(wasm_hash, Ok(Arc::clone(&serialized_module)));
+        Some(Ok(serialized_module))
     }
 
     #[doc(hidden)]
@@ -46,3 +356,168 @@ impl CompilationCache {
         self.cache.lock().unwrap().clear()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{wasm_utils::compile, WasmtimeEmbedder};
+    use ic_logger::replica_logger::no_op_logger;
+    use ic_wasm_types::BinaryEncodedWasm;
+
+    fn compile_module(wat: &str) -> (CanisterModule, Arc<SerializedModule>) {
+        let wasm = wat::parse_str(wat).unwrap();
+        let embedder = WasmtimeEmbedder::new(EmbeddersConfig::default(), no_op_logger());
+        let (_, result) = compile(&embedder, &BinaryEncodedWasm::new(wasm.clone()));
+        let (_, serialized_module) = result.unwrap();
+        (CanisterModule::new(wasm), Arc::new(serialized_module))
+    }
+
+    fn disk_backed_cache(dir: &Path, max_disk_size: NumBytes, version_key: u8) -> CompilationCache {
+        CompilationCache::new_with_disk_cache(
+            NumBytes::new(1 << 30),
+            dir.to_path_buf(),
+            max_disk_size,
+            [version_key; DIGEST_LENGTH],
+            &MetricsRegistry::new(),
+            no_op_logger(),
+        )
+        .unwrap()
+    }
+
+    /// Returns the number of files in the cache directory besides the version
+    /// key file.
+    fn cache_entries(dir: &Path) -> usize {
+        fs::read_dir(dir)
+            .unwrap()
+            .filter(|entry| entry.as_ref().unwrap().file_name() != VERSION_KEY_FILE)
+            .count()
+    }
+
+    #[test]
+    fn module_survives_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let (canister_module, serialized_module) =
+            compile_module(r#"(module (func (export "canister_update go")))"#);
+
+        let cache = disk_backed_cache(dir.path(), NumBytes::new(1 << 30), 0);
+        cache.insert(&canister_module, Ok(Arc::clone(&serialized_module)));
+
+        let cache = disk_backed_cache(dir.path(), NumBytes::new(1 << 30), 0);
+        let loaded = cache.get(&canister_module).unwrap().unwrap();
+        assert_eq!(loaded.bytes.as_slice(), serialized_module.bytes.as_slice());
+        assert_eq!(
+            loaded.exported_functions,
+            serialized_module.exported_functions
+        );
+        assert_eq!(loaded.compilation_cost, serialized_module.compilation_cost);
+    }
+
+    #[test]
+    fn entry_with_other_version_is_discarded() {
+        let dir = tempfile::tempdir().unwrap();
+        let (canister_module, serialized_module) =
+            compile_module(r#"(module (func (export "canister_update go")))"#);
+
+        let cache = disk_backed_cache(dir.path(), NumBytes::new(1 << 30), 0);
+        cache.insert(&canister_module, Ok(serialized_module));
+
+        let cache = disk_backed_cache(dir.path(), NumBytes::new(1 << 30), 1);
+        assert!(cache.get(&canister_module).is_none());
+        assert_eq!(cache_entries(dir.path()), 0);
+    }
+
+    #[test]
+    fn cache_dir_of_other_version_is_wiped() {
+        let dir = tempfile::tempdir().unwrap();
+        let (canister_module, serialized_module) =
+            compile_module(r#"(module (func (export "canister_update go")))"#);
+
+        let cache = disk_backed_cache(dir.path(), NumBytes::new(1 << 30), 0);
+        cache.insert(&canister_module, Ok(serialized_module));
+        fs::write(dir.path().join("unknown"), b"stale").unwrap();
+        assert_eq!(cache_entries(dir.path()), 2);
+
+        // Reopening with the same version keeps all files.
+        disk_backed_cache(dir.path(), NumBytes::new(1 << 30), 0);
+        assert_eq!(cache_entries(dir.path()), 2);
+
+        disk_backed_cache(dir.path(), NumBytes::new(1 << 30), 1);
+        assert_eq!(cache_entries(dir.path()), 0);
+    }
+
+    #[test]
+    fn version_key_depends_on_replica_version() {
+        let config = EmbeddersConfig::default();
+        let first = ReplicaVersion::try_from("first").unwrap();
+        let second = ReplicaVersion::try_from("second").unwrap();
+        assert_eq!(
+            compilation_cache_version_key(&config, &first),
+            compilation_cache_version_key(&config, &first)
+        );
+        assert_ne!(
+            compilation_cache_version_key(&config, &first),
+            compilation_cache_version_key(&config, &second)
+        );
+    }
+
+    #[test]
+    fn corrupted_entry_is_discarded() {
+        let dir = tempfile::tempdir().unwrap();
+        let (canister_module, serialized_module) =
+            compile_module(r#"(module (func (export "canister_update go")))"#);
+
+        let cache = disk_backed_cache(dir.path(), NumBytes::new(1 << 30), 0);
+        cache.insert(&canister_module, Ok(serialized_module));
+
+        let path = dir
+            .path()
+            .join(hex::encode(WasmHash::from(&canister_module).to_slice()));
+        let mut contents = fs::read(&path).unwrap();
+        let last = contents.len() - 1;
+        contents[last] ^= 0xff;
+        fs::write(&path, contents).unwrap();
+
+        let cache = disk_backed_cache(dir.path(), NumBytes::new(1 << 30), 0);
+        assert!(cache.get(&canister_module).is_none());
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn disk_cache_is_bounded_by_size() {
+        let dir = tempfile::tempdir().unwrap();
+        let (first_module, first_serialized) =
+            compile_module(r#"(module (func (export "canister_update first")))"#);
+        let (second_module, second_serialized) =
+            compile_module(r#"(module (func (export "canister_update second")))"#);
+
+        // Only one of the modules fits into the cache.
+        let entry_size =
+            (HEADER_LENGTH + bincode::serialize(first_serialized.as_ref()).unwrap().len()) as u64;
+        let cache = disk_backed_cache(dir.path(), NumBytes::new(entry_size + 100), 0);
+        cache.insert(&first_module, Ok(first_serialized));
+        // Make sure the modification times differ.
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        cache.insert(&second_module, Ok(second_serialized));
+        assert_eq!(cache_entries(dir.path()), 1);
+
+        let cache = disk_backed_cache(dir.path(), NumBytes::new(entry_size + 100), 0);
+        assert!(cache.get(&first_module).is_none());
+        assert!(cache.get(&second_module).is_some());
+    }
+
+    #[test]
+    fn compilation_errors_are_not_persisted() {
+        let dir = tempfile::tempdir().unwrap();
+        let canister_module = CanisterModule::new(vec![0, 1, 2, 3]);
+        let cache = disk_backed_cache(dir.path(), NumBytes::new(1 << 30), 0);
+        cache.insert(
+            &canister_module,
+            Err(
+                ic_interfaces::execution_environment::HypervisorError::InvalidWasm(
+                    ic_wasm_types::WasmValidationError::WasmtimeValidation("error".to_string()),
+                ),
+            ),
+        );
+        assert_eq!(cache_entries(dir.path()), 0);
+    }
+}
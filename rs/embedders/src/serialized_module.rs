@@ -8,7 +8,8 @@ use serde::{Deserialize, Serialize};
 use wasmtime::Module;
 
 use crate::wasm_utils::{
-    InstrumentationOutput, Segments, WasmImportsDetails, WasmValidationDetails,
+    InstrumentationOutput, Segments, WasmImportsDetails, WasmMemoryDeclaration,
+    WasmValidationDetails,
 };
 
 /// A `wasmtime::Module` that has been serialized.
@@ -69,6 +70,8 @@ pub struct SerializedModule {
     pub compilation_cost: NumInstructions,
     /// Imported System API functions that are deprecated, should become deprecated, or should only be used by NNS canisters.
     pub imports_details: WasmImportsDetails,
+    /// The main memory declared by the module, if any.
+    pub main_memory: Option<WasmMemoryDeclaration>,
 }
 
 impl CountBytes for SerializedModule {
@@ -91,6 +94,7 @@ impl SerializedModule {
             wasm_metadata: validation_details.wasm_metadata,
             compilation_cost: instrumentation_output.compilation_cost,
             imports_details: validation_details.imports_details,
+            main_memory: validation_details.main_memory,
         })
     }
 
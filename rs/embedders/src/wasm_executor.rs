@@ -15,7 +15,9 @@ use wasmtime::Module;
 
 use crate::wasmtime_embedder::CanisterMemoryType;
 use crate::{
-    wasm_utils::{compile, decoding::decode_wasm, Segments, WasmImportsDetails},
+    wasm_utils::{
+        compile, decoding::decode_wasm, Segments, WasmImportsDetails, WasmMemoryDeclaration,
+    },
     wasmtime_embedder::WasmtimeInstance,
     CompilationCache, CompilationResult, SerializedModule, WasmExecutionInput, WasmtimeEmbedder,
 };
@@ -50,13 +52,20 @@ pub trait WasmExecutor: Send + Sync {
         execution_state: &ExecutionState,
     ) -> (Option<CompilationResult>, WasmExecutionResult);
 
+    /// Creates the execution state of a canister with the given module. Also
+    /// returns the main memory declared by the module, if any.
     fn create_execution_state(
         &self,
         canister_module: CanisterModule,
         canister_root: PathBuf,
         canister_id: CanisterId,
         compilation_cache: Arc<CompilationCache>,
-    ) -> HypervisorResult<(ExecutionState, NumInstructions, Option<CompilationResult>)>;
+    ) -> HypervisorResult<(
+        ExecutionState,
+        NumInstructions,
+        Option<CompilationResult>,
+        Option<WasmMemoryDeclaration>,
+    )>;
 }
 
 struct WasmExecutorMetrics {
@@ -270,7 +279,12 @@ impl WasmExecutor for WasmExecutorImpl {
         canister_root: PathBuf,
         canister_id: CanisterId,
         compilation_cache: Arc<CompilationCache>,
-    ) -> HypervisorResult<(ExecutionState, NumInstructions, Option<CompilationResult>)> {
+    ) -> HypervisorResult<(
+        ExecutionState,
+        NumInstructions,
+        Option<CompilationResult>,
+        Option<WasmMemoryDeclaration>,
+    )> {
         // Compile Wasm binary and cache it.
         let wasm_binary = WasmBinary::new(canister_module);
         let CacheLookup {
@@ -314,6 +328,7 @@ impl WasmExecutor for WasmExecutorImpl {
             execution_state,
             serialized_module.compilation_cost,
             compilation_result,
+            serialized_module.main_memory,
         ))
     }
 }
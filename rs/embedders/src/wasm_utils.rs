@@ -38,6 +38,13 @@ pub struct WasmImportsDetails {
 #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Default)]
 pub struct Complexity(pub u64);
 
+/// The main memory declared by a Wasm module.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct WasmMemoryDeclaration {
+    /// The maximum size of the memory in Wasm pages, if the module declares one.
+    pub maximum: Option<u64>,
+}
+
 /// Returned as a result of `validate_wasm_binary` and provides
 /// additional information about the validation.
 #[derive(Debug, PartialEq, Eq, Default)]
@@ -46,6 +53,8 @@ pub struct WasmValidationDetails {
     pub wasm_metadata: WasmMetadata,
     pub largest_function_instruction_count: NumInstructions,
     pub max_complexity: Complexity,
+    /// The main memory declared by the module, if any.
+    pub main_memory: Option<WasmMemoryDeclaration>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
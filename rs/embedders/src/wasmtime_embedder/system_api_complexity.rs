@@ -34,6 +34,10 @@ pub mod overhead {
         pub const CANISTER_STATUS: NumInstructions = NumInstructions::new(0);
         pub const CANISTER_VERSION: NumInstructions = NumInstructions::new(0);
         pub const CERTIFIED_DATA_SET: NumInstructions = NumInstructions::new(0);
+        pub const COST_CALL: NumInstructions = NumInstructions::new(0);
+        pub const COST_CREATE_CANISTER: NumInstructions = NumInstructions::new(0);
+        pub const COST_HTTP_REQUEST: NumInstructions = NumInstructions::new(0);
+        pub const COST_SIGN_WITH_ECDSA: NumInstructions = NumInstructions::new(0);
         pub const CYCLES_BURN: NumInstructions = NumInstructions::new(100);
         pub const DATA_CERTIFICATE_COPY: NumInstructions = NumInstructions::new(0);
         pub const DATA_CERTIFICATE_PRESENT: NumInstructions = NumInstructions::new(0);
@@ -91,6 +95,10 @@ pub mod overhead {
         pub const CERTIFIED_DATA_SET: NumInstructions = NumInstructions::new(500);
         pub const CONTROLLER_COPY: NumInstructions = NumInstructions::new(500);
         pub const CONTROLLER_SIZE: NumInstructions = NumInstructions::new(500);
+        pub const COST_CALL: NumInstructions = NumInstructions::new(500);
+        pub const COST_CREATE_CANISTER: NumInstructions = NumInstructions::new(500);
+        pub const COST_HTTP_REQUEST: NumInstructions = NumInstructions::new(500);
+        pub const COST_SIGN_WITH_ECDSA: NumInstructions = NumInstructions::new(500);
         pub const DATA_CERTIFICATE_COPY: NumInstructions = NumInstructions::new(500);
         pub const DATA_CERTIFICATE_PRESENT: NumInstructions = NumInstructions::new(500);
         pub const DATA_CERTIFICATE_SIZE: NumInstructions = NumInstructions::new(500);
@@ -6,7 +6,7 @@ use ic_embedders::{
     wasm_utils::{
         validate_and_instrument_for_testing,
         validation::{extract_custom_section_name, RESERVED_SYMBOLS},
-        Complexity, WasmImportsDetails, WasmValidationDetails,
+        Complexity, WasmImportsDetails, WasmMemoryDeclaration, WasmValidationDetails,
     },
     WasmtimeEmbedder,
 };
@@ -1136,3 +1136,22 @@ fn wasm_with_multiple_code_sections_is_invalid() {
         ))
     )
 }
+
+#[test]
+fn can_validate_declared_main_memory() {
+    let main_memory = |wat: &str| {
+        validate_wasm_binary(&wat2wasm(wat).unwrap(), &EmbeddersConfig::default())
+            .unwrap()
+            .main_memory
+    };
+
+    assert_eq!(main_memory("(module)"), None);
+    assert_eq!(
+        main_memory("(module (memory 1))"),
+        Some(WasmMemoryDeclaration { maximum: None })
+    );
+    assert_eq!(
+        main_memory("(module (memory 1 2))"),
+        Some(WasmMemoryDeclaration { maximum: Some(2) })
+    );
+}
@@ -20,23 +20,34 @@ use ic_interfaces::execution_environment::{
 };
 use ic_logger::{error, fatal, info, ReplicaLogger};
 use ic_management_canister_types::{
-    CanisterChangeDetails, CanisterChangeOrigin, CanisterInstallModeV2, CanisterStatusResultV2,
-    CanisterStatusType, ChunkHash, InstallChunkedCodeArgs, InstallCodeArgsV2, Method as Ic00Method,
-    StoredChunksReply, UploadChunkReply,
+    CanisterChangeDetails, CanisterChangeOrigin, CanisterInstallModeV2, CanisterSnapshotDataKind,
+    CanisterSnapshotDataOffset, CanisterSnapshotResponse, CanisterStatusResultV2,
+    CanisterStatusType, ChunkHash, DeleteCanisterSnapshotArgs, InstallChunkedCodeArgs,
+    InstallCodeArgsV2, ListCanisterSnapshotArgs, ListCanisterSnapshotsResponse,
+    LoadCanisterSnapshotArgs, MemoryMetrics, Method as Ic00Method, ReadCanisterSnapshotDataArgs,
+    ReadCanisterSnapshotDataResponse, ReadCanisterSnapshotMetadataArgs,
+    ReadCanisterSnapshotMetadataResponse, SnapshotGlobal, StoredChunksReply,
+    TakeCanisterSnapshotArgs, UploadCanisterSnapshotDataArgs, UploadCanisterSnapshotMetadataArgs,
+    UploadCanisterSnapshotMetadataResponse, UploadChunkReply,
 };
 use ic_registry_provisional_whitelist::ProvisionalWhitelist;
 use ic_registry_subnet_type::SubnetType;
 use ic_replicated_state::canister_state::system_state::ReservationError;
 use ic_replicated_state::{
-    canister_state::system_state::{
-        wasm_chunk_store::{self, WasmChunkStore},
-        CyclesUseCase,
+    canister_snapshots::{CanisterSnapshot, SnapshotId, WasmModuleUpload},
+    canister_state::{
+        system_state::{
+            wasm_chunk_store::{self, WasmChunkHash, WasmChunkStore},
+            CyclesUseCase,
+        },
+        WASM_PAGE_SIZE_IN_BYTES,
     },
     metadata_state::subnet_call_context_manager::InstallCodeCallId,
-    page_map::PageAllocatorFileDescriptor,
-    CallOrigin, CanisterState, CanisterStatus, NetworkTopology, ReplicatedState, SchedulerState,
-    SystemState,
+    page_map::{Buffer, PageAllocatorFileDescriptor},
+    CallOrigin, CanisterState, CanisterStatus, Global, Memory, NetworkTopology, NumWasmPages,
+    PageMap, ReplicatedState, SchedulerState, SystemState,
 };
+use ic_sys::PAGE_SIZE;
 use ic_system_api::ExecutionParameters;
 use ic_types::{
     ingress::{IngressState, IngressStatus},
@@ -47,7 +58,7 @@ use ic_types::{
     nominal_cycles::NominalCycles,
     CanisterId, CanisterTimer, ComputeAllocation, Cycles, InvalidComputeAllocationError,
     InvalidMemoryAllocationError, MemoryAllocation, NumBytes, NumInstructions, PrincipalId,
-    SubnetId, Time,
+    SubnetId, Time, MAX_STABLE_MEMORY_IN_BYTES, MAX_WASM_MEMORY_IN_BYTES,
 };
 use ic_wasm_types::{CanisterModule, WasmHash};
 use num_traits::cast::ToPrimitive;
@@ -116,6 +127,7 @@ pub(crate) struct CanisterMgrConfig {
     rate_limiting_of_heap_delta: FlagStatus,
     heap_delta_rate_limit: NumBytes,
     upload_wasm_chunk_instructions: NumInstructions,
+    max_number_of_snapshots_per_canister: usize,
 }
 
 impl CanisterMgrConfig {
@@ -134,6 +146,7 @@ impl CanisterMgrConfig {
         rate_limiting_of_heap_delta: FlagStatus,
         heap_delta_rate_limit: NumBytes,
         upload_wasm_chunk_instructions: NumInstructions,
+        max_number_of_snapshots_per_canister: usize,
     ) -> Self {
         Self {
             subnet_memory_capacity,
@@ -149,6 +162,7 @@ impl CanisterMgrConfig {
             rate_limiting_of_heap_delta,
             heap_delta_rate_limit,
             upload_wasm_chunk_instructions,
+            max_number_of_snapshots_per_canister,
         }
     }
 }
@@ -467,7 +481,8 @@ impl CanisterManager {
             | Ok(Ic00Method::BitcoinSendTransaction)
             | Ok(Ic00Method::BitcoinSendTransactionInternal)
             | Ok(Ic00Method::BitcoinGetCurrentFeePercentiles)
-            | Ok(Ic00Method::NodeMetricsHistory) => Err(UserError::new(
+            | Ok(Ic00Method::NodeMetricsHistory)
+            | Ok(Ic00Method::SubnetInfo) => Err(UserError::new(
                 ErrorCode::CanisterRejectedMessage,
                 format!("Only canisters can call ic00 method {}", method_name),
             )),
@@ -490,7 +505,11 @@ impl CanisterManager {
             | Ok(Ic00Method::TakeCanisterSnapshot)
             | Ok(Ic00Method::LoadCanisterSnapshot)
             | Ok(Ic00Method::ListCanisterSnapshots)
-            | Ok(Ic00Method::DeleteCanisterSnapshot) => {
+            | Ok(Ic00Method::DeleteCanisterSnapshot)
+            | Ok(Ic00Method::ReadCanisterSnapshotMetadata)
+            | Ok(Ic00Method::ReadCanisterSnapshotData)
+            | Ok(Ic00Method::UploadCanisterSnapshotMetadata)
+            | Ok(Ic00Method::UploadCanisterSnapshotData) => {
                 // Reject large install methods if the flag is not enabled, or
                 // they are not implemented.
                 match method {
@@ -651,6 +670,9 @@ impl CanisterManager {
         if let Some(log_visibility) = settings.log_visibility() {
             canister.system_state.log_visibility = log_visibility;
         }
+        if let Some(wasm_memory_threshold) = settings.wasm_memory_threshold() {
+            canister.system_state.wasm_memory_threshold = wasm_memory_threshold;
+        }
     }
 
     /// Tries to apply the requested settings on the canister identified by
@@ -690,6 +712,14 @@ impl CanisterManager {
 
         let is_controllers_change =
             validated_settings.controller().is_some() || validated_settings.controllers().is_some();
+        let freezing_threshold = validated_settings.freezing_threshold();
+        let compute_allocation = validated_settings.compute_allocation();
+        let memory_allocation = validated_settings.memory_allocation();
+        let log_visibility = validated_settings.log_visibility();
+        let is_settings_change = freezing_threshold.is_some()
+            || compute_allocation.is_some()
+            || memory_allocation.is_some()
+            || log_visibility.is_some();
 
         let old_usage = canister.memory_usage();
         let old_mem = canister.memory_allocation().allocated_bytes(old_usage);
@@ -729,10 +759,24 @@ impl CanisterManager {
             let new_controllers = canister.system_state.controllers.iter().copied().collect();
             canister.system_state.add_canister_change(
                 timestamp_nanos,
-                origin,
+                origin.clone(),
                 CanisterChangeDetails::controllers_change(new_controllers),
             );
         }
+        // A call that changes both the controllers and other settings results
+        // in two history entries with the same canister version.
+        if is_settings_change {
+            canister.system_state.add_canister_change(
+                timestamp_nanos,
+                origin,
+                CanisterChangeDetails::settings_change(
+                    freezing_threshold.map(|t| t.get()),
+                    compute_allocation.map(|c| c.as_percent()),
+                    memory_allocation.map(|m| m.bytes().get()),
+                    log_visibility,
+                ),
+            );
+        }
 
         Ok(())
     }
@@ -1170,10 +1214,14 @@ impl CanisterManager {
     }
 
     /// Fetches the current status of the canister.
+    ///
+    /// Snapshots are kept outside of the canister state, so their memory usage
+    /// has to be provided by the caller.
     pub(crate) fn get_canister_status(
         &self,
         sender: PrincipalId,
         canister: &mut CanisterState,
+        snapshots_memory_usage: NumBytes,
         subnet_size: usize,
     ) -> Result<CanisterStatusResultV2, CanisterManagerError> {
         // Skip the controller check if the canister itself is requesting its
@@ -1196,6 +1244,20 @@ impl CanisterManager {
         let freeze_threshold = canister.system_state.freeze_threshold;
         let reserved_cycles_limit = canister.system_state.reserved_balance_limit();
         let log_visibility = canister.system_state.log_visibility;
+        let wasm_memory_threshold = canister.system_state.wasm_memory_threshold;
+
+        let execution_state = canister.execution_state.as_ref();
+        let memory_metrics = MemoryMetrics::new(
+            execution_state.map_or(NumBytes::from(0), |es| es.wasm_memory_usage()),
+            execution_state.map_or(NumBytes::from(0), |es| es.stable_memory_usage()),
+            execution_state.map_or(NumBytes::from(0), |es| es.globals_memory_usage()),
+            execution_state.map_or(NumBytes::from(0), |es| es.wasm_binary_memory_usage()),
+            canister.wasm_custom_sections_memory_usage(),
+            canister.canister_history_memory_usage(),
+            canister.wasm_chunk_store_memory_usage(),
+            snapshots_memory_usage,
+            canister_message_memory_usage,
+        );
 
         Ok(CanisterStatusResultV2::new(
             canister.status(),
@@ -1206,12 +1268,14 @@ impl CanisterManager {
             *controller,
             controllers,
             canister_memory_usage,
+            memory_metrics,
             canister.system_state.balance().get(),
             compute_allocation.as_percent(),
             Some(memory_allocation.bytes().get()),
             freeze_threshold.get(),
             reserved_cycles_limit.map(|x| x.get()),
             log_visibility,
+            wasm_memory_threshold.get(),
             self.cycles_account_manager
                 .idle_cycles_burned_rate(
                     memory_allocation,
@@ -1804,6 +1868,636 @@ impl CanisterManager {
             .collect();
         Ok(StoredChunksReply(keys))
     }
+
+    /// Returns the snapshot identified by `snapshot_id` after checking that
+    /// it belongs to the given canister.
+    fn get_snapshot<'a>(
+        &self,
+        state: &'a ReplicatedState,
+        canister_id: CanisterId,
+        snapshot_id: SnapshotId,
+    ) -> Result<&'a Arc<CanisterSnapshot>, CanisterManagerError> {
+        match state.canister_snapshots.get(snapshot_id) {
+            Some(snapshot) if *snapshot.canister_id() == canister_id => Ok(snapshot),
+            _ => Err(CanisterManagerError::CanisterSnapshotNotFound {
+                canister_id,
+                snapshot_id,
+            }),
+        }
+    }
+
+    /// Takes a snapshot of the Wasm module, memories, exported globals,
+    /// certified data and Wasm chunk store of the canister.
+    ///
+    /// If `replace_snapshot` is given, that snapshot is deleted once the new
+    /// one has been taken, so it does not count against the snapshot limit.
+    pub(crate) fn take_canister_snapshot(
+        &self,
+        sender: PrincipalId,
+        args: &TakeCanisterSnapshotArgs,
+        state: &mut ReplicatedState,
+        round_limits: &mut RoundLimits,
+    ) -> Result<CanisterSnapshotResponse, CanisterManagerError> {
+        let canister_id = args.get_canister_id();
+        let canister = self.validate_canister_exists(state, canister_id)?;
+        validate_controller(canister, &sender)?;
+
+        let replace_snapshot = args.replace_snapshot.map(SnapshotId::new);
+        match replace_snapshot {
+            Some(snapshot_id) => {
+                self.get_snapshot(state, canister_id, snapshot_id)?;
+            }
+            None => {
+                let number_of_snapshots = state
+                    .canister_snapshots
+                    .number_of_snapshots_by_canister(canister_id);
+                if number_of_snapshots >= self.config.max_number_of_snapshots_per_canister {
+                    return Err(CanisterManagerError::CanisterSnapshotLimitExceeded {
+                        canister_id,
+                        limit: self.config.max_number_of_snapshots_per_canister,
+                    });
+                }
+            }
+        }
+        let execution_state = canister.execution_state.as_ref().ok_or_else(|| {
+            CanisterManagerError::Hypervisor(canister_id, HypervisorError::WasmModuleNotFound)
+        })?;
+
+        // Page maps are copied on write, so the snapshot shares the pages of
+        // the canister until either of them changes.
+        let snapshot = CanisterSnapshot::new(
+            canister_id,
+            state.time(),
+            canister.system_state.canister_version,
+            canister.system_state.certified_data.clone(),
+            Some(execution_state.stable_memory.page_map.clone()),
+            Some(execution_state.wasm_memory.page_map.clone()),
+            canister.system_state.wasm_chunk_store.clone(),
+            Some(execution_state.wasm_binary.binary.clone()),
+            execution_state.wasm_memory.size,
+            execution_state.stable_memory.size,
+            execution_state.exported_globals.clone(),
+        );
+        let snapshot_size = snapshot.memory_usage();
+        round_limits
+            .subnet_available_memory
+            .try_decrement(snapshot_size, NumBytes::from(0), NumBytes::from(0))
+            .map_err(
+                |_| CanisterManagerError::SubnetMemoryCapacityOverSubscribed {
+                    requested: snapshot_size,
+                    available: NumBytes::from(
+                        round_limits
+                            .subnet_available_memory
+                            .get_execution_memory()
+                            .max(0) as u64,
+                    ),
+                },
+            )?;
+
+        if let Some(snapshot_id) = replace_snapshot {
+            self.remove_snapshot(state, snapshot_id, round_limits);
+        }
+        let taken_at_timestamp = snapshot.taken_at_timestamp().as_nanos_since_unix_epoch();
+        let snapshot_id = state.canister_snapshots.push(Arc::new(snapshot));
+        Ok(CanisterSnapshotResponse {
+            id: snapshot_id.get(),
+            taken_at_timestamp,
+            total_size: snapshot_size.get(),
+        })
+    }
+
+    /// Lists the snapshots of the canister in the order they were created.
+    pub(crate) fn list_canister_snapshots(
+        &self,
+        sender: PrincipalId,
+        args: &ListCanisterSnapshotArgs,
+        state: &ReplicatedState,
+    ) -> Result<ListCanisterSnapshotsResponse, CanisterManagerError> {
+        let canister_id = args.get_canister_id();
+        let canister = self.validate_canister_exists(state, canister_id)?;
+        validate_controller(canister, &sender)?;
+
+        let snapshots = state
+            .canister_snapshots
+            .list_snapshots(canister_id)
+            .map(|(snapshot_id, snapshot)| CanisterSnapshotResponse {
+                id: snapshot_id.get(),
+                taken_at_timestamp: snapshot.taken_at_timestamp().as_nanos_since_unix_epoch(),
+                total_size: snapshot.memory_usage().get(),
+            })
+            .collect();
+        Ok(ListCanisterSnapshotsResponse(snapshots))
+    }
+
+    /// Deletes a snapshot of the canister and releases the memory it used.
+    pub(crate) fn delete_canister_snapshot(
+        &self,
+        sender: PrincipalId,
+        args: &DeleteCanisterSnapshotArgs,
+        state: &mut ReplicatedState,
+        round_limits: &mut RoundLimits,
+    ) -> Result<(), CanisterManagerError> {
+        let canister_id = args.get_canister_id();
+        let canister = self.validate_canister_exists(state, canister_id)?;
+        validate_controller(canister, &sender)?;
+        let snapshot_id = SnapshotId::new(args.snapshot_id);
+        self.get_snapshot(state, canister_id, snapshot_id)?;
+
+        self.remove_snapshot(state, snapshot_id, round_limits);
+        Ok(())
+    }
+
+    /// Removes a snapshot that is known to exist and returns the memory
+    /// reserved for it to the subnet.
+    fn remove_snapshot(
+        &self,
+        state: &mut ReplicatedState,
+        snapshot_id: SnapshotId,
+        round_limits: &mut RoundLimits,
+    ) {
+        if let Some(snapshot) = state.canister_snapshots.remove(snapshot_id) {
+            round_limits.subnet_available_memory.increment(
+                snapshot.reserved_memory(),
+                NumBytes::from(0),
+                NumBytes::from(0),
+            );
+        }
+    }
+
+    pub(crate) fn read_snapshot_metadata(
+        &self,
+        sender: PrincipalId,
+        args: &ReadCanisterSnapshotMetadataArgs,
+        state: &ReplicatedState,
+    ) -> Result<ReadCanisterSnapshotMetadataResponse, CanisterManagerError> {
+        let canister_id = args.get_canister_id();
+        let canister = self.validate_canister_exists(state, canister_id)?;
+        // Allow the canister itself to perform this operation.
+        if sender != canister.system_state.canister_id.into() {
+            validate_controller(canister, &sender)?
+        }
+        let snapshot = self.get_snapshot(state, canister_id, SnapshotId::new(args.snapshot_id))?;
+
+        Ok(ReadCanisterSnapshotMetadataResponse {
+            taken_at_timestamp: snapshot.taken_at_timestamp().as_nanos_since_unix_epoch(),
+            canister_version: snapshot.canister_version(),
+            certified_data: snapshot.certified_data().clone(),
+            wasm_module_size: snapshot
+                .wasm_binary()
+                .as_ref()
+                .map_or(0, |binary| binary.len() as u64),
+            exported_globals: snapshot
+                .exported_globals()
+                .iter()
+                .map(SnapshotGlobal::from)
+                .collect(),
+            wasm_memory_size: num_wasm_pages_to_bytes(snapshot.wasm_memory_size()).get(),
+            stable_memory_size: num_wasm_pages_to_bytes(snapshot.stable_memory_size()).get(),
+            wasm_chunk_store: snapshot
+                .chunk_store()
+                .keys()
+                .map(|k| ChunkHash { hash: k.to_vec() })
+                .collect(),
+        })
+    }
+
+    pub(crate) fn read_snapshot_data(
+        &self,
+        sender: PrincipalId,
+        args: &ReadCanisterSnapshotDataArgs,
+        state: &ReplicatedState,
+    ) -> Result<ReadCanisterSnapshotDataResponse, CanisterManagerError> {
+        let canister_id = args.get_canister_id();
+        let canister = self.validate_canister_exists(state, canister_id)?;
+        // Allow the canister itself to perform this operation.
+        if sender != canister.system_state.canister_id.into() {
+            validate_controller(canister, &sender)?
+        }
+        let snapshot = self.get_snapshot(state, canister_id, SnapshotId::new(args.snapshot_id))?;
+
+        let chunk = match &args.kind {
+            CanisterSnapshotDataKind::WasmModule { offset, size } => {
+                let binary = snapshot
+                    .wasm_binary()
+                    .as_ref()
+                    .map_or(&[][..], |binary| binary.as_slice());
+                let range = validate_snapshot_data_range(*offset, *size, binary.len() as u64)?;
+                binary[range].to_vec()
+            }
+            CanisterSnapshotDataKind::MainMemory { offset, size } => read_snapshot_memory(
+                snapshot.wasm_memory(),
+                snapshot.wasm_memory_size(),
+                *offset,
+                *size,
+            )?,
+            CanisterSnapshotDataKind::StableMemory { offset, size } => read_snapshot_memory(
+                snapshot.stable_memory(),
+                snapshot.stable_memory_size(),
+                *offset,
+                *size,
+            )?,
+            CanisterSnapshotDataKind::WasmChunk { hash } => {
+                let hash: WasmChunkHash = hash.as_slice().try_into().map_err(|_| {
+                    CanisterManagerError::InvalidSnapshotData {
+                        message: format!("Invalid chunk hash length {}", hash.len()),
+                    }
+                })?;
+                snapshot
+                    .chunk_store()
+                    .get_chunk_data(&hash)
+                    .ok_or_else(|| CanisterManagerError::InvalidSnapshotData {
+                        message: format!("Chunk {} not found", hex::encode(hash)),
+                    })?
+                    .flatten()
+                    .copied()
+                    .collect()
+            }
+        };
+        Ok(ReadCanisterSnapshotDataResponse { chunk })
+    }
+
+    /// Creates a new, empty snapshot of the given canister that can be filled
+    /// in with `upload_snapshot_data`.
+    pub(crate) fn upload_snapshot_metadata(
+        &self,
+        sender: PrincipalId,
+        args: &UploadCanisterSnapshotMetadataArgs,
+        state: &mut ReplicatedState,
+        round_limits: &mut RoundLimits,
+    ) -> Result<UploadCanisterSnapshotMetadataResponse, CanisterManagerError> {
+        let canister_id = args.get_canister_id();
+        let canister = self.validate_canister_exists(state, canister_id)?;
+        // Allow the canister itself to perform this operation.
+        if sender != canister.system_state.canister_id.into() {
+            validate_controller(canister, &sender)?
+        }
+
+        let number_of_snapshots = state
+            .canister_snapshots
+            .number_of_snapshots_by_canister(canister_id);
+        if number_of_snapshots >= self.config.max_number_of_snapshots_per_canister {
+            return Err(CanisterManagerError::CanisterSnapshotLimitExceeded {
+                canister_id,
+                limit: self.config.max_number_of_snapshots_per_canister,
+            });
+        }
+        let wasm_module_hash =
+            WasmHash::try_from(args.wasm_module_hash.clone()).map_err(|hash| {
+                CanisterManagerError::InvalidSnapshotData {
+                    message: format!("Invalid Wasm module hash length {}", hash.len()),
+                }
+            })?;
+        if args.wasm_module_size > wasm_chunk_store::DEFAULT_MAX_SIZE.get() {
+            return Err(CanisterManagerError::InvalidSnapshotData {
+                message: format!(
+                    "Wasm module size {} exceeds the maximum of {}",
+                    args.wasm_module_size,
+                    wasm_chunk_store::DEFAULT_MAX_SIZE
+                ),
+            });
+        }
+        if args.wasm_memory_size > MAX_WASM_MEMORY_IN_BYTES {
+            return Err(CanisterManagerError::InvalidSnapshotData {
+                message: format!(
+                    "Wasm memory size {} exceeds the maximum of {}",
+                    args.wasm_memory_size, MAX_WASM_MEMORY_IN_BYTES
+                ),
+            });
+        }
+        if args.stable_memory_size > MAX_STABLE_MEMORY_IN_BYTES {
+            return Err(CanisterManagerError::InvalidSnapshotData {
+                message: format!(
+                    "Stable memory size {} exceeds the maximum of {}",
+                    args.stable_memory_size, MAX_STABLE_MEMORY_IN_BYTES
+                ),
+            });
+        }
+        let wasm_memory_size = bytes_to_num_wasm_pages(args.wasm_memory_size);
+        let stable_memory_size = bytes_to_num_wasm_pages(args.stable_memory_size);
+
+        // Reserve the memory needed by the snapshot up front, so that later
+        // uploads of the data cannot fail due to lack of memory.
+        let snapshot_size = num_wasm_pages_to_bytes(wasm_memory_size)
+            + num_wasm_pages_to_bytes(stable_memory_size)
+            + NumBytes::from(args.wasm_module_size + args.certified_data.len() as u64);
+        round_limits
+            .subnet_available_memory
+            .try_decrement(snapshot_size, NumBytes::from(0), NumBytes::from(0))
+            .map_err(
+                |_| CanisterManagerError::SubnetMemoryCapacityOverSubscribed {
+                    requested: snapshot_size,
+                    available: NumBytes::from(
+                        round_limits
+                            .subnet_available_memory
+                            .get_execution_memory()
+                            .max(0) as u64,
+                    ),
+                },
+            )?;
+
+        let mut snapshot = CanisterSnapshot::new(
+            canister_id,
+            state.time(),
+            canister.system_state.canister_version,
+            args.certified_data.clone(),
+            Some(PageMap::new(Arc::clone(&self.fd_factory))),
+            Some(PageMap::new(Arc::clone(&self.fd_factory))),
+            WasmChunkStore::new(Arc::clone(&self.fd_factory)),
+            None,
+            wasm_memory_size,
+            stable_memory_size,
+            args.exported_globals.iter().map(Global::from).collect(),
+        );
+        // Partially uploaded snapshots use less memory than was reserved
+        // above, so removing the snapshot must release the reservation.
+        *snapshot.reserved_memory_mut() = snapshot_size;
+        // The Wasm module is assembled as it is uploaded and only becomes part
+        // of the snapshot once it is complete and matches the given hash.
+        if args.wasm_module_size > 0 {
+            *snapshot.wasm_module_upload_mut() = Some(WasmModuleUpload::new(
+                PageMap::new(Arc::clone(&self.fd_factory)),
+                args.wasm_module_size,
+                wasm_module_hash,
+            ));
+        }
+        let snapshot_id = state.canister_snapshots.push(Arc::new(snapshot));
+        Ok(UploadCanisterSnapshotMetadataResponse {
+            snapshot_id: snapshot_id.get(),
+        })
+    }
+
+    /// Writes a chunk of data into a snapshot previously created with
+    /// `upload_snapshot_metadata`. Returns the resulting heap delta.
+    pub(crate) fn upload_snapshot_data(
+        &self,
+        sender: PrincipalId,
+        args: &UploadCanisterSnapshotDataArgs,
+        state: &mut ReplicatedState,
+    ) -> Result<NumBytes, CanisterManagerError> {
+        let canister_id = args.get_canister_id();
+        let canister = self.validate_canister_exists(state, canister_id)?;
+        // Allow the canister itself to perform this operation.
+        if sender != canister.system_state.canister_id.into() {
+            validate_controller(canister, &sender)?
+        }
+        let snapshot_id = SnapshotId::new(args.snapshot_id);
+        self.get_snapshot(state, canister_id, snapshot_id)?;
+
+        if args.chunk.len() as u64 > MAX_SNAPSHOT_DATA_CHUNK_SIZE {
+            return Err(CanisterManagerError::InvalidSnapshotData {
+                message: format!(
+                    "Chunk size {} exceeds the maximum of {}",
+                    args.chunk.len(),
+                    MAX_SNAPSHOT_DATA_CHUNK_SIZE
+                ),
+            });
+        }
+
+        // The snapshot was looked up above, so it must exist.
+        let snapshot = state
+            .canister_snapshots
+            .get_mut(snapshot_id)
+            .expect("Error: Snapshot must exist after lookup");
+        match &args.kind {
+            CanisterSnapshotDataOffset::WasmModule { offset } => {
+                let upload = snapshot.wasm_module_upload_mut().as_mut().ok_or_else(|| {
+                    CanisterManagerError::InvalidSnapshotData {
+                        message: "The snapshot has no Wasm module left to upload".to_string(),
+                    }
+                })?;
+                let heap_delta = upload
+                    .append(*offset, &args.chunk)
+                    .map_err(|message| CanisterManagerError::InvalidSnapshotData { message })?;
+                if upload.is_complete() {
+                    let module = upload
+                        .finish()
+                        .map_err(|message| CanisterManagerError::InvalidSnapshotData { message })?;
+                    *snapshot.wasm_binary_mut() = Some(module);
+                    *snapshot.wasm_module_upload_mut() = None;
+                }
+                Ok(heap_delta)
+            }
+            CanisterSnapshotDataOffset::MainMemory { offset } => {
+                let memory_size = snapshot.wasm_memory_size();
+                write_snapshot_memory(
+                    snapshot.wasm_memory_mut(),
+                    memory_size,
+                    *offset,
+                    &args.chunk,
+                )
+            }
+            CanisterSnapshotDataOffset::StableMemory { offset } => {
+                let memory_size = snapshot.stable_memory_size();
+                write_snapshot_memory(
+                    snapshot.stable_memory_mut(),
+                    memory_size,
+                    *offset,
+                    &args.chunk,
+                )
+            }
+            CanisterSnapshotDataOffset::WasmChunk => {
+                snapshot
+                    .chunk_store_mut()
+                    .insert_chunk(&args.chunk)
+                    .map_err(|err| CanisterManagerError::InvalidSnapshotData { message: err })?;
+                Ok(wasm_chunk_store::chunk_size())
+            }
+        }
+    }
+
+    /// Replaces the Wasm module, memories, exported globals, certified data
+    /// and Wasm chunk store of the canister with the contents of a snapshot
+    /// and records the load in the canister history.
+    pub(crate) fn load_canister_snapshot(
+        &self,
+        origin: CanisterChangeOrigin,
+        args: &LoadCanisterSnapshotArgs,
+        state: &mut ReplicatedState,
+        round_limits: &mut RoundLimits,
+        subnet_size: usize,
+        resource_saturation: &ResourceSaturation,
+    ) -> Result<(), CanisterManagerError> {
+        let canister_id = args.get_canister_id();
+        let canister = self.validate_canister_exists(state, canister_id)?;
+        validate_controller(canister, &origin.origin())?;
+        let snapshot_id = SnapshotId::new(args.snapshot_id);
+        let snapshot = Arc::clone(self.get_snapshot(state, canister_id, snapshot_id)?);
+
+        // The Wasm module is only set once an uploaded snapshot is complete.
+        let wasm_binary = snapshot.wasm_binary().clone().ok_or_else(|| {
+            CanisterManagerError::InvalidSnapshotData {
+                message: format!("Snapshot {} has no complete Wasm module", snapshot_id),
+            }
+        })?;
+        let (compilation_cost, result) = self.hypervisor.create_execution_state(
+            wasm_binary,
+            "NOT_USED".into(),
+            canister_id,
+            round_limits,
+            CompilationCostHandling::CountFullAmount,
+        );
+
+        // Like `install_code`, charge for compiling the Wasm module, even if loading the
+        // snapshot fails below.
+        let canister = state
+            .canister_state_mut(&canister_id)
+            .ok_or(CanisterManagerError::CanisterNotFound(canister_id))?;
+        let memory_usage = canister.memory_usage();
+        let message_memory_usage = canister.message_memory_usage();
+        let compute_allocation = canister.compute_allocation();
+        let reveal_top_up = canister.controllers().contains(&origin.origin());
+        let prepaid_cycles = self
+            .cycles_account_manager
+            .prepay_execution_cycles(
+                &mut canister.system_state,
+                memory_usage,
+                message_memory_usage,
+                compute_allocation,
+                compilation_cost,
+                subnet_size,
+                reveal_top_up,
+            )
+            .map_err(CanisterManagerError::InstallCodeNotEnoughCycles)?;
+        // To keep the invariant that `prepay_execution_cycles` is always paired
+        // with `refund_unused_execution_cycles` we refund zero immediately.
+        self.cycles_account_manager.refund_unused_execution_cycles(
+            &mut canister.system_state,
+            NumInstructions::from(0),
+            compilation_cost,
+            prepaid_cycles,
+            // This counter is incremented if we refund more
+            // instructions than initially charged, which is impossible
+            // here.
+            &IntCounter::new("no_op", "no_op").unwrap(),
+            subnet_size,
+            &self.log,
+        );
+
+        let mut execution_state =
+            result.map_err(|err| CanisterManagerError::Hypervisor(canister_id, err))?;
+        if execution_state.exported_globals.len() != snapshot.exported_globals().len() {
+            return Err(CanisterManagerError::InvalidSnapshotData {
+                message: format!(
+                    "Snapshot {} has {} exported globals, but its Wasm module exports {}",
+                    snapshot_id,
+                    snapshot.exported_globals().len(),
+                    execution_state.exported_globals.len()
+                ),
+            });
+        }
+        let new_memory = |page_map: &Option<PageMap>, size| {
+            Memory::new(
+                page_map
+                    .clone()
+                    .unwrap_or_else(|| PageMap::new(Arc::clone(&self.fd_factory))),
+                size,
+            )
+        };
+        execution_state.exported_globals = snapshot.exported_globals().clone();
+        execution_state.wasm_memory =
+            new_memory(snapshot.wasm_memory(), snapshot.wasm_memory_size());
+        execution_state.stable_memory =
+            new_memory(snapshot.stable_memory(), snapshot.stable_memory_size());
+
+        let mut new_canister = canister.clone();
+        new_canister.execution_state = Some(execution_state);
+        new_canister.system_state.certified_data = snapshot.certified_data().clone();
+        new_canister.system_state.wasm_chunk_store = snapshot.chunk_store().clone();
+
+        let memory_allocation = canister.memory_allocation();
+        let old_mem = memory_allocation.allocated_bytes(canister.memory_usage());
+        let new_usage = new_canister.memory_usage();
+        if let MemoryAllocation::Reserved(reserved_bytes) = memory_allocation {
+            if new_usage > reserved_bytes {
+                return Err(CanisterManagerError::NotEnoughMemoryAllocationGiven {
+                    memory_allocation_given: memory_allocation,
+                    memory_usage_needed: new_usage,
+                });
+            }
+        }
+        let new_mem = memory_allocation.allocated_bytes(new_usage);
+        if new_mem >= old_mem {
+            let requested = new_mem - old_mem;
+            if requested.get() > 0 {
+                // Reserve cycles for the memory growth, as `install_code` does.
+                let reservation_cycles = self.cycles_account_manager.storage_reservation_cycles(
+                    requested,
+                    resource_saturation,
+                    subnet_size,
+                );
+                new_canister
+                    .system_state
+                    .reserve_cycles(reservation_cycles)
+                    .map_err(|err| match err {
+                        ReservationError::InsufficientCycles {
+                            requested: threshold,
+                            available,
+                        } => CanisterManagerError::InsufficientCyclesInMemoryGrow {
+                            bytes: requested,
+                            available,
+                            threshold,
+                        },
+                        ReservationError::ReservedLimitExceed {
+                            requested: reservation_requested,
+                            limit,
+                        } => CanisterManagerError::ReservedCyclesLimitExceededInMemoryGrow {
+                            bytes: requested,
+                            requested: reservation_requested,
+                            limit,
+                        },
+                    })?;
+                let threshold = self.cycles_account_manager.freeze_threshold_cycles(
+                    new_canister.system_state.freeze_threshold,
+                    memory_allocation,
+                    new_usage,
+                    new_canister.message_memory_usage(),
+                    new_canister.compute_allocation(),
+                    subnet_size,
+                    new_canister.system_state.reserved_balance(),
+                );
+                if new_canister.system_state.balance() < threshold {
+                    return Err(CanisterManagerError::InsufficientCyclesInMemoryGrow {
+                        bytes: requested,
+                        available: new_canister.system_state.balance(),
+                        threshold,
+                    });
+                }
+            }
+            round_limits
+                .subnet_available_memory
+                .try_decrement(requested, NumBytes::from(0), NumBytes::from(0))
+                .map_err(
+                    |_| CanisterManagerError::SubnetMemoryCapacityOverSubscribed {
+                        requested,
+                        available: NumBytes::from(
+                            round_limits
+                                .subnet_available_memory
+                                .get_execution_memory()
+                                .max(0) as u64,
+                        ),
+                    },
+                )?;
+        } else {
+            round_limits.subnet_available_memory.increment(
+                old_mem - new_mem,
+                NumBytes::from(0),
+                NumBytes::from(0),
+            );
+        }
+
+        new_canister.system_state.canister_version += 1;
+        new_canister.system_state.add_canister_change(
+            state.time(),
+            origin,
+            CanisterChangeDetails::load_snapshot(
+                snapshot.canister_version(),
+                snapshot_id.get(),
+                snapshot.taken_at_timestamp().as_nanos_since_unix_epoch(),
+            ),
+        );
+        state.put_canister_state(new_canister);
+        Ok(())
+    }
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -1885,6 +2579,23 @@ pub(crate) enum CanisterManagerError {
     WasmChunkStoreError {
         message: String,
     },
+    CanisterSnapshotNotFound {
+        canister_id: CanisterId,
+        snapshot_id: SnapshotId,
+    },
+    InvalidSnapshotData {
+        message: String,
+    },
+    CanisterSnapshotLimitExceeded {
+        canister_id: CanisterId,
+        limit: usize,
+    },
+    MissingUpgradeOptionError {
+        message: String,
+    },
+    InvalidUpgradeOptionError {
+        message: String,
+    },
 }
 
 impl From<CanisterManagerError> for UserError {
@@ -2121,6 +2832,48 @@ impl From<CanisterManagerError> for UserError {
                     )
                 )
             }
+            CanisterSnapshotNotFound { canister_id, snapshot_id } => {
+                Self::new(
+                    ErrorCode::CanisterContractViolation,
+                    format!(
+                        "Could not find the snapshot ID {} for canister {}.",
+                        snapshot_id, canister_id,
+                    )
+                )
+            }
+            InvalidSnapshotData { message } => {
+                Self::new(
+                    ErrorCode::CanisterContractViolation,
+                    format!(
+                        "Invalid canister snapshot data: {}", message
+                    )
+                )
+            }
+            CanisterSnapshotLimitExceeded { canister_id, limit } => {
+                Self::new(
+                    ErrorCode::CanisterContractViolation,
+                    format!(
+                        "Canister {} has reached the maximum number of {} snapshots.",
+                        canister_id, limit,
+                    )
+                )
+            }
+            MissingUpgradeOptionError { message } => {
+                Self::new(
+                    ErrorCode::CanisterContractViolation,
+                    format!(
+                        "Missing upgrade option: {}", message
+                    )
+                )
+            }
+            InvalidUpgradeOptionError { message } => {
+                Self::new(
+                    ErrorCode::CanisterContractViolation,
+                    format!(
+                        "Invalid upgrade option: {}", message
+                    )
+                )
+            }
         }
     }
 }
@@ -2138,6 +2891,81 @@ impl From<CanisterManagerError> for RejectContext {
     }
 }
 
+/// The maximum number of bytes that can be read from or uploaded to a canister
+/// snapshot in a single message.
+const MAX_SNAPSHOT_DATA_CHUNK_SIZE: u64 = 2_000_000;
+
+/// Checks that the range `[offset, offset + size)` is within `len` bytes and
+/// does not exceed the maximum chunk size.
+fn validate_snapshot_data_range(
+    offset: u64,
+    size: u64,
+    len: u64,
+) -> Result<std::ops::Range<usize>, CanisterManagerError> {
+    if size > MAX_SNAPSHOT_DATA_CHUNK_SIZE {
+        return Err(CanisterManagerError::InvalidSnapshotData {
+            message: format!(
+                "Requested size {} exceeds the maximum chunk size of {}",
+                size, MAX_SNAPSHOT_DATA_CHUNK_SIZE
+            ),
+        });
+    }
+    match offset.checked_add(size) {
+        Some(end) if end <= len => Ok(offset as usize..end as usize),
+        _ => Err(CanisterManagerError::InvalidSnapshotData {
+            message: format!(
+                "Range with offset {} and size {} is out of bounds of {} bytes",
+                offset, size, len
+            ),
+        }),
+    }
+}
+
+fn num_wasm_pages_to_bytes(pages: NumWasmPages) -> NumBytes {
+    NumBytes::from((pages.get() * WASM_PAGE_SIZE_IN_BYTES) as u64)
+}
+
+fn bytes_to_num_wasm_pages(bytes: u64) -> NumWasmPages {
+    NumWasmPages::from(
+        ((bytes + WASM_PAGE_SIZE_IN_BYTES as u64 - 1) / WASM_PAGE_SIZE_IN_BYTES as u64) as usize,
+    )
+}
+
+fn read_snapshot_memory(
+    memory: &Option<PageMap>,
+    memory_size: NumWasmPages,
+    offset: u64,
+    size: u64,
+) -> Result<Vec<u8>, CanisterManagerError> {
+    let len = num_wasm_pages_to_bytes(memory_size).get();
+    let range = validate_snapshot_data_range(offset, size, len)?;
+    let mut chunk = vec![0; range.len()];
+    if let Some(page_map) = memory {
+        Buffer::new(page_map.clone()).read(&mut chunk, range.start);
+    }
+    Ok(chunk)
+}
+
+fn write_snapshot_memory(
+    memory: &mut Option<PageMap>,
+    memory_size: NumWasmPages,
+    offset: u64,
+    chunk: &[u8],
+) -> Result<NumBytes, CanisterManagerError> {
+    let len = num_wasm_pages_to_bytes(memory_size).get();
+    let range = validate_snapshot_data_range(offset, chunk.len() as u64, len)?;
+    let page_map = memory
+        .as_mut()
+        .ok_or_else(|| CanisterManagerError::InvalidSnapshotData {
+            message: "Snapshot has no memory to write to".to_string(),
+        })?;
+    let mut buffer = Buffer::new(page_map.clone());
+    buffer.write(chunk, range.start);
+    let heap_delta = NumBytes::from((buffer.dirty_pages().count() * PAGE_SIZE) as u64);
+    *page_map = buffer.into_page_map();
+    Ok(heap_delta)
+}
+
 /// Uninstalls a canister.
 ///
 /// See https://sdk.dfinity.org/docs/interface-spec/index.html#ic-uninstall_code
@@ -22,6 +22,7 @@ pub(crate) struct CanisterSettings {
     pub(crate) freezing_threshold: Option<NumSeconds>,
     pub(crate) reserved_cycles_limit: Option<Cycles>,
     pub(crate) log_visibility: Option<LogVisibility>,
+    pub(crate) wasm_memory_threshold: Option<NumBytes>,
 }
 
 impl CanisterSettings {
@@ -33,6 +34,7 @@ impl CanisterSettings {
         freezing_threshold: Option<NumSeconds>,
         reserved_cycles_limit: Option<Cycles>,
         log_visibility: Option<LogVisibility>,
+        wasm_memory_threshold: Option<NumBytes>,
     ) -> Self {
         Self {
             controller,
@@ -42,6 +44,7 @@ impl CanisterSettings {
             freezing_threshold,
             reserved_cycles_limit,
             log_visibility,
+            wasm_memory_threshold,
         }
     }
 
@@ -72,6 +75,10 @@ impl CanisterSettings {
     pub fn log_visibility(&self) -> Option<LogVisibility> {
         self.log_visibility
     }
+
+    pub fn wasm_memory_threshold(&self) -> Option<NumBytes> {
+        self.wasm_memory_threshold
+    }
 }
 
 impl TryFrom<CanisterSettingsArgs> for CanisterSettings {
@@ -109,6 +116,15 @@ impl TryFrom<CanisterSettingsArgs> for CanisterSettings {
             None => None,
         };
 
+        let wasm_memory_threshold = match input.wasm_memory_threshold {
+            Some(threshold) => Some(NumBytes::from(threshold.0.to_u64().ok_or(
+                UpdateSettingsError::WasmMemoryThresholdOutOfRange {
+                    provided: threshold,
+                },
+            )?)),
+            None => None,
+        };
+
         Ok(CanisterSettings::new(
             controller,
             input
@@ -119,6 +135,7 @@ impl TryFrom<CanisterSettingsArgs> for CanisterSettings {
             freezing_threshold,
             reserved_cycles_limit,
             input.log_visibility,
+            wasm_memory_threshold,
         ))
     }
 }
@@ -142,6 +159,7 @@ pub(crate) struct CanisterSettingsBuilder {
     freezing_threshold: Option<NumSeconds>,
     reserved_cycles_limit: Option<Cycles>,
     log_visibility: Option<LogVisibility>,
+    wasm_memory_threshold: Option<NumBytes>,
 }
 
 #[allow(dead_code)]
@@ -155,6 +173,7 @@ impl CanisterSettingsBuilder {
             freezing_threshold: None,
             reserved_cycles_limit: None,
             log_visibility: None,
+            wasm_memory_threshold: None,
         }
     }
 
@@ -167,6 +186,7 @@ impl CanisterSettingsBuilder {
             freezing_threshold: self.freezing_threshold,
             reserved_cycles_limit: self.reserved_cycles_limit,
             log_visibility: self.log_visibility,
+            wasm_memory_threshold: self.wasm_memory_threshold,
         }
     }
 
@@ -218,6 +238,13 @@ impl CanisterSettingsBuilder {
             ..self
         }
     }
+
+    pub fn with_wasm_memory_threshold(self, wasm_memory_threshold: NumBytes) -> Self {
+        Self {
+            wasm_memory_threshold: Some(wasm_memory_threshold),
+            ..self
+        }
+    }
 }
 
 pub enum UpdateSettingsError {
@@ -225,6 +252,7 @@ pub enum UpdateSettingsError {
     MemoryAllocation(InvalidMemoryAllocationError),
     FreezingThresholdOutOfRange { provided: candid::Nat },
     ReservedCyclesLimitOutOfRange { provided: candid::Nat },
+    WasmMemoryThresholdOutOfRange { provided: candid::Nat },
 }
 
 impl From<UpdateSettingsError> for UserError {
@@ -260,6 +288,13 @@ impl From<UpdateSettingsError> for UserError {
                     provided
                 ),
             ),
+            UpdateSettingsError::WasmMemoryThresholdOutOfRange { provided } => UserError::new(
+                ErrorCode::CanisterContractViolation,
+                format!(
+                    "Wasm memory threshold expected to be in the range of [0..2^64-1], got {}",
+                    provided
+                ),
+            ),
         }
     }
 }
@@ -285,6 +320,7 @@ pub(crate) struct ValidatedCanisterSettings {
     reserved_cycles_limit: Option<Cycles>,
     reservation_cycles: Cycles,
     log_visibility: Option<LogVisibility>,
+    wasm_memory_threshold: Option<NumBytes>,
 }
 
 impl ValidatedCanisterSettings {
@@ -319,6 +355,10 @@ impl ValidatedCanisterSettings {
     pub fn log_visibility(&self) -> Option<LogVisibility> {
         self.log_visibility
     }
+
+    pub fn wasm_memory_threshold(&self) -> Option<NumBytes> {
+        self.wasm_memory_threshold
+    }
 }
 
 /// Validates the new canisters settings:
@@ -516,5 +556,6 @@ pub(crate) fn validate_canister_settings(
         reserved_cycles_limit: settings.reserved_cycles_limit(),
         reservation_cycles,
         log_visibility: settings.log_visibility(),
+        wasm_memory_threshold: settings.wasm_memory_threshold(),
     })
 }
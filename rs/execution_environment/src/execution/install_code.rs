@@ -45,6 +45,23 @@ pub(crate) enum StableMemoryHandling {
     Replace,
 }
 
+/// Indicates whether to keep the old Wasm main memory or replace it with the
+/// main memory of the new Wasm module. The main memory is only kept on
+/// upgrades with enhanced orthogonal persistence.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum MainMemoryHandling {
+    Keep,
+    Replace,
+}
+
+/// Indicates how the stable memory and the Wasm main memory are handled when
+/// the execution state of a canister is replaced.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct MemoryHandling {
+    pub stable_memory_handling: StableMemoryHandling,
+    pub main_memory_handling: MainMemoryHandling,
+}
+
 /// The main steps of `install_code` execution that may fail with an error or
 /// change the canister state.
 #[derive(Clone, Debug)]
@@ -54,7 +71,7 @@ pub(crate) enum InstallCodeStep {
     ReplaceExecutionStateAndAllocations {
         instructions_from_compilation: NumInstructions,
         maybe_execution_state: HypervisorResult<ExecutionState>,
-        stable_memory_handling: StableMemoryHandling,
+        memory_handling: MemoryHandling,
     },
     ClearCertifiedData,
     DeactivateGlobalTimer,
@@ -462,6 +479,7 @@ impl InstallCodeHelper {
                 freezing_threshold: None,
                 reserved_cycles_limit: None,
                 log_visibility: None,
+                wasm_memory_threshold: None,
             },
             self.canister.memory_usage(),
             self.canister.message_memory_usage(),
@@ -508,14 +526,14 @@ impl InstallCodeHelper {
         &mut self,
         instructions_from_compilation: NumInstructions,
         maybe_execution_state: HypervisorResult<ExecutionState>,
-        stable_memory_handling: StableMemoryHandling,
+        memory_handling: MemoryHandling,
         original: &OriginalContext,
     ) -> Result<(), CanisterManagerError> {
         self.steps
             .push(InstallCodeStep::ReplaceExecutionStateAndAllocations {
                 instructions_from_compilation,
                 maybe_execution_state: maybe_execution_state.clone(),
-                stable_memory_handling,
+                memory_handling,
             });
 
         self.reduce_instructions_by(instructions_from_compilation);
@@ -529,19 +547,22 @@ impl InstallCodeHelper {
             .as_ref()
             .map_or(NumBytes::from(0), |es| es.metadata.memory_usage());
 
-        // Replace the execution state and maybe the stable memory.
+        // Replace the execution state and maybe the stable and main memories.
         let mut execution_state =
             maybe_execution_state.map_err(|err| (self.canister.canister_id(), err))?;
 
         let new_wasm_custom_sections_memory_used = execution_state.metadata.memory_usage();
 
-        execution_state.stable_memory =
-            match (stable_memory_handling, self.canister.execution_state.take()) {
-                (StableMemoryHandling::Keep, Some(old)) => old.stable_memory,
-                (StableMemoryHandling::Keep, None) | (StableMemoryHandling::Replace, _) => {
-                    execution_state.stable_memory
-                }
-            };
+        if let Some(old) = self.canister.execution_state.take() {
+            if memory_handling.stable_memory_handling == StableMemoryHandling::Keep {
+                execution_state.stable_memory = old.stable_memory;
+            }
+            if memory_handling.main_memory_handling == MainMemoryHandling::Keep {
+                // Note that the data segments of the new module are discarded
+                // together with its initial main memory.
+                execution_state.wasm_memory = old.wasm_memory;
+            }
+        }
         self.canister.execution_state = Some(execution_state);
 
         // Update the compute allocation.
@@ -747,11 +768,11 @@ impl InstallCodeHelper {
             InstallCodeStep::ReplaceExecutionStateAndAllocations {
                 instructions_from_compilation,
                 maybe_execution_state,
-                stable_memory_handling,
+                memory_handling,
             } => self.replace_execution_state_and_allocations(
                 instructions_from_compilation,
                 maybe_execution_state,
-                stable_memory_handling,
+                memory_handling,
                 original,
             ),
             InstallCodeStep::ClearCertifiedData => {
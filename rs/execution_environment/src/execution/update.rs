@@ -18,7 +18,9 @@ use ic_interfaces::execution_environment::{
 };
 use ic_logger::{info, ReplicaLogger};
 use ic_management_canister_types::IC_00;
-use ic_replicated_state::{CallOrigin, CanisterState};
+use ic_replicated_state::{
+    canister_state::system_state::LowResourceHookStatus, CallOrigin, CanisterState,
+};
 use ic_system_api::{ApiType, ExecutionParameters};
 use ic_types::messages::{
     CallContextId, CanisterCall, CanisterCallOrTask, CanisterMessage, CanisterMessageOrTask,
@@ -149,6 +151,18 @@ pub fn execute_update(
             time,
             helper.call_context_id(),
         ),
+        CanisterCallOrTask::Task(CanisterTask::OnLowWasmMemory) => ApiType::system_task(
+            IC_00.get(),
+            SystemMethod::CanisterOnLowWasmMemory,
+            time,
+            helper.call_context_id(),
+        ),
+        CanisterCallOrTask::Task(CanisterTask::OnLowCycles) => ApiType::system_task(
+            IC_00.get(),
+            SystemMethod::CanisterOnLowCycles,
+            time,
+            helper.call_context_id(),
+        ),
     };
 
     let memory_usage = helper.canister().memory_usage();
@@ -315,6 +329,9 @@ impl UpdateHelper {
                 // The global timer is one-off.
                 canister.system_state.global_timer = CanisterTimer::Inactive;
             }
+            // The low resource hooks are marked as executed in `finish()`.
+            CanisterCallOrTask::Task(CanisterTask::OnLowWasmMemory)
+            | CanisterCallOrTask::Task(CanisterTask::OnLowCycles) => {}
         }
 
         Ok(Self {
@@ -426,6 +443,24 @@ impl UpdateHelper {
             original.time,
         );
 
+        // The low resource hooks run once until the resource recovers. They are
+        // only marked as executed once the hook has run to completion, so that
+        // a hook whose execution is aborted or fails before it finishes is run
+        // again in a later round.
+        match original.call_or_task {
+            CanisterCallOrTask::Call(_)
+            | CanisterCallOrTask::Task(CanisterTask::Heartbeat)
+            | CanisterCallOrTask::Task(CanisterTask::GlobalTimer) => {}
+            CanisterCallOrTask::Task(CanisterTask::OnLowWasmMemory) => {
+                self.canister.system_state.on_low_wasm_memory_hook_status =
+                    LowResourceHookStatus::Executed;
+            }
+            CanisterCallOrTask::Task(CanisterTask::OnLowCycles) => {
+                self.canister.system_state.on_low_cycles_hook_status =
+                    LowResourceHookStatus::Executed;
+            }
+        }
+
         let heap_delta = if output.wasm_result.is_ok() {
             NumBytes::from((output.instance_stats.dirty_pages * ic_sys::PAGE_SIZE) as u64)
         } else {
@@ -7,22 +7,28 @@ use std::sync::Arc;
 
 use crate::as_round_instructions;
 use crate::canister_manager::{
-    DtsInstallCodeResult, InstallCodeContext, PausedInstallCodeExecution,
+    CanisterManagerError, DtsInstallCodeResult, InstallCodeContext, PausedInstallCodeExecution,
 };
 use crate::execution::common::{ingress_status_with_processing_state, update_round_limits};
 use crate::execution::install_code::{
-    canister_layout, finish_err, InstallCodeHelper, OriginalContext, PausedInstallCodeHelper,
-    StableMemoryHandling,
+    canister_layout, finish_err, InstallCodeHelper, MainMemoryHandling, MemoryHandling,
+    OriginalContext, PausedInstallCodeHelper, StableMemoryHandling,
 };
 use crate::execution_environment::{RoundContext, RoundLimits};
 use ic_base_types::PrincipalId;
 use ic_embedders::wasm_executor::{CanisterStateChanges, PausedWasmExecution, WasmExecutionResult};
-use ic_interfaces::execution_environment::{HypervisorError, WasmExecutionOutput};
+use ic_embedders::wasm_utils::WasmMemoryDeclaration;
+use ic_interfaces::execution_environment::{
+    HypervisorError, HypervisorResult, WasmExecutionOutput,
+};
 use ic_logger::{info, warn, ReplicaLogger};
-use ic_management_canister_types::{CanisterInstallModeV2, SkipPreUpgrade};
+use ic_management_canister_types::{
+    CanisterInstallModeV2, CanisterUpgradeOptions, WasmMemoryPersistence,
+};
 use ic_replicated_state::page_map::PageAllocatorFileDescriptor;
 use ic_replicated_state::{
-    metadata_state::subnet_call_context_manager::InstallCodeCallId, CanisterState, SystemState,
+    metadata_state::subnet_call_context_manager::InstallCodeCallId, CanisterState, ExecutionState,
+    NumWasmPages, SystemState,
 };
 use ic_system_api::ApiType;
 use ic_types::methods::{FuncRef, SystemMethod, WasmMethod};
@@ -34,10 +40,16 @@ use ic_types::{
 #[cfg(test)]
 mod tests;
 
+/// The name of the custom section (`icp:public` or `icp:private`) with which a
+/// Wasm module declares that it uses enhanced orthogonal persistence, i.e.,
+/// that it keeps its state in the Wasm main memory across upgrades.
+const ENHANCED_ORTHOGONAL_PERSISTENCE_SECTION: &str = "enhanced-orthogonal-persistence";
+
 /// Performs a canister upgrade. The algorithm consists of six stages:
 /// - Stage 0: validate input.
 /// - Stage 1: invoke `canister_pre_upgrade()` (if present) using the old code.
-/// - Stage 2: create a new execution state based on the new Wasm code, deactivate global timer, and bump canister version.
+/// - Stage 2: create a new execution state based on the new Wasm code (keeping the Wasm main memory
+///   with enhanced orthogonal persistence), deactivate global timer, and bump canister version.
 /// - Stage 3: invoke the `start()` method (if present).
 /// - Stage 4: invoke the `canister_post_upgrade()` method (if present).
 /// - Stage 5: finalize execution and refund execution cycles.
@@ -129,9 +141,14 @@ pub(crate) fn execute_upgrade(
     };
 
     let method = WasmMethod::System(SystemMethod::CanisterPreUpgrade);
-    if context.mode == CanisterInstallModeV2::Upgrade(Some(SkipPreUpgrade(Some(true))))
-        || !execution_state.exports_method(&method)
-    {
+    let skip_pre_upgrade = matches!(
+        context.mode,
+        CanisterInstallModeV2::Upgrade(Some(CanisterUpgradeOptions {
+            skip_pre_upgrade: Some(true),
+            ..
+        }))
+    );
+    if skip_pre_upgrade || !execution_state.exports_method(&method) {
         // If the Wasm module does not export the method, or skip_pre_upgrade
         // is enabled then this execution succeeds as a no-op.
         upgrade_stage_2_and_3a_create_execution_state_and_call_start(
@@ -270,20 +287,42 @@ fn upgrade_stage_2_and_3a_create_execution_state_and_call_start(
     let module_hash = wasm_module.module_hash();
     // Stage 2: create a new execution state based on the new Wasm code, deactivate global timer, and bump canister version.
     // Replace the execution state of the canister with a new execution state, but
-    // persist the stable memory (if it exists).
+    // persist the stable memory (if it exists) and, with enhanced orthogonal
+    // persistence, the main memory.
     let layout = canister_layout(&original.canister_layout_path, &canister_id);
-    let (instructions_from_compilation, result) = round.hypervisor.create_execution_state(
-        wasm_module,
-        layout.raw_path(),
-        canister_id,
-        round_limits,
-        original.compilation_cost_handling,
-    );
+    let (instructions_from_compilation, result) =
+        round.hypervisor.create_execution_state_with_main_memory(
+            wasm_module,
+            layout.raw_path(),
+            canister_id,
+            round_limits,
+            original.compilation_cost_handling,
+        );
+    let (result, new_main_memory) = match result {
+        Ok((execution_state, main_memory)) => (Ok(execution_state), main_memory),
+        Err(err) => (Err(err), None),
+    };
+
+    let main_memory_handling = match determine_main_memory_handling(
+        context.mode,
+        &helper.canister().execution_state,
+        &result,
+        new_main_memory,
+    ) {
+        Ok(main_memory_handling) => main_memory_handling,
+        Err(err) => {
+            let instructions_left = helper.instructions_left();
+            return finish_err(clean_canister, instructions_left, original, round, err);
+        }
+    };
 
     if let Err(err) = helper.replace_execution_state_and_allocations(
         instructions_from_compilation,
         result,
-        StableMemoryHandling::Keep,
+        MemoryHandling {
+            stable_memory_handling: StableMemoryHandling::Keep,
+            main_memory_handling,
+        },
         &original,
     ) {
         let instructions_left = helper.instructions_left();
@@ -792,3 +831,132 @@ impl PausedInstallCodeExecution for PausedPostUpgradeExecution {
         (self.original.message, self.original.call_id, Cycles::zero())
     }
 }
+
+/// Returns true if the given Wasm module declares that it uses enhanced
+/// orthogonal persistence.
+fn expects_enhanced_orthogonal_persistence(execution_state: &ExecutionState) -> bool {
+    execution_state
+        .metadata
+        .get_custom_section(ENHANCED_ORTHOGONAL_PERSISTENCE_SECTION)
+        .is_some()
+}
+
+/// Determines whether the Wasm main memory is kept or replaced by the upgrade.
+///
+/// The main memory is only kept if the `wasm_memory_persistence` upgrade option
+/// is `Keep`, which requires the new Wasm module to use enhanced orthogonal
+/// persistence and to declare a main memory that can hold the kept one, i.e.,
+/// of the same type (32-bit or 64-bit) and with a maximum size (if any) of at
+/// least the current size of the kept memory. Whether the retained memory
+/// layout is compatible with the new module is checked by the module's runtime
+/// system itself when it starts: an incompatible layout makes the `start()` or
+/// `canister_post_upgrade()` method trap, which rolls back the whole upgrade
+/// instead of corrupting the state.
+///
+/// Conversely, replacing the main memory of a canister whose module uses
+/// enhanced orthogonal persistence would silently discard its state, so this
+/// requires the `wasm_memory_persistence` option to be `Replace` explicitly.
+fn determine_main_memory_handling(
+    mode: CanisterInstallModeV2,
+    old_state: &Option<ExecutionState>,
+    new_state: &HypervisorResult<ExecutionState>,
+    new_main_memory: Option<WasmMemoryDeclaration>,
+) -> Result<MainMemoryHandling, CanisterManagerError> {
+    let wasm_memory_persistence = match mode {
+        CanisterInstallModeV2::Upgrade(Some(CanisterUpgradeOptions {
+            wasm_memory_persistence,
+            ..
+        })) => wasm_memory_persistence,
+        CanisterInstallModeV2::Install
+        | CanisterInstallModeV2::Reinstall
+        | CanisterInstallModeV2::Upgrade(None) => None,
+    };
+
+    match wasm_memory_persistence {
+        Some(WasmMemoryPersistence::Keep) => {
+            // If the new module fails to compile, the error is reported later on.
+            if let Ok(new_state) = new_state {
+                if !expects_enhanced_orthogonal_persistence(new_state) {
+                    return Err(CanisterManagerError::InvalidUpgradeOptionError {
+                        message: "The `wasm_memory_persistence: opt Keep` upgrade option \
+                            requires that the new canister module supports enhanced \
+                            orthogonal persistence."
+                            .to_string(),
+                    });
+                }
+                if let Some(old_state) = old_state {
+                    check_kept_wasm_memory_compatibility(old_state, new_main_memory)?;
+                }
+            }
+            Ok(MainMemoryHandling::Keep)
+        }
+        Some(WasmMemoryPersistence::Replace) => Ok(MainMemoryHandling::Replace),
+        None => {
+            if old_state
+                .as_ref()
+                .map_or(false, expects_enhanced_orthogonal_persistence)
+            {
+                return Err(CanisterManagerError::MissingUpgradeOptionError {
+                    message: "Enhanced orthogonal persistence requires the \
+                        `wasm_memory_persistence` upgrade option."
+                        .to_string(),
+                });
+            }
+            Ok(MainMemoryHandling::Replace)
+        }
+    }
+}
+
+/// Checks that the main memory declared by the new Wasm module can hold the
+/// main memory that is kept from the old one: the current size of the kept
+/// memory must not exceed the maximum size declared by the new module. Both
+/// memories are 32-bit, as validation rejects modules with a 64-bit memory.
+///
+/// This is checked before the old memory is replaced, as the new module would
+/// otherwise be instantiated with a memory that violates its declaration. The
+/// declared memory is taken from the validation of the new module, so that the
+/// module is not parsed again.
+fn check_kept_wasm_memory_compatibility(
+    old_state: &ExecutionState,
+    new_main_memory: Option<WasmMemoryDeclaration>,
+) -> Result<(), CanisterManagerError> {
+    check_wasm_memory_compatibility(old_state.wasm_memory.size, new_main_memory).map_err(
+        |message| CanisterManagerError::InvalidUpgradeOptionError {
+            message: format!(
+                "The `wasm_memory_persistence: opt Keep` upgrade option requires that the new \
+                canister module is compatible with the kept main memory: {}",
+                message
+            ),
+        },
+    )
+}
+
+/// Checks that a kept main memory of `kept_size` can be used by a module that
+/// declares `new_main_memory`, as described in
+/// `check_kept_wasm_memory_compatibility()`. Returns a description of the
+/// incompatibility otherwise.
+///
+/// Public so that tools that replace the module of a canister outside of
+/// `install_code`, like the replay tool, apply the same rules. The declaration
+/// of the new module is returned by
+/// `Hypervisor::create_execution_state_with_main_memory()`.
+pub fn check_wasm_memory_compatibility(
+    kept_size: NumWasmPages,
+    new_main_memory: Option<WasmMemoryDeclaration>,
+) -> Result<(), String> {
+    let new_main_memory = new_main_memory
+        .ok_or_else(|| "the new module does not declare a main memory.".to_string())?;
+
+    let kept_pages = kept_size.get() as u64;
+    if let Some(maximum) = new_main_memory.maximum {
+        if kept_pages > maximum {
+            return Err(format!(
+                "the new module declares a maximum main memory size of {} Wasm pages, but the \
+                kept main memory has {} Wasm pages.",
+                maximum, kept_pages
+            ));
+        }
+    }
+
+    Ok(())
+}
@@ -1,9 +1,12 @@
 use std::collections::HashMap;
 
+use ic_embedders::wasm_utils::WasmMemoryDeclaration;
 use ic_error_types::ErrorCode;
 use ic_logger::replica_logger::LogEntryLogger;
-use ic_management_canister_types::{EmptyBlob, Payload, SkipPreUpgrade};
-use ic_replicated_state::{canister_state::NextExecution, CanisterState};
+use ic_management_canister_types::{
+    CanisterUpgradeOptions, EmptyBlob, Payload, WasmMemoryPersistence,
+};
+use ic_replicated_state::{canister_state::NextExecution, CanisterState, NumWasmPages};
 use ic_state_machine_tests::{IngressState, WasmResult};
 use ic_test_utilities::types::ids::user_test_id;
 use ic_test_utilities_execution_environment::{
@@ -353,9 +356,18 @@ fn test_pre_upgrade_execution_with_canister_install_mode_v2() {
 
     for skip_pre_upgrade in [
         None,
-        Some(SkipPreUpgrade(None)),
-        Some(SkipPreUpgrade(Some(false))),
-        Some(SkipPreUpgrade(Some(true))),
+        Some(CanisterUpgradeOptions {
+            skip_pre_upgrade: None,
+            wasm_memory_persistence: None,
+        }),
+        Some(CanisterUpgradeOptions {
+            skip_pre_upgrade: Some(false),
+            wasm_memory_persistence: None,
+        }),
+        Some(CanisterUpgradeOptions {
+            skip_pre_upgrade: Some(true),
+            wasm_memory_persistence: None,
+        }),
     ] {
         let old_binary = binary(&[(Function::PreUpgrade, Execution::ShortTrap)]);
         let canister_id = test.create_canister(Cycles::from(1_000_000_000_000u128));
@@ -364,7 +376,12 @@ fn test_pre_upgrade_execution_with_canister_install_mode_v2() {
 
         let result = test.upgrade_canister_v2(canister_id, new_empty_binary(), skip_pre_upgrade);
 
-        if skip_pre_upgrade == Some(SkipPreUpgrade(Some(true))) {
+        if skip_pre_upgrade
+            == Some(CanisterUpgradeOptions {
+                skip_pre_upgrade: Some(true),
+                wasm_memory_persistence: None,
+            })
+        {
             assert_eq!(result, Ok(()));
             assert_canister_state_after_ok(
                 &canister_state_before,
@@ -386,9 +403,18 @@ fn test_upgrade_execution_with_canister_install_mode_v2() {
 
     for skip_pre_upgrade in [
         None,
-        Some(SkipPreUpgrade(None)),
-        Some(SkipPreUpgrade(Some(false))),
-        Some(SkipPreUpgrade(Some(true))),
+        Some(CanisterUpgradeOptions {
+            skip_pre_upgrade: None,
+            wasm_memory_persistence: None,
+        }),
+        Some(CanisterUpgradeOptions {
+            skip_pre_upgrade: Some(false),
+            wasm_memory_persistence: None,
+        }),
+        Some(CanisterUpgradeOptions {
+            skip_pre_upgrade: Some(true),
+            wasm_memory_persistence: None,
+        }),
     ] {
         let old_binary = binary(&[(Function::PreUpgrade, Execution::Short)]);
         let canister_id = test.create_canister(Cycles::from(1_000_000_000_000u128));
@@ -994,7 +1020,10 @@ fn upgrade_with_skip_pre_upgrade_fails_on_no_execution_state() {
     let result = test.upgrade_canister_v2(
         canister_id,
         new_empty_binary(),
-        Some(SkipPreUpgrade(Some(true))),
+        Some(CanisterUpgradeOptions {
+            skip_pre_upgrade: Some(true),
+            wasm_memory_persistence: None,
+        }),
     );
     assert_eq!(
         result.unwrap_err().code(),
@@ -1012,8 +1041,202 @@ fn upgrade_with_skip_pre_upgrade_ok_with_no_pre_upgrade() {
     let result = test.upgrade_canister_v2(
         canister_id,
         new_empty_binary(),
-        Some(SkipPreUpgrade(Some(true))),
+        Some(CanisterUpgradeOptions {
+            skip_pre_upgrade: Some(true),
+            wasm_memory_persistence: None,
+        }),
     );
     assert_eq!(result, Ok(()));
     assert_canister_state_after_ok(&canister_state_before, test.canister_state(canister_id));
 }
+
+////////////////////////////////////////////////////////////////////////
+// Enhanced orthogonal persistence
+
+/// Returns a WASM binary that initializes its main memory with the given
+/// `data` and exports a `read` method replying with it. If `eop` is set, the
+/// binary declares that it uses enhanced orthogonal persistence.
+fn persistence_binary(data: &str, eop: bool) -> Vec<u8> {
+    persistence_binary_with_memory(data, eop, "1")
+}
+
+/// Like `persistence_binary`, but declares the main memory with the given
+/// limits, e.g., "1 2" for an initial size of one and a maximum size of two
+/// Wasm pages.
+fn persistence_binary_with_memory(data: &str, eop: bool, memory_limits: &str) -> Vec<u8> {
+    let len = data.len();
+    let mut binary = wat::parse_str(format!(
+        r#"
+        (module
+            (import "ic0" "msg_reply" (func $msg_reply))
+            (import "ic0" "msg_reply_data_append"
+                (func $msg_reply_data_append (param i32 i32)))
+            (func (export "canister_update read")
+                (call $msg_reply_data_append (i32.const 0) (i32.const {len}))
+                (call $msg_reply)
+            )
+            (memory {memory_limits})
+            (data (i32.const 0) "{data}")
+        )"#
+    ))
+    .unwrap();
+    if eop {
+        // Append a custom section named `icp:private enhanced-orthogonal-persistence`
+        // with an empty content.
+        let name = b"icp:private enhanced-orthogonal-persistence";
+        binary.push(0);
+        binary.push(name.len() as u8 + 1);
+        binary.push(name.len() as u8);
+        binary.extend_from_slice(name);
+    }
+    binary
+}
+
+fn upgrade_options(
+    wasm_memory_persistence: Option<WasmMemoryPersistence>,
+) -> Option<CanisterUpgradeOptions> {
+    Some(CanisterUpgradeOptions {
+        skip_pre_upgrade: None,
+        wasm_memory_persistence,
+    })
+}
+
+#[test]
+fn upgrade_with_enhanced_orthogonal_persistence_keeps_main_memory() {
+    let mut test = execution_test_with_max_rounds(1);
+    let canister_id = test
+        .canister_from_binary(persistence_binary("old", true))
+        .unwrap();
+
+    let result = test.upgrade_canister_v2(
+        canister_id,
+        persistence_binary("new", true),
+        upgrade_options(Some(WasmMemoryPersistence::Keep)),
+    );
+    assert_eq!(result, Ok(()));
+    let result = test.ingress(canister_id, "read", vec![]).unwrap();
+    assert_eq!(result, WasmResult::Reply(b"old".to_vec()));
+}
+
+#[test]
+fn upgrade_with_enhanced_orthogonal_persistence_replaces_main_memory() {
+    let mut test = execution_test_with_max_rounds(1);
+    let canister_id = test
+        .canister_from_binary(persistence_binary("old", true))
+        .unwrap();
+
+    let result = test.upgrade_canister_v2(
+        canister_id,
+        persistence_binary("new", true),
+        upgrade_options(Some(WasmMemoryPersistence::Replace)),
+    );
+    assert_eq!(result, Ok(()));
+    let result = test.ingress(canister_id, "read", vec![]).unwrap();
+    assert_eq!(result, WasmResult::Reply(b"new".to_vec()));
+}
+
+#[test]
+fn upgrade_fails_on_keep_main_memory_without_enhanced_orthogonal_persistence() {
+    let mut test = execution_test_with_max_rounds(1);
+    let canister_id = test
+        .canister_from_binary(persistence_binary("old", true))
+        .unwrap();
+    let canister_state_before = test.canister_state(canister_id).clone();
+
+    let err = test
+        .upgrade_canister_v2(
+            canister_id,
+            persistence_binary("new", false),
+            upgrade_options(Some(WasmMemoryPersistence::Keep)),
+        )
+        .unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterContractViolation);
+    assert!(err.description().contains("Invalid upgrade option"));
+    assert_canister_state_after_err(&canister_state_before, test.canister_state(canister_id));
+}
+
+#[test]
+fn upgrade_fails_on_keep_main_memory_exceeding_new_maximum_memory_size() {
+    let mut test = execution_test_with_max_rounds(1);
+    let canister_id = test
+        .canister_from_binary(persistence_binary_with_memory("old", true, "3"))
+        .unwrap();
+    let canister_state_before = test.canister_state(canister_id).clone();
+
+    let err = test
+        .upgrade_canister_v2(
+            canister_id,
+            persistence_binary_with_memory("new", true, "1 2"),
+            upgrade_options(Some(WasmMemoryPersistence::Keep)),
+        )
+        .unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterContractViolation);
+    assert!(err
+        .description()
+        .contains("maximum main memory size of 2 Wasm pages"));
+    assert_canister_state_after_err(&canister_state_before, test.canister_state(canister_id));
+
+    // A maximum size that accommodates the kept main memory is fine.
+    let result = test.upgrade_canister_v2(
+        canister_id,
+        persistence_binary_with_memory("new", true, "1 3"),
+        upgrade_options(Some(WasmMemoryPersistence::Keep)),
+    );
+    assert_eq!(result, Ok(()));
+    let result = test.ingress(canister_id, "read", vec![]).unwrap();
+    assert_eq!(result, WasmResult::Reply(b"old".to_vec()));
+}
+
+#[test]
+fn check_wasm_memory_compatibility_reports_incompatible_main_memory() {
+    let kept_size = NumWasmPages::new(3);
+    let declaration = |maximum| Some(WasmMemoryDeclaration { maximum });
+
+    assert_eq!(
+        super::check_wasm_memory_compatibility(kept_size, declaration(None)),
+        Ok(())
+    );
+    assert_eq!(
+        super::check_wasm_memory_compatibility(kept_size, declaration(Some(3))),
+        Ok(())
+    );
+    let err = super::check_wasm_memory_compatibility(kept_size, declaration(Some(2))).unwrap_err();
+    assert!(err.contains("maximum main memory size of 2 Wasm pages"));
+    let err = super::check_wasm_memory_compatibility(kept_size, None).unwrap_err();
+    assert!(err.contains("does not declare a main memory"));
+}
+
+#[test]
+fn upgrade_fails_on_missing_wasm_memory_persistence_with_enhanced_orthogonal_persistence() {
+    let mut test = execution_test_with_max_rounds(1);
+    let canister_id = test
+        .canister_from_binary(persistence_binary("old", true))
+        .unwrap();
+    let canister_state_before = test.canister_state(canister_id).clone();
+
+    for upgrade_options in [None, upgrade_options(None)] {
+        let err = test
+            .upgrade_canister_v2(
+                canister_id,
+                persistence_binary("new", true),
+                upgrade_options,
+            )
+            .unwrap_err();
+        assert_eq!(err.code(), ErrorCode::CanisterContractViolation);
+        assert!(err.description().contains("Missing upgrade option"));
+        assert_canister_state_after_err(&canister_state_before, test.canister_state(canister_id));
+    }
+}
+
+#[test]
+fn upgrade_without_enhanced_orthogonal_persistence_replaces_main_memory() {
+    let mut test = execution_test_with_max_rounds(1);
+    let canister_id = test
+        .canister_from_binary(persistence_binary("old", false))
+        .unwrap();
+
+    let result = test.upgrade_canister_v2(canister_id, persistence_binary("new", false), None);
+    assert_eq!(result, Ok(()));
+    let result = test.ingress(canister_id, "read", vec![]).unwrap();
+    assert_eq!(result, WasmResult::Reply(b"new".to_vec()));
+}
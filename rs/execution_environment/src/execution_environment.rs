@@ -36,12 +36,14 @@ use ic_logger::{error, info, warn, ReplicaLogger};
 use ic_management_canister_types::{
     CanisterChangeOrigin, CanisterHttpRequestArgs, CanisterIdRecord, CanisterInfoRequest,
     CanisterInfoResponse, CanisterSettingsArgs, CanisterStatusType, ClearChunkStoreArgs,
-    ComputeInitialEcdsaDealingsArgs, CreateCanisterArgs, ECDSAPublicKeyArgs,
-    ECDSAPublicKeyResponse, EcdsaKeyId, EmptyBlob, InstallChunkedCodeArgs, InstallCodeArgsV2,
-    Method as Ic00Method, NodeMetricsHistoryArgs, Payload as Ic00Payload,
-    ProvisionalCreateCanisterWithCyclesArgs, ProvisionalTopUpCanisterArgs, SetupInitialDKGArgs,
-    SignWithECDSAArgs, StoredChunksArgs, UninstallCodeArgs, UpdateSettingsArgs, UploadChunkArgs,
-    IC_00,
+    ComputeInitialEcdsaDealingsArgs, CreateCanisterArgs, DeleteCanisterSnapshotArgs,
+    ECDSAPublicKeyArgs, ECDSAPublicKeyResponse, EcdsaKeyId, EmptyBlob, InstallChunkedCodeArgs,
+    InstallCodeArgsV2, ListCanisterSnapshotArgs, LoadCanisterSnapshotArgs, Method as Ic00Method,
+    NodeMetricsHistoryArgs, Payload as Ic00Payload, ProvisionalCreateCanisterWithCyclesArgs,
+    ProvisionalTopUpCanisterArgs, ReadCanisterSnapshotDataArgs, ReadCanisterSnapshotMetadataArgs,
+    SetupInitialDKGArgs, SignWithECDSAArgs, StoredChunksArgs, SubnetInfoArgs, SubnetInfoResponse,
+    TakeCanisterSnapshotArgs, UninstallCodeArgs, UpdateSettingsArgs,
+    UploadCanisterSnapshotDataArgs, UploadCanisterSnapshotMetadataArgs, UploadChunkArgs, IC_00,
 };
 use ic_metrics::MetricsRegistry;
 use ic_registry_provisional_whitelist::ProvisionalWhitelist;
@@ -373,6 +375,7 @@ impl ExecutionEnvironment {
             config.rate_limiting_of_heap_delta,
             heap_delta_rate_limit,
             upload_wasm_chunk_instructions,
+            config.max_number_of_snapshots_per_canister,
         );
         let metrics = ExecutionEnvironmentMetrics::new(metrics_registry);
         let canister_manager = CanisterManager::new(
@@ -1110,6 +1113,12 @@ impl ExecutionEnvironment {
                 Some((res, msg.take_cycles()))
             }
 
+            Ok(Ic00Method::SubnetInfo) => {
+                let res =
+                    SubnetInfoArgs::decode(payload).and_then(|args| self.subnet_info(&state, args));
+                Some((res, msg.take_cycles()))
+            }
+
             Ok(Ic00Method::FetchCanisterLogs) => match self.config.canister_logging {
                 FlagStatus::Enabled => Some((
                     Err(UserError::new(
@@ -1135,14 +1144,10 @@ impl ExecutionEnvironment {
 
             Ok(Ic00Method::TakeCanisterSnapshot) => match self.config.canister_snapshots {
                 FlagStatus::Enabled => {
-                    // TODO(EXC-1529): Implement take_canister_snapshot.
-                    Some((
-                        Err(UserError::new(
-                            ErrorCode::CanisterRejectedMessage,
-                            "Canister snapshotting API is not yet implemented.",
-                        )),
-                        msg.take_cycles(),
-                    ))
+                    let res = TakeCanisterSnapshotArgs::decode(payload).and_then(|args| {
+                        self.take_canister_snapshot(*msg.sender(), &mut state, args, round_limits)
+                    });
+                    Some((res, msg.take_cycles()))
                 }
                 FlagStatus::Disabled => {
                     let err = Err(UserError::new(
@@ -1155,14 +1160,23 @@ impl ExecutionEnvironment {
 
             Ok(Ic00Method::LoadCanisterSnapshot) => match self.config.canister_snapshots {
                 FlagStatus::Enabled => {
-                    // TODO(EXC-1530): Implement load_canister_snapshot.
-                    Some((
-                        Err(UserError::new(
-                            ErrorCode::CanisterRejectedMessage,
-                            "Canister snapshotting API is not yet implemented.",
-                        )),
-                        msg.take_cycles(),
-                    ))
+                    let resource_saturation =
+                        self.subnet_memory_saturation(&round_limits.subnet_available_memory);
+                    let res = LoadCanisterSnapshotArgs::decode(payload).and_then(|args| {
+                        let origin = msg.canister_change_origin(args.get_sender_canister_version());
+                        self.canister_manager
+                            .load_canister_snapshot(
+                                origin,
+                                &args,
+                                &mut state,
+                                round_limits,
+                                registry_settings.subnet_size,
+                                &resource_saturation,
+                            )
+                            .map(|()| EmptyBlob.encode())
+                            .map_err(|err| err.into())
+                    });
+                    Some((res, msg.take_cycles()))
                 }
                 FlagStatus::Disabled => {
                     let err = Err(UserError::new(
@@ -1175,14 +1189,9 @@ impl ExecutionEnvironment {
 
             Ok(Ic00Method::ListCanisterSnapshots) => match self.config.canister_snapshots {
                 FlagStatus::Enabled => {
-                    // TODO(EXC-1531): Implement list_canister_snapshot.
-                    Some((
-                        Err(UserError::new(
-                            ErrorCode::CanisterRejectedMessage,
-                            "Canister snapshotting API is not yet implemented.",
-                        )),
-                        msg.take_cycles(),
-                    ))
+                    let res = ListCanisterSnapshotArgs::decode(payload)
+                        .and_then(|args| self.list_canister_snapshots(*msg.sender(), &state, args));
+                    Some((res, msg.take_cycles()))
                 }
                 FlagStatus::Disabled => {
                     let err = Err(UserError::new(
@@ -1195,14 +1204,10 @@ impl ExecutionEnvironment {
 
             Ok(Ic00Method::DeleteCanisterSnapshot) => match self.config.canister_snapshots {
                 FlagStatus::Enabled => {
-                    // TODO(EXC-1532): Implement delete_canister_snapshot.
-                    Some((
-                        Err(UserError::new(
-                            ErrorCode::CanisterRejectedMessage,
-                            "Canister snapshotting API is not yet implemented.",
-                        )),
-                        msg.take_cycles(),
-                    ))
+                    let res = DeleteCanisterSnapshotArgs::decode(payload).and_then(|args| {
+                        self.delete_canister_snapshot(*msg.sender(), &mut state, args, round_limits)
+                    });
+                    Some((res, msg.take_cycles()))
                 }
                 FlagStatus::Disabled => {
                     let err = Err(UserError::new(
@@ -1213,6 +1218,86 @@ impl ExecutionEnvironment {
                 }
             },
 
+            Ok(Ic00Method::ReadCanisterSnapshotMetadata) => {
+                match self.config.canister_snapshot_data_transfer {
+                    FlagStatus::Enabled => {
+                        let res =
+                            ReadCanisterSnapshotMetadataArgs::decode(payload).and_then(|args| {
+                                self.read_canister_snapshot_metadata(*msg.sender(), &state, args)
+                            });
+                        Some((res, msg.take_cycles()))
+                    }
+                    FlagStatus::Disabled => {
+                        let err = Err(UserError::new(
+                            ErrorCode::CanisterContractViolation,
+                            "This API is not enabled on this subnet".to_string(),
+                        ));
+                        Some((err, msg.take_cycles()))
+                    }
+                }
+            }
+
+            Ok(Ic00Method::ReadCanisterSnapshotData) => {
+                match self.config.canister_snapshot_data_transfer {
+                    FlagStatus::Enabled => {
+                        let res = ReadCanisterSnapshotDataArgs::decode(payload).and_then(|args| {
+                            self.read_canister_snapshot_data(*msg.sender(), &state, args)
+                        });
+                        Some((res, msg.take_cycles()))
+                    }
+                    FlagStatus::Disabled => {
+                        let err = Err(UserError::new(
+                            ErrorCode::CanisterContractViolation,
+                            "This API is not enabled on this subnet".to_string(),
+                        ));
+                        Some((err, msg.take_cycles()))
+                    }
+                }
+            }
+
+            Ok(Ic00Method::UploadCanisterSnapshotMetadata) => {
+                match self.config.canister_snapshot_data_transfer {
+                    FlagStatus::Enabled => {
+                        let res =
+                            UploadCanisterSnapshotMetadataArgs::decode(payload).and_then(|args| {
+                                self.upload_canister_snapshot_metadata(
+                                    *msg.sender(),
+                                    &mut state,
+                                    args,
+                                    round_limits,
+                                )
+                            });
+                        Some((res, msg.take_cycles()))
+                    }
+                    FlagStatus::Disabled => {
+                        let err = Err(UserError::new(
+                            ErrorCode::CanisterContractViolation,
+                            "This API is not enabled on this subnet".to_string(),
+                        ));
+                        Some((err, msg.take_cycles()))
+                    }
+                }
+            }
+
+            Ok(Ic00Method::UploadCanisterSnapshotData) => {
+                match self.config.canister_snapshot_data_transfer {
+                    FlagStatus::Enabled => {
+                        let res =
+                            UploadCanisterSnapshotDataArgs::decode(payload).and_then(|args| {
+                                self.upload_canister_snapshot_data(*msg.sender(), &mut state, args)
+                            });
+                        Some((res, msg.take_cycles()))
+                    }
+                    FlagStatus::Disabled => {
+                        let err = Err(UserError::new(
+                            ErrorCode::CanisterContractViolation,
+                            "This API is not enabled on this subnet".to_string(),
+                        ));
+                        Some((err, msg.take_cycles()))
+                    }
+                }
+            }
+
             Err(ParseError::VariantNotFound) => {
                 let res = Err(UserError::new(
                     ErrorCode::CanisterMethodNotFound,
@@ -1613,10 +1698,13 @@ impl ExecutionEnvironment {
         state: &mut ReplicatedState,
         subnet_size: usize,
     ) -> Result<Vec<u8>, UserError> {
+        let snapshots_memory_usage = state
+            .canister_snapshots
+            .memory_usage_by_canister(canister_id);
         let canister = get_canister_mut(canister_id, state)?;
 
         self.canister_manager
-            .get_canister_status(sender, canister, subnet_size)
+            .get_canister_status(sender, canister, snapshots_memory_usage, subnet_size)
             .map(|status| status.encode())
             .map_err(|err| err.into())
     }
@@ -1749,6 +1837,96 @@ impl ExecutionEnvironment {
             .map_err(|err| err.into())
     }
 
+    fn take_canister_snapshot(
+        &self,
+        sender: PrincipalId,
+        state: &mut ReplicatedState,
+        args: TakeCanisterSnapshotArgs,
+        round_limits: &mut RoundLimits,
+    ) -> Result<Vec<u8>, UserError> {
+        self.canister_manager
+            .take_canister_snapshot(sender, &args, state, round_limits)
+            .map(|response| response.encode())
+            .map_err(|err| err.into())
+    }
+
+    fn list_canister_snapshots(
+        &self,
+        sender: PrincipalId,
+        state: &ReplicatedState,
+        args: ListCanisterSnapshotArgs,
+    ) -> Result<Vec<u8>, UserError> {
+        self.canister_manager
+            .list_canister_snapshots(sender, &args, state)
+            .map(|response| response.encode())
+            .map_err(|err| err.into())
+    }
+
+    fn delete_canister_snapshot(
+        &self,
+        sender: PrincipalId,
+        state: &mut ReplicatedState,
+        args: DeleteCanisterSnapshotArgs,
+        round_limits: &mut RoundLimits,
+    ) -> Result<Vec<u8>, UserError> {
+        self.canister_manager
+            .delete_canister_snapshot(sender, &args, state, round_limits)
+            .map(|()| EmptyBlob.encode())
+            .map_err(|err| err.into())
+    }
+
+    fn read_canister_snapshot_metadata(
+        &self,
+        sender: PrincipalId,
+        state: &ReplicatedState,
+        args: ReadCanisterSnapshotMetadataArgs,
+    ) -> Result<Vec<u8>, UserError> {
+        self.canister_manager
+            .read_snapshot_metadata(sender, &args, state)
+            .map(|response| response.encode())
+            .map_err(|err| err.into())
+    }
+
+    fn read_canister_snapshot_data(
+        &self,
+        sender: PrincipalId,
+        state: &ReplicatedState,
+        args: ReadCanisterSnapshotDataArgs,
+    ) -> Result<Vec<u8>, UserError> {
+        self.canister_manager
+            .read_snapshot_data(sender, &args, state)
+            .map(|response| response.encode())
+            .map_err(|err| err.into())
+    }
+
+    fn upload_canister_snapshot_metadata(
+        &self,
+        sender: PrincipalId,
+        state: &mut ReplicatedState,
+        args: UploadCanisterSnapshotMetadataArgs,
+        round_limits: &mut RoundLimits,
+    ) -> Result<Vec<u8>, UserError> {
+        self.canister_manager
+            .upload_snapshot_metadata(sender, &args, state, round_limits)
+            .map(|response| response.encode())
+            .map_err(|err| err.into())
+    }
+
+    fn upload_canister_snapshot_data(
+        &self,
+        sender: PrincipalId,
+        state: &mut ReplicatedState,
+        args: UploadCanisterSnapshotDataArgs,
+    ) -> Result<Vec<u8>, UserError> {
+        self.canister_manager
+            .upload_snapshot_data(sender, &args, state)
+            .map(|heap_delta_increase| {
+                state.metadata.heap_delta_estimate += heap_delta_increase;
+                EmptyBlob.encode()
+            })
+            .map_err(|err| err.into())
+    }
+
     fn node_metrics_history(
         &self,
         state: &ReplicatedState,
@@ -1773,6 +1951,62 @@ impl ExecutionEnvironment {
         Ok(Encode!(&result).unwrap())
     }
 
+    fn subnet_info(
+        &self,
+        state: &ReplicatedState,
+        args: SubnetInfoArgs,
+    ) -> Result<Vec<u8>, UserError> {
+        if args.subnet_id != self.own_subnet_id.get() {
+            return Err(UserError::new(
+                ErrorCode::CanisterRejectedMessage,
+                format!(
+                    "Provided target subnet ID {} does not match current subnet ID {}.",
+                    args.subnet_id, self.own_subnet_id
+                ),
+            ));
+        }
+
+        let network_topology = &state.metadata.network_topology;
+        let subnet_topology = network_topology
+            .subnets
+            .get(&self.own_subnet_id)
+            .ok_or_else(|| {
+                UserError::new(
+                    ErrorCode::CanisterRejectedMessage,
+                    format!(
+                        "Subnet {} not found in the network topology.",
+                        self.own_subnet_id
+                    ),
+                )
+            })?;
+        let ecdsa_key_ids = network_topology
+            .ecdsa_signing_subnets
+            .iter()
+            .filter(|(_, subnets)| subnets.contains(&self.own_subnet_id))
+            .map(|(key_id, _)| key_id.clone())
+            .collect();
+        let features = state.metadata.own_subnet_features;
+        let enabled_features = [
+            ("canister_sandboxing", features.canister_sandboxing),
+            ("http_requests", features.http_requests),
+            ("sev_enabled", features.sev_enabled),
+        ]
+        .into_iter()
+        .filter(|(_, enabled)| *enabled)
+        .map(|(name, _)| name.to_string())
+        .collect();
+
+        let result = SubnetInfoResponse {
+            replica_version: subnet_topology.replica_version.to_string(),
+            subnet_type: state.metadata.own_subnet_type.as_ref().to_string(),
+            subnet_size: subnet_topology.nodes.len() as u64,
+            ecdsa_key_ids,
+            enabled_features,
+            num_canisters: state.canister_states.len() as u64,
+        };
+        Ok(result.encode())
+    }
+
     // Executes an inter-canister response.
     //
     // Returns a tuple with the result, along with a flag indicating whether or
@@ -2640,6 +2874,8 @@ impl ExecutionEnvironment {
         match task {
             ExecutionTask::Heartbeat
             | ExecutionTask::GlobalTimer
+            | ExecutionTask::OnLowWasmMemory
+            | ExecutionTask::OnLowCycles
             | ExecutionTask::PausedExecution(_)
             | ExecutionTask::AbortedExecution { .. } => {
                 panic!(
@@ -2736,7 +2972,9 @@ impl ExecutionEnvironment {
                     ExecutionTask::AbortedExecution { .. }
                     | ExecutionTask::AbortedInstallCode { .. }
                     | ExecutionTask::Heartbeat
-                    | ExecutionTask::GlobalTimer => task,
+                    | ExecutionTask::GlobalTimer
+                    | ExecutionTask::OnLowWasmMemory
+                    | ExecutionTask::OnLowCycles => task,
                     ExecutionTask::PausedExecution(id) => {
                         let paused = self.take_paused_execution(id).unwrap();
                         let (input, prepaid_execution_cycles) = paused.abort(log);
@@ -3294,6 +3532,14 @@ pub fn execute_canister(
                 let task = CanisterMessageOrTask::Task(CanisterTask::GlobalTimer);
                 (task, None)
             }
+            ExecutionTask::OnLowWasmMemory => {
+                let task = CanisterMessageOrTask::Task(CanisterTask::OnLowWasmMemory);
+                (task, None)
+            }
+            ExecutionTask::OnLowCycles => {
+                let task = CanisterMessageOrTask::Task(CanisterTask::OnLowCycles);
+                (task, None)
+            }
             ExecutionTask::AbortedExecution {
                 input,
                 prepaid_execution_cycles,
@@ -1,27 +1,38 @@
 use assert_matches::assert_matches;
 use candid::{Decode, Encode};
-use ic_config::flag_status::FlagStatus;
+use ic_config::{
+    execution_environment::MAX_NUMBER_OF_SNAPSHOTS_PER_CANISTER, flag_status::FlagStatus,
+};
 use ic_registry_routing_table::RoutingTable;
 use ic_replicated_state::canister_state::system_state::CyclesUseCase;
 use ic_replicated_state::ReplicatedState;
 use ic_types::nominal_cycles::NominalCycles;
 
 use ic_base_types::{NumBytes, NumSeconds};
+use ic_cycles_account_manager::ResourceSaturation;
 use ic_error_types::{ErrorCode, RejectCode, UserError};
 use ic_management_canister_types::{
     self as ic00, BitcoinGetUtxosArgs, BitcoinNetwork, BoundedHttpHeaders, CanisterChange,
-    CanisterHttpRequestArgs, CanisterIdRecord, CanisterStatusResultV2, CanisterStatusType,
+    CanisterChangeDetails, CanisterChangeOrigin, CanisterHttpRequestArgs, CanisterIdRecord,
+    CanisterSnapshotDataKind, CanisterSnapshotDataOffset, CanisterSnapshotResponse,
+    CanisterStatusResultV2, CanisterStatusType, ChunkHash, DeleteCanisterSnapshotArgs,
     DerivationPath, EcdsaCurve, EcdsaKeyId, EmptyBlob, FetchCanisterLogsRequest, HttpMethod,
+    ListCanisterSnapshotArgs, ListCanisterSnapshotsResponse, LoadCanisterSnapshotArgs,
     LogVisibility, Method, Payload as Ic00Payload, ProvisionalCreateCanisterWithCyclesArgs,
-    ProvisionalTopUpCanisterArgs, TransformContext, TransformFunc, IC_00,
+    ProvisionalTopUpCanisterArgs, ReadCanisterSnapshotDataArgs, ReadCanisterSnapshotDataResponse,
+    ReadCanisterSnapshotMetadataArgs, ReadCanisterSnapshotMetadataResponse, SnapshotGlobal,
+    TakeCanisterSnapshotArgs, TransformContext, TransformFunc, UploadCanisterSnapshotDataArgs,
+    UploadCanisterSnapshotMetadataArgs, UploadCanisterSnapshotMetadataResponse, UploadChunkArgs,
+    IC_00,
 };
 use ic_registry_routing_table::canister_id_into_u64;
 use ic_registry_routing_table::CanisterIdRange;
 use ic_registry_subnet_type::SubnetType;
 use ic_replicated_state::{
     canister_state::{DEFAULT_QUEUE_CAPACITY, WASM_PAGE_SIZE_IN_BYTES},
+    page_map::Buffer,
     testing::{CanisterQueuesTesting, SystemStateTesting},
-    CanisterStatus, SystemState,
+    CanisterStatus, Memory, SystemState,
 };
 use ic_test_utilities::assert_utils::assert_balance_equals;
 use ic_test_utilities_execution_environment::{
@@ -37,7 +48,7 @@ use ic_types::{
     messages::{
         CallbackId, Payload, RejectContext, RequestOrResponse, Response, MAX_RESPONSE_COUNT_BYTES,
     },
-    CanisterId, Cycles, PrincipalId, RegistryVersion,
+    CanisterId, Cycles, NumInstructions, PrincipalId, RegistryVersion, ReplicaVersion,
 };
 use ic_types_test_utils::ids::{canister_test_id, node_test_id, subnet_test_id, user_test_id};
 use ic_universal_canister::{call_args, wasm, UNIVERSAL_CANISTER_WASM};
@@ -652,6 +663,55 @@ fn get_running_canister_status_from_another_canister() {
     );
 }
 
+#[test]
+fn get_canister_status_memory_metrics() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let controller = test.universal_canister().unwrap();
+    let canister = test.universal_canister().unwrap();
+    let canister_status_args = Encode!(&CanisterIdRecord::from(canister)).unwrap();
+    let get_canister_status = wasm()
+        .call_simple(
+            ic00::IC_00,
+            Method::CanisterStatus,
+            call_args().other_side(canister_status_args),
+        )
+        .build();
+    test.set_controller(canister, controller.get()).unwrap();
+    let result = test.ingress(controller, "update", get_canister_status);
+    let reply = get_reply(result);
+    let csr = CanisterStatusResultV2::decode(&reply).unwrap();
+    let memory_metrics = csr.memory_metrics();
+    let execution_state = test.execution_state(canister);
+    assert_eq!(
+        memory_metrics.wasm_memory_size(),
+        execution_state.wasm_memory_usage()
+    );
+    assert_eq!(
+        memory_metrics.stable_memory_size(),
+        execution_state.stable_memory_usage()
+    );
+    assert_eq!(
+        memory_metrics.global_memory_size(),
+        execution_state.globals_memory_usage()
+    );
+    assert_eq!(
+        memory_metrics.wasm_binary_size(),
+        execution_state.wasm_binary_memory_usage()
+    );
+    assert_eq!(memory_metrics.snapshots_size(), NumBytes::from(0));
+    // All components except messages and snapshots add up to the memory size.
+    assert_eq!(
+        csr.memory_size(),
+        memory_metrics.wasm_memory_size()
+            + memory_metrics.stable_memory_size()
+            + memory_metrics.global_memory_size()
+            + memory_metrics.wasm_binary_size()
+            + memory_metrics.custom_sections_size()
+            + memory_metrics.canister_history_size()
+            + memory_metrics.wasm_chunk_store_size()
+    );
+}
+
 #[test]
 fn get_canister_status_from_another_canister_when_memory_low() {
     let mut test = ExecutionTestBuilder::new().build();
@@ -2242,6 +2302,71 @@ fn ecdsa_signature_rejected_without_fee() {
     );
 }
 
+#[test]
+fn ecdsa_signature_fee_quoted_by_cost_sign_with_ecdsa_is_charged() {
+    let fee = 1_000_000;
+    let payment = 2_000_000;
+    let ecdsa_key = make_key("secp256k1");
+    let mut test = ExecutionTestBuilder::new()
+        .with_own_subnet_id(subnet_test_id(1))
+        .with_nns_subnet_id(subnet_test_id(2))
+        .with_ecdsa_signature_fee(fee)
+        .with_ecdsa_key(ecdsa_key.clone())
+        .build();
+
+    // Replies with the fee quoted for a signature with the key "secp256k1".
+    let quoting_canister_id = test
+        .canister_from_wat(
+            r#"(module
+                (import "ic0" "cost_sign_with_ecdsa"
+                    (func $cost_sign_with_ecdsa (param i32 i32 i32 i32) (result i32)))
+                (import "ic0" "msg_reply_data_append"
+                    (func $msg_reply_data_append (param i32 i32)))
+                (import "ic0" "msg_reply" (func $msg_reply))
+                (func (export "canister_update quote")
+                    (if (call $cost_sign_with_ecdsa (i32.const 16) (i32.const 9) (i32.const 0) (i32.const 0))
+                        (then (unreachable)))
+                    (call $msg_reply_data_append (i32.const 0) (i32.const 16))
+                    (call $msg_reply))
+                (memory 1)
+                (data (i32.const 16) "secp256k1"))"#,
+        )
+        .unwrap();
+    let quote = match test.ingress(quoting_canister_id, "quote", vec![]).unwrap() {
+        WasmResult::Reply(bytes) => Cycles::new(u128::from_le_bytes(bytes.try_into().unwrap())),
+        WasmResult::Reject(msg) => panic!("Unexpected reject: {}", msg),
+    };
+    assert!(quote > Cycles::zero());
+
+    let canister_id = test.universal_canister().unwrap();
+    let esda_args = ic00::SignWithECDSAArgs {
+        message_hash: [1; 32],
+        derivation_path: DerivationPath::new(vec![]),
+        key_id: ecdsa_key,
+    };
+    let run = wasm()
+        .call_with_cycles(
+            ic00::IC_00,
+            Method::SignWithECDSA,
+            call_args()
+                .other_side(esda_args.encode())
+                .on_reject(wasm().reject_message().reject()),
+            Cycles::from(payment),
+        )
+        .build();
+    test.ingress_raw(canister_id, "update", run);
+
+    let (_, context) = test
+        .state()
+        .metadata
+        .subnet_call_context_manager
+        .sign_with_ecdsa_contexts
+        .iter()
+        .next()
+        .unwrap();
+    assert_eq!(context.request.payment, Cycles::new(payment) - quote);
+}
+
 #[test]
 fn ecdsa_signature_with_unknown_key_rejected() {
     let correct_key = make_key("correct_key");
@@ -2278,6 +2403,97 @@ fn ecdsa_signature_with_unknown_key_rejected() {
     );
 }
 
+#[test]
+fn subnet_info_returns_own_subnet_info() {
+    let ecdsa_key = make_key("secp256k1");
+    let own_subnet_id = subnet_test_id(1);
+    let mut test = ExecutionTestBuilder::new()
+        .with_subnet_type(SubnetType::Application)
+        .with_own_subnet_id(own_subnet_id)
+        .with_subnet_features("http_requests,sev_enabled")
+        .with_ecdsa_key(ecdsa_key.clone())
+        .build();
+    let canister_id = test.universal_canister().unwrap();
+    let args = ic00::SubnetInfoArgs {
+        subnet_id: own_subnet_id.get(),
+    };
+    let run = wasm()
+        .call_simple(
+            ic00::IC_00,
+            Method::SubnetInfo,
+            call_args()
+                .other_side(args.encode())
+                .on_reject(wasm().reject_message().reject()),
+        )
+        .build();
+
+    let result = test.ingress(canister_id, "update", run).unwrap();
+    let response = match result {
+        WasmResult::Reply(bytes) => ic00::SubnetInfoResponse::decode(&bytes).unwrap(),
+        WasmResult::Reject(msg) => panic!("Unexpected reject: {}", msg),
+    };
+    assert_eq!(
+        response,
+        ic00::SubnetInfoResponse {
+            replica_version: ReplicaVersion::default().to_string(),
+            subnet_type: "application".to_string(),
+            subnet_size: test.subnet_size() as u64,
+            ecdsa_key_ids: vec![ecdsa_key],
+            enabled_features: vec!["http_requests".to_string(), "sev_enabled".to_string()],
+            num_canisters: 1,
+        }
+    );
+}
+
+#[test]
+fn subnet_info_via_ingress_is_rejected() {
+    let own_subnet_id = subnet_test_id(1);
+    let mut test = ExecutionTestBuilder::new()
+        .with_own_subnet_id(own_subnet_id)
+        .build();
+    let args = ic00::SubnetInfoArgs {
+        subnet_id: own_subnet_id.get(),
+    };
+    let err = test
+        .should_accept_ingress_message(IC_00, Method::SubnetInfo, args.encode())
+        .unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterRejectedMessage);
+    assert_eq!(
+        err.description(),
+        "ic00 method subnet_info can be called only by a canister"
+    );
+}
+
+#[test]
+fn subnet_info_reports_number_of_hosted_canisters() {
+    let own_subnet_id = subnet_test_id(1);
+    let mut test = ExecutionTestBuilder::new()
+        .with_own_subnet_id(own_subnet_id)
+        .build();
+    let canister_id = test.universal_canister().unwrap();
+    test.create_canister(Cycles::new(1_000_000_000_000));
+    test.create_canister(Cycles::new(1_000_000_000_000));
+    let args = ic00::SubnetInfoArgs {
+        subnet_id: own_subnet_id.get(),
+    };
+    let run = wasm()
+        .call_simple(
+            ic00::IC_00,
+            Method::SubnetInfo,
+            call_args()
+                .other_side(args.encode())
+                .on_reject(wasm().reject_message().reject()),
+        )
+        .build();
+
+    let result = test.ingress(canister_id, "update", run).unwrap();
+    let response = match result {
+        WasmResult::Reply(bytes) => ic00::SubnetInfoResponse::decode(&bytes).unwrap(),
+        WasmResult::Reject(msg) => panic!("Unexpected reject: {}", msg),
+    };
+    assert_eq!(response.num_canisters, 3);
+}
+
 #[test]
 fn ecdsa_public_key_req_with_unknown_key_rejected() {
     let correct_key = make_key("correct_key");
@@ -3118,40 +3334,6 @@ fn output_requests_on_application_subnets_update_subnet_available_memory_reserve
     assert_correct_request(system_state, canister_id);
 }
 
-#[test]
-fn test_request_snapshot_rejected_because_feature_is_enabled() {
-    let own_subnet = subnet_test_id(1);
-    let caller_canister = canister_test_id(1);
-    let mut test = ExecutionTestBuilder::new()
-        .with_own_subnet_id(own_subnet)
-        .with_manual_execution()
-        .with_snapshots(FlagStatus::Enabled)
-        .with_caller(own_subnet, caller_canister)
-        .build();
-
-    // Inject a take_canister_snapshot request.
-    test.inject_call_to_ic00(
-        Method::TakeCanisterSnapshot,
-        Encode!().unwrap(),
-        Cycles::new(1_000_000_000),
-    );
-
-    test.execute_subnet_message();
-
-    let (receiver, response) = &get_output_messages(test.state_mut()).pop().unwrap();
-    assert_matches!(response, RequestOrResponse::Response(_));
-    if let RequestOrResponse::Response(res) = response {
-        assert_eq!(res.originator, *receiver);
-        assert_eq!(
-            res.response_payload,
-            Payload::Reject(RejectContext::new(
-                RejectCode::CanisterReject,
-                "Canister snapshotting API is not yet implemented."
-            ))
-        );
-    }
-}
-
 #[test]
 fn test_request_snapshot_rejected_because_feature_is_disabled() {
     let own_subnet = subnet_test_id(1);
@@ -3193,81 +3375,1250 @@ fn test_ingress_snapshot_rejected_because_feature_is_disabled() {
     let uni = test.universal_canister().unwrap();
 
     let snapshot_methods = [
-        Method::TakeCanisterSnapshot,
-        Method::LoadCanisterSnapshot,
-        Method::DeleteCanisterSnapshot,
-        Method::ListCanisterSnapshots,
+        (
+            Method::TakeCanisterSnapshot,
+            TakeCanisterSnapshotArgs {
+                canister_id: uni.get(),
+                replace_snapshot: None,
+            }
+            .encode(),
+        ),
+        (
+            Method::DeleteCanisterSnapshot,
+            DeleteCanisterSnapshotArgs {
+                canister_id: uni.get(),
+                snapshot_id: 0,
+            }
+            .encode(),
+        ),
+        (
+            Method::ListCanisterSnapshots,
+            ListCanisterSnapshotArgs {
+                canister_id: uni.get(),
+            }
+            .encode(),
+        ),
     ];
-    for method in snapshot_methods {
+    for (method, args) in snapshot_methods {
         let call = wasm()
             .call_simple(
                 ic00::IC_00,
                 method,
                 call_args()
-                    .other_side(vec![])
+                    .other_side(args)
                     .on_reject(wasm().reject_message().reject()),
             )
             .build();
         let result = test.ingress(uni, "update", call).unwrap();
-        let expected_result = WasmResult::Reject(format!("Unable to route management canister request {}: UserError(UserError {{ code: CanisterRejectedMessage, description: {} }})", method, "\"Snapshotting API is not yet implemented\""));
-        assert_eq!(result, expected_result);
+        assert_eq!(
+            result,
+            WasmResult::Reject("This API is not enabled on this subnet".to_string())
+        );
     }
 }
 
 #[test]
-fn test_canister_settings_log_visibility_default_controllers() {
-    // Arrange.
-    let mut test = ExecutionTestBuilder::new().build();
-    let canister_id = test.create_canister(Cycles::new(1_000_000_000));
-    // Act.
-    let result = test.canister_status(canister_id);
-    let canister_status = CanisterStatusResultV2::decode(&get_reply(result)).unwrap();
-    // Assert.
+fn take_list_and_delete_canister_snapshots() {
+    let mut test = ExecutionTestBuilder::new()
+        .with_snapshots(FlagStatus::Enabled)
+        .build();
+    let canister_id = test.universal_canister().unwrap();
+    let take = |replace_snapshot| {
+        TakeCanisterSnapshotArgs {
+            canister_id: canister_id.get(),
+            replace_snapshot,
+        }
+        .encode()
+    };
+    let list = ListCanisterSnapshotArgs {
+        canister_id: canister_id.get(),
+    }
+    .encode();
+    let delete = |snapshot_id| {
+        DeleteCanisterSnapshotArgs {
+            canister_id: canister_id.get(),
+            snapshot_id,
+        }
+        .encode()
+    };
+
+    let result = test.subnet_message(Method::TakeCanisterSnapshot, take(None));
+    let snapshot = CanisterSnapshotResponse::decode(&get_reply(result)).unwrap();
     assert_eq!(
-        canister_status.settings().log_visibility(),
-        LogVisibility::Controllers
+        snapshot.taken_at_timestamp,
+        test.state().time().as_nanos_since_unix_epoch()
+    );
+    assert_eq!(
+        snapshot.total_size,
+        test.state()
+            .canister_snapshots
+            .memory_usage_by_canister(canister_id)
+            .get()
+    );
+    let result = test.subnet_message(Method::ListCanisterSnapshots, list.clone());
+    assert_eq!(
+        ListCanisterSnapshotsResponse::decode(&get_reply(result)).unwrap(),
+        ListCanisterSnapshotsResponse(vec![snapshot.clone()])
+    );
+
+    // The test configuration allows a single snapshot per canister.
+    let err = test
+        .subnet_message(Method::TakeCanisterSnapshot, take(None))
+        .unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterContractViolation);
+    assert!(err
+        .description()
+        .contains("has reached the maximum number of 1 snapshots"));
+
+    // Replacing the snapshot does not count against the limit.
+    let result = test.subnet_message(Method::TakeCanisterSnapshot, take(Some(snapshot.id)));
+    let new_snapshot = CanisterSnapshotResponse::decode(&get_reply(result)).unwrap();
+    assert_ne!(new_snapshot.id, snapshot.id);
+    let result = test.subnet_message(Method::ListCanisterSnapshots, list.clone());
+    assert_eq!(
+        ListCanisterSnapshotsResponse::decode(&get_reply(result)).unwrap(),
+        ListCanisterSnapshotsResponse(vec![new_snapshot.clone()])
+    );
+
+    let err = test
+        .subnet_message(Method::DeleteCanisterSnapshot, delete(snapshot.id))
+        .unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterContractViolation);
+    assert!(err.description().contains("Could not find the snapshot ID"));
+    let result = test
+        .subnet_message(Method::DeleteCanisterSnapshot, delete(new_snapshot.id))
+        .unwrap();
+    assert_eq!(WasmResult::Reply(EmptyBlob.encode()), result);
+    let result = test.subnet_message(Method::ListCanisterSnapshots, list);
+    assert_eq!(
+        ListCanisterSnapshotsResponse::decode(&get_reply(result)).unwrap(),
+        ListCanisterSnapshotsResponse(vec![])
     );
 }
 
 #[test]
-fn test_canister_settings_log_visibility_create_with_settings() {
-    // Arrange.
-    let mut test = ExecutionTestBuilder::new().build();
-    // Act.
-    let canister_id = test
-        .create_canister_with_settings(
-            Cycles::new(1_000_000_000),
-            ic00::CanisterSettingsArgsBuilder::new()
-                .with_log_visibility(LogVisibility::Public)
-                .build(),
-        )
-        .unwrap();
-    let result = test.canister_status(canister_id);
-    let canister_status = CanisterStatusResultV2::decode(&get_reply(result)).unwrap();
-    // Assert.
+fn take_canister_snapshot_fails_for_canister_without_code() {
+    let mut test = ExecutionTestBuilder::new()
+        .with_snapshots(FlagStatus::Enabled)
+        .build();
+    let canister_id = test.create_canister(Cycles::new(1_000_000_000_000));
+
+    let args = TakeCanisterSnapshotArgs {
+        canister_id: canister_id.get(),
+        replace_snapshot: None,
+    };
+    let err = test
+        .subnet_message(Method::TakeCanisterSnapshot, args.encode())
+        .unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterWasmModuleNotFound);
     assert_eq!(
-        canister_status.settings().log_visibility(),
-        LogVisibility::Public
+        test.state()
+            .canister_snapshots
+            .number_of_snapshots_by_canister(canister_id),
+        0
     );
 }
 
 #[test]
-fn test_canister_settings_log_visibility_set_to_public() {
-    // Arrange.
-    let mut test = ExecutionTestBuilder::new().build();
-    let canister_id = test.create_canister(Cycles::new(1_000_000_000));
-    // Act.
-    test.set_log_visibility(canister_id, LogVisibility::Public)
-        .unwrap();
-    let result = test.canister_status(canister_id);
-    let canister_status = CanisterStatusResultV2::decode(&get_reply(result)).unwrap();
-    // Assert.
+fn take_canister_snapshot_fails_for_non_controller() {
+    let mut test = ExecutionTestBuilder::new()
+        .with_snapshots(FlagStatus::Enabled)
+        .build();
+    let canister_id = test.universal_canister().unwrap();
+
+    test.set_user_id(user_test_id(42));
+    let args = TakeCanisterSnapshotArgs {
+        canister_id: canister_id.get(),
+        replace_snapshot: None,
+    };
+    let err = test
+        .subnet_message(Method::TakeCanisterSnapshot, args.encode())
+        .unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterInvalidController);
+}
+
+#[test]
+fn take_canister_snapshot_fails_above_snapshot_limit() {
+    let mut test = ExecutionTestBuilder::new()
+        .with_snapshots(FlagStatus::Enabled)
+        .build();
+    let canister_id = test.universal_canister().unwrap();
+
+    let args = TakeCanisterSnapshotArgs {
+        canister_id: canister_id.get(),
+        replace_snapshot: None,
+    };
+    for _ in 0..MAX_NUMBER_OF_SNAPSHOTS_PER_CANISTER {
+        test.subnet_message(Method::TakeCanisterSnapshot, args.encode())
+            .unwrap();
+    }
+    let available_memory = test.subnet_available_memory().get_execution_memory();
+    let err = test
+        .subnet_message(Method::TakeCanisterSnapshot, args.encode())
+        .unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterContractViolation);
+    assert!(err.description().contains(&format!(
+        "has reached the maximum number of {} snapshots",
+        MAX_NUMBER_OF_SNAPSHOTS_PER_CANISTER
+    )));
+    assert_eq!(
+        test.state()
+            .canister_snapshots
+            .number_of_snapshots_by_canister(canister_id),
+        MAX_NUMBER_OF_SNAPSHOTS_PER_CANISTER
+    );
+    assert_eq!(
+        test.subnet_available_memory().get_execution_memory(),
+        available_memory
+    );
+}
+
+#[test]
+fn take_canister_snapshot_replaces_only_snapshots_of_the_canister() {
+    let mut test = ExecutionTestBuilder::new()
+        .with_snapshots(FlagStatus::Enabled)
+        .build();
+    let canister_id = test.universal_canister().unwrap();
+    let other_canister_id = test.universal_canister().unwrap();
+    let take = |canister_id: CanisterId, replace_snapshot| {
+        TakeCanisterSnapshotArgs {
+            canister_id: canister_id.get(),
+            replace_snapshot,
+        }
+        .encode()
+    };
+
+    let result = test.subnet_message(Method::TakeCanisterSnapshot, take(other_canister_id, None));
+    let other_snapshot = CanisterSnapshotResponse::decode(&get_reply(result)).unwrap();
+
+    // Neither the snapshot of another canister nor an unknown snapshot can be
+    // replaced, and no new snapshot is taken in that case.
+    for replace_snapshot in [other_snapshot.id, other_snapshot.id + 1] {
+        let err = test
+            .subnet_message(
+                Method::TakeCanisterSnapshot,
+                take(canister_id, Some(replace_snapshot)),
+            )
+            .unwrap_err();
+        assert_eq!(err.code(), ErrorCode::CanisterContractViolation);
+        assert!(err.description().contains("Could not find the snapshot ID"));
+    }
+    assert_eq!(
+        test.state()
+            .canister_snapshots
+            .number_of_snapshots_by_canister(canister_id),
+        0
+    );
+    assert_eq!(
+        test.state()
+            .canister_snapshots
+            .number_of_snapshots_by_canister(other_canister_id),
+        1
+    );
+
+    // The replaced snapshot is deleted once the new one is taken.
+    let result = test.subnet_message(
+        Method::TakeCanisterSnapshot,
+        take(other_canister_id, Some(other_snapshot.id)),
+    );
+    let new_snapshot = CanisterSnapshotResponse::decode(&get_reply(result)).unwrap();
+    let snapshot_ids: Vec<_> = test
+        .state()
+        .canister_snapshots
+        .list_snapshots(other_canister_id)
+        .map(|(snapshot_id, _)| snapshot_id.get())
+        .collect();
+    assert_eq!(snapshot_ids, vec![new_snapshot.id]);
+}
+
+#[test]
+fn canister_snapshots_reserve_and_release_subnet_memory() {
+    let mut test = ExecutionTestBuilder::new()
+        .with_snapshots(FlagStatus::Enabled)
+        .build();
+    let canister_id = test.universal_canister().unwrap();
+    let initial_memory = test.subnet_available_memory().get_execution_memory();
+
+    let args = TakeCanisterSnapshotArgs {
+        canister_id: canister_id.get(),
+        replace_snapshot: None,
+    };
+    let result = test.subnet_message(Method::TakeCanisterSnapshot, args.encode());
+    let snapshot = CanisterSnapshotResponse::decode(&get_reply(result)).unwrap();
+    assert!(snapshot.total_size > 0);
+    assert_eq!(
+        test.subnet_available_memory().get_execution_memory(),
+        initial_memory - snapshot.total_size as i64
+    );
+
+    // Replacing a snapshot releases the memory of the replaced one.
+    let args = TakeCanisterSnapshotArgs {
+        canister_id: canister_id.get(),
+        replace_snapshot: Some(snapshot.id),
+    };
+    let result = test.subnet_message(Method::TakeCanisterSnapshot, args.encode());
+    let new_snapshot = CanisterSnapshotResponse::decode(&get_reply(result)).unwrap();
+    assert_eq!(
+        test.subnet_available_memory().get_execution_memory(),
+        initial_memory - new_snapshot.total_size as i64
+    );
+
+    let args = DeleteCanisterSnapshotArgs {
+        canister_id: canister_id.get(),
+        snapshot_id: new_snapshot.id,
+    };
+    test.subnet_message(Method::DeleteCanisterSnapshot, args.encode())
+        .unwrap();
+    assert_eq!(
+        test.subnet_available_memory().get_execution_memory(),
+        initial_memory
+    );
+}
+
+const SNAPSHOT_ROUND_TRIP_WAT: &str = r#"(module
+    (import "ic0" "stable_grow" (func $stable_grow (param i32) (result i32)))
+    (import "ic0" "stable_write" (func $stable_write (param i32 i32 i32)))
+    (import "ic0" "certified_data_set" (func $certified_data_set (param i32 i32)))
+    (func $init
+        (drop (call $stable_grow (i32.const 2)))
+        (call $stable_write (i32.const 100) (i32.const 0) (i32.const 11))
+        (call $stable_write (i32.const 70000) (i32.const 70000) (i32.const 11))
+        (call $certified_data_set (i32.const 0) (i32.const 11))
+        (global.set $counter (i64.const 42))
+    )
+    (memory 2)
+    (data (i32.const 0) "hello world")
+    (data (i32.const 70000) "second page")
+    (global $counter (export "counter") (mut i64) (i64.const 0))
+    (export "canister_init" (func $init))
+)"#;
+
+#[test]
+fn canister_snapshot_round_trip_through_download_and_upload() {
+    // Pages that do not align with Wasm or OS pages.
+    const PAGE_SIZE: u64 = 50_000;
+
+    let mut test = ExecutionTestBuilder::new()
+        .with_snapshots(FlagStatus::Enabled)
+        .with_snapshot_data_transfer(FlagStatus::Enabled)
+        .with_wasm_chunk_store(FlagStatus::Enabled)
+        .build();
+    let canister_id = test.canister_from_wat(SNAPSHOT_ROUND_TRIP_WAT).unwrap();
+    let other_canister_id = test.create_canister(Cycles::new(1_000_000_000_000));
+    let args = UploadChunkArgs {
+        canister_id: canister_id.get(),
+        chunk: vec![8; 10],
+    };
+    test.subnet_message(Method::UploadChunk, args.encode())
+        .unwrap();
+
+    // Take a snapshot and download it page by page.
+    let args = TakeCanisterSnapshotArgs {
+        canister_id: canister_id.get(),
+        replace_snapshot: None,
+    };
+    let result = test.subnet_message(Method::TakeCanisterSnapshot, args.encode());
+    let snapshot_id = CanisterSnapshotResponse::decode(&get_reply(result))
+        .unwrap()
+        .id;
+    let args = ReadCanisterSnapshotMetadataArgs {
+        canister_id: canister_id.get(),
+        snapshot_id,
+    };
+    let result = test.subnet_message(Method::ReadCanisterSnapshotMetadata, args.encode());
+    let metadata = ReadCanisterSnapshotMetadataResponse::decode(&get_reply(result)).unwrap();
+
+    let mut read = |kind| {
+        let args = ReadCanisterSnapshotDataArgs {
+            canister_id: canister_id.get(),
+            snapshot_id,
+            kind,
+        };
+        let result = test.subnet_message(Method::ReadCanisterSnapshotData, args.encode());
+        ReadCanisterSnapshotDataResponse::decode(&get_reply(result))
+            .unwrap()
+            .chunk
+    };
+    let mut read_pages = |total_size: u64, kind: fn(u64, u64) -> CanisterSnapshotDataKind| {
+        let mut bytes = vec![];
+        while (bytes.len() as u64) < total_size {
+            let offset = bytes.len() as u64;
+            bytes.extend(read(kind(offset, PAGE_SIZE.min(total_size - offset))));
+        }
+        bytes
+    };
+    let wasm_module = read_pages(metadata.wasm_module_size, |offset, size| {
+        CanisterSnapshotDataKind::WasmModule { offset, size }
+    });
+    let wasm_memory = read_pages(metadata.wasm_memory_size, |offset, size| {
+        CanisterSnapshotDataKind::MainMemory { offset, size }
+    });
+    let stable_memory = read_pages(metadata.stable_memory_size, |offset, size| {
+        CanisterSnapshotDataKind::StableMemory { offset, size }
+    });
+    let wasm_chunks: Vec<_> = metadata
+        .wasm_chunk_store
+        .iter()
+        .map(|chunk_hash| {
+            read(CanisterSnapshotDataKind::WasmChunk {
+                hash: chunk_hash.hash.clone(),
+            })
+        })
+        .collect();
+    assert_eq!(wasm_chunks, vec![vec![8; 10]]);
+
+    // Upload the downloaded snapshot to another canister and load it there.
+    let args = UploadCanisterSnapshotMetadataArgs {
+        canister_id: other_canister_id.get(),
+        wasm_module_size: wasm_module.len() as u64,
+        wasm_module_hash: ic_crypto_sha2::Sha256::hash(&wasm_module).to_vec(),
+        exported_globals: metadata.exported_globals.clone(),
+        wasm_memory_size: metadata.wasm_memory_size,
+        stable_memory_size: metadata.stable_memory_size,
+        certified_data: metadata.certified_data.clone(),
+    };
+    let result = test.subnet_message(Method::UploadCanisterSnapshotMetadata, args.encode());
+    let uploaded_snapshot_id = UploadCanisterSnapshotMetadataResponse::decode(&get_reply(result))
+        .unwrap()
+        .snapshot_id;
+    let mut uploads = vec![];
+    for (bytes, kind) in [
+        (
+            &wasm_module,
+            (|offset| CanisterSnapshotDataOffset::WasmModule { offset })
+                as fn(u64) -> CanisterSnapshotDataOffset,
+        ),
+        (&wasm_memory, |offset| {
+            CanisterSnapshotDataOffset::MainMemory { offset }
+        }),
+        (&stable_memory, |offset| {
+            CanisterSnapshotDataOffset::StableMemory { offset }
+        }),
+    ] {
+        for (i, chunk) in bytes.chunks(PAGE_SIZE as usize).enumerate() {
+            uploads.push((kind(i as u64 * PAGE_SIZE), chunk.to_vec()));
+        }
+    }
+    for chunk in wasm_chunks {
+        uploads.push((CanisterSnapshotDataOffset::WasmChunk, chunk));
+    }
+    for (kind, chunk) in uploads {
+        let args = UploadCanisterSnapshotDataArgs {
+            canister_id: other_canister_id.get(),
+            snapshot_id: uploaded_snapshot_id,
+            kind,
+            chunk,
+        };
+        test.subnet_message(Method::UploadCanisterSnapshotData, args.encode())
+            .unwrap();
+    }
+    let args = LoadCanisterSnapshotArgs {
+        canister_id: other_canister_id.get(),
+        snapshot_id: uploaded_snapshot_id,
+        sender_canister_version: None,
+    };
+    test.subnet_message(Method::LoadCanisterSnapshot, args.encode())
+        .unwrap();
+
+    let memory_bytes = |memory: &Memory| {
+        let mut bytes = vec![0; memory.size.get() * WASM_PAGE_SIZE_IN_BYTES];
+        Buffer::new(memory.page_map.clone()).read(&mut bytes, 0);
+        bytes
+    };
+    let original = test.canister_state(canister_id);
+    let restored = test.canister_state(other_canister_id);
+    let original_execution_state = original.execution_state.as_ref().unwrap();
+    let restored_execution_state = restored.execution_state.as_ref().unwrap();
+    assert_eq!(
+        restored_execution_state.wasm_binary.binary.as_slice(),
+        original_execution_state.wasm_binary.binary.as_slice()
+    );
+    let restored_wasm_memory = memory_bytes(&restored_execution_state.wasm_memory);
+    assert_eq!(&restored_wasm_memory[..11], b"hello world");
+    assert_eq!(&restored_wasm_memory[70000..70011], b"second page");
+    assert_eq!(
+        restored_wasm_memory,
+        memory_bytes(&original_execution_state.wasm_memory)
+    );
+    let restored_stable_memory = memory_bytes(&restored_execution_state.stable_memory);
+    assert_eq!(&restored_stable_memory[100..111], b"hello world");
+    assert_eq!(&restored_stable_memory[70000..70011], b"second page");
+    assert_eq!(
+        restored_stable_memory,
+        memory_bytes(&original_execution_state.stable_memory)
+    );
+    assert_eq!(
+        restored_execution_state.exported_globals,
+        original_execution_state.exported_globals
+    );
+    assert_eq!(
+        restored.system_state.certified_data,
+        original.system_state.certified_data
+    );
+    assert_eq!(
+        restored.system_state.certified_data,
+        b"hello world".to_vec()
+    );
+    assert_eq!(
+        restored
+            .system_state
+            .wasm_chunk_store
+            .keys()
+            .collect::<Vec<_>>(),
+        original
+            .system_state
+            .wasm_chunk_store
+            .keys()
+            .collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn upload_and_read_canister_snapshot() {
+    let mut test = ExecutionTestBuilder::new()
+        .with_snapshot_data_transfer(FlagStatus::Enabled)
+        .build();
+    let canister_id = test.create_canister(Cycles::new(1_000_000_000_000));
+
+    let args = UploadCanisterSnapshotMetadataArgs {
+        canister_id: canister_id.get(),
+        wasm_module_size: 4,
+        wasm_module_hash: ic_crypto_sha2::Sha256::hash(b"\0asm").to_vec(),
+        exported_globals: vec![SnapshotGlobal::I32(5), SnapshotGlobal::F64(1.5)],
+        wasm_memory_size: WASM_PAGE_SIZE_IN_BYTES as u64,
+        stable_memory_size: 0,
+        certified_data: vec![1, 2, 3],
+    };
+    let result = test.subnet_message(Method::UploadCanisterSnapshotMetadata, args.encode());
+    let snapshot_id = UploadCanisterSnapshotMetadataResponse::decode(&get_reply(result))
+        .unwrap()
+        .snapshot_id;
+
+    let uploads = [
+        (
+            CanisterSnapshotDataOffset::WasmModule { offset: 0 },
+            b"\0asm".to_vec(),
+        ),
+        (
+            CanisterSnapshotDataOffset::MainMemory { offset: 100 },
+            vec![7; 10],
+        ),
+        (CanisterSnapshotDataOffset::WasmChunk, vec![8; 10]),
+    ];
+    for (kind, chunk) in uploads {
+        let args = UploadCanisterSnapshotDataArgs {
+            canister_id: canister_id.get(),
+            snapshot_id,
+            kind,
+            chunk,
+        };
+        let result = test
+            .subnet_message(Method::UploadCanisterSnapshotData, args.encode())
+            .unwrap();
+        assert_eq!(WasmResult::Reply(EmptyBlob.encode()), result);
+    }
+
+    let args = ReadCanisterSnapshotMetadataArgs {
+        canister_id: canister_id.get(),
+        snapshot_id,
+    };
+    let result = test.subnet_message(Method::ReadCanisterSnapshotMetadata, args.encode());
+    let metadata = ReadCanisterSnapshotMetadataResponse::decode(&get_reply(result)).unwrap();
+    let chunk_hash = ic_crypto_sha2::Sha256::hash(&[8; 10]).to_vec();
+    assert_eq!(metadata.wasm_module_size, 4);
+    assert_eq!(
+        metadata.exported_globals,
+        vec![SnapshotGlobal::I32(5), SnapshotGlobal::F64(1.5)]
+    );
+    assert_eq!(metadata.wasm_memory_size, WASM_PAGE_SIZE_IN_BYTES as u64);
+    assert_eq!(metadata.stable_memory_size, 0);
+    assert_eq!(metadata.certified_data, vec![1, 2, 3]);
+    assert_eq!(
+        metadata.wasm_chunk_store,
+        vec![ChunkHash {
+            hash: chunk_hash.clone()
+        }]
+    );
+
+    let reads = [
+        (
+            CanisterSnapshotDataKind::WasmModule { offset: 0, size: 4 },
+            b"\0asm".to_vec(),
+        ),
+        (
+            CanisterSnapshotDataKind::MainMemory {
+                offset: 98,
+                size: 14,
+            },
+            [vec![0; 2], vec![7; 10], vec![0; 2]].concat(),
+        ),
+        (
+            CanisterSnapshotDataKind::WasmChunk { hash: chunk_hash },
+            vec![8; 10],
+        ),
+    ];
+    for (kind, expected) in reads {
+        let args = ReadCanisterSnapshotDataArgs {
+            canister_id: canister_id.get(),
+            snapshot_id,
+            kind,
+        };
+        let result = test.subnet_message(Method::ReadCanisterSnapshotData, args.encode());
+        let response = ReadCanisterSnapshotDataResponse::decode(&get_reply(result)).unwrap();
+        assert_eq!(response.chunk, expected);
+    }
+}
+
+#[test]
+fn read_canister_snapshot_data_fails_out_of_bounds() {
+    let mut test = ExecutionTestBuilder::new()
+        .with_snapshot_data_transfer(FlagStatus::Enabled)
+        .build();
+    let canister_id = test.create_canister(Cycles::new(1_000_000_000_000));
+
+    let args = UploadCanisterSnapshotMetadataArgs {
+        canister_id: canister_id.get(),
+        wasm_module_size: 4,
+        wasm_module_hash: ic_crypto_sha2::Sha256::hash(b"\0asm").to_vec(),
+        exported_globals: vec![],
+        wasm_memory_size: 0,
+        stable_memory_size: 0,
+        certified_data: vec![],
+    };
+    let result = test.subnet_message(Method::UploadCanisterSnapshotMetadata, args.encode());
+    let snapshot_id = UploadCanisterSnapshotMetadataResponse::decode(&get_reply(result))
+        .unwrap()
+        .snapshot_id;
+
+    let args = ReadCanisterSnapshotDataArgs {
+        canister_id: canister_id.get(),
+        snapshot_id,
+        kind: CanisterSnapshotDataKind::WasmModule { offset: 2, size: 4 },
+    };
+    let err = test
+        .subnet_message(Method::ReadCanisterSnapshotData, args.encode())
+        .unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterContractViolation);
+
+    let args = UploadCanisterSnapshotDataArgs {
+        canister_id: canister_id.get(),
+        snapshot_id,
+        kind: CanisterSnapshotDataOffset::StableMemory { offset: 0 },
+        chunk: vec![1],
+    };
+    let err = test
+        .subnet_message(Method::UploadCanisterSnapshotData, args.encode())
+        .unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterContractViolation);
+}
+
+#[test]
+fn read_canister_snapshot_metadata_fails_for_other_canister() {
+    let mut test = ExecutionTestBuilder::new()
+        .with_snapshot_data_transfer(FlagStatus::Enabled)
+        .build();
+    let canister_id = test.create_canister(Cycles::new(1_000_000_000_000));
+    let other_canister_id = test.create_canister(Cycles::new(1_000_000_000_000));
+
+    let args = UploadCanisterSnapshotMetadataArgs {
+        canister_id: canister_id.get(),
+        wasm_module_size: 0,
+        wasm_module_hash: vec![0; 32],
+        exported_globals: vec![],
+        wasm_memory_size: 0,
+        stable_memory_size: 0,
+        certified_data: vec![],
+    };
+    let result = test.subnet_message(Method::UploadCanisterSnapshotMetadata, args.encode());
+    let snapshot_id = UploadCanisterSnapshotMetadataResponse::decode(&get_reply(result))
+        .unwrap()
+        .snapshot_id;
+
+    let args = ReadCanisterSnapshotMetadataArgs {
+        canister_id: other_canister_id.get(),
+        snapshot_id,
+    };
+    let err = test
+        .subnet_message(Method::ReadCanisterSnapshotMetadata, args.encode())
+        .unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterContractViolation);
+    assert!(err.description().contains("Could not find the snapshot ID"));
+}
+
+#[test]
+fn canister_snapshot_data_transfer_rejected_because_feature_is_disabled() {
+    let mut test = ExecutionTestBuilder::new()
+        .with_snapshots(FlagStatus::Enabled)
+        .build();
+    let canister_id = test.create_canister(Cycles::new(1_000_000_000_000));
+
+    let args = ReadCanisterSnapshotMetadataArgs {
+        canister_id: canister_id.get(),
+        snapshot_id: 0,
+    };
+    let err = test
+        .subnet_message(Method::ReadCanisterSnapshotMetadata, args.encode())
+        .unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterContractViolation);
+    assert_eq!(err.description(), "This API is not enabled on this subnet");
+}
+
+#[test]
+fn upload_canister_snapshot_fails_above_snapshot_limit() {
+    let mut test = ExecutionTestBuilder::new()
+        .with_snapshot_data_transfer(FlagStatus::Enabled)
+        .build();
+    let canister_id = test.create_canister(Cycles::new(1_000_000_000_000));
+
+    let args = UploadCanisterSnapshotMetadataArgs {
+        canister_id: canister_id.get(),
+        wasm_module_size: 0,
+        wasm_module_hash: vec![0; 32],
+        exported_globals: vec![],
+        wasm_memory_size: 0,
+        stable_memory_size: 0,
+        certified_data: vec![],
+    };
+    for _ in 0..MAX_NUMBER_OF_SNAPSHOTS_PER_CANISTER {
+        test.subnet_message(Method::UploadCanisterSnapshotMetadata, args.encode())
+            .unwrap();
+    }
+    let err = test
+        .subnet_message(Method::UploadCanisterSnapshotMetadata, args.encode())
+        .unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterContractViolation);
+    assert!(err
+        .description()
+        .contains("has reached the maximum number of 1 snapshots"));
+}
+
+#[test]
+fn delete_partially_uploaded_canister_snapshot_releases_reserved_memory() {
+    let mut test = ExecutionTestBuilder::new()
+        .with_snapshots(FlagStatus::Enabled)
+        .with_snapshot_data_transfer(FlagStatus::Enabled)
+        .build();
+    let canister_id = test.create_canister(Cycles::new(1_000_000_000_000));
+    let initial_memory = test.subnet_available_memory().get_execution_memory();
+
+    let args = UploadCanisterSnapshotMetadataArgs {
+        canister_id: canister_id.get(),
+        wasm_module_size: 0,
+        wasm_module_hash: vec![0; 32],
+        exported_globals: vec![],
+        wasm_memory_size: 2 * WASM_PAGE_SIZE_IN_BYTES as u64,
+        stable_memory_size: WASM_PAGE_SIZE_IN_BYTES as u64,
+        certified_data: vec![1; 10],
+    };
+    let result = test.subnet_message(Method::UploadCanisterSnapshotMetadata, args.encode());
+    let snapshot_id = UploadCanisterSnapshotMetadataResponse::decode(&get_reply(result))
+        .unwrap()
+        .snapshot_id;
+    let reserved_memory = 3 * WASM_PAGE_SIZE_IN_BYTES as i64 + 10;
+    assert_eq!(
+        test.subnet_available_memory().get_execution_memory(),
+        initial_memory - reserved_memory
+    );
+
+    // Only part of the main memory is uploaded, so the snapshot uses less
+    // memory than was reserved for it.
+    let args = UploadCanisterSnapshotDataArgs {
+        canister_id: canister_id.get(),
+        snapshot_id,
+        kind: CanisterSnapshotDataOffset::MainMemory { offset: 0 },
+        chunk: vec![1; 100],
+    };
+    test.subnet_message(Method::UploadCanisterSnapshotData, args.encode())
+        .unwrap();
+    assert!(
+        test.state()
+            .canister_snapshots
+            .memory_usage_by_canister(canister_id)
+            .get()
+            < reserved_memory as u64
+    );
+
+    let args = DeleteCanisterSnapshotArgs {
+        canister_id: canister_id.get(),
+        snapshot_id,
+    };
+    test.subnet_message(Method::DeleteCanisterSnapshot, args.encode())
+        .unwrap();
+    assert_eq!(
+        test.subnet_available_memory().get_execution_memory(),
+        initial_memory
+    );
+}
+
+#[test]
+fn upload_canister_snapshot_rejects_wasm_module_with_wrong_hash() {
+    let mut test = ExecutionTestBuilder::new()
+        .with_snapshot_data_transfer(FlagStatus::Enabled)
+        .build();
+    let canister_id = test.create_canister(Cycles::new(1_000_000_000_000));
+
+    let args = UploadCanisterSnapshotMetadataArgs {
+        canister_id: canister_id.get(),
+        wasm_module_size: 4,
+        wasm_module_hash: ic_crypto_sha2::Sha256::hash(b"\0asm").to_vec(),
+        exported_globals: vec![],
+        wasm_memory_size: 0,
+        stable_memory_size: 0,
+        certified_data: vec![],
+    };
+    let result = test.subnet_message(Method::UploadCanisterSnapshotMetadata, args.encode());
+    let snapshot_id = UploadCanisterSnapshotMetadataResponse::decode(&get_reply(result))
+        .unwrap()
+        .snapshot_id;
+
+    let upload = |offset, chunk: &[u8]| UploadCanisterSnapshotDataArgs {
+        canister_id: canister_id.get(),
+        snapshot_id,
+        kind: CanisterSnapshotDataOffset::WasmModule { offset },
+        chunk: chunk.to_vec(),
+    };
+    // Chunks of the Wasm module must be uploaded in order.
+    let err = test
+        .subnet_message(
+            Method::UploadCanisterSnapshotData,
+            upload(2, b"sm").encode(),
+        )
+        .unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterContractViolation);
+
+    let err = test
+        .subnet_message(
+            Method::UploadCanisterSnapshotData,
+            upload(0, b"wasm").encode(),
+        )
+        .unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterContractViolation);
+    assert!(err
+        .description()
+        .contains("does not match the expected module hash"));
+
+    // The upload starts over after a hash mismatch.
+    test.subnet_message(
+        Method::UploadCanisterSnapshotData,
+        upload(0, b"\0asm").encode(),
+    )
+    .unwrap();
+}
+
+#[test]
+fn load_canister_snapshot_replaces_code_and_records_history() {
+    let mut test = ExecutionTestBuilder::new()
+        .with_snapshots(FlagStatus::Enabled)
+        .with_snapshot_data_transfer(FlagStatus::Enabled)
+        .build();
+    let canister_id = test.create_canister(Cycles::new(1_000_000_000_000));
+    let wasm_module = wat::parse_str("(module (memory 1))").unwrap();
+
+    let args = UploadCanisterSnapshotMetadataArgs {
+        canister_id: canister_id.get(),
+        wasm_module_size: wasm_module.len() as u64,
+        wasm_module_hash: ic_crypto_sha2::Sha256::hash(&wasm_module).to_vec(),
+        exported_globals: vec![],
+        wasm_memory_size: WASM_PAGE_SIZE_IN_BYTES as u64,
+        stable_memory_size: 0,
+        certified_data: vec![1, 2, 3],
+    };
+    let result = test.subnet_message(Method::UploadCanisterSnapshotMetadata, args.encode());
+    let snapshot_id = UploadCanisterSnapshotMetadataResponse::decode(&get_reply(result))
+        .unwrap()
+        .snapshot_id;
+
+    let args = LoadCanisterSnapshotArgs {
+        canister_id: canister_id.get(),
+        snapshot_id,
+        sender_canister_version: None,
+    };
+    // A snapshot can only be loaded once its Wasm module is complete.
+    let err = test
+        .subnet_message(Method::LoadCanisterSnapshot, args.encode())
+        .unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterContractViolation);
+    assert!(err.description().contains("has no complete Wasm module"));
+
+    let upload = UploadCanisterSnapshotDataArgs {
+        canister_id: canister_id.get(),
+        snapshot_id,
+        kind: CanisterSnapshotDataOffset::WasmModule { offset: 0 },
+        chunk: wasm_module.clone(),
+    };
+    test.subnet_message(Method::UploadCanisterSnapshotData, upload.encode())
+        .unwrap();
+    let metadata_args = ReadCanisterSnapshotMetadataArgs {
+        canister_id: canister_id.get(),
+        snapshot_id,
+    };
+    let result = test.subnet_message(Method::ReadCanisterSnapshotMetadata, metadata_args.encode());
+    let metadata = ReadCanisterSnapshotMetadataResponse::decode(&get_reply(result)).unwrap();
+
+    let version_before_load = test
+        .canister_state(canister_id)
+        .system_state
+        .canister_version;
+    let result = test
+        .subnet_message(Method::LoadCanisterSnapshot, args.encode())
+        .unwrap();
+    assert_eq!(WasmResult::Reply(EmptyBlob.encode()), result);
+
+    let canister = test.canister_state(canister_id);
+    let execution_state = canister.execution_state.as_ref().unwrap();
+    assert_eq!(
+        execution_state.wasm_binary.binary.as_slice(),
+        &wasm_module[..]
+    );
+    assert_eq!(execution_state.wasm_memory.size.get(), 1);
+    assert_eq!(canister.system_state.certified_data, vec![1, 2, 3]);
+    assert_eq!(
+        canister.system_state.canister_version,
+        version_before_load + 1
+    );
+    let history = canister.system_state.get_canister_history();
+    assert_eq!(
+        **history.get_changes(1).next().unwrap(),
+        CanisterChange::new(
+            test.state().time().as_nanos_since_unix_epoch(),
+            version_before_load + 1,
+            CanisterChangeOrigin::from_user(test.user_id().get()),
+            CanisterChangeDetails::load_snapshot(
+                metadata.canister_version,
+                snapshot_id,
+                metadata.taken_at_timestamp,
+            ),
+        )
+    );
+}
+
+#[test]
+fn load_canister_snapshot_fails_for_other_canister() {
+    let mut test = ExecutionTestBuilder::new()
+        .with_snapshots(FlagStatus::Enabled)
+        .with_snapshot_data_transfer(FlagStatus::Enabled)
+        .build();
+    let canister_id = test.create_canister(Cycles::new(1_000_000_000_000));
+    let other_canister_id = test.create_canister(Cycles::new(1_000_000_000_000));
+
+    let args = UploadCanisterSnapshotMetadataArgs {
+        canister_id: canister_id.get(),
+        wasm_module_size: 0,
+        wasm_module_hash: vec![0; 32],
+        exported_globals: vec![],
+        wasm_memory_size: 0,
+        stable_memory_size: 0,
+        certified_data: vec![],
+    };
+    let result = test.subnet_message(Method::UploadCanisterSnapshotMetadata, args.encode());
+    let snapshot_id = UploadCanisterSnapshotMetadataResponse::decode(&get_reply(result))
+        .unwrap()
+        .snapshot_id;
+
+    let args = LoadCanisterSnapshotArgs {
+        canister_id: other_canister_id.get(),
+        snapshot_id,
+        sender_canister_version: None,
+    };
+    let err = test
+        .subnet_message(Method::LoadCanisterSnapshot, args.encode())
+        .unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterContractViolation);
+    assert!(err.description().contains("Could not find the snapshot ID"));
+    // A failed load leaves only the creation in the canister history.
+    assert_eq!(
+        test.canister_state(other_canister_id)
+            .system_state
+            .get_canister_history()
+            .get_total_num_changes(),
+        1
+    );
+}
+
+/// Uploads a snapshot of `canister_id` with the given Wasm module and Wasm memory size, and
+/// returns its ID.
+fn upload_snapshot_with_wasm_module(
+    test: &mut ExecutionTest,
+    canister_id: CanisterId,
+    wasm_module: &[u8],
+    wasm_memory_size: u64,
+    exported_globals: Vec<SnapshotGlobal>,
+) -> u64 {
+    let args = UploadCanisterSnapshotMetadataArgs {
+        canister_id: canister_id.get(),
+        wasm_module_size: wasm_module.len() as u64,
+        wasm_module_hash: ic_crypto_sha2::Sha256::hash(wasm_module).to_vec(),
+        exported_globals,
+        wasm_memory_size,
+        stable_memory_size: 0,
+        certified_data: vec![],
+    };
+    let result = test.subnet_message(Method::UploadCanisterSnapshotMetadata, args.encode());
+    let snapshot_id = UploadCanisterSnapshotMetadataResponse::decode(&get_reply(result))
+        .unwrap()
+        .snapshot_id;
+    let upload = UploadCanisterSnapshotDataArgs {
+        canister_id: canister_id.get(),
+        snapshot_id,
+        kind: CanisterSnapshotDataOffset::WasmModule { offset: 0 },
+        chunk: wasm_module.to_vec(),
+    };
+    test.subnet_message(Method::UploadCanisterSnapshotData, upload.encode())
+        .unwrap();
+    snapshot_id
+}
+
+#[test]
+fn load_canister_snapshot_charges_for_compilation() {
+    let mut test = ExecutionTestBuilder::new()
+        .with_snapshots(FlagStatus::Enabled)
+        .with_snapshot_data_transfer(FlagStatus::Enabled)
+        .build();
+    let canister_id = test.create_canister(Cycles::new(1_000_000_000_000));
+    let wasm_module = wat::parse_str("(module (memory 1))").unwrap();
+    // The base fee of a message that executes no instructions.
+    let base_fee = test
+        .cycles_account_manager()
+        .execution_cost(NumInstructions::from(0), test.subnet_size());
+
+    let snapshot_id = upload_snapshot_with_wasm_module(
+        &mut test,
+        canister_id,
+        &wasm_module,
+        WASM_PAGE_SIZE_IN_BYTES as u64,
+        vec![],
+    );
+    let balance_before = test.canister_state(canister_id).system_state.balance();
+    let args = LoadCanisterSnapshotArgs {
+        canister_id: canister_id.get(),
+        snapshot_id,
+        sender_canister_version: None,
+    };
+    test.subnet_message(Method::LoadCanisterSnapshot, args.encode())
+        .unwrap();
+    let canister = test.canister_state(canister_id);
+    assert_eq!(canister.system_state.reserved_balance(), Cycles::new(0));
+    assert!(
+        balance_before - canister.system_state.balance() > base_fee,
+        "Loading the snapshot cost {}, which is not more than the base fee {}",
+        balance_before - canister.system_state.balance(),
+        base_fee
+    );
+
+    // The compilation is paid for even if loading fails afterwards.
+    let snapshot_id = upload_snapshot_with_wasm_module(
+        &mut test,
+        canister_id,
+        &wasm_module,
+        WASM_PAGE_SIZE_IN_BYTES as u64,
+        vec![SnapshotGlobal::I32(1)],
+    );
+    let balance_before = test.canister_state(canister_id).system_state.balance();
+    let args = LoadCanisterSnapshotArgs {
+        canister_id: canister_id.get(),
+        snapshot_id,
+        sender_canister_version: None,
+    };
+    let err = test
+        .subnet_message(Method::LoadCanisterSnapshot, args.encode())
+        .unwrap_err();
+    assert!(err.description().contains("exported globals"), "{}", err);
+    assert!(balance_before - test.canister_state(canister_id).system_state.balance() > base_fee);
+}
+
+#[test]
+fn load_canister_snapshot_reserves_cycles_for_memory_growth() {
+    const CYCLES: Cycles = Cycles::new(1_000_000_000_000_000);
+    const CAPACITY: u64 = 20_000_000_000;
+    const THRESHOLD: u64 = CAPACITY / 2;
+    const WASM_MEMORY_PAGES: u64 = 160;
+
+    let mut test = ExecutionTestBuilder::new()
+        .with_snapshots(FlagStatus::Enabled)
+        .with_snapshot_data_transfer(FlagStatus::Enabled)
+        .with_subnet_execution_memory(CAPACITY as i64)
+        .with_subnet_memory_reservation(0)
+        .with_subnet_memory_threshold(THRESHOLD as i64)
+        .build();
+
+    // Use up the subnet memory below the threshold, so that memory growth reserves cycles.
+    test.create_canister_with_allocation(CYCLES, None, Some(THRESHOLD))
+        .unwrap();
+
+    let wasm_module = wat::parse_str(format!("(module (memory {}))", WASM_MEMORY_PAGES)).unwrap();
+    let wasm_memory_size = WASM_MEMORY_PAGES * WASM_PAGE_SIZE_IN_BYTES as u64;
+    let create_canister = |test: &mut ExecutionTest, reserved_cycles_limit: Cycles| {
+        test.create_canister_with_settings(
+            CYCLES,
+            ic00::CanisterSettingsArgsBuilder::new()
+                .with_reserved_cycles_limit(reserved_cycles_limit.get())
+                .build(),
+        )
+        .unwrap()
+    };
+
+    let canister_id = create_canister(&mut test, CYCLES);
+    let snapshot_id = upload_snapshot_with_wasm_module(
+        &mut test,
+        canister_id,
+        &wasm_module,
+        wasm_memory_size,
+        vec![],
+    );
+    let subnet_memory_usage =
+        CAPACITY - test.subnet_available_memory().get_execution_memory() as u64;
+    let memory_usage_before = test.canister_state(canister_id).memory_usage();
+    let args = LoadCanisterSnapshotArgs {
+        canister_id: canister_id.get(),
+        snapshot_id,
+        sender_canister_version: None,
+    };
+    test.subnet_message(Method::LoadCanisterSnapshot, args.encode())
+        .unwrap();
+    let memory_usage_after = test.canister_state(canister_id).memory_usage();
+    assert!(memory_usage_after - memory_usage_before >= NumBytes::from(wasm_memory_size));
+
+    let reserved_cycles = test
+        .canister_state(canister_id)
+        .system_state
+        .reserved_balance();
+    assert!(reserved_cycles > Cycles::new(0));
+    assert_eq!(
+        reserved_cycles,
+        test.cycles_account_manager().storage_reservation_cycles(
+            memory_usage_after - memory_usage_before,
+            &ResourceSaturation::new(subnet_memory_usage, THRESHOLD, CAPACITY),
+            test.subnet_size(),
+        )
+    );
+
+    // Loading fails if the cycles cannot be reserved.
+    let canister_id = create_canister(&mut test, Cycles::new(1));
+    let snapshot_id = upload_snapshot_with_wasm_module(
+        &mut test,
+        canister_id,
+        &wasm_module,
+        wasm_memory_size,
+        vec![],
+    );
+    let args = LoadCanisterSnapshotArgs {
+        canister_id: canister_id.get(),
+        snapshot_id,
+        sender_canister_version: None,
+    };
+    let err = test
+        .subnet_message(Method::LoadCanisterSnapshot, args.encode())
+        .unwrap_err();
+    assert_eq!(
+        err.code(),
+        ErrorCode::ReservedCyclesLimitExceededInMemoryGrow
+    );
+    let canister = test.canister_state(canister_id);
+    assert!(canister.execution_state.is_none());
+    assert_eq!(canister.system_state.reserved_balance(), Cycles::new(0));
+}
+
+#[test]
+fn load_canister_snapshot_rejected_because_feature_is_disabled() {
+    let mut test = ExecutionTestBuilder::new()
+        .with_snapshot_data_transfer(FlagStatus::Enabled)
+        .build();
+    let canister_id = test.create_canister(Cycles::new(1_000_000_000_000));
+
+    let args = LoadCanisterSnapshotArgs {
+        canister_id: canister_id.get(),
+        snapshot_id: 0,
+        sender_canister_version: None,
+    };
+    let err = test
+        .subnet_message(Method::LoadCanisterSnapshot, args.encode())
+        .unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterContractViolation);
+    assert_eq!(err.description(), "This API is not enabled on this subnet");
+}
+
+#[test]
+fn test_canister_settings_log_visibility_default_controllers() {
+    // Arrange.
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.create_canister(Cycles::new(1_000_000_000));
+    // Act.
+    let result = test.canister_status(canister_id);
+    let canister_status = CanisterStatusResultV2::decode(&get_reply(result)).unwrap();
+    // Assert.
+    assert_eq!(
+        canister_status.settings().log_visibility(),
+        LogVisibility::Controllers
+    );
+}
+
+#[test]
+fn test_canister_settings_log_visibility_create_with_settings() {
+    // Arrange.
+    let mut test = ExecutionTestBuilder::new().build();
+    // Act.
+    let canister_id = test
+        .create_canister_with_settings(
+            Cycles::new(1_000_000_000),
+            ic00::CanisterSettingsArgsBuilder::new()
+                .with_log_visibility(LogVisibility::Public)
+                .build(),
+        )
+        .unwrap();
+    let result = test.canister_status(canister_id);
+    let canister_status = CanisterStatusResultV2::decode(&get_reply(result)).unwrap();
+    // Assert.
     assert_eq!(
         canister_status.settings().log_visibility(),
         LogVisibility::Public
     );
 }
 
+#[test]
+fn test_canister_settings_log_visibility_set_to_public() {
+    // Arrange.
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.create_canister(Cycles::new(1_000_000_000));
+    // Act.
+    test.set_log_visibility(canister_id, LogVisibility::Public)
+        .unwrap();
+    let result = test.canister_status(canister_id);
+    let canister_status = CanisterStatusResultV2::decode(&get_reply(result)).unwrap();
+    // Assert.
+    assert_eq!(
+        canister_status.settings().log_visibility(),
+        LogVisibility::Public
+    );
+}
+
+#[test]
+fn test_canister_settings_wasm_memory_threshold_create_with_settings() {
+    // Arrange.
+    let mut test = ExecutionTestBuilder::new().build();
+    // Act.
+    let canister_id = test
+        .create_canister_with_settings(
+            Cycles::new(1_000_000_000),
+            ic00::CanisterSettingsArgsBuilder::new()
+                .with_wasm_memory_threshold(1 << 20)
+                .build(),
+        )
+        .unwrap();
+    let result = test.canister_status(canister_id);
+    let canister_status = CanisterStatusResultV2::decode(&get_reply(result)).unwrap();
+    // Assert.
+    assert_eq!(
+        canister_status.settings().wasm_memory_threshold(),
+        candid::Nat::from(1_u64 << 20)
+    );
+    assert_eq!(
+        test.canister_state(canister_id)
+            .system_state
+            .wasm_memory_threshold,
+        NumBytes::from(1 << 20)
+    );
+}
+
 #[test]
 fn test_fetch_canister_logs_should_accept_ingress_message_disabled() {
     // Arrange.
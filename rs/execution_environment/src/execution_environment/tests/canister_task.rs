@@ -1,13 +1,19 @@
 use assert_matches::assert_matches;
+use ic_base_types::NumSeconds;
+use ic_config::execution_environment::Config as HypervisorConfig;
+use ic_config::subnet_config::{SchedulerConfig, SubnetConfig};
+use ic_management_canister_types::CanisterSettingsArgsBuilder;
 use ic_registry_subnet_type::SubnetType;
+use ic_replicated_state::canister_state::system_state::LowResourceHookStatus;
+use ic_replicated_state::canister_state::WASM_PAGE_SIZE_IN_BYTES;
 use ic_replicated_state::NumWasmPages;
 use ic_replicated_state::{page_map::PAGE_SIZE, CanisterStatus};
-use ic_state_machine_tests::{Cycles, StateMachine};
+use ic_state_machine_tests::{Cycles, StateMachine, StateMachineConfig};
 use ic_state_machine_tests::{StateMachineBuilder, WasmResult};
 use ic_test_utilities_execution_environment::{wat_compilation_cost, ExecutionTestBuilder};
 use ic_test_utilities_metrics::fetch_int_counter_vec;
 use ic_types::messages::CanisterTask;
-use ic_types::NumBytes;
+use ic_types::{NumBytes, MAX_WASM_MEMORY_IN_BYTES};
 use ic_universal_canister::{wasm, UNIVERSAL_CANISTER_WASM};
 use maplit::btreemap;
 use std::time::{Duration, UNIX_EPOCH};
@@ -762,3 +768,167 @@ fn global_timer_resumes_after_canister_is_being_stopped_and_started_again() {
     let result = env.query(canister_id, "query", get_global_counter).unwrap();
     assert_eq!(result, WasmResult::Reply(10_u64.to_le_bytes().into()));
 }
+
+#[test]
+fn on_low_wasm_memory_hook_is_executed() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let wat = r#"(module
+            (func (export "canister_on_low_wasm_memory")
+                (drop (memory.grow (i32.const 10)))
+            )
+            (memory 1 20)
+        )"#;
+    let canister_id = test.canister_from_wat(wat).unwrap();
+    test.canister_task(canister_id, CanisterTask::OnLowWasmMemory);
+    assert_eq!(
+        test.execution_state(canister_id).wasm_memory.size,
+        NumWasmPages::new(11)
+    );
+    assert_eq!(
+        test.canister_state(canister_id)
+            .system_state
+            .on_low_wasm_memory_hook_status,
+        LowResourceHookStatus::Executed
+    );
+}
+
+#[test]
+fn on_low_cycles_hook_is_executed() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let wat = r#"(module
+            (func (export "canister_on_low_cycles")
+                (drop (memory.grow (i32.const 10)))
+            )
+            (memory 1 20)
+        )"#;
+    let canister_id = test.canister_from_wat(wat).unwrap();
+    test.canister_task(canister_id, CanisterTask::OnLowCycles);
+    assert_eq!(
+        test.execution_state(canister_id).wasm_memory.size,
+        NumWasmPages::new(11)
+    );
+    assert_eq!(
+        test.canister_state(canister_id)
+            .system_state
+            .on_low_cycles_hook_status,
+        LowResourceHookStatus::Executed
+    );
+}
+
+/// Returns a canister that counts the executions of the given hook in the
+/// first four bytes of its memory. The count is returned by the `count` query
+/// and the `grow` update grows the memory by 10 pages.
+fn low_resource_hook_counter_wat(hook: &str) -> String {
+    format!(
+        r#"(module
+            (import "ic0" "msg_reply" (func $msg_reply))
+            (import "ic0" "msg_reply_data_append"
+                (func $msg_reply_data_append (param i32 i32)))
+            (func (export "canister_update grow")
+                (drop (memory.grow (i32.const 10)))
+                (call $msg_reply)
+            )
+            (func (export "canister_query count")
+                (call $msg_reply_data_append (i32.const 0) (i32.const 4))
+                (call $msg_reply)
+            )
+            (func (export "{hook}")
+                (i32.store (i32.const 0)
+                    (i32.add (i32.load (i32.const 0)) (i32.const 1)))
+            )
+            (memory 1)
+        )"#
+    )
+}
+
+fn state_machine_with_scheduler_config(
+    update_scheduler_config: impl FnOnce(&mut SchedulerConfig),
+) -> StateMachine {
+    let mut subnet_config = SubnetConfig::new(SubnetType::Application);
+    update_scheduler_config(&mut subnet_config.scheduler_config);
+    StateMachineBuilder::new()
+        .with_subnet_type(SubnetType::Application)
+        .with_config(Some(StateMachineConfig::new(
+            subnet_config,
+            HypervisorConfig::default(),
+        )))
+        .build()
+}
+
+#[test]
+fn on_low_wasm_memory_hook_runs_once_when_threshold_is_reached() {
+    let env = StateMachine::new();
+    let wat = low_resource_hook_counter_wat("canister_on_low_wasm_memory");
+    let canister_id = env.install_canister_wat(&wat, vec![], None);
+    // The hook runs once fewer than 5 Wasm pages remain available.
+    let wasm_memory_threshold = MAX_WASM_MEMORY_IN_BYTES - 5 * WASM_PAGE_SIZE_IN_BYTES as u64;
+    env.update_settings(
+        &canister_id,
+        CanisterSettingsArgsBuilder::new()
+            .with_wasm_memory_threshold(wasm_memory_threshold)
+            .build(),
+    )
+    .unwrap();
+
+    // The remaining Wasm memory is above the threshold.
+    env.tick();
+    let result = env.query(canister_id, "count", vec![]).unwrap();
+    assert_eq!(result, WasmResult::Reply(0_u32.to_le_bytes().to_vec()));
+
+    // Fall below the threshold.
+    env.execute_ingress(canister_id, "grow", vec![]).unwrap();
+    for _ in 0..5 {
+        env.tick();
+    }
+
+    // The hook is only executed once.
+    let result = env.query(canister_id, "count", vec![]).unwrap();
+    assert_eq!(result, WasmResult::Reply(1_u32.to_le_bytes().to_vec()));
+    assert_eq!(
+        env.get_latest_state()
+            .canister_state(&canister_id)
+            .unwrap()
+            .system_state
+            .on_low_wasm_memory_hook_status,
+        LowResourceHookStatus::Executed
+    );
+}
+
+#[test]
+fn on_low_cycles_hook_runs_once_until_canister_is_topped_up() {
+    // With a threshold of 100 years, the idle canister below is low on cycles.
+    let env = state_machine_with_scheduler_config(|config| {
+        config.low_cycles_threshold = NumSeconds::from(100 * 365 * 24 * 3600);
+    });
+    let wat = low_resource_hook_counter_wat("canister_on_low_cycles");
+    let canister_id = env
+        .install_canister_with_cycles(
+            wat::parse_str(wat).unwrap(),
+            vec![],
+            None,
+            Cycles::new(100_000_000_000),
+        )
+        .unwrap();
+
+    for _ in 0..5 {
+        env.tick();
+    }
+
+    // The hook is only executed once.
+    let result = env.query(canister_id, "count", vec![]).unwrap();
+    assert_eq!(result, WasmResult::Reply(1_u32.to_le_bytes().to_vec()));
+
+    // Topping up the canister resets the hook.
+    env.add_cycles(canister_id, u64::MAX as u128);
+    env.tick();
+    assert_eq!(
+        env.get_latest_state()
+            .canister_state(&canister_id)
+            .unwrap()
+            .system_state
+            .on_low_cycles_hook_status,
+        LowResourceHookStatus::ConditionNotSatisfied
+    );
+    let result = env.query(canister_id, "count", vec![]).unwrap();
+    assert_eq!(result, WasmResult::Reply(1_u32.to_le_bytes().to_vec()));
+}
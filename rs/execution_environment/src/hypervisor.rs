@@ -3,11 +3,11 @@ use ic_config::execution_environment::{Config, MAX_COMPILATION_CACHE_SIZE};
 use ic_config::flag_status::FlagStatus;
 use ic_cycles_account_manager::CyclesAccountManager;
 use ic_embedders::wasm_executor::{WasmExecutionResult, WasmExecutor};
-use ic_embedders::wasm_utils::decoding::decoded_wasm_size;
+use ic_embedders::wasm_utils::{decoding::decoded_wasm_size, WasmMemoryDeclaration};
+use ic_embedders::{compilation_cache_version_key, CompilationCache, CompilationResult};
 use ic_embedders::{wasm_executor::WasmExecutorImpl, WasmExecutionInput, WasmtimeEmbedder};
-use ic_embedders::{CompilationCache, CompilationResult};
 use ic_interfaces::execution_environment::{HypervisorResult, WasmExecutionOutput};
-use ic_logger::ReplicaLogger;
+use ic_logger::{error, ReplicaLogger};
 use ic_metrics::buckets::decimal_buckets_with_zero;
 use ic_metrics::{buckets::exponential_buckets, MetricsRegistry};
 use ic_registry_subnet_type::SubnetType;
@@ -16,8 +16,8 @@ use ic_replicated_state::{page_map::allocated_pages_count, ExecutionState, Syste
 use ic_system_api::ExecutionParameters;
 use ic_system_api::{sandbox_safe_system_state::SandboxSafeSystemState, ApiType};
 use ic_types::{
-    messages::RequestMetadata, methods::FuncRef, CanisterId, NumBytes, NumInstructions, SubnetId,
-    Time,
+    messages::RequestMetadata, methods::FuncRef, CanisterId, NumBytes, NumInstructions,
+    ReplicaVersion, SubnetId, Time,
 };
 use ic_wasm_types::CanisterModule;
 use prometheus::{Histogram, IntCounter, IntGauge};
@@ -180,6 +180,32 @@ impl Hypervisor {
         round_limits: &mut RoundLimits,
         compilation_cost_handling: CompilationCostHandling,
     ) -> (NumInstructions, HypervisorResult<ExecutionState>) {
+        let (compilation_cost, result) = self.create_execution_state_with_main_memory(
+            canister_module,
+            canister_root,
+            canister_id,
+            round_limits,
+            compilation_cost_handling,
+        );
+        (
+            compilation_cost,
+            result.map(|(execution_state, _main_memory)| execution_state),
+        )
+    }
+
+    /// Same as `create_execution_state()`, but also returns the main memory
+    /// declared by the module, as found when the module was validated.
+    pub fn create_execution_state_with_main_memory(
+        &self,
+        canister_module: CanisterModule,
+        canister_root: PathBuf,
+        canister_id: CanisterId,
+        round_limits: &mut RoundLimits,
+        compilation_cost_handling: CompilationCostHandling,
+    ) -> (
+        NumInstructions,
+        HypervisorResult<(ExecutionState, Option<WasmMemoryDeclaration>)>,
+    ) {
         // If a wasm instruction has no arguments then it can be represented as
         // a single byte. So taking the length of the wasm source is a
         // conservative estimate of the number of instructions. If we can't
@@ -204,7 +230,7 @@ impl Hypervisor {
             Arc::clone(&self.compilation_cache),
         );
         match creation_result {
-            Ok((execution_state, compilation_cost, compilation_result)) => {
+            Ok((execution_state, compilation_cost, compilation_result, main_memory)) => {
                 if let Some(compilation_result) = compilation_result {
                     self.metrics
                         .observe_compilation_metrics(&compilation_result);
@@ -212,7 +238,7 @@ impl Hypervisor {
                 round_limits.instructions -= as_round_instructions(
                     compilation_cost_handling.adjusted_compilation_cost(compilation_cost),
                 );
-                (compilation_cost, Ok(execution_state))
+                (compilation_cost, Ok((execution_state, main_memory)))
             }
             Err(err) => {
                 round_limits.instructions -= as_round_instructions(compilation_cost);
@@ -236,6 +262,31 @@ impl Hypervisor {
         embedder_config.dirty_page_overhead = dirty_page_overhead;
         embedder_config.feature_flags.canister_logging = config.canister_logging;
 
+        let compilation_cache = match &config.compilation_cache_dir {
+            Some(dir) => CompilationCache::new_with_disk_cache(
+                config.max_compilation_cache_size,
+                dir.clone(),
+                config.max_compilation_cache_disk_size,
+                compilation_cache_version_key(&embedder_config, &ReplicaVersion::default()),
+                metrics_registry,
+                log.clone(),
+            )
+            .unwrap_or_else(|err| {
+                // The on-disk cache is an optimization, so the replica keeps
+                // running with the in-memory cache only.
+                error!(
+                    log,
+                    "Failed to initialize the compilation cache directory {}: {}. \
+                     Falling back to the in-memory compilation cache.",
+                    dir.display(),
+                    err
+                );
+                CompilationCache::new(config.max_compilation_cache_size)
+            }),
+            None => CompilationCache::new(config.max_compilation_cache_size),
+        };
+        let compilation_cache = Arc::new(compilation_cache);
+
         let wasm_executor: Arc<dyn WasmExecutor> = match config.canister_sandboxing_flag {
             FlagStatus::Enabled => {
                 let executor = SandboxedExecutionController::new(
@@ -265,7 +316,7 @@ impl Hypervisor {
             own_subnet_type,
             log,
             cycles_account_manager,
-            compilation_cache: Arc::new(CompilationCache::new(config.max_compilation_cache_size)),
+            compilation_cache,
             deterministic_time_slicing: config.deterministic_time_slicing,
             cost_to_compile_wasm_instruction: config
                 .embedders_config
@@ -6,7 +6,9 @@ use ic_cycles_account_manager::ResourceSaturation;
 use ic_embedders::wasm_utils::instrumentation::instruction_to_cost_new;
 use ic_error_types::{ErrorCode, RejectCode};
 use ic_interfaces::execution_environment::{HypervisorError, SubnetAvailableMemory};
-use ic_management_canister_types::{CanisterChange, CanisterHttpResponsePayload, SkipPreUpgrade};
+use ic_management_canister_types::{
+    CanisterChange, CanisterHttpResponsePayload, CanisterUpgradeOptions,
+};
 use ic_nns_constants::CYCLES_MINTING_CANISTER_ID;
 use ic_registry_subnet_type::SubnetType;
 use ic_replicated_state::canister_state::{NextExecution, WASM_PAGE_SIZE_IN_BYTES};
@@ -6327,7 +6329,10 @@ fn upgrade_with_skip_pre_upgrade_preserves_stable_memory() {
     test.upgrade_canister_v2(
         canister_id,
         wat::parse_str(wat.clone()).unwrap(),
-        Some(SkipPreUpgrade(Some(true))),
+        Some(CanisterUpgradeOptions {
+            skip_pre_upgrade: Some(true),
+            wasm_memory_persistence: None,
+        }),
     )
     .unwrap();
 
@@ -6336,7 +6341,10 @@ fn upgrade_with_skip_pre_upgrade_preserves_stable_memory() {
         .upgrade_canister_v2(
             canister_id,
             wat::parse_str(wat).unwrap(),
-            Some(SkipPreUpgrade(Some(false))),
+            Some(CanisterUpgradeOptions {
+                skip_pre_upgrade: Some(false),
+                wasm_memory_persistence: None,
+            }),
         )
         .unwrap_err();
     assert_eq!(ErrorCode::CanisterTrapped, err.code());
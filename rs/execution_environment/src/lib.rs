@@ -29,7 +29,7 @@ use ic_cycles_account_manager::CyclesAccountManager;
 use ic_interfaces::execution_environment::AnonymousQueryService;
 use ic_interfaces::execution_environment::{
     IngressFilter, IngressFilterService, IngressHistoryReader, IngressHistoryWriter,
-    QueryExecutionService, QueryHandler, Scheduler,
+    QueryExecutionService, QueryHandler, RemoteQueryClient, RemoteQueryHandler, Scheduler,
 };
 use ic_interfaces_state_manager::StateReader;
 use ic_logger::ReplicaLogger;
@@ -42,7 +42,7 @@ use ic_types::{messages::CallContextId, SubnetId};
 use ingress_filter::IngressFilterImpl;
 pub use metrics::IngressFilterMetrics;
 pub use query_handler::InternalHttpQueryHandler;
-use query_handler::{HttpQueryHandler, QueryScheduler, QuerySchedulerFlag};
+use query_handler::{HttpQueryHandler, QueryScheduler, QuerySchedulerFlag, RemoteQueryHandlerImpl};
 pub use scheduler::RoundSchedule;
 use scheduler::SchedulerImpl;
 use std::sync::Arc;
@@ -87,13 +87,21 @@ pub struct ExecutionServices {
     pub sync_query_handler: Arc<dyn QueryHandler<State = ReplicatedState>>,
     pub async_query_handler: QueryExecutionService,
     pub anonymous_query_handler: AnonymousQueryService,
+    pub remote_query_handler: Arc<dyn RemoteQueryHandler>,
     pub scheduler: Box<dyn Scheduler<State = ReplicatedState>>,
     pub query_stats_payload_builder: QueryStatsPayloadBuilderParams,
+    /// The hypervisor shared by the scheduler and the query handlers. Exposed
+    /// so that tools driving the replica, like the replay tool, can compile
+    /// canister modules with the same configuration and compilation cache.
+    pub hypervisor: Arc<Hypervisor>,
 }
 
 impl ExecutionServices {
     /// Constructs the public facing components that the
     /// `ExecutionEnvironment` crate exports.
+    ///
+    /// Query calls to canisters on other subnets are forwarded through
+    /// `remote_query_client` if one is provided.
     #[allow(clippy::type_complexity, clippy::too_many_arguments)]
     pub fn setup_execution(
         logger: ReplicaLogger,
@@ -105,6 +113,7 @@ impl ExecutionServices {
         cycles_account_manager: Arc<CyclesAccountManager>,
         state_reader: Arc<dyn StateReader<State = ReplicatedState>>,
         fd_factory: Arc<dyn PageAllocatorFileDescriptor>,
+        remote_query_client: Option<Arc<dyn RemoteQueryClient>>,
     ) -> ExecutionServices {
         let hypervisor = Arc::new(Hypervisor::new(
             config.clone(),
@@ -145,13 +154,18 @@ impl ExecutionServices {
         ));
         let sync_query_handler = Arc::new(InternalHttpQueryHandler::new(
             logger.clone(),
-            hypervisor,
+            Arc::clone(&hypervisor),
             own_subnet_type,
             config.clone(),
             metrics_registry,
             scheduler_config.max_instructions_per_message_without_dts,
             Arc::clone(&cycles_account_manager),
             query_stats_collector,
+            remote_query_client,
+        ));
+        let remote_query_handler = Arc::new(RemoteQueryHandlerImpl::new(
+            Arc::clone(&sync_query_handler),
+            Arc::clone(&state_reader),
         ));
 
         let query_scheduler = QueryScheduler::new(
@@ -209,8 +223,10 @@ impl ExecutionServices {
             sync_query_handler,
             async_query_handler,
             anonymous_query_handler,
+            remote_query_handler,
             scheduler,
             query_stats_payload_builder,
+            hypervisor,
         }
     }
 
@@ -148,6 +148,8 @@ pub(crate) struct QueryHandlerMetrics {
     pub evaluated_canisters: Histogram,
     /// The number of nested composite query execution errors.
     pub nested_execution_errors: IntCounter,
+    /// The number of query calls forwarded to canisters on other subnets.
+    pub remote_query_calls: IntCounter,
 }
 
 impl QueryHandlerMetrics {
@@ -267,6 +269,10 @@ impl QueryHandlerMetrics {
                 "execution_query_nested_execution_errors_total",
                 "The total number of nested composite query execution errors"
             ),
+            remote_query_calls: metrics_registry.int_counter(
+                "execution_query_remote_calls_total",
+                "The total number of query calls forwarded to canisters on other subnets"
+            ),
         }
     }
 }
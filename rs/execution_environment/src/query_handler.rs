@@ -22,6 +22,8 @@ use ic_cycles_account_manager::CyclesAccountManager;
 use ic_error_types::{ErrorCode, RejectCode, UserError};
 use ic_interfaces::execution_environment::{
     QueryExecutionError, QueryExecutionResponse, QueryExecutionService, QueryHandler,
+    RemoteQueryClient, RemoteQueryError, RemoteQueryHandler, RemoteQueryRequest,
+    RemoteQueryResponse,
 };
 use ic_interfaces_state_manager::{Labeled, StateReader};
 use ic_logger::ReplicaLogger;
@@ -35,9 +37,9 @@ use ic_types::{
     ingress::WasmResult,
     messages::{
         Blob, Certificate, CertificateDelegation, HttpQueryResponse, HttpQueryResponseReply,
-        UserQuery,
+        RejectContext, UserQuery,
     },
-    CanisterId, NumInstructions, PrincipalId,
+    CanisterId, NodeId, NumInstructions, PrincipalId,
 };
 use prometheus::Histogram;
 use serde::Serialize;
@@ -112,6 +114,8 @@ pub struct InternalHttpQueryHandler {
     cycles_account_manager: Arc<CyclesAccountManager>,
     local_query_execution_stats: QueryStatsCollector,
     query_cache: query_cache::QueryCache,
+    remote_query_client: Option<Arc<dyn RemoteQueryClient>>,
+    remote_query_call_limit: query_context::RemoteQueryCallLimit,
 }
 
 #[derive(Clone)]
@@ -150,10 +154,13 @@ impl InternalHttpQueryHandler {
         max_instructions_per_query: NumInstructions,
         cycles_account_manager: Arc<CyclesAccountManager>,
         local_query_execution_stats: QueryStatsCollector,
+        remote_query_client: Option<Arc<dyn RemoteQueryClient>>,
     ) -> Self {
         let query_cache_capacity = config.query_cache_capacity;
         let query_max_expiry_time = config.query_cache_max_expiry_time;
         let query_data_certificate_expiry_time = config.query_cache_data_certificate_expiry_time;
+        let remote_query_call_limit =
+            query_context::RemoteQueryCallLimit::new(config.max_outstanding_remote_query_calls);
         Self {
             log,
             hypervisor,
@@ -169,6 +176,76 @@ impl InternalHttpQueryHandler {
                 query_max_expiry_time,
                 query_data_certificate_expiry_time,
             ),
+            remote_query_client,
+            remote_query_call_limit,
+        }
+    }
+
+    /// Executes a query call made by a canister on another subnet, using the
+    /// limits of the request where they are stricter than the local ones.
+    pub fn remote_query(
+        &self,
+        request: RemoteQueryRequest,
+        state: Labeled<Arc<ReplicatedState>>,
+    ) -> RemoteQueryResponse {
+        let measurement_scope = MeasurementScope::root(&self.metrics.query);
+        let own_subnet_id = self.cycles_account_manager.get_subnet_id();
+
+        // Do not forward the call any further if the routing tables of the
+        // two subnets disagree on where the receiver is hosted.
+        let network_topology = &state.get_ref().metadata.network_topology;
+        if network_topology.routing_table.route(request.receiver.get()) != Some(own_subnet_id) {
+            let error = UserError::new(
+                ErrorCode::CanisterNotHostedBySubnet,
+                format!(
+                    "Canister {} is not hosted by subnet {}",
+                    request.receiver, own_subnet_id
+                ),
+            );
+            return RemoteQueryResponse {
+                payload: ic_types::messages::Payload::Reject(RejectContext::from(error)),
+                instructions_used: NumInstructions::from(0),
+            };
+        }
+
+        let mut context = query_context::QueryContext::new(
+            &self.log,
+            self.hypervisor.as_ref(),
+            own_subnet_id,
+            self.own_subnet_type,
+            state,
+            None,
+            subnet_memory_capacity(&self.config),
+            self.config.max_canister_memory_size,
+            self.max_instructions_per_query,
+            self.config
+                .max_query_call_graph_depth
+                .min(request.max_call_graph_depth),
+            self.config
+                .max_query_call_graph_instructions
+                .min(request.instruction_limit),
+            self.config.max_query_call_walltime.min(request.time_limit),
+            self.config.instruction_overhead_per_query_call,
+            self.config.composite_queries,
+            &self.metrics.query_critical_error,
+            if self.config.query_stats_aggregation == FlagStatus::Enabled {
+                Some(&self.local_query_execution_stats)
+            } else {
+                None
+            },
+            self.remote_query_client.as_deref(),
+            &self.remote_query_call_limit,
+        );
+
+        let (payload, instructions_used) = context.run_remote(
+            request,
+            self.cycles_account_manager.as_ref(),
+            &measurement_scope,
+        );
+        context.observe_metrics(&self.metrics);
+        RemoteQueryResponse {
+            payload,
+            instructions_used,
         }
     }
 
@@ -291,9 +368,10 @@ impl QueryHandler for InternalHttpQueryHandler {
         let mut context = query_context::QueryContext::new(
             &self.log,
             self.hypervisor.as_ref(),
+            self.cycles_account_manager.get_subnet_id(),
             self.own_subnet_type,
             state,
-            data_certificate,
+            Some((data_certificate, query.receiver)),
             subnet_available_memory,
             max_canister_memory_size,
             self.max_instructions_per_query,
@@ -302,13 +380,14 @@ impl QueryHandler for InternalHttpQueryHandler {
             self.config.max_query_call_walltime,
             self.config.instruction_overhead_per_query_call,
             self.config.composite_queries,
-            query.receiver,
             &self.metrics.query_critical_error,
             if self.config.query_stats_aggregation == FlagStatus::Enabled {
                 Some(&self.local_query_execution_stats)
             } else {
                 None
             },
+            self.remote_query_client.as_deref(),
+            &self.remote_query_call_limit,
         );
 
         let result = context.run(
@@ -320,7 +399,9 @@ impl QueryHandler for InternalHttpQueryHandler {
         context.observe_metrics(&self.metrics);
 
         // Add the query execution result to the query cache (if the query caching is enabled).
-        if self.config.query_caching == FlagStatus::Enabled {
+        // Results that depend on the state of other subnets are never cached, as
+        // the cache entry cannot be invalidated when that state changes.
+        if self.config.query_caching == FlagStatus::Enabled && context.remote_query_calls() == 0 {
             if let (Some(key), Some(env)) = (cache_entry_key, cache_entry_env) {
                 let call_counters = context.system_api_call_counters();
                 let _evaluated_ids = context.evaluated_canister_ids();
@@ -332,6 +413,53 @@ impl QueryHandler for InternalHttpQueryHandler {
     }
 }
 
+/// Executes query calls received from other subnets against the latest
+/// certified state, after checking that the calling node belongs to the subnet
+/// hosting the calling canister.
+pub(crate) struct RemoteQueryHandlerImpl {
+    internal: Arc<InternalHttpQueryHandler>,
+    state_reader: Arc<dyn StateReader<State = ReplicatedState>>,
+}
+
+impl RemoteQueryHandlerImpl {
+    pub(crate) fn new(
+        internal: Arc<InternalHttpQueryHandler>,
+        state_reader: Arc<dyn StateReader<State = ReplicatedState>>,
+    ) -> Self {
+        Self {
+            internal,
+            state_reader,
+        }
+    }
+}
+
+impl RemoteQueryHandler for RemoteQueryHandlerImpl {
+    fn query(
+        &self,
+        peer: NodeId,
+        request: RemoteQueryRequest,
+    ) -> Result<RemoteQueryResponse, RemoteQueryError> {
+        let state = self
+            .state_reader
+            .get_state_at(self.state_reader.latest_certified_height())
+            .map_err(|_| RemoteQueryError::CertifiedStateUnavailable)?;
+
+        // The network topology mirrors the registry, so this only admits nodes
+        // that the registry assigns to the subnet hosting the sender.
+        let network_topology = &state.get_ref().metadata.network_topology;
+        let authorized = network_topology
+            .routing_table
+            .route(request.sender.get())
+            .and_then(|subnet_id| network_topology.subnets.get(&subnet_id))
+            .map_or(false, |subnet| subnet.nodes.contains(&peer));
+        if !authorized {
+            return Err(RemoteQueryError::Unauthorized(peer));
+        }
+
+        Ok(self.internal.remote_query(request, state))
+    }
+}
+
 fn fetch_canister_logs(
     sender: PrincipalId,
     state: &ReplicatedState,
@@ -43,6 +43,12 @@ struct PendingCall(CanisterState, CallOrigin, VecDeque<Arc<Request>>);
 /// - the limit on the total number of executed instructions by all queries and
 ///   response callbacks.
 ///
+/// Query calls to canisters on other subnets are evaluated by these subnets
+/// within what is left of both limits (and of the time limit). The remote
+/// sub-graph appears as a single edge that immediately produces a response.
+/// Query calls from a remote sub-graph back to a canister on the call stack of
+/// any calling subnet are rejected.
+///
 /// A note on re-entrancy: currently re-entrant query calls are not allowed.
 /// In other words, if a canister is in the call stack, then an attempt to make a
 /// new query call to that canister will result in an error. This restriction
@@ -124,7 +130,11 @@ pub(super) fn evaluate_query_call_graph(
                     // properly handle the response of the callee.
                     call_stack.push(PendingCall(canister, call_origin, requests));
 
-                    match query_context.handle_request(request, measurement_scope) {
+                    let callers: Vec<_> = call_stack
+                        .iter()
+                        .map(|PendingCall(canister, _, _)| canister.canister_id())
+                        .collect();
+                    match query_context.handle_request(request, &callers, measurement_scope) {
                         ExecutionResult::Calls(canister, call_origin, requests) => {
                             call_stack.push(PendingCall(canister, call_origin, requests));
                         }
@@ -2,7 +2,7 @@ use super::query_call_graph::evaluate_query_call_graph;
 use crate::{
     execution::common::{self, validate_method},
     execution::nonreplicated_query::execute_non_replicated_query,
-    execution_environment::{as_round_instructions, RoundLimits},
+    execution_environment::{as_num_instructions, as_round_instructions, RoundLimits},
     hypervisor::Hypervisor,
     metrics::{
         CallTreeMetricsNoOp, MeasurementScope, QueryHandlerMetrics, QUERY_HANDLER_CRITICAL_ERROR,
@@ -17,7 +17,8 @@ use ic_constants::SMALL_APP_SUBNET_MAX_SIZE;
 use ic_cycles_account_manager::{CyclesAccountManager, ResourceSaturation};
 use ic_error_types::{ErrorCode, RejectCode, UserError};
 use ic_interfaces::execution_environment::{
-    ExecutionMode, HypervisorError, SubnetAvailableMemory, SystemApiCallCounters,
+    ExecutionMode, HypervisorError, RemoteQueryClient, RemoteQueryRequest, SubnetAvailableMemory,
+    SystemApiCallCounters,
 };
 use ic_interfaces_state_manager::Labeled;
 use ic_logger::{error, ReplicaLogger};
@@ -34,7 +35,7 @@ use ic_types::{
         UserQuery,
     },
     methods::WasmMethod,
-    CanisterId, Cycles, NumInstructions, NumMessages, Time,
+    CanisterId, Cycles, NumInstructions, NumMessages, SubnetId, Time,
 };
 use ic_types::{
     methods::{FuncRef, WasmClosure},
@@ -43,7 +44,10 @@ use ic_types::{
 use prometheus::IntCounter;
 use std::{
     collections::{BTreeSet, VecDeque},
-    sync::Arc,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
     time::Duration,
     time::Instant,
 };
@@ -87,16 +91,59 @@ fn wasm_query_method(
     }
 }
 
+/// Limits the number of query calls to canisters on other subnets that are
+/// outstanding at the same time, across all query contexts.
+///
+/// The query execution thread making such a call waits for its response, so
+/// without a limit, slow or unresponsive subnets could occupy all query
+/// execution threads of this node as well as the query workers of their
+/// endpoints. Calls beyond the limit are rejected right away.
+pub(super) struct RemoteQueryCallLimit {
+    max_outstanding_calls: usize,
+    outstanding_calls: AtomicUsize,
+}
+
+impl RemoteQueryCallLimit {
+    pub(super) fn new(max_outstanding_calls: usize) -> Self {
+        Self {
+            max_outstanding_calls,
+            outstanding_calls: AtomicUsize::new(0),
+        }
+    }
+
+    /// Reserves a slot for a call, unless the limit is reached. The slot is
+    /// released when the returned guard is dropped.
+    pub(super) fn try_reserve(&self) -> Option<RemoteQueryCallSlot<'_>> {
+        self.outstanding_calls
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |outstanding_calls| {
+                (outstanding_calls < self.max_outstanding_calls).then_some(outstanding_calls + 1)
+            })
+            .ok()
+            .map(|_| RemoteQueryCallSlot(self))
+    }
+}
+
+/// A slot reserved for an outstanding call, see [`RemoteQueryCallLimit`].
+pub(super) struct RemoteQueryCallSlot<'a>(&'a RemoteQueryCallLimit);
+
+impl Drop for RemoteQueryCallSlot<'_> {
+    fn drop(&mut self) {
+        self.0.outstanding_calls.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
 /// Executes a single user query along with its outgoing query calls.
 pub(super) struct QueryContext<'a> {
     log: &'a ReplicaLogger,
     hypervisor: &'a Hypervisor,
+    own_subnet_id: SubnetId,
     own_subnet_type: SubnetType,
     // The state against which all queries in the context will be executed.
     state: Labeled<Arc<ReplicatedState>>,
     network_topology: Arc<NetworkTopology>,
-    // Certificate for certified queries + canister ID of the root query of this context
-    data_certificate: (Vec<u8>, CanisterId),
+    // Certificate for certified queries + canister ID of the root query of this context.
+    // Queries received from other subnets are executed without a certificate.
+    data_certificate: Option<(Vec<u8>, CanisterId)>,
     max_canister_memory_size: NumBytes,
     max_instructions_per_query: NumInstructions,
     max_query_call_graph_depth: usize,
@@ -115,6 +162,18 @@ pub(super) struct QueryContext<'a> {
     evaluated_canister_ids: BTreeSet<CanisterId>,
     /// The number of nested composite query execution errors.
     nested_execution_errors: usize,
+    /// The client used to forward query calls to canisters on other subnets.
+    /// If not set, such calls are rejected as if the canister did not exist.
+    remote_query_client: Option<&'a dyn RemoteQueryClient>,
+    /// Limits the calls to other subnets that are outstanding at the same time.
+    remote_query_call_limit: &'a RemoteQueryCallLimit,
+    /// The number of query calls to other subnets, including the ones
+    /// rejected because of the limit on outstanding calls.
+    remote_query_calls: usize,
+    /// If this context executes a query call received from another subnet,
+    /// the canisters on the call stack of the calling subnets. Query calls to
+    /// any of these canisters would re-enter them and are rejected.
+    remote_call_stack: Vec<CanisterId>,
 }
 
 impl<'a> QueryContext<'a> {
@@ -122,9 +181,10 @@ impl<'a> QueryContext<'a> {
     pub(super) fn new(
         log: &'a ReplicaLogger,
         hypervisor: &'a Hypervisor,
+        own_subnet_id: SubnetId,
         own_subnet_type: SubnetType,
         state: Labeled<Arc<ReplicatedState>>,
-        data_certificate: Vec<u8>,
+        data_certificate: Option<(Vec<u8>, CanisterId)>,
         subnet_available_memory: SubnetAvailableMemory,
         max_canister_memory_size: NumBytes,
         max_instructions_per_query: NumInstructions,
@@ -133,9 +193,10 @@ impl<'a> QueryContext<'a> {
         max_query_call_walltime: Duration,
         instruction_overhead_per_query_call: NumInstructions,
         composite_queries: FlagStatus,
-        canister_id: CanisterId,
         query_critical_error: &'a IntCounter,
         local_query_execution_stats: Option<&'a QueryStatsCollector>,
+        remote_query_client: Option<&'a dyn RemoteQueryClient>,
+        remote_query_call_limit: &'a RemoteQueryCallLimit,
     ) -> Self {
         let network_topology = Arc::new(state.get_ref().metadata.network_topology.clone());
         let round_limits = RoundLimits {
@@ -147,10 +208,11 @@ impl<'a> QueryContext<'a> {
         Self {
             log,
             hypervisor,
+            own_subnet_id,
             own_subnet_type,
             state,
             network_topology,
-            data_certificate: (data_certificate, canister_id),
+            data_certificate,
             max_canister_memory_size,
             max_instructions_per_query,
             max_query_call_graph_depth,
@@ -166,6 +228,10 @@ impl<'a> QueryContext<'a> {
             system_api_call_counters: SystemApiCallCounters::default(),
             evaluated_canister_ids: BTreeSet::default(),
             nested_execution_errors: 0,
+            remote_query_client,
+            remote_query_call_limit,
+            remote_query_calls: 0,
+            remote_call_stack: vec![],
         }
     }
 
@@ -188,26 +254,7 @@ impl<'a> QueryContext<'a> {
     ) -> Result<WasmResult, UserError> {
         let canister_id = query.receiver;
         let old_canister = self.state.get_ref().get_active_canister(&canister_id)?;
-
-        let subnet_size = self
-            .network_topology
-            .get_subnet_size(&cycles_account_manager.get_subnet_id())
-            .unwrap_or(SMALL_APP_SUBNET_MAX_SIZE);
-        if cycles_account_manager.freeze_threshold_cycles(
-            old_canister.system_state.freeze_threshold,
-            old_canister.system_state.memory_allocation,
-            old_canister.memory_usage(),
-            old_canister.message_memory_usage(),
-            old_canister.scheduler_state.compute_allocation,
-            subnet_size,
-            old_canister.system_state.reserved_balance(),
-        ) > old_canister.system_state.balance()
-        {
-            return Err(UserError::new(
-                ErrorCode::CanisterOutOfCycles,
-                format!("Canister {} is unable to process query calls because it's frozen. Please top up the canister with cycles and try again.", canister_id))
-            );
-        }
+        self.check_not_frozen(old_canister, &cycles_account_manager)?;
 
         let call_origin = CallOrigin::Query(query.source);
 
@@ -303,6 +350,97 @@ impl<'a> QueryContext<'a> {
         }
     }
 
+    /// Returns an error if the given canister is below its freezing threshold
+    /// and thus cannot pay for the execution of queries.
+    fn check_not_frozen(
+        &self,
+        canister: &CanisterState,
+        cycles_account_manager: &CyclesAccountManager,
+    ) -> Result<(), UserError> {
+        let subnet_size = self
+            .network_topology
+            .get_subnet_size(&cycles_account_manager.get_subnet_id())
+            .unwrap_or(SMALL_APP_SUBNET_MAX_SIZE);
+        if cycles_account_manager.freeze_threshold_cycles(
+            canister.system_state.freeze_threshold,
+            canister.system_state.memory_allocation,
+            canister.memory_usage(),
+            canister.message_memory_usage(),
+            canister.scheduler_state.compute_allocation,
+            subnet_size,
+            canister.system_state.reserved_balance(),
+        ) > canister.system_state.balance()
+        {
+            return Err(UserError::new(
+                ErrorCode::CanisterOutOfCycles,
+                format!("Canister {} is unable to process query calls because it's frozen. Please top up the canister with cycles and try again.", canister.canister_id()))
+            );
+        }
+        Ok(())
+    }
+
+    /// Executes the given query call made by a canister on another subnet
+    /// along with its outgoing query calls.
+    ///
+    /// As for user queries, the called canister must not be frozen. Every
+    /// canister executed here has its query statistics recorded on this
+    /// subnet, so that it is charged for its part of the call graph.
+    ///
+    /// Returns the reply or reject of the called canister together with the
+    /// number of instructions executed by the whole call graph.
+    pub(super) fn run_remote(
+        &mut self,
+        request: RemoteQueryRequest,
+        cycles_account_manager: &CyclesAccountManager,
+        measurement_scope: &MeasurementScope,
+    ) -> (Payload, NumInstructions) {
+        if let Ok(canister) = self.state.get_ref().get_active_canister(&request.receiver) {
+            if let Err(err) = self.check_not_frozen(canister, cycles_account_manager) {
+                return (
+                    Payload::Reject(RejectContext::from(err)),
+                    NumInstructions::from(0),
+                );
+            }
+        }
+        self.remote_call_stack = request.call_stack;
+
+        let instructions_before = self.round_limits.instructions;
+        let request = Arc::new(Request {
+            receiver: request.receiver,
+            sender: request.sender,
+            // The callback only exists on the calling subnet, the response
+            // is matched to it there.
+            sender_reply_callback: CallbackId::from(0),
+            payment: Cycles::zero(),
+            method_name: request.method_name,
+            method_payload: request.method_payload,
+            metadata: None,
+        });
+
+        let response = match self.handle_request(request, &[], measurement_scope) {
+            ExecutionResult::Response(response) => response,
+            ExecutionResult::Calls(canister, call_origin, requests) => evaluate_query_call_graph(
+                self,
+                canister,
+                call_origin,
+                requests,
+                self.max_query_call_graph_depth,
+                measurement_scope,
+            ),
+            ExecutionResult::SystemError(err) => QueryResponse::UserError(err),
+        };
+        let payload = match response {
+            QueryResponse::CanisterResponse(response) => response.response_payload,
+            QueryResponse::UserError(err) => Payload::Reject(RejectContext::from(err)),
+            QueryResponse::UserResponse(_) => {
+                unreachable!("A canister query cannot produce a user response.");
+            }
+        };
+        let instructions_used =
+            as_num_instructions(instructions_before - self.round_limits.instructions);
+        (payload, instructions_used)
+    }
+
     // A helper function that extracts the query calls of the given canister and
     // enqueues them onto the given deque.
     fn extract_query_requests(
@@ -533,6 +671,11 @@ impl<'a> QueryContext<'a> {
         metrics
             .nested_execution_errors
             .inc_by(self.nested_execution_errors as u64);
+
+        // Observe query calls forwarded to other subnets.
+        metrics
+            .remote_query_calls
+            .inc_by(self.remote_query_calls as u64);
     }
 
     fn execute_callback(
@@ -774,9 +917,15 @@ impl<'a> QueryContext<'a> {
     /// of outgoing query calls (requests).
     /// If the execution produces a response, then the function returns it and
     /// discards the call context and outgoing requests.
+    ///
+    /// Query calls to canisters on other subnets are forwarded to these
+    /// subnets, which evaluate the sub-graph of the call using the remaining
+    /// limits. `callers` are the canisters on the call stack of this subnet,
+    /// from the root of the call graph to the sender.
     pub fn handle_request(
         &mut self,
         request: Arc<Request>,
+        callers: &[CanisterId],
         measurement_scope: &MeasurementScope,
     ) -> ExecutionResult {
         // A handy function to create a `Response` using parameters from the `Request`
@@ -792,6 +941,27 @@ impl<'a> QueryContext<'a> {
 
         let canister_id = request.receiver;
 
+        // The canisters on the call stack of other subnets are waiting for the
+        // response of this sub-graph, so they cannot be called again.
+        if self.remote_call_stack.contains(&canister_id) {
+            return ExecutionResult::Response(to_query_result(Payload::Reject(
+                RejectContext::new(
+                    RejectCode::CanisterError,
+                    format!(
+                        "Composite query: re-entrant call to canister {} across subnets is not allowed",
+                        canister_id
+                    ),
+                ),
+            )));
+        }
+
+        if let Some(client) = self.remote_query_client {
+            if let Some(subnet_id) = self.remote_subnet(&canister_id) {
+                let payload = self.execute_remote_query(client, subnet_id, &request, callers);
+                return ExecutionResult::Response(to_query_result(payload));
+            }
+        }
+
         let canister = match self.state.get_ref().get_active_canister(&canister_id) {
             Ok(canister) => canister,
             Err(err) => {
@@ -858,6 +1028,75 @@ impl<'a> QueryContext<'a> {
         }
     }
 
+    /// Returns the ID of the subnet hosting the given canister if it is not
+    /// hosted by this subnet.
+    fn remote_subnet(&self, canister_id: &CanisterId) -> Option<SubnetId> {
+        self.network_topology
+            .routing_table
+            .route(canister_id.get())
+            .filter(|subnet_id| *subnet_id != self.own_subnet_id)
+    }
+
+    /// Forwards the query call to the given subnet and charges the
+    /// instructions executed there against the limit of this query context.
+    ///
+    /// The call stack is passed on, so that the remote subnet can reject
+    /// calls that would re-enter any canister on it.
+    fn execute_remote_query(
+        &mut self,
+        client: &dyn RemoteQueryClient,
+        subnet_id: SubnetId,
+        request: &Request,
+        callers: &[CanisterId],
+    ) -> Payload {
+        let remote_request = RemoteQueryRequest {
+            sender: request.sender,
+            receiver: request.receiver,
+            method_name: request.method_name.clone(),
+            method_payload: request.method_payload.clone(),
+            instruction_limit: as_num_instructions(self.round_limits.instructions),
+            max_call_graph_depth: self
+                .max_query_call_graph_depth
+                .saturating_sub(callers.len()),
+            time_limit: self
+                .query_context_time_limit
+                .saturating_sub(self.query_context_time_start.elapsed()),
+            call_stack: self
+                .remote_call_stack
+                .iter()
+                .chain(callers)
+                .copied()
+                .collect(),
+        };
+        self.remote_query_calls += 1;
+        let Some(_slot) = self.remote_query_call_limit.try_reserve() else {
+            return Payload::Reject(RejectContext::new(
+                RejectCode::SysTransient,
+                format!(
+                    "Composite query: failed to call canister {} on subnet {}: too many calls to other subnets are outstanding",
+                    request.receiver, subnet_id
+                ),
+            ));
+        };
+        self.round_limits.instructions -= self.instruction_overhead_per_query_call;
+        // Query execution threads are not part of an async runtime, so wait
+        // here for the response. The client bounds the wait by the time limit
+        // and the slot bounds the number of threads waiting at the same time.
+        match futures::executor::block_on(client.query(subnet_id, remote_request)) {
+            Ok(response) => {
+                self.round_limits.instructions -= as_round_instructions(response.instructions_used);
+                response.payload
+            }
+            Err(err) => Payload::Reject(RejectContext::new(
+                RejectCode::SysTransient,
+                format!(
+                    "Composite query: failed to call canister {} on subnet {}: {}",
+                    request.receiver, subnet_id, err
+                ),
+            )),
+        }
+    }
+
     /// Extracts the query result from the call context action.
     fn action_to_result(
         &self,
@@ -1060,10 +1299,11 @@ impl<'a> QueryContext<'a> {
     }
 
     fn get_data_certificate(&self, canister_id: &CanisterId) -> Option<Vec<u8>> {
-        if canister_id != &self.data_certificate.1 {
-            None
-        } else {
-            Some(self.data_certificate.0.clone())
+        match &self.data_certificate {
+            Some((data_certificate, root_canister_id)) if root_canister_id == canister_id => {
+                Some(data_certificate.clone())
+            }
+            _ => None,
         }
     }
 
@@ -1081,4 +1321,9 @@ impl<'a> QueryContext<'a> {
     pub fn nested_execution_errors(&self) -> usize {
         self.nested_execution_errors
     }
+
+    /// Returns the number of query calls forwarded to other subnets.
+    pub fn remote_query_calls(&self) -> usize {
+        self.remote_query_calls
+    }
 }
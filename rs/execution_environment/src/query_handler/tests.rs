@@ -1,17 +1,26 @@
 use crate::InternalHttpQueryHandler;
 use ic_base_types::{CanisterId, NumSeconds};
 use ic_btc_interface::NetworkInRequest as BitcoinNetwork;
-use ic_config::execution_environment::INSTRUCTION_OVERHEAD_PER_QUERY_CALL;
+use ic_config::execution_environment::{Config, INSTRUCTION_OVERHEAD_PER_QUERY_CALL};
 use ic_error_types::{ErrorCode, UserError};
+use ic_interfaces::execution_environment::{
+    RemoteQueryClient, RemoteQueryFuture, RemoteQueryRequest, RemoteQueryResponse,
+};
+use ic_interfaces_state_manager::Labeled;
 use ic_management_canister_types::{BitcoinGetBalanceArgs, BitcoinGetUtxosArgs, Payload};
 use ic_registry_subnet_type::SubnetType;
 use ic_test_utilities::{
-    types::ids::user_test_id,
+    types::ids::{canister_test_id, subnet_test_id, user_test_id},
     universal_canister::{call_args, wasm},
 };
 use ic_test_utilities_execution_environment::{ExecutionTest, ExecutionTestBuilder};
-use ic_types::{ingress::WasmResult, messages::UserQuery, Cycles, NumInstructions};
-use std::sync::Arc;
+use ic_types::{
+    ingress::WasmResult,
+    messages::{self, UserQuery},
+    Cycles, Height, NumInstructions, SubnetId,
+};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 const CYCLES_BALANCE: Cycles = Cycles::new(100_000_000_000_000);
 
@@ -1720,3 +1729,322 @@ fn test_call_context_performance_counter_correctly_reported_on_composite_query()
     assert!(counters[1] < counters[2]);
     assert!(counters[2] < counters[3]);
 }
+
+/// A remote query client that responds to every query call with the same
+/// response and records the calls it receives.
+struct FakeRemoteQueryClient {
+    response: RemoteQueryResponse,
+    calls: Mutex<Vec<(SubnetId, RemoteQueryRequest)>>,
+}
+
+impl FakeRemoteQueryClient {
+    fn new(response: RemoteQueryResponse) -> Arc<Self> {
+        Arc::new(Self {
+            response,
+            calls: Mutex::new(vec![]),
+        })
+    }
+}
+
+impl RemoteQueryClient for FakeRemoteQueryClient {
+    fn query(&self, subnet_id: SubnetId, request: RemoteQueryRequest) -> RemoteQueryFuture {
+        self.calls.lock().unwrap().push((subnet_id, request));
+        Box::pin(std::future::ready(Ok(self.response.clone())))
+    }
+}
+
+#[test]
+fn composite_query_forwards_calls_to_remote_subnet() {
+    let remote_subnet = subnet_test_id(3);
+    let remote_canister = canister_test_id(7);
+    let client = FakeRemoteQueryClient::new(RemoteQueryResponse {
+        payload: messages::Payload::Data(b"remote".to_vec()),
+        instructions_used: NumInstructions::from(1_000),
+    });
+    let mut test = ExecutionTestBuilder::new()
+        .with_composite_queries()
+        .with_caller(remote_subnet, remote_canister)
+        .with_remote_query_client(client.clone())
+        .build();
+    let canister = test.universal_canister_with_cycles(CYCLES_BALANCE).unwrap();
+
+    let other_side = wasm().reply_data(b"pong").build();
+    let result = test.query(
+        UserQuery {
+            source: user_test_id(2),
+            receiver: canister,
+            method_name: "composite_query".to_string(),
+            method_payload: wasm()
+                .composite_query(remote_canister, call_args().other_side(other_side.clone()))
+                .build(),
+            ingress_expiry: 0,
+            nonce: None,
+        },
+        Arc::new(test.state().clone()),
+        vec![],
+    );
+    assert_eq!(result, Ok(WasmResult::Reply(b"remote".to_vec())));
+
+    let calls = client.calls.lock().unwrap();
+    assert_eq!(calls.len(), 1);
+    let (subnet_id, request) = &calls[0];
+    let config = Config::default();
+    assert_eq!(*subnet_id, remote_subnet);
+    assert_eq!(request.sender, canister);
+    assert_eq!(request.receiver, remote_canister);
+    assert_eq!(request.method_name, "composite_query");
+    assert_eq!(request.method_payload, other_side);
+    assert_eq!(
+        request.max_call_graph_depth,
+        config.max_query_call_graph_depth - 1
+    );
+    assert!(request.instruction_limit < config.max_query_call_graph_instructions);
+    assert!(request.time_limit <= config.max_query_call_walltime);
+    assert_eq!(request.call_stack, vec![canister]);
+}
+
+#[test]
+fn composite_query_charges_remote_instructions() {
+    let remote_subnet = subnet_test_id(3);
+    let remote_canister = canister_test_id(7);
+    let instructions_used = Config::default().max_query_call_graph_instructions;
+    let client = FakeRemoteQueryClient::new(RemoteQueryResponse {
+        payload: messages::Payload::Data(b"remote".to_vec()),
+        instructions_used,
+    });
+    let mut test = ExecutionTestBuilder::new()
+        .with_composite_queries()
+        .with_caller(remote_subnet, remote_canister)
+        .with_remote_query_client(client)
+        .build();
+    let canister = test.universal_canister_with_cycles(CYCLES_BALANCE).unwrap();
+
+    let result = test.query(
+        UserQuery {
+            source: user_test_id(2),
+            receiver: canister,
+            method_name: "composite_query".to_string(),
+            method_payload: wasm().composite_query(remote_canister, call_args()).build(),
+            ingress_expiry: 0,
+            nonce: None,
+        },
+        Arc::new(test.state().clone()),
+        vec![],
+    );
+    assert_eq!(
+        result.unwrap_err().code(),
+        ErrorCode::QueryCallGraphTotalInstructionLimitExceeded
+    );
+}
+
+#[test]
+fn remote_query_call_limit_releases_slots_on_drop() {
+    let limit = super::query_context::RemoteQueryCallLimit::new(2);
+    let first = limit.try_reserve();
+    let second = limit.try_reserve();
+    assert!(first.is_some());
+    assert!(second.is_some());
+    assert!(limit.try_reserve().is_none());
+
+    drop(first);
+    let third = limit.try_reserve();
+    assert!(third.is_some());
+    assert!(limit.try_reserve().is_none());
+
+    drop(second);
+    drop(third);
+    assert!(limit.try_reserve().is_some());
+}
+
+#[test]
+fn composite_query_rejects_remote_call_when_too_many_are_outstanding() {
+    let remote_subnet = subnet_test_id(3);
+    let remote_canister = canister_test_id(7);
+    let client = FakeRemoteQueryClient::new(RemoteQueryResponse {
+        payload: messages::Payload::Data(b"remote".to_vec()),
+        instructions_used: NumInstructions::from(1_000),
+    });
+    let mut test = ExecutionTestBuilder::new()
+        .with_composite_queries()
+        .with_caller(remote_subnet, remote_canister)
+        .with_remote_query_client(client.clone())
+        .build();
+    let canister = test.universal_canister_with_cycles(CYCLES_BALANCE).unwrap();
+    let query = UserQuery {
+        source: user_test_id(2),
+        receiver: canister,
+        method_name: "composite_query".to_string(),
+        method_payload: wasm()
+            .composite_query(
+                remote_canister,
+                call_args().on_reject(wasm().reject_message().reject()),
+            )
+            .build(),
+        ingress_expiry: 0,
+        nonce: None,
+    };
+
+    // Occupy all slots, as if other query execution threads were waiting for
+    // the responses of remote calls.
+    let limit = &downcast_query_handler(test.query_handler()).remote_query_call_limit;
+    let slots: Vec<_> = (0..Config::default().max_outstanding_remote_query_calls)
+        .map(|_| limit.try_reserve().unwrap())
+        .collect();
+    match test.query(query.clone(), Arc::new(test.state().clone()), vec![]) {
+        Ok(WasmResult::Reject(message)) => {
+            assert!(message.contains("too many calls to other subnets are outstanding"))
+        }
+        result => panic!("Unexpected result {:?}", result),
+    }
+    assert!(client.calls.lock().unwrap().is_empty());
+
+    // Once a slot is released, the call is forwarded again.
+    drop(slots);
+    let result = test.query(query, Arc::new(test.state().clone()), vec![]);
+    assert_eq!(result, Ok(WasmResult::Reply(b"remote".to_vec())));
+    assert_eq!(client.calls.lock().unwrap().len(), 1);
+}
+
+#[test]
+fn remote_query_is_executed_on_local_canister() {
+    let remote_subnet = subnet_test_id(3);
+    let remote_canister = canister_test_id(7);
+    let mut test = ExecutionTestBuilder::new()
+        .with_composite_queries()
+        .with_caller(remote_subnet, remote_canister)
+        .build();
+    let canister = test.universal_canister_with_cycles(CYCLES_BALANCE).unwrap();
+
+    let query_handler = downcast_query_handler(test.query_handler());
+    let response = query_handler.remote_query(
+        RemoteQueryRequest {
+            sender: remote_canister,
+            receiver: canister,
+            method_name: "query".to_string(),
+            method_payload: wasm().reply_data(b"pong").build(),
+            instruction_limit: NumInstructions::from(1_000_000_000),
+            max_call_graph_depth: 3,
+            time_limit: Duration::from_secs(10),
+            call_stack: vec![remote_canister],
+        },
+        Labeled::new(Height::from(0), Arc::new(test.state().clone())),
+    );
+    assert_eq!(response.payload, messages::Payload::Data(b"pong".to_vec()));
+    assert!(response.instructions_used > NumInstructions::from(0));
+    assert!(response.instructions_used < NumInstructions::from(1_000_000_000));
+}
+
+#[test]
+fn remote_query_to_canister_on_other_subnet_is_rejected() {
+    let remote_subnet = subnet_test_id(3);
+    let remote_canister = canister_test_id(7);
+    let test = ExecutionTestBuilder::new()
+        .with_composite_queries()
+        .with_caller(remote_subnet, remote_canister)
+        .build();
+
+    let query_handler = downcast_query_handler(test.query_handler());
+    let response = query_handler.remote_query(
+        RemoteQueryRequest {
+            sender: remote_canister,
+            receiver: remote_canister,
+            method_name: "query".to_string(),
+            method_payload: vec![],
+            instruction_limit: NumInstructions::from(1_000_000_000),
+            max_call_graph_depth: 3,
+            time_limit: Duration::from_secs(10),
+            call_stack: vec![remote_canister],
+        },
+        Labeled::new(Height::from(0), Arc::new(test.state().clone())),
+    );
+    match response.payload {
+        messages::Payload::Reject(context) => {
+            assert!(context.message().contains("is not hosted by subnet"))
+        }
+        payload => panic!("Unexpected payload {:?}", payload),
+    }
+    assert_eq!(response.instructions_used, NumInstructions::from(0));
+}
+
+#[test]
+fn remote_query_rejects_reentrant_call() {
+    let remote_subnet = subnet_test_id(3);
+    let remote_canister = canister_test_id(7);
+    let client = FakeRemoteQueryClient::new(RemoteQueryResponse {
+        payload: messages::Payload::Data(b"remote".to_vec()),
+        instructions_used: NumInstructions::from(1_000),
+    });
+    let mut test = ExecutionTestBuilder::new()
+        .with_composite_queries()
+        .with_caller(remote_subnet, remote_canister)
+        .with_remote_query_client(client.clone())
+        .build();
+    let canister = test.universal_canister_with_cycles(CYCLES_BALANCE).unwrap();
+
+    // The remote canister calls `canister`, which calls the remote canister
+    // back while it is still waiting for the response.
+    let query_handler = downcast_query_handler(test.query_handler());
+    let response = query_handler.remote_query(
+        RemoteQueryRequest {
+            sender: remote_canister,
+            receiver: canister,
+            method_name: "composite_query".to_string(),
+            method_payload: wasm()
+                .composite_query(
+                    remote_canister,
+                    call_args().on_reject(wasm().reject_message().reject()),
+                )
+                .build(),
+            instruction_limit: NumInstructions::from(1_000_000_000),
+            max_call_graph_depth: 3,
+            time_limit: Duration::from_secs(10),
+            call_stack: vec![remote_canister],
+        },
+        Labeled::new(Height::from(0), Arc::new(test.state().clone())),
+    );
+    match response.payload {
+        messages::Payload::Reject(context) => {
+            assert!(context.message().contains("re-entrant call"))
+        }
+        payload => panic!("Unexpected payload {:?}", payload),
+    }
+    assert!(client.calls.lock().unwrap().is_empty());
+}
+
+#[test]
+fn remote_query_to_frozen_canister_is_rejected() {
+    let remote_subnet = subnet_test_id(3);
+    let remote_canister = canister_test_id(7);
+    let mut test = ExecutionTestBuilder::new()
+        .with_composite_queries()
+        .with_caller(remote_subnet, remote_canister)
+        .build();
+    // Same amount of cycles as in `queries_to_frozen_canisters_are_rejected`.
+    let canister = test
+        .universal_canister_with_cycles(Cycles::new(80_000_631_070))
+        .unwrap();
+    test.update_freezing_threshold(canister, NumSeconds::from(3_000_000_000))
+        .unwrap();
+
+    let query_handler = downcast_query_handler(test.query_handler());
+    let response = query_handler.remote_query(
+        RemoteQueryRequest {
+            sender: remote_canister,
+            receiver: canister,
+            method_name: "query".to_string(),
+            method_payload: wasm().reply_data(b"pong").build(),
+            instruction_limit: NumInstructions::from(1_000_000_000),
+            max_call_graph_depth: 3,
+            time_limit: Duration::from_secs(10),
+            call_stack: vec![remote_canister],
+        },
+        Labeled::new(Height::from(0), Arc::new(test.state().clone())),
+    );
+    match response.payload {
+        messages::Payload::Reject(context) => {
+            assert!(context.message().contains("because it's frozen"))
+        }
+        payload => panic!("Unexpected payload {:?}", payload),
+    }
+    assert_eq!(response.instructions_used, NumInstructions::from(0));
+}
@@ -21,7 +21,9 @@ use ic_management_canister_types::{CanisterStatusType, EcdsaKeyId, Method as Ic0
 use ic_metrics::MetricsRegistry;
 use ic_replicated_state::{
     canister_state::{
-        execution_state::NextScheduledMethod, system_state::CyclesUseCase, NextExecution,
+        execution_state::NextScheduledMethod,
+        system_state::{CyclesUseCase, LowResourceHookStatus},
+        NextExecution,
     },
     page_map::PageAllocatorFileDescriptor,
     CanisterState, CanisterStatus, ExecutionTask, InputQueueType, NetworkTopology, ReplicatedState,
@@ -562,13 +564,14 @@ impl SchedulerImpl {
         (new_state, message_instructions)
     }
 
-    /// Invoked in the first iteration of the inner round to add the `Heartbeat`
-    /// and `GlobalTimer` tasks that are carried out prior to processing
-    /// any input messages.
+    /// Invoked in the first iteration of the inner round to add the `Heartbeat`,
+    /// `GlobalTimer`, `OnLowWasmMemory` and `OnLowCycles` tasks that are
+    /// carried out prior to processing any input messages.
     /// It also returns the list of canisters that have non-zero priority credit.
     fn initialize_inner_round(
         &self,
         state: &mut ReplicatedState,
+        subnet_size: usize,
     ) -> (BTreeSet<CanisterId>, BTreeSet<CanisterId>) {
         let _timer = self
             .metrics
@@ -585,7 +588,8 @@ impl SchedulerImpl {
                 non_zero_priority_credit_canister_ids.insert(canister.system_state.canister_id);
             }
 
-            // Add `Heartbeat` or `GlobalTimer` for running canisters only.
+            // Add `Heartbeat`, `GlobalTimer` or the low resource hooks for
+            // running canisters only.
             match canister.system_state.status {
                 CanisterStatus::Running { .. } => {}
                 CanisterStatus::Stopping { .. } | CanisterStatus::Stopped => {
@@ -593,12 +597,17 @@ impl SchedulerImpl {
                 }
             }
 
+            let low_resource_hook_tasks = self.low_resource_hook_tasks(canister, subnet_size);
             let may_schedule_heartbeat = canister.exports_heartbeat_method();
             let may_schedule_global_timer = canister.exports_global_timer_method()
                 && canister.system_state.global_timer.has_reached_deadline(now);
 
-            if !may_schedule_heartbeat && !may_schedule_global_timer {
-                // Canister has no heartbeat and no (schedulable) global timer.
+            if low_resource_hook_tasks.is_empty()
+                && !may_schedule_heartbeat
+                && !may_schedule_global_timer
+            {
+                // Canister has no low resource hook to run, no heartbeat and
+                // no (schedulable) global timer.
                 continue;
             }
 
@@ -608,20 +617,31 @@ impl SchedulerImpl {
                     // is pending.
                 }
                 NextExecution::None | NextExecution::StartNew => {
-                    for _ in 0..NextScheduledMethod::NUMBER_OF_VARIANTS {
-                        let method_chosen = is_next_method_chosen(
-                            canister,
-                            &mut heartbeat_and_timer_canister_ids,
-                            may_schedule_heartbeat,
-                            may_schedule_global_timer,
-                        );
+                    if may_schedule_heartbeat || may_schedule_global_timer {
+                        for _ in 0..NextScheduledMethod::NUMBER_OF_VARIANTS {
+                            let method_chosen = is_next_method_chosen(
+                                canister,
+                                &mut heartbeat_and_timer_canister_ids,
+                                may_schedule_heartbeat,
+                                may_schedule_global_timer,
+                            );
 
-                        canister.inc_next_scheduled_method();
+                            canister.inc_next_scheduled_method();
 
-                        if method_chosen {
-                            break;
+                            if method_chosen {
+                                break;
+                            }
                         }
                     }
+
+                    // The low resource hooks go in front of all other tasks so
+                    // that the canister can react before the resource runs out.
+                    if !low_resource_hook_tasks.is_empty() {
+                        for task in low_resource_hook_tasks.into_iter().rev() {
+                            canister.system_state.task_queue.push_front(task);
+                        }
+                        heartbeat_and_timer_canister_ids.insert(canister.canister_id());
+                    }
                 }
             }
         }
@@ -631,6 +651,68 @@ impl SchedulerImpl {
         )
     }
 
+    /// Updates the status of the low resource hooks of the given canister and
+    /// returns the tasks of the hooks that should be run in this round.
+    ///
+    /// A hook becomes ready once its resource is low and runs only once until
+    /// the resource recovers. The Wasm memory is low if the memory remaining
+    /// below the Wasm memory limit falls below the canister's
+    /// `wasm_memory_threshold` setting. The cycles balance is low if the canister
+    /// would reach its freezing threshold within `low_cycles_threshold` while
+    /// staying idle.
+    fn low_resource_hook_tasks(
+        &self,
+        canister: &mut CanisterState,
+        subnet_size: usize,
+    ) -> Vec<ExecutionTask> {
+        let mut tasks = Vec::new();
+
+        // Skip the checks if the canister doesn't export the hook and there is
+        // no status to reset.
+        let exports_on_low_wasm_memory = canister.exports_on_low_wasm_memory_method();
+        if exports_on_low_wasm_memory
+            || canister.system_state.on_low_wasm_memory_hook_status
+                != LowResourceHookStatus::ConditionNotSatisfied
+        {
+            let is_low = canister.is_low_wasm_memory();
+            if canister
+                .system_state
+                .on_low_wasm_memory_hook_status
+                .update(is_low)
+                && exports_on_low_wasm_memory
+            {
+                tasks.push(ExecutionTask::OnLowWasmMemory);
+            }
+        }
+
+        let exports_on_low_cycles = canister.exports_on_low_cycles_method();
+        if exports_on_low_cycles
+            || canister.system_state.on_low_cycles_hook_status
+                != LowResourceHookStatus::ConditionNotSatisfied
+        {
+            let low_cycles_threshold = self.cycles_account_manager.freeze_threshold_cycles(
+                canister.system_state.freeze_threshold + self.config.low_cycles_threshold,
+                canister.system_state.memory_allocation,
+                canister.memory_usage(),
+                canister.message_memory_usage(),
+                canister.compute_allocation(),
+                subnet_size,
+                canister.system_state.reserved_balance(),
+            );
+            let is_low = canister.system_state.balance() < low_cycles_threshold;
+            if canister
+                .system_state
+                .on_low_cycles_hook_status
+                .update(is_low)
+                && exports_on_low_cycles
+            {
+                tasks.push(ExecutionTask::OnLowCycles);
+            }
+        }
+
+        tasks
+    }
+
     /// Performs multiple iterations of canister execution until the instruction
     /// limit per round is reached or the canisters become idle. The canisters
     /// are executed in parallel using the thread pool.
@@ -708,12 +790,13 @@ impl SchedulerImpl {
             let mut round_limits = scheduler_round_limits.canister_round_limits();
             let preparation_timer = self.metrics.round_inner_iteration_prep.start_timer();
 
-            // Add `Heartbeat` and `GlobalTimer` tasks to be executed before input messages.
+            // Add `Heartbeat`, `GlobalTimer` and low resource hook tasks to be
+            // executed before input messages.
             if is_first_iteration {
                 (
                     heartbeat_and_timer_canister_ids,
                     non_zero_priority_credit_canister_ids,
-                ) = self.initialize_inner_round(&mut state)
+                ) = self.initialize_inner_round(&mut state, registry_settings.subnet_size)
             }
 
             // Update subnet available memory before taking out the canisters.
@@ -812,12 +895,15 @@ impl SchedulerImpl {
                 .metrics
                 .round_inner_heartbeat_overhead_duration
                 .start_timer();
-            // Remove all remaining `Heartbeat` and `GlobalTimer` tasks
-            // because they will be added again in the next round.
+            // Remove all remaining `Heartbeat`, `GlobalTimer` and low resource
+            // hook tasks because they will be added again in the next round.
             for canister_id in &heartbeat_and_timer_canister_ids {
                 let canister = state.canister_state_mut(canister_id).unwrap();
                 canister.system_state.task_queue.retain(|task| match task {
-                    ExecutionTask::Heartbeat | ExecutionTask::GlobalTimer => false,
+                    ExecutionTask::Heartbeat
+                    | ExecutionTask::GlobalTimer
+                    | ExecutionTask::OnLowWasmMemory
+                    | ExecutionTask::OnLowCycles => false,
                     ExecutionTask::PausedExecution(..)
                     | ExecutionTask::PausedInstallCode(..)
                     | ExecutionTask::AbortedExecution { .. }
@@ -1346,8 +1432,8 @@ impl SchedulerImpl {
             .iter()
             .filter(|(_, canister)| !canister.system_state.task_queue.is_empty());
 
-        // 1. Heartbeat and GlobalTimer tasks exist only during the round
-        //    and must not exist after the round.
+        // 1. Heartbeat, GlobalTimer and low resource hook tasks exist only
+        //    during the round and must not exist after the round.
         // 2. Paused executions can exist only in ordinary rounds (not checkpoint rounds).
         // 3. If deterministic time slicing is disabled, then there are no paused tasks.
         //    Aborted tasks may still exist if DTS was disabled in recent checkpoints.
@@ -1368,6 +1454,12 @@ impl SchedulerImpl {
                             id
                         );
                     }
+                    ExecutionTask::OnLowWasmMemory | ExecutionTask::OnLowCycles => {
+                        panic!(
+                            "Unexpected low resource hook task {:?} after a round in canister {:?}",
+                            task, id
+                        );
+                    }
                     ExecutionTask::PausedExecution(_) | ExecutionTask::PausedInstallCode(_) => {
                         assert_eq!(
                             self.deterministic_time_slicing,
@@ -2091,7 +2183,11 @@ fn observe_replicated_state_metrics(
             Some(&ExecutionTask::AbortedInstallCode { .. }) => {
                 num_aborted_install += 1;
             }
-            Some(&ExecutionTask::Heartbeat) | Some(&ExecutionTask::GlobalTimer) | None => {}
+            Some(&ExecutionTask::Heartbeat)
+            | Some(&ExecutionTask::GlobalTimer)
+            | Some(&ExecutionTask::OnLowWasmMemory)
+            | Some(&ExecutionTask::OnLowCycles)
+            | None => {}
         }
         consumed_cycles_total += canister
             .system_state
@@ -2326,6 +2422,7 @@ fn get_instructions_limits_for_subnet_message(
             | BitcoinGetCurrentFeePercentiles
             | BitcoinGetSuccessors
             | NodeMetricsHistory
+            | SubnetInfo
             | FetchCanisterLogs
             | ProvisionalCreateCanisterWithCycles
             | ProvisionalTopUpCanister
@@ -2336,7 +2433,11 @@ fn get_instructions_limits_for_subnet_message(
             | TakeCanisterSnapshot
             | LoadCanisterSnapshot
             | ListCanisterSnapshots
-            | DeleteCanisterSnapshot => default_limits,
+            | DeleteCanisterSnapshot
+            | ReadCanisterSnapshotMetadata
+            | ReadCanisterSnapshotData
+            | UploadCanisterSnapshotMetadata
+            | UploadCanisterSnapshotData => default_limits,
             InstallCode | InstallChunkedCode => InstructionLimits::new(
                 dts,
                 config.max_instructions_per_install_code,
@@ -16,6 +16,7 @@ use ic_embedders::{
         CanisterStateChanges, PausedWasmExecution, SliceExecutionOutput, WasmExecutionResult,
         WasmExecutor,
     },
+    wasm_utils::WasmMemoryDeclaration,
     CompilationCache, CompilationResult, WasmExecutionInput,
 };
 use ic_error_types::UserError;
@@ -1007,7 +1008,12 @@ impl WasmExecutor for TestWasmExecutor {
         _canister_root: PathBuf,
         canister_id: CanisterId,
         _compilation_cache: Arc<CompilationCache>,
-    ) -> HypervisorResult<(ExecutionState, NumInstructions, Option<CompilationResult>)> {
+    ) -> HypervisorResult<(
+        ExecutionState,
+        NumInstructions,
+        Option<CompilationResult>,
+        Option<WasmMemoryDeclaration>,
+    )> {
         let mut guard = self.core.lock().unwrap();
         guard.create_execution_state(canister_module, canister_id)
     }
@@ -1136,7 +1142,12 @@ impl TestWasmExecutorCore {
         &mut self,
         canister_module: CanisterModule,
         _canister_id: CanisterId,
-    ) -> HypervisorResult<(ExecutionState, NumInstructions, Option<CompilationResult>)> {
+    ) -> HypervisorResult<(
+        ExecutionState,
+        NumInstructions,
+        Option<CompilationResult>,
+        Option<WasmMemoryDeclaration>,
+    )> {
         let mut exported_functions = vec![
             WasmMethod::Update("update".into()),
             WasmMethod::System(SystemMethod::CanisterPostUpgrade),
@@ -1163,6 +1174,7 @@ impl TestWasmExecutorCore {
             execution_state,
             NumInstructions::from(0),
             Some(compilation_result),
+            None,
         ))
     }
 
@@ -5,8 +5,8 @@ use ic_error_types::{ErrorCode, UserError};
 use ic_management_canister_types::CanisterInstallMode::{Install, Reinstall, Upgrade};
 use ic_management_canister_types::{
     self as ic00, CanisterChange, CanisterChangeDetails, CanisterChangeOrigin, CanisterIdRecord,
-    CanisterInfoRequest, CanisterInfoResponse, CreateCanisterArgs, InstallCodeArgs, Method,
-    Payload, UpdateSettingsArgs,
+    CanisterInfoRequest, CanisterInfoResponse, CreateCanisterArgs, InstallCodeArgs, LogVisibility,
+    Method, Payload, UpdateSettingsArgs,
 };
 use ic_registry_subnet_type::SubnetType;
 use ic_replicated_state::canister_state::system_state::{
@@ -534,6 +534,145 @@ fn canister_history_tracks_controllers_change() {
     }
 }
 
+#[test]
+fn canister_history_tracks_settings_change() {
+    let mut now = std::time::SystemTime::now();
+    let (env, _test_canister, _test_canister_sha256) = test_setup(SubnetType::Application, now);
+
+    // declare user IDs
+    let user_id1 = user_test_id(7).get();
+    let user_id2 = user_test_id(8).get();
+
+    // create canister via ingress from user_id1
+    let wasm_result = env
+        .execute_ingress_as(
+            user_id1,
+            ic00::IC_00,
+            ic00::Method::ProvisionalCreateCanisterWithCycles,
+            ic00::ProvisionalCreateCanisterWithCyclesArgs {
+                amount: Some(candid::Nat::from(INITIAL_CYCLES_BALANCE.get())),
+                settings: Some(
+                    CanisterSettingsArgsBuilder::new()
+                        .with_controllers(vec![user_id1])
+                        .build(),
+                ),
+                specified_id: None,
+                sender_canister_version: None,
+            }
+            .encode(),
+        )
+        .expect("failed to create canister");
+    let canister_id = match wasm_result {
+        WasmResult::Reply(bytes) => CanisterIdRecord::decode(&bytes[..])
+            .expect("failed to decode canister ID record")
+            .get_canister_id(),
+        WasmResult::Reject(reason) => panic!("create_canister call rejected: {}", reason),
+    };
+    // update reference canister history
+    let mut reference_change_entries: Vec<CanisterChange> = vec![CanisterChange::new(
+        now.duration_since(UNIX_EPOCH).unwrap().as_nanos() as u64,
+        0,
+        CanisterChangeOrigin::from_user(user_id1),
+        CanisterChangeDetails::canister_creation(vec![user_id1]),
+    )];
+
+    // update freezing threshold and log visibility via ingress from user_id1
+    now += Duration::from_secs(5);
+    env.set_time(now);
+    env.tick();
+    env.execute_ingress_as(
+        user_id1,
+        ic00::IC_00,
+        Method::UpdateSettings,
+        UpdateSettingsArgs {
+            canister_id: canister_id.into(),
+            settings: CanisterSettingsArgsBuilder::new()
+                .with_freezing_threshold(1_000_000)
+                .with_log_visibility(LogVisibility::Public)
+                .build(),
+            sender_canister_version: None,
+        }
+        .encode(),
+    )
+    .unwrap();
+    // update reference canister history
+    reference_change_entries.push(CanisterChange::new(
+        now.duration_since(UNIX_EPOCH).unwrap().as_nanos() as u64,
+        1,
+        CanisterChangeOrigin::from_user(user_id1),
+        CanisterChangeDetails::settings_change(
+            Some(1_000_000),
+            None,
+            None,
+            Some(LogVisibility::Public),
+        ),
+    ));
+
+    // update controllers and allocations via ingress from user_id1
+    now += Duration::from_secs(5);
+    env.set_time(now);
+    env.tick();
+    env.execute_ingress_as(
+        user_id1,
+        ic00::IC_00,
+        Method::UpdateSettings,
+        UpdateSettingsArgs {
+            canister_id: canister_id.into(),
+            settings: CanisterSettingsArgsBuilder::new()
+                .with_controllers(vec![user_id1, user_id2])
+                .with_compute_allocation(1)
+                .with_memory_allocation(1 << 30)
+                .build(),
+            sender_canister_version: None,
+        }
+        .encode(),
+    )
+    .unwrap();
+    // update reference canister history: both changes share the canister version
+    reference_change_entries.push(CanisterChange::new(
+        now.duration_since(UNIX_EPOCH).unwrap().as_nanos() as u64,
+        2,
+        CanisterChangeOrigin::from_user(user_id1),
+        CanisterChangeDetails::controllers_change(vec![user_id1, user_id2]),
+    ));
+    reference_change_entries.push(CanisterChange::new(
+        now.duration_since(UNIX_EPOCH).unwrap().as_nanos() as u64,
+        2,
+        CanisterChangeOrigin::from_user(user_id1),
+        CanisterChangeDetails::settings_change(None, Some(1), Some(1 << 30), None),
+    ));
+
+    // update the reserved cycles limit via ingress from user_id2 (not tracked in canister history)
+    now += Duration::from_secs(5);
+    env.set_time(now);
+    env.tick();
+    env.execute_ingress_as(
+        user_id2,
+        ic00::IC_00,
+        Method::UpdateSettings,
+        UpdateSettingsArgs {
+            canister_id: canister_id.into(),
+            settings: CanisterSettingsArgsBuilder::new()
+                .with_reserved_cycles_limit(1_000_000_000_000)
+                .build(),
+            sender_canister_version: None,
+        }
+        .encode(),
+    )
+    .unwrap();
+
+    // check canister history
+    let history = get_canister_history(&env, canister_id);
+    assert_eq!(history.get_total_num_changes(), 4);
+    assert_eq!(
+        history
+            .get_changes(history.get_total_num_changes() as usize)
+            .map(|c| (**c).clone())
+            .collect::<Vec<CanisterChange>>(),
+        reference_change_entries
+    );
+}
+
 #[test]
 fn canister_history_cleared_if_canister_out_of_cycles() {
     let mut now = std::time::SystemTime::now();
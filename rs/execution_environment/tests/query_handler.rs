@@ -51,6 +51,7 @@ async fn query_non_existent() {
             cycles_account_manager,
             state_manager,
             Arc::new(TestPageAllocatorFileDescriptorImpl::new()),
+            None,
         );
 
         let receiver = CanisterId::from(1234);
@@ -15,12 +15,16 @@ use ic_types::{
     ingress::{IngressStatus, WasmResult},
     messages::{
         AnonymousQuery, AnonymousQueryResponse, CertificateDelegation, HttpQueryResponse,
-        MessageId, SignedIngressContent, UserQuery,
+        MessageId, Payload, SignedIngressContent, UserQuery,
     },
-    Cycles, ExecutionRound, Height, NumInstructions, NumPages, Randomness, Time,
+    CanisterId, Cycles, ExecutionRound, Height, NodeId, NumInstructions, NumPages, Randomness,
+    SubnetId, Time,
 };
 use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::pin::Pin;
 use std::sync::Arc;
+use std::time::Duration;
 use std::{collections::BTreeMap, ops};
 use std::{collections::BTreeSet, convert::TryFrom};
 use std::{convert::Infallible, fmt};
@@ -456,6 +460,86 @@ pub trait QueryHandler: Send + Sync {
     ) -> Result<WasmResult, UserError>;
 }
 
+/// A query call made by a canister during the evaluation of a composite query
+/// call graph to a canister that is hosted on another subnet.
+///
+/// The limits are what is left of the limits of the whole call graph at the
+/// time the call is made, so that the remote subnet cannot exceed them.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RemoteQueryRequest {
+    pub sender: CanisterId,
+    pub receiver: CanisterId,
+    pub method_name: String,
+    #[serde(with = "serde_bytes")]
+    pub method_payload: Vec<u8>,
+    /// The number of instructions left for the query call graph.
+    pub instruction_limit: NumInstructions,
+    /// The remaining depth of the query call graph.
+    pub max_call_graph_depth: usize,
+    /// The walltime left for the query call graph.
+    pub time_limit: Duration,
+    /// The canisters on the path from the root of the query call graph to the
+    /// sender (inclusive), across all subnets the call graph spans so far.
+    /// Used to reject query calls that re-enter one of these canisters.
+    pub call_stack: Vec<CanisterId>,
+}
+
+/// The response to a [`RemoteQueryRequest`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RemoteQueryResponse {
+    /// The reply or reject produced by the called canister.
+    pub payload: Payload,
+    /// The number of instructions executed on the remote subnet, to be
+    /// charged against the instruction limit of the query call graph.
+    pub instructions_used: NumInstructions,
+}
+
+/// Errors that can occur when executing a query call on a remote subnet.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RemoteQueryError {
+    /// The calling node is not a node of the subnet hosting the sender.
+    Unauthorized(NodeId),
+    /// The node has no certified state to execute the query against.
+    CertifiedStateUnavailable,
+    /// The request could not be delivered or the response could not be
+    /// received, e.g. because the time limit elapsed.
+    Transport(String),
+}
+
+impl fmt::Display for RemoteQueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unauthorized(node_id) => {
+                write!(f, "Node {} is not allowed to make query calls", node_id)
+            }
+            Self::CertifiedStateUnavailable => write!(f, "Certified state is not available yet"),
+            Self::Transport(err) => write!(f, "Transport error: {}", err),
+        }
+    }
+}
+
+/// The future returned by [`RemoteQueryClient::query`].
+pub type RemoteQueryFuture =
+    Pin<Box<dyn Future<Output = Result<RemoteQueryResponse, RemoteQueryError>> + Send>>;
+
+/// Interface for forwarding query calls to canisters hosted on other subnets.
+pub trait RemoteQueryClient: Send + Sync {
+    /// Sends the query call to a node of the given subnet. The returned future
+    /// resolves once the node responds or the time limit of the request elapses.
+    fn query(&self, subnet_id: SubnetId, request: RemoteQueryRequest) -> RemoteQueryFuture;
+}
+
+/// Interface for executing query calls received from other subnets.
+pub trait RemoteQueryHandler: Send + Sync {
+    /// Executes the query call made by a canister on the subnet of the given
+    /// (authenticated) node against the latest certified state.
+    fn query(
+        &self,
+        peer: NodeId,
+        request: RemoteQueryRequest,
+    ) -> Result<RemoteQueryResponse, RemoteQueryError>;
+}
+
 /// Errors that can be returned when reading/writing from/to ingress history.
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum IngressHistoryError {
@@ -1101,6 +1185,56 @@ pub trait SystemApi {
         dst: u32,
         heap: &mut [u8],
     ) -> HypervisorResult<()>;
+
+    /// Copies to `dst` the amount of cycles charged for an inter-canister
+    /// call with a method name of `method_name_size` bytes and a payload of
+    /// `payload_size` bytes, including the prepayment for the response.
+    ///
+    /// The amount is a 128-bit value in little-endian encoding.
+    fn ic0_cost_call(
+        &self,
+        method_name_size: u64,
+        payload_size: u64,
+        dst: u32,
+        heap: &mut [u8],
+    ) -> HypervisorResult<()>;
+
+    /// Copies to `dst` the amount of cycles charged for creating a canister.
+    ///
+    /// The amount is a 128-bit value in little-endian encoding.
+    fn ic0_cost_create_canister(&self, dst: u32, heap: &mut [u8]) -> HypervisorResult<()>;
+
+    /// Copies to `dst` the amount of cycles charged for an HTTP outcall with
+    /// a request of `request_size` bytes and a response limited to
+    /// `max_res_bytes` bytes.
+    ///
+    /// The amount is a 128-bit value in little-endian encoding.
+    fn ic0_cost_http_request(
+        &self,
+        request_size: u64,
+        max_res_bytes: u64,
+        dst: u32,
+        heap: &mut [u8],
+    ) -> HypervisorResult<()>;
+
+    /// Copies to `dst` the amount of cycles charged for a threshold ECDSA
+    /// signature with the key identified by the name in src/size and the
+    /// given curve. The fee is the one charged by the subnet that signs with
+    /// the key, and is zero for canisters on the NNS subnet.
+    ///
+    /// Returns 0 on success, 1 if the curve is unknown and 2 if no subnet is
+    /// enabled to sign with the key. Nothing is copied in the latter cases.
+    ///
+    /// This system call traps if src+size or dst+16 exceeds the size of the
+    /// WebAssembly memory.
+    fn ic0_cost_sign_with_ecdsa(
+        &self,
+        src: u32,
+        size: u32,
+        ecdsa_curve: u32,
+        dst: u32,
+        heap: &mut [u8],
+    ) -> HypervisorResult<u32>;
 }
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
@@ -41,7 +41,8 @@ use ic_types::{
     malicious_flags::MaliciousFlags,
     registry::RegistryClientError,
     xnet::{StreamHeader, StreamIndex},
-    Height, NodeId, NumBytes, PrincipalIdBlobParseError, RegistryVersion, SubnetId, Time,
+    Height, NodeId, NumBytes, PrincipalIdBlobParseError, RegistryVersion, ReplicaVersion, SubnetId,
+    Time,
 };
 use ic_utils::thread::JoinOnDrop;
 #[cfg(test)]
@@ -845,6 +846,13 @@ impl BatchProcessorImpl {
                 })
                 .transpose()?
                 .unwrap_or_default();
+            let replica_version = ReplicaVersion::try_from(subnet_record.replica_version_id)
+                .map_err(|err| {
+                    Persistent(format!(
+                        "'replica version from subnet record for subnet {}', err: {}",
+                        *subnet_id, err
+                    ))
+                })?;
 
             subnets.insert(
                 *subnet_id,
@@ -854,6 +862,7 @@ impl BatchProcessorImpl {
                     subnet_type,
                     subnet_features,
                     ecdsa_keys_held,
+                    replica_version,
                 },
             );
         }
@@ -21,7 +21,7 @@ use ic_test_utilities_metrics::fetch_int_counter_vec;
 use ic_types::consensus::ecdsa::QuadrupleId;
 use ic_types::messages::SignedIngress;
 use ic_types::{batch::BatchMessages, crypto::canister_threshold_sig::MasterEcdsaPublicKey};
-use ic_types::{Height, PrincipalId, SubnetId, Time};
+use ic_types::{Height, PrincipalId, ReplicaVersion, SubnetId, Time};
 use maplit::btreemap;
 use mockall::{mock, predicate::*, Sequence};
 use std::collections::{BTreeMap, BTreeSet};
@@ -114,6 +114,7 @@ fn test_fixture(provided_batch: &Batch) -> StateMachineTestFixture {
             subnet_type: SubnetType::Application,
             subnet_features: SubnetFeatures::default(),
             ecdsa_keys_held: BTreeSet::new(),
+            replica_version: ReplicaVersion::default(),
         },
     );
 
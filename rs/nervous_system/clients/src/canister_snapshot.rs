@@ -0,0 +1,50 @@
+use candid::CandidType;
+use ic_base_types::PrincipalId;
+use ic_management_canister_types::IC_00;
+use ic_nervous_system_runtime::Runtime;
+use serde::Deserialize;
+
+/// The argument of the `take_canister_snapshot` management canister method.
+#[derive(Clone, PartialEq, Eq, Debug, CandidType, Deserialize)]
+pub struct TakeCanisterSnapshotArgs {
+    pub canister_id: PrincipalId,
+    /// If set, the new snapshot replaces (i.e. the management canister deletes) this one.
+    pub replace_snapshot: Option<Vec<u8>>,
+}
+
+/// The argument of the `load_canister_snapshot` management canister method.
+#[derive(Clone, PartialEq, Eq, Debug, CandidType, Deserialize)]
+pub struct LoadCanisterSnapshotArgs {
+    pub canister_id: PrincipalId,
+    pub snapshot_id: Vec<u8>,
+    pub sender_canister_version: Option<u64>,
+}
+
+/// Describes a canister snapshot, as returned by `take_canister_snapshot`.
+#[derive(Clone, PartialEq, Eq, Debug, CandidType, Deserialize)]
+pub struct CanisterSnapshot {
+    /// The (opaque) ID of the snapshot, a `blob` in the management canister's interface.
+    pub id: Vec<u8>,
+    pub taken_at_timestamp: u64,
+    pub total_size: u64,
+}
+
+/// A wrapper call to the management canister `take_canister_snapshot` API.
+pub async fn take_canister_snapshot<Rt>(
+    args: TakeCanisterSnapshotArgs,
+) -> Result<CanisterSnapshot, (i32, String)>
+where
+    Rt: Runtime,
+{
+    Rt::call_with_cleanup(IC_00, "take_canister_snapshot", (args,))
+        .await
+        .map(|response: (CanisterSnapshot,)| response.0)
+}
+
+/// A wrapper call to the management canister `load_canister_snapshot` API.
+pub async fn load_canister_snapshot<Rt>(args: LoadCanisterSnapshotArgs) -> Result<(), (i32, String)>
+where
+    Rt: Runtime,
+{
+    Rt::call_with_cleanup(IC_00, "load_canister_snapshot", (args,)).await
+}
@@ -1,4 +1,5 @@
 pub mod canister_id_record;
+pub mod canister_snapshot;
 pub mod canister_status;
 pub mod management_canister_client;
 pub mod update_settings;
@@ -1,5 +1,9 @@
 use crate::{
     canister_id_record::CanisterIdRecord,
+    canister_snapshot::{
+        load_canister_snapshot, take_canister_snapshot, CanisterSnapshot, LoadCanisterSnapshotArgs,
+        TakeCanisterSnapshotArgs,
+    },
     canister_status::{canister_status, CanisterStatusResultFromManagementCanister},
     update_settings::{update_settings, UpdateSettings},
 };
@@ -32,6 +36,18 @@ pub trait ManagementCanisterClient {
     /// A call to the `update_settings` management canister endpoint.
     async fn update_settings(&self, settings: UpdateSettings) -> Result<(), (i32, String)>;
 
+    /// A call to the `take_canister_snapshot` management canister endpoint.
+    async fn take_canister_snapshot(
+        &self,
+        args: TakeCanisterSnapshotArgs,
+    ) -> Result<CanisterSnapshot, (i32, String)>;
+
+    /// A call to the `load_canister_snapshot` management canister endpoint.
+    async fn load_canister_snapshot(
+        &self,
+        args: LoadCanisterSnapshotArgs,
+    ) -> Result<(), (i32, String)>;
+
     fn canister_version(&self) -> Option<u64>;
 }
 
@@ -92,6 +108,42 @@ impl<Rt: Runtime + Sync> ManagementCanisterClient for ManagementCanisterClientIm
         update_settings::<Rt>(settings).await
     }
 
+    async fn take_canister_snapshot(
+        &self,
+        args: TakeCanisterSnapshotArgs,
+    ) -> Result<CanisterSnapshot, (i32, String)> {
+        let _tracker = self.proxied_canister_calls_tracker.map(|tracker| {
+            let encoded_args = Encode!(&args).unwrap_or_default();
+            ProxiedCanisterCallsTracker::start_tracking(
+                tracker,
+                dfn_core::api::caller(),
+                IC_00,
+                "take_canister_snapshot",
+                &encoded_args,
+            )
+        });
+
+        take_canister_snapshot::<Rt>(args).await
+    }
+
+    async fn load_canister_snapshot(
+        &self,
+        args: LoadCanisterSnapshotArgs,
+    ) -> Result<(), (i32, String)> {
+        let _tracker = self.proxied_canister_calls_tracker.map(|tracker| {
+            let encoded_args = Encode!(&args).unwrap_or_default();
+            ProxiedCanisterCallsTracker::start_tracking(
+                tracker,
+                dfn_core::api::caller(),
+                IC_00,
+                "load_canister_snapshot",
+                &encoded_args,
+            )
+        });
+
+        load_canister_snapshot::<Rt>(args).await
+    }
+
     fn canister_version(&self) -> Option<u64> {
         Some(dfn_core::api::canister_version())
     }
@@ -179,6 +231,22 @@ where
         self.inner.update_settings(settings).await
     }
 
+    async fn take_canister_snapshot(
+        &self,
+        args: TakeCanisterSnapshotArgs,
+    ) -> Result<CanisterSnapshot, (i32, String)> {
+        let _loan = self.try_borrow_slot()?;
+        self.inner.take_canister_snapshot(args).await
+    }
+
+    async fn load_canister_snapshot(
+        &self,
+        args: LoadCanisterSnapshotArgs,
+    ) -> Result<(), (i32, String)> {
+        let _loan = self.try_borrow_slot()?;
+        self.inner.load_canister_snapshot(args).await
+    }
+
     fn canister_version(&self) -> Option<u64> {
         // This does not actually call the management canister. This implies a few things:
         //
@@ -236,12 +304,16 @@ impl MockManagementCanisterClient {
 pub enum MockManagementCanisterClientCall {
     CanisterStatus(CanisterIdRecord),
     UpdateSettings(UpdateSettings),
+    TakeCanisterSnapshot(TakeCanisterSnapshotArgs),
+    LoadCanisterSnapshot(LoadCanisterSnapshotArgs),
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum MockManagementCanisterClientReply {
     CanisterStatus(Result<CanisterStatusResultFromManagementCanister, (i32, String)>),
     UpdateSettings(Result<(), (i32, String)>),
+    TakeCanisterSnapshot(Result<CanisterSnapshot, (i32, String)>),
+    LoadCanisterSnapshot(Result<(), (i32, String)>),
 }
 
 #[async_trait]
@@ -297,6 +369,58 @@ impl ManagementCanisterClient for MockManagementCanisterClient {
         }
     }
 
+    async fn take_canister_snapshot(
+        &self,
+        args: TakeCanisterSnapshotArgs,
+    ) -> Result<CanisterSnapshot, (i32, String)> {
+        self.calls
+            .lock()
+            .unwrap()
+            .push_back(MockManagementCanisterClientCall::TakeCanisterSnapshot(args));
+
+        let reply = self
+            .replies
+            .lock()
+            .unwrap()
+            .pop_front()
+            .expect("Expected a MockManagementCanisterClientCall to be on the queue.");
+
+        match reply {
+            MockManagementCanisterClientReply::TakeCanisterSnapshot(response) => response,
+            err => panic!(
+                "Expected MockManagementCanisterClientReply::TakeCanisterSnapshot to be at \
+                the front of the queue. Had {:?}",
+                err
+            ),
+        }
+    }
+
+    async fn load_canister_snapshot(
+        &self,
+        args: LoadCanisterSnapshotArgs,
+    ) -> Result<(), (i32, String)> {
+        self.calls
+            .lock()
+            .unwrap()
+            .push_back(MockManagementCanisterClientCall::LoadCanisterSnapshot(args));
+
+        let reply = self
+            .replies
+            .lock()
+            .unwrap()
+            .pop_front()
+            .expect("Expected a MockManagementCanisterClientCall to be on the queue.");
+
+        match reply {
+            MockManagementCanisterClientReply::LoadCanisterSnapshot(response) => response,
+            err => panic!(
+                "Expected MockManagementCanisterClientReply::LoadCanisterSnapshot to be at \
+                the front of the queue. Had {:?}",
+                err
+            ),
+        }
+    }
+
     fn canister_version(&self) -> Option<u64> {
         None
     }
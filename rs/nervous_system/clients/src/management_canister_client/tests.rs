@@ -55,6 +55,18 @@ async fn test_limit_outstanding_calls() {
             ) -> Result<(), (i32, String)> {
                 unimplemented!();
             }
+            async fn take_canister_snapshot(
+                &self,
+                _args: TakeCanisterSnapshotArgs,
+            ) -> Result<CanisterSnapshot, (i32, String)> {
+                unimplemented!();
+            }
+            async fn load_canister_snapshot(
+                &self,
+                _args: LoadCanisterSnapshotArgs,
+            ) -> Result<(), (i32, String)> {
+                unimplemented!();
+            }
             fn canister_version(&self) -> Option<u64> {
                 unimplemented!();
             }
@@ -1,15 +1,18 @@
 use crate::NervousSystemError;
 use async_trait::async_trait;
+use dfn_candid::candid_one;
 use dfn_core::{api::PrincipalId, call, CanisterId};
 use dfn_protobuf::protobuf;
 use ic_crypto_sha2::Sha256;
-use ic_ledger_core::block::BlockIndex;
+use ic_ledger_core::{block::BlockIndex, timestamp::TimeStamp};
 use icp_ledger::{
     tokens_from_proto, AccountBalanceArgs, AccountIdentifier, Memo, SendArgs,
-    Subaccount as IcpSubaccount, Tokens, TotalSupplyArgs,
+    Subaccount as IcpSubaccount, Tokens, TotalSupplyArgs, TransferArgs,
+    TransferError as IcpTransferError,
 };
 use icrc_ledger_types::icrc1::account::{Account, Subaccount};
 use mockall::automock;
+use std::fmt;
 
 pub struct IcpLedgerCanister {
     id: CanisterId,
@@ -21,6 +24,27 @@ impl IcpLedgerCanister {
     }
 }
 
+/// The error returned by `ICRC1Ledger::transfer_funds_with_created_at_time` and
+/// `IcpLedger::transfer_funds_with_created_at_time`.
+#[derive(Debug)]
+pub enum TransferWithCreatedAtTimeError {
+    /// The ledger rejected the transfer, so the funds were not transferred. Retrying the same
+    /// transfer would fail the same way.
+    Rejected(NervousSystemError),
+    /// The ledger could not be called, or did not reply, so it is not known whether the transfer
+    /// was recorded. The transfer can be retried with the same `created_at_time`.
+    UnknownOutcome(NervousSystemError),
+}
+
+impl fmt::Display for TransferWithCreatedAtTimeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Rejected(err) => write!(f, "Transfer rejected: {}", err),
+            Self::UnknownOutcome(err) => write!(f, "Transfer outcome unknown: {}", err),
+        }
+    }
+}
+
 /// A trait defining common patterns for accessing the ICRC1 Ledger canister.
 #[automock]
 #[async_trait]
@@ -38,6 +62,24 @@ pub trait ICRC1Ledger: Send + Sync {
         memo: u64,
     ) -> Result<BlockIndex, NervousSystemError>;
 
+    /// Like `transfer_funds`, but sets the `created_at_time` of the transfer, which makes the
+    /// ledger deduplicate it: if a transfer with the same arguments and `created_at_time` was
+    /// already recorded, the funds are not transferred again. Retrying a transfer whose outcome
+    /// is not known is therefore safe, as long as the ledger's transaction window (24 hours by
+    /// default) has not passed since `created_at_time_nanos`.
+    ///
+    /// Returns the block height at which the transfer was recorded. A duplicate is reported as
+    /// success, with the block height of the transfer it duplicates.
+    async fn transfer_funds_with_created_at_time(
+        &self,
+        amount_e8s: u64,
+        fee_e8s: u64,
+        from_subaccount: Option<Subaccount>,
+        to: Account,
+        memo: u64,
+        created_at_time_nanos: u64,
+    ) -> Result<BlockIndex, TransferWithCreatedAtTimeError>;
+
     /// Gets the total supply of tokens from the sum of all accounts except for the
     /// minting canister's.
     async fn total_supply(&self) -> Result<Tokens, NervousSystemError>;
@@ -66,6 +108,18 @@ pub trait IcpLedger: Send + Sync {
         memo: u64,
     ) -> Result<u64, NervousSystemError>;
 
+    /// Like `transfer_funds`, but sets the `created_at_time` of the transfer, which makes the
+    /// ledger deduplicate it. See `ICRC1Ledger::transfer_funds_with_created_at_time`.
+    async fn transfer_funds_with_created_at_time(
+        &self,
+        amount_e8s: u64,
+        fee_e8s: u64,
+        from_subaccount: Option<IcpSubaccount>,
+        to: AccountIdentifier,
+        memo: u64,
+        created_at_time_nanos: u64,
+    ) -> Result<u64, TransferWithCreatedAtTimeError>;
+
     /// Gets the total supply of tokens from the sum of all accounts except for the
     /// minting canister's.
     async fn total_supply(&self) -> Result<Tokens, NervousSystemError>;
@@ -80,6 +134,30 @@ pub trait IcpLedger: Send + Sync {
     fn canister_id(&self) -> CanisterId;
 }
 
+/// Interprets the reply of the ICP ledger's `transfer` method to a transfer that has its
+/// `created_at_time` set.
+fn icp_transfer_with_created_at_time_result(
+    result: Result<Result<BlockIndex, IcpTransferError>, (Option<i32>, String)>,
+) -> Result<BlockIndex, TransferWithCreatedAtTimeError> {
+    match result {
+        Ok(Ok(block_index)) => Ok(block_index),
+        // The transfer was already recorded, by an earlier call with the same arguments.
+        Ok(Err(IcpTransferError::TxDuplicate { duplicate_of })) => Ok(duplicate_of),
+        Ok(Err(err)) => Err(TransferWithCreatedAtTimeError::Rejected(
+            NervousSystemError::new_with_message(format!(
+                "'transfer' of the ledger canister failed. Error: {}",
+                err
+            )),
+        )),
+        Err((code, msg)) => Err(TransferWithCreatedAtTimeError::UnknownOutcome(
+            NervousSystemError::new_with_message(format!(
+                "Error calling method 'transfer' of the ledger canister. Code: {:?}. Message: {}",
+                code, msg
+            )),
+        )),
+    }
+}
+
 fn icrc1_account_to_icp_accountidentifier(account: Account) -> AccountIdentifier {
     AccountIdentifier::new(account.owner.into(), account.subaccount.map(IcpSubaccount))
 }
@@ -105,6 +183,27 @@ impl ICRC1Ledger for IcpLedgerCanister {
         .await
     }
 
+    async fn transfer_funds_with_created_at_time(
+        &self,
+        amount_e8s: u64,
+        fee_e8s: u64,
+        from_subaccount: Option<Subaccount>,
+        to: Account,
+        memo: u64,
+        created_at_time_nanos: u64,
+    ) -> Result<BlockIndex, TransferWithCreatedAtTimeError> {
+        <IcpLedgerCanister as IcpLedger>::transfer_funds_with_created_at_time(
+            self,
+            amount_e8s,
+            fee_e8s,
+            from_subaccount.map(IcpSubaccount),
+            icrc1_account_to_icp_accountidentifier(to),
+            memo,
+            created_at_time_nanos,
+        )
+        .await
+    }
+
     async fn total_supply(&self) -> Result<Tokens, NervousSystemError> {
         <IcpLedgerCanister as IcpLedger>::total_supply(self).await
     }
@@ -162,6 +261,37 @@ impl IcpLedger for IcpLedgerCanister {
         })
     }
 
+    async fn transfer_funds_with_created_at_time(
+        &self,
+        amount_e8s: u64,
+        fee_e8s: u64,
+        from_subaccount: Option<IcpSubaccount>,
+        to: AccountIdentifier,
+        memo: u64,
+        created_at_time_nanos: u64,
+    ) -> Result<u64, TransferWithCreatedAtTimeError> {
+        // Unlike `send_pb`, which traps on any error, `transfer` tells a rejected transfer
+        // apart from a duplicate.
+        let result: Result<Result<BlockIndex, IcpTransferError>, (Option<i32>, String)> = call(
+            self.id,
+            "transfer",
+            candid_one,
+            TransferArgs {
+                memo: Memo(memo),
+                amount: Tokens::from_e8s(amount_e8s),
+                fee: Tokens::from_e8s(fee_e8s),
+                from_subaccount,
+                to: to.to_address(),
+                created_at_time: Some(TimeStamp::from_nanos_since_unix_epoch(
+                    created_at_time_nanos,
+                )),
+            },
+        )
+        .await;
+
+        icp_transfer_with_created_at_time_result(result)
+    }
+
     async fn total_supply(&self) -> Result<Tokens, NervousSystemError> {
         let result: Result<Tokens, (Option<i32>, String)> =
             call(self.id, "total_supply_pb", protobuf, TotalSupplyArgs {})
@@ -247,3 +377,44 @@ pub fn compute_distribution_subaccount_bytes(principal_id: PrincipalId, nonce: u
 pub fn compute_distribution_subaccount(principal_id: PrincipalId, nonce: u64) -> IcpSubaccount {
     IcpSubaccount(compute_distribution_subaccount_bytes(principal_id, nonce))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_icp_transfer_with_created_at_time_result() {
+        assert_eq!(
+            icp_transfer_with_created_at_time_result(Ok(Ok(42))).unwrap(),
+            42
+        );
+
+        // A retry of a transfer that was recorded, but whose reply was lost, succeeds.
+        assert_eq!(
+            icp_transfer_with_created_at_time_result(Ok(Err(IcpTransferError::TxDuplicate {
+                duplicate_of: 41,
+            })))
+            .unwrap(),
+            41
+        );
+
+        assert!(matches!(
+            icp_transfer_with_created_at_time_result(Ok(Err(
+                IcpTransferError::InsufficientFunds {
+                    balance: Tokens::from_e8s(1),
+                }
+            ))),
+            Err(TransferWithCreatedAtTimeError::Rejected(_))
+        ));
+        assert!(matches!(
+            icp_transfer_with_created_at_time_result(Ok(Err(IcpTransferError::TxTooOld {
+                allowed_window_nanos: 1,
+            }))),
+            Err(TransferWithCreatedAtTimeError::Rejected(_))
+        ));
+        assert!(matches!(
+            icp_transfer_with_created_at_time_result(Err((None, "No reply.".to_string()))),
+            Err(TransferWithCreatedAtTimeError::UnknownOutcome(_))
+        ));
+    }
+}
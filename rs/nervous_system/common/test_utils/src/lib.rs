@@ -9,7 +9,7 @@ use futures::{
     StreamExt,
 };
 use ic_nervous_system_common::{
-    ledger::{ICRC1Ledger, IcpLedger},
+    ledger::{ICRC1Ledger, IcpLedger, TransferWithCreatedAtTimeError},
     NervousSystemError,
 };
 use icp_ledger::{AccountIdentifier, Tokens};
@@ -106,6 +106,38 @@ impl ICRC1Ledger for InterleavingTestLedger {
             .await
     }
 
+    async fn transfer_funds_with_created_at_time(
+        &self,
+        amount_e8s: u64,
+        fee_e8s: u64,
+        from_subaccount: Option<icrc_ledger_types::icrc1::account::Subaccount>,
+        to: Account,
+        memo: u64,
+        created_at_time_nanos: u64,
+    ) -> Result<u64, TransferWithCreatedAtTimeError> {
+        let msg = LedgerMessage::Transfer {
+            amount_e8s,
+            fee_e8s,
+            from_subaccount,
+            to,
+            memo,
+        };
+        atomic::fence(AtomicOrdering::SeqCst);
+        self.notify(msg)
+            .await
+            .map_err(TransferWithCreatedAtTimeError::UnknownOutcome)?;
+        self.underlying
+            .transfer_funds_with_created_at_time(
+                amount_e8s,
+                fee_e8s,
+                from_subaccount,
+                to,
+                memo,
+                created_at_time_nanos,
+            )
+            .await
+    }
+
     async fn total_supply(&self) -> Result<Tokens, NervousSystemError> {
         atomic::fence(AtomicOrdering::SeqCst);
         self.notify(LedgerMessage::TotalSupply).await?;
@@ -224,6 +256,18 @@ impl ICRC1Ledger for SpyLedger {
         }
     }
 
+    async fn transfer_funds_with_created_at_time(
+        &self,
+        _amount_e8s: u64,
+        _fee_e8s: u64,
+        _from_subaccount: Option<icrc_ledger_types::icrc1::account::Subaccount>,
+        _to: Account,
+        _memo: u64,
+        _created_at_time_nanos: u64,
+    ) -> Result<u64, TransferWithCreatedAtTimeError> {
+        unimplemented!()
+    }
+
     async fn total_supply(&self) -> Result<Tokens, NervousSystemError> {
         unimplemented!();
     }
@@ -294,6 +338,18 @@ impl IcpLedger for SpyLedger {
         }
     }
 
+    async fn transfer_funds_with_created_at_time(
+        &self,
+        _amount_e8s: u64,
+        _fee_e8s: u64,
+        _from_subaccount: Option<icp_ledger::Subaccount>,
+        _to: AccountIdentifier,
+        _memo: u64,
+        _created_at_time_nanos: u64,
+    ) -> Result<u64, TransferWithCreatedAtTimeError> {
+        unimplemented!()
+    }
+
     async fn total_supply(&self) -> Result<Tokens, NervousSystemError> {
         unimplemented!()
     }
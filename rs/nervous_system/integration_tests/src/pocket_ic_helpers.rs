@@ -245,6 +245,11 @@ pub mod nns {
                     Encode!(&ListNeurons {
                         neuron_ids: vec![],
                         include_neurons_readable_by_caller: true,
+                        page_number: None,
+                        page_size: None,
+                        neuron_states: vec![],
+                        min_stake_e8s: None,
+                        has_hot_keys: None,
                     })
                     .unwrap(),
                 )
@@ -951,6 +951,11 @@ pub fn list_neurons(pocket_ic: &PocketIc, sender: PrincipalId) -> ListNeuronsRes
             Encode!(&ListNeurons {
                 neuron_ids: vec![],
                 include_neurons_readable_by_caller: true,
+                page_number: None,
+                page_size: None,
+                neuron_states: vec![],
+                min_stake_e8s: None,
+                has_hot_keys: None,
             })
             .unwrap(),
         )
@@ -1,10 +1,13 @@
 use crate::LOG_PREFIX;
 use candid::{CandidType, Deserialize, Encode, Principal};
-use dfn_core::api::CanisterId;
+use dfn_core::api::{CanisterId, PrincipalId};
 #[cfg(target_arch = "wasm32")]
 use dfn_core::println;
 use ic_crypto_sha2::Sha256;
-use ic_management_canister_types::{CanisterInstallMode, InstallCodeArgs, IC_00};
+use ic_management_canister_types::{
+    CanisterInstallMode, CanisterInstallModeV2, InstallChunkedCodeArgs, InstallCodeArgs,
+    StoredChunksArgs, StoredChunksReply, IC_00,
+};
 use ic_nervous_system_clients::{
     canister_id_record::CanisterIdRecord,
     canister_status::{
@@ -56,6 +59,28 @@ pub struct ChangeCanisterRequest {
     pub memory_allocation: Option<candid::Nat>,
     #[serde(serialize_with = "serialize_optional_nat")]
     pub query_allocation: Option<candid::Nat>,
+
+    /// If set, the new wasm module is not shipped inline (i.e. `wasm_module` must be
+    /// empty), but is instead assembled from chunks that were previously uploaded to the
+    /// chunk store of a canister. This allows installing wasm modules that do not fit
+    /// into a single message.
+    pub chunked_canister_wasm: Option<ChunkedCanisterWasm>,
+}
+
+/// A wasm module that has been split into chunks, which were uploaded to the chunk
+/// store of `store_canister_id` via the `upload_chunk` method of the management canister.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ChunkedCanisterWasm {
+    /// The SHA-256 hash of the entire wasm module (i.e. of the concatenation of all chunks).
+    #[serde(with = "serde_bytes")]
+    pub wasm_module_hash: Vec<u8>,
+
+    /// The canister whose chunk store contains the chunks. Must be on the same subnet as
+    /// the canister to change, and must be controlled by the caller of `change_canister`.
+    pub store_canister_id: CanisterId,
+
+    /// The SHA-256 hashes of the chunks, in the order in which they are to be assembled.
+    pub chunk_hashes_list: Vec<Vec<u8>>,
 }
 
 impl ChangeCanisterRequest {
@@ -76,6 +101,7 @@ impl ChangeCanisterRequest {
             .field("compute_allocation", &self.compute_allocation)
             .field("memory_allocation", &self.memory_allocation)
             .field("query_allocation", &self.query_allocation)
+            .field("chunked_canister_wasm", &self.chunked_canister_wasm)
             .finish()
     }
 }
@@ -107,6 +133,7 @@ impl ChangeCanisterRequest {
             compute_allocation: None,
             memory_allocation: None,
             query_allocation: None,
+            chunked_canister_wasm: None,
         }
     }
 
@@ -120,6 +147,12 @@ impl ChangeCanisterRequest {
         self
     }
 
+    pub fn with_chunked_wasm(mut self, chunked_canister_wasm: ChunkedCanisterWasm) -> Self {
+        self.wasm_module = Vec::new();
+        self.chunked_canister_wasm = Some(chunked_canister_wasm);
+        self
+    }
+
     pub fn with_arg(mut self, arg: Vec<u8>) -> Self {
         self.arg = arg;
         self
@@ -207,6 +240,18 @@ where
     let canister_id = request.canister_id;
     let stop_before_installing = request.stop_before_installing;
 
+    // Make sure that all the chunks are actually present before touching the canister, so
+    // that it is not needlessly stopped (and restarted).
+    if let Some(chunked_canister_wasm) = &request.chunked_canister_wasm {
+        if let Err(err) = check_chunks_are_stored::<Rt>(chunked_canister_wasm).await {
+            println!(
+                "{}change_canister: Not changing canister {}: {}",
+                LOG_PREFIX, canister_id, err
+            );
+            return;
+        }
+    }
+
     if stop_before_installing {
         let stop_result = stop_canister::<Rt>(canister_id).await;
         if stop_result.is_err() {
@@ -246,7 +291,57 @@ where
     res.unwrap();
 }
 
-/// Calls the "install_code" method of the management canister.
+/// Returns an error if the chunk store of the store canister does not contain all the
+/// chunks of `chunked_canister_wasm`.
+async fn check_chunks_are_stored<Rt>(
+    chunked_canister_wasm: &ChunkedCanisterWasm,
+) -> Result<(), String>
+where
+    Rt: Runtime,
+{
+    let ChunkedCanisterWasm {
+        store_canister_id,
+        chunk_hashes_list,
+        wasm_module_hash: _,
+    } = chunked_canister_wasm;
+
+    let stored_chunks: Result<(StoredChunksReply,), (i32, String)> = Rt::call_with_cleanup(
+        CanisterId::ic_00(),
+        "stored_chunks",
+        (StoredChunksArgs {
+            canister_id: store_canister_id.get(),
+        },),
+    )
+    .await;
+    let (StoredChunksReply(stored_chunks),) = stored_chunks.map_err(|(code, message)| {
+        format!(
+            "Could not list the chunks stored in {}: {} (code {})",
+            store_canister_id, message, code
+        )
+    })?;
+
+    let missing_chunks = chunk_hashes_list
+        .iter()
+        .filter(|chunk_hash| {
+            !stored_chunks
+                .iter()
+                .any(|stored| stored.hash == **chunk_hash)
+        })
+        .count();
+    if missing_chunks > 0 {
+        return Err(format!(
+            "{} out of {} chunks are missing from the chunk store of {}",
+            missing_chunks,
+            chunk_hashes_list.len(),
+            store_canister_id
+        ));
+    }
+
+    Ok(())
+}
+
+/// Calls the "install_code" method of the management canister, or "install_chunked_code"
+/// if the request refers to a chunked wasm module.
 async fn install_code(request: ChangeCanisterRequest) -> ic_cdk::api::call::CallResult<()> {
     let ChangeCanisterRequest {
         mode,
@@ -256,6 +351,7 @@ async fn install_code(request: ChangeCanisterRequest) -> ic_cdk::api::call::Call
         compute_allocation,
         memory_allocation,
         query_allocation,
+        chunked_canister_wasm,
 
         stop_before_installing: _,
     } = request;
@@ -263,6 +359,22 @@ async fn install_code(request: ChangeCanisterRequest) -> ic_cdk::api::call::Call
     let canister_id = canister_id.get();
     let sender_canister_version = Some(ic_cdk::api::canister_version());
 
+    if let Some(chunked_canister_wasm) = chunked_canister_wasm {
+        let install_chunked_code_args = install_chunked_code_args(
+            mode,
+            canister_id,
+            chunked_canister_wasm,
+            arg,
+            sender_canister_version,
+        );
+        return ic_cdk::api::call::call(
+            Principal::try_from(IC_00.get().as_slice()).unwrap(),
+            "install_chunked_code",
+            (&install_chunked_code_args,),
+        )
+        .await;
+    }
+
     let install_code_args = InstallCodeArgs {
         mode,
         canister_id,
@@ -284,6 +396,41 @@ async fn install_code(request: ChangeCanisterRequest) -> ic_cdk::api::call::Call
     .await
 }
 
+/// Returns the arguments of the "install_chunked_code" method of the management canister
+/// that install the wasm module assembled from the chunks of `chunked_canister_wasm`.
+fn install_chunked_code_args(
+    mode: CanisterInstallMode,
+    canister_id: PrincipalId,
+    chunked_canister_wasm: ChunkedCanisterWasm,
+    arg: Vec<u8>,
+    sender_canister_version: Option<u64>,
+) -> InstallChunkedCodeArgs {
+    let ChunkedCanisterWasm {
+        wasm_module_hash,
+        store_canister_id,
+        chunk_hashes_list,
+    } = chunked_canister_wasm;
+
+    let mode = match mode {
+        CanisterInstallMode::Install => CanisterInstallModeV2::Install,
+        CanisterInstallMode::Reinstall => CanisterInstallModeV2::Reinstall,
+        CanisterInstallMode::Upgrade => CanisterInstallModeV2::Upgrade(None),
+    };
+    InstallChunkedCodeArgs {
+        mode,
+        target_canister: canister_id,
+        store_canister: Some(store_canister_id.get()),
+        chunk_hashes_list: chunk_hashes_list
+            .into_iter()
+            .map(serde_bytes::ByteBuf::from)
+            .collect(),
+        // The management canister checks that the assembled wasm module has this hash.
+        wasm_module_hash,
+        arg,
+        sender_canister_version,
+    }
+}
+
 pub async fn start_canister<Rt>(canister_id: CanisterId) -> Result<(), (i32, String)>
 where
     Rt: Runtime,
@@ -347,3 +494,6 @@ where
         None => serializer.serialize_none(),
     }
 }
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,234 @@
+use super::*;
+use async_trait::async_trait;
+use candid::{
+    de::IDLDeserialize,
+    ser::IDLBuilder,
+    utils::{ArgumentDecoder, ArgumentEncoder},
+    Decode,
+};
+use ic_management_canister_types::ChunkHash;
+use std::{cell::RefCell, collections::BTreeMap, future::Future};
+
+thread_local! {
+    // The chunks in the chunk store of each canister that is controlled by root.
+    static STORED_CHUNKS: RefCell<BTreeMap<CanisterId, Vec<Vec<u8>>>> =
+        RefCell::new(BTreeMap::new());
+    // The names of the management canister methods that were called, in order.
+    static CALLED_METHODS: RefCell<Vec<String>> = RefCell::new(vec![]);
+}
+
+/// Simulates the management canister, which only implements `stored_chunks`.
+struct MockRuntime {}
+
+#[async_trait]
+impl Runtime for MockRuntime {
+    async fn call_with_cleanup<In, Out>(
+        id: CanisterId,
+        method: &str,
+        args: In,
+    ) -> Result<Out, (i32, String)>
+    where
+        In: ArgumentEncoder + Send,
+        Out: for<'a> ArgumentDecoder<'a>,
+    {
+        assert_eq!(id, CanisterId::ic_00());
+        CALLED_METHODS.with(|called_methods| called_methods.borrow_mut().push(method.to_string()));
+        if method != "stored_chunks" {
+            return Err((5, format!("Unexpected call to {}", method)));
+        }
+
+        let mut idl_builder = IDLBuilder::new();
+        args.encode(&mut idl_builder).unwrap();
+        let args = Decode!(&idl_builder.serialize_to_vec().unwrap(), StoredChunksArgs).unwrap();
+        let canister_id = args.get_canister_id();
+        let stored_chunks = STORED_CHUNKS
+            .with(|stored_chunks| stored_chunks.borrow().get(&canister_id).cloned())
+            .ok_or_else(|| {
+                (
+                    5,
+                    format!(
+                        "Only the controllers of the canister {} can control it.",
+                        canister_id
+                    ),
+                )
+            })?;
+
+        let reply = StoredChunksReply(
+            stored_chunks
+                .into_iter()
+                .map(|hash| ChunkHash { hash })
+                .collect(),
+        );
+        let reply = Encode!(&reply).unwrap();
+        let mut idl_deserializer = IDLDeserialize::new(&reply).unwrap();
+        Ok(Out::decode(&mut idl_deserializer).unwrap())
+    }
+
+    async fn call_without_cleanup<In, Out>(
+        _id: CanisterId,
+        _method: &str,
+        _args: In,
+    ) -> Result<Out, (i32, String)>
+    where
+        In: ArgumentEncoder + Send,
+        Out: for<'a> ArgumentDecoder<'a>,
+    {
+        unimplemented!()
+    }
+
+    async fn call_bytes_with_cleanup(
+        _id: CanisterId,
+        _method: &str,
+        _args: &[u8],
+    ) -> Result<Vec<u8>, (i32, String)> {
+        unimplemented!()
+    }
+
+    fn spawn_future<F: 'static + Future<Output = ()>>(_future: F) {
+        unimplemented!()
+    }
+}
+
+const STORE_CANISTER_ID: CanisterId = CanisterId::from_u64(1);
+const OTHER_CANISTER_ID: CanisterId = CanisterId::from_u64(2);
+const UNCONTROLLED_CANISTER_ID: CanisterId = CanisterId::from_u64(3);
+const TARGET_CANISTER_ID: CanisterId = CanisterId::from_u64(4);
+
+/// Uploads two chunks to the chunk store of STORE_CANISTER_ID, and none to OTHER_CANISTER_ID.
+fn set_up_chunk_stores() {
+    STORED_CHUNKS.with(|stored_chunks| {
+        *stored_chunks.borrow_mut() = BTreeMap::from([
+            (STORE_CANISTER_ID, vec![vec![0xbb; 32], vec![0xcc; 32]]),
+            (OTHER_CANISTER_ID, vec![]),
+        ])
+    });
+}
+
+fn chunked_canister_wasm(
+    store_canister_id: CanisterId,
+    chunk_hashes_list: Vec<Vec<u8>>,
+) -> ChunkedCanisterWasm {
+    ChunkedCanisterWasm {
+        wasm_module_hash: vec![0xaa; 32],
+        store_canister_id,
+        chunk_hashes_list,
+    }
+}
+
+#[tokio::test]
+async fn check_chunks_are_stored_succeeds_if_all_chunks_are_stored() {
+    set_up_chunk_stores();
+
+    // The chunks can be listed in any order, and the same chunk can be used repeatedly.
+    let chunk_hashes_list = vec![vec![0xcc; 32], vec![0xbb; 32], vec![0xcc; 32]];
+    let result = check_chunks_are_stored::<MockRuntime>(&chunked_canister_wasm(
+        STORE_CANISTER_ID,
+        chunk_hashes_list,
+    ))
+    .await;
+
+    assert_eq!(result, Ok(()));
+}
+
+#[tokio::test]
+async fn check_chunks_are_stored_fails_if_chunks_are_missing() {
+    set_up_chunk_stores();
+
+    let chunk_hashes_list = vec![vec![0xbb; 32], vec![0xdd; 32]];
+    let err = check_chunks_are_stored::<MockRuntime>(&chunked_canister_wasm(
+        STORE_CANISTER_ID,
+        chunk_hashes_list,
+    ))
+    .await
+    .unwrap_err();
+
+    assert!(
+        err.contains(&format!(
+            "1 out of 2 chunks are missing from the chunk store of {}",
+            STORE_CANISTER_ID
+        )),
+        "{}",
+        err
+    );
+}
+
+#[tokio::test]
+async fn check_chunks_are_stored_fails_for_wrong_store_canister() {
+    set_up_chunk_stores();
+    let chunk_hashes_list = vec![vec![0xbb; 32], vec![0xcc; 32]];
+
+    // The chunks were uploaded to a different canister.
+    let err = check_chunks_are_stored::<MockRuntime>(&chunked_canister_wasm(
+        OTHER_CANISTER_ID,
+        chunk_hashes_list.clone(),
+    ))
+    .await
+    .unwrap_err();
+    assert!(
+        err.contains(&format!(
+            "2 out of 2 chunks are missing from the chunk store of {}",
+            OTHER_CANISTER_ID
+        )),
+        "{}",
+        err
+    );
+
+    // The chunk store of a canister that root does not control cannot be listed.
+    let err = check_chunks_are_stored::<MockRuntime>(&chunked_canister_wasm(
+        UNCONTROLLED_CANISTER_ID,
+        chunk_hashes_list,
+    ))
+    .await
+    .unwrap_err();
+    assert!(
+        err.contains(&format!(
+            "Could not list the chunks stored in {}",
+            UNCONTROLLED_CANISTER_ID
+        )),
+        "{}",
+        err
+    );
+}
+
+#[tokio::test]
+async fn change_canister_does_not_stop_canister_if_chunks_are_missing() {
+    set_up_chunk_stores();
+
+    let request =
+        ChangeCanisterRequest::new(true, CanisterInstallMode::Upgrade, TARGET_CANISTER_ID)
+            .with_chunked_wasm(chunked_canister_wasm(
+                OTHER_CANISTER_ID,
+                vec![vec![0xbb; 32], vec![0xcc; 32]],
+            ));
+    change_canister::<MockRuntime>(request).await;
+
+    // Only the chunk store was inspected.
+    CALLED_METHODS.with(|called_methods| {
+        assert_eq!(*called_methods.borrow(), vec!["stored_chunks".to_string()])
+    });
+}
+
+#[test]
+fn install_chunked_code_args_refer_to_stored_chunks() {
+    let args = install_chunked_code_args(
+        CanisterInstallMode::Upgrade,
+        TARGET_CANISTER_ID.get(),
+        chunked_canister_wasm(STORE_CANISTER_ID, vec![vec![0xbb; 32], vec![0xcc; 32]]),
+        Encode!().unwrap(),
+        Some(42),
+    );
+
+    assert_eq!(args.mode, CanisterInstallModeV2::Upgrade(None));
+    assert_eq!(args.target_canister, TARGET_CANISTER_ID.get());
+    assert_eq!(args.store_canister, Some(STORE_CANISTER_ID.get()));
+    assert_eq!(
+        args.chunk_hashes_list,
+        vec![
+            serde_bytes::ByteBuf::from(vec![0xbb; 32]),
+            serde_bytes::ByteBuf::from(vec![0xcc; 32]),
+        ]
+    );
+    assert_eq!(args.wasm_module_hash, vec![0xaa; 32]);
+    assert_eq!(args.arg, Encode!().unwrap());
+    assert_eq!(args.sender_canister_version, Some(42));
+}
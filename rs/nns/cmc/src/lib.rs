@@ -120,9 +120,54 @@ pub enum SubnetSelection {
     Subnet { subnet: SubnetId },
 }
 
-#[derive(Serialize, Deserialize, CandidType, Clone, Debug, PartialEq, Eq)]
+/// Properties that a subnet must fulfill to be selected for a new canister.
+///
+/// Unset fields do not restrict the selection. Except for `subnet_type`, the
+/// properties are looked up in the registry (or, for the number of canisters,
+/// on the subnet itself) when the canister is created.
+#[derive(Serialize, Deserialize, CandidType, Clone, Debug, Default, PartialEq, Eq)]
 pub struct SubnetFilter {
     pub subnet_type: Option<String>,
+    /// Only select subnets all of whose nodes are located in data centers in one
+    /// of the given regions. Data center regions are hierarchical, comma-separated
+    /// strings (e.g., "Europe,CH,Zurich"), and a region given here matches all data
+    /// centers whose region starts with it (e.g., "Europe" or "Europe,CH").
+    pub data_center_regions: Option<Vec<String>>,
+    /// Only select subnets with at least this many nodes.
+    pub min_replication_factor: Option<u64>,
+    /// Only select subnets that can host at least this many more canisters, i.e.,
+    /// whose `max_number_of_canisters` limit exceeds the number of canisters they
+    /// currently host by at least this many (no limit counts as unbounded).
+    pub min_canister_capacity: Option<u64>,
+    /// Only select subnets that support all of the given features.
+    pub required_features: Option<Vec<SubnetFeature>>,
+}
+
+impl SubnetFilter {
+    /// Returns true if the filter uses properties that need to be looked up in
+    /// the registry.
+    pub fn requires_registry_lookup(&self) -> bool {
+        let SubnetFilter {
+            subnet_type: _,
+            data_center_regions,
+            min_replication_factor,
+            min_canister_capacity,
+            required_features,
+        } = self;
+        data_center_regions.is_some()
+            || min_replication_factor.is_some()
+            || min_canister_capacity.is_some()
+            || required_features.is_some()
+    }
+}
+
+/// Subnet features that can be required when selecting a subnet.
+#[derive(Serialize, Deserialize, CandidType, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SubnetFeature {
+    /// Canisters can make HTTP requests to the web2 (HTTPS outcalls).
+    HttpOutcalls,
+    /// Signing with a threshold ECDSA key is enabled on the subnet.
+    Ecdsa,
 }
 pub enum NotifyErrorCode {
     /// An internal error in the cycles minting canister (e.g., inconsistent state).
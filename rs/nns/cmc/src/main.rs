@@ -42,10 +42,12 @@ use std::{
     thread::LocalKey,
     time::{Duration, SystemTime, UNIX_EPOCH},
 };
+use subnet_selection::{subnet_matches_filter, RealSubnetRegistryClient, SubnetRegistryClient};
 
 mod environment;
 mod exchange_rate_canister;
 mod limiter;
+mod subnet_selection;
 
 /// The past 30 days are used for the average ICP/XDR rate.
 const NUM_DAYS_FOR_ICP_XDR_AVERAGE: usize = 30;
@@ -1991,17 +1993,21 @@ async fn do_create_canister(
     // of subnets. Otherwise, fall back to the list of subnets for the
     // provided controller id.
 
+    // Filters that select on properties recorded in the registry are applied
+    // lazily to the shuffled subnets below, so that the registry only needs to
+    // be consulted for the subnets that are actually considered.
+    let mut registry_subnet_filter = None;
     let mut subnets: Vec<SubnetId> = match subnet_selection {
         Some(option) => match option {
             SubnetSelection::Filter(subnet_filter) => {
-                with_state(|state| match subnet_filter.subnet_type {
+                let subnets = with_state(|state| match &subnet_filter.subnet_type {
                     Some(subnet_type) => {
                         let subnet_types_to_subnets = state
                             .subnet_types_to_subnets
                             .as_ref()
                             .expect("subnet types to subnets mapping is `None`");
                         subnet_types_to_subnets
-                            .get(&subnet_type)
+                            .get(subnet_type)
                             .map(|set| set.iter().cloned().collect())
                             .ok_or(format!(
                                 "Provided subnet type {} does not exist",
@@ -2009,7 +2015,11 @@ async fn do_create_canister(
                             ))
                     }
                     None => Ok(get_subnets_for(&controller_id)),
-                })
+                });
+                if subnet_filter.requires_registry_lookup() {
+                    registry_subnet_filter = Some(subnet_filter);
+                }
+                subnets
             }
             SubnetSelection::Subnet { subnet } => with_state(|state| {
                 if state.default_subnets.contains(&subnet)
@@ -2042,12 +2052,7 @@ async fn do_create_canister(
     subnets.shuffle(&mut rng);
 
     let mut last_err = None;
-
-    if mint_cycles && !subnets.is_empty() {
-        // TODO(NNS1-503): If CreateCanister fails, then we still have minted
-        // these cycles.
-        ensure_balance(cycles)?;
-    }
+    let mut cycles_minted = false;
 
     let canister_settings = settings
         .map(|mut settings| {
@@ -2062,7 +2067,31 @@ async fn do_create_canister(
                 .build()
         });
 
+    let subnet_registry_client = RealSubnetRegistryClient::default();
     for subnet_id in subnets {
+        match subnet_fulfills_registry_filter(
+            &subnet_registry_client,
+            subnet_id,
+            registry_subnet_filter.as_ref(),
+        )
+        .await
+        {
+            Ok(true) => (),
+            Ok(false) => continue,
+            Err(err) => {
+                print(format!("[cycles] {}", err));
+                last_err = Some(err);
+                continue;
+            }
+        }
+
+        if mint_cycles && !cycles_minted {
+            // TODO(NNS1-503): If CreateCanister fails, then we still have minted
+            // these cycles.
+            ensure_balance(cycles)?;
+            cycles_minted = true;
+        }
+
         let result: Result<CanisterIdRecord, _> = dfn_core::api::call_with_funds_and_cleanup(
             subnet_id.into(),
             &Method::CreateCanister.to_string(),
@@ -2098,7 +2127,37 @@ async fn do_create_canister(
         return Ok(canister_id);
     }
 
-    Err(last_err.unwrap_or_else(|| "No subnets in which to create a canister.".to_owned()))
+    Err(last_err.unwrap_or_else(|| {
+        if registry_subnet_filter.is_some() {
+            "No subnet that fulfills the subnet filter is available to create a canister."
+                .to_owned()
+        } else {
+            "No subnets in which to create a canister.".to_owned()
+        }
+    }))
+}
+
+/// Returns true if the given subnet fulfills the criteria of the filter that
+/// are looked up when the canister is created (i.e., all but the subnet type),
+/// or if there is no such filter.
+async fn subnet_fulfills_registry_filter(
+    subnet_registry_client: &impl SubnetRegistryClient,
+    subnet_id: SubnetId,
+    registry_subnet_filter: Option<&SubnetFilter>,
+) -> Result<bool, String> {
+    let Some(subnet_filter) = registry_subnet_filter else {
+        return Ok(true);
+    };
+    let properties = subnet_registry_client
+        .get_subnet_properties(subnet_id, subnet_filter)
+        .await
+        .map_err(|err| {
+            format!(
+                "Looking up the properties of subnet {} failed: {}",
+                subnet_id, err
+            )
+        })?;
+    Ok(subnet_matches_filter(subnet_filter, &properties))
 }
 
 fn ensure_balance(cycles: Cycles) -> Result<(), String> {
@@ -2325,6 +2384,7 @@ fn get_subnet_selection(
     } else if let Some(subnet_type) = subnet_type {
         Ok(Some(SubnetSelection::Filter(SubnetFilter {
             subnet_type: Some(subnet_type),
+            ..Default::default()
         })))
     } else {
         Ok(subnet_selection)
@@ -2334,6 +2394,7 @@ fn get_subnet_selection(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::subnet_selection::SubnetProperties;
     use ic_types_test_utils::ids::{subnet_test_id, user_test_id};
     use rand::Rng;
     use std::cmp::{max, min};
@@ -2868,6 +2929,88 @@ mod tests {
         );
     }
 
+    /// Returns the given result for every subnet and records the subnets whose
+    /// properties were looked up.
+    struct MockSubnetRegistryClient {
+        result: Result<SubnetProperties, String>,
+        lookups: std::sync::Mutex<Vec<SubnetId>>,
+    }
+
+    impl MockSubnetRegistryClient {
+        fn new(result: Result<SubnetProperties, String>) -> Self {
+            Self {
+                result,
+                lookups: Default::default(),
+            }
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl SubnetRegistryClient for MockSubnetRegistryClient {
+        async fn get_subnet_properties(
+            &self,
+            subnet_id: SubnetId,
+            _filter: &SubnetFilter,
+        ) -> Result<SubnetProperties, String> {
+            self.lookups.lock().unwrap().push(subnet_id);
+            self.result.clone()
+        }
+    }
+
+    #[test]
+    fn test_subnet_fulfills_registry_filter() {
+        use futures::FutureExt;
+
+        let subnet_id = subnet_test_id(1);
+        let filter = SubnetFilter {
+            min_replication_factor: Some(13),
+            ..Default::default()
+        };
+        let properties = |replication_factor| SubnetProperties {
+            replication_factor,
+            ..Default::default()
+        };
+
+        // Without a filter, the registry is not consulted.
+        let client = MockSubnetRegistryClient::new(Err("unreachable".to_string()));
+        assert_eq!(
+            subnet_fulfills_registry_filter(&client, subnet_id, None)
+                .now_or_never()
+                .unwrap(),
+            Ok(true)
+        );
+        assert!(client.lookups.lock().unwrap().is_empty());
+
+        let client = MockSubnetRegistryClient::new(Ok(properties(13)));
+        assert_eq!(
+            subnet_fulfills_registry_filter(&client, subnet_id, Some(&filter))
+                .now_or_never()
+                .unwrap(),
+            Ok(true)
+        );
+        assert_eq!(*client.lookups.lock().unwrap(), vec![subnet_id]);
+
+        let client = MockSubnetRegistryClient::new(Ok(properties(4)));
+        assert_eq!(
+            subnet_fulfills_registry_filter(&client, subnet_id, Some(&filter))
+                .now_or_never()
+                .unwrap(),
+            Ok(false)
+        );
+
+        // A failed lookup is reported, so that the next subnet can be tried.
+        let client = MockSubnetRegistryClient::new(Err("registry unavailable".to_string()));
+        assert_eq!(
+            subnet_fulfills_registry_filter(&client, subnet_id, Some(&filter))
+                .now_or_never()
+                .unwrap(),
+            Err(format!(
+                "Looking up the properties of subnet {} failed: registry unavailable",
+                subnet_id
+            ))
+        );
+    }
+
     #[test]
     fn test_candid_interface_compatibility() {
         use candid_parser::utils::{service_equal, CandidSource};
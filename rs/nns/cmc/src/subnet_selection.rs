@@ -0,0 +1,934 @@
+use async_trait::async_trait;
+use cycles_minting_canister::{SubnetFeature, SubnetFilter};
+use dfn_candid::candid_one;
+use dfn_core::api::call_with_cleanup;
+use futures::future::join_all;
+use ic_management_canister_types::{EcdsaKeyId, Method, SubnetInfoArgs, SubnetInfoResponse};
+use ic_nns_constants::REGISTRY_CANISTER_ID;
+use ic_protobuf::registry::{
+    crypto::v1::EcdsaSigningSubnetList, dc::v1::DataCenterRecord, node::v1::NodeRecord,
+    node_operator::v1::NodeOperatorRecord, subnet::v1::SubnetRecord,
+};
+use ic_registry_keys::{
+    make_data_center_record_key, make_ecdsa_signing_subnet_list_key, make_node_operator_record_key,
+    make_node_record_key, make_subnet_record_key,
+};
+use ic_registry_transport::{
+    deserialize_get_latest_version_response, deserialize_get_value_response,
+    serialize_get_value_request, Error as RegistryTransportError,
+};
+use ic_types::{subnet_id_into_protobuf, NodeId, PrincipalId, SubnetId};
+use on_wire::bytes;
+use prost::Message;
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, BTreeSet},
+    thread::LocalKey,
+};
+
+/// The properties of a subnet that are relevant when selecting a subnet with a
+/// `SubnetFilter`. Except for the number of canisters, they are recorded in the
+/// registry.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SubnetProperties {
+    /// The number of nodes of the subnet.
+    pub replication_factor: u64,
+    /// The maximum number of canisters the subnet can host; 0 means unlimited.
+    pub max_number_of_canisters: u64,
+    /// The number of canisters currently hosted by the subnet. Only looked up
+    /// if the filter requires a minimum canister capacity and the subnet limits
+    /// the number of canisters.
+    pub num_canisters: Option<u64>,
+    /// Whether canisters on the subnet can make HTTPS outcalls.
+    pub http_outcalls: bool,
+    /// Whether signing with a threshold ECDSA key is enabled on the subnet. Only
+    /// looked up if the filter requires the `Ecdsa` feature.
+    pub ecdsa: bool,
+    /// The regions of the data centers hosting the nodes of the subnet. Only
+    /// looked up if the filter restricts the data center regions.
+    pub data_center_regions: Option<BTreeSet<String>>,
+}
+
+#[async_trait]
+pub trait SubnetRegistryClient {
+    /// Looks up the properties of the given subnet that are needed to decide
+    /// whether it matches the given filter. Properties that are expensive to
+    /// look up, e.g., the data center regions, which require reading the
+    /// records of all nodes of the subnet, are only looked up if the filter
+    /// uses them.
+    async fn get_subnet_properties(
+        &self,
+        subnet_id: SubnetId,
+        filter: &SubnetFilter,
+    ) -> Result<SubnetProperties, String>;
+}
+
+/// The calls that are made to look up the properties of a subnet.
+#[async_trait]
+pub trait SubnetLookupCalls {
+    /// Reads the latest version of the registry.
+    async fn get_latest_version(&self) -> Result<u64, String>;
+
+    /// Reads the value of the given key at the given registry version, if
+    /// present.
+    async fn get_value(&self, key: &str, version: u64) -> Result<Option<Vec<u8>>, String>;
+
+    /// Asks the given subnet how many canisters it currently hosts.
+    async fn get_num_canisters(&self, subnet_id: SubnetId) -> Result<u64, String>;
+}
+
+pub struct RealSubnetLookupCalls;
+
+#[async_trait]
+impl SubnetLookupCalls for RealSubnetLookupCalls {
+    async fn get_latest_version(&self) -> Result<u64, String> {
+        let response: Result<Vec<u8>, (Option<i32>, String)> =
+            call_with_cleanup(REGISTRY_CANISTER_ID, "get_latest_version", bytes, vec![]).await;
+        let response = response.map_err(|(code, msg)| {
+            format!(
+                "Getting the latest version of the registry failed with code {}: {:?}",
+                code.unwrap_or_default(),
+                msg
+            )
+        })?;
+        deserialize_get_latest_version_response(response)
+            .map_err(|err| format!("Getting the latest version of the registry failed: {}", err))
+    }
+
+    async fn get_value(&self, key: &str, version: u64) -> Result<Option<Vec<u8>>, String> {
+        let request = serialize_get_value_request(key.as_bytes().to_vec(), Some(version))
+            .map_err(|err| format!("Could not serialize the registry request: {}", err))?;
+        let response: Result<Vec<u8>, (Option<i32>, String)> =
+            call_with_cleanup(REGISTRY_CANISTER_ID, "get_value", bytes, request).await;
+        let response = response.map_err(|(code, msg)| {
+            format!(
+                "Getting {} from the registry failed with code {}: {:?}",
+                key,
+                code.unwrap_or_default(),
+                msg
+            )
+        })?;
+        match deserialize_get_value_response(response) {
+            Ok((value, _version)) => Ok(Some(value)),
+            Err(RegistryTransportError::KeyNotPresent(_)) => Ok(None),
+            Err(err) => Err(format!("Getting {} from the registry failed: {}", key, err)),
+        }
+    }
+
+    async fn get_num_canisters(&self, subnet_id: SubnetId) -> Result<u64, String> {
+        let response: Result<SubnetInfoResponse, (Option<i32>, String)> = call_with_cleanup(
+            subnet_id.into(),
+            &Method::SubnetInfo.to_string(),
+            candid_one,
+            SubnetInfoArgs {
+                subnet_id: subnet_id.get(),
+            },
+        )
+        .await;
+        response
+            .map(|subnet_info| subnet_info.num_canisters)
+            .map_err(|(code, msg)| {
+                format!(
+                    "Getting the subnet info of subnet {} failed with code {}: {}",
+                    subnet_id,
+                    code.unwrap_or_default(),
+                    msg
+                )
+            })
+    }
+}
+
+/// Looks up subnet properties in the registry, serving registry values from
+/// the given cache where possible.
+pub struct RegistrySubnetClient<Calls> {
+    calls: Calls,
+    cache: &'static LocalKey<RefCell<RegistryCache>>,
+}
+
+pub type RealSubnetRegistryClient = RegistrySubnetClient<RealSubnetLookupCalls>;
+
+impl Default for RealSubnetRegistryClient {
+    fn default() -> Self {
+        Self::new(RealSubnetLookupCalls, &REGISTRY_CACHE)
+    }
+}
+
+#[async_trait]
+impl<Calls: SubnetLookupCalls + Sync> SubnetRegistryClient for RegistrySubnetClient<Calls> {
+    async fn get_subnet_properties(
+        &self,
+        subnet_id: SubnetId,
+        filter: &SubnetFilter,
+    ) -> Result<SubnetProperties, String> {
+        // All records are read at the same version, so that the properties are
+        // consistent and can be served from the cache.
+        let version = self.calls.get_latest_version().await?;
+        let subnet_record: SubnetRecord = self
+            .get_value(make_subnet_record_key(subnet_id), version)
+            .await?;
+
+        let num_canisters = if filter.min_canister_capacity.is_some()
+            && subnet_record.max_number_of_canisters != 0
+        {
+            Some(self.calls.get_num_canisters(subnet_id).await?)
+        } else {
+            None
+        };
+
+        let ecdsa = if filter
+            .required_features
+            .iter()
+            .flatten()
+            .any(|feature| *feature == SubnetFeature::Ecdsa)
+        {
+            self.ecdsa_signing_is_enabled(subnet_id, &subnet_record, version)
+                .await?
+        } else {
+            false
+        };
+
+        let data_center_regions = if filter.data_center_regions.is_some() {
+            Some(
+                self.get_data_center_regions(&subnet_record, version)
+                    .await?,
+            )
+        } else {
+            None
+        };
+
+        Ok(SubnetProperties {
+            replication_factor: subnet_record.membership.len() as u64,
+            max_number_of_canisters: subnet_record.max_number_of_canisters,
+            num_canisters,
+            http_outcalls: subnet_record
+                .features
+                .map(|features| features.http_requests)
+                .unwrap_or(false),
+            ecdsa,
+            data_center_regions,
+        })
+    }
+}
+
+impl<Calls: SubnetLookupCalls + Sync> RegistrySubnetClient<Calls> {
+    pub fn new(calls: Calls, cache: &'static LocalKey<RefCell<RegistryCache>>) -> Self {
+        Self { calls, cache }
+    }
+
+    /// Returns true if signing with at least one of the ECDSA keys held by the
+    /// given subnet is enabled on it. Holding a key (i.e., having it in the
+    /// subnet's `ecdsa_config`) does not imply that the subnet serves signature
+    /// requests; that is recorded in the key's signing subnet list.
+    async fn ecdsa_signing_is_enabled(
+        &self,
+        subnet_id: SubnetId,
+        subnet_record: &SubnetRecord,
+        version: u64,
+    ) -> Result<bool, String> {
+        let key_ids = subnet_record
+            .ecdsa_config
+            .iter()
+            .flat_map(|ecdsa_config| ecdsa_config.key_ids.iter())
+            .map(|key_id| {
+                EcdsaKeyId::try_from(key_id.clone())
+                    .map_err(|err| format!("Invalid ECDSA key ID in subnet record: {}", err))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        for key_id in key_ids {
+            let signing_subnet_list: Option<EcdsaSigningSubnetList> = self
+                .get_value_if_present(make_ecdsa_signing_subnet_list_key(&key_id), version)
+                .await?;
+            let signing_is_enabled = signing_subnet_list.is_some_and(|signing_subnet_list| {
+                signing_subnet_list
+                    .subnets
+                    .contains(&subnet_id_into_protobuf(subnet_id))
+            });
+            if signing_is_enabled {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// Returns the regions of the data centers hosting the nodes of the given
+    /// subnet.
+    ///
+    /// Node operators and data centers are usually shared by several nodes, so
+    /// each of them is only looked up once. The lookups of each kind happen
+    /// concurrently.
+    async fn get_data_center_regions(
+        &self,
+        subnet_record: &SubnetRecord,
+        version: u64,
+    ) -> Result<BTreeSet<String>, String> {
+        let node_ids = subnet_record
+            .membership
+            .iter()
+            .map(|node_id| {
+                PrincipalId::try_from(node_id.as_slice())
+                    .map(NodeId::from)
+                    .map_err(|err| format!("Invalid node ID in subnet record: {}", err))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let node_records: Vec<NodeRecord> = join_all(
+            node_ids
+                .into_iter()
+                .map(|node_id| self.get_value(make_node_record_key(node_id), version)),
+        )
+        .await
+        .into_iter()
+        .collect::<Result<_, _>>()?;
+
+        let node_operator_ids = node_records
+            .iter()
+            .map(|node_record| {
+                PrincipalId::try_from(node_record.node_operator_id.as_slice())
+                    .map_err(|err| format!("Invalid node operator ID in node record: {}", err))
+            })
+            .collect::<Result<BTreeSet<_>, _>>()?;
+        let node_operator_records: Vec<NodeOperatorRecord> =
+            join_all(node_operator_ids.into_iter().map(|node_operator_id| {
+                self.get_value(make_node_operator_record_key(node_operator_id), version)
+            }))
+            .await
+            .into_iter()
+            .collect::<Result<_, _>>()?;
+
+        let data_center_ids = node_operator_records
+            .into_iter()
+            .map(|node_operator_record| node_operator_record.dc_id)
+            .collect::<BTreeSet<_>>();
+        let data_center_records: Vec<DataCenterRecord> = join_all(
+            data_center_ids
+                .iter()
+                .map(|dc_id| self.get_value(make_data_center_record_key(dc_id), version)),
+        )
+        .await
+        .into_iter()
+        .collect::<Result<_, _>>()?;
+
+        Ok(data_center_records
+            .into_iter()
+            .map(|data_center_record| data_center_record.region)
+            .collect())
+    }
+
+    /// Reads the value of the given key at the given registry version, failing
+    /// if the key is not present.
+    async fn get_value<T: Message + Default>(
+        &self,
+        key: String,
+        version: u64,
+    ) -> Result<T, String> {
+        self.get_value_if_present(key.clone(), version)
+            .await?
+            .ok_or_else(|| format!("{} is not present in the registry", key))
+    }
+
+    /// Reads the value of the given key at the given registry version, if
+    /// present. Values are served from the registry cache where possible.
+    async fn get_value_if_present<T: Message + Default>(
+        &self,
+        key: String,
+        version: u64,
+    ) -> Result<Option<T>, String> {
+        let cached = self
+            .cache
+            .with(|cache| cache.borrow().get(&key, version).cloned());
+        let value = match cached {
+            Some(value) => value,
+            None => {
+                let value = self.calls.get_value(&key, version).await?;
+                self.cache.with(|cache| {
+                    cache
+                        .borrow_mut()
+                        .insert(key.clone(), version, value.clone())
+                });
+                value
+            }
+        };
+        value
+            .map(|value| {
+                T::decode(value.as_slice()).map_err(|err| {
+                    format!("Could not decode the registry value of {}: {}", key, err)
+                })
+            })
+            .transpose()
+    }
+}
+
+/// Registry values read at a single registry version, by key. A value of `None`
+/// records that the key was not present at that version.
+///
+/// Values at a given version never change, so they can be reused until the
+/// registry moves to a new version, at which point the cache is reset. This
+/// saves most registry calls when canisters are created with a subnet filter
+/// in quick succession, e.g., the node records when filtering by data center
+/// region.
+#[derive(Default)]
+pub struct RegistryCache {
+    version: u64,
+    values: BTreeMap<String, Option<Vec<u8>>>,
+}
+
+impl RegistryCache {
+    /// Returns the cached value of the given key, if it was read at the given
+    /// version.
+    fn get(&self, key: &str, version: u64) -> Option<&Option<Vec<u8>>> {
+        if self.version == version {
+            self.values.get(key)
+        } else {
+            None
+        }
+    }
+
+    /// Caches the value of the given key read at the given version. Values
+    /// read at a newer version reset the cache; values read at an older
+    /// version, e.g., by a lookup that completes last, are dropped.
+    fn insert(&mut self, key: String, version: u64, value: Option<Vec<u8>>) {
+        if self.version > version {
+            return;
+        }
+        if self.version < version {
+            *self = RegistryCache {
+                version,
+                values: BTreeMap::new(),
+            };
+        }
+        self.values.insert(key, value);
+    }
+}
+
+thread_local! {
+    static REGISTRY_CACHE: RefCell<RegistryCache> = RefCell::new(RegistryCache::default());
+}
+
+/// Returns true if a subnet with the given properties fulfills the registry
+/// based criteria of the filter. The `subnet_type` of the filter is not
+/// considered here.
+pub fn subnet_matches_filter(filter: &SubnetFilter, properties: &SubnetProperties) -> bool {
+    let SubnetFilter {
+        subnet_type: _,
+        data_center_regions,
+        min_replication_factor,
+        min_canister_capacity,
+        required_features,
+    } = filter;
+
+    if let Some(min_replication_factor) = min_replication_factor {
+        if properties.replication_factor < *min_replication_factor {
+            return false;
+        }
+    }
+
+    if let Some(min_canister_capacity) = min_canister_capacity {
+        if properties.max_number_of_canisters != 0 {
+            // The number of canisters must have been looked up.
+            let Some(num_canisters) = properties.num_canisters else {
+                return false;
+            };
+            let remaining_capacity = properties
+                .max_number_of_canisters
+                .saturating_sub(num_canisters);
+            if remaining_capacity < *min_canister_capacity {
+                return false;
+            }
+        }
+    }
+
+    for feature in required_features.iter().flatten() {
+        let supported = match feature {
+            SubnetFeature::HttpOutcalls => properties.http_outcalls,
+            SubnetFeature::Ecdsa => properties.ecdsa,
+        };
+        if !supported {
+            return false;
+        }
+    }
+
+    if let Some(allowed_regions) = data_center_regions {
+        let Some(subnet_regions) = &properties.data_center_regions else {
+            return false;
+        };
+        if subnet_regions.is_empty()
+            || !subnet_regions.iter().all(|subnet_region| {
+                allowed_regions
+                    .iter()
+                    .any(|allowed_region| region_is_within(subnet_region, allowed_region))
+            })
+        {
+            return false;
+        }
+    }
+
+    true
+}
+
+/// Returns true if `region` equals `parent_region` or is a more specific
+/// region within it, e.g., "Europe,CH,Zurich" is within "Europe,CH".
+fn region_is_within(region: &str, parent_region: &str) -> bool {
+    match region.strip_prefix(parent_region) {
+        Some(rest) => rest.is_empty() || rest.starts_with(','),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::FutureExt;
+    use ic_management_canister_types::EcdsaCurve;
+    use ic_protobuf::registry::subnet::v1::{EcdsaConfig, SubnetFeatures as SubnetFeaturesPb};
+    use ic_types_test_utils::ids::{node_test_id, subnet_test_id, user_test_id};
+    use std::sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    };
+
+    fn properties() -> SubnetProperties {
+        SubnetProperties {
+            replication_factor: 13,
+            max_number_of_canisters: 0,
+            num_canisters: None,
+            http_outcalls: true,
+            ecdsa: false,
+            data_center_regions: Some(
+                ["Europe,CH,Zurich", "Europe,DE,Frankfurt"]
+                    .into_iter()
+                    .map(String::from)
+                    .collect(),
+            ),
+        }
+    }
+
+    #[test]
+    fn test_empty_filter_matches_any_subnet() {
+        assert!(subnet_matches_filter(
+            &SubnetFilter::default(),
+            &SubnetProperties::default()
+        ));
+    }
+
+    #[test]
+    fn test_min_replication_factor() {
+        let filter = |min_replication_factor| SubnetFilter {
+            min_replication_factor: Some(min_replication_factor),
+            ..Default::default()
+        };
+        assert!(subnet_matches_filter(&filter(13), &properties()));
+        assert!(!subnet_matches_filter(&filter(28), &properties()));
+    }
+
+    #[test]
+    fn test_min_canister_capacity() {
+        let filter = SubnetFilter {
+            min_canister_capacity: Some(100_000),
+            ..Default::default()
+        };
+        let with_canisters = |max_number_of_canisters, num_canisters| SubnetProperties {
+            max_number_of_canisters,
+            num_canisters: Some(num_canisters),
+            ..properties()
+        };
+        // No limit on the number of canisters.
+        assert!(subnet_matches_filter(&filter, &properties()));
+        assert!(subnet_matches_filter(&filter, &with_canisters(100_000, 0)));
+        assert!(subnet_matches_filter(
+            &filter,
+            &with_canisters(120_000, 20_000)
+        ));
+        // The canisters already on the subnet reduce the remaining capacity.
+        assert!(!subnet_matches_filter(
+            &filter,
+            &with_canisters(120_000, 20_001)
+        ));
+        assert!(!subnet_matches_filter(
+            &filter,
+            &with_canisters(100_000, 200_000)
+        ));
+        // The number of canisters must have been looked up.
+        assert!(!subnet_matches_filter(
+            &filter,
+            &SubnetProperties {
+                max_number_of_canisters: 1_000_000,
+                num_canisters: None,
+                ..properties()
+            }
+        ));
+    }
+
+    #[test]
+    fn test_required_features() {
+        let filter = |required_features| SubnetFilter {
+            required_features: Some(required_features),
+            ..Default::default()
+        };
+        assert!(subnet_matches_filter(
+            &filter(vec![SubnetFeature::HttpOutcalls]),
+            &properties()
+        ));
+        assert!(!subnet_matches_filter(
+            &filter(vec![SubnetFeature::HttpOutcalls, SubnetFeature::Ecdsa]),
+            &properties()
+        ));
+        assert!(subnet_matches_filter(
+            &filter(vec![SubnetFeature::HttpOutcalls, SubnetFeature::Ecdsa]),
+            &SubnetProperties {
+                ecdsa: true,
+                ..properties()
+            }
+        ));
+    }
+
+    #[test]
+    fn test_data_center_regions() {
+        let filter = |regions: &[&str]| SubnetFilter {
+            data_center_regions: Some(regions.iter().map(|region| region.to_string()).collect()),
+            ..Default::default()
+        };
+        assert!(subnet_matches_filter(&filter(&["Europe"]), &properties()));
+        assert!(subnet_matches_filter(
+            &filter(&["Europe,CH", "Europe,DE"]),
+            &properties()
+        ));
+        // All nodes must be located in one of the given regions.
+        assert!(!subnet_matches_filter(
+            &filter(&["Europe,CH"]),
+            &properties()
+        ));
+        // Regions only match on whole components.
+        assert!(!subnet_matches_filter(&filter(&["Euro"]), &properties()));
+        // The regions must have been looked up.
+        assert!(!subnet_matches_filter(
+            &filter(&["Europe"]),
+            &SubnetProperties {
+                data_center_regions: None,
+                ..properties()
+            }
+        ));
+    }
+
+    #[test]
+    fn test_region_is_within() {
+        assert!(region_is_within("Europe,CH,Zurich", "Europe,CH,Zurich"));
+        assert!(region_is_within("Europe,CH,Zurich", "Europe,CH"));
+        assert!(!region_is_within("Europe,CH,Zurich", "Europe,C"));
+        assert!(!region_is_within("Europe", "Europe,CH"));
+    }
+
+    const ZURICH: &str = "Europe,CH,Zurich";
+    const FRANKFURT: &str = "Europe,DE,Frankfurt";
+
+    fn key_id() -> EcdsaKeyId {
+        EcdsaKeyId {
+            curve: EcdsaCurve::Secp256k1,
+            name: "key_1".to_string(),
+        }
+    }
+
+    /// Serves registry values from a map, regardless of the requested version,
+    /// and records the calls made.
+    #[derive(Default)]
+    struct FakeSubnetLookupCalls {
+        latest_version: AtomicU64,
+        values: BTreeMap<String, Vec<u8>>,
+        num_canisters: u64,
+        get_value_calls: Mutex<Vec<(String, u64)>>,
+        get_num_canisters_calls: Mutex<Vec<SubnetId>>,
+    }
+
+    impl FakeSubnetLookupCalls {
+        fn insert(&mut self, key: String, value: impl Message) {
+            self.values.insert(key, value.encode_to_vec());
+        }
+
+        fn num_get_value_calls(&self) -> usize {
+            self.get_value_calls.lock().unwrap().len()
+        }
+    }
+
+    #[async_trait]
+    impl SubnetLookupCalls for FakeSubnetLookupCalls {
+        async fn get_latest_version(&self) -> Result<u64, String> {
+            Ok(self.latest_version.load(Ordering::SeqCst))
+        }
+
+        async fn get_value(&self, key: &str, version: u64) -> Result<Option<Vec<u8>>, String> {
+            self.get_value_calls
+                .lock()
+                .unwrap()
+                .push((key.to_string(), version));
+            Ok(self.values.get(key).cloned())
+        }
+
+        async fn get_num_canisters(&self, subnet_id: SubnetId) -> Result<u64, String> {
+            self.get_num_canisters_calls.lock().unwrap().push(subnet_id);
+            Ok(self.num_canisters)
+        }
+    }
+
+    /// A registry with subnet 1, whose four nodes are run by two node operators
+    /// in Zurich and Frankfurt. The subnet holds an ECDSA key and can host up to
+    /// 100 canisters, 60 of which it already hosts.
+    fn fake_registry() -> FakeSubnetLookupCalls {
+        let mut calls = FakeSubnetLookupCalls {
+            latest_version: AtomicU64::new(1),
+            num_canisters: 60,
+            ..Default::default()
+        };
+        calls.insert(
+            make_subnet_record_key(subnet_test_id(1)),
+            SubnetRecord {
+                membership: (1..=4).map(|i| node_test_id(i).get().to_vec()).collect(),
+                max_number_of_canisters: 100,
+                features: Some(SubnetFeaturesPb {
+                    http_requests: true,
+                    ..Default::default()
+                }),
+                ecdsa_config: Some(EcdsaConfig {
+                    key_ids: vec![(&key_id()).into()],
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+        );
+        for i in 1..=4 {
+            calls.insert(
+                make_node_record_key(node_test_id(i)),
+                NodeRecord {
+                    node_operator_id: user_test_id(1 + i % 2).get().to_vec(),
+                    ..Default::default()
+                },
+            );
+        }
+        for (i, dc_id, region) in [(1, "fr1", FRANKFURT), (2, "zh1", ZURICH)] {
+            calls.insert(
+                make_node_operator_record_key(user_test_id(i).get()),
+                NodeOperatorRecord {
+                    dc_id: dc_id.to_string(),
+                    ..Default::default()
+                },
+            );
+            calls.insert(
+                make_data_center_record_key(dc_id),
+                DataCenterRecord {
+                    id: dc_id.to_string(),
+                    region: region.to_string(),
+                    ..Default::default()
+                },
+            );
+        }
+        calls
+    }
+
+    fn enable_ecdsa_signing(calls: &mut FakeSubnetLookupCalls) {
+        calls.insert(
+            make_ecdsa_signing_subnet_list_key(&key_id()),
+            EcdsaSigningSubnetList {
+                subnets: vec![subnet_id_into_protobuf(subnet_test_id(1))],
+            },
+        );
+    }
+
+    fn filter_using_all_properties() -> SubnetFilter {
+        SubnetFilter {
+            data_center_regions: Some(vec!["Europe".to_string()]),
+            min_replication_factor: Some(4),
+            min_canister_capacity: Some(40),
+            required_features: Some(vec![SubnetFeature::HttpOutcalls, SubnetFeature::Ecdsa]),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_registry_client_only_looks_up_properties_used_by_the_filter() {
+        thread_local! {
+            static CACHE: RefCell<RegistryCache> = RefCell::new(RegistryCache::default());
+        }
+        let mut calls = fake_registry();
+        enable_ecdsa_signing(&mut calls);
+        let client = RegistrySubnetClient::new(calls, &CACHE);
+
+        let properties = client
+            .get_subnet_properties(subnet_test_id(1), &SubnetFilter::default())
+            .now_or_never()
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(
+            properties,
+            SubnetProperties {
+                replication_factor: 4,
+                max_number_of_canisters: 100,
+                num_canisters: None,
+                http_outcalls: true,
+                ecdsa: false,
+                data_center_regions: None,
+            }
+        );
+        // Only the subnet record is read.
+        assert_eq!(client.calls.num_get_value_calls(), 1);
+        assert!(client
+            .calls
+            .get_num_canisters_calls
+            .lock()
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_registry_client_looks_up_all_properties_used_by_the_filter() {
+        thread_local! {
+            static CACHE: RefCell<RegistryCache> = RefCell::new(RegistryCache::default());
+        }
+        let mut calls = fake_registry();
+        enable_ecdsa_signing(&mut calls);
+        let client = RegistrySubnetClient::new(calls, &CACHE);
+        let filter = filter_using_all_properties();
+
+        let properties = client
+            .get_subnet_properties(subnet_test_id(1), &filter)
+            .now_or_never()
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(
+            properties,
+            SubnetProperties {
+                replication_factor: 4,
+                max_number_of_canisters: 100,
+                num_canisters: Some(60),
+                http_outcalls: true,
+                ecdsa: true,
+                data_center_regions: Some(
+                    [ZURICH, FRANKFURT].into_iter().map(String::from).collect()
+                ),
+            }
+        );
+        assert!(subnet_matches_filter(&filter, &properties));
+        // The subnet record, the signing subnet list, four node records and
+        // each of the two node operator and data center records once.
+        assert_eq!(client.calls.num_get_value_calls(), 10);
+        assert_eq!(
+            *client.calls.get_num_canisters_calls.lock().unwrap(),
+            vec![subnet_test_id(1)]
+        );
+
+        // The remaining capacity of 40 canisters does not suffice for more.
+        let filter = SubnetFilter {
+            min_canister_capacity: Some(41),
+            ..filter
+        };
+        assert!(!subnet_matches_filter(&filter, &properties));
+    }
+
+    #[test]
+    fn test_registry_client_requires_ecdsa_signing_to_be_enabled() {
+        thread_local! {
+            static CACHE: RefCell<RegistryCache> = RefCell::new(RegistryCache::default());
+        }
+        // The subnet holds the key, but signing with it is not enabled.
+        let client = RegistrySubnetClient::new(fake_registry(), &CACHE);
+        let filter = SubnetFilter {
+            required_features: Some(vec![SubnetFeature::Ecdsa]),
+            ..Default::default()
+        };
+
+        let properties = client
+            .get_subnet_properties(subnet_test_id(1), &filter)
+            .now_or_never()
+            .unwrap()
+            .unwrap();
+
+        assert!(!properties.ecdsa);
+        assert!(!subnet_matches_filter(&filter, &properties));
+    }
+
+    #[test]
+    fn test_registry_client_fails_for_unknown_subnet() {
+        thread_local! {
+            static CACHE: RefCell<RegistryCache> = RefCell::new(RegistryCache::default());
+        }
+        let client = RegistrySubnetClient::new(fake_registry(), &CACHE);
+
+        let result = client
+            .get_subnet_properties(subnet_test_id(2), &SubnetFilter::default())
+            .now_or_never()
+            .unwrap();
+
+        assert_eq!(
+            result,
+            Err(format!(
+                "{} is not present in the registry",
+                make_subnet_record_key(subnet_test_id(2))
+            ))
+        );
+    }
+
+    #[test]
+    fn test_registry_client_caches_values_per_version() {
+        thread_local! {
+            static CACHE: RefCell<RegistryCache> = RefCell::new(RegistryCache::default());
+        }
+        let mut calls = fake_registry();
+        enable_ecdsa_signing(&mut calls);
+        let client = RegistrySubnetClient::new(calls, &CACHE);
+        let filter = filter_using_all_properties();
+        let get_subnet_properties = || {
+            client
+                .get_subnet_properties(subnet_test_id(1), &filter)
+                .now_or_never()
+                .unwrap()
+                .unwrap()
+        };
+
+        let properties = get_subnet_properties();
+        assert_eq!(client.calls.num_get_value_calls(), 10);
+
+        // At the same version, all registry values are served from the cache.
+        assert_eq!(get_subnet_properties(), properties);
+        assert_eq!(client.calls.num_get_value_calls(), 10);
+        // The number of canisters is not recorded in the registry and is
+        // always looked up.
+        assert_eq!(
+            client.calls.get_num_canisters_calls.lock().unwrap().len(),
+            2
+        );
+
+        // A new registry version resets the cache.
+        client.calls.latest_version.store(2, Ordering::SeqCst);
+        assert_eq!(get_subnet_properties(), properties);
+        let get_value_calls = client.calls.get_value_calls.lock().unwrap().clone();
+        assert_eq!(get_value_calls.len(), 20);
+        assert!(get_value_calls[10..]
+            .iter()
+            .all(|(_key, version)| *version == 2));
+        assert_eq!(get_subnet_properties(), properties);
+        assert_eq!(client.calls.num_get_value_calls(), 20);
+    }
+
+    #[test]
+    fn test_registry_cache_only_moves_forward() {
+        let mut cache = RegistryCache::default();
+        cache.insert("a".to_string(), 2, Some(vec![1]));
+        cache.insert("b".to_string(), 2, None);
+        assert_eq!(cache.get("a", 2), Some(&Some(vec![1])));
+        assert_eq!(cache.get("b", 2), Some(&None));
+        assert_eq!(cache.get("a", 1), None);
+        assert_eq!(cache.get("c", 2), None);
+
+        // A value read at an older version, e.g., by a lookup that completes
+        // last, is dropped.
+        cache.insert("c".to_string(), 1, Some(vec![3]));
+        assert_eq!(cache.get("c", 1), None);
+        assert_eq!(cache.get("c", 2), None);
+
+        // A value read at a newer version resets the cache.
+        cache.insert("c".to_string(), 3, Some(vec![3]));
+        assert_eq!(cache.get("c", 3), Some(&Some(vec![3])));
+        assert_eq!(cache.get("a", 2), None);
+        assert_eq!(cache.get("a", 3), None);
+    }
+}
@@ -17,7 +17,11 @@ use async_trait::async_trait;
 use criterion::{criterion_group, criterion_main, Criterion};
 use futures::future::FutureExt;
 use ic_base_types::{CanisterId, PrincipalId};
-use ic_nervous_system_common::{cmc::FakeCmc, ledger::IcpLedger, NervousSystemError};
+use ic_nervous_system_common::{
+    cmc::FakeCmc,
+    ledger::{IcpLedger, TransferWithCreatedAtTimeError},
+    NervousSystemError,
+};
 use ic_nns_common::pb::v1::NeuronId;
 use ic_nns_governance::{
     governance::{Environment, Governance, HeapGrowthPotential},
@@ -93,6 +97,18 @@ impl IcpLedger for MockLedger {
         unimplemented!()
     }
 
+    async fn transfer_funds_with_created_at_time(
+        &self,
+        _amount_e8s: u64,
+        _fee_e8s: u64,
+        _from_subaccount: Option<Subaccount>,
+        _to: AccountIdentifier,
+        _memo: u64,
+        _created_at_time_nanos: u64,
+    ) -> Result<u64, TransferWithCreatedAtTimeError> {
+        unimplemented!()
+    }
+
     async fn total_supply(&self) -> Result<Tokens, NervousSystemError> {
         Err(NervousSystemError::default())
     }
@@ -46,15 +46,15 @@ use ic_nns_governance::{
             ClaimOrRefresh, Command, NeuronIdOrSubaccount, RegisterVote,
         },
         manage_neuron_response, ClaimOrRefreshNeuronFromAccount,
-        ClaimOrRefreshNeuronFromAccountResponse, ExecuteNnsFunction,
-        GetNeuronsFundAuditInfoRequest, GetNeuronsFundAuditInfoResponse,
-        Governance as GovernanceProto, GovernanceError, ListKnownNeuronsResponse, ListNeurons,
-        ListNeuronsResponse, ListNodeProvidersResponse, ListProposalInfo, ListProposalInfoResponse,
-        ManageNeuron, ManageNeuronResponse, MostRecentMonthlyNodeProviderRewards, NetworkEconomics,
-        Neuron, NeuronInfo, NnsFunction, NodeProvider, Proposal, ProposalInfo, RewardEvent,
-        RewardNodeProviders, SettleCommunityFundParticipation,
-        SettleNeuronsFundParticipationRequest, SettleNeuronsFundParticipationResponse,
-        UpdateNodeProvider, Vote,
+        ClaimOrRefreshNeuronFromAccountResponse, ExecuteNnsFunction, GetNeuronVotingHistoryRequest,
+        GetNeuronVotingHistoryResponse, GetNeuronsFundAuditInfoRequest,
+        GetNeuronsFundAuditInfoResponse, Governance as GovernanceProto, GovernanceError,
+        ListKnownNeuronsResponse, ListNeurons, ListNeuronsResponse, ListNodeProvidersResponse,
+        ListProposalInfo, ListProposalInfoResponse, ManageNeuron, ManageNeuronResponse,
+        MostRecentMonthlyNodeProviderRewards, NetworkEconomics, Neuron, NeuronInfo, NnsFunction,
+        NodeProvider, Proposal, ProposalInfo, RewardEvent, RewardNodeProviders,
+        SettleCommunityFundParticipation, SettleNeuronsFundParticipationRequest,
+        SettleNeuronsFundParticipationResponse, UpdateNodeProvider, Vote,
     },
     storage::{grow_upgrades_memory_to, validate_stable_storage, with_upgrades_memory},
 };
@@ -609,6 +609,20 @@ fn get_proposal_info_(id: ProposalId) -> Option<ProposalInfo> {
     governance().get_proposal_info(&caller(), id)
 }
 
+/// Returns the votes cast by a neuron, in ascending order of proposal id.
+#[export_name = "canister_query get_neuron_voting_history"]
+fn get_neuron_voting_history() {
+    debug_log("get_neuron_voting_history");
+    over(candid_one, get_neuron_voting_history_)
+}
+
+#[candid_method(query, rename = "get_neuron_voting_history")]
+fn get_neuron_voting_history_(
+    request: GetNeuronVotingHistoryRequest,
+) -> Result<GetNeuronVotingHistoryResponse, GovernanceError> {
+    governance().get_neuron_voting_history(&caller(), &request)
+}
+
 #[export_name = "canister_query get_neurons_fund_audit_info"]
 fn get_neurons_fund_audit_info() {
     debug_log("get_neurons_fund_audit_info");
@@ -243,6 +243,10 @@ pub struct Neuron {
     /// of the different states.
     #[prost(enumeration = "NeuronType", optional, tag = "22")]
     pub neuron_type: ::core::option::Option<i32>,
+    /// The maturity disbursements initiated by this neuron that have not been
+    /// finalized yet. See \[ManageNeuron.DisburseMaturity\].
+    #[prost(message, repeated, tag = "23")]
+    pub disburse_maturity_in_progress: ::prost::alloc::vec::Vec<DisburseMaturityInProgress>,
     /// At any time, at most one of `when_dissolved` and
     /// `dissolve_delay` are specified.
     ///
@@ -311,6 +315,41 @@ pub mod neuron {
         DissolveDelaySeconds(u64),
     }
 }
+/// A maturity disbursement that has been initiated, but whose ICP has not been
+/// minted yet. Maturity modulation is only applied when it is finalized.
+#[derive(candid::CandidType, candid::Deserialize, serde::Serialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DisburseMaturityInProgress {
+    /// The amount of maturity being disbursed, before maturity modulation is
+    /// applied.
+    #[prost(uint64, tag = "1")]
+    pub amount_e8s: u64,
+    /// The timestamp at which the disbursement was initiated.
+    #[prost(uint64, tag = "2")]
+    pub timestamp_of_disbursement_seconds: u64,
+    /// The account to which the ICP is minted.
+    #[prost(message, optional, tag = "3")]
+    pub account_to_disburse_to: ::core::option::Option<::icp_ledger::protobuf::AccountIdentifier>,
+    /// The timestamp at which the disbursement is finalized, i.e., the ICP is
+    /// minted.
+    #[prost(uint64, tag = "4")]
+    pub finalize_disbursement_timestamp_seconds: u64,
+    /// The memo of the minting transfer. Set when the disbursement is initiated,
+    /// and unique among the disbursements in progress of the neuron.
+    #[prost(uint64, optional, tag = "5")]
+    pub memo: ::core::option::Option<u64>,
+    /// The amount of the minting transfer, i.e., `amount_e8s` with maturity
+    /// modulation applied. Set when the minting transfer is first attempted, and
+    /// reused when it is retried, together with `created_at_time_nanos`.
+    #[prost(uint64, optional, tag = "6")]
+    pub minting_amount_e8s: ::core::option::Option<u64>,
+    /// The `created_at_time` of the minting transfer, which makes the ledger
+    /// deduplicate retries of the transfer. Set when the minting transfer is
+    /// first attempted.
+    #[prost(uint64, optional, tag = "7")]
+    pub created_at_time_nanos: ::core::option::Option<u64>,
+}
 /// Subset of Neuron that has no collections or big fields that might not exist in most neurons, and
 /// the goal is to keep the size of the struct consistent and can be easily stored in a
 /// StableBTreeMap. For the meaning of each field, see the Neuron struct.
@@ -659,7 +698,7 @@ pub struct ManageNeuron {
     pub neuron_id_or_subaccount: ::core::option::Option<manage_neuron::NeuronIdOrSubaccount>,
     #[prost(
         oneof = "manage_neuron::Command",
-        tags = "2, 3, 4, 5, 6, 7, 8, 9, 10, 13, 14, 15"
+        tags = "2, 3, 4, 5, 6, 7, 8, 9, 10, 13, 14, 15, 16"
     )]
     pub command: ::core::option::Option<manage_neuron::Command>,
 }
@@ -861,6 +900,23 @@ pub mod manage_neuron {
         #[prost(uint32, optional, tag = "1")]
         pub percentage_to_stake: ::core::option::Option<u32>,
     }
+    /// Disburse the maturity of a neuron to a ledger account. The maturity is
+    /// deducted from the neuron right away, and the corresponding ICP (subject
+    /// to the maturity modulation at that time) are minted after a delay of
+    /// 7 days. Until then, the disbursement is recorded in the neuron's
+    /// `disburse_maturity_in_progress`.
+    #[derive(candid::CandidType, candid::Deserialize, serde::Serialize, comparable::Comparable)]
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct DisburseMaturity {
+        /// The percentage of maturity to disburse, from 1 to 100 (inclusive).
+        #[prost(uint32, tag = "1")]
+        pub percentage_to_disburse: u32,
+        /// The account to disburse to. If not set, the caller's default account
+        /// is used.
+        #[prost(message, optional, tag = "2")]
+        pub to_account: ::core::option::Option<::icp_ledger::protobuf::AccountIdentifier>,
+    }
     /// Disburse a portion of this neuron's stake into another neuron.
     /// This allows to split a neuron but with a new dissolve delay
     /// and owned by someone else.
@@ -1008,6 +1064,8 @@ pub mod manage_neuron {
         Merge(Merge),
         #[prost(message, tag = "15")]
         StakeMaturity(StakeMaturity),
+        #[prost(message, tag = "16")]
+        DisburseMaturity(DisburseMaturity),
     }
 }
 /// The response of the ManageNeuron command
@@ -1019,7 +1077,7 @@ pub mod manage_neuron {
 pub struct ManageNeuronResponse {
     #[prost(
         oneof = "manage_neuron_response::Command",
-        tags = "1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13"
+        tags = "1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14"
     )]
     pub command: ::core::option::Option<manage_neuron_response::Command>,
 }
@@ -1066,6 +1124,15 @@ pub mod manage_neuron_response {
     #[derive(candid::CandidType, candid::Deserialize, serde::Serialize, comparable::Comparable)]
     #[allow(clippy::derive_partial_eq_without_eq)]
     #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct DisburseMaturityResponse {
+        /// The amount of maturity deducted from the neuron. The amount of ICP
+        /// minted will differ due to maturity modulation.
+        #[prost(uint64, tag = "1")]
+        pub amount_deducted_e8s: u64,
+    }
+    #[derive(candid::CandidType, candid::Deserialize, serde::Serialize, comparable::Comparable)]
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
     pub struct FollowResponse {}
     #[derive(candid::CandidType, candid::Deserialize, serde::Serialize, comparable::Comparable)]
     #[allow(clippy::derive_partial_eq_without_eq)]
@@ -1152,6 +1219,8 @@ pub mod manage_neuron_response {
         Merge(MergeResponse),
         #[prost(message, tag = "13")]
         StakeMaturity(StakeMaturityResponse),
+        #[prost(message, tag = "14")]
+        DisburseMaturity(DisburseMaturityResponse),
     }
 }
 #[derive(candid::CandidType, candid::Deserialize, serde::Serialize, comparable::Comparable)]
@@ -1418,6 +1487,14 @@ pub struct ProposalData {
     /// `cf_participants` and use only this field for managing the Neurons' Fund swap participation.
     #[prost(message, optional, tag = "21")]
     pub neurons_fund_data: ::core::option::Option<NeuronsFundData>,
+    /// For `ExecuteNnsFunction` proposals, the payload decoded and rendered as
+    /// Candid text at submission time. Wasm modules and install arguments are
+    /// rendered as their SHA-256 hashes. Payloads of NNS functions whose payload
+    /// type governance does not know are rendered without field names, or as
+    /// their SHA-256 hash if they are not valid Candid. Renderings longer than
+    /// 10_000 bytes are truncated.
+    #[prost(string, optional, tag = "22")]
+    pub payload_text_rendering: ::core::option::Option<::prost::alloc::string::String>,
 }
 /// This structure contains data for settling the Neurons' Fund participation in an SNS token swap.
 #[derive(candid::CandidType, candid::Deserialize, serde::Serialize, comparable::Comparable)]
@@ -1461,6 +1538,50 @@ pub struct NeuronsFundAuditInfo {
     #[prost(message, optional, tag = "3")]
     pub neurons_fund_refunds: ::core::option::Option<NeuronsFundSnapshot>,
 }
+/// A request to list the votes cast by a neuron, in ascending order of proposal ID.
+/// Votes cast by following are listed as well, and flagged as such. Only the votes
+/// on the 50,000 most recent proposals are kept.
+///
+/// The voting history of a neuron can be read by its controller and hot keys. The
+/// voting history of known neurons is public.
+#[derive(candid::CandidType, candid::Deserialize, serde::Serialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetNeuronVotingHistoryRequest {
+    /// The neuron whose votes are listed.
+    #[prost(message, optional, tag = "1")]
+    pub neuron_id: ::core::option::Option<::ic_nns_common::pb::v1::NeuronId>,
+    /// If specified, only return votes on proposals with an ID greater than or equal to
+    /// the specified proposal ID. If not specified, start with the oldest vote.
+    #[prost(message, optional, tag = "2")]
+    pub start: ::core::option::Option<::ic_nns_common::pb::v1::ProposalId>,
+    /// Limit on the number of votes to return. If no value is specified, or if a value
+    /// greater than 100 is specified, 100 will be used.
+    #[prost(uint32, tag = "3")]
+    pub limit: u32,
+}
+/// A vote recorded in the voting history of a neuron.
+#[derive(candid::CandidType, candid::Deserialize, serde::Serialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct NeuronVote {
+    #[prost(message, optional, tag = "1")]
+    pub proposal_id: ::core::option::Option<::ic_nns_common::pb::v1::ProposalId>,
+    #[prost(enumeration = "Vote", tag = "2")]
+    pub vote: i32,
+    /// Whether the vote was cast on behalf of the neuron because it follows other
+    /// neurons, rather than by the neuron directly.
+    #[prost(bool, tag = "3")]
+    pub cast_by_following: bool,
+}
+#[derive(candid::CandidType, candid::Deserialize, serde::Serialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetNeuronVotingHistoryResponse {
+    /// The votes of the neuron, in ascending order of proposal ID.
+    #[prost(message, repeated, tag = "1")]
+    pub votes: ::prost::alloc::vec::Vec<NeuronVote>,
+}
 #[derive(candid::CandidType, candid::Deserialize, serde::Serialize, comparable::Comparable)]
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -1811,6 +1932,12 @@ pub struct ProposalInfo {
     pub deadline_timestamp_seconds: ::core::option::Option<u64>,
     #[prost(message, optional, tag = "20")]
     pub derived_proposal_information: ::core::option::Option<DerivedProposalInformation>,
+    /// See ProposalData.payload_text_rendering. Like the payload itself, this is
+    /// omitted by `list_proposals` if it is larger than
+    /// EXECUTE_NNS_FUNCTION_PAYLOAD_LISTING_BYTES_MAX, or if `omit_large_fields`
+    /// is set.
+    #[prost(string, optional, tag = "21")]
+    pub payload_text_rendering: ::core::option::Option<::prost::alloc::string::String>,
 }
 /// Network economics contains the parameters for several operations related
 /// to the economy of the network. When submitting a NetworkEconomics proposal
@@ -2338,7 +2465,7 @@ pub mod governance {
         pub timestamp: u64,
         #[prost(
             oneof = "neuron_in_flight_command::Command",
-            tags = "2, 3, 5, 7, 8, 9, 10, 20, 21"
+            tags = "2, 3, 5, 7, 8, 9, 10, 20, 21, 22"
         )]
         pub command: ::core::option::Option<neuron_in_flight_command::Command>,
     }
@@ -2381,6 +2508,8 @@ pub mod governance {
             Spawn(::ic_nns_common::pb::v1::NeuronId),
             #[prost(message, tag = "21")]
             SyncCommand(SyncCommand),
+            #[prost(message, tag = "22")]
+            FinalizeDisburseMaturity(super::super::DisburseMaturityInProgress),
         }
     }
     /// Stores metrics that are too costly to compute each time metrics are
@@ -2646,8 +2775,9 @@ pub struct ListProposalInfo {
     /// respected even when this option is set to true.
     #[prost(bool, optional, tag = "6")]
     pub include_all_manage_neuron_proposals: ::core::option::Option<bool>,
-    /// Omits "large fields" from the response. Currently only omits the
-    /// `logo` and `token_logo` field of CreateServiceNervousSystem proposals. This
+    /// Omits "large fields" from the response. Currently omits the `logo` and
+    /// `token_logo` field of CreateServiceNervousSystem proposals and the
+    /// `payload_text_rendering` of all proposals. This
     /// is useful to improve download times and to ensure that the response to the
     /// request doesn't exceed the message size limit.
     #[prost(bool, optional, tag = "7")]
@@ -2677,6 +2807,29 @@ pub struct ListNeurons {
     /// neurons that the calling principal is authorized to read.
     #[prost(bool, tag = "2")]
     pub include_neurons_readable_by_caller: bool,
+    /// The "requested list" is sorted by neuron ID, filtered (see below), and,
+    /// if `page_size` is specified, split into pages of `page_size` neurons. Only
+    /// the page with index `page_number` (starting from 0, which is the default)
+    /// is returned then.
+    #[prost(uint64, optional, tag = "3")]
+    pub page_number: ::core::option::Option<u64>,
+    /// The number of neurons per page. If no value is specified, the requested
+    /// list is not paginated. If a value greater than 500 is specified, 500 will
+    /// be used.
+    #[prost(uint64, optional, tag = "4")]
+    pub page_size: ::core::option::Option<u64>,
+    /// If not empty, only neurons in one of these states are returned.
+    #[prost(enumeration = "NeuronState", repeated, tag = "5")]
+    pub neuron_states: ::prost::alloc::vec::Vec<i32>,
+    /// If specified, only neurons with a stake of at least this amount are
+    /// returned.
+    #[prost(uint64, optional, tag = "6")]
+    pub min_stake_e8s: ::core::option::Option<u64>,
+    /// If specified, only neurons that have (if true) or do not have (if false)
+    /// hot keys are returned. Neurons whose full data the caller is not
+    /// authorized to read never match this filter.
+    #[prost(bool, optional, tag = "7")]
+    pub has_hot_keys: ::core::option::Option<bool>,
 }
 /// A response to a `ListNeurons` request.
 ///
@@ -2695,6 +2848,10 @@ pub struct ListNeuronsResponse {
     /// `ManageNeuron` topic).
     #[prost(message, repeated, tag = "2")]
     pub full_neurons: ::prost::alloc::vec::Vec<Neuron>,
+    /// The number of pages of the filtered "requested list", given the page size
+    /// of the request. Only set if the request specified a page size.
+    #[prost(uint64, optional, tag = "3")]
+    pub total_pages_available: ::core::option::Option<u64>,
 }
 /// A response to "ListKnownNeurons"
 #[derive(candid::CandidType, candid::Deserialize, serde::Serialize, comparable::Comparable)]
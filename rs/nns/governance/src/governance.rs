@@ -27,7 +27,9 @@ use crate::{
             ClaimOrRefresh, Command, NeuronIdOrSubaccount,
         },
         manage_neuron_response,
-        manage_neuron_response::{MergeMaturityResponse, StakeMaturityResponse},
+        manage_neuron_response::{
+            DisburseMaturityResponse, MergeMaturityResponse, StakeMaturityResponse,
+        },
         neuron::{DissolveState, Followees},
         neurons_fund_snapshot::NeuronsFundNeuronPortion as NeuronsFundNeuronPortionPb,
         proposal,
@@ -35,12 +37,14 @@ use crate::{
         reward_node_provider::{RewardMode, RewardToAccount},
         settle_neurons_fund_participation_request, settle_neurons_fund_participation_response,
         settle_neurons_fund_participation_response::NeuronsFundNeuron as NeuronsFundNeuronPb,
-        swap_background_information, Ballot, CreateServiceNervousSystem, ExecuteNnsFunction,
-        GetNeuronsFundAuditInfoRequest, GetNeuronsFundAuditInfoResponse,
-        Governance as GovernanceProto, GovernanceError, KnownNeuron, ListKnownNeuronsResponse,
-        ListNeurons, ListNeuronsResponse, ListProposalInfo, ListProposalInfoResponse, ManageNeuron,
-        ManageNeuronResponse, MostRecentMonthlyNodeProviderRewards, Motion, NetworkEconomics,
-        Neuron, NeuronInfo, NeuronState, NeuronsFundAuditInfo, NeuronsFundData,
+        swap_background_information, Ballot, CreateServiceNervousSystem,
+        DisburseMaturityInProgress, ExecuteNnsFunction, GetNeuronVotingHistoryRequest,
+        GetNeuronVotingHistoryResponse, GetNeuronsFundAuditInfoRequest,
+        GetNeuronsFundAuditInfoResponse, Governance as GovernanceProto, GovernanceError,
+        KnownNeuron, ListKnownNeuronsResponse, ListNeurons, ListNeuronsResponse, ListProposalInfo,
+        ListProposalInfoResponse, ManageNeuron, ManageNeuronResponse,
+        MostRecentMonthlyNodeProviderRewards, Motion, NetworkEconomics, Neuron, NeuronInfo,
+        NeuronState, NeuronsFundAuditInfo, NeuronsFundData,
         NeuronsFundParticipation as NeuronsFundParticipationPb,
         NeuronsFundSnapshot as NeuronsFundSnapshotPb, NnsFunction, NodeProvider, Proposal,
         ProposalData, ProposalInfo, ProposalRewardStatus, ProposalStatus, RewardEvent,
@@ -48,8 +52,12 @@ use crate::{
         SettleNeuronsFundParticipationResponse, Tally, Topic, UpdateNodeProvider, Vote,
         WaitForQuietState,
     },
-    proposals::create_service_nervous_system::ExecutedCreateServiceNervousSystemProposal,
-    storage::with_stable_neuron_store,
+    proposals::{
+        create_service_nervous_system::ExecutedCreateServiceNervousSystemProposal,
+        execute_nns_function::render_execute_nns_function_payload,
+    },
+    storage::{with_stable_neuron_store, with_voting_history_store, with_voting_history_store_mut},
+    voting_history::{record_neuron_votes, VOTING_HISTORY_RETENTION_NUM_PROPOSALS},
 };
 use async_trait::async_trait;
 use candid::{Decode, Encode};
@@ -61,7 +69,10 @@ use dfn_protobuf::ToProto;
 use ic_base_types::{CanisterId, PrincipalId};
 use ic_crypto_sha2::Sha256;
 use ic_nervous_system_common::{
-    cmc::CMC, ledger, ledger::IcpLedger, NervousSystemError, SECONDS_PER_DAY,
+    cmc::CMC,
+    ledger,
+    ledger::{IcpLedger, TransferWithCreatedAtTimeError},
+    NervousSystemError, SECONDS_PER_DAY,
 };
 use ic_nervous_system_governance::maturity_modulation::apply_maturity_modulation;
 use ic_nervous_system_proto::pb::v1::GlobalTimeOfDay;
@@ -172,6 +183,16 @@ pub const MAX_NUMBER_OF_NEURONS: usize = 280_000;
 /// The maximum number results returned by the method `list_proposals`.
 pub const MAX_LIST_PROPOSAL_RESULTS: u32 = 100;
 
+/// The maximum number of neurons returned by the method `list_neurons`.
+pub const MAX_LIST_NEURONS_RESULTS: u64 = 500;
+
+/// The maximum number of votes returned by the method `get_neuron_voting_history`.
+pub const MAX_NEURON_VOTING_HISTORY_RESULTS: u32 = 100;
+
+/// The maximum number of votes that are removed from the voting history in a single run of the
+/// periodic tasks, either because the neuron was removed or because the proposal is too old.
+pub const MAX_VOTING_HISTORY_ENTRIES_TO_REMOVE_PER_PERIODIC_TASK: usize = 1_000;
+
 /// The number of e8s per ICP;
 const E8S_PER_ICP: u64 = TOKEN_SUBDIVIDABLE_BY;
 
@@ -209,6 +230,19 @@ const NODE_PROVIDER_REWARD_PERIOD_SECONDS: u64 = 2629800;
 
 const VALID_MATURITY_MODULATION_BASIS_POINTS_RANGE: RangeInclusive<i32> = -500..=500;
 
+/// The time between initiating a maturity disbursement and minting the
+/// corresponding ICP. Maturity modulation is applied at the end of this period.
+pub const MATURITY_DISBURSEMENT_DELAY_SECONDS: u64 = 7 * ONE_DAY_SECONDS;
+
+/// The maximum number of maturity disbursements a neuron can have in progress.
+pub const MAX_NUM_DISBURSE_MATURITY_IN_PROGRESS: usize = 10;
+
+/// How long after its `created_at_time` a rejected minting transfer of a maturity disbursement
+/// is known not to have been recorded by the ledger. The ledger only deduplicates transfers
+/// within its transaction window of 24 hours, so after that, a transfer whose earlier attempt
+/// was recorded is rejected as too old rather than reported as a duplicate.
+const MATURITY_DISBURSEMENT_TRANSFER_DEDUPLICATION_WINDOW_SECONDS: u64 = ONE_DAY_SECONDS - 60 * 60;
+
 // Constant set of deprecated but not yet deleted topics. These topics should
 // not be allowed to be followed on. They are represented as a constant array
 // instead of a static HashSet for brevity, and because search time is equivalent
@@ -459,6 +493,12 @@ impl ManageNeuronResponse {
         }
     }
 
+    pub fn disburse_maturity_response(response: DisburseMaturityResponse) -> Self {
+        ManageNeuronResponse {
+            command: Some(manage_neuron_response::Command::DisburseMaturity(response)),
+        }
+    }
+
     pub fn follow_response() -> Self {
         ManageNeuronResponse {
             command: Some(manage_neuron_response::Command::Follow(
@@ -1131,6 +1171,7 @@ impl ProposalInfo {
     fn omit_large_fields(self) -> Self {
         ProposalInfo {
             proposal: self.proposal.map(|proposal| proposal.omit_large_fields()),
+            payload_text_rendering: None,
             ..self
         }
     }
@@ -1938,31 +1979,88 @@ impl Governance {
         caller: &PrincipalId,
     ) -> ListNeuronsResponse {
         let now = self.env.now();
-        let implicitly_requested_neurons = if req.include_neurons_readable_by_caller {
-            self.get_neuron_ids_by_principal(caller)
-        } else {
-            Vec::new()
+        let ListNeurons {
+            neuron_ids,
+            include_neurons_readable_by_caller,
+            page_number,
+            page_size,
+            neuron_states,
+            min_stake_e8s,
+            has_hot_keys,
+        } = req;
+
+        let mut requested_neuron_ids: BTreeSet<NeuronId> =
+            neuron_ids.iter().map(|id| NeuronId { id: *id }).collect();
+        if *include_neurons_readable_by_caller {
+            requested_neuron_ids.extend(self.get_neuron_ids_by_principal(caller));
+        }
+
+        // The neurons whose full data the caller may read, i.e. the neurons
+        // that the caller controls or is a hot key of, and those that follow
+        // such neurons on the `NeuronManagement` topic (see `get_full_neuron`).
+        let full_neuron_ids: HashSet<NeuronId> = self
+            .get_managed_neuron_ids_for(self.get_neuron_ids_by_principal(caller))
+            .into_iter()
+            .collect();
+
+        // Neurons that do not exist are dropped here, so that they do not take
+        // up room in the pages. Hot keys are only revealed to callers who may
+        // read the full neuron, so when filtering by hot keys, the neurons of
+        // other callers never match.
+        let is_selected = |neuron_id: &NeuronId, neuron: &Neuron| {
+            (neuron_states.is_empty() || neuron_states.contains(&(neuron.state(now) as i32)))
+                && min_stake_e8s.map_or(true, |min_stake_e8s| neuron.stake_e8s() >= min_stake_e8s)
+                && has_hot_keys.map_or(true, |has_hot_keys| {
+                    full_neuron_ids.contains(neuron_id)
+                        && has_hot_keys == !neuron.hot_keys.is_empty()
+                })
         };
-        let request_neuron_ids: Vec<NeuronId> = req
-            .neuron_ids
-            .iter()
-            .map(|id| NeuronId { id: *id })
+        let is_filtered =
+            !neuron_states.is_empty() || min_stake_e8s.is_some() || has_hot_keys.is_some();
+        let selected_neuron_ids: Vec<NeuronId> = requested_neuron_ids
+            .into_iter()
+            .filter(|neuron_id| {
+                if is_filtered {
+                    self.neuron_store
+                        .with_abridged_neuron(neuron_id, |neuron| is_selected(neuron_id, neuron))
+                        .unwrap_or_default()
+                } else {
+                    self.neuron_store.contains(*neuron_id)
+                }
+            })
             .collect();
-        let requested_list = || {
-            request_neuron_ids
-                .iter()
-                .chain(implicitly_requested_neurons.iter())
+
+        // Only paginate if the caller asked for it, so that callers that are
+        // not aware of pagination keep receiving all their neurons.
+        let (page, total_pages_available) = match page_size {
+            Some(page_size) => {
+                let page_size = (*page_size).clamp(1, MAX_LIST_NEURONS_RESULTS);
+                let total_pages_available = (selected_neuron_ids.len() as u64).div_ceil(page_size);
+                let page: Vec<NeuronId> = selected_neuron_ids
+                    .into_iter()
+                    .skip(page_number.unwrap_or_default().saturating_mul(page_size) as usize)
+                    .take(page_size as usize)
+                    .collect();
+                (page, Some(total_pages_available))
+            }
+            None => (selected_neuron_ids, None),
         };
+
+        let mut neuron_infos = HashMap::new();
+        let mut full_neurons = Vec::new();
+        for neuron_id in page {
+            let _ = self.with_neuron(&neuron_id, |neuron| {
+                neuron_infos.insert(neuron_id.id, neuron.get_neuron_info(now));
+                if full_neuron_ids.contains(&neuron_id) {
+                    full_neurons.push(neuron.clone().without_deprecated_topics_from_followees());
+                }
+            });
+        }
+
         ListNeuronsResponse {
-            neuron_infos: requested_list()
-                .filter_map(|id| {
-                    self.with_neuron(id, |neuron| (id.id, neuron.get_neuron_info(now)))
-                        .ok()
-                })
-                .collect(),
-            full_neurons: requested_list()
-                .filter_map(|neuron_id| self.get_full_neuron(neuron_id, caller).ok())
-                .collect(),
+            neuron_infos,
+            full_neurons,
+            total_pages_available,
         }
     }
 
@@ -2431,6 +2529,7 @@ impl Governance {
             known_neuron_data: None,
             spawn_at_timestamp_seconds: None,
             neuron_type: parent_neuron.neuron_type,
+            disburse_maturity_in_progress: vec![],
         };
 
         // Add the child neuron to the set of neurons undergoing ledger updates.
@@ -2717,6 +2816,7 @@ impl Governance {
             joined_community_fund_timestamp_seconds: None,
             known_neuron_data: None,
             neuron_type: None,
+            disburse_maturity_in_progress: vec![],
         };
 
         // `add_neuron` will verify that `child_neuron.controller` `is_self_authenticating()`, so we don't need to check it here.
@@ -2839,6 +2939,152 @@ impl Governance {
         Ok(responses)
     }
 
+    /// Disburses the maturity of a neuron to a ledger account.
+    ///
+    /// The maturity is deducted from the neuron right away and recorded in the
+    /// neuron's `disburse_maturity_in_progress`. The corresponding ICP are
+    /// minted by `finalize_maturity_disbursements` once
+    /// `MATURITY_DISBURSEMENT_DELAY_SECONDS` have passed, applying the maturity
+    /// modulation of that time.
+    ///
+    /// Pre-conditions:
+    /// - The neuron is controlled by `caller`
+    /// - The neuron is not in spawning state.
+    /// - The percentage to disburse is between 1 and 100 (inclusive).
+    /// - The neuron has less than `MAX_NUM_DISBURSE_MATURITY_IN_PROGRESS`
+    ///   disbursements in progress.
+    /// - The amount to disburse is more than the transaction fee, even with the
+    ///   worst case maturity modulation.
+    pub fn disburse_maturity(
+        &mut self,
+        id: &NeuronId,
+        caller: &PrincipalId,
+        disburse_maturity: &manage_neuron::DisburseMaturity,
+    ) -> Result<DisburseMaturityResponse, GovernanceError> {
+        let (
+            neuron_state,
+            is_neuron_controlled_by_caller,
+            neuron_maturity_e8s_equivalent,
+            num_disbursements_in_progress,
+        ) = self.with_neuron(id, |neuron| {
+            (
+                neuron.state(self.env.now()),
+                neuron.is_controlled_by(caller),
+                neuron.maturity_e8s_equivalent,
+                neuron.disburse_maturity_in_progress.len(),
+            )
+        })?;
+
+        if neuron_state == NeuronState::Spawning {
+            return Err(GovernanceError::new_with_message(
+                ErrorType::PreconditionFailed,
+                "Can't perform operation on neuron: Neuron is spawning.",
+            ));
+        }
+
+        if !is_neuron_controlled_by_caller {
+            return Err(GovernanceError::new(ErrorType::NotAuthorized));
+        }
+
+        let percentage_to_disburse = disburse_maturity.percentage_to_disburse;
+        if percentage_to_disburse > 100 || percentage_to_disburse == 0 {
+            return Err(GovernanceError::new_with_message(
+                ErrorType::PreconditionFailed,
+                "The percentage of maturity to disburse must be a value between 1 and 100 (inclusive)."));
+        }
+
+        if num_disbursements_in_progress >= MAX_NUM_DISBURSE_MATURITY_IN_PROGRESS {
+            return Err(GovernanceError::new_with_message(
+                ErrorType::PreconditionFailed,
+                format!(
+                    "The neuron already has {} maturity disbursements in progress, which is the maximum.",
+                    MAX_NUM_DISBURSE_MATURITY_IN_PROGRESS
+                ),
+            ));
+        }
+
+        // If no account was provided, transfer to the caller's account.
+        let to_account: AccountIdentifier = match disburse_maturity.to_account.as_ref() {
+            None => AccountIdentifier::new(*caller, None),
+            Some(ai_pb) => AccountIdentifier::try_from(ai_pb).map_err(|e| {
+                GovernanceError::new_with_message(
+                    ErrorType::InvalidCommand,
+                    format!("The recipient's subaccount is invalid due to: {}", e),
+                )
+            })?,
+        };
+
+        let maturity_to_disburse =
+            (neuron_maturity_e8s_equivalent.saturating_mul(percentage_to_disburse as u64)) / 100;
+
+        let worst_case_amount_e8s = apply_maturity_modulation(
+            maturity_to_disburse,
+            *VALID_MATURITY_MODULATION_BASIS_POINTS_RANGE.start(),
+        )
+        .map_err(|err| {
+            GovernanceError::new_with_message(
+                ErrorType::PreconditionFailed,
+                format!(
+                    "Could not calculate the worst case maturity modulation \
+                     and therefore cannot disburse maturity. Err: {}",
+                    err
+                ),
+            )
+        })?;
+        let transaction_fee_e8s = self.transaction_fee();
+        if worst_case_amount_e8s < transaction_fee_e8s {
+            return Err(GovernanceError::new_with_message(
+                ErrorType::InsufficientFunds,
+                format!(
+                    "If the worst case maturity modulation is applied (-5%), this neuron would \
+                     disburse {} e8s, but can't disburse an amount less than the transaction fee \
+                     of {} e8s.",
+                    worst_case_amount_e8s, transaction_fee_e8s
+                ),
+            ));
+        }
+
+        let now = self.env.now();
+        let in_flight_command = NeuronInFlightCommand {
+            timestamp: now,
+            command: Some(InFlightCommand::SyncCommand(SyncCommand {})),
+        };
+
+        // Lock the neuron so that we're sure that we are not disbursing the maturity in the middle of another ongoing operation.
+        let _neuron_lock = self.lock_neuron_for_command(id.id, in_flight_command)?;
+
+        self.with_neuron_mut(id, |neuron| {
+            // The memo tells apart the minting transfers of disbursements of this neuron that have
+            // the same amount and recipient, so that the ledger does not take one for a duplicate
+            // of another.
+            let memo = neuron
+                .disburse_maturity_in_progress
+                .iter()
+                .filter_map(|disbursement| disbursement.memo.map(|memo| memo.saturating_add(1)))
+                .fold(now, u64::max);
+            neuron.maturity_e8s_equivalent = neuron
+                .maturity_e8s_equivalent
+                .saturating_sub(maturity_to_disburse);
+            neuron
+                .disburse_maturity_in_progress
+                .push(DisburseMaturityInProgress {
+                    amount_e8s: maturity_to_disburse,
+                    timestamp_of_disbursement_seconds: now,
+                    account_to_disburse_to: Some(to_account.into()),
+                    finalize_disbursement_timestamp_seconds: now
+                        + MATURITY_DISBURSEMENT_DELAY_SECONDS,
+                    memo: Some(memo),
+                    minting_amount_e8s: None,
+                    created_at_time_nanos: None,
+                });
+        })
+        .expect("Expected the neuron to exist");
+
+        Ok(DisburseMaturityResponse {
+            amount_deducted_e8s: maturity_to_disburse,
+        })
+    }
+
     /// Disburse part of the stake of a neuron into a new neuron, possibly
     /// owned by someone else and with a different dissolve delay.
     ///
@@ -3031,6 +3277,7 @@ impl Governance {
             known_neuron_data: None,
             spawn_at_timestamp_seconds: None,
             neuron_type: None,
+            disburse_maturity_in_progress: vec![],
         };
 
         self.add_neuron(child_nid.id, child_neuron.clone())?;
@@ -3243,6 +3490,48 @@ impl Governance {
         }
     }
 
+    /// Lists the votes cast by a neuron, directly or by following, in
+    /// ascending order of proposal ID.
+    ///
+    /// The voting history of a neuron can only be read by its controller and
+    /// hot keys, unless the neuron is a known neuron, in which case it is public.
+    pub fn get_neuron_voting_history(
+        &self,
+        caller: &PrincipalId,
+        request: &GetNeuronVotingHistoryRequest,
+    ) -> Result<GetNeuronVotingHistoryResponse, GovernanceError> {
+        let neuron_id = request.neuron_id.ok_or_else(|| {
+            GovernanceError::new_with_message(
+                ErrorType::InvalidCommand,
+                "neuron_id is not specified.",
+            )
+        })?;
+        let is_readable_by_caller = self.with_neuron(&neuron_id, |neuron| {
+            neuron.is_voting_history_readable_by(caller)
+        })?;
+        if !is_readable_by_caller {
+            return Err(GovernanceError::new_with_message(
+                ErrorType::NotAuthorized,
+                format!(
+                    "Caller {} is not authorized to read the voting history of neuron {}.",
+                    caller, neuron_id.id
+                ),
+            ));
+        }
+
+        let limit = if request.limit == 0 || request.limit > MAX_NEURON_VOTING_HISTORY_RESULTS {
+            MAX_NEURON_VOTING_HISTORY_RESULTS
+        } else {
+            request.limit
+        };
+        let start_proposal_id = request.start.map(|start| start.id).unwrap_or_default();
+        let votes = with_voting_history_store(|voting_history| {
+            voting_history.list_votes(neuron_id, start_proposal_id, limit as usize)
+        });
+
+        Ok(GetNeuronVotingHistoryResponse { votes })
+    }
+
     /// Tries to get the Neurons' Fund participation data for an SNS Swap created via given proposal.
     ///
     /// - The returned structure is anomymized w.r.t. NNS neuron IDs.
@@ -3385,6 +3674,14 @@ impl Governance {
         } else {
             data.proposal.clone()
         };
+        // Like the payload, large renderings of it are dropped from multi queries.
+        let payload_text_rendering = data
+            .payload_text_rendering
+            .as_ref()
+            .filter(|rendering| {
+                !multi_query || rendering.len() <= EXECUTE_NNS_FUNCTION_PAYLOAD_LISTING_BYTES_MAX
+            })
+            .cloned();
 
         /// Remove all ballots except the ballots belonging to a neuron present
         /// in `except_from`.
@@ -3421,6 +3718,7 @@ impl Governance {
                 data.get_deadline_timestamp_seconds(voting_period_seconds),
             ),
             derived_proposal_information: data.derived_proposal_information.clone(),
+            payload_text_rendering,
         }
     }
 
@@ -3483,14 +3781,15 @@ impl Governance {
     /// caller either controls or is a registered hot key for.
     ///
     /// - Proposals with `ExecuteNnsFunction` as action have their
-    /// `payload` cleared if larger than
+    /// `payload` and `payload_text_rendering` cleared if larger than
     /// EXECUTE_NNS_FUNCTION_PAYLOAD_LISTING_BYTES_MAX.  The caller can
     /// retrieve dropped payloads by calling `get_proposal_info` for
     /// each proposal of interest.
     ///
     /// - If `omit_large_fields` is set to true, some "large fields" such as
-    /// CreateServiceNervousSystem's logo and token_logo are omitted (set to
-    /// none) from each proposal before returning. This is useful when these
+    /// CreateServiceNervousSystem's logo and token_logo, and the payload
+    /// renderings, are omitted (set to none) from each proposal before
+    /// returning. This is useful when these
     /// fields would cause the message to exceed the maximum message size.
     /// Consider using this field and then calling `get_proposal_info` for each
     /// proposal of interest.
@@ -3813,6 +4112,7 @@ impl Governance {
                     known_neuron_data: None,
                     spawn_at_timestamp_seconds: None,
                     neuron_type: None,
+                    disburse_maturity_in_progress: vec![],
                 };
                 self.add_neuron(nid.id, neuron)
             }
@@ -4485,6 +4785,12 @@ impl Governance {
                         "Cannot issue a disburse to neuron command through a proposal",
                     ));
                 }
+                Command::DisburseMaturity(_) => {
+                    return Err(GovernanceError::new_with_message(
+                        ErrorType::NotAuthorized,
+                        "Cannot issue a disburse maturity command through a proposal",
+                    ));
+                }
                 _ => {}
             }
         }
@@ -4756,6 +5062,17 @@ impl Governance {
         // Validate proposal
         let action = self.validate_proposal(proposal)?;
 
+        // Render the payload of ExecuteNnsFunction proposals, so that voters can
+        // inspect it. Payloads that cannot be decoded are rejected.
+        let payload_text_rendering = match &action {
+            Action::ExecuteNnsFunction(execute_nns_function) => {
+                render_execute_nns_function_payload(execute_nns_function).map_err(|err| {
+                    GovernanceError::new_with_message(ErrorType::InvalidProposal, err)
+                })?
+            }
+            _ => None,
+        };
+
         // Before actually modifying anything, we first make sure that
         // the neuron is allowed to make this proposal and create the
         // electoral roll.
@@ -4925,6 +5242,7 @@ impl Governance {
             proposal_timestamp_seconds: now_seconds,
             ballots,
             wait_for_quiet_state,
+            payload_text_rendering,
             ..Default::default()
         };
 
@@ -5105,6 +5423,11 @@ impl Governance {
         let mut induction_votes = BTreeMap::new();
         induction_votes.insert(*voting_neuron_id, vote_of_neuron);
 
+        // The votes cast during the cascade, recorded in the voting history in a single batch once
+        // the cascade is over. This adds one stable memory write per ballot, whose cost is bounded
+        // as the voting history only keeps `VOTING_HISTORY_RETENTION_NUM_PROPOSALS` proposals.
+        let mut cast_votes = Vec::new();
+
         // Retain only neurons that have a ballot that can still be cast.  This excludes
         // neurons with no ballots or ballots that have already been cast.
         fn retain_neurons_with_castable_ballots(
@@ -5140,6 +5463,7 @@ impl Governance {
                             });
                         match register_ballot_result {
                             Ok(_) => {
+                                cast_votes.push((*k, *v, k != voting_neuron_id));
                                 // Only update a vote if it was previously unspecified. Following
                                 // can trigger votes for neurons that have already voted (manually)
                                 // and we don't change these votes.
@@ -5190,7 +5514,7 @@ impl Governance {
 
             // Following is not enabled for neuron management proposals
             if topic == Topic::NeuronManagement {
-                return;
+                break;
             }
 
             // Calling "would_follow_ballots" for neurons that cannot vote is wasteful.
@@ -5219,7 +5543,7 @@ impl Governance {
             // If induction_votes is empty, the loop will terminate
             // here.
             if induction_votes.is_empty() {
-                return;
+                break;
             }
             // We now continue to the next iteration of the loop.
             // Because induction_votes is not empty, either at least
@@ -5257,6 +5581,8 @@ impl Governance {
             // breadth-first search (BFS) algorithm. A node is
             // explored when it has voted yes or no.
         }
+
+        record_neuron_votes(*proposal_id, cast_votes);
     }
 
     fn register_vote(
@@ -5618,6 +5944,7 @@ impl Governance {
             known_neuron_data: None,
             spawn_at_timestamp_seconds: None,
             neuron_type: None,
+            disburse_maturity_in_progress: vec![],
         };
 
         // This also verifies that there are not too many neurons already.
@@ -5830,6 +6157,9 @@ impl Governance {
             Some(Command::StakeMaturity(s)) => self
                 .stake_maturity_of_neuron(&id, caller, s)
                 .map(|(response, _)| ManageNeuronResponse::stake_maturity_response(response)),
+            Some(Command::DisburseMaturity(d)) => self
+                .disburse_maturity(&id, caller, d)
+                .map(ManageNeuronResponse::disburse_maturity_response),
             Some(Command::Split(s)) => self
                 .split_neuron(&id, caller, s)
                 .await
@@ -5964,9 +6294,13 @@ impl Governance {
         // Try to spawn neurons (potentially multiple times per day).
         } else if self.can_spawn_neurons() {
             self.spawn_neurons().await;
+        // Try to mint the ICP of finalized maturity disbursements.
+        } else if self.can_finalize_maturity_disbursements() {
+            self.finalize_maturity_disbursements().await;
         }
 
         self.unstake_maturity_of_dissolved_neurons();
+        self.remove_old_voting_history();
         self.maybe_gc();
         self.maybe_run_migrations();
         self.maybe_run_validations();
@@ -6006,6 +6340,24 @@ impl Governance {
             .maturity_modulation_last_updated_at_timestamp_seconds = Some(now_seconds);
     }
 
+    /// Removes a batch of votes from the voting history: first the votes of removed neurons, then
+    /// the votes on proposals that are older than the `VOTING_HISTORY_RETENTION_NUM_PROPOSALS`
+    /// most recent proposals.
+    fn remove_old_voting_history(&self) {
+        let first_retained_proposal_id = self
+            .next_proposal_id()
+            .saturating_sub(VOTING_HISTORY_RETENTION_NUM_PROPOSALS);
+        with_voting_history_store_mut(|voting_history| {
+            let num_removed_votes = voting_history.remove_votes_of_removed_neurons(
+                MAX_VOTING_HISTORY_ENTRIES_TO_REMOVE_PER_PERIODIC_TASK,
+            );
+            voting_history.remove_votes_before_proposal(
+                first_retained_proposal_id,
+                MAX_VOTING_HISTORY_ENTRIES_TO_REMOVE_PER_PERIODIC_TASK - num_removed_votes,
+            );
+        });
+    }
+
     /// When a neuron is finally dissolved, if there is any staked maturity it is moved to regular maturity
     /// which can be spawned (and is modulated).
     fn unstake_maturity_of_dissolved_neurons(&mut self) {
@@ -6191,6 +6543,232 @@ impl Governance {
         self.heap_data.spawning_neurons = Some(false);
     }
 
+    fn can_finalize_maturity_disbursements(&self) -> bool {
+        let now_seconds = self.env.now();
+        !self
+            .neuron_store
+            .list_neurons_ready_to_finalize_maturity_disbursement(now_seconds)
+            .is_empty()
+    }
+
+    /// Mints the ICP of maturity disbursements whose delay has passed, modulated by the maturity
+    /// modulation rate of the day. The neuron is locked while the ICP are minted, and the
+    /// maturity disbursement is only removed from the neuron once minting succeeds. If minting
+    /// fails, the disbursement stays in progress and is retried the next time this runs.
+    ///
+    /// The amount and `created_at_time` of the minting transfer are recorded on the disbursement
+    /// before the transfer is first attempted, and reused by the retries, together with the memo
+    /// of the disbursement. That way, the ledger deduplicates a retry of a transfer that was
+    /// recorded even though the call failed, and the ICP are not minted twice.
+    async fn finalize_maturity_disbursements(&mut self) {
+        let maturity_modulation = match self.heap_data.cached_daily_maturity_modulation_basis_points
+        {
+            None => return,
+            Some(value) => value,
+        };
+
+        // Sanity check that the maturity modulation returned is within bounds.
+        if !VALID_MATURITY_MODULATION_BASIS_POINTS_RANGE.contains(&maturity_modulation) {
+            println!(
+                "{}Maturity modulation (in basis points) out-of-bounds. Should be in range [-500, 500], actually is: {}",
+                LOG_PREFIX, maturity_modulation
+            );
+            return;
+        }
+
+        let now_seconds = self.env.now();
+        let neuron_ids = self
+            .neuron_store
+            .list_neurons_ready_to_finalize_maturity_disbursement(now_seconds);
+
+        for neuron_id in neuron_ids {
+            // Disbursements are finalized in the order in which they were initiated. The neuron
+            // might have changed since it was listed, so the entry is re-checked here.
+            let disbursement = match self.with_neuron(&neuron_id, |neuron| {
+                neuron.disburse_maturity_in_progress.first().cloned()
+            }) {
+                Ok(Some(disbursement))
+                    if disbursement.finalize_disbursement_timestamp_seconds <= now_seconds =>
+                {
+                    disbursement
+                }
+                _ => continue,
+            };
+
+            let to_account = match disbursement
+                .account_to_disburse_to
+                .as_ref()
+                .map(AccountIdentifier::try_from)
+            {
+                Some(Ok(to_account)) => to_account,
+                _ => {
+                    println!(
+                        "{}Invalid account in maturity disbursement {:?} of neuron {:?}, skipping",
+                        LOG_PREFIX, disbursement, neuron_id
+                    );
+                    continue;
+                }
+            };
+
+            let minting_disbursement = match (
+                disbursement.minting_amount_e8s,
+                disbursement.created_at_time_nanos,
+            ) {
+                (Some(_), Some(_)) => disbursement.clone(),
+                _ => {
+                    let amount_e8s = match apply_maturity_modulation(
+                        disbursement.amount_e8s,
+                        maturity_modulation,
+                    ) {
+                        Ok(amount_e8s) => amount_e8s,
+                        Err(err) => {
+                            println!(
+                                "{}Could not apply modulation to {:?} for neuron {:?} due to {:?}, skipping",
+                                LOG_PREFIX, disbursement, neuron_id, err
+                            );
+                            continue;
+                        }
+                    };
+                    // The neuron ID is added, so that the transfers of different neurons made at
+                    // the same time, of the same amount, to the same account and with the same
+                    // memo are not taken for duplicates of one another by the ledger.
+                    let created_at_time_nanos = now_seconds
+                        .saturating_mul(1_000_000_000)
+                        .saturating_add(neuron_id.id % 1_000_000_000);
+                    DisburseMaturityInProgress {
+                        minting_amount_e8s: Some(amount_e8s),
+                        created_at_time_nanos: Some(created_at_time_nanos),
+                        ..disbursement.clone()
+                    }
+                }
+            };
+            let amount_e8s = minting_disbursement.minting_amount_e8s.unwrap_or_default();
+            let created_at_time_nanos = minting_disbursement
+                .created_at_time_nanos
+                .unwrap_or_default();
+            let memo = minting_disbursement
+                .memo
+                .unwrap_or(minting_disbursement.timestamp_of_disbursement_seconds);
+
+            let in_flight_command = NeuronInFlightCommand {
+                timestamp: now_seconds,
+                command: Some(InFlightCommand::FinalizeDisburseMaturity(
+                    minting_disbursement.clone(),
+                )),
+            };
+            let _lock = match self.lock_neuron_for_command(neuron_id.id, in_flight_command) {
+                Ok(lock) => lock,
+                Err(error) => {
+                    println!(
+                        "{}Tried to finalize maturity disbursement but neuron was already locked: {:?}. Error: {:?}",
+                        LOG_PREFIX, neuron_id, error,
+                    );
+                    continue;
+                }
+            };
+
+            // Record the transfer before making it, so that it is retried with the same
+            // arguments if the call fails.
+            if minting_disbursement != disbursement {
+                self.replace_first_maturity_disbursement(
+                    &neuron_id,
+                    &disbursement,
+                    Some(minting_disbursement.clone()),
+                );
+            }
+
+            // The disbursement stays in progress until the minting succeeds, so
+            // that a failed transfer is retried the next time this runs.
+            //
+            // This is a minting transfer, from the governance canister's (which is also the
+            // minting canister) main account to the account given in the disbursement.
+            match self
+                .ledger
+                .transfer_funds_with_created_at_time(
+                    amount_e8s,
+                    0, // Minting transfers don't pay a fee.
+                    None,
+                    to_account,
+                    memo,
+                    created_at_time_nanos,
+                )
+                .await
+            {
+                Ok(block_height) => {
+                    println!(
+                        "{}Finalized maturity disbursement {:?} of neuron {:?} at block {}.",
+                        LOG_PREFIX, minting_disbursement, neuron_id, block_height,
+                    );
+                    self.replace_first_maturity_disbursement(
+                        &neuron_id,
+                        &minting_disbursement,
+                        None,
+                    );
+                }
+                Err(TransferWithCreatedAtTimeError::UnknownOutcome(error)) => {
+                    println!(
+                        "{}Error finalizing maturity disbursement {:?} of neuron {:?}. The outcome of the ledger update is unknown, it is retried with the same arguments: {}.",
+                        LOG_PREFIX, minting_disbursement, neuron_id, error,
+                    );
+                }
+                Err(TransferWithCreatedAtTimeError::Rejected(error)) => {
+                    // Within the deduplication window, the ledger would have reported an earlier
+                    // attempt of the transfer as a duplicate, so the rejection means that the ICP
+                    // have not been minted, and the next attempt can be a new transfer. After the
+                    // window, that is not known, so the transfer is left as it is.
+                    let created_at_seconds = created_at_time_nanos / 1_000_000_000;
+                    let within_deduplication_window = now_seconds
+                        < created_at_seconds.saturating_add(
+                            MATURITY_DISBURSEMENT_TRANSFER_DEDUPLICATION_WINDOW_SECONDS,
+                        );
+                    println!(
+                        "{}Error finalizing maturity disbursement {:?} of neuron {:?}. Ledger update failed with err: {}. Known not to be recorded: {}.",
+                        LOG_PREFIX, minting_disbursement, neuron_id, error, within_deduplication_window,
+                    );
+                    if within_deduplication_window {
+                        self.replace_first_maturity_disbursement(
+                            &neuron_id,
+                            &minting_disbursement,
+                            Some(DisburseMaturityInProgress {
+                                minting_amount_e8s: None,
+                                created_at_time_nanos: None,
+                                ..minting_disbursement.clone()
+                            }),
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    /// Replaces the first maturity disbursement in progress of the neuron with `replacement`, or
+    /// removes it if `replacement` is `None`. Does nothing if the first disbursement is not
+    /// `expected`, i.e., if the neuron changed in the meantime.
+    fn replace_first_maturity_disbursement(
+        &mut self,
+        neuron_id: &NeuronId,
+        expected: &DisburseMaturityInProgress,
+        replacement: Option<DisburseMaturityInProgress>,
+    ) {
+        let result = self.with_neuron_mut(neuron_id, |neuron| {
+            if neuron.disburse_maturity_in_progress.first() != Some(expected) {
+                return;
+            }
+            match replacement {
+                Some(replacement) => neuron.disburse_maturity_in_progress[0] = replacement,
+                None => {
+                    neuron.disburse_maturity_in_progress.remove(0);
+                }
+            }
+        });
+        if let Err(error) = result {
+            println!(
+                "{}Failed to update maturity disbursement {:?} of neuron {:?}: {:?}",
+                LOG_PREFIX, expected, neuron_id, error,
+            );
+        }
+    }
+
     /// Return `true` if rewards should be distributed, `false` otherwise
     fn should_distribute_rewards(&self) -> bool {
         let latest_distribution_nominal_end_timestamp_seconds =
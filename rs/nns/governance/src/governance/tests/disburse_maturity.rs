@@ -0,0 +1,379 @@
+use crate::{
+    governance::{
+        tests::{MockEnvironment, StubCMC, StubIcpLedger},
+        Governance, MATURITY_DISBURSEMENT_DELAY_SECONDS,
+    },
+    pb::v1::{
+        governance_error::ErrorType, manage_neuron::DisburseMaturity,
+        manage_neuron_response::DisburseMaturityResponse, neuron, DisburseMaturityInProgress,
+        Governance as GovernanceProto, NetworkEconomics, Neuron,
+    },
+};
+use async_trait::async_trait;
+use ic_base_types::{CanisterId, PrincipalId};
+use ic_nervous_system_common::{
+    ledger::{IcpLedger, TransferWithCreatedAtTimeError},
+    NervousSystemError,
+};
+use ic_nns_common::pb::v1::NeuronId;
+use icp_ledger::{AccountIdentifier, Subaccount, Tokens};
+use maplit::btreemap;
+use std::sync::{Arc, Mutex};
+
+const NOW: u64 = 1_700_000_000;
+
+fn neuron_with_maturity(controller: PrincipalId, maturity_e8s_equivalent: u64) -> Neuron {
+    Neuron {
+        id: Some(NeuronId { id: 1 }),
+        controller: Some(controller),
+        cached_neuron_stake_e8s: 100_000_000,
+        account: b"a__4___8__12__16__20__24__28__32".to_vec(),
+        // One year
+        dissolve_state: Some(neuron::DissolveState::DissolveDelaySeconds(31557600)),
+        maturity_e8s_equivalent,
+        ..Default::default()
+    }
+}
+
+fn new_governance(neuron: Neuron, ledger: Box<dyn IcpLedger>) -> Governance {
+    Governance::new(
+        GovernanceProto {
+            economics: Some(NetworkEconomics::with_default_values()),
+            neurons: btreemap! {
+                1 => neuron
+            },
+            ..GovernanceProto::default()
+        },
+        Box::new(MockEnvironment::new(vec![], NOW)),
+        ledger,
+        Box::new(StubCMC {}),
+    )
+}
+
+#[test]
+fn test_disburse_maturity() {
+    let principal = PrincipalId::new_user_test_id(1);
+    let mut governance = new_governance(
+        neuron_with_maturity(principal, 1_000_000_000),
+        Box::new(StubIcpLedger {}),
+    );
+
+    let response = governance
+        .disburse_maturity(
+            &NeuronId { id: 1 },
+            &principal,
+            &DisburseMaturity {
+                percentage_to_disburse: 40,
+                to_account: None,
+            },
+        )
+        .expect("Expected call to succeed");
+
+    assert_eq!(
+        response,
+        DisburseMaturityResponse {
+            amount_deducted_e8s: 400_000_000,
+        }
+    );
+    let neuron = governance
+        .with_neuron(&NeuronId { id: 1 }, |neuron| neuron.clone())
+        .unwrap();
+    assert_eq!(neuron.maturity_e8s_equivalent, 600_000_000);
+    let disbursement = |amount_e8s, memo| DisburseMaturityInProgress {
+        amount_e8s,
+        timestamp_of_disbursement_seconds: NOW,
+        account_to_disburse_to: Some(AccountIdentifier::new(principal, None).into()),
+        finalize_disbursement_timestamp_seconds: NOW + MATURITY_DISBURSEMENT_DELAY_SECONDS,
+        memo: Some(memo),
+        minting_amount_e8s: None,
+        created_at_time_nanos: None,
+    };
+    assert_eq!(
+        neuron.disburse_maturity_in_progress,
+        vec![disbursement(400_000_000, NOW)]
+    );
+
+    // A second disbursement initiated at the same time gets a different memo.
+    governance
+        .disburse_maturity(
+            &NeuronId { id: 1 },
+            &principal,
+            &DisburseMaturity {
+                percentage_to_disburse: 50,
+                to_account: None,
+            },
+        )
+        .expect("Expected call to succeed");
+    let neuron = governance
+        .with_neuron(&NeuronId { id: 1 }, |neuron| neuron.clone())
+        .unwrap();
+    assert_eq!(
+        neuron.disburse_maturity_in_progress,
+        vec![
+            disbursement(400_000_000, NOW),
+            disbursement(300_000_000, NOW + 1)
+        ]
+    );
+}
+
+#[test]
+fn test_disburse_maturity_fails_for_invalid_requests() {
+    let principal = PrincipalId::new_user_test_id(1);
+    let mut governance = new_governance(
+        neuron_with_maturity(principal, 1_000_000_000),
+        Box::new(StubIcpLedger {}),
+    );
+
+    let mut disburse = |caller: PrincipalId, percentage_to_disburse: u32| {
+        governance
+            .disburse_maturity(
+                &NeuronId { id: 1 },
+                &caller,
+                &DisburseMaturity {
+                    percentage_to_disburse,
+                    to_account: None,
+                },
+            )
+            .unwrap_err()
+            .error_type
+    };
+
+    assert_eq!(
+        disburse(PrincipalId::new_user_test_id(2), 50),
+        ErrorType::NotAuthorized as i32
+    );
+    assert_eq!(disburse(principal, 0), ErrorType::PreconditionFailed as i32);
+    assert_eq!(
+        disburse(principal, 101),
+        ErrorType::PreconditionFailed as i32
+    );
+    // 1% of the maturity is less than the transaction fee.
+    assert_eq!(
+        new_governance(
+            neuron_with_maturity(principal, 1_000_000),
+            Box::new(StubIcpLedger {})
+        )
+        .disburse_maturity(
+            &NeuronId { id: 1 },
+            &principal,
+            &DisburseMaturity {
+                percentage_to_disburse: 1,
+                to_account: None,
+            },
+        )
+        .unwrap_err()
+        .error_type,
+        ErrorType::InsufficientFunds as i32
+    );
+}
+
+/// A minting transfer: amount, recipient, memo and `created_at_time`.
+type Transfer = (u64, AccountIdentifier, u64, u64);
+
+/// Records the minting transfers without talking to a ledger. Like the ledger, it deduplicates
+/// transfers that have the same arguments and `created_at_time`.
+#[derive(Default)]
+struct RecordingIcpLedger {
+    transfers: Arc<Mutex<Vec<Transfer>>>,
+    /// Makes all transfers fail while set, without recording them.
+    fail_transfers: Arc<Mutex<bool>>,
+    /// Makes all transfers fail while set, after recording them, as when the reply of the
+    /// ledger is lost.
+    lose_replies: Arc<Mutex<bool>>,
+}
+
+#[async_trait]
+impl IcpLedger for RecordingIcpLedger {
+    async fn transfer_funds(
+        &self,
+        _amount_e8s: u64,
+        _fee_e8s: u64,
+        _from_subaccount: Option<Subaccount>,
+        _to: AccountIdentifier,
+        _memo: u64,
+    ) -> Result<u64, NervousSystemError> {
+        unimplemented!()
+    }
+
+    async fn transfer_funds_with_created_at_time(
+        &self,
+        amount_e8s: u64,
+        _fee_e8s: u64,
+        _from_subaccount: Option<Subaccount>,
+        to: AccountIdentifier,
+        memo: u64,
+        created_at_time_nanos: u64,
+    ) -> Result<u64, TransferWithCreatedAtTimeError> {
+        if *self.fail_transfers.lock().unwrap() {
+            return Err(TransferWithCreatedAtTimeError::UnknownOutcome(
+                NervousSystemError::new_with_message("Ledger unavailable"),
+            ));
+        }
+        let transfer = (amount_e8s, to, memo, created_at_time_nanos);
+        let mut transfers = self.transfers.lock().unwrap();
+        let block_index = match transfers.iter().position(|recorded| *recorded == transfer) {
+            Some(duplicate_of) => duplicate_of as u64,
+            None => {
+                transfers.push(transfer);
+                transfers.len() as u64 - 1
+            }
+        };
+        if *self.lose_replies.lock().unwrap() {
+            return Err(TransferWithCreatedAtTimeError::UnknownOutcome(
+                NervousSystemError::new_with_message("Reply lost"),
+            ));
+        }
+        Ok(block_index)
+    }
+
+    async fn total_supply(&self) -> Result<Tokens, NervousSystemError> {
+        unimplemented!()
+    }
+
+    async fn account_balance(
+        &self,
+        _account: AccountIdentifier,
+    ) -> Result<Tokens, NervousSystemError> {
+        unimplemented!()
+    }
+
+    fn canister_id(&self) -> CanisterId {
+        unimplemented!()
+    }
+}
+
+#[tokio::test]
+async fn test_finalize_maturity_disbursements() {
+    let principal = PrincipalId::new_user_test_id(1);
+    let to_account = AccountIdentifier::new(PrincipalId::new_user_test_id(2), None);
+    let disbursement =
+        |amount_e8s, finalize_disbursement_timestamp_seconds| DisburseMaturityInProgress {
+            amount_e8s,
+            timestamp_of_disbursement_seconds: 0,
+            account_to_disburse_to: Some(to_account.into()),
+            finalize_disbursement_timestamp_seconds,
+            memo: Some(finalize_disbursement_timestamp_seconds),
+            minting_amount_e8s: None,
+            created_at_time_nanos: None,
+        };
+    let neuron = Neuron {
+        disburse_maturity_in_progress: vec![
+            disbursement(100_000_000, NOW - 1),
+            disbursement(200_000_000, NOW + 1),
+        ],
+        ..neuron_with_maturity(principal, 0)
+    };
+    let ledger = RecordingIcpLedger::default();
+    let transfers = Arc::clone(&ledger.transfers);
+    let mut governance = new_governance(neuron, Box::new(ledger));
+    // +2%
+    governance
+        .heap_data
+        .cached_daily_maturity_modulation_basis_points = Some(200);
+
+    governance.finalize_maturity_disbursements().await;
+
+    // Only the first disbursement is due, and it is minted with the maturity
+    // modulation applied.
+    assert_eq!(
+        *transfers.lock().unwrap(),
+        vec![(102_000_000, to_account, NOW - 1, NOW * 1_000_000_000 + 1)]
+    );
+    let neuron = governance
+        .with_neuron(&NeuronId { id: 1 }, |neuron| neuron.clone())
+        .unwrap();
+    assert_eq!(
+        neuron.disburse_maturity_in_progress,
+        vec![disbursement(200_000_000, NOW + 1)]
+    );
+    assert!(governance.heap_data.in_flight_commands.is_empty());
+}
+
+fn new_governance_with_due_disbursement(
+    ledger: RecordingIcpLedger,
+) -> (Governance, DisburseMaturityInProgress, AccountIdentifier) {
+    let to_account = AccountIdentifier::new(PrincipalId::new_user_test_id(2), None);
+    let disbursement = DisburseMaturityInProgress {
+        amount_e8s: 100_000_000,
+        timestamp_of_disbursement_seconds: 0,
+        account_to_disburse_to: Some(to_account.into()),
+        finalize_disbursement_timestamp_seconds: NOW - 1,
+        memo: Some(42),
+        minting_amount_e8s: None,
+        created_at_time_nanos: None,
+    };
+    let neuron = Neuron {
+        disburse_maturity_in_progress: vec![disbursement.clone()],
+        ..neuron_with_maturity(PrincipalId::new_user_test_id(1), 0)
+    };
+    let mut governance = new_governance(neuron, Box::new(ledger));
+    governance
+        .heap_data
+        .cached_daily_maturity_modulation_basis_points = Some(0);
+    (governance, disbursement, to_account)
+}
+
+fn disburse_maturity_in_progress(governance: &Governance) -> Vec<DisburseMaturityInProgress> {
+    governance
+        .with_neuron(&NeuronId { id: 1 }, |neuron| {
+            neuron.disburse_maturity_in_progress.clone()
+        })
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_failed_maturity_disbursement_is_retried() {
+    let ledger = RecordingIcpLedger::default();
+    let transfers = Arc::clone(&ledger.transfers);
+    let fail_transfers = Arc::clone(&ledger.fail_transfers);
+    let (mut governance, disbursement, to_account) = new_governance_with_due_disbursement(ledger);
+
+    // A failed transfer keeps the disbursement in progress, with the transfer
+    // recorded on it, and releases the neuron.
+    *fail_transfers.lock().unwrap() = true;
+    governance.finalize_maturity_disbursements().await;
+    assert!(transfers.lock().unwrap().is_empty());
+    assert_eq!(
+        disburse_maturity_in_progress(&governance),
+        vec![DisburseMaturityInProgress {
+            minting_amount_e8s: Some(100_000_000),
+            created_at_time_nanos: Some(NOW * 1_000_000_000 + 1),
+            ..disbursement
+        }]
+    );
+    assert!(governance.heap_data.in_flight_commands.is_empty());
+
+    *fail_transfers.lock().unwrap() = false;
+    governance.finalize_maturity_disbursements().await;
+    assert_eq!(
+        *transfers.lock().unwrap(),
+        vec![(100_000_000, to_account, 42, NOW * 1_000_000_000 + 1)]
+    );
+    assert!(disburse_maturity_in_progress(&governance).is_empty());
+}
+
+#[tokio::test]
+async fn test_maturity_disbursement_whose_transfer_failed_after_being_recorded_is_minted_once() {
+    let ledger = RecordingIcpLedger::default();
+    let transfers = Arc::clone(&ledger.transfers);
+    let lose_replies = Arc::clone(&ledger.lose_replies);
+    let (mut governance, _, to_account) = new_governance_with_due_disbursement(ledger);
+    let expected_transfers = vec![(100_000_000, to_account, 42, NOW * 1_000_000_000 + 1)];
+
+    // The ledger records the transfer, but the reply is lost.
+    *lose_replies.lock().unwrap() = true;
+    governance.finalize_maturity_disbursements().await;
+    assert_eq!(*transfers.lock().unwrap(), expected_transfers);
+    assert_eq!(disburse_maturity_in_progress(&governance).len(), 1);
+
+    // The retry uses the recorded amount, memo and created_at_time, even though
+    // the maturity modulation changed, so the ledger takes it for a duplicate.
+    *lose_replies.lock().unwrap() = false;
+    governance
+        .heap_data
+        .cached_daily_maturity_modulation_basis_points = Some(300);
+    governance.finalize_maturity_disbursements().await;
+    assert_eq!(*transfers.lock().unwrap(), expected_transfers);
+    assert!(disburse_maturity_in_progress(&governance).is_empty());
+    assert!(governance.heap_data.in_flight_commands.is_empty());
+}
@@ -19,6 +19,7 @@ use lazy_static::lazy_static;
 use maplit::{btreemap, hashmap};
 use std::convert::TryFrom;
 
+mod disburse_maturity;
 mod stake_maturity;
 
 #[test]
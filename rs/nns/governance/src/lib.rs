@@ -159,6 +159,7 @@ pub mod proposals;
 mod reward;
 pub mod storage;
 mod subaccount_index;
+mod voting_history;
 
 #[automock]
 trait Clock {
@@ -395,6 +396,12 @@ pub fn encode_metrics(
         "The number of neurons in stable memory.",
     )?;
 
+    w.encode_gauge(
+        "governance_voting_history_len",
+        storage::with_voting_history_store(|voting_history| voting_history.num_entries()) as f64,
+        "Total number of votes in the voting history of all neurons.",
+    )?;
+
     let mut builder = w.gauge_vec(
         "governance_proposal_deadline_timestamp_seconds",
         "The deadline for open proposals, labelled with proposal id",
@@ -79,6 +79,13 @@ impl Neuron {
         self.is_hotkey_or_controller(principal)
     }
 
+    /// Returns true if and only if `principal` is allowed to read the voting
+    /// history of this neuron, i.e., if this neuron is a known neuron, or if
+    /// `principal` is either the controller or one of the authorized hot keys.
+    pub(crate) fn is_voting_history_readable_by(&self, principal: &PrincipalId) -> bool {
+        self.known_neuron_data.is_some() || self.is_hotkey_or_controller(principal)
+    }
+
     /// Returns true if and only if `principal` is either the controller or a hotkey
     fn is_hotkey_or_controller(&self, principal: &PrincipalId) -> bool {
         self.is_controlled_by(principal) || self.hot_keys.contains(principal)
@@ -258,6 +265,16 @@ impl Neuron {
             && self.staked_maturity_e8s_equivalent.unwrap_or(0) > 0
     }
 
+    /// Returns true if the oldest maturity disbursement in progress can be
+    /// finalized at `now_seconds`.
+    pub(crate) fn ready_to_finalize_maturity_disbursement(&self, now_seconds: u64) -> bool {
+        self.disburse_maturity_in_progress
+            .first()
+            .map_or(false, |disbursement| {
+                disbursement.finalize_disbursement_timestamp_seconds <= now_seconds
+            })
+    }
+
     pub(crate) fn unstake_maturity(&mut self, now_seconds: u64) {
         if self.ready_to_unstake_maturity(now_seconds) {
             self.maturity_e8s_equivalent = self
@@ -741,7 +758,7 @@ impl Neuron {
     /// The exact criteria is subject to change. Currently, all of the following must hold:
     ///
     ///     1. Not seed or ect: NeuronType is not NeuronType::Seed or NeuronType::Ect
-    ///     2. Not funded: No stake, and no (unstaked) maturity.
+    ///     2. Not funded: No stake, no (unstaked) maturity, and no maturity disbursement in progress.
     ///     3. Dissolved sufficiently "long ago": Precisely, dissolved as of now - 2 weeks.
     ///     4. Member of the Neuron's Fund.
     ///
@@ -807,7 +824,7 @@ impl Neuron {
 
     pub fn is_funded(&self) -> bool {
         let amount_e8s = self.stake_e8s() + self.maturity_e8s_equivalent;
-        amount_e8s > 0
+        amount_e8s > 0 || !self.disburse_maturity_in_progress.is_empty()
     }
 
     /// If not dissolving, returns None. Otherwise, returns Some Unix timestamp (seconds) when the
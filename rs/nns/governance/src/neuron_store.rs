@@ -7,7 +7,7 @@ use crate::{
     storage::{
         neuron_indexes::{CorruptedNeuronIndexes, NeuronIndex},
         with_stable_neuron_indexes, with_stable_neuron_indexes_mut, with_stable_neuron_store,
-        with_stable_neuron_store_mut,
+        with_stable_neuron_store_mut, with_voting_history_store_mut,
     },
     Clock, IcClock,
 };
@@ -428,6 +428,7 @@ impl NeuronStore {
         }
 
         self.remove_neuron_from_indexes(&neuron_to_remove);
+        with_voting_history_store_mut(|voting_history| voting_history.remove_neuron(*neuron_id));
     }
 
     fn remove_neuron_from_indexes(&mut self, neuron: &Neuron) {
@@ -636,6 +637,19 @@ impl NeuronStore {
             .collect()
     }
 
+    /// List all neuron ids whose neurons have a maturity disbursement that can be finalized.
+    pub fn list_neurons_ready_to_finalize_maturity_disbursement(
+        &self,
+        now_seconds: u64,
+    ) -> Vec<NeuronId> {
+        // Neurons with maturity disbursements in progress are funded, and therefore in the heap.
+        let filter = |neuron: &Neuron| neuron.ready_to_finalize_maturity_disbursement(now_seconds);
+        self.map_heap_neurons_filtered(filter, |neuron| neuron.id)
+            .into_iter()
+            .flatten()
+            .collect()
+    }
+
     /// List all neuron ids of known neurons
     pub fn list_known_neuron_ids(&self) -> Vec<NeuronId> {
         with_stable_neuron_indexes(|indexes| indexes.known_neuron().list_known_neuron_ids())
@@ -727,6 +741,31 @@ impl NeuronStore {
         Ok(f(neuron.deref()))
     }
 
+    /// Like `with_neuron`, but the neuron passed to `f` only has its main fields and hot keys
+    /// populated when it lives in stable storage, i.e. its recent ballots, followees, etc. may be
+    /// missing. This avoids reading those from stable memory when they are not needed.
+    pub fn with_abridged_neuron<R>(
+        &self,
+        neuron_id: &NeuronId,
+        f: impl FnOnce(&Neuron) -> R,
+    ) -> Result<R, NeuronStoreError> {
+        let heap_neuron = self.heap_neurons.get(&neuron_id.id);
+        if let Some(heap_neuron) = heap_neuron {
+            if !heap_neuron.is_inactive(self.now()) {
+                return Ok(f(heap_neuron));
+            }
+        }
+
+        let stable_neuron = with_stable_neuron_store(|stable_neuron_store| {
+            stable_neuron_store.read_abridged(*neuron_id).ok()
+        });
+        match (stable_neuron, heap_neuron) {
+            (Some(stable), _) => Ok(f(&stable)),
+            (None, Some(heap)) => Ok(f(heap)),
+            (None, None) => Err(NeuronStoreError::not_found(*neuron_id)),
+        }
+    }
+
     // Below are indexes related methods. They don't have a unified interface yet, but NNS1-2507 will change that.
 
     // Read methods for indexes.
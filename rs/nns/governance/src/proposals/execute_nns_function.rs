@@ -0,0 +1,422 @@
+use crate::pb::v1::{ExecuteNnsFunction, NnsFunction};
+use candid::{types::value::IDLValue, CandidType, Decode, IDLArgs, Nat};
+use ic_base_types::CanisterId;
+use ic_crypto_sha2::Sha256;
+use ic_management_canister_types::CanisterInstallMode;
+use ic_nervous_system_root::change_canister::{
+    AddCanisterRequest, ChangeCanisterRequest, ChunkedCanisterWasm,
+};
+use registry_canister::mutations::{
+    do_add_node_operator::AddNodeOperatorPayload, do_add_nodes_to_subnet::AddNodesToSubnetPayload,
+    do_change_subnet_membership::ChangeSubnetMembershipPayload,
+    do_create_subnet::CreateSubnetPayload, do_recover_subnet::RecoverSubnetPayload,
+    do_remove_nodes_from_subnet::RemoveNodesFromSubnetPayload,
+    do_update_node_operator_config::UpdateNodeOperatorConfigPayload,
+    do_update_subnet::UpdateSubnetPayload,
+    do_update_subnet_replica::UpdateSubnetReplicaVersionPayload,
+    node_management::do_remove_nodes::RemoveNodesPayload,
+};
+use serde::de::DeserializeOwned;
+
+/// The maximum length of a payload rendering. Longer renderings are truncated.
+pub(crate) const PAYLOAD_TEXT_RENDERING_BYTES_MAX: usize = 10_000;
+
+/// Appended to renderings that were truncated.
+const TRUNCATION_MARKER: &str = " ... (truncated)";
+
+/// Decodes the payload of an `ExecuteNnsFunction` proposal and renders it as
+/// Candid text, so that voters do not have to decode the payload themselves.
+///
+/// Returns an error if the payload cannot be decoded into the type expected by
+/// the NNS function. Payloads of NNS functions whose type is not known here are
+/// rendered as untyped Candid, or as their SHA-256 hash if they are not valid
+/// Candid. Returns `Ok(None)` only for unknown NNS functions.
+///
+/// The Wasm module and argument of canister installations and upgrades are
+/// rendered as their SHA-256 hashes. Renderings longer than
+/// `PAYLOAD_TEXT_RENDERING_BYTES_MAX` are truncated.
+pub(crate) fn render_execute_nns_function_payload(
+    execute_nns_function: &ExecuteNnsFunction,
+) -> Result<Option<String>, String> {
+    let payload = &execute_nns_function.payload;
+    let Ok(nns_function) = NnsFunction::try_from(execute_nns_function.nns_function) else {
+        return Ok(None);
+    };
+
+    let rendering = match nns_function {
+        // Subnet updates.
+        NnsFunction::CreateSubnet => decode_and_render::<CreateSubnetPayload>(payload)?,
+        NnsFunction::UpdateConfigOfSubnet => decode_and_render::<UpdateSubnetPayload>(payload)?,
+        NnsFunction::RecoverSubnet => decode_and_render::<RecoverSubnetPayload>(payload)?,
+        NnsFunction::UpdateSubnetReplicaVersion => {
+            decode_and_render::<UpdateSubnetReplicaVersionPayload>(payload)?
+        }
+        NnsFunction::AddNodeToSubnet => decode_and_render::<AddNodesToSubnetPayload>(payload)?,
+        NnsFunction::RemoveNodesFromSubnet => {
+            decode_and_render::<RemoveNodesFromSubnetPayload>(payload)?
+        }
+        NnsFunction::ChangeSubnetMembership => {
+            decode_and_render::<ChangeSubnetMembershipPayload>(payload)?
+        }
+
+        // Node operations.
+        NnsFunction::AssignNoid => decode_and_render::<AddNodeOperatorPayload>(payload)?,
+        NnsFunction::UpdateNodeOperatorConfig => {
+            decode_and_render::<UpdateNodeOperatorConfigPayload>(payload)?
+        }
+        NnsFunction::RemoveNodes => decode_and_render::<RemoveNodesPayload>(payload)?,
+
+        // Canister installations and upgrades.
+        NnsFunction::NnsCanisterUpgrade => {
+            let request =
+                ChangeCanisterRequestRendering::from(decode::<ChangeCanisterRequest>(payload)?);
+            render(to_idl_value(&request)?)
+        }
+        NnsFunction::NnsCanisterInstall => {
+            let request = AddCanisterRequestRendering::from(decode::<AddCanisterRequest>(payload)?);
+            render(to_idl_value(&request)?)
+        }
+
+        _ => render_untyped(payload),
+    };
+
+    Ok(Some(truncate(rendering)))
+}
+
+/// Renders a payload whose type is not known, in which case the field names
+/// are shown as their Candid hashes. Payloads that are too large to be
+/// rendered in full are rendered as their hash.
+fn render_untyped(payload: &[u8]) -> String {
+    let args = if payload.len() <= PAYLOAD_TEXT_RENDERING_BYTES_MAX {
+        IDLArgs::from_bytes(payload).ok()
+    } else {
+        None
+    };
+    match args {
+        Some(args) => args.to_string(),
+        None => format!(
+            "(record {{ payload_sha256 = \"{}\"; payload_size = {} }})",
+            sha256_hex(payload),
+            payload.len()
+        ),
+    }
+}
+
+fn truncate(mut rendering: String) -> String {
+    if rendering.len() <= PAYLOAD_TEXT_RENDERING_BYTES_MAX {
+        return rendering;
+    }
+    let mut end = PAYLOAD_TEXT_RENDERING_BYTES_MAX - TRUNCATION_MARKER.len();
+    while !rendering.is_char_boundary(end) {
+        end -= 1;
+    }
+    rendering.truncate(end);
+    rendering.push_str(TRUNCATION_MARKER);
+    rendering
+}
+
+fn decode<T>(payload: &[u8]) -> Result<T, String>
+where
+    T: CandidType + DeserializeOwned,
+{
+    Decode!(payload, T).map_err(|err| {
+        // Only keep the name of the type, without its path.
+        let type_name = std::any::type_name::<T>()
+            .rsplit("::")
+            .next()
+            .unwrap_or_default();
+        format!(
+            "The payload could not be decoded into a {}: {}",
+            type_name, err
+        )
+    })
+}
+
+fn decode_and_render<T>(payload: &[u8]) -> Result<String, String>
+where
+    T: CandidType + DeserializeOwned,
+{
+    let payload = decode::<T>(payload)?;
+    let value = to_idl_value(&payload)?;
+    Ok(render(value))
+}
+
+/// A `ChangeCanisterRequest` whose (possibly very large) `wasm_module` and
+/// `arg` fields are replaced by their SHA-256 hashes, so that the request can
+/// be rendered without converting the Wasm module to a Candid value.
+#[derive(CandidType)]
+struct ChangeCanisterRequestRendering {
+    stop_before_installing: bool,
+    mode: CanisterInstallMode,
+    canister_id: CanisterId,
+    wasm_module_sha256: String,
+    arg_sha256: String,
+    compute_allocation: Option<Nat>,
+    memory_allocation: Option<Nat>,
+    query_allocation: Option<Nat>,
+    chunked_canister_wasm: Option<ChunkedCanisterWasm>,
+}
+
+impl From<ChangeCanisterRequest> for ChangeCanisterRequestRendering {
+    fn from(request: ChangeCanisterRequest) -> Self {
+        let ChangeCanisterRequest {
+            stop_before_installing,
+            mode,
+            canister_id,
+            wasm_module,
+            arg,
+            compute_allocation,
+            memory_allocation,
+            query_allocation,
+            chunked_canister_wasm,
+        } = request;
+        Self {
+            stop_before_installing,
+            mode,
+            canister_id,
+            wasm_module_sha256: sha256_hex(&wasm_module),
+            arg_sha256: sha256_hex(&arg),
+            compute_allocation,
+            memory_allocation,
+            query_allocation,
+            chunked_canister_wasm,
+        }
+    }
+}
+
+/// An `AddCanisterRequest` whose `wasm_module` and `arg` fields are replaced
+/// by their SHA-256 hashes, like `ChangeCanisterRequestRendering`.
+#[derive(CandidType)]
+struct AddCanisterRequestRendering {
+    name: String,
+    wasm_module_sha256: String,
+    arg_sha256: String,
+    compute_allocation: Option<Nat>,
+    memory_allocation: Option<Nat>,
+    query_allocation: Option<Nat>,
+    initial_cycles: u64,
+}
+
+impl From<AddCanisterRequest> for AddCanisterRequestRendering {
+    fn from(request: AddCanisterRequest) -> Self {
+        let AddCanisterRequest {
+            name,
+            wasm_module,
+            arg,
+            compute_allocation,
+            memory_allocation,
+            query_allocation,
+            initial_cycles,
+        } = request;
+        Self {
+            name,
+            wasm_module_sha256: sha256_hex(&wasm_module),
+            arg_sha256: sha256_hex(&arg),
+            compute_allocation,
+            memory_allocation,
+            query_allocation,
+            initial_cycles,
+        }
+    }
+}
+
+fn to_idl_value<T: CandidType>(value: &T) -> Result<IDLValue, String> {
+    IDLValue::try_from_candid_type(value)
+        .map_err(|err| format!("The payload could not be rendered as Candid: {}", err))
+}
+
+fn render(value: IDLValue) -> String {
+    IDLArgs::new(&[value]).to_string()
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::hash(bytes)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use candid::Encode;
+    use ic_base_types::{NodeId, PrincipalId};
+
+    fn execute_nns_function(nns_function: NnsFunction, payload: Vec<u8>) -> ExecuteNnsFunction {
+        ExecuteNnsFunction {
+            nns_function: nns_function as i32,
+            payload,
+        }
+    }
+
+    #[test]
+    fn renders_remove_nodes_payload() {
+        let payload = RemoveNodesPayload {
+            node_ids: vec![NodeId::from(PrincipalId::new_node_test_id(1))],
+        };
+
+        let rendering = render_execute_nns_function_payload(&execute_nns_function(
+            NnsFunction::RemoveNodes,
+            Encode!(&payload).unwrap(),
+        ))
+        .unwrap()
+        .unwrap();
+
+        assert!(rendering.contains("node_ids"), "{}", rendering);
+        assert!(
+            rendering.contains(&PrincipalId::new_node_test_id(1).to_string()),
+            "{}",
+            rendering
+        );
+    }
+
+    #[test]
+    fn renders_canister_upgrade_with_hashes() {
+        let wasm_module = vec![0, 97, 115, 109, 1, 0, 0, 0];
+        let arg = Encode!().unwrap();
+        let payload = ChangeCanisterRequest {
+            stop_before_installing: true,
+            mode: ic_management_canister_types::CanisterInstallMode::Upgrade,
+            canister_id: ic_nns_constants::GOVERNANCE_CANISTER_ID,
+            wasm_module: wasm_module.clone(),
+            arg: arg.clone(),
+            compute_allocation: None,
+            memory_allocation: None,
+            query_allocation: None,
+            chunked_canister_wasm: None,
+        };
+
+        let rendering = render_execute_nns_function_payload(&execute_nns_function(
+            NnsFunction::NnsCanisterUpgrade,
+            Encode!(&payload).unwrap(),
+        ))
+        .unwrap()
+        .unwrap();
+
+        assert!(
+            rendering.contains(&format!(
+                "wasm_module_sha256 = \"{}\"",
+                sha256_hex(&wasm_module)
+            )),
+            "{}",
+            rendering
+        );
+        assert!(
+            rendering.contains(&format!("arg_sha256 = \"{}\"", sha256_hex(&arg))),
+            "{}",
+            rendering
+        );
+        assert!(!rendering.contains("wasm_module ="), "{}", rendering);
+    }
+
+    #[test]
+    fn renders_canister_install_with_hashes() {
+        // A Wasm module far larger than the rendering limit.
+        let wasm_module = vec![42; 2 * 1024 * 1024];
+        let arg = Encode!(&"init".to_string()).unwrap();
+        let payload = AddCanisterRequest {
+            name: "my-canister".to_string(),
+            wasm_module: wasm_module.clone(),
+            arg: arg.clone(),
+            compute_allocation: None,
+            memory_allocation: None,
+            query_allocation: None,
+            initial_cycles: 1_000,
+        };
+
+        let rendering = render_execute_nns_function_payload(&execute_nns_function(
+            NnsFunction::NnsCanisterInstall,
+            Encode!(&payload).unwrap(),
+        ))
+        .unwrap()
+        .unwrap();
+
+        assert!(rendering.contains("\"my-canister\""), "{}", rendering);
+        assert!(
+            rendering.contains(&format!(
+                "wasm_module_sha256 = \"{}\"",
+                sha256_hex(&wasm_module)
+            )),
+            "{}",
+            rendering
+        );
+        assert!(
+            rendering.contains(&format!("arg_sha256 = \"{}\"", sha256_hex(&arg))),
+            "{}",
+            rendering
+        );
+        assert!(!rendering.ends_with(TRUNCATION_MARKER), "{}", rendering);
+    }
+
+    #[test]
+    fn rejects_payload_that_cannot_be_decoded() {
+        let error = render_execute_nns_function_payload(&execute_nns_function(
+            NnsFunction::UpdateConfigOfSubnet,
+            vec![1, 2, 3],
+        ))
+        .unwrap_err();
+
+        assert!(
+            error.contains("could not be decoded into a UpdateSubnetPayload"),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn renders_other_nns_functions_without_types() {
+        let rendering = render_execute_nns_function_payload(&execute_nns_function(
+            NnsFunction::ClearProvisionalWhitelist,
+            Encode!(&"hello".to_string(), &42_u64).unwrap(),
+        ))
+        .unwrap()
+        .unwrap();
+        assert!(rendering.contains("\"hello\""), "{}", rendering);
+        assert!(rendering.contains("42"), "{}", rendering);
+
+        let rendering = render_execute_nns_function_payload(&execute_nns_function(
+            NnsFunction::ClearProvisionalWhitelist,
+            vec![1, 2, 3],
+        ))
+        .unwrap()
+        .unwrap();
+        assert_eq!(
+            rendering,
+            format!(
+                "(record {{ payload_sha256 = \"{}\"; payload_size = 3 }})",
+                sha256_hex(&[1, 2, 3])
+            )
+        );
+    }
+
+    #[test]
+    fn does_not_render_unknown_nns_functions() {
+        assert_eq!(
+            render_execute_nns_function_payload(&ExecuteNnsFunction {
+                nns_function: i32::MAX,
+                payload: vec![1, 2, 3],
+            }),
+            Ok(None)
+        );
+    }
+
+    #[test]
+    fn truncates_long_renderings() {
+        let payload = RemoveNodesPayload {
+            node_ids: (0..1000)
+                .map(|id| NodeId::from(PrincipalId::new_node_test_id(id)))
+                .collect(),
+        };
+
+        let rendering = render_execute_nns_function_payload(&execute_nns_function(
+            NnsFunction::RemoveNodes,
+            Encode!(&payload).unwrap(),
+        ))
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(rendering.len(), PAYLOAD_TEXT_RENDERING_BYTES_MAX);
+        assert!(rendering.ends_with(TRUNCATION_MARKER), "{}", rendering);
+    }
+}
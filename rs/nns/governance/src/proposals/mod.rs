@@ -1,2 +1,3 @@
 pub mod create_service_nervous_system;
+pub mod execute_nns_function;
 pub mod proposal_submission;
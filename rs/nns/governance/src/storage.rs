@@ -1,4 +1,4 @@
-use crate::{governance::LOG_PREFIX, pb::v1::AuditEvent};
+use crate::{governance::LOG_PREFIX, pb::v1::AuditEvent, voting_history::VotingHistoryStore};
 
 #[cfg(target_arch = "wasm32")]
 use dfn_core::println;
@@ -26,6 +26,12 @@ const NEURON_FOLLOWING_INDEX_MEMORY_ID: MemoryId = MemoryId::new(11);
 const NEURON_KNOWN_NEURON_INDEX_MEMORY_ID: MemoryId = MemoryId::new(12);
 const NEURON_ACCOUNT_ID_INDEX_MEMORY_ID: MemoryId = MemoryId::new(13);
 
+const DISBURSE_MATURITY_IN_PROGRESS_NEURONS_MEMORY_ID: MemoryId = MemoryId::new(14);
+
+const VOTING_HISTORY_MEMORY_ID: MemoryId = MemoryId::new(15);
+const VOTING_HISTORY_REMOVED_NEURONS_MEMORY_ID: MemoryId = MemoryId::new(16);
+const VOTING_HISTORY_PROPOSAL_INDEX_MEMORY_ID: MemoryId = MemoryId::new(17);
+
 pub mod neuron_indexes;
 pub mod neurons;
 
@@ -50,6 +56,9 @@ struct State {
 
     // Neuron indexes stored in stable storage.
     stable_neuron_indexes: neuron_indexes::StableNeuronIndexes<VM>,
+
+    // Votes cast by neurons, kept for auditing the voting history of neurons.
+    voting_history_store: VotingHistoryStore<VM>,
 }
 
 impl State {
@@ -73,6 +82,8 @@ impl State {
                 hot_keys: memory_manager.get(HOT_KEYS_NEURONS_MEMORY_ID),
                 followees: memory_manager.get(FOLLOWEES_NEURONS_MEMORY_ID),
                 recent_ballots: memory_manager.get(RECENT_BALLOTS_NEURONS_MEMORY_ID),
+                disburse_maturity_in_progress: memory_manager
+                    .get(DISBURSE_MATURITY_IN_PROGRESS_NEURONS_MEMORY_ID),
 
                 // Singletons
                 known_neuron_data: memory_manager.get(KNOWN_NEURON_DATA_NEURONS_MEMORY_ID),
@@ -93,11 +104,21 @@ impl State {
             .build()
         });
 
+        let voting_history_store = MEMORY_MANAGER.with(|memory_manager| {
+            let memory_manager = memory_manager.borrow();
+            VotingHistoryStore::new(
+                memory_manager.get(VOTING_HISTORY_MEMORY_ID),
+                memory_manager.get(VOTING_HISTORY_PROPOSAL_INDEX_MEMORY_ID),
+                memory_manager.get(VOTING_HISTORY_REMOVED_NEURONS_MEMORY_ID),
+            )
+        });
+
         Self {
             upgrades_memory,
             audit_events_log,
             stable_neuron_store,
             stable_neuron_indexes,
+            voting_history_store,
         }
     }
 
@@ -106,6 +127,7 @@ impl State {
     fn validate(&self) {
         self.stable_neuron_store.validate();
         self.stable_neuron_indexes.validate();
+        self.voting_history_store.validate();
     }
 }
 
@@ -159,6 +181,22 @@ pub(crate) fn with_stable_neuron_indexes_mut<R>(
     })
 }
 
+pub(crate) fn with_voting_history_store<R>(f: impl FnOnce(&VotingHistoryStore<VM>) -> R) -> R {
+    STATE.with(|state| {
+        let voting_history_store = &state.borrow().voting_history_store;
+        f(voting_history_store)
+    })
+}
+
+pub(crate) fn with_voting_history_store_mut<R>(
+    f: impl FnOnce(&mut VotingHistoryStore<VM>) -> R,
+) -> R {
+    STATE.with(|state| {
+        let voting_history_store = &mut state.borrow_mut().voting_history_store;
+        f(voting_history_store)
+    })
+}
+
 /// Validates that some of the data in stable storage can be read, in order to prevent broken
 /// schema. Should only be called in post_upgrade.
 pub fn validate_stable_storage() {
@@ -3,7 +3,8 @@ use crate::{
     pb::v1::{
         abridged_neuron::DissolveState as AbridgedNeuronDissolveState,
         neuron::{DissolveState as NeuronDissolveState, Followees},
-        AbridgedNeuron, BallotInfo, KnownNeuronData, Neuron, NeuronStakeTransfer, Topic,
+        AbridgedNeuron, BallotInfo, DisburseMaturityInProgress, KnownNeuronData, Neuron,
+        NeuronStakeTransfer, Topic,
     },
     storage::validate_stable_btree_map,
 };
@@ -65,6 +66,7 @@ pub(crate) struct StableNeuronStoreBuilder<Memory> {
     pub hot_keys: Memory,
     pub recent_ballots: Memory,
     pub followees: Memory,
+    pub disburse_maturity_in_progress: Memory,
 
     // Singletons
     pub known_neuron_data: Memory,
@@ -83,6 +85,7 @@ where
             hot_keys,
             recent_ballots,
             followees,
+            disburse_maturity_in_progress,
 
             // Singletons
             known_neuron_data,
@@ -96,6 +99,7 @@ where
             hot_keys_map: StableBTreeMap::init(hot_keys),
             followees_map: StableBTreeMap::init(followees),
             recent_ballots_map: StableBTreeMap::init(recent_ballots),
+            disburse_maturity_in_progress_map: StableBTreeMap::init(disburse_maturity_in_progress),
 
             // Singletons
             known_neuron_data_map: StableBTreeMap::init(known_neuron_data),
@@ -114,6 +118,8 @@ where
     hot_keys_map: StableBTreeMap<(NeuronId, /* index */ u64), PrincipalId, Memory>,
     recent_ballots_map: StableBTreeMap<(NeuronId, /* index */ u64), BallotInfo, Memory>,
     followees_map: StableBTreeMap<FolloweesKey, NeuronId, Memory>,
+    disburse_maturity_in_progress_map:
+        StableBTreeMap<(NeuronId, /* index */ u64), DisburseMaturityInProgress, Memory>,
 
     // Singletons
     known_neuron_data_map: StableBTreeMap<NeuronId, KnownNeuronData, Memory>,
@@ -165,6 +171,7 @@ where
             hot_keys,
             recent_ballots,
             followees,
+            disburse_maturity_in_progress,
 
             known_neuron_data,
             transfer,
@@ -196,6 +203,11 @@ where
         update_repeated_field(neuron_id, hot_keys, &mut self.hot_keys_map);
         update_repeated_field(neuron_id, recent_ballots, &mut self.recent_ballots_map);
         self.update_followees(neuron_id, followees);
+        update_repeated_field(
+            neuron_id,
+            disburse_maturity_in_progress,
+            &mut self.disburse_maturity_in_progress_map,
+        );
 
         update_singleton_field(
             neuron_id,
@@ -218,6 +230,32 @@ where
         Ok(self.reconstitute_neuron(neuron_id, main_neuron_part))
     }
 
+    /// Retrieves an existing entry, but only its main part and hot keys. The
+    /// remaining collections and singletons (e.g. recent ballots and followees)
+    /// are left empty, which makes this considerably cheaper than `read`.
+    ///
+    /// The result must therefore never be written back.
+    pub fn read_abridged(&self, neuron_id: NeuronId) -> Result<Neuron, NeuronStoreError> {
+        let main_neuron_part = self
+            .main
+            .get(&neuron_id)
+            .ok_or_else(|| NeuronStoreError::not_found(neuron_id))?;
+
+        Ok(DecomposedNeuron {
+            id: neuron_id,
+            main: main_neuron_part,
+
+            hot_keys: read_repeated_field(neuron_id, &self.hot_keys_map),
+            recent_ballots: vec![],
+            followees: HashMap::new(),
+            disburse_maturity_in_progress: vec![],
+
+            known_neuron_data: None,
+            transfer: None,
+        }
+        .reconstitute())
+    }
+
     /// Changes an existing entry.
     ///
     /// If the entry does not already exist, returns a NotFound Err.
@@ -235,6 +273,7 @@ where
             hot_keys,
             recent_ballots,
             followees,
+            disburse_maturity_in_progress,
 
             known_neuron_data,
             transfer,
@@ -273,6 +312,13 @@ where
         if followees != old_neuron.followees {
             self.update_followees(neuron_id, followees);
         }
+        if disburse_maturity_in_progress != old_neuron.disburse_maturity_in_progress {
+            update_repeated_field(
+                neuron_id,
+                disburse_maturity_in_progress,
+                &mut self.disburse_maturity_in_progress_map,
+            );
+        }
 
         if known_neuron_data != old_neuron.known_neuron_data {
             update_singleton_field(
@@ -306,6 +352,11 @@ where
         update_repeated_field(neuron_id, vec![], &mut self.hot_keys_map);
         update_repeated_field(neuron_id, vec![], &mut self.recent_ballots_map);
         self.update_followees(neuron_id, hashmap![]);
+        update_repeated_field(
+            neuron_id,
+            vec![],
+            &mut self.disburse_maturity_in_progress_map,
+        );
 
         update_singleton_field(neuron_id, None, &mut self.known_neuron_data_map);
         update_singleton_field(neuron_id, None, &mut self.transfer_map);
@@ -348,6 +399,7 @@ where
         validate_stable_btree_map(&self.hot_keys_map);
         validate_stable_btree_map(&self.recent_ballots_map);
         validate_stable_btree_map(&self.followees_map);
+        validate_stable_btree_map(&self.disburse_maturity_in_progress_map);
         validate_stable_btree_map(&self.known_neuron_data_map);
         validate_stable_btree_map(&self.transfer_map);
     }
@@ -358,6 +410,8 @@ where
         let hot_keys = read_repeated_field(neuron_id, &self.hot_keys_map);
         let recent_ballots = read_repeated_field(neuron_id, &self.recent_ballots_map);
         let followees = self.read_followees(neuron_id);
+        let disburse_maturity_in_progress =
+            read_repeated_field(neuron_id, &self.disburse_maturity_in_progress_map);
 
         let known_neuron_data = self.known_neuron_data_map.get(&neuron_id);
         let transfer = self.transfer_map.get(&neuron_id);
@@ -369,6 +423,7 @@ where
             hot_keys,
             recent_ballots,
             followees,
+            disburse_maturity_in_progress,
 
             known_neuron_data,
             transfer,
@@ -481,6 +536,7 @@ pub(crate) fn new_heap_based() -> StableNeuronStore<VectorMemory> {
         hot_keys: VectorMemory::default(),
         recent_ballots: VectorMemory::default(),
         followees: VectorMemory::default(),
+        disburse_maturity_in_progress: VectorMemory::default(),
 
         // Singletons
         known_neuron_data: VectorMemory::default(),
@@ -530,6 +586,22 @@ impl Storable for BallotInfo {
     };
 }
 
+impl Storable for DisburseMaturityInProgress {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::from(self.encode_to_vec())
+    }
+
+    fn from_bytes(bytes: Cow<'_, [u8]>) -> Self {
+        Self::decode(&bytes[..]).expect("Unable to deserialize DisburseMaturityInProgress.")
+    }
+
+    const BOUND: Bound = Bound::Bounded {
+        // How this number was chosen: Similar to how MAX_SIZE was chosen for Neuron.
+        max_size: 128,
+        is_fixed_size: false,
+    };
+}
+
 impl Storable for KnownNeuronData {
     fn to_bytes(&self) -> Cow<'_, [u8]> {
         Cow::from(self.encode_to_vec())
@@ -589,6 +661,7 @@ lazy_static! {
 ///         hot_keys,
 ///         recent_ballots,
 ///         followees,
+///         disburse_maturity_in_progress,
 ///
 ///         known_neuron_data,
 ///         transfer,
@@ -609,6 +682,7 @@ struct DecomposedNeuron {
     hot_keys: Vec<PrincipalId>,
     recent_ballots: Vec<BallotInfo>,
     followees: HashMap</* topic ID */ i32, Followees>,
+    disburse_maturity_in_progress: Vec<DisburseMaturityInProgress>,
 
     // Singletons
     known_neuron_data: Option<KnownNeuronData>,
@@ -641,6 +715,7 @@ impl TryFrom<Neuron> for DecomposedNeuron {
             known_neuron_data,
             neuron_type,
             dissolve_state,
+            disburse_maturity_in_progress,
         } = source;
 
         let id = id.ok_or(NeuronStoreError::NeuronIdIsNone)?;
@@ -671,6 +746,7 @@ impl TryFrom<Neuron> for DecomposedNeuron {
             hot_keys,
             recent_ballots,
             followees,
+            disburse_maturity_in_progress,
 
             // Singletons
             known_neuron_data,
@@ -688,6 +764,7 @@ impl DecomposedNeuron {
             hot_keys,
             recent_ballots,
             followees,
+            disburse_maturity_in_progress,
 
             known_neuron_data,
             transfer,
@@ -733,6 +810,7 @@ impl DecomposedNeuron {
             known_neuron_data,
             neuron_type,
             dissolve_state: dissolve_state.map(NeuronDissolveState::from),
+            disburse_maturity_in_progress,
         }
     }
 }
@@ -6,7 +6,11 @@ use async_trait::async_trait;
 use candid::{Decode, Encode};
 use ic_base_types::{CanisterId, PrincipalId};
 use ic_ledger_core::Tokens;
-use ic_nervous_system_common::{cmc::CMC, ledger::IcpLedger, NervousSystemError, E8};
+use ic_nervous_system_common::{
+    cmc::CMC,
+    ledger::{IcpLedger, TransferWithCreatedAtTimeError},
+    NervousSystemError, E8,
+};
 use ic_nns_constants::SNS_WASM_CANISTER_ID;
 use ic_sns_swap::pb::{
     v1 as sns_swap_pb,
@@ -86,6 +90,18 @@ impl IcpLedger for StubIcpLedger {
         unimplemented!()
     }
 
+    async fn transfer_funds_with_created_at_time(
+        &self,
+        _amount_e8s: u64,
+        _fee_e8s: u64,
+        _from_subaccount: Option<Subaccount>,
+        _to: AccountIdentifier,
+        _memo: u64,
+        _created_at_time_nanos: u64,
+    ) -> Result<u64, TransferWithCreatedAtTimeError> {
+        unimplemented!()
+    }
+
     async fn total_supply(&self) -> Result<Tokens, NervousSystemError> {
         unimplemented!()
     }
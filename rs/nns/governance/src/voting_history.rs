@@ -0,0 +1,398 @@
+use crate::{
+    pb::v1::{NeuronVote, Vote},
+    storage::{validate_stable_btree_map, with_voting_history_store_mut},
+};
+use ic_nns_common::pb::v1::{NeuronId, ProposalId};
+use ic_stable_structures::{storable::Bound, Memory, StableBTreeMap, Storable};
+use prost::Message;
+use std::borrow::Cow;
+
+/// The number of most recent proposals for which the votes are kept in the voting history. Votes
+/// on older proposals are removed in batches, see `remove_votes_before_proposal`.
+pub const VOTING_HISTORY_RETENTION_NUM_PROPOSALS: u64 = 50_000;
+
+/// Stores the votes cast by every neuron, keyed by (neuron id, proposal id), so that the voting
+/// history of a neuron can be audited after the ballots of the proposals have been garbage
+/// collected and after the vote has been pushed out of the neuron's `recent_ballots`. Votes cast
+/// by following are flagged as such.
+///
+/// The votes of removed neurons are not removed right away, as a neuron can have voted on a large
+/// number of proposals. Instead, the neuron is marked for removal and its votes are removed in
+/// batches, see `remove_votes_of_removed_neurons`. Similarly, votes on proposals that are older
+/// than the retention period are removed in batches, for which the votes are also indexed by
+/// (proposal id, neuron id).
+pub struct VotingHistoryStore<M: Memory> {
+    neuron_id_proposal_id_to_vote: StableBTreeMap<(NeuronId, u64), NeuronVote, M>,
+    proposal_id_neuron_id_index: StableBTreeMap<(u64, NeuronId), (), M>,
+    removed_neuron_ids: StableBTreeMap<NeuronId, (), M>,
+}
+
+impl<M: Memory> VotingHistoryStore<M> {
+    pub fn new(votes_memory: M, proposal_index_memory: M, removed_neurons_memory: M) -> Self {
+        Self {
+            neuron_id_proposal_id_to_vote: StableBTreeMap::init(votes_memory),
+            proposal_id_neuron_id_index: StableBTreeMap::init(proposal_index_memory),
+            removed_neuron_ids: StableBTreeMap::init(removed_neurons_memory),
+        }
+    }
+
+    /// Records that the neuron voted `vote` on the proposal, either directly or by following. A
+    /// neuron only votes once on each proposal, so an existing entry would only be overwritten
+    /// with the same vote.
+    pub fn record_vote(
+        &mut self,
+        neuron_id: NeuronId,
+        proposal_id: ProposalId,
+        vote: Vote,
+        cast_by_following: bool,
+    ) {
+        self.neuron_id_proposal_id_to_vote.insert(
+            (neuron_id, proposal_id.id),
+            NeuronVote {
+                proposal_id: Some(proposal_id),
+                vote: vote as i32,
+                cast_by_following,
+            },
+        );
+        self.proposal_id_neuron_id_index
+            .insert((proposal_id.id, neuron_id), ());
+    }
+
+    /// Records the votes cast on the proposal, as a batch. See `record_vote`.
+    pub fn record_votes(
+        &mut self,
+        proposal_id: ProposalId,
+        votes: impl IntoIterator<Item = (NeuronId, Vote, bool)>,
+    ) {
+        for (neuron_id, vote, cast_by_following) in votes {
+            self.record_vote(neuron_id, proposal_id, vote, cast_by_following);
+        }
+    }
+
+    /// Lists at most `limit` votes of the neuron on proposals with an id greater than or equal to
+    /// `start_proposal_id`, in ascending order of proposal id.
+    pub fn list_votes(
+        &self,
+        neuron_id: NeuronId,
+        start_proposal_id: u64,
+        limit: usize,
+    ) -> Vec<NeuronVote> {
+        self.neuron_id_proposal_id_to_vote
+            .range((neuron_id, start_proposal_id)..=(neuron_id, u64::MAX))
+            .take(limit)
+            .map(|(_, vote)| vote)
+            .collect()
+    }
+
+    /// Marks the voting history of the neuron for removal. Should be called when the neuron is
+    /// removed. The votes are removed by `remove_votes_of_removed_neurons`.
+    pub fn remove_neuron(&mut self, neuron_id: NeuronId) {
+        self.removed_neuron_ids.insert(neuron_id, ());
+    }
+
+    /// Removes at most `max_votes` votes of neurons that were marked for removal, and returns the
+    /// number of removed votes. A neuron is no longer marked once all of its votes are removed.
+    pub fn remove_votes_of_removed_neurons(&mut self, max_votes: usize) -> usize {
+        let mut num_removed_votes = 0;
+        while let Some((neuron_id, ())) = self.removed_neuron_ids.first_key_value() {
+            let keys_to_remove: Vec<_> = self
+                .neuron_id_proposal_id_to_vote
+                .range((neuron_id, 0)..=(neuron_id, u64::MAX))
+                .take(max_votes - num_removed_votes)
+                .map(|(key, _)| key)
+                .collect();
+            for (neuron_id, proposal_id) in &keys_to_remove {
+                self.remove_vote(*neuron_id, *proposal_id);
+            }
+            num_removed_votes += keys_to_remove.len();
+            if num_removed_votes == max_votes {
+                break;
+            }
+            self.removed_neuron_ids.remove(&neuron_id);
+        }
+        num_removed_votes
+    }
+
+    /// Removes at most `max_votes` votes on proposals with an id smaller than
+    /// `first_retained_proposal_id`, oldest proposals first, and returns the number of removed
+    /// votes.
+    pub fn remove_votes_before_proposal(
+        &mut self,
+        first_retained_proposal_id: u64,
+        max_votes: usize,
+    ) -> usize {
+        let keys_to_remove: Vec<_> = self
+            .proposal_id_neuron_id_index
+            .range(..(first_retained_proposal_id, NeuronId { id: 0 }))
+            .take(max_votes)
+            .map(|(key, _)| key)
+            .collect();
+        for (proposal_id, neuron_id) in &keys_to_remove {
+            self.remove_vote(*neuron_id, *proposal_id);
+        }
+        keys_to_remove.len()
+    }
+
+    fn remove_vote(&mut self, neuron_id: NeuronId, proposal_id: u64) {
+        self.neuron_id_proposal_id_to_vote
+            .remove(&(neuron_id, proposal_id));
+        self.proposal_id_neuron_id_index
+            .remove(&(proposal_id, neuron_id));
+    }
+
+    /// Returns the total number of votes stored, for all neurons.
+    pub fn num_entries(&self) -> usize {
+        self.neuron_id_proposal_id_to_vote.len() as usize
+    }
+
+    /// Returns the number of neurons whose votes are still to be removed.
+    pub fn num_removed_neurons(&self) -> usize {
+        self.removed_neuron_ids.len() as usize
+    }
+
+    /// Validates that some of the data in stable storage can be read, in order to prevent broken
+    /// schema. Should only be called in post_upgrade.
+    pub fn validate(&self) {
+        validate_stable_btree_map(&self.neuron_id_proposal_id_to_vote);
+        validate_stable_btree_map(&self.proposal_id_neuron_id_index);
+        validate_stable_btree_map(&self.removed_neuron_ids);
+    }
+}
+
+impl Storable for NeuronVote {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::from(self.encode_to_vec())
+    }
+
+    fn from_bytes(bytes: Cow<'_, [u8]>) -> Self {
+        Self::decode(&bytes[..]).expect("Unable to deserialize NeuronVote.")
+    }
+
+    const BOUND: Bound = Bound::Bounded {
+        // How this number was chosen: the same as for BallotInfo, which has the same fields except
+        // for `cast_by_following`, which takes at most 2 more bytes.
+        max_size: 48,
+        is_fixed_size: false,
+    };
+}
+
+/// Records the votes of neurons on the proposal in the voting history kept in stable storage. Each
+/// vote is given as (neuron id, vote, cast by following).
+pub(crate) fn record_neuron_votes(
+    proposal_id: ProposalId,
+    votes: impl IntoIterator<Item = (NeuronId, Vote, bool)>,
+) {
+    with_voting_history_store_mut(|voting_history| voting_history.record_votes(proposal_id, votes));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ic_stable_structures::VectorMemory;
+
+    fn new_store() -> VotingHistoryStore<VectorMemory> {
+        VotingHistoryStore::new(
+            VectorMemory::default(),
+            VectorMemory::default(),
+            VectorMemory::default(),
+        )
+    }
+
+    fn neuron_vote(proposal_id: u64, vote: Vote, cast_by_following: bool) -> NeuronVote {
+        NeuronVote {
+            proposal_id: Some(ProposalId { id: proposal_id }),
+            vote: vote as i32,
+            cast_by_following,
+        }
+    }
+
+    #[test]
+    fn list_votes_of_a_single_neuron() {
+        let mut store = new_store();
+        store.record_vote(NeuronId { id: 1 }, ProposalId { id: 3 }, Vote::Yes, false);
+        store.record_vote(NeuronId { id: 1 }, ProposalId { id: 1 }, Vote::No, true);
+        store.record_vote(NeuronId { id: 2 }, ProposalId { id: 2 }, Vote::Yes, false);
+        store.record_vote(NeuronId { id: 1 }, ProposalId { id: 2 }, Vote::Yes, false);
+
+        assert_eq!(
+            store.list_votes(NeuronId { id: 1 }, 0, 100),
+            vec![
+                neuron_vote(1, Vote::No, true),
+                neuron_vote(2, Vote::Yes, false),
+                neuron_vote(3, Vote::Yes, false)
+            ]
+        );
+        assert_eq!(
+            store.list_votes(NeuronId { id: 2 }, 0, 100),
+            vec![neuron_vote(2, Vote::Yes, false)]
+        );
+        assert_eq!(store.list_votes(NeuronId { id: 3 }, 0, 100), vec![]);
+    }
+
+    #[test]
+    fn list_votes_paginated() {
+        let mut store = new_store();
+        for proposal_id in 1..=5 {
+            store.record_vote(
+                NeuronId { id: 1 },
+                ProposalId { id: proposal_id },
+                Vote::Yes,
+                false,
+            );
+        }
+
+        assert_eq!(
+            store.list_votes(NeuronId { id: 1 }, 0, 2),
+            vec![
+                neuron_vote(1, Vote::Yes, false),
+                neuron_vote(2, Vote::Yes, false)
+            ]
+        );
+        assert_eq!(
+            store.list_votes(NeuronId { id: 1 }, 3, 2),
+            vec![
+                neuron_vote(3, Vote::Yes, false),
+                neuron_vote(4, Vote::Yes, false)
+            ]
+        );
+        assert_eq!(
+            store.list_votes(NeuronId { id: 1 }, 5, 2),
+            vec![neuron_vote(5, Vote::Yes, false)]
+        );
+    }
+
+    #[test]
+    fn remove_neuron_only_removes_its_votes() {
+        let mut store = new_store();
+        store.record_vote(NeuronId { id: 1 }, ProposalId { id: 1 }, Vote::Yes, false);
+        store.record_vote(NeuronId { id: 1 }, ProposalId { id: 2 }, Vote::No, true);
+        store.record_vote(NeuronId { id: 2 }, ProposalId { id: 1 }, Vote::Yes, false);
+
+        store.remove_neuron(NeuronId { id: 1 });
+        assert_eq!(store.remove_votes_of_removed_neurons(100), 2);
+
+        assert_eq!(store.list_votes(NeuronId { id: 1 }, 0, 100), vec![]);
+        assert_eq!(
+            store.list_votes(NeuronId { id: 2 }, 0, 100),
+            vec![neuron_vote(1, Vote::Yes, false)]
+        );
+        assert_eq!(store.num_entries(), 1);
+        assert_eq!(store.num_removed_neurons(), 0);
+    }
+
+    #[test]
+    fn remove_votes_of_removed_neurons_in_batches() {
+        let mut store = new_store();
+        for neuron_id in 1..=3 {
+            for proposal_id in 1..=5 {
+                store.record_vote(
+                    NeuronId { id: neuron_id },
+                    ProposalId { id: proposal_id },
+                    Vote::Yes,
+                    neuron_id != 1,
+                );
+            }
+        }
+        store.remove_neuron(NeuronId { id: 1 });
+        store.remove_neuron(NeuronId { id: 3 });
+        // Marking a neuron for removal does not remove any votes yet.
+        assert_eq!(store.num_entries(), 15);
+        assert_eq!(store.num_removed_neurons(), 2);
+
+        assert_eq!(store.remove_votes_of_removed_neurons(3), 3);
+        assert_eq!(store.list_votes(NeuronId { id: 1 }, 0, 100).len(), 2);
+        assert_eq!(store.num_removed_neurons(), 2);
+
+        // A batch can span several neurons.
+        assert_eq!(store.remove_votes_of_removed_neurons(4), 4);
+        assert_eq!(store.list_votes(NeuronId { id: 1 }, 0, 100), vec![]);
+        assert_eq!(store.list_votes(NeuronId { id: 3 }, 0, 100).len(), 3);
+        assert_eq!(store.num_removed_neurons(), 1);
+
+        assert_eq!(store.remove_votes_of_removed_neurons(4), 3);
+        assert_eq!(store.num_removed_neurons(), 0);
+        assert_eq!(store.num_entries(), 5);
+        assert_eq!(store.list_votes(NeuronId { id: 2 }, 0, 100).len(), 5);
+
+        // Nothing is left to remove.
+        assert_eq!(store.remove_votes_of_removed_neurons(4), 0);
+    }
+
+    #[test]
+    fn record_votes_as_a_batch() {
+        let mut store = new_store();
+        store.record_votes(
+            ProposalId { id: 1 },
+            vec![
+                (NeuronId { id: 1 }, Vote::Yes, false),
+                (NeuronId { id: 2 }, Vote::No, true),
+            ],
+        );
+
+        assert_eq!(
+            store.list_votes(NeuronId { id: 1 }, 0, 100),
+            vec![neuron_vote(1, Vote::Yes, false)]
+        );
+        assert_eq!(
+            store.list_votes(NeuronId { id: 2 }, 0, 100),
+            vec![neuron_vote(1, Vote::No, true)]
+        );
+    }
+
+    #[test]
+    fn remove_votes_before_proposal_in_batches() {
+        let mut store = new_store();
+        for neuron_id in 1..=3 {
+            for proposal_id in 1..=4 {
+                store.record_vote(
+                    NeuronId { id: neuron_id },
+                    ProposalId { id: proposal_id },
+                    Vote::Yes,
+                    false,
+                );
+            }
+        }
+
+        // The votes on the oldest proposal are removed first, across neurons.
+        assert_eq!(store.remove_votes_before_proposal(3, 4), 4);
+        for neuron_id in 1..=3 {
+            assert_eq!(
+                store.list_votes(NeuronId { id: neuron_id }, 0, 1),
+                vec![neuron_vote(
+                    if neuron_id == 1 { 3 } else { 2 },
+                    Vote::Yes,
+                    false
+                )]
+            );
+        }
+
+        assert_eq!(store.remove_votes_before_proposal(3, 4), 2);
+        assert_eq!(store.num_entries(), 6);
+        for neuron_id in 1..=3 {
+            assert_eq!(
+                store.list_votes(NeuronId { id: neuron_id }, 0, 100),
+                vec![
+                    neuron_vote(3, Vote::Yes, false),
+                    neuron_vote(4, Vote::Yes, false)
+                ]
+            );
+        }
+
+        // Votes on retained proposals are never removed.
+        assert_eq!(store.remove_votes_before_proposal(3, 4), 0);
+    }
+
+    #[test]
+    fn removing_votes_of_removed_neurons_also_removes_them_from_the_proposal_index() {
+        let mut store = new_store();
+        store.record_vote(NeuronId { id: 1 }, ProposalId { id: 1 }, Vote::Yes, false);
+        store.record_vote(NeuronId { id: 2 }, ProposalId { id: 1 }, Vote::No, false);
+        store.remove_neuron(NeuronId { id: 1 });
+        assert_eq!(store.remove_votes_of_removed_neurons(100), 1);
+
+        // Only the vote of the neuron that was not removed is left to be pruned.
+        assert_eq!(store.remove_votes_before_proposal(2, 100), 1);
+        assert_eq!(store.num_entries(), 0);
+    }
+}
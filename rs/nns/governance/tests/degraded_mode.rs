@@ -2,10 +2,18 @@
 //! the heap cannot grow very much.
 use assert_matches::assert_matches;
 use async_trait::async_trait;
+use candid::Encode;
 use futures::future::FutureExt;
 use ic_base_types::{CanisterId, PrincipalId};
-use ic_nervous_system_common::{cmc::CMC, ledger::IcpLedger, NervousSystemError};
+use ic_management_canister_types::CanisterInstallMode;
+use ic_nervous_system_common::{
+    cmc::CMC,
+    ledger::{IcpLedger, TransferWithCreatedAtTimeError},
+    NervousSystemError,
+};
+use ic_nervous_system_root::change_canister::ChangeCanisterRequest;
 use ic_nns_common::pb::v1::NeuronId;
+use ic_nns_constants::GOVERNANCE_CANISTER_ID;
 use ic_nns_governance::{
     governance::{
         Environment, Governance, HeapGrowthPotential, HEAP_SIZE_SOFT_LIMIT_IN_WASM32_PAGES,
@@ -72,6 +80,18 @@ impl IcpLedger for DegradedEnv {
         unimplemented!()
     }
 
+    async fn transfer_funds_with_created_at_time(
+        &self,
+        _amount_e8s: u64,
+        _fee_e8s: u64,
+        _from_subaccount: Option<Subaccount>,
+        _to: AccountIdentifier,
+        _memo: u64,
+        _created_at_time_nanos: u64,
+    ) -> Result<u64, TransferWithCreatedAtTimeError> {
+        unimplemented!()
+    }
+
     async fn total_supply(&self) -> Result<Tokens, NervousSystemError> {
         unimplemented!()
     }
@@ -181,7 +201,12 @@ async fn test_can_submit_nns_canister_upgrade_in_degraded_mode() {
                 summary: "proposal 1".to_string(),
                 action: Some(proposal::Action::ExecuteNnsFunction(ExecuteNnsFunction {
                     nns_function: NnsFunction::NnsCanisterUpgrade as i32,
-                    payload: Vec::new(),
+                    payload: Encode!(&ChangeCanisterRequest::new(
+                        true,
+                        CanisterInstallMode::Upgrade,
+                        GOVERNANCE_CANISTER_ID,
+                    ))
+                    .unwrap(),
                 })),
                 ..Default::default()
             },
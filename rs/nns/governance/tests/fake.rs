@@ -4,7 +4,11 @@ use cycles_minting_canister::{IcpXdrConversionRate, IcpXdrConversionRateCertifie
 use futures::future::FutureExt;
 use ic_base_types::{CanisterId, PrincipalId};
 use ic_ledger_core::tokens::CheckedSub;
-use ic_nervous_system_common::{cmc::CMC, ledger::IcpLedger, NervousSystemError};
+use ic_nervous_system_common::{
+    cmc::CMC,
+    ledger::{IcpLedger, TransferWithCreatedAtTimeError},
+    NervousSystemError,
+};
 use ic_nns_common::{
     pb::v1::{NeuronId, ProposalId},
     types::UpdateIcpXdrConversionRatePayload,
@@ -285,6 +289,22 @@ impl IcpLedger for FakeDriver {
         Ok(0)
     }
 
+    async fn transfer_funds_with_created_at_time(
+        &self,
+        amount_e8s: u64,
+        fee_e8s: u64,
+        from_subaccount: Option<Subaccount>,
+        to_account: AccountIdentifier,
+        memo: u64,
+        _created_at_time_nanos: u64,
+    ) -> Result<u64, TransferWithCreatedAtTimeError> {
+        // The fake ledger always replies, so transfers are never retried and need not be
+        // deduplicated.
+        self.transfer_funds(amount_e8s, fee_e8s, from_subaccount, to_account, memo)
+            .await
+            .map_err(TransferWithCreatedAtTimeError::Rejected)
+    }
+
     async fn total_supply(&self) -> Result<Tokens, NervousSystemError> {
         Ok(self.get_supply())
     }
@@ -12,7 +12,11 @@ use comparable::Comparable;
 use futures::future::FutureExt;
 use ic_base_types::{CanisterId, PrincipalId};
 use ic_crypto_sha2::Sha256;
-use ic_nervous_system_common::{cmc::CMC, ledger::IcpLedger, NervousSystemError};
+use ic_nervous_system_common::{
+    cmc::CMC,
+    ledger::{IcpLedger, TransferWithCreatedAtTimeError},
+    NervousSystemError,
+};
 use ic_nns_common::{
     pb::v1::{NeuronId, ProposalId},
     types::UpdateIcpXdrConversionRatePayload,
@@ -488,6 +492,22 @@ impl IcpLedger for NNSFixture {
         Ok(0)
     }
 
+    async fn transfer_funds_with_created_at_time(
+        &self,
+        amount_e8s: u64,
+        fee_e8s: u64,
+        from_subaccount: Option<Subaccount>,
+        to_account: AccountIdentifier,
+        memo: u64,
+        _created_at_time_nanos: u64,
+    ) -> Result<u64, TransferWithCreatedAtTimeError> {
+        // The fixture ledger always replies, so transfers are never retried and need not be
+        // deduplicated.
+        self.transfer_funds(amount_e8s, fee_e8s, from_subaccount, to_account, memo)
+            .await
+            .map_err(TransferWithCreatedAtTimeError::Rejected)
+    }
+
     async fn total_supply(&self) -> Result<Tokens, NervousSystemError> {
         Ok(self.nns_state.try_lock().unwrap().ledger.get_supply())
     }
@@ -950,6 +970,27 @@ impl IcpLedger for NNS {
             .await
     }
 
+    async fn transfer_funds_with_created_at_time(
+        &self,
+        amount_e8s: u64,
+        fee_e8s: u64,
+        from_subaccount: Option<Subaccount>,
+        to_account: AccountIdentifier,
+        memo: u64,
+        created_at_time_nanos: u64,
+    ) -> Result<u64, TransferWithCreatedAtTimeError> {
+        self.fixture
+            .transfer_funds_with_created_at_time(
+                amount_e8s,
+                fee_e8s,
+                from_subaccount,
+                to_account,
+                memo,
+                created_at_time_nanos,
+            )
+            .await
+    }
+
     async fn total_supply(&self) -> Result<Tokens, NervousSystemError> {
         self.fixture.total_supply().await
     }
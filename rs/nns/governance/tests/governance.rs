@@ -21,15 +21,19 @@ use fixtures::{
 use futures::future::FutureExt;
 use ic_base_types::{CanisterId, NumBytes, PrincipalId};
 use ic_crypto_sha2::Sha256;
+use ic_management_canister_types::CanisterInstallMode;
 use ic_nervous_system_clients::canister_status::{CanisterStatusResultV2, CanisterStatusType};
 use ic_nervous_system_common::{
-    cmc::CMC, ledger::IcpLedger, NervousSystemError, E8, SECONDS_PER_DAY,
+    cmc::CMC,
+    ledger::{IcpLedger, TransferWithCreatedAtTimeError},
+    NervousSystemError, E8, SECONDS_PER_DAY,
 };
 use ic_nervous_system_common_test_keys::{
     TEST_NEURON_1_OWNER_PRINCIPAL, TEST_NEURON_2_OWNER_PRINCIPAL,
 };
 use ic_nervous_system_common_test_utils::{LedgerReply, SpyLedger};
 use ic_nervous_system_proto::pb::v1::{Duration, GlobalTimeOfDay, Image};
+use ic_nervous_system_root::change_canister::ChangeCanisterRequest;
 use ic_neurons_fund::{PolynomialMatchingFunction, SerializableFunction};
 use ic_nns_common::{
     pb::v1::{NeuronId, ProposalId},
@@ -76,9 +80,9 @@ use ic_nns_governance::{
         settle_neurons_fund_participation_request, swap_background_information,
         AddOrRemoveNodeProvider, ApproveGenesisKyc, Ballot, BallotChange, BallotInfo,
         BallotInfoChange, CreateServiceNervousSystem, Empty, ExecuteNnsFunction,
-        Governance as GovernanceProto, GovernanceChange, GovernanceError,
-        IdealMatchedParticipationFunction, KnownNeuron, KnownNeuronData, ListNeurons,
-        ListNeuronsResponse, ListProposalInfo, ListProposalInfoResponse, ManageNeuron,
+        GetNeuronVotingHistoryRequest, Governance as GovernanceProto, GovernanceChange,
+        GovernanceError, IdealMatchedParticipationFunction, KnownNeuron, KnownNeuronData,
+        ListNeurons, ListNeuronsResponse, ListProposalInfo, ListProposalInfoResponse, ManageNeuron,
         ManageNeuronResponse, MostRecentMonthlyNodeProviderRewards, Motion, NetworkEconomics,
         Neuron, NeuronChange, NeuronState, NeuronType, NeuronsFundData, NeuronsFundParticipation,
         NeuronsFundSnapshot, NnsFunction, NodeProvider, Proposal, ProposalChange, ProposalData,
@@ -1653,6 +1657,88 @@ async fn test_all_follow_proposer() {
     );
 }
 
+/// The voting history of a neuron keeps both the votes it cast directly and
+/// the ones cast by following, flagged as such.
+#[tokio::test]
+async fn test_voting_history_records_direct_and_followed_votes() {
+    let driver = fake::FakeDriver::default();
+    let mut gov = Governance::new(
+        fixture_for_following(),
+        driver.get_fake_env(),
+        driver.get_fake_ledger(),
+        driver.get_fake_cmc(),
+    );
+    gov.manage_neuron(
+        // Must match neuron 5's serialized_id.
+        &principal(5),
+        &ManageNeuron {
+            id: None,
+            neuron_id_or_subaccount: Some(NeuronIdOrSubaccount::NeuronId(NeuronId { id: 5 })),
+            command: Some(manage_neuron::Command::Follow(manage_neuron::Follow {
+                topic: Topic::Unspecified as i32,
+                followees: [NeuronId { id: 1 }].to_vec(),
+            })),
+        },
+    )
+    .now_or_never()
+    .unwrap()
+    .expect("Manage neuron failed");
+
+    gov.make_proposal(
+        &NeuronId { id: 1 },
+        // Must match neuron 1's serialized_id.
+        &principal(1),
+        &Proposal {
+            title: Some("A Reasonable Title".to_string()),
+            summary: "test".to_string(),
+            action: Some(proposal::Action::ManageNetworkEconomics(NetworkEconomics {
+                ..Default::default()
+            })),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    let voting_history = |id: u64| {
+        gov.get_neuron_voting_history(
+            &principal(id),
+            &GetNeuronVotingHistoryRequest {
+                neuron_id: Some(NeuronId { id }),
+                start: None,
+                limit: 0,
+            },
+        )
+        .unwrap()
+        .votes
+    };
+    assert_eq!(
+        voting_history(1),
+        vec![ic_nns_governance::pb::v1::NeuronVote {
+            proposal_id: Some(ProposalId { id: 1 }),
+            vote: Vote::Yes as i32,
+            cast_by_following: false,
+        }]
+    );
+    // Neuron 5 voted by following neuron 1.
+    assert_eq!(
+        gov.get_proposal_data(ProposalId { id: 1 })
+            .unwrap()
+            .ballots
+            .get(&5)
+            .unwrap()
+            .vote,
+        Vote::Yes as i32
+    );
+    assert_eq!(
+        voting_history(5),
+        vec![ic_nns_governance::pb::v1::NeuronVote {
+            proposal_id: Some(ProposalId { id: 1 }),
+            vote: Vote::Yes as i32,
+            cast_by_following: true,
+        }]
+    );
+}
+
 /// Here we configure the nodes so that to vote no to the proposal.
 ///
 /// Neuron 1 still makes the proposal.
@@ -7639,6 +7725,75 @@ fn test_get_pending_proposals_removes_execute_nns_function_payload() {
     );
 }
 
+#[test]
+fn test_list_proposals_removes_large_payload_text_rendering() {
+    // ARRANGE
+    let proposal_id = ProposalId { id: 2 };
+    let mut proto = fixture_for_proposals(proposal_id, vec![1, 2, 3]);
+    let large_rendering = "x".repeat(EXECUTE_NNS_FUNCTION_PAYLOAD_LISTING_BYTES_MAX + 1);
+    proto
+        .proposals
+        .get_mut(&proposal_id.id)
+        .unwrap()
+        .payload_text_rendering = Some(large_rendering.clone());
+    let driver = fake::FakeDriver::default();
+    let gov = Governance::new(
+        proto,
+        driver.get_fake_env(),
+        driver.get_fake_ledger(),
+        driver.get_fake_cmc(),
+    );
+    let caller = &principal(1);
+
+    // ACT
+    let listed = gov.list_proposals(caller, &ListProposalInfo::default());
+    let info = gov.get_proposal_info(caller, proposal_id).unwrap();
+
+    // ASSERT
+    assert_eq!(listed.proposal_info[0].payload_text_rendering, None);
+    assert_eq!(info.payload_text_rendering, Some(large_rendering));
+}
+
+#[test]
+fn test_list_proposals_omits_payload_text_rendering_when_omitting_large_fields() {
+    // ARRANGE
+    let proposal_id = ProposalId { id: 2 };
+    let mut proto = fixture_for_proposals(proposal_id, vec![1, 2, 3]);
+    proto
+        .proposals
+        .get_mut(&proposal_id.id)
+        .unwrap()
+        .payload_text_rendering = Some("(record {})".to_string());
+    let driver = fake::FakeDriver::default();
+    let gov = Governance::new(
+        proto,
+        driver.get_fake_env(),
+        driver.get_fake_ledger(),
+        driver.get_fake_cmc(),
+    );
+    let caller = &principal(1);
+
+    // ACT
+    let listed = gov.list_proposals(caller, &ListProposalInfo::default());
+    let listed_without_large_fields = gov.list_proposals(
+        caller,
+        &ListProposalInfo {
+            omit_large_fields: Some(true),
+            ..Default::default()
+        },
+    );
+
+    // ASSERT
+    assert_eq!(
+        listed.proposal_info[0].payload_text_rendering,
+        Some("(record {})".to_string())
+    );
+    assert_eq!(
+        listed_without_large_fields.proposal_info[0].payload_text_rendering,
+        None
+    );
+}
+
 fn proposal_ids(response: &ListProposalInfoResponse) -> Vec<u64> {
     response
         .proposal_info
@@ -8587,6 +8742,11 @@ fn test_list_neurons() {
         &ListNeurons {
             include_neurons_readable_by_caller: true,
             neuron_ids: vec![],
+            page_number: None,
+            page_size: None,
+            neuron_states: vec![],
+            min_stake_e8s: None,
+            has_hot_keys: None,
         },
         &p1,
     );
@@ -8607,6 +8767,11 @@ fn test_list_neurons() {
         &ListNeurons {
             include_neurons_readable_by_caller: true,
             neuron_ids: vec![200],
+            page_number: None,
+            page_size: None,
+            neuron_states: vec![],
+            min_stake_e8s: None,
+            has_hot_keys: None,
         },
         &p5,
     );
@@ -8633,6 +8798,11 @@ fn test_list_neurons() {
         &ListNeurons {
             include_neurons_readable_by_caller: true,
             neuron_ids: vec![42, 99],
+            page_number: None,
+            page_size: None,
+            neuron_states: vec![],
+            min_stake_e8s: None,
+            has_hot_keys: None,
         },
         &p4,
     );
@@ -8654,6 +8824,140 @@ fn test_list_neurons() {
     );
 }
 
+/// Tests that `list_neurons` splits the (filtered) neurons readable by the
+/// caller into pages.
+#[test]
+fn test_list_neurons_paginated_and_filtered() {
+    let mut driver = fake::FakeDriver::default();
+    let controller = principal(1);
+    // Neurons 1-5 are not dissolving, neurons 6-10 are dissolved. Neurons with
+    // an even ID have a hot key. Neuron N has a stake of N ICP.
+    let proto = GovernanceProto {
+        neurons: (1..=10)
+            .map(|id| {
+                let dissolve_delay_seconds = if id <= 5 { ONE_YEAR_SECONDS } else { 0 };
+                let hot_keys = if id % 2 == 0 {
+                    vec![principal(2)]
+                } else {
+                    vec![]
+                };
+                (
+                    id,
+                    Neuron {
+                        id: Some(NeuronId { id }),
+                        account: driver.random_byte_array().to_vec(),
+                        controller: Some(controller),
+                        hot_keys,
+                        cached_neuron_stake_e8s: id * E8,
+                        dissolve_state: Some(DissolveState::DissolveDelaySeconds(
+                            dissolve_delay_seconds,
+                        )),
+                        ..Default::default()
+                    },
+                )
+            })
+            .collect(),
+        ..Default::default()
+    };
+    let gov = Governance::new(
+        proto,
+        driver.get_fake_env(),
+        driver.get_fake_ledger(),
+        driver.get_fake_cmc(),
+    );
+    let list_neurons = |request: ListNeurons| {
+        let response = gov.list_neurons_by_principal(
+            &ListNeurons {
+                include_neurons_readable_by_caller: true,
+                ..request
+            },
+            &controller,
+        );
+        let mut neuron_ids = response
+            .full_neurons
+            .iter()
+            .map(|neuron| neuron.id.unwrap().id)
+            .collect::<Vec<_>>();
+        neuron_ids.sort();
+        (neuron_ids, response.total_pages_available)
+    };
+
+    assert_eq!(
+        list_neurons(ListNeurons {
+            page_size: Some(4),
+            ..Default::default()
+        }),
+        (vec![1, 2, 3, 4], Some(3))
+    );
+    assert_eq!(
+        list_neurons(ListNeurons {
+            page_number: Some(2),
+            page_size: Some(4),
+            ..Default::default()
+        }),
+        (vec![9, 10], Some(3))
+    );
+    assert_eq!(
+        list_neurons(ListNeurons {
+            page_number: Some(3),
+            page_size: Some(4),
+            ..Default::default()
+        }),
+        (vec![], Some(3))
+    );
+    assert_eq!(
+        list_neurons(ListNeurons {
+            neuron_states: vec![NeuronState::Dissolved as i32],
+            min_stake_e8s: Some(7 * E8),
+            ..Default::default()
+        }),
+        (vec![7, 8, 9, 10], None)
+    );
+    assert_eq!(
+        list_neurons(ListNeurons {
+            neuron_states: vec![NeuronState::NotDissolving as i32],
+            has_hot_keys: Some(true),
+            ..Default::default()
+        }),
+        (vec![2, 4], None)
+    );
+    // Without a page size, all neurons are returned.
+    assert_eq!(
+        list_neurons(ListNeurons::default()),
+        ((1..=10).collect(), None)
+    );
+
+    // Whether a neuron has hot keys is not revealed to other principals.
+    let stranger = principal(3);
+    for has_hot_keys in [true, false] {
+        let response = gov.list_neurons_by_principal(
+            &ListNeurons {
+                neuron_ids: vec![1, 2],
+                has_hot_keys: Some(has_hot_keys),
+                ..Default::default()
+            },
+            &stranger,
+        );
+        assert_eq!(response, ListNeuronsResponse::default());
+    }
+    let response = gov.list_neurons_by_principal(
+        &ListNeurons {
+            neuron_ids: vec![1, 2],
+            ..Default::default()
+        },
+        &stranger,
+    );
+    assert_eq!(
+        response
+            .neuron_infos
+            .keys()
+            .copied()
+            .collect::<HashSet<u64>>(),
+        vec![1, 2].into_iter().collect::<HashSet<u64>>()
+    );
+    assert!(response.full_neurons.is_empty());
+}
+
 #[test]
 fn test_list_proposals_omits_deprecated_topics_from_followees() {
     let controller = principal(1);
@@ -8688,6 +8992,11 @@ fn test_list_proposals_omits_deprecated_topics_from_followees() {
         &ListNeurons {
             neuron_ids: vec![neuron_id.id],
             include_neurons_readable_by_caller: true,
+            page_number: None,
+            page_size: None,
+            neuron_states: vec![],
+            min_stake_e8s: None,
+            has_hot_keys: None,
         },
         &controller,
     );
@@ -8840,7 +9149,12 @@ async fn test_max_number_of_proposals_with_ballots() {
                 summary: "NnsCanisterUpgrade should go through despite the limit".to_string(),
                 action: Some(proposal::Action::ExecuteNnsFunction(ExecuteNnsFunction {
                     nns_function: NnsFunction::NnsCanisterUpgrade as i32,
-                    payload: Vec::new(),
+                    payload: Encode!(&ChangeCanisterRequest::new(
+                        true,
+                        CanisterInstallMode::Upgrade,
+                        GOVERNANCE_CANISTER_ID,
+                    ))
+                    .unwrap(),
                 })),
                 ..Default::default()
             },
@@ -14423,6 +14737,18 @@ impl IcpLedger for StubIcpLedger {
         unimplemented!()
     }
 
+    async fn transfer_funds_with_created_at_time(
+        &self,
+        _amount_e8s: u64,
+        _fee_e8s: u64,
+        _from_subaccount: Option<Subaccount>,
+        _to: AccountIdentifier,
+        _memo: u64,
+        _created_at_time_nanos: u64,
+    ) -> Result<u64, TransferWithCreatedAtTimeError> {
+        unimplemented!()
+    }
+
     async fn total_supply(&self) -> Result<Tokens, NervousSystemError> {
         unimplemented!()
     }
@@ -6,7 +6,10 @@ use futures::channel::{
     oneshot::{self, Sender as OSender},
 };
 use ic_base_types::CanisterId;
-use ic_nervous_system_common::{ledger::IcpLedger, NervousSystemError};
+use ic_nervous_system_common::{
+    ledger::{IcpLedger, TransferWithCreatedAtTimeError},
+    NervousSystemError,
+};
 use ic_nns_governance::{
     governance::{Environment, HeapGrowthPotential},
     pb::v1::{ExecuteNnsFunction, GovernanceError},
@@ -90,6 +93,38 @@ impl IcpLedger for InterleavingTestLedger {
             .await
     }
 
+    async fn transfer_funds_with_created_at_time(
+        &self,
+        amount_e8s: u64,
+        fee_e8s: u64,
+        from_subaccount: Option<Subaccount>,
+        to: AccountIdentifier,
+        memo: u64,
+        created_at_time_nanos: u64,
+    ) -> Result<u64, TransferWithCreatedAtTimeError> {
+        let msg = LedgerMessage::Transfer {
+            amount_e8s,
+            fee_e8s,
+            from_subaccount,
+            to,
+            memo,
+        };
+        atomic::fence(AOrdering::SeqCst);
+        self.notify(msg)
+            .await
+            .map_err(TransferWithCreatedAtTimeError::UnknownOutcome)?;
+        self.underlying
+            .transfer_funds_with_created_at_time(
+                amount_e8s,
+                fee_e8s,
+                from_subaccount,
+                to,
+                memo,
+                created_at_time_nanos,
+            )
+            .await
+    }
+
     async fn total_supply(&self) -> Result<Tokens, NervousSystemError> {
         atomic::fence(AOrdering::SeqCst);
         self.notify(LedgerMessage::TotalSupply).await?;
@@ -134,6 +134,7 @@ fn test_root_restarts_canister_during_upgrade_canister_with_stop_canister_timeou
         compute_allocation: None,
         memory_allocation: None,
         query_allocation: None,
+        chunked_canister_wasm: None,
     };
 
     let _: () = update_with_sender(
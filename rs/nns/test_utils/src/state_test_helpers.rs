@@ -1265,6 +1265,11 @@ pub fn list_neurons(state_machine: &mut StateMachine, sender: PrincipalId) -> Li
             Encode!(&ListNeurons {
                 neuron_ids: vec![],
                 include_neurons_readable_by_caller: true,
+                page_number: None,
+                page_size: None,
+                neuron_states: vec![],
+                min_stake_e8s: None,
+                has_hot_keys: None,
             })
             .unwrap(),
         )
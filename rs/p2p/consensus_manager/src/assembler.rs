@@ -0,0 +1,76 @@
+use std::sync::Arc;
+
+use axum::Router;
+use bytes::Bytes;
+use futures::{future::BoxFuture, FutureExt};
+use ic_base_types::NodeId;
+use ic_protobuf::proxy::{ProtoProxy, ProxyDecodeError};
+use ic_quic_transport::Transport;
+use ic_types::artifact::ArtifactKind;
+
+/// Converts artifacts to and from the representation that is sent to peers in
+/// response to artifact RPCs.
+///
+/// This allows a client to send only parts of an artifact and have the
+/// receiver reconstruct the remaining parts, e.g. from its own pools or by
+/// fetching them from the peer that sent the artifact.
+pub trait ArtifactAssembler<Artifact: ArtifactKind>: Send + Sync {
+    /// Encodes `message` for sending it to a peer.
+    fn disassemble_message(&self, message: Artifact::Message) -> Bytes;
+
+    /// Reconstructs the artifact with the given `id` from `bytes` received
+    /// from `peer`.
+    ///
+    /// The caller is responsible for checking that the returned artifact
+    /// corresponds to `id`.
+    fn assemble_message(
+        &self,
+        id: Artifact::Id,
+        bytes: Bytes,
+        peer: NodeId,
+        transport: Arc<dyn Transport>,
+    ) -> BoxFuture<'static, Result<Artifact::Message, AssembleError>>;
+
+    /// Returns the routes that peers use to fetch the parts of an artifact
+    /// that were not sent along with it.
+    fn router(&self) -> Router {
+        Router::new()
+    }
+}
+
+#[derive(Debug)]
+pub enum AssembleError {
+    /// The received artifact could not be decoded.
+    Decode(ProxyDecodeError),
+    /// Some part of the artifact could not be retrieved.
+    Fetch(String),
+}
+
+impl From<ProxyDecodeError> for AssembleError {
+    fn from(err: ProxyDecodeError) -> Self {
+        Self::Decode(err)
+    }
+}
+
+/// Sends artifacts in full, encoded as their protobuf representation.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ProtoArtifactAssembler;
+
+impl<Artifact: ArtifactKind> ArtifactAssembler<Artifact> for ProtoArtifactAssembler {
+    fn disassemble_message(&self, message: Artifact::Message) -> Bytes {
+        Bytes::from(Artifact::PbMessage::proxy_encode(message))
+    }
+
+    fn assemble_message(
+        &self,
+        _id: Artifact::Id,
+        bytes: Bytes,
+        _peer: NodeId,
+        _transport: Arc<dyn Transport>,
+    ) -> BoxFuture<'static, Result<Artifact::Message, AssembleError>> {
+        futures::future::ready(
+            Artifact::PbMessage::proxy_decode(&bytes).map_err(AssembleError::from),
+        )
+        .boxed()
+    }
+}
@@ -0,0 +1,774 @@
+//! Sending block proposals without the ingress messages they include.
+//!
+//! Every node already has most of the ingress messages of a block proposal in
+//! its ingress pool, so sending them along with the proposal means they are
+//! gossiped twice. Instead, block proposals are sent with an empty ingress
+//! buffer, keeping only the ids (and offsets) of the messages together with
+//! the hashes of their signed bytes. The receiver reassembles the block
+//! proposal from its validated ingress pool and only fetches the messages it
+//! does not have from the peer that sent the proposal. Every message is
+//! checked against its hash, as the id of an ingress message does not cover
+//! its signature.
+
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, RwLock},
+};
+
+use axum::{
+    extract::State,
+    http::{Request, StatusCode},
+    routing::any,
+    Router,
+};
+use bytes::Bytes;
+use futures::{future::BoxFuture, stream, FutureExt, StreamExt, TryStreamExt};
+use ic_base_types::NodeId;
+use ic_crypto_sha2::Sha256;
+use ic_interfaces::{ingress_pool::IngressPool, p2p::consensus::ValidatedPoolReader};
+use ic_logger::{warn, ReplicaLogger};
+use ic_metrics::MetricsRegistry;
+use ic_protobuf::{
+    p2p::v1 as pb,
+    proxy::{ProtoProxy, ProxyDecodeError},
+    types::v1 as pb_types,
+};
+use ic_quic_transport::Transport;
+use ic_types::{
+    artifact::{ArtifactKind, IngressMessageId},
+    artifact_kind::{ConsensusArtifact, IngressArtifact},
+    batch::IngressPayload,
+    consensus::{ConsensusMessage, ConsensusMessageId},
+    messages::SignedIngress,
+};
+
+use crate::{
+    assembler::{ArtifactAssembler, AssembleError},
+    metrics::{
+        BlockAssemblerMetrics, ASSEMBLE_RESULT_ERROR, ASSEMBLE_RESULT_SUCCESS,
+        INGRESS_SOURCE_BLOCK, INGRESS_SOURCE_INGRESS_POOL, INGRESS_SOURCE_NOT_FOUND,
+        INGRESS_SOURCE_PEER,
+    },
+    uri_prefix,
+};
+
+type ConsensusPoolRef = Arc<RwLock<dyn ValidatedPoolReader<ConsensusArtifact> + Send + Sync>>;
+type IngressPoolRef = Arc<RwLock<dyn IngressPool>>;
+
+/// The maximum number of ingress messages of a block proposal that are fetched
+/// from the peer concurrently.
+const MAX_CONCURRENT_INGRESS_FETCHES: usize = 32;
+
+/// [`ArtifactAssembler`] for consensus artifacts that strips the ingress
+/// messages from block proposals.
+#[derive(Clone)]
+pub struct ConsensusArtifactAssembler {
+    log: ReplicaLogger,
+    metrics: BlockAssemblerMetrics,
+    ingress_pool: IngressPoolRef,
+    consensus_pool: ConsensusPoolRef,
+}
+
+impl ConsensusArtifactAssembler {
+    pub fn new(
+        log: ReplicaLogger,
+        metrics_registry: &MetricsRegistry,
+        ingress_pool: IngressPoolRef,
+        consensus_pool: ConsensusPoolRef,
+    ) -> Self {
+        Self {
+            log,
+            metrics: BlockAssemblerMetrics::new(metrics_registry),
+            ingress_pool,
+            consensus_pool,
+        }
+    }
+
+    async fn assemble(
+        self,
+        id: ConsensusMessageId,
+        bytes: Bytes,
+        peer: NodeId,
+        transport: Arc<dyn Transport>,
+    ) -> Result<ConsensusMessage, AssembleError> {
+        let stripped = pb::StrippedConsensusMessage::proxy_decode(&bytes)?;
+        let mut pb_message = pb_types::ConsensusMessage::proxy_decode(&stripped.message)?;
+        let Some(pb_ingress) = stripped_ingress_payload(&mut pb_message) else {
+            return Ok(ConsensusMessage::try_from(pb_message)?);
+        };
+
+        let result = self
+            .fill_ingress_payload(id, pb_ingress, stripped.ingress_hashes, peer, transport)
+            .await;
+        let label = match result {
+            Ok(()) => ASSEMBLE_RESULT_SUCCESS,
+            Err(_) => ASSEMBLE_RESULT_ERROR,
+        };
+        self.metrics
+            .assembled_block_proposals_total
+            .with_label_values(&[label])
+            .inc();
+        result?;
+
+        Ok(ConsensusMessage::try_from(pb_message)?)
+    }
+
+    /// Fills the buffer of a stripped ingress payload with the ingress
+    /// messages it references, looking them up in the local validated ingress
+    /// pool and fetching the remaining ones from `peer`, one request per
+    /// distinct missing message.
+    ///
+    /// Every message must match the hash of its signed bytes in
+    /// `ingress_hashes`.
+    async fn fill_ingress_payload(
+        &self,
+        block_proposal_id: ConsensusMessageId,
+        pb_ingress: &mut pb_types::IngressPayload,
+        ingress_hashes: Vec<Vec<u8>>,
+        peer: NodeId,
+        transport: Arc<dyn Transport>,
+    ) -> Result<(), AssembleError> {
+        let ingress_ids = IngressPayload::try_from(pb_ingress.clone())
+            .map_err(ProxyDecodeError::Other)?
+            .message_ids();
+        if ingress_hashes.len() != ingress_ids.len() {
+            return Err(AssembleError::Decode(ProxyDecodeError::Other(format!(
+                "Block proposal references {} ingress messages but has {} ingress hashes",
+                ingress_ids.len(),
+                ingress_hashes.len()
+            ))));
+        }
+
+        let ingress_pool = self.ingress_pool.clone();
+        let lookup_ids = ingress_ids.clone();
+        let mut messages = tokio::task::spawn_blocking(move || {
+            let pool = ingress_pool.read().unwrap();
+            lookup_ids
+                .iter()
+                .map(|id| get_from_ingress_pool(&*pool, id))
+                .collect::<Vec<_>>()
+        })
+        .await
+        .map_err(|err| AssembleError::Fetch(format!("Ingress pool lookup failed: {}", err)))?;
+
+        // Messages in the pool that were signed differently than the ones in
+        // the block proposal are fetched from the peer as well.
+        for (message, hash) in messages.iter_mut().zip(&ingress_hashes) {
+            if message
+                .as_ref()
+                .is_some_and(|message| &ingress_hash(message) != hash)
+            {
+                *message = None;
+            }
+        }
+
+        // The positions of the missing messages, by id.
+        let mut missing: BTreeMap<&IngressMessageId, Vec<usize>> = BTreeMap::new();
+        for (index, message) in messages.iter().enumerate() {
+            if message.is_none() {
+                missing.entry(&ingress_ids[index]).or_default().push(index);
+            }
+        }
+        let missing_count: usize = missing.values().map(Vec::len).sum();
+        self.metrics
+            .stripped_ingress_messages_total
+            .with_label_values(&[INGRESS_SOURCE_INGRESS_POOL])
+            .inc_by((messages.len() - missing_count) as u64);
+        self.metrics
+            .stripped_ingress_messages_total
+            .with_label_values(&[INGRESS_SOURCE_PEER])
+            .inc_by(missing_count as u64);
+
+        let fetched: Vec<SignedIngress> = stream::iter(missing.iter().map(|(id, indices)| {
+            fetch_ingress(
+                transport.clone(),
+                peer,
+                block_proposal_id.clone(),
+                (*id).clone(),
+                ingress_hashes[indices[0]].clone(),
+            )
+        }))
+        .buffered(MAX_CONCURRENT_INGRESS_FETCHES)
+        .try_collect()
+        .await
+        .map_err(|err| {
+            warn!(
+                self.log,
+                "Failed to fetch ingress messages of block proposal from peer {}: {:?}", peer, err
+            );
+            err
+        })?;
+        for (indices, message) in missing.into_values().zip(fetched) {
+            for index in indices {
+                if ingress_hash(&message) != ingress_hashes[index] {
+                    return Err(AssembleError::Fetch(format!(
+                        "Ingress message {} from peer {} does not match its hash in the block proposal",
+                        ingress_ids[index], peer
+                    )));
+                }
+                messages[index] = Some(message.clone());
+            }
+        }
+
+        let mut buffer = Vec::new();
+        for (id_and_pos, message) in pb_ingress
+            .id_and_pos
+            .iter()
+            .zip(messages.into_iter().flatten())
+        {
+            if id_and_pos.offset != buffer.len() as u64 {
+                return Err(AssembleError::Decode(ProxyDecodeError::Other(format!(
+                    "Unexpected offset {} of ingress message in block proposal",
+                    id_and_pos.offset
+                ))));
+            }
+            buffer.extend_from_slice(message.binary().as_ref());
+        }
+        pb_ingress.buffer = buffer;
+        Ok(())
+    }
+
+    /// Returns the ingress message with the given id and hash, if it is in the
+    /// validated ingress pool or in the payload of the given validated block
+    /// proposal.
+    fn get_ingress(
+        &self,
+        ingress_message_id: &IngressMessageId,
+        ingress_hash: &[u8],
+        block_proposal_id: &ConsensusMessageId,
+    ) -> Option<SignedIngress> {
+        let ingress =
+            get_from_ingress_pool(&*self.ingress_pool.read().unwrap(), ingress_message_id)
+                .filter(|ingress| self::ingress_hash(ingress) == ingress_hash);
+        if ingress.is_some() {
+            self.metrics
+                .ingress_requests_total
+                .with_label_values(&[INGRESS_SOURCE_INGRESS_POOL])
+                .inc();
+            return ingress;
+        }
+
+        let ingress = self.get_from_block(ingress_message_id, block_proposal_id);
+        let label = match ingress {
+            Some(_) => INGRESS_SOURCE_BLOCK,
+            None => INGRESS_SOURCE_NOT_FOUND,
+        };
+        self.metrics
+            .ingress_requests_total
+            .with_label_values(&[label])
+            .inc();
+        ingress
+    }
+
+    fn get_from_block(
+        &self,
+        ingress_message_id: &IngressMessageId,
+        block_proposal_id: &ConsensusMessageId,
+    ) -> Option<SignedIngress> {
+        let block_proposal = self
+            .consensus_pool
+            .read()
+            .unwrap()
+            .get_validated_by_identifier(block_proposal_id)?;
+        let ingress = block_proposal_ingress(&block_proposal)?;
+        let index = ingress
+            .message_ids()
+            .iter()
+            .position(|id| id == ingress_message_id)?;
+        ingress.get(index).ok().map(|(_, message)| message)
+    }
+}
+
+impl ArtifactAssembler<ConsensusArtifact> for ConsensusArtifactAssembler {
+    fn disassemble_message(&self, message: ConsensusMessage) -> Bytes {
+        let ingress_hashes = block_proposal_ingress(&message)
+            .map(|ingress| {
+                (0..ingress.message_count())
+                    .filter_map(|index| ingress.get(index).ok())
+                    .map(|(_, message)| ingress_hash(&message))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let mut pb_message = pb_types::ConsensusMessage::from(message);
+        if let Some(pb_types::consensus_message::Msg::BlockProposal(proposal)) = &mut pb_message.msg
+        {
+            if let Some(pb_ingress) = proposal
+                .value
+                .as_mut()
+                .and_then(|block| block.ingress_payload.as_mut())
+            {
+                pb_ingress.buffer.clear();
+            }
+        }
+        Bytes::from(pb::StrippedConsensusMessage::proxy_encode(
+            pb::StrippedConsensusMessage {
+                message: pb_types::ConsensusMessage::proxy_encode(pb_message),
+                ingress_hashes,
+            },
+        ))
+    }
+
+    fn assemble_message(
+        &self,
+        id: ConsensusMessageId,
+        bytes: Bytes,
+        peer: NodeId,
+        transport: Arc<dyn Transport>,
+    ) -> BoxFuture<'static, Result<ConsensusMessage, AssembleError>> {
+        self.clone().assemble(id, bytes, peer, transport).boxed()
+    }
+
+    fn router(&self) -> Router {
+        Router::new()
+            .route(&ingress_uri(), any(ingress_rpc_handler))
+            .with_state(self.clone())
+    }
+}
+
+fn ingress_uri() -> String {
+    format!("/{}/ingress", uri_prefix::<ConsensusArtifact>())
+}
+
+/// Returns the SHA-256 hash of the signed bytes of the ingress message, by
+/// which stripped block proposals reference it.
+fn ingress_hash(ingress: &SignedIngress) -> Vec<u8> {
+    Sha256::hash(ingress.binary().as_ref()).to_vec()
+}
+
+/// Returns the ingress payload of `message` if it is a block proposal with a
+/// data payload.
+fn block_proposal_ingress(message: &ConsensusMessage) -> Option<&IngressPayload> {
+    match message {
+        ConsensusMessage::BlockProposal(proposal) => {
+            let payload = proposal.as_ref().payload.as_ref();
+            (!payload.is_summary()).then(|| &payload.as_data().batch.ingress)
+        }
+        _ => None,
+    }
+}
+
+/// Returns the ingress payload of `pb_message` if it is a block proposal whose
+/// ingress messages were stripped.
+fn stripped_ingress_payload(
+    pb_message: &mut pb_types::ConsensusMessage,
+) -> Option<&mut pb_types::IngressPayload> {
+    match &mut pb_message.msg {
+        Some(pb_types::consensus_message::Msg::BlockProposal(proposal)) => proposal
+            .value
+            .as_mut()
+            .and_then(|block| block.ingress_payload.as_mut())
+            .filter(|pb_ingress| !pb_ingress.id_and_pos.is_empty() && pb_ingress.buffer.is_empty()),
+        _ => None,
+    }
+}
+
+/// Returns the ingress message with the given id from the validated section of
+/// the ingress pool. Unvalidated messages are not used, as they may not even
+/// be signed correctly.
+fn get_from_ingress_pool(pool: &dyn IngressPool, id: &IngressMessageId) -> Option<SignedIngress> {
+    pool.validated()
+        .get(id)
+        .map(|artifact| artifact.msg.signed_ingress.clone())
+}
+
+/// Fetches the ingress message with the given id and hash of a block proposal
+/// from `peer`.
+async fn fetch_ingress(
+    transport: Arc<dyn Transport>,
+    peer: NodeId,
+    block_proposal_id: ConsensusMessageId,
+    ingress_message_id: IngressMessageId,
+    ingress_hash: Vec<u8>,
+) -> Result<SignedIngress, AssembleError> {
+    let request = pb::GetIngressMessageInBlockRequest {
+        ingress_message_id: <IngressArtifact as ArtifactKind>::PbId::proxy_encode(
+            ingress_message_id.clone(),
+        ),
+        block_proposal_id: <ConsensusArtifact as ArtifactKind>::PbId::proxy_encode(
+            block_proposal_id,
+        ),
+        ingress_hash,
+    };
+    let request = Request::builder()
+        .uri(ingress_uri())
+        .body(Bytes::from(
+            pb::GetIngressMessageInBlockRequest::proxy_encode(request),
+        ))
+        .unwrap();
+
+    let response = transport
+        .rpc(&peer, request)
+        .await
+        .map_err(|err| AssembleError::Fetch(err.to_string()))?;
+    if response.status() != StatusCode::OK {
+        return Err(AssembleError::Fetch(format!(
+            "Peer {} responded with status {} to request for ingress message {}",
+            peer,
+            response.status(),
+            ingress_message_id
+        )));
+    }
+
+    let ingress = <IngressArtifact as ArtifactKind>::PbMessage::proxy_decode(response.body())?;
+    if IngressMessageId::from(&ingress) != ingress_message_id {
+        return Err(AssembleError::Fetch(format!(
+            "Peer {} responded with wrong ingress message for {}",
+            peer, ingress_message_id
+        )));
+    }
+    Ok(ingress)
+}
+
+async fn ingress_rpc_handler(
+    State(assembler): State<ConsensusArtifactAssembler>,
+    payload: Bytes,
+) -> Result<Bytes, StatusCode> {
+    let jh = tokio::task::spawn_blocking(move || {
+        let request = pb::GetIngressMessageInBlockRequest::proxy_decode(&payload)
+            .map_err(|_| StatusCode::BAD_REQUEST)?;
+        let ingress_message_id: IngressMessageId =
+            <IngressArtifact as ArtifactKind>::PbId::proxy_decode(&request.ingress_message_id)
+                .map_err(|_| StatusCode::BAD_REQUEST)?;
+        let block_proposal_id: ConsensusMessageId =
+            <ConsensusArtifact as ArtifactKind>::PbId::proxy_decode(&request.block_proposal_id)
+                .map_err(|_| StatusCode::BAD_REQUEST)?;
+        let ingress = assembler
+            .get_ingress(
+                &ingress_message_id,
+                &request.ingress_hash,
+                &block_proposal_id,
+            )
+            .ok_or(StatusCode::NO_CONTENT)?;
+        Ok::<_, StatusCode>(Bytes::from(
+            <IngressArtifact as ArtifactKind>::PbMessage::proxy_encode(ingress),
+        ))
+    });
+    let bytes = jh.await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)??;
+
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use axum::http::Response;
+    use ic_interfaces::{
+        consensus_pool::ValidatedArtifact,
+        ingress_pool::{
+            IngressPoolObject, PoolSection, UnvalidatedIngressArtifact, ValidatedIngressArtifact,
+        },
+    };
+    use ic_logger::replica_logger::no_op_logger;
+    use ic_p2p_test_utils::mocks::MockTransport;
+    use ic_test_utilities::{
+        consensus::fake::FakeContentSigner, types::messages::SignedIngressBuilder,
+    };
+    use ic_types::{
+        batch::{BatchPayload, ValidationContext},
+        consensus::{
+            dkg::Dealings, Block, BlockPayload, BlockProposal, DataPayload, Payload, Rank,
+        },
+        crypto::{CryptoHash, CryptoHashOf},
+        time::UNIX_EPOCH,
+        Height, RegistryVersion, Time,
+    };
+    use ic_types_test_utils::ids::{NODE_1, NODE_2};
+
+    use super::*;
+
+    struct FakeSection<T>(HashMap<IngressMessageId, T>);
+
+    impl<T> PoolSection<T> for FakeSection<T> {
+        fn get(&self, message_id: &IngressMessageId) -> Option<&T> {
+            self.0.get(message_id)
+        }
+
+        fn get_all_by_expiry_range(
+            &self,
+            _range: std::ops::RangeInclusive<Time>,
+        ) -> Box<dyn Iterator<Item = &T> + '_> {
+            Box::new(self.0.values())
+        }
+
+        fn get_timestamp(&self, _message_id: &IngressMessageId) -> Option<Time> {
+            None
+        }
+
+        fn size(&self) -> usize {
+            self.0.len()
+        }
+    }
+
+    struct FakeIngressPool {
+        validated: FakeSection<ValidatedIngressArtifact>,
+        unvalidated: FakeSection<UnvalidatedIngressArtifact>,
+    }
+
+    impl FakeIngressPool {
+        fn new(validated: Vec<SignedIngress>) -> Self {
+            Self {
+                validated: FakeSection(
+                    validated
+                        .into_iter()
+                        .map(|ingress| {
+                            (
+                                IngressMessageId::from(&ingress),
+                                ValidatedArtifact {
+                                    msg: IngressPoolObject::from(ingress),
+                                    timestamp: UNIX_EPOCH,
+                                },
+                            )
+                        })
+                        .collect(),
+                ),
+                unvalidated: FakeSection(HashMap::new()),
+            }
+        }
+    }
+
+    impl IngressPool for FakeIngressPool {
+        fn validated(&self) -> &dyn PoolSection<ValidatedIngressArtifact> {
+            &self.validated
+        }
+
+        fn unvalidated(&self) -> &dyn PoolSection<UnvalidatedIngressArtifact> {
+            &self.unvalidated
+        }
+    }
+
+    struct FakeConsensusPool(Option<ConsensusMessage>);
+
+    impl ValidatedPoolReader<ConsensusArtifact> for FakeConsensusPool {
+        fn contains(&self, id: &ConsensusMessageId) -> bool {
+            self.get_validated_by_identifier(id).is_some()
+        }
+
+        fn get_validated_by_identifier(&self, id: &ConsensusMessageId) -> Option<ConsensusMessage> {
+            self.0
+                .clone()
+                .filter(|message| &ConsensusMessageId::from(message) == id)
+        }
+
+        fn get_all_validated_by_filter(
+            &self,
+            _filter: &<ConsensusArtifact as ArtifactKind>::Filter,
+        ) -> Box<dyn Iterator<Item = ConsensusMessage> + '_> {
+            Box::new(self.0.clone().into_iter())
+        }
+    }
+
+    fn ingress_messages(count: u64) -> Vec<SignedIngress> {
+        (0..count)
+            .map(|nonce| SignedIngressBuilder::new().nonce(nonce).build())
+            .collect()
+    }
+
+    fn block_proposal(ingress: Vec<SignedIngress>) -> ConsensusMessage {
+        let block = Block::new(
+            CryptoHashOf::from(CryptoHash(vec![])),
+            Payload::new(
+                ic_types::crypto::crypto_hash,
+                BlockPayload::Data(DataPayload {
+                    batch: BatchPayload {
+                        ingress: IngressPayload::from(ingress),
+                        ..BatchPayload::default()
+                    },
+                    dealings: Dealings::new_empty(Height::from(0)),
+                    ecdsa: None,
+                }),
+            ),
+            Height::from(1),
+            Rank(0),
+            ValidationContext {
+                registry_version: RegistryVersion::from(1),
+                certified_height: Height::from(0),
+                time: UNIX_EPOCH,
+            },
+        );
+        ConsensusMessage::BlockProposal(BlockProposal::fake(block, NODE_1))
+    }
+
+    fn assembler(
+        ingress_pool: Vec<SignedIngress>,
+        consensus_pool: Option<ConsensusMessage>,
+    ) -> ConsensusArtifactAssembler {
+        ConsensusArtifactAssembler::new(
+            no_op_logger(),
+            &MetricsRegistry::default(),
+            Arc::new(RwLock::new(FakeIngressPool::new(ingress_pool))),
+            Arc::new(RwLock::new(FakeConsensusPool(consensus_pool))),
+        )
+    }
+
+    /// Check that block proposals are sent without their ingress messages and
+    /// reassembled from the ingress pool of the receiver.
+    #[test]
+    fn block_proposal_is_assembled_from_ingress_pool() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let ingress = ingress_messages(3);
+        let proposal = block_proposal(ingress.clone());
+        let id = ConsensusMessageId::from(&proposal);
+
+        let sender = assembler(vec![], None);
+        let bytes = sender.disassemble_message(proposal.clone());
+        let stripped = pb::StrippedConsensusMessage::proxy_decode(&bytes).unwrap();
+        assert!(stripped_ingress_payload(
+            &mut pb_types::ConsensusMessage::proxy_decode(&stripped.message).unwrap()
+        )
+        .is_some());
+        assert_eq!(stripped.ingress_hashes.len(), ingress.len());
+
+        let receiver = assembler(ingress, None);
+        let mut mock_transport = MockTransport::new();
+        mock_transport.expect_rpc().never();
+        let assembled = rt
+            .block_on(receiver.assemble_message(id, bytes, NODE_2, Arc::new(mock_transport)))
+            .unwrap();
+        assert_eq!(assembled, proposal);
+    }
+
+    /// Check that ingress messages missing from the ingress pool are fetched
+    /// from the peer that sent the block proposal.
+    #[test]
+    fn missing_ingress_is_fetched_from_peer() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let ingress = ingress_messages(3);
+        let proposal = block_proposal(ingress.clone());
+        let id = ConsensusMessageId::from(&proposal);
+
+        let sender = assembler(vec![], Some(proposal.clone()));
+        let bytes = sender.disassemble_message(proposal.clone());
+
+        let receiver = assembler(vec![ingress[0].clone(), ingress[2].clone()], None);
+        let mut mock_transport = MockTransport::new();
+        mock_transport
+            .expect_rpc()
+            .once()
+            .returning(move |peer, request| {
+                assert_eq!(*peer, NODE_2);
+                assert_eq!(request.uri(), ingress_uri().as_str());
+                let request =
+                    pb::GetIngressMessageInBlockRequest::proxy_decode(request.body()).unwrap();
+                let ingress_message_id = <IngressArtifact as ArtifactKind>::PbId::proxy_decode(
+                    &request.ingress_message_id,
+                )
+                .unwrap();
+                let block_proposal_id = <ConsensusArtifact as ArtifactKind>::PbId::proxy_decode(
+                    &request.block_proposal_id,
+                )
+                .unwrap();
+                let ingress = sender
+                    .get_ingress(
+                        &ingress_message_id,
+                        &request.ingress_hash,
+                        &block_proposal_id,
+                    )
+                    .unwrap();
+                Ok(Response::builder()
+                    .body(Bytes::from(
+                        <IngressArtifact as ArtifactKind>::PbMessage::proxy_encode(ingress),
+                    ))
+                    .unwrap())
+            });
+        let assembled = rt
+            .block_on(receiver.assemble_message(id, bytes, NODE_2, Arc::new(mock_transport)))
+            .unwrap();
+        assert_eq!(assembled, proposal);
+    }
+
+    /// Check that an ingress message that does not match the requested id is
+    /// rejected.
+    #[test]
+    fn wrong_ingress_from_peer_is_rejected() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let ingress = ingress_messages(2);
+        let proposal = block_proposal(ingress.clone());
+        let id = ConsensusMessageId::from(&proposal);
+        let bytes = assembler(vec![], None).disassemble_message(proposal);
+
+        let receiver = assembler(vec![ingress[1].clone()], None);
+        let mut mock_transport = MockTransport::new();
+        mock_transport.expect_rpc().once().returning(move |_, _| {
+            Ok(Response::builder()
+                .body(Bytes::from(
+                    <IngressArtifact as ArtifactKind>::PbMessage::proxy_encode(ingress[1].clone()),
+                ))
+                .unwrap())
+        });
+        assert!(matches!(
+            rt.block_on(receiver.assemble_message(id, bytes, NODE_2, Arc::new(mock_transport))),
+            Err(AssembleError::Fetch(_))
+        ));
+    }
+
+    /// Check that an ingress message that does not match its hash in the block
+    /// proposal is rejected, even if it is in the ingress pool.
+    #[test]
+    fn ingress_not_matching_hash_is_rejected() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let ingress = ingress_messages(2);
+        let proposal = block_proposal(ingress.clone());
+        let id = ConsensusMessageId::from(&proposal);
+        let bytes = assembler(vec![], None).disassemble_message(proposal);
+        let mut stripped = pb::StrippedConsensusMessage::proxy_decode(&bytes).unwrap();
+        stripped.ingress_hashes[0] = vec![0; 32];
+        let bytes = Bytes::from(pb::StrippedConsensusMessage::proxy_encode(stripped));
+
+        // The message in the pool does not match the hash, so it is fetched
+        // from the peer, which does not have a matching one either.
+        let receiver = assembler(ingress.clone(), None);
+        let mut mock_transport = MockTransport::new();
+        mock_transport.expect_rpc().once().returning(move |_, _| {
+            Ok(Response::builder()
+                .body(Bytes::from(
+                    <IngressArtifact as ArtifactKind>::PbMessage::proxy_encode(ingress[0].clone()),
+                ))
+                .unwrap())
+        });
+        assert!(matches!(
+            rt.block_on(receiver.assemble_message(id, bytes, NODE_2, Arc::new(mock_transport))),
+            Err(AssembleError::Fetch(_))
+        ));
+    }
+
+    /// Check that an ingress message included more than once is fetched with a
+    /// single request.
+    #[test]
+    fn duplicate_missing_ingress_is_fetched_once() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let ingress = ingress_messages(1);
+        let proposal = block_proposal(vec![ingress[0].clone(), ingress[0].clone()]);
+        let id = ConsensusMessageId::from(&proposal);
+        let bytes = assembler(vec![], None).disassemble_message(proposal.clone());
+
+        let receiver = assembler(vec![], None);
+        let mut mock_transport = MockTransport::new();
+        mock_transport.expect_rpc().once().returning(move |_, _| {
+            Ok(Response::builder()
+                .body(Bytes::from(
+                    <IngressArtifact as ArtifactKind>::PbMessage::proxy_encode(ingress[0].clone()),
+                ))
+                .unwrap())
+        });
+        let assembled = rt
+            .block_on(receiver.assemble_message(id, bytes, NODE_2, Arc::new(mock_transport)))
+            .unwrap();
+        assert_eq!(assembled, proposal);
+    }
+
+    /// Check that other consensus messages are sent unchanged.
+    #[test]
+    fn block_proposal_without_ingress_is_not_stripped() {
+        let proposal = block_proposal(vec![]);
+        let bytes = assembler(vec![], None).disassemble_message(proposal.clone());
+        assert_eq!(
+            pb::StrippedConsensusMessage::proxy_decode(&bytes).unwrap(),
+            pb::StrippedConsensusMessage {
+                message: pb_types::ConsensusMessage::proxy_encode(proposal),
+                ingress_hashes: vec![],
+            }
+        );
+    }
+}
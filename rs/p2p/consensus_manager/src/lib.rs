@@ -1,6 +1,7 @@
 use std::sync::{Arc, RwLock};
 
 use crate::{
+    assembler::{ArtifactAssembler, ProtoArtifactAssembler},
     metrics::ConsensusManagerMetrics,
     receiver::{build_axum_router, ConsensusManagerReceiver},
     sender::ConsensusManagerSender,
@@ -29,10 +30,15 @@ use tokio::{
 };
 use tokio_util::sync::CancellationToken;
 
+mod assembler;
+mod block_assembler;
 mod metrics;
 mod receiver;
 mod sender;
 
+pub use assembler::{ArtifactAssembler, AssembleError, ProtoArtifactAssembler};
+pub use block_assembler::ConsensusArtifactAssembler;
+
 type StartConsensusManagerFn = Box<dyn FnOnce(Arc<dyn Transport>, watch::Receiver<SubnetTopology>)>;
 
 pub struct ConsensusManagerBuilder {
@@ -66,7 +72,31 @@ impl ConsensusManagerBuilder {
         Pool: 'static + Send + Sync + ValidatedPoolReader<Artifact>,
         Artifact: ArtifactKind,
     {
-        let (router, adverts_from_peers_rx) = build_axum_router(self.log.clone(), raw_pool.clone());
+        self.add_client_with_assembler(
+            adverts_to_send,
+            raw_pool,
+            priority_fn_producer,
+            sender,
+            Arc::new(ProtoArtifactAssembler),
+        )
+    }
+
+    /// Same as [`Self::add_client`], but artifacts requested by peers are sent
+    /// and received through `assembler`, which may strip parts of them that
+    /// peers can reconstruct on their own.
+    pub fn add_client_with_assembler<Artifact, Pool>(
+        &mut self,
+        adverts_to_send: Receiver<ArtifactProcessorEvent<Artifact>>,
+        raw_pool: Arc<RwLock<Pool>>,
+        priority_fn_producer: Arc<dyn PriorityFnAndFilterProducer<Artifact, Pool>>,
+        sender: UnboundedSender<UnvalidatedArtifactMutation<Artifact>>,
+        assembler: Arc<dyn ArtifactAssembler<Artifact>>,
+    ) where
+        Pool: 'static + Send + Sync + ValidatedPoolReader<Artifact>,
+        Artifact: ArtifactKind,
+    {
+        let (router, adverts_from_peers_rx) =
+            build_axum_router(self.log.clone(), raw_pool.clone(), assembler.clone());
 
         let log = self.log.clone();
         let rt_handle = self.rt_handle.clone();
@@ -83,6 +113,7 @@ impl ConsensusManagerBuilder {
                 raw_pool,
                 priority_fn_producer,
                 sender,
+                assembler,
                 transport,
                 topology_watcher,
                 cancellation_token,
@@ -121,6 +152,7 @@ fn start_consensus_manager<Artifact, Pool>(
     raw_pool: Arc<RwLock<Pool>>,
     priority_fn_producer: Arc<dyn PriorityFnAndFilterProducer<Artifact, Pool>>,
     sender: UnboundedSender<UnvalidatedArtifactMutation<Artifact>>,
+    assembler: Arc<dyn ArtifactAssembler<Artifact>>,
     transport: Arc<dyn Transport>,
     topology_watcher: watch::Receiver<SubnetTopology>,
     cancellation_token: CancellationToken,
@@ -148,6 +180,7 @@ fn start_consensus_manager<Artifact, Pool>(
         raw_pool,
         priority_fn_producer,
         sender,
+        assembler,
         transport,
         topology_watcher,
     );
@@ -9,6 +9,14 @@ pub(crate) const DOWNLOAD_TASK_RESULT_LABEL: &str = "result";
 pub(crate) const DOWNLOAD_TASK_RESULT_COMPLETED: &str = "completed";
 pub(crate) const DOWNLOAD_TASK_RESULT_DROP: &str = "drop";
 pub(crate) const DOWNLOAD_TASK_RESULT_ALL_PEERS_DELETED: &str = "all_peers_removed";
+pub(crate) const INGRESS_SOURCE_LABEL: &str = "source";
+pub(crate) const INGRESS_SOURCE_INGRESS_POOL: &str = "ingress_pool";
+pub(crate) const INGRESS_SOURCE_BLOCK: &str = "block";
+pub(crate) const INGRESS_SOURCE_PEER: &str = "peer";
+pub(crate) const INGRESS_SOURCE_NOT_FOUND: &str = "not_found";
+pub(crate) const ASSEMBLE_RESULT_LABEL: &str = "result";
+pub(crate) const ASSEMBLE_RESULT_SUCCESS: &str = "success";
+pub(crate) const ASSEMBLE_RESULT_ERROR: &str = "error";
 
 #[derive(Clone)]
 pub(crate) struct ConsensusManagerMetrics {
@@ -269,3 +277,50 @@ impl ConsensusManagerMetrics {
         }
     }
 }
+
+#[derive(Clone)]
+pub(crate) struct BlockAssemblerMetrics {
+    // Receive side
+    pub assembled_block_proposals_total: IntCounterVec,
+    pub stripped_ingress_messages_total: IntCounterVec,
+
+    // Send side
+    pub ingress_requests_total: IntCounterVec,
+}
+
+impl BlockAssemblerMetrics {
+    pub fn new(metrics_registry: &MetricsRegistry) -> Self {
+        Self {
+            assembled_block_proposals_total: metrics_registry.register(
+                IntCounterVec::new(
+                    opts!(
+                        "ic_consensus_manager_assembled_block_proposals_total",
+                        "Block proposals with stripped ingress messages that were received, by result of the reassembly.",
+                    ),
+                    &[ASSEMBLE_RESULT_LABEL],
+                )
+                .unwrap(),
+            ),
+            stripped_ingress_messages_total: metrics_registry.register(
+                IntCounterVec::new(
+                    opts!(
+                        "ic_consensus_manager_stripped_ingress_messages_total",
+                        "Stripped ingress messages of received block proposals, by where they were found.",
+                    ),
+                    &[INGRESS_SOURCE_LABEL],
+                )
+                .unwrap(),
+            ),
+            ingress_requests_total: metrics_registry.register(
+                IntCounterVec::new(
+                    opts!(
+                        "ic_consensus_manager_ingress_requests_total",
+                        "Requests from peers for ingress messages of block proposals, by where they were found.",
+                    ),
+                    &[INGRESS_SOURCE_LABEL],
+                )
+                .unwrap(),
+            ),
+        }
+    }
+}
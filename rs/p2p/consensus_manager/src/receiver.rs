@@ -5,6 +5,7 @@ use std::{
 };
 
 use crate::{
+    assembler::ArtifactAssembler,
     metrics::{
         ConsensusManagerMetrics, DOWNLOAD_TASK_RESULT_ALL_PEERS_DELETED,
         DOWNLOAD_TASK_RESULT_COMPLETED, DOWNLOAD_TASK_RESULT_DROP,
@@ -42,12 +43,14 @@ const MAX_ARTIFACT_RPC_TIMEOUT: Duration = Duration::from_secs(120);
 const PRIORITY_FUNCTION_UPDATE_INTERVAL: Duration = Duration::from_secs(3);
 
 type ValidatedPoolReaderRef<T> = Arc<RwLock<dyn ValidatedPoolReader<T> + Send + Sync>>;
+type ArtifactAssemblerRef<T> = Arc<dyn ArtifactAssembler<T>>;
 type ReceivedAdvertSender<A> = Sender<(SlotUpdate<A>, NodeId, ConnId)>;
 
 #[allow(unused)]
 pub fn build_axum_router<Artifact: ArtifactKind>(
     log: ReplicaLogger,
     pool: ValidatedPoolReaderRef<Artifact>,
+    assembler: ArtifactAssemblerRef<Artifact>,
 ) -> (Router, Receiver<(SlotUpdate<Artifact>, NodeId, ConnId)>) {
     let (update_tx, update_rx) = tokio::sync::mpsc::channel(100);
     let router = Router::new()
@@ -55,18 +58,22 @@ pub fn build_axum_router<Artifact: ArtifactKind>(
             &format!("/{}/rpc", uri_prefix::<Artifact>()),
             any(rpc_handler),
         )
-        .with_state(pool)
+        .with_state((pool, assembler.clone()))
         .route(
             &format!("/{}/update", uri_prefix::<Artifact>()),
             any(update_handler),
         )
-        .with_state((log, update_tx));
+        .with_state((log, update_tx))
+        .merge(assembler.router());
 
     (router, update_rx)
 }
 
 async fn rpc_handler<Artifact: ArtifactKind>(
-    State(pool): State<ValidatedPoolReaderRef<Artifact>>,
+    State((pool, assembler)): State<(
+        ValidatedPoolReaderRef<Artifact>,
+        ArtifactAssemblerRef<Artifact>,
+    )>,
     payload: Bytes,
 ) -> Result<Bytes, StatusCode> {
     let jh = tokio::task::spawn_blocking(move || {
@@ -77,7 +84,7 @@ async fn rpc_handler<Artifact: ArtifactKind>(
             .unwrap()
             .get_validated_by_identifier(&id)
             .ok_or(StatusCode::NO_CONTENT)?;
-        Ok::<_, StatusCode>(Bytes::from(Artifact::PbMessage::proxy_encode(artifact)))
+        Ok::<_, StatusCode>(assembler.disassemble_message(artifact))
     });
     let bytes = jh.await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)??;
 
@@ -166,6 +173,7 @@ pub(crate) struct ConsensusManagerReceiver<Artifact: ArtifactKind, Pool, Receive
     priority_fn_producer: Arc<dyn PriorityFnAndFilterProducer<Artifact, Pool>>,
     current_priority_fn: watch::Sender<PriorityFn<Artifact::Id, Artifact::Attribute>>,
     sender: UnboundedSender<UnvalidatedArtifactMutation<Artifact>>,
+    assembler: ArtifactAssemblerRef<Artifact>,
 
     slot_table: HashMap<NodeId, HashMap<SlotNumber, SlotEntry<Artifact::Id>>>,
     active_downloads: HashMap<Artifact::Id, watch::Sender<PeerCounter>>,
@@ -195,6 +203,7 @@ where
         raw_pool: Arc<RwLock<Pool>>,
         priority_fn_producer: Arc<dyn PriorityFnAndFilterProducer<Artifact, Pool>>,
         sender: UnboundedSender<UnvalidatedArtifactMutation<Artifact>>,
+        assembler: ArtifactAssemblerRef<Artifact>,
         transport: Arc<dyn Transport>,
         topology_watcher: watch::Receiver<SubnetTopology>,
     ) {
@@ -211,6 +220,7 @@ where
             priority_fn_producer,
             current_priority_fn,
             sender,
+            assembler,
             transport,
             active_downloads: HashMap::new(),
             slot_table: HashMap::new(),
@@ -307,6 +317,7 @@ where
                     peer_rx,
                     self.current_priority_fn.subscribe(),
                     self.sender.clone(),
+                    self.assembler.clone(),
                     self.transport.clone(),
                     self.metrics.clone(),
                 ),
@@ -404,6 +415,7 @@ where
                             rx,
                             self.current_priority_fn.subscribe(),
                             self.sender.clone(),
+                            self.assembler.clone(),
                             self.transport.clone(),
                             self.metrics.clone(),
                         ),
@@ -492,6 +504,7 @@ where
         mut artifact: Option<(Artifact::Message, NodeId)>,
         mut peer_rx: &mut watch::Receiver<PeerCounter>,
         mut priority_fn_watcher: watch::Receiver<PriorityFn<Artifact::Id, Artifact::Attribute>>,
+        assembler: ArtifactAssemblerRef<Artifact>,
         transport: Arc<dyn Transport>,
         metrics: ConsensusManagerMetrics,
     ) -> Result<(Artifact::Message, NodeId), DownloadStopped> {
@@ -546,7 +559,17 @@ where
                     match timeout_at(next_request_at, transport.rpc(&peer, request)).await {
                         Ok(Ok(response)) if response.status() == StatusCode::OK => {
                             let body = response.into_body();
-                            if let Ok(message) = Artifact::PbMessage::proxy_decode(&body) {
+                            let message = timeout_at(
+                                next_request_at,
+                                assembler.assemble_message(
+                                    id.clone(),
+                                    body,
+                                    peer,
+                                    transport.clone(),
+                                ),
+                            )
+                            .await;
+                            if let Ok(Ok(message)) = message {
                                 if &Artifact::message_to_advert(&message).id == id {
                                     result = Ok((message, peer));
                                     break;
@@ -596,6 +619,7 @@ where
         mut peer_rx: watch::Receiver<PeerCounter>,
         mut priority_fn_watcher: watch::Receiver<PriorityFn<Artifact::Id, Artifact::Attribute>>,
         sender: UnboundedSender<UnvalidatedArtifactMutation<Artifact>>,
+        assembler: ArtifactAssemblerRef<Artifact>,
         transport: Arc<dyn Transport>,
         metrics: ConsensusManagerMetrics,
     ) -> (
@@ -611,6 +635,7 @@ where
             artifact,
             &mut peer_rx,
             priority_fn_watcher,
+            assembler,
             transport,
             metrics.clone(),
         )
@@ -710,6 +735,7 @@ impl<T> SlotEntry<T> {
 mod tests {
     use std::{backtrace::Backtrace, sync::Mutex};
 
+    use crate::assembler::ProtoArtifactAssembler;
     use axum::http::Response;
     use ic_logger::replica_logger::no_op_logger;
     use ic_metrics::MetricsRegistry;
@@ -758,6 +784,7 @@ mod tests {
             priority_fn_producer,
             current_priority_fn,
             sender,
+            assembler: Arc::new(ProtoArtifactAssembler),
             transport,
             active_downloads: HashMap::new(),
             slot_table: HashMap::new(),
@@ -1879,6 +1906,7 @@ mod tests {
                     None,
                     &mut peer_rx,
                     pfn_rx,
+                    Arc::new(ProtoArtifactAssembler),
                     Arc::new(mock_transport),
                     ConsensusManagerMetrics::new::<U64Artifact>(&MetricsRegistry::default()),
                 )
@@ -19,6 +19,13 @@
 ///
 /// The steps described above are performed by the router.
 ///
+/// Network events can be injected to reproduce faults. Sets of nodes can be
+/// isolated from the rest of the network, the links between pairs of nodes can
+/// drop or delay a fraction of the messages and link latencies and capacities
+/// can be changed. Events
+/// are either applied immediately with `TransportRouter::apply` or scripted
+/// over (simulated) time with a `NetworkSchedule`.
+///
 ///
 /// ┌──────┐                           ┌──────┐
 /// │ Node ├───┐                  ┌────┤ Node │
@@ -42,9 +49,10 @@ use axum::{
 use bytes::Bytes;
 use ic_quic_transport::{ConnId, SendError, Transport};
 use ic_types::NodeId;
+use rand::{rngs::StdRng, Rng, SeedableRng};
 use std::{
-    collections::HashMap,
-    sync::{Arc, RwLock},
+    collections::{BTreeSet, HashMap},
+    sync::{Arc, Mutex, RwLock},
     time::Duration,
 };
 use tokio::{
@@ -53,6 +61,8 @@ use tokio::{
         mpsc::{unbounded_channel, UnboundedSender},
         oneshot, Semaphore,
     },
+    task::JoinHandle,
+    time::Instant,
 };
 use tower::ServiceExt;
 
@@ -77,6 +87,161 @@ impl PeerHandle {
             down_capacity: Arc::new(Semaphore::new(capacity)),
         }
     }
+
+    /// Replaces the capacities of the link. Messages that already hold
+    /// capacity on the old link are not affected.
+    fn set_capacity(&mut self, capacity: usize) {
+        self.up_capacity = Arc::new(Semaphore::new(capacity));
+        self.down_capacity = Arc::new(Semaphore::new(capacity));
+    }
+}
+
+/// Faults injected on the link between two nodes. Both apply to messages in
+/// either direction.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct LinkFaults {
+    /// Fraction of the messages that are dropped.
+    drop_rate: f64,
+    /// Fraction of the messages that are delayed by `delay`.
+    delay_rate: f64,
+    /// Time added to the link latency for delayed messages.
+    delay: Duration,
+}
+
+impl LinkFaults {
+    /// Panics if `drop_rate` or `delay_rate` is not in `[0.0, 1.0]`.
+    pub fn new(drop_rate: f64, delay_rate: f64, delay: Duration) -> Self {
+        assert!(
+            (0.0..=1.0).contains(&drop_rate),
+            "drop rate {} is not in [0.0, 1.0]",
+            drop_rate
+        );
+        assert!(
+            (0.0..=1.0).contains(&delay_rate),
+            "delay rate {} is not in [0.0, 1.0]",
+            delay_rate
+        );
+        Self {
+            drop_rate,
+            delay_rate,
+            delay,
+        }
+    }
+}
+
+/// An event that changes the conditions of the simulated network.
+#[derive(Clone, Debug)]
+pub enum NetworkEvent {
+    /// Drops all messages between the given nodes and the rest of the
+    /// network. Messages among the given nodes are still delivered.
+    Isolate(BTreeSet<NodeId>),
+    /// Sets the faults of the link between the given nodes.
+    SetLinkFaults(NodeId, NodeId, LinkFaults),
+    /// Sets the latency of the link of the given node.
+    SetLatency(NodeId, Duration),
+    /// Sets the capacity of the link of the given node.
+    SetCapacity(NodeId, usize),
+    /// Removes all partitions and link faults. Latencies and capacities are
+    /// left unchanged.
+    Heal,
+}
+
+/// Network events to apply, each at an offset from the start of the schedule.
+///
+/// Example:
+///
+/// ```ignore
+/// let schedule = NetworkSchedule::new()
+///     .at(Duration::from_secs(5), NetworkEvent::Isolate([NODE_1].into()))
+///     .at(Duration::from_secs(20), NetworkEvent::Heal);
+/// transport_router.run_schedule(schedule);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct NetworkSchedule {
+    events: Vec<(Duration, NetworkEvent)>,
+}
+
+impl NetworkSchedule {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `event` to be applied `offset` after the start of the schedule.
+    pub fn at(mut self, offset: Duration, event: NetworkEvent) -> Self {
+        self.events.push((offset, event));
+        self
+    }
+}
+
+/// State of the simulated network shared by the router and all peers.
+#[derive(Default)]
+struct Network {
+    peers: HashMap<NodeId, PeerHandle>,
+    /// Sets of nodes that are cut off from the rest of the network.
+    partitions: Vec<BTreeSet<NodeId>>,
+    /// Faults of the links between pairs of nodes, keyed by `link_key`.
+    link_faults: HashMap<(NodeId, NodeId), LinkFaults>,
+}
+
+impl Network {
+    /// Returns true if no partition separates `a` and `b`.
+    fn reachable(&self, a: &NodeId, b: &NodeId) -> bool {
+        self.partitions
+            .iter()
+            .all(|partition| partition.contains(a) == partition.contains(b))
+    }
+
+    /// Returns the faults of the link between `a` and `b`.
+    fn link_faults(&self, a: &NodeId, b: &NodeId) -> LinkFaults {
+        self.link_faults
+            .get(&link_key(a, b))
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+/// Links are undirected, so both orders of a pair map to the same key.
+fn link_key(a: &NodeId, b: &NodeId) -> (NodeId, NodeId) {
+    if a <= b {
+        (*a, *b)
+    } else {
+        (*b, *a)
+    }
+}
+
+/// The links a message traverses and the faults drawn for it.
+struct Route {
+    origin: PeerHandle,
+    dest: PeerHandle,
+    origin_delay: Duration,
+    dest_delay: Duration,
+    dropped: bool,
+}
+
+impl Route {
+    /// Reserves capacities for a message of `size` bytes and waits for the
+    /// required latency. Returns false if the message is lost on the way.
+    async fn transmit(&self, size: usize) -> bool {
+        let permit = self
+            .origin
+            .up_capacity
+            .acquire_many(size as u32)
+            .await
+            .unwrap();
+        tokio::time::sleep(self.origin_delay).await;
+        drop(permit);
+        if self.dropped {
+            return false;
+        }
+        let _permit = self
+            .dest
+            .down_capacity
+            .acquire_many(size as u32)
+            .await
+            .unwrap();
+        tokio::time::sleep(self.dest_delay).await;
+        true
+    }
 }
 
 impl Default for TransportRouter {
@@ -87,28 +252,41 @@ impl Default for TransportRouter {
 
 #[derive(Clone)]
 pub struct TransportRouter {
-    peers: Arc<RwLock<HashMap<NodeId, PeerHandle>>>,
+    network: Arc<RwLock<Network>>,
+    rng: Arc<Mutex<StdRng>>,
     router_req_tx: UnboundedSender<(Request<Bytes>, NodeId, oneshot::Sender<Response<Bytes>>)>,
     router_resp_tx: UnboundedSender<(Response<Bytes>, NodeId, oneshot::Sender<Response<Bytes>>)>,
 }
 
 impl TransportRouter {
     pub fn new() -> Self {
+        Self::with_rng(StdRng::from_entropy())
+    }
+
+    /// Creates a router whose link faults are drawn from an RNG seeded with
+    /// `seed`, so that faulty runs can be reproduced.
+    pub fn with_seed(seed: u64) -> Self {
+        Self::with_rng(StdRng::seed_from_u64(seed))
+    }
+
+    fn with_rng(rng: StdRng) -> Self {
         let (router_req_tx, mut router_req_rx) =
             unbounded_channel::<(Request<Bytes>, NodeId, oneshot::Sender<Response<Bytes>>)>();
         let (router_resp_tx, mut router_resp_rx) =
             unbounded_channel::<(Response<Bytes>, NodeId, oneshot::Sender<Response<Bytes>>)>();
-        let peers = Arc::new(RwLock::new(HashMap::new()));
-        let peers_c = peers.clone();
+        let network = Arc::new(RwLock::new(Network::default()));
+        let rng = Arc::new(Mutex::new(rng));
+        let network_c = network.clone();
+        let rng_c = rng.clone();
         // Spawn request router for all requests.
         tokio::spawn(async move {
             loop {
                 select! {
                     Some((req,dest,resp)) = router_req_rx.recv() => {
-                        Self::handle_incoming_request(peers_c.clone(), req, dest, resp);
+                        Self::handle_incoming_request(&network_c, &rng_c, req, dest, resp);
                     }
                     Some((req,dest,resp)) = router_resp_rx.recv() => {
-                        Self::handle_incoming_response(peers_c.clone(), req, dest, resp);
+                        Self::handle_incoming_response(&network_c, &rng_c, req, dest, resp);
                     }
                     else => break,
                 }
@@ -116,7 +294,8 @@ impl TransportRouter {
         });
 
         Self {
-            peers,
+            network,
+            rng,
             router_req_tx,
             router_resp_tx,
         }
@@ -135,9 +314,10 @@ impl TransportRouter {
         // capacity and processing rate >> ingestion rate.
         let (rpc_tx, mut rpc_rx) =
             unbounded_channel::<(Request<Bytes>, oneshot::Sender<Response<Bytes>>)>();
-        self.peers
+        self.network
             .write()
             .unwrap()
+            .peers
             .insert(node_id, PeerHandle::new(rpc_tx, latency, capacity));
         let this_node_id = node_id;
         let router_resp_tx = self.router_resp_tx.clone();
@@ -171,77 +351,125 @@ impl TransportRouter {
         }
     }
 
+    /// Applies `event` to the network. Messages that are already in flight
+    /// are not affected.
+    pub fn apply(&self, event: NetworkEvent) {
+        let mut network = self.network.write().unwrap();
+        match event {
+            NetworkEvent::Isolate(nodes) => network.partitions.push(nodes),
+            NetworkEvent::SetLinkFaults(a, b, faults) => {
+                network.link_faults.insert(link_key(&a, &b), faults);
+            }
+            NetworkEvent::SetLatency(node_id, latency) => {
+                if let Some(peer) = network.peers.get_mut(&node_id) {
+                    peer.latency = latency;
+                }
+            }
+            NetworkEvent::SetCapacity(node_id, capacity) => {
+                if let Some(peer) = network.peers.get_mut(&node_id) {
+                    peer.set_capacity(capacity);
+                }
+            }
+            NetworkEvent::Heal => {
+                network.partitions.clear();
+                network.link_faults.clear();
+            }
+        }
+    }
+
+    /// Spawns a task that applies the events of `schedule` at their offsets
+    /// from now. Uses tokio time, so it follows simulated time in paused
+    /// runtimes and turmoil simulations.
+    pub fn run_schedule(&self, schedule: NetworkSchedule) -> JoinHandle<()> {
+        let this = self.clone();
+        let mut events = schedule.events;
+        events.sort_by_key(|(offset, _)| *offset);
+        let start = Instant::now();
+        tokio::spawn(async move {
+            for (offset, event) in events {
+                tokio::time::sleep_until(start + offset).await;
+                this.apply(event);
+            }
+        })
+    }
+
+    /// Looks up the links between `origin` and `dest` and draws the faults
+    /// for a message sent over them. Returns `None` if either node is unknown.
+    fn route(
+        network: &RwLock<Network>,
+        rng: &Mutex<StdRng>,
+        origin: &NodeId,
+        dest: &NodeId,
+    ) -> Option<Route> {
+        let network = network.read().unwrap();
+        let origin_ph = network.peers.get(origin)?.clone();
+        let dest_ph = network.peers.get(dest)?.clone();
+        let reachable = network.reachable(origin, dest);
+        let faults = network.link_faults(origin, dest);
+        drop(network);
+
+        let mut rng = rng.lock().unwrap();
+        let origin_delay = origin_ph.latency;
+        let mut dest_delay = dest_ph.latency;
+        if rng.gen_bool(faults.delay_rate) {
+            dest_delay += faults.delay;
+        }
+        let dropped = !reachable || rng.gen_bool(faults.drop_rate);
+
+        Some(Route {
+            origin: origin_ph,
+            dest: dest_ph,
+            origin_delay,
+            dest_delay,
+            dropped,
+        })
+    }
+
     /// Reserves capacities for the request and waits for the required latency.
-    /// After using the requested resources the request is delivered to the peer.
+    /// After using the requested resources the request is delivered to the peer,
+    /// unless it is lost due to a partition or link fault.
     fn handle_incoming_request(
-        peers: Arc<RwLock<HashMap<NodeId, PeerHandle>>>,
+        network: &RwLock<Network>,
+        rng: &Mutex<StdRng>,
         req: Request<Bytes>,
         dest: NodeId,
         resp: oneshot::Sender<Response<Bytes>>,
     ) {
         let request_size = request_size(&req);
         let origin_id = req.extensions().get::<NodeId>().unwrap();
-        let peers_g = peers.read().unwrap();
-        if !peers_g.contains_key(&dest) || !peers_g.contains_key(origin_id) {
+        let Some(route) = Self::route(network, rng, origin_id, &dest) else {
             return;
-        }
-        let dest_ph = peers_g.get(&dest).unwrap().clone();
-        let origin_ph = peers_g.get(origin_id).unwrap().clone();
-        drop(peers_g);
+        };
 
         let req_fut = async move {
-            let _permit = origin_ph
-                .up_capacity
-                .acquire_many(request_size as u32)
-                .await
-                .unwrap();
-            tokio::time::sleep(origin_ph.latency).await;
-            drop(_permit);
-            let _permit = dest_ph
-                .down_capacity
-                .acquire_many(request_size as u32)
-                .await
-                .unwrap();
-            tokio::time::sleep(dest_ph.latency).await;
-            let _ = dest_ph.rpc_tx.send((req, resp));
+            if route.transmit(request_size).await {
+                let _ = route.dest.rpc_tx.send((req, resp));
+            }
         };
         tokio::spawn(req_fut);
     }
 
     /// Reserves capacities for the response and waits for the required latency.
-    /// After using the requested resources the response is delivered.
+    /// After using the requested resources the response is delivered, unless it
+    /// is lost due to a partition or link fault.
     fn handle_incoming_response(
-        peers: Arc<RwLock<HashMap<NodeId, PeerHandle>>>,
+        network: &RwLock<Network>,
+        rng: &Mutex<StdRng>,
         req: Response<Bytes>,
         dest: NodeId,
         resp: oneshot::Sender<Response<Bytes>>,
     ) {
         let response_size = response_size(&req);
         let origin_id = req.extensions().get::<NodeId>().unwrap();
-        let peers_g = peers.read().unwrap();
-        if !peers_g.contains_key(&dest) || !peers_g.contains_key(origin_id) {
+        let Some(route) = Self::route(network, rng, origin_id, &dest) else {
             return;
-        }
-        let dest_ph = peers_g.get(&dest).unwrap().clone();
-        let origin_ph = peers_g.get(origin_id).unwrap().clone();
-        drop(peers_g);
+        };
 
         let resp_fut = async move {
-            let _permit = origin_ph
-                .up_capacity
-                .acquire_many(response_size as u32)
-                .await
-                .unwrap();
-            tokio::time::sleep(origin_ph.latency).await;
-            drop(_permit);
-            let _permit = dest_ph
-                .down_capacity
-                .acquire_many(response_size as u32)
-                .await
-                .unwrap();
-            tokio::time::sleep(dest_ph.latency).await;
-            // Receiver might have already stopped listening, therefore ignore the result.
-            let _ = resp.send(req);
+            if route.transmit(response_size).await {
+                // Receiver might have already stopped listening, therefore ignore the result.
+                let _ = resp.send(req);
+            }
         };
         tokio::spawn(resp_fut);
     }
@@ -309,12 +537,11 @@ impl Transport for PeerTransport {
     }
 
     fn peers(&self) -> Vec<(NodeId, ConnId)> {
-        self.global
+        let network = self.global.network.read().unwrap();
+        network
             .peers
-            .read()
-            .unwrap()
             .iter()
-            .filter(|(&n, _)| n != self.node_id)
+            .filter(|(&n, _)| n != self.node_id && network.reachable(&n, &self.node_id))
             .map(|(k, _)| (*k, ConnId::from(u64::MAX)))
             .collect()
     }
@@ -16,6 +16,7 @@ use ic_logger::info;
 use ic_memory_transport::TransportRouter;
 use ic_p2p_test_utils::{
     mocks::MockStateSync,
+    network_faults::{faulty_links, isolate, LinkFaults, NetworkEvent, NetworkSchedule},
     turmoil::{
         add_peer_manager_to_sim, add_transport_to_sim, wait_for, wait_for_timeout, waiter_fut,
         PeerManagerAction,
@@ -24,7 +25,7 @@ use ic_p2p_test_utils::{
 };
 use ic_test_utilities_logger::with_test_replica_logger;
 use ic_types::{crypto::CryptoHash, Height, RegistryVersion};
-use ic_types_test_utils::ids::{NODE_1, NODE_2, NODE_3};
+use ic_types_test_utils::ids::{node_test_id, NODE_1, NODE_2, NODE_3};
 use tokio::sync::Notify;
 use turmoil::Builder;
 
@@ -135,6 +136,89 @@ fn test_full_subnet() {
     });
 }
 
+/// Test one node syncing the state in a 13 node subnet while it is first
+/// isolated from the subnet and then connected over lossy links. Runs on
+/// paused time, so the scheduled events and sleeps do not take real time.
+#[test]
+fn test_full_subnet_with_network_faults() {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .start_paused(true)
+        .build()
+        .unwrap();
+    let rt_handle = runtime.handle().clone();
+    with_test_replica_logger(|log| {
+        runtime.block_on(async move {
+            let mut transport_router = TransportRouter::with_seed(0);
+            let subnet_size = 13;
+            let global_state = State::new();
+
+            // Create empty node
+            let (state_sync_empty, _join_handle_empty) = create_node(
+                0,
+                log.clone(),
+                &mut transport_router,
+                &rt_handle,
+                false,
+                global_state.clone(),
+                latency_50ms_throughput_300mbits(),
+            );
+
+            let mut join_handles = Vec::new();
+            let mut states = Vec::new();
+            // Create nodes that provide global state.
+            for i in 1..subnet_size {
+                let (state_sync, join_handle) = create_node(
+                    i,
+                    log.clone(),
+                    &mut transport_router,
+                    &rt_handle,
+                    true,
+                    global_state.clone(),
+                    latency_30ms_throughput_1000mbits(),
+                );
+                join_handles.push(join_handle);
+                states.push(state_sync);
+            }
+
+            let schedule = isolate(
+                NetworkSchedule::new(),
+                [node_test_id(0)],
+                Duration::ZERO,
+                Duration::from_secs(10),
+            );
+            let schedule = faulty_links(
+                schedule,
+                node_test_id(0),
+                (1..subnet_size).map(node_test_id),
+                LinkFaults::new(0.1, 0.2, Duration::from_millis(200)),
+                Duration::from_secs(10),
+            )
+            .at(
+                Duration::from_secs(30),
+                NetworkEvent::SetCapacity(node_test_id(0), 100_000_000),
+            );
+            transport_router.run_schedule(schedule);
+            global_state.add_new_chunks(100, 1_000_000);
+
+            // Verify that the isolated node does not make progress.
+            tokio::time::sleep(Duration::from_secs(8)).await;
+            assert!(states.iter().all(|s| !s.is_equal(&state_sync_empty)));
+
+            // Verify that empty node has caught up after the partition healed.
+            let fut = async move {
+                while !states.is_empty() {
+                    tokio::time::sleep(Duration::from_secs(2)).await;
+                    states.retain(|s| !s.is_equal(&state_sync_empty));
+                }
+            };
+            tokio::time::timeout(TEST_STATE_SYNC_TIMEOUT, fut)
+                .await
+                .unwrap();
+        });
+    });
+}
+
 /// Test one node syncing the state in a 13 node subnet with many small chunks.
 #[test]
 fn test_full_subnet_mini_chunks() {
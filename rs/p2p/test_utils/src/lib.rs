@@ -45,6 +45,7 @@ use tokio::{
 
 pub mod consensus;
 pub mod mocks;
+pub mod network_faults;
 pub mod turmoil;
 
 /// Creates a temp crypto component with TLS key and specified node id.
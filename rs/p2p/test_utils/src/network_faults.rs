@@ -0,0 +1,38 @@
+//! Helpers to build network fault schedules for tests that run on the
+//! in-memory transport. The schedules are applied with
+//! `TransportRouter::run_schedule()`.
+use std::{collections::BTreeSet, time::Duration};
+
+use ic_base_types::NodeId;
+pub use ic_memory_transport::{LinkFaults, NetworkEvent, NetworkSchedule};
+
+/// Adds events that isolate `nodes` from the rest of the network at `start`
+/// and heal the network at `end`.
+///
+/// Healing also clears all link faults, so faults that should hold after the
+/// partition must be added after this call with an offset of at least `end`.
+pub fn isolate(
+    schedule: NetworkSchedule,
+    nodes: impl IntoIterator<Item = NodeId>,
+    start: Duration,
+    end: Duration,
+) -> NetworkSchedule {
+    let nodes: BTreeSet<NodeId> = nodes.into_iter().collect();
+    schedule
+        .at(start, NetworkEvent::Isolate(nodes))
+        .at(end, NetworkEvent::Heal)
+}
+
+/// Adds events that set `faults` on the links between `node` and each of
+/// `peers` at `offset`.
+pub fn faulty_links(
+    schedule: NetworkSchedule,
+    node: NodeId,
+    peers: impl IntoIterator<Item = NodeId>,
+    faults: LinkFaults,
+    offset: Duration,
+) -> NetworkSchedule {
+    peers.into_iter().fold(schedule, |schedule, peer| {
+        schedule.at(offset, NetworkEvent::SetLinkFaults(node, peer, faults))
+    })
+}
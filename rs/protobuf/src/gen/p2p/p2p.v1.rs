@@ -55,3 +55,28 @@ pub struct Advert {
     #[prost(bytes = "vec", tag = "2")]
     pub attribute: ::prost::alloc::vec::Vec<u8>,
 }
+#[derive(serde::Serialize, serde::Deserialize)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetIngressMessageInBlockRequest {
+    #[prost(bytes = "vec", tag = "1")]
+    pub ingress_message_id: ::prost::alloc::vec::Vec<u8>,
+    #[prost(bytes = "vec", tag = "2")]
+    pub block_proposal_id: ::prost::alloc::vec::Vec<u8>,
+    #[prost(bytes = "vec", tag = "3")]
+    pub ingress_hash: ::prost::alloc::vec::Vec<u8>,
+}
+/// A consensus message whose ingress messages were stripped, if it is a block
+/// proposal.
+#[derive(serde::Serialize, serde::Deserialize)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct StrippedConsensusMessage {
+    /// The encoded `types.v1.ConsensusMessage`, with an empty ingress buffer.
+    #[prost(bytes = "vec", tag = "1")]
+    pub message: ::prost::alloc::vec::Vec<u8>,
+    /// The SHA-256 hashes of the signed bytes of the stripped ingress messages, in
+    /// the order of the ingress payload.
+    #[prost(bytes = "vec", repeated, tag = "2")]
+    pub ingress_hashes: ::prost::alloc::vec::Vec<::prost::alloc::vec::Vec<u8>>,
+}
@@ -160,6 +160,8 @@ pub mod wasm_method {
         CanisterHeartbeat = 6,
         Empty = 7,
         CanisterGlobalTimer = 8,
+        CanisterOnLowWasmMemory = 9,
+        CanisterOnLowCycles = 10,
     }
     impl SystemMethod {
         /// String value of the enum field names used in the ProtoBuf definition.
@@ -177,6 +179,10 @@ pub mod wasm_method {
                 SystemMethod::CanisterHeartbeat => "SYSTEM_METHOD_CANISTER_HEARTBEAT",
                 SystemMethod::Empty => "SYSTEM_METHOD_EMPTY",
                 SystemMethod::CanisterGlobalTimer => "SYSTEM_METHOD_CANISTER_GLOBAL_TIMER",
+                SystemMethod::CanisterOnLowWasmMemory => {
+                    "SYSTEM_METHOD_CANISTER_ON_LOW_WASM_MEMORY"
+                }
+                SystemMethod::CanisterOnLowCycles => "SYSTEM_METHOD_CANISTER_ON_LOW_CYCLES",
             }
         }
         /// Creates an enum from field names used in the ProtoBuf definition.
@@ -191,6 +197,8 @@ pub mod wasm_method {
                 "SYSTEM_METHOD_CANISTER_HEARTBEAT" => Some(Self::CanisterHeartbeat),
                 "SYSTEM_METHOD_EMPTY" => Some(Self::Empty),
                 "SYSTEM_METHOD_CANISTER_GLOBAL_TIMER" => Some(Self::CanisterGlobalTimer),
+                "SYSTEM_METHOD_CANISTER_ON_LOW_WASM_MEMORY" => Some(Self::CanisterOnLowWasmMemory),
+                "SYSTEM_METHOD_CANISTER_ON_LOW_CYCLES" => Some(Self::CanisterOnLowCycles),
                 _ => None,
             }
         }
@@ -365,6 +373,8 @@ pub mod execution_task {
         Unspecified = 0,
         Heartbeat = 1,
         Timer = 2,
+        OnLowWasmMemory = 3,
+        OnLowCycles = 4,
     }
     impl CanisterTask {
         /// String value of the enum field names used in the ProtoBuf definition.
@@ -376,6 +386,8 @@ pub mod execution_task {
                 CanisterTask::Unspecified => "CANISTER_TASK_UNSPECIFIED",
                 CanisterTask::Heartbeat => "CANISTER_TASK_HEARTBEAT",
                 CanisterTask::Timer => "CANISTER_TASK_TIMER",
+                CanisterTask::OnLowWasmMemory => "CANISTER_TASK_ON_LOW_WASM_MEMORY",
+                CanisterTask::OnLowCycles => "CANISTER_TASK_ON_LOW_CYCLES",
             }
         }
         /// Creates an enum from field names used in the ProtoBuf definition.
@@ -384,6 +396,8 @@ pub mod execution_task {
                 "CANISTER_TASK_UNSPECIFIED" => Some(Self::Unspecified),
                 "CANISTER_TASK_HEARTBEAT" => Some(Self::Heartbeat),
                 "CANISTER_TASK_TIMER" => Some(Self::Timer),
+                "CANISTER_TASK_ON_LOW_WASM_MEMORY" => Some(Self::OnLowWasmMemory),
+                "CANISTER_TASK_ON_LOW_CYCLES" => Some(Self::OnLowCycles),
                 _ => None,
             }
         }
@@ -447,6 +461,28 @@ pub struct CanisterControllersChange {
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CanisterLoadSnapshot {
+    #[prost(uint64, tag = "1")]
+    pub canister_version: u64,
+    #[prost(uint64, tag = "2")]
+    pub snapshot_id: u64,
+    #[prost(uint64, tag = "3")]
+    pub taken_at_timestamp: u64,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CanisterSettingsChange {
+    #[prost(uint64, optional, tag = "1")]
+    pub freezing_threshold: ::core::option::Option<u64>,
+    #[prost(uint64, optional, tag = "2")]
+    pub compute_allocation: ::core::option::Option<u64>,
+    #[prost(uint64, optional, tag = "3")]
+    pub memory_allocation: ::core::option::Option<u64>,
+    #[prost(enumeration = "LogVisibility", optional, tag = "4")]
+    pub log_visibility: ::core::option::Option<i32>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct CanisterChange {
     #[prost(uint64, tag = "1")]
     pub timestamp_nanos: u64,
@@ -454,7 +490,7 @@ pub struct CanisterChange {
     pub canister_version: u64,
     #[prost(oneof = "canister_change::ChangeOrigin", tags = "3, 4")]
     pub change_origin: ::core::option::Option<canister_change::ChangeOrigin>,
-    #[prost(oneof = "canister_change::ChangeDetails", tags = "5, 6, 7, 8")]
+    #[prost(oneof = "canister_change::ChangeDetails", tags = "5, 6, 7, 8, 9, 10")]
     pub change_details: ::core::option::Option<canister_change::ChangeDetails>,
 }
 /// Nested message and enum types in `CanisterChange`.
@@ -478,6 +514,10 @@ pub mod canister_change {
         CanisterCodeDeployment(super::CanisterCodeDeployment),
         #[prost(message, tag = "8")]
         CanisterControllersChange(super::CanisterControllersChange),
+        #[prost(message, tag = "9")]
+        CanisterLoadSnapshot(super::CanisterLoadSnapshot),
+        #[prost(message, tag = "10")]
+        CanisterSettingsChange(super::CanisterSettingsChange),
     }
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -619,6 +659,16 @@ pub struct CanisterStateBits {
     /// Log records of the canister.
     #[prost(message, repeated, tag = "43")]
     pub canister_log_records: ::prost::alloc::vec::Vec<CanisterLogRecord>,
+    /// Status of the `canister_on_low_wasm_memory` hook.
+    #[prost(enumeration = "LowResourceHookStatus", tag = "44")]
+    pub on_low_wasm_memory_hook_status: i32,
+    /// Status of the `canister_on_low_cycles` hook.
+    #[prost(enumeration = "LowResourceHookStatus", tag = "45")]
+    pub on_low_cycles_hook_status: i32,
+    /// The remaining Wasm memory below which the `canister_on_low_wasm_memory`
+    /// hook runs.
+    #[prost(uint64, tag = "46")]
+    pub wasm_memory_threshold: u64,
     #[prost(oneof = "canister_state_bits::CanisterStatus", tags = "11, 12, 13")]
     pub canister_status: ::core::option::Option<canister_state_bits::CanisterStatus>,
 }
@@ -788,3 +838,37 @@ impl LogVisibility {
         }
     }
 }
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum LowResourceHookStatus {
+    Unspecified = 0,
+    ConditionNotSatisfied = 1,
+    Ready = 2,
+    Executed = 3,
+}
+impl LowResourceHookStatus {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            LowResourceHookStatus::Unspecified => "LOW_RESOURCE_HOOK_STATUS_UNSPECIFIED",
+            LowResourceHookStatus::ConditionNotSatisfied => {
+                "LOW_RESOURCE_HOOK_STATUS_CONDITION_NOT_SATISFIED"
+            }
+            LowResourceHookStatus::Ready => "LOW_RESOURCE_HOOK_STATUS_READY",
+            LowResourceHookStatus::Executed => "LOW_RESOURCE_HOOK_STATUS_EXECUTED",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "LOW_RESOURCE_HOOK_STATUS_UNSPECIFIED" => Some(Self::Unspecified),
+            "LOW_RESOURCE_HOOK_STATUS_CONDITION_NOT_SATISFIED" => Some(Self::ConditionNotSatisfied),
+            "LOW_RESOURCE_HOOK_STATUS_READY" => Some(Self::Ready),
+            "LOW_RESOURCE_HOOK_STATUS_EXECUTED" => Some(Self::Executed),
+            _ => None,
+        }
+    }
+}
@@ -84,6 +84,8 @@ impl NiDkgTag {
 pub struct CanisterUpgradeOptions {
     #[prost(bool, optional, tag = "1")]
     pub skip_pre_upgrade: ::core::option::Option<bool>,
+    #[prost(enumeration = "WasmMemoryPersistence", optional, tag = "2")]
+    pub wasm_memory_persistence: ::core::option::Option<i32>,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -138,3 +140,32 @@ impl CanisterInstallMode {
         }
     }
 }
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum WasmMemoryPersistence {
+    Unspecified = 0,
+    Keep = 1,
+    Replace = 2,
+}
+impl WasmMemoryPersistence {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            WasmMemoryPersistence::Unspecified => "WASM_MEMORY_PERSISTENCE_UNSPECIFIED",
+            WasmMemoryPersistence::Keep => "WASM_MEMORY_PERSISTENCE_KEEP",
+            WasmMemoryPersistence::Replace => "WASM_MEMORY_PERSISTENCE_REPLACE",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "WASM_MEMORY_PERSISTENCE_UNSPECIFIED" => Some(Self::Unspecified),
+            "WASM_MEMORY_PERSISTENCE_KEEP" => Some(Self::Keep),
+            "WASM_MEMORY_PERSISTENCE_REPLACE" => Some(Self::Replace),
+            _ => None,
+        }
+    }
+}
@@ -1940,6 +1940,7 @@ impl ProposalPayload<ChangeCanisterRequest> for ProposeToChangeNnsCanisterCmd {
             compute_allocation: self.compute_allocation.map(candid::Nat::from),
             memory_allocation: self.memory_allocation.map(candid::Nat::from),
             query_allocation: None,
+            chunked_canister_wasm: None,
         }
     }
 }
@@ -1,4 +1,4 @@
-use ic_artifact_pool::consensus_pool::ConsensusPoolImpl;
+use ic_artifact_pool::{consensus_pool::ConsensusPoolImpl, packed_backup::PackedBackup};
 use ic_config::artifact_pool::BACKUP_GROUP_SIZE;
 use ic_consensus::consensus::dkg_key_manager::DkgKeyManager;
 use ic_consensus_utils::pool_reader::PoolReader;
@@ -26,7 +26,7 @@ use std::{
     fs,
     io::Read,
     path::{Path, PathBuf},
-    sync::Arc,
+    sync::{Arc, Mutex},
 };
 
 use crate::player::ReplayError;
@@ -52,7 +52,7 @@ fn read_file(path: &Path) -> Vec<u8> {
 }
 
 // Renames the file at `path` and by adding a prefix 'invalid_'.
-pub(crate) fn rename_file(original_file: &Path) {
+fn rename_file(original_file: &Path) {
     let file_name = original_file
         .file_name()
         .expect("File name is missing")
@@ -63,6 +63,87 @@ pub(crate) fn rename_file(original_file: &Path) {
     fs::rename(original_file, renamed_file).expect("Error renaming a file");
 }
 
+/// The backup of a replica version, stored in the directory format, in packed
+/// segments, or a mix of both. In either format, artifacts are addressed by
+/// their path in the directory layout, i.e.
+/// `<backup_dir>/<group>/<height>/<file_name>`.
+pub(crate) struct BackupSpool {
+    dir: PathBuf,
+    packed: Mutex<PackedBackup>,
+}
+
+impl BackupSpool {
+    pub(crate) fn open(backup_dir: &Path) -> Self {
+        let packed = PackedBackup::open(backup_dir).unwrap_or_else(|err| {
+            panic!(
+                "Couldn't open the packed backup at {:?}: {:?}",
+                backup_dir, err
+            )
+        });
+        Self {
+            dir: backup_dir.to_path_buf(),
+            packed: Mutex::new(packed),
+        }
+    }
+
+    /// Deduce the file name of a CUP at a specific height
+    pub(crate) fn cup_file_name(&self, height: Height) -> PathBuf {
+        cup_file_name(&self.dir, height)
+    }
+
+    // Returns the height and the file name of the packed artifact at `path`, if
+    // there is no such file on disk.
+    fn packed_location(&self, path: &Path) -> Option<(Height, String)> {
+        if path.exists() {
+            return None;
+        }
+        let file_name = path.file_name()?.to_str()?.to_string();
+        let height = path.parent()?.file_name()?.to_str()?.parse::<u64>().ok()?;
+        let height = Height::from(height);
+        self.packed
+            .lock()
+            .unwrap()
+            .contains(height, &file_name)
+            .then_some((height, file_name))
+    }
+
+    /// Returns true if the artifact at `path` exists in either format.
+    pub(crate) fn exists(&self, path: &Path) -> bool {
+        path.exists() || self.packed_location(path).is_some()
+    }
+
+    // Reads the artifact at `path` and the returns the content as bytes.
+    fn read_file(&self, path: &Path) -> Vec<u8> {
+        let Some((height, file_name)) = self.packed_location(path) else {
+            return read_file(path);
+        };
+        self.packed
+            .lock()
+            .unwrap()
+            .read(height, &file_name)
+            .unwrap_or_else(|err| panic!("Couldn't read packed artifact {:?}: {:?}", path, err))
+            .unwrap_or_else(|| panic!("Packed artifact {:?} is missing", path))
+    }
+
+    /// Renames the artifact at `path` by adding a prefix 'invalid_'.
+    pub(crate) fn rename_file(&self, path: &Path) {
+        let Some((height, file_name)) = self.packed_location(path) else {
+            return rename_file(path);
+        };
+        let renamed = format!("invalid_{}", file_name);
+        println!(
+            "Renaming packed artifact {:?} to {:?}",
+            path,
+            path.with_file_name(&renamed)
+        );
+        self.packed
+            .lock()
+            .unwrap()
+            .rename(height, &file_name, &renamed)
+            .expect("Error renaming a packed artifact");
+    }
+}
+
 /// All possible exits from the deserialization loop of the artifacts. All
 /// exits except for `Done` require for the upper layers to catch up.
 pub(crate) enum ExitPoint {
@@ -83,11 +164,11 @@ pub(crate) enum ExitPoint {
 /// Deserialize the CUP at the given height and inserts it into the pool.
 pub(crate) fn insert_cup_at_height(
     pool: &mut dyn MutablePool<ConsensusArtifact, ChangeSet = ChangeSet>,
-    backup_dir: &Path,
+    spool: &BackupSpool,
     height: Height,
 ) -> Result<(), ReplayError> {
-    let file = &cup_file_name(backup_dir, height);
-    if let Some(cup) = read_cup_file(file) {
+    let file = &spool.cup_file_name(height);
+    if let Some(cup) = read_cup_file(spool, file) {
         pool.apply_changes(
             ChangeAction::AddToValidated(ValidatedConsensusArtifact {
                 msg: cup.into_message(),
@@ -101,13 +182,13 @@ pub(crate) fn insert_cup_at_height(
     }
 }
 
-pub(crate) fn read_cup_proto_file(file: &Path) -> Option<pb::CatchUpPackage> {
-    let buffer = read_file(file);
+pub(crate) fn read_cup_proto_file(spool: &BackupSpool, file: &Path) -> Option<pb::CatchUpPackage> {
+    let buffer = spool.read_file(file);
 
     match pb::CatchUpPackage::decode(buffer.as_slice()) {
         Ok(proto) => Some(proto),
         Err(err) => {
-            rename_file(file);
+            spool.rename_file(file);
             println!(
                 "Protobuf decoding of CUP at {} failed: {:?}",
                 file.display(),
@@ -119,13 +200,13 @@ pub(crate) fn read_cup_proto_file(file: &Path) -> Option<pb::CatchUpPackage> {
 }
 
 /// Deserializes the CUP file and returns it.
-pub(crate) fn read_cup_file(file: &Path) -> Option<CatchUpPackage> {
-    let protobuf = read_cup_proto_file(file)?;
+pub(crate) fn read_cup_file(spool: &BackupSpool, file: &Path) -> Option<CatchUpPackage> {
+    let protobuf = read_cup_proto_file(spool, file)?;
 
     match CatchUpPackage::try_from(&protobuf) {
         Ok(cup) => Some(cup),
         Err(err) => {
-            rename_file(file);
+            spool.rename_file(file);
             println!("{}", deserialization_error(file, err));
             None
         }
@@ -133,7 +214,7 @@ pub(crate) fn read_cup_file(file: &Path) -> Option<CatchUpPackage> {
 }
 
 /// Deduce the file name of a CUP at a specific height
-pub(crate) fn cup_file_name(backup_dir: &Path, height: Height) -> PathBuf {
+fn cup_file_name(backup_dir: &Path, height: Height) -> PathBuf {
     let group_key = (height.get() / BACKUP_GROUP_SIZE) * BACKUP_GROUP_SIZE;
     backup_dir
         .join(group_key.to_string())
@@ -144,12 +225,17 @@ pub(crate) fn cup_file_name(backup_dir: &Path, height: Height) -> PathBuf {
 /// Read all files from the backup folder starting from the `start_height` and
 /// convert them into batches.
 pub(super) fn heights_to_artifacts_metadata(
-    backup_dir: &Path,
+    spool: &BackupSpool,
     start_height: Height,
 ) -> Result<BTreeMap<Height, HeightArtifacts>, std::io::Error> {
-    let mut results = Vec::new();
-    for group_dir in fs::read_dir(backup_dir)? {
-        for height_dir in fs::read_dir(group_dir?.path())? {
+    let mut files_by_height: BTreeMap<Height, (PathBuf, Vec<String>)> = BTreeMap::new();
+    for group_dir in fs::read_dir(&spool.dir)? {
+        let group_dir = group_dir?.path();
+        // Skip packed segments, which are read below.
+        if !group_dir.is_dir() {
+            continue;
+        }
+        for height_dir in fs::read_dir(group_dir)? {
             let path = height_dir?.path();
             let height = Height::from(
                 path.file_name()
@@ -176,6 +262,31 @@ pub(super) fn heights_to_artifacts_metadata(
                         .to_string(),
                 );
             }
+            files_by_height.insert(height, (path, files));
+        }
+    }
+
+    // Add the packed artifacts, addressed by the height directories they would have in
+    // the directory layout.
+    let packed = spool.packed.lock().unwrap();
+    for height in packed.heights().filter(|height| *height >= start_height) {
+        let (_, files) = files_by_height.entry(height).or_insert_with(|| {
+            let path = cup_file_name(&spool.dir, height)
+                .parent()
+                .expect("CUP file has no parent directory")
+                .to_path_buf();
+            (path, Vec::new())
+        });
+        for file_name in packed.file_names(height) {
+            if !files.contains(&file_name) {
+                files.push(file_name);
+            }
+        }
+    }
+
+    Ok(files_by_height
+        .into_iter()
+        .map(|(height, (path, files))| {
             let get_files = |s| {
                 files
                     .iter()
@@ -183,7 +294,7 @@ pub(super) fn heights_to_artifacts_metadata(
                     .cloned()
                     .collect::<Vec<_>>()
             };
-            results.push((
+            (
                 height,
                 HeightArtifacts {
                     path,
@@ -192,13 +303,13 @@ pub(super) fn heights_to_artifacts_metadata(
                     finalizations: get_files("finalization"),
                     notarizations: get_files("notarization"),
                 },
-            ));
-        }
-    }
-    Ok(results.into_iter().collect())
+            )
+        })
+        .collect())
 }
 
 fn read_artifact_if_correct_height<T, PBT>(
+    spool: &BackupSpool,
     file: &PathBuf,
     artifact_type: &str,
     height: Height,
@@ -207,16 +318,16 @@ where
     T: TryFrom<PBT> + HasHeight,
     PBT: prost::Message + std::default::Default,
 {
-    let buffer = read_file(file);
+    let buffer = spool.read_file(file);
     let Ok(fn_pb) = PBT::decode(buffer.as_slice()) else {
         println!("Error: Protobuf decoding of {artifact_type} failed: {file:?}");
-        rename_file(file);
+        spool.rename_file(file);
         return Err(ExitPoint::ValidationIncomplete(height));
     };
 
     let Ok(artifact) = T::try_from(fn_pb) else {
         println!("Error: Deserialization of the {artifact_type}: failed: {file:?}",);
-        rename_file(file);
+        spool.rename_file(file);
         return Err(ExitPoint::ValidationIncomplete(height));
     };
 
@@ -224,7 +335,7 @@ where
         Ok(artifact)
     } else {
         println!("Error: A {artifact_type} with an unexpected height detected: {file:?}");
-        rename_file(file);
+        spool.rename_file(file);
         Err(ExitPoint::ValidationIncomplete(height))
     }
 }
@@ -236,6 +347,7 @@ pub(crate) fn deserialize_consensus_artifacts(
     registry_client: Arc<dyn RegistryClient>,
     crypto: Arc<dyn CryptoComponentForVerificationOnly>,
     pool: &mut ConsensusPoolImpl,
+    spool: &BackupSpool,
     height_to_batches: &mut BTreeMap<Height, HeightArtifacts>,
     subnet_id: SubnetId,
     validator: &ReplayValidator,
@@ -264,10 +376,10 @@ pub(crate) fn deserialize_consensus_artifacts(
         if height > Height::from(0) && height_artifacts.contains_cup {
             last_cup_height = Some(height);
             let file = &path.join("catch_up_package.bin");
-            if let Some(cup) = read_cup_file(file) {
+            if let Some(cup) = read_cup_file(spool, file) {
                 if cup.height() != height {
                     println!("A CUP with an unexpected height detected: {:?}", file);
-                    rename_file(file);
+                    spool.rename_file(file);
                     return Ok(());
                 }
             }
@@ -298,6 +410,7 @@ pub(crate) fn deserialize_consensus_artifacts(
             finalized_block_hash = file_name.split('_').nth(1);
             let file = path.join(file_name);
             let finalization = read_artifact_if_correct_height::<Finalization, pb::Finalization>(
+                spool,
                 &file,
                 "finalization",
                 height,
@@ -306,7 +419,7 @@ pub(crate) fn deserialize_consensus_artifacts(
                 finalization.signature.signers.clone().into_iter().collect();
             if unique_signers.len() != finalization.signature.signers.len() {
                 println!("Detected repeated signers on the finalization signature");
-                rename_file(&file);
+                spool.rename_file(&file);
             } else if let Err(err) = crypto.verify_multi_sig_combined(
                 &finalization.signature.signature,
                 &finalization.content,
@@ -314,7 +427,7 @@ pub(crate) fn deserialize_consensus_artifacts(
                 registry_version,
             ) {
                 println!("Cannot verify the signature on the finalization: {:?}", err);
-                rename_file(&file);
+                spool.rename_file(&file);
             } else {
                 let message = finalization.into_message();
                 expected.insert(message.get_cm_hash(), file);
@@ -326,6 +439,7 @@ pub(crate) fn deserialize_consensus_artifacts(
         if let Some(file_name) = height_artifacts.proposals.first() {
             let file = path.join(file_name);
             let proposal = read_artifact_if_correct_height::<BlockProposal, pb::BlockProposal>(
+                spool,
                 &file,
                 "block proposal",
                 height,
@@ -360,7 +474,7 @@ pub(crate) fn deserialize_consensus_artifacts(
 
         // Insert the random beacon and the random tape.
         let rb_path = path.join("random_beacon.bin");
-        if !rb_path.exists() {
+        if !spool.exists(&rb_path) {
             println!(
                 "Stopping deserialization at height {:?} as this height contains no random beacon.",
                 height,
@@ -368,6 +482,7 @@ pub(crate) fn deserialize_consensus_artifacts(
             return Ok(());
         }
         let rb = read_artifact_if_correct_height::<RandomBeacon, pb::RandomBeacon>(
+            spool,
             &rb_path,
             "random beacon",
             height,
@@ -375,7 +490,7 @@ pub(crate) fn deserialize_consensus_artifacts(
         artifacts.push(rb.into_message());
 
         let rt_path = path.join("random_tape.bin");
-        if !rt_path.exists() {
+        if !spool.exists(&rt_path) {
             println!(
                 "Stopping deserialization at height {:?} as this height contains no random tape.",
                 height,
@@ -383,6 +498,7 @@ pub(crate) fn deserialize_consensus_artifacts(
             return Ok(());
         }
         let rt = read_artifact_if_correct_height::<RandomTape, pb::RandomTape>(
+            spool,
             &rt_path,
             "random tape",
             height,
@@ -393,6 +509,7 @@ pub(crate) fn deserialize_consensus_artifacts(
         for file_name in &height_artifacts.notarizations.first() {
             let file = path.join(file_name);
             let not = read_artifact_if_correct_height::<Notarization, pb::Notarization>(
+                spool,
                 &file,
                 "notarization",
                 height,
@@ -432,11 +549,11 @@ pub(crate) fn deserialize_consensus_artifacts(
                 Some(name) => {
                     let artifact_path = path.join(name);
                     assert!(
-                        artifact_path.exists(),
+                        spool.exists(&artifact_path),
                         "Path to invalid artifact doesn't exist."
                     );
                     println!("Invalid artifact detected: {:?}", &artifact_path);
-                    rename_file(&artifact_path);
+                    spool.rename_file(&artifact_path);
                     return Err(ExitPoint::ValidationIncomplete(height));
                 }
                 None => println!("Failed to get path for invalid artifact: {:?}", i),
@@ -448,7 +565,7 @@ pub(crate) fn deserialize_consensus_artifacts(
         // If they weren't we remove them here and hopefully rsync the correct ones next time.
         for (_, artifact_path) in expected {
             println!("Artifact couldn't be validated: {:?}", artifact_path);
-            rename_file(&artifact_path);
+            spool.rename_file(&artifact_path);
         }
 
         if let Some(height) = failure_after_height {
@@ -56,6 +56,19 @@ pub enum SubCommand {
     /// Create a recovery CUP and write it to a file.
     GetRecoveryCup(GetRecoveryCupCmd),
 
+    /// Dump the Wasm module, heap, stable memory and queues of a canister after
+    /// replaying until the target height.
+    DumpCanister(DumpCanisterCmd),
+
+    /// Replay with the Wasm module of a canister substituted by another one.
+    /// WARNING: The resulting state diverges from the subnet's state and must
+    /// not be used for recovery.
+    SubstituteWasm(SubstituteWasmCmd),
+
+    /// Write the messages executed, instructions and cycles consumed by every
+    /// replayed batch to a file, as one line of JSON per batch.
+    TraceExecution(TraceExecutionCmd),
+
     /// Restore from the backup. Deprecated.
     RestoreFromBackup(RestoreFromBackupCmd),
 
@@ -88,6 +101,35 @@ pub struct GetRecoveryCupCmd {
     pub registry_store_sha256: Option<String>,
 }
 
+#[derive(Clone, Parser)]
+pub struct DumpCanisterCmd {
+    /// Id of the canister to dump.
+    pub canister_id: CanisterId,
+    /// Directory to write the canister's Wasm module, memories and queues to.
+    pub output_dir: PathBuf,
+}
+
+#[derive(Clone, Parser)]
+pub struct SubstituteWasmCmd {
+    /// Id of the canister whose Wasm module is substituted.
+    pub canister_id: CanisterId,
+    /// Path to the (uncompressed) Wasm module to execute instead.
+    pub wasm_file: PathBuf,
+    /// New directory to replay the state in. The checkpoints of the original
+    /// state are linked into it, and the diverged checkpoints are written
+    /// there, leaving the original state untouched.
+    pub scratch_state_dir: PathBuf,
+    /// Also write an execution trace of every replayed batch to this file.
+    #[clap(long)]
+    pub trace: Option<PathBuf>,
+}
+
+#[derive(Clone, Parser)]
+pub struct TraceExecutionCmd {
+    /// Output file
+    pub output_file: PathBuf,
+}
+
 #[derive(Clone, Parser)]
 pub struct AddAndBlessReplicaVersionCmd {
     /// The Replica version ID.
@@ -0,0 +1,579 @@
+//! A state manager wrapper that lets the replay tool patch and observe the
+//! states that message routing executes batches on.
+//!
+//! Message routing takes the tip of the state manager, executes a batch on it
+//! and commits the result. By wrapping the state manager handed to message
+//! routing, we can substitute the Wasm module of a canister in every tip before
+//! execution, and compare every committed state to the tip it was computed
+//! from to trace what the batch executed.
+use ic_crypto_tree_hash::{LabeledTree, MixedHashTree};
+use ic_execution_environment::{
+    as_round_instructions, execution::upgrade::check_wasm_memory_compatibility,
+    CompilationCostHandling, Hypervisor, RoundLimits,
+};
+use ic_interfaces::execution_environment::SubnetAvailableMemory;
+use ic_interfaces_state_manager::{
+    CertificationMask, CertificationScope, CertifiedStateSnapshot, Labeled, StateHashError,
+    StateManager, StateManagerResult, StateReader,
+};
+use ic_metrics::MetricsRegistry;
+use ic_replicated_state::{ExecutionState, ReplicatedState};
+use ic_state_manager::StateManagerImpl;
+use ic_types::{
+    consensus::certification::Certification, CanisterId, CryptoHashOfPartialState,
+    CryptoHashOfState, Height, NumInstructions,
+};
+use ic_wasm_types::CanisterModule;
+use serde::Serialize;
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{BufWriter, Write},
+    mem::discriminant,
+    path::Path,
+    sync::{Arc, Mutex, RwLock},
+};
+
+/// Histogram of the instructions executed by each execution round.
+const ROUND_INSTRUCTIONS_METRIC: &str = "execution_round_instructions";
+
+/// The Wasm module to run instead of the installed one of a canister.
+struct WasmSubstitution {
+    canister_id: CanisterId,
+    module: CanisterModule,
+}
+
+/// The execution of a single batch.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct BatchTrace {
+    pub height: u64,
+    /// Messages executed by all canisters.
+    pub messages_executed: u64,
+    /// Instructions executed by all canisters.
+    pub instructions: u64,
+    /// Cycles consumed by all canisters.
+    pub cycles_consumed: u128,
+    /// The canisters that were executed or whose cycles balance changed.
+    pub canisters: Vec<CanisterTrace>,
+}
+
+/// The execution of a single canister within a batch.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct CanisterTrace {
+    pub canister_id: String,
+    /// The number of times the canister was scheduled and executed.
+    pub executions: u64,
+    pub cycles_consumed: u128,
+    pub balance_before: u128,
+    pub balance_after: u128,
+}
+
+/// The per-canister counters a trace is computed from.
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+struct CanisterCounters {
+    executions: u64,
+    cycles_consumed: u128,
+    balance: u128,
+}
+
+fn canister_counters(state: &ReplicatedState) -> BTreeMap<CanisterId, CanisterCounters> {
+    state
+        .canisters_iter()
+        .map(|canister| {
+            let system_state = &canister.system_state;
+            let counters = CanisterCounters {
+                executions: system_state.canister_metrics.executed,
+                cycles_consumed: system_state
+                    .canister_metrics
+                    .consumed_cycles_since_replica_started
+                    .get(),
+                balance: system_state.balance().get(),
+            };
+            (canister.canister_id(), counters)
+        })
+        .collect()
+}
+
+/// Returns the number of messages executed on the subnet so far.
+fn messages_executed(state: &ReplicatedState) -> u64 {
+    state.metadata.subnet_metrics.update_transactions_total
+}
+
+/// Returns the number of instructions executed by all execution rounds so
+/// far, i.e. the sum of the round instructions histogram. Fails if the
+/// histogram is not registered, e.g. because the scheduler stopped exporting
+/// it.
+fn instructions_executed(metrics_registry: &MetricsRegistry) -> Result<u64, String> {
+    metrics_registry
+        .prometheus_registry()
+        .gather()
+        .iter()
+        .find(|family| family.get_name() == ROUND_INSTRUCTIONS_METRIC)
+        .and_then(|family| family.get_metric().first())
+        .map(|metric| metric.get_histogram().get_sample_sum() as u64)
+        .ok_or_else(|| format!("Metric {} is not registered", ROUND_INSTRUCTIONS_METRIC))
+}
+
+/// Writes a [`BatchTrace`] for every committed state as a line of JSON.
+struct ExecutionTracer {
+    output: BufWriter<File>,
+    metrics_registry: MetricsRegistry,
+    tip: BTreeMap<CanisterId, CanisterCounters>,
+    messages: u64,
+    instructions: u64,
+}
+
+impl ExecutionTracer {
+    fn new(path: &Path, metrics_registry: MetricsRegistry) -> std::io::Result<Self> {
+        Ok(Self {
+            output: BufWriter::new(File::create(path)?),
+            metrics_registry,
+            tip: BTreeMap::new(),
+            messages: 0,
+            instructions: 0,
+        })
+    }
+
+    fn before_execution(&mut self, tip: &ReplicatedState) -> Result<(), String> {
+        self.tip = canister_counters(tip);
+        self.messages = messages_executed(tip);
+        self.instructions = instructions_executed(&self.metrics_registry)?;
+        Ok(())
+    }
+
+    fn after_execution(&mut self, state: &ReplicatedState, height: Height) -> Result<(), String> {
+        let messages = messages_executed(state);
+        let instructions = instructions_executed(&self.metrics_registry)?;
+        let mut trace = BatchTrace {
+            height: height.get(),
+            messages_executed: messages.saturating_sub(self.messages),
+            instructions: instructions.saturating_sub(self.instructions),
+            ..Default::default()
+        };
+        for (canister_id, after) in canister_counters(state) {
+            let before = self.tip.get(&canister_id).copied().unwrap_or_default();
+            if after == before {
+                continue;
+            }
+            let cycles_consumed = after.cycles_consumed.saturating_sub(before.cycles_consumed);
+            trace.cycles_consumed += cycles_consumed;
+            trace.canisters.push(CanisterTrace {
+                canister_id: canister_id.to_string(),
+                executions: after.executions.saturating_sub(before.executions),
+                cycles_consumed,
+                balance_before: before.balance,
+                balance_after: after.balance,
+            });
+        }
+
+        let line = serde_json::to_string(&trace).expect("Failed to serialize the batch trace");
+        writeln!(self.output, "{}", line)
+            .and_then(|_| self.output.flush())
+            .map_err(|err| format!("Failed to write the execution trace: {}", err))
+    }
+}
+
+/// Creates the execution state of `module` installed on `canister_id`,
+/// keeping the memories and globals of `old_state`. Fails if the new module
+/// can't be compiled or is not compatible with them, applying the same rules
+/// as an upgrade that keeps the main memory.
+fn substituted_execution_state(
+    hypervisor: &Hypervisor,
+    canister_id: CanisterId,
+    module: &CanisterModule,
+    old_state: &ExecutionState,
+) -> Result<ExecutionState, String> {
+    // Compilation is not part of the replayed execution, so it must not
+    // be limited by the round.
+    let mut round_limits = RoundLimits {
+        instructions: as_round_instructions(NumInstructions::from(u64::MAX)),
+        subnet_available_memory: SubnetAvailableMemory::new(i64::MAX, i64::MAX, i64::MAX),
+        compute_allocation_used: 0,
+    };
+    let (_, result) = hypervisor.create_execution_state_with_main_memory(
+        module.clone(),
+        old_state.canister_root.clone(),
+        canister_id,
+        &mut round_limits,
+        CompilationCostHandling::CountFullAmount,
+    );
+    let (mut new_state, new_main_memory) = result.map_err(|err| err.to_string())?;
+
+    check_wasm_memory_compatibility(old_state.wasm_memory.size, new_main_memory)?;
+    let globals_match = old_state.exported_globals.len() == new_state.exported_globals.len()
+        && old_state
+            .exported_globals
+            .iter()
+            .zip(new_state.exported_globals.iter())
+            .all(|(old, new)| discriminant(old) == discriminant(new));
+    if !globals_match {
+        return Err(format!(
+            "the new module exports the globals {:?}, but the installed one exports {:?}.",
+            new_state.exported_globals, old_state.exported_globals
+        ));
+    }
+
+    new_state.wasm_memory = old_state.wasm_memory.clone();
+    new_state.stable_memory = old_state.stable_memory.clone();
+    new_state.exported_globals = old_state.exported_globals.clone();
+    new_state.last_executed_round = old_state.last_executed_round;
+    new_state.next_scheduled_method = old_state.next_scheduled_method;
+    Ok(new_state)
+}
+
+/// Delegates to a [`StateManagerImpl`], substituting the Wasm module of a
+/// canister in every tip and tracing the execution of every batch, if
+/// configured to do so.
+pub(crate) struct InterceptingStateManager {
+    inner: Arc<StateManagerImpl>,
+    hypervisor: Arc<Hypervisor>,
+    metrics_registry: MetricsRegistry,
+    substitution: RwLock<Option<WasmSubstitution>>,
+    tracer: Mutex<Option<ExecutionTracer>>,
+    trace_error: Mutex<Option<String>>,
+}
+
+impl InterceptingStateManager {
+    pub(crate) fn new(
+        inner: Arc<StateManagerImpl>,
+        hypervisor: Arc<Hypervisor>,
+        metrics_registry: MetricsRegistry,
+    ) -> Self {
+        Self {
+            inner,
+            hypervisor,
+            metrics_registry,
+            substitution: RwLock::new(None),
+            tracer: Mutex::new(None),
+            trace_error: Mutex::new(None),
+        }
+    }
+
+    /// Executes all batches delivered from now on with the given Wasm module
+    /// installed on `canister_id`.
+    pub(crate) fn substitute_wasm(&self, canister_id: CanisterId, module: CanisterModule) {
+        *self.substitution.write().unwrap() = Some(WasmSubstitution {
+            canister_id,
+            module,
+        });
+    }
+
+    /// Returns whether the Wasm module of some canister is substituted.
+    pub(crate) fn substitutes_wasm(&self) -> bool {
+        self.substitution.read().unwrap().is_some()
+    }
+
+    /// Writes a trace of all batches executed from now on to `path`.
+    pub(crate) fn trace_execution(&self, path: &Path) -> std::io::Result<()> {
+        let tracer = ExecutionTracer::new(path, self.metrics_registry.clone())?;
+        *self.tracer.lock().unwrap() = Some(tracer);
+        Ok(())
+    }
+
+    /// Returns the error that stopped the execution trace, if any.
+    pub(crate) fn take_trace_error(&self) -> Option<String> {
+        self.trace_error.lock().unwrap().take()
+    }
+
+    /// Runs `f` on the tracer, if tracing. The first error stops the trace
+    /// and is kept for [`Self::take_trace_error`], as the state manager
+    /// interface has no way to return it to message routing.
+    fn trace(&self, f: impl FnOnce(&mut ExecutionTracer) -> Result<(), String>) {
+        let mut tracer = self.tracer.lock().unwrap();
+        if let Some(err) = tracer.as_mut().map(f).and_then(Result::err) {
+            eprintln!("Stopped tracing the execution: {}", err);
+            *tracer = None;
+            *self.trace_error.lock().unwrap() = Some(err);
+        }
+    }
+
+    fn substitute(&self, height: Height, state: &mut ReplicatedState) {
+        let substitution = self.substitution.read().unwrap();
+        let Some(substitution) = substitution.as_ref() else {
+            return;
+        };
+        let execution_state = state
+            .canister_state_mut(&substitution.canister_id)
+            .and_then(|canister| canister.execution_state.as_mut());
+        let Some(execution_state) = execution_state else {
+            panic!(
+                "Canister {} has no Wasm module installed at height {}",
+                substitution.canister_id, height
+            );
+        };
+        if execution_state.wasm_binary.binary.module_hash() == substitution.module.module_hash() {
+            return;
+        }
+        *execution_state = substituted_execution_state(
+            &self.hypervisor,
+            substitution.canister_id,
+            &substitution.module,
+            execution_state,
+        )
+        .unwrap_or_else(|err| {
+            panic!(
+                "Couldn't substitute the Wasm module of canister {} at height {}: {}",
+                substitution.canister_id, height, err
+            )
+        });
+        println!(
+            "Substituted the Wasm module of canister {} on top of height {}",
+            substitution.canister_id, height
+        );
+    }
+}
+
+impl StateManager for InterceptingStateManager {
+    fn list_state_hashes_to_certify(&self) -> Vec<(Height, CryptoHashOfPartialState)> {
+        self.inner.list_state_hashes_to_certify()
+    }
+
+    fn deliver_state_certification(&self, certification: Certification) {
+        self.inner.deliver_state_certification(certification)
+    }
+
+    fn get_state_hash_at(&self, height: Height) -> Result<CryptoHashOfState, StateHashError> {
+        self.inner.get_state_hash_at(height)
+    }
+
+    fn fetch_state(
+        &self,
+        height: Height,
+        root_hash: CryptoHashOfState,
+        cup_interval_length: Height,
+    ) {
+        self.inner
+            .fetch_state(height, root_hash, cup_interval_length)
+    }
+
+    fn list_state_heights(&self, cert_mask: CertificationMask) -> Vec<Height> {
+        self.inner.list_state_heights(cert_mask)
+    }
+
+    fn remove_states_below(&self, height: Height) {
+        self.inner.remove_states_below(height)
+    }
+
+    fn remove_inmemory_states_below(&self, height: Height) {
+        self.inner.remove_inmemory_states_below(height)
+    }
+
+    fn commit_and_certify(
+        &self,
+        state: ReplicatedState,
+        height: Height,
+        scope: CertificationScope,
+    ) {
+        self.trace(|tracer| tracer.after_execution(&state, height));
+        self.inner.commit_and_certify(state, height, scope)
+    }
+
+    fn take_tip(&self) -> (Height, ReplicatedState) {
+        let (height, mut state) = self.inner.take_tip();
+        self.substitute(height, &mut state);
+        self.trace(|tracer| tracer.before_execution(&state));
+        (height, state)
+    }
+
+    fn take_tip_at(&self, height: Height) -> StateManagerResult<ReplicatedState> {
+        let mut state = self.inner.take_tip_at(height)?;
+        self.substitute(height, &mut state);
+        self.trace(|tracer| tracer.before_execution(&state));
+        Ok(state)
+    }
+
+    fn report_diverged_checkpoint(&self, height: Height) {
+        self.inner.report_diverged_checkpoint(height)
+    }
+}
+
+impl StateReader for InterceptingStateManager {
+    type State = ReplicatedState;
+
+    fn get_state_at(&self, height: Height) -> StateManagerResult<Labeled<Arc<Self::State>>> {
+        self.inner.get_state_at(height)
+    }
+
+    fn get_latest_state(&self) -> Labeled<Arc<Self::State>> {
+        self.inner.get_latest_state()
+    }
+
+    fn latest_state_height(&self) -> Height {
+        self.inner.latest_state_height()
+    }
+
+    fn latest_certified_height(&self) -> Height {
+        self.inner.latest_certified_height()
+    }
+
+    fn read_certified_state(
+        &self,
+        paths: &LabeledTree<()>,
+    ) -> Option<(Arc<Self::State>, MixedHashTree, Certification)> {
+        self.inner.read_certified_state(paths)
+    }
+
+    fn get_certified_state_snapshot(
+        &self,
+    ) -> Option<Box<dyn CertifiedStateSnapshot<State = Self::State> + 'static>> {
+        self.inner.get_certified_state_snapshot()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ic_management_canister_types::{CanisterUpgradeOptions, WasmMemoryPersistence};
+    use ic_replicated_state::canister_state::system_state::CyclesUseCase;
+    use ic_test_utilities::{
+        state::{CanisterStateBuilder, ReplicatedStateBuilder},
+        types::ids::canister_test_id,
+    };
+    use ic_test_utilities_execution_environment::{ExecutionTest, ExecutionTestBuilder};
+    use ic_types::Cycles;
+
+    /// A module that supports enhanced orthogonal persistence, so that an
+    /// upgrade can keep its main memory, with the given memory limits.
+    fn module_with_memory(memory_limits: &str) -> Vec<u8> {
+        let mut binary = wat::parse_str(format!(
+            r#"(module
+                (func (export "canister_update read"))
+                (memory {memory_limits})
+            )"#
+        ))
+        .unwrap();
+        // Append a custom section named `icp:private enhanced-orthogonal-persistence`
+        // with an empty content.
+        let name = b"icp:private enhanced-orthogonal-persistence";
+        binary.push(0);
+        binary.push(name.len() as u8 + 1);
+        binary.push(name.len() as u8);
+        binary.extend_from_slice(name);
+        binary
+    }
+
+    #[test]
+    fn test_substituted_execution_state_applies_the_upgrade_rules() {
+        let mut test = ExecutionTestBuilder::new().build();
+        let canister_id = test
+            .canister_from_binary(module_with_memory("2 3"))
+            .unwrap();
+        let old_state = test.execution_state(canister_id).clone();
+
+        let substitute = |test: &ExecutionTest, binary: &[u8]| {
+            substituted_execution_state(
+                test.hypervisor_deprecated(),
+                canister_id,
+                &CanisterModule::new(binary.to_vec()),
+                &old_state,
+            )
+        };
+        let upgrade = |test: &mut ExecutionTest, binary: &[u8]| {
+            test.upgrade_canister_v2(
+                canister_id,
+                binary.to_vec(),
+                Some(CanisterUpgradeOptions {
+                    skip_pre_upgrade: None,
+                    wasm_memory_persistence: Some(WasmMemoryPersistence::Keep),
+                }),
+            )
+        };
+
+        // The kept main memory of two Wasm pages exceeds the declared maximum,
+        // so both the substitution and an upgrade that keeps it fail.
+        let too_small = module_with_memory("1 1");
+        let err = substitute(&test, &too_small).unwrap_err();
+        assert!(
+            err.contains("maximum main memory size of 1 Wasm pages"),
+            "{}",
+            err
+        );
+        let err = upgrade(&mut test, &too_small).unwrap_err();
+        assert!(
+            err.description()
+                .contains("maximum main memory size of 1 Wasm pages"),
+            "{}",
+            err
+        );
+
+        // Otherwise, both succeed and keep the main memory.
+        let compatible = module_with_memory("1 3");
+        let new_state = substitute(&test, &compatible).unwrap();
+        assert_eq!(new_state.wasm_memory.size, old_state.wasm_memory.size);
+        assert_eq!(
+            new_state.wasm_binary.binary.module_hash(),
+            CanisterModule::new(compatible.clone()).module_hash()
+        );
+        upgrade(&mut test, &compatible).unwrap();
+        assert_eq!(
+            test.execution_state(canister_id).wasm_memory.size,
+            old_state.wasm_memory.size
+        );
+    }
+
+    #[test]
+    fn test_execution_tracer() {
+        let tmp = tempfile::tempdir().expect("Could not create a temp dir");
+        let path = tmp.path().join("trace.json");
+        let metrics_registry = MetricsRegistry::new();
+        let instructions =
+            metrics_registry.histogram(ROUND_INSTRUCTIONS_METRIC, "Instructions.", vec![1_000.0]);
+        instructions.observe(500.0);
+        let mut tracer = ExecutionTracer::new(&path, metrics_registry).unwrap();
+
+        let executed = canister_test_id(1);
+        let idle = canister_test_id(2);
+        let tip = ReplicatedStateBuilder::new()
+            .with_canister(
+                CanisterStateBuilder::new()
+                    .with_canister_id(executed)
+                    .with_cycles(1_000_u128)
+                    .build(),
+            )
+            .with_canister(CanisterStateBuilder::new().with_canister_id(idle).build())
+            .build();
+        tracer.before_execution(&tip).unwrap();
+
+        // Execute two messages on one of the canisters.
+        let mut state = tip.clone();
+        let canister = state.canister_state_mut(&executed).unwrap();
+        canister.system_state.canister_metrics.executed += 2;
+        canister
+            .system_state
+            .remove_cycles(Cycles::new(300), CyclesUseCase::Instructions);
+        state.metadata.subnet_metrics.update_transactions_total += 2;
+        instructions.observe(700.0);
+        instructions.observe(500.0);
+        tracer.after_execution(&state, Height::new(7)).unwrap();
+        drop(tracer);
+
+        let expected = BatchTrace {
+            height: 7,
+            messages_executed: 2,
+            instructions: 1_200,
+            cycles_consumed: 300,
+            canisters: vec![CanisterTrace {
+                canister_id: executed.to_string(),
+                executions: 2,
+                cycles_consumed: 300,
+                balance_before: 1_000,
+                balance_after: 700,
+            }],
+        };
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            format!("{}\n", serde_json::to_string(&expected).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_execution_tracer_requires_round_instructions_metric() {
+        let tmp = tempfile::tempdir().expect("Could not create a temp dir");
+        let mut tracer =
+            ExecutionTracer::new(&tmp.path().join("trace.json"), MetricsRegistry::new()).unwrap();
+        assert_eq!(
+            tracer.before_execution(&ReplicatedStateBuilder::new().build()),
+            Err("Metric execution_round_instructions is not registered".to_string())
+        );
+    }
+}
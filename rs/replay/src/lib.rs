@@ -24,14 +24,17 @@ use ic_nns_constants::GOVERNANCE_CANISTER_ID;
 use ic_protobuf::registry::subnet::v1::InitialNiDkgTranscriptRecord;
 use ic_protobuf::types::v1 as pb;
 use ic_types::ReplicaVersion;
+use ic_wasm_types::CanisterModule;
 use prost::Message;
 use std::cell::RefCell;
 use std::convert::TryFrom;
+use std::path::Path;
 use std::rc::Rc;
 
 mod backup;
 pub mod cmd;
 pub mod ingress;
+mod interceptor;
 mod mocks;
 pub mod player;
 mod validator;
@@ -109,6 +112,26 @@ pub fn replay(args: ReplayToolArgs) -> ReplayResult {
             }
         }
 
+        if let Some(SubCommand::SubstituteWasm(cmd)) = subcmd {
+            let question = format!(
+                "Replaying with a substituted Wasm module for canister {} makes the state \
+                 diverge from the subnet's state. The resulting checkpoints are written to {:?} \
+                 and must not be used for recovery.\nContinue?",
+                cmd.canister_id, cmd.scratch_state_dir
+            );
+            if !consent_given(&question) {
+                return;
+            }
+            link_checkpoints(&cfg.state_manager.state_root(), &cmd.scratch_state_dir)
+                .unwrap_or_else(|err| {
+                    panic!(
+                        "Couldn't set up the scratch state directory {:?}: {}",
+                        cmd.scratch_state_dir, err
+                    )
+                });
+            cfg.state_manager.state_root = cmd.scratch_state_dir.clone();
+        }
+
         if let Some(SubCommand::RestoreFromBackup(cmd)) = subcmd {
             let _enter_guard = rt.enter();
 
@@ -129,9 +152,17 @@ pub fn replay(args: ReplayToolArgs) -> ReplayResult {
         {
             let _enter_guard = rt.enter();
             let player = match (subcmd.as_ref(), target_height) {
+                (
+                    Some(
+                        SubCommand::DumpCanister(_)
+                        | SubCommand::SubstituteWasm(_)
+                        | SubCommand::TraceExecution(_),
+                    ),
+                    target_height,
+                ) => Player::new(cfg, subnet_id).with_replay_target_height(target_height),
                 (Some(_), Some(_)) => {
                     panic!(
-                    "Target height cannot be used with any sub-command in subnet-recovery mode."
+                    "Target height cannot be used with this sub-command in subnet-recovery mode."
                 );
                 }
                 (_, target_height) => {
@@ -139,6 +170,24 @@ pub fn replay(args: ReplayToolArgs) -> ReplayResult {
                 }
             };
 
+            let player = match subcmd {
+                Some(SubCommand::SubstituteWasm(cmd)) => {
+                    let wasm = std::fs::read(&cmd.wasm_file).unwrap_or_else(|err| {
+                        panic!("Couldn't read the Wasm module {:?}: {}", cmd.wasm_file, err)
+                    });
+                    let player =
+                        player.with_substituted_wasm(cmd.canister_id, CanisterModule::new(wasm));
+                    match &cmd.trace {
+                        Some(path) => player.with_execution_trace(path),
+                        None => player,
+                    }
+                }
+                Some(SubCommand::TraceExecution(cmd)) => {
+                    player.with_execution_trace(&cmd.output_file)
+                }
+                _ => player,
+            };
+
             if let Some(SubCommand::GetRecoveryCup(cmd)) = subcmd {
                 cmd_get_recovery_cup(&player, cmd).unwrap();
                 return;
@@ -200,6 +249,11 @@ pub fn replay(args: ReplayToolArgs) -> ReplayResult {
                     if let Some(SubCommand::UpdateRegistryLocalStore) = subcmd {
                         player.update_registry_local_store();
                         Ok(player.get_latest_state_params(None, Vec::new()))
+                    } else if let Some(SubCommand::DumpCanister(cmd)) = subcmd {
+                        player
+                            .dump_canister(cmd.canister_id, &cmd.output_dir)
+                            .unwrap();
+                        Ok(state_params)
                     } else {
                         Ok(state_params)
                     }
@@ -223,6 +277,37 @@ pub fn consent_given(question: &str) -> bool {
     matches!(s.as_str(), "\n" | "y\n" | "Y\n")
 }
 
+/// Links the checkpoints of the state in `state_root` into the new directory
+/// `scratch_root`, so that replaying on top of `scratch_root` leaves the
+/// original state untouched. Checkpoint files are never modified in place, so
+/// they are hard-linked where possible and copied otherwise.
+fn link_checkpoints(state_root: &Path, scratch_root: &Path) -> std::io::Result<()> {
+    fn link_recursively(src: &Path, dst: &Path) -> std::io::Result<()> {
+        std::fs::create_dir_all(dst)?;
+        for entry in std::fs::read_dir(src)? {
+            let entry = entry?;
+            let dst = dst.join(entry.file_name());
+            if entry.file_type()?.is_dir() {
+                link_recursively(&entry.path(), &dst)?;
+            } else if std::fs::hard_link(entry.path(), &dst).is_err() {
+                std::fs::copy(entry.path(), &dst)?;
+            }
+        }
+        Ok(())
+    }
+
+    if scratch_root.exists() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::AlreadyExists,
+            "the directory already exists",
+        ));
+    }
+    link_recursively(
+        &state_root.join("checkpoints"),
+        &scratch_root.join("checkpoints"),
+    )
+}
+
 // Creates a recovery CUP by using the latest CUP and overriding the height and
 // the state hash.
 fn cmd_get_recovery_cup(
@@ -1,5 +1,6 @@
-use crate::backup::{cup_file_name, rename_file};
+use crate::backup::BackupSpool;
 use crate::ingress::IngressWithPrinter;
+use crate::interceptor::InterceptingStateManager;
 use crate::{
     backup,
     validator::{InvalidArtifact, ReplayValidator},
@@ -47,7 +48,9 @@ use ic_registry_transport::{
     deserialize_get_value_response, serialize_get_changes_since_request,
     serialize_get_value_request,
 };
-use ic_replicated_state::ReplicatedState;
+use ic_replicated_state::{
+    num_bytes_try_from, page_map::PAGE_SIZE, CanisterState, Memory, PageIndex, ReplicatedState,
+};
 use ic_state_manager::StateManagerImpl;
 use ic_types::batch::{BatchMessages, BlockmakerMetrics};
 use ic_types::consensus::certification::CertificationShare;
@@ -58,19 +61,22 @@ use ic_types::{
     ingress::{IngressState, IngressStatus, WasmResult},
     messages::UserQuery,
     time::current_time,
-    CryptoHashOfState, Height, PrincipalId, Randomness, RegistryVersion, ReplicaVersion, SubnetId,
-    Time, UserId,
+    CanisterId, CryptoHashOfState, Height, PrincipalId, Randomness, RegistryVersion,
+    ReplicaVersion, SubnetId, Time, UserId,
 };
 use ic_types::{
     consensus::CatchUpContentProtobufBytes,
     crypto::{CombinedThresholdSig, CombinedThresholdSigOf},
 };
 use ic_types::{CryptoHashOfPartialState, NodeId};
+use ic_wasm_types::CanisterModule;
 use serde::{Deserialize, Serialize};
 use slog_async::AsyncGuard;
 use std::collections::{HashMap, HashSet};
 use std::{
     collections::BTreeMap,
+    fs::File,
+    io::{BufWriter, Write},
     path::{Path, PathBuf},
     sync::Arc,
     time::Duration,
@@ -101,14 +107,35 @@ pub enum ReplayError {
     CUPVerificationFailed(Height),
     /// Replay was successful, but manual inspection is required to choose correct state.
     ManualInspectionRequired(StateParams),
+    /// Replay was successful, but the execution trace stopped with the given error.
+    ExecutionTraceFailed(String),
 }
 
 pub type ReplayResult = Result<StateParams, ReplayError>;
 
+/// A summary of a canister's state, written next to its memory contents by
+/// [`Player::dump_canister`].
+#[derive(Clone, Debug, Serialize)]
+pub struct CanisterDumpSummary {
+    pub canister_id: String,
+    pub height: Height,
+    pub status: String,
+    pub controllers: Vec<String>,
+    pub cycles_balance: u128,
+    pub module_hash: Option<String>,
+    pub wasm_memory_bytes: u64,
+    pub stable_memory_bytes: u64,
+    pub ingress_queue_messages: usize,
+    pub input_queues_messages: usize,
+    pub output_queues_messages: usize,
+}
+
 /// The main ic-replay component that sets up consensus and execution
 /// environment to replay past blocks.
 pub struct Player {
     state_manager: Arc<StateManagerImpl>,
+    /// The state manager that message routing executes batches with.
+    intercepting_state_manager: Arc<InterceptingStateManager>,
     message_routing: Arc<dyn MessageRouting>,
     consensus_pool: Option<ConsensusPoolImpl>,
     membership: Option<Arc<Membership>>,
@@ -167,8 +194,9 @@ impl Player {
             .join(subnet_id.to_string())
             .join(replica_version.to_string());
         // Extract the genesis CUP and instantiate a new pool.
-        let cup_file = backup::cup_file_name(&backup_dir, Height::from(start_height));
-        let initial_cup_proto = backup::read_cup_proto_file(&cup_file)
+        let spool = BackupSpool::open(&backup_dir);
+        let cup_file = spool.cup_file_name(Height::from(start_height));
+        let initial_cup_proto = backup::read_cup_proto_file(&spool, &cup_file)
             .expect("CUP of the starting block should be valid");
         // This would create a new pool with just the genesis CUP.
         let pool = ConsensusPoolImpl::new(
@@ -304,9 +332,15 @@ impl Player {
             Arc::clone(&cycles_account_manager),
             Arc::clone(&state_manager) as Arc<_>,
             state_manager.get_fd_factory(),
+            None,
         );
-        let message_routing = Arc::new(MessageRoutingImpl::new(
+        let intercepting_state_manager = Arc::new(InterceptingStateManager::new(
             state_manager.clone(),
+            execution_service.hypervisor,
+            metrics_registry.clone(),
+        ));
+        let message_routing = Arc::new(MessageRoutingImpl::new(
+            intercepting_state_manager.clone(),
             state_manager.clone(),
             execution_service.ingress_history_writer.clone(),
             execution_service.scheduler,
@@ -350,6 +384,7 @@ impl Player {
         });
         Player {
             state_manager,
+            intercepting_state_manager,
             message_routing,
             consensus_pool,
             membership,
@@ -376,6 +411,29 @@ impl Player {
         self
     }
 
+    /// Replay all batches with the given Wasm module installed on the given
+    /// canister, instead of its actual one.
+    ///
+    /// The memories and globals of the installed module are kept, so the
+    /// substitute should be a build of the same canister, e.g. one containing
+    /// a fix or additional logging, that declares a compatible main memory
+    /// and the same globals. The resulting states diverge from the subnet's,
+    /// so certifications are not redelivered.
+    pub fn with_substituted_wasm(self, canister_id: CanisterId, module: CanisterModule) -> Self {
+        self.intercepting_state_manager
+            .substitute_wasm(canister_id, module);
+        self
+    }
+
+    /// Write a trace of every replayed batch to the given file, as one line of
+    /// JSON per batch.
+    pub fn with_execution_trace(self, path: &Path) -> Self {
+        self.intercepting_state_manager
+            .trace_execution(path)
+            .unwrap_or_else(|err| panic!("Couldn't create the trace file {:?}: {}", path, err));
+        self
+    }
+
     /// In case a consensus pool was supplied, replay past finalized but
     /// un-executed blocks by delivering ingress messages for execution,
     /// and make a full checkpoint of the latest state when they all finish.
@@ -440,6 +498,9 @@ impl Player {
             self.get_latest_state_params(Some(latest_context_time), invalid_artifacts);
         println!("Latest registry version: {}", state_params.registry_version);
 
+        if let Some(err) = self.intercepting_state_manager.take_trace_error() {
+            return Err(ReplayError::ExecutionTraceFailed(err));
+        }
         if inspection_required {
             Err(ReplayError::ManualInspectionRequired(state_params))
         } else {
@@ -490,8 +551,14 @@ impl Player {
 
         // Redeliver certifications to state manager. It will panic if there is any
         // mismatch.
-        let manual_inspection_required =
-            self.redeliver_certifications(certification_pool, validator);
+        let manual_inspection_required = if self.intercepting_state_manager.substitutes_wasm() {
+            println!(
+                "Skipping the redelivery of certifications, as a Wasm module was substituted."
+            );
+            false
+        } else {
+            self.redeliver_certifications(certification_pool, validator)
+        };
 
         println!("All blocks successfully replayed.");
         // We only want to persist the checkpoint after the latest batch.
@@ -845,6 +912,24 @@ impl Player {
         }
     }
 
+    /// Write the Wasm module, the heap and stable memory contents and the
+    /// queues of the given canister in the latest state to `output_dir`.
+    pub fn dump_canister(&self, canister_id: CanisterId, output_dir: &Path) -> Result<(), String> {
+        let state = self.state_manager.get_latest_state();
+        let height = state.height();
+        let canister = state
+            .get_ref()
+            .canister_state(&canister_id)
+            .ok_or_else(|| format!("Canister {} not found at height {}", canister_id, height))?;
+        dump_canister_state(canister, height, output_dir)?;
+
+        println!(
+            "Dumped canister {} at height {} to {:?}",
+            canister_id, height, output_dir
+        );
+        Ok(())
+    }
+
     /// Return the highest CatchUpPackage
     pub fn get_highest_catch_up_package(&self) -> CatchUpPackage {
         PoolReader::new(self.consensus_pool.as_ref().unwrap()).get_highest_catch_up_package()
@@ -926,9 +1011,9 @@ impl Player {
             .expect("No backup path found")
             .clone();
         let start_height = Height::from(start_height);
-        let mut height_to_batches =
-            backup::heights_to_artifacts_metadata(&backup_dir, start_height)
-                .unwrap_or_else(|err| panic!("File scanning failed: {:?}", err));
+        let spool = BackupSpool::open(&backup_dir);
+        let mut height_to_batches = backup::heights_to_artifacts_metadata(&spool, start_height)
+            .unwrap_or_else(|err| panic!("File scanning failed: {:?}", err));
         println!(
             "Restoring the replica state of subnet {:?} starting from the height {:?}",
             backup_dir, start_height
@@ -937,9 +1022,9 @@ impl Player {
         // Assert consistent initial state
         if let Err(err) = self.verify_latest_cup() {
             if let ReplayError::CUPVerificationFailed(height) = err {
-                let file = cup_file_name(&backup_dir, height);
+                let file = spool.cup_file_name(height);
                 println!("Invalid CUP detected: {:?}", file);
-                rename_file(&file);
+                spool.rename_file(&file);
             }
             return Err(err);
         }
@@ -957,6 +1042,7 @@ impl Player {
                 self.registry.clone(),
                 self.crypto.clone(),
                 self.consensus_pool.as_mut().unwrap(),
+                &spool,
                 &mut height_to_batches,
                 self.subnet_id,
                 self.validator.as_ref().unwrap(),
@@ -984,14 +1070,14 @@ impl Player {
                 Err(backup::ExitPoint::CUPHeightWasFinalized(cup_height)) => {
                     backup::insert_cup_at_height(
                         self.consensus_pool.as_mut().unwrap(),
-                        &backup_dir,
+                        &spool,
                         cup_height,
                     )?;
                     if let Err(err) = self.assert_consistency_and_clean_up() {
                         if let ReplayError::CUPVerificationFailed(height) = err {
-                            let file = cup_file_name(&backup_dir, height);
+                            let file = spool.cup_file_name(height);
                             println!("Invalid CUP detected: {:?}", file);
-                            rename_file(&file);
+                            spool.rename_file(&file);
                         }
                         return Err(err);
                     }
@@ -1128,6 +1214,82 @@ impl Player {
     }
 }
 
+/// Write the Wasm module, the heap and stable memory contents and the queues
+/// of the given canister, as well as a [`CanisterDumpSummary`], to
+/// `output_dir`.
+fn dump_canister_state(
+    canister: &CanisterState,
+    height: Height,
+    output_dir: &Path,
+) -> Result<(), String> {
+    std::fs::create_dir_all(output_dir)
+        .map_err(|err| format!("Couldn't create {:?}: {}", output_dir, err))?;
+
+    let execution_state = canister.execution_state.as_ref();
+    if let Some(execution_state) = execution_state {
+        let path = output_dir.join("canister.wasm");
+        std::fs::write(&path, execution_state.wasm_binary.binary.as_slice())
+            .map_err(|err| format!("Couldn't write {:?}: {}", path, err))?;
+        write_memory(
+            &execution_state.wasm_memory,
+            &output_dir.join("wasm_memory.bin"),
+        )?;
+        write_memory(
+            &execution_state.stable_memory,
+            &output_dir.join("stable_memory.bin"),
+        )?;
+    }
+    let queues = canister.system_state.queues();
+    let path = output_dir.join("queues.txt");
+    std::fs::write(&path, format!("{:#?}", queues))
+        .map_err(|err| format!("Couldn't write {:?}: {}", path, err))?;
+
+    let memory_bytes = |memory: &Memory| {
+        num_bytes_try_from(memory.size)
+            .map(|bytes| bytes.get())
+            .unwrap_or_default()
+    };
+    let summary = CanisterDumpSummary {
+        canister_id: canister.canister_id().to_string(),
+        height,
+        status: canister.system_state.status_string().to_string(),
+        controllers: canister
+            .system_state
+            .controllers
+            .iter()
+            .map(|controller| controller.to_string())
+            .collect(),
+        cycles_balance: canister.system_state.balance().get(),
+        module_hash: execution_state
+            .map(|execution_state| hex::encode(execution_state.wasm_binary.binary.module_hash())),
+        wasm_memory_bytes: execution_state
+            .map(|execution_state| memory_bytes(&execution_state.wasm_memory))
+            .unwrap_or_default(),
+        stable_memory_bytes: execution_state
+            .map(|execution_state| memory_bytes(&execution_state.stable_memory))
+            .unwrap_or_default(),
+        ingress_queue_messages: queues.ingress_queue_message_count(),
+        input_queues_messages: queues.input_queues_message_count(),
+        output_queues_messages: queues.output_queues_message_count(),
+    };
+    let path = output_dir.join("summary.json");
+    let json = serde_json::to_string_pretty(&summary).map_err(|err| format!("{}", err))?;
+    std::fs::write(&path, json).map_err(|err| format!("Couldn't write {:?}: {}", path, err))
+}
+
+/// Write the contents of the given memory to a file, page by page.
+fn write_memory(memory: &Memory, path: &Path) -> Result<(), String> {
+    let num_bytes = num_bytes_try_from(memory.size)?.get() as usize;
+    let write = || -> std::io::Result<()> {
+        let mut file = BufWriter::new(File::create(path)?);
+        for page in 0..num_bytes / PAGE_SIZE {
+            file.write_all(memory.page_map.get_page(PageIndex::new(page as u64)))?;
+        }
+        file.flush()
+    };
+    write().map_err(|err| format!("Couldn't write {:?}: {}", path, err))
+}
+
 /// Return the set of signers that created multiple valid certification shares for the same height
 fn find_malicious_nodes(
     certification_pool: &CertificationPoolImpl,
@@ -1324,7 +1486,12 @@ fn get_state_hash<T>(
 #[cfg(test)]
 mod tests {
     use ic_logger::replica_logger::no_op_logger;
-    use ic_test_utilities::{consensus::fake::FakeSigner, types::ids::node_test_id};
+    use ic_replicated_state::canister_state::WASM_PAGE_SIZE_IN_BYTES;
+    use ic_test_utilities::{
+        consensus::fake::FakeSigner,
+        state::CanisterStateBuilder,
+        types::ids::{canister_test_id, node_test_id},
+    };
     use ic_types::{
         consensus::certification::{
             CertificationContent, CertificationMessage, CertificationShare,
@@ -1451,4 +1618,38 @@ mod tests {
             f
         ));
     }
+    #[test]
+    fn test_dump_canister_state() {
+        let tmp = tempfile::tempdir().expect("Could not create a temp dir");
+        let wasm = vec![0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00];
+        let canister = CanisterStateBuilder::new()
+            .with_canister_id(canister_test_id(7))
+            .with_wasm(wasm.clone())
+            .with_stable_memory(vec![1, 2, 3])
+            .build();
+
+        dump_canister_state(&canister, Height::from(42), tmp.path()).unwrap();
+
+        let read = |name: &str| std::fs::read(tmp.path().join(name)).unwrap();
+        assert_eq!(read("canister.wasm"), wasm);
+        assert!(read("wasm_memory.bin").is_empty());
+        let stable_memory = read("stable_memory.bin");
+        assert_eq!(stable_memory.len(), WASM_PAGE_SIZE_IN_BYTES);
+        assert_eq!(stable_memory[..3], [1, 2, 3]);
+        assert!(stable_memory[3..].iter().all(|byte| *byte == 0));
+        assert!(!read("queues.txt").is_empty());
+
+        let summary: serde_json::Value = serde_json::from_slice(&read("summary.json")).unwrap();
+        assert_eq!(summary["canister_id"], canister_test_id(7).to_string());
+        assert_eq!(summary["height"], 42);
+        assert_eq!(
+            summary["module_hash"],
+            hex::encode(CanisterModule::new(wasm).module_hash())
+        );
+        assert_eq!(summary["wasm_memory_bytes"], 0);
+        assert_eq!(
+            summary["stable_memory_bytes"],
+            WASM_PAGE_SIZE_IN_BYTES as u64
+        );
+    }
 }
@@ -260,11 +260,18 @@ pub fn setup_consensus_and_p2p(
     } = artifact_pools;
 
     if ENABLE_NEW_P2P_CONSENSUS {
-        new_p2p_consensus.add_client(
+        let assembler = ic_consensus_manager::ConsensusArtifactAssembler::new(
+            log.clone(),
+            metrics_registry,
+            ingress_pool.clone(),
+            consensus_pool.clone(),
+        );
+        new_p2p_consensus.add_client_with_assembler(
             consensus_rx,
             consensus_pool,
             p2p_clients.consensus.priority_fn_producer,
             p2p_clients.consensus.client_handle.sender,
+            Arc::new(assembler),
         );
     } else {
         backends.insert(
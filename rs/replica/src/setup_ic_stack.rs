@@ -30,7 +30,7 @@ use ic_types::{
     NodeId, SubnetId,
 };
 use ic_xnet_endpoint::{XNetEndpoint, XNetEndpointConfig};
-use ic_xnet_payload_builder::XNetPayloadBuilderImpl;
+use ic_xnet_payload_builder::{XNetPayloadBuilderImpl, XNetRemoteQueryClient};
 use std::sync::{Arc, RwLock};
 use tokio::sync::mpsc::UnboundedSender;
 
@@ -173,6 +173,13 @@ pub fn construct_ic_stack(
         subnet_config.cycles_account_manager_config,
     ));
 
+    // Query calls to canisters on other subnets are sent to their XNet endpoints.
+    let remote_query_client = Arc::new(XNetRemoteQueryClient::new(
+        registry.clone(),
+        Arc::clone(&crypto) as Arc<_>,
+        rt_handle_xnet.clone(),
+        metrics_registry,
+    ));
     let execution_services = ExecutionServices::setup_execution(
         log.clone(),
         metrics_registry,
@@ -183,6 +190,7 @@ pub fn construct_ic_stack(
         cycles_account_manager.clone(),
         state_manager.clone(),
         state_manager.get_fd_factory(),
+        Some(remote_query_client),
     );
     // ---------- MESSAGE ROUTING DEPS FOLLOW ----------
     let certified_stream_store: Arc<dyn CertifiedStreamStore> =
@@ -219,6 +227,7 @@ pub fn construct_ic_stack(
     let xnet_endpoint = XNetEndpoint::new(
         rt_handle_xnet.clone(),
         Arc::clone(&certified_stream_store),
+        Arc::clone(&execution_services.remote_query_handler),
         Arc::clone(&crypto) as Arc<_>,
         registry.clone(),
         xnet_config,
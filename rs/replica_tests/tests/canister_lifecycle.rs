@@ -6,13 +6,13 @@ use ic_error_types::{ErrorCode, RejectCode};
 use ic_management_canister_types::{
     self as ic00, CanisterChange, CanisterIdRecord, CanisterInstallMode,
     CanisterSettingsArgsBuilder, CanisterStatusResultV2, CanisterStatusType, EmptyBlob,
-    InstallCodeArgs, LogVisibility, Method, Payload, UpdateSettingsArgs, IC_00,
+    InstallCodeArgs, LogVisibility, MemoryMetrics, Method, Payload, UpdateSettingsArgs, IC_00,
 };
 use ic_registry_provisional_whitelist::ProvisionalWhitelist;
 use ic_replica_tests as utils;
 use ic_replica_tests::assert_reject;
 use ic_test_utilities::assert_utils::assert_balance_equals;
-use ic_test_utilities::universal_canister::management::SkipPreUpgrade;
+use ic_test_utilities::universal_canister::management::CanisterUpgradeOptions;
 use ic_test_utilities::universal_canister::{call_args, management, wasm, UNIVERSAL_CANISTER_WASM};
 use ic_types::{ingress::WasmResult, CanisterId, ComputeAllocation, Cycles, NumBytes, PrincipalId};
 use maplit::btreeset;
@@ -707,12 +707,24 @@ fn can_get_canister_information() {
                 canister_a.get(),
                 vec![canister_a.get()],
                 NumBytes::from((2 * size_of::<CanisterChange>() + 2 * size_of::<PrincipalId>()) as u64),
+                MemoryMetrics::new(
+                    NumBytes::from(0),
+                    NumBytes::from(0),
+                    NumBytes::from(0),
+                    NumBytes::from(0),
+                    NumBytes::from(0),
+                    NumBytes::from((2 * size_of::<CanisterChange>() + 2 * size_of::<PrincipalId>()) as u64),
+                    NumBytes::from(0),
+                    NumBytes::from(0),
+                    NumBytes::from(0),
+                ),
                 num_cycles.get(),
                 ComputeAllocation::default().as_percent(),
                 None,
                 2592000,
                 Some(5_000_000_000_000u128),
                 LogVisibility::default(),
+                0,
                 0u128,
                 0u128,
                 0u128,
@@ -765,12 +777,14 @@ fn can_get_canister_information() {
                     // We don't assert a specific memory size since the universal canister's
                     // size changes between updates.
                     NumBytes::from(0),
+                    MemoryMetrics::default(),
                     num_cycles.get(),
                     ComputeAllocation::default().as_percent(),
                     None,
                     259200,
                     None,
                     LogVisibility::default(),
+                    0,
                     0u128,
                     0u128,
                     0u128,
@@ -990,7 +1004,10 @@ fn test_canister_skip_upgrade() {
         assert_matches!(
             canister.update(wasm().call(
                 management::install_code(canister_id, UNIVERSAL_CANISTER_WASM).with_mode(
-                    management::InstallMode::Upgrade(Some(SkipPreUpgrade(Some(false)))),
+                    management::InstallMode::Upgrade(Some(CanisterUpgradeOptions {
+                        skip_pre_upgrade: Some(false),
+                        wasm_memory_persistence: None
+                    })),
                 ),
             )),
             Ok(WasmResult::Reject(_))
@@ -1000,7 +1017,10 @@ fn test_canister_skip_upgrade() {
         assert_matches!(
             canister.update(wasm().call(
                 management::install_code(canister_id, UNIVERSAL_CANISTER_WASM).with_mode(
-                    management::InstallMode::Upgrade(Some(SkipPreUpgrade(Some(true),)))
+                    management::InstallMode::Upgrade(Some(CanisterUpgradeOptions {
+                        skip_pre_upgrade: Some(true),
+                        wasm_memory_persistence: None
+                    }))
                 ),
             )),
             Ok(WasmResult::Reply(_))
@@ -1011,7 +1031,10 @@ fn test_canister_skip_upgrade() {
         assert_matches!(
             canister.update(wasm().call(
                 management::install_code(canister_id, UNIVERSAL_CANISTER_WASM).with_mode(
-                    management::InstallMode::Upgrade(Some(SkipPreUpgrade(Some(false)))),
+                    management::InstallMode::Upgrade(Some(CanisterUpgradeOptions {
+                        skip_pre_upgrade: Some(false),
+                        wasm_memory_persistence: None
+                    })),
                 ),
             )),
             Ok(WasmResult::Reply(_))
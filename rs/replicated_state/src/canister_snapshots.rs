@@ -1,7 +1,12 @@
-use ic_types::{CanisterId, Time};
-use ic_wasm_types::CanisterModule;
+use ic_sys::PAGE_SIZE;
+use ic_types::{CanisterId, NumBytes, Time};
+use ic_wasm_types::{CanisterModule, WasmHash};
 
-use crate::{canister_state::system_state::wasm_chunk_store::WasmChunkStore, PageMap};
+use crate::{
+    canister_state::{execution_state::Global, system_state::wasm_chunk_store::WasmChunkStore},
+    page_map::Buffer,
+    NumWasmPages, PageMap,
+};
 
 use phantom_newtype::Id;
 use std::{collections::BTreeMap, sync::Arc};
@@ -13,7 +18,7 @@ pub type SnapshotId = Id<SnapshotIdTag, u64>;
 ///
 /// Additionally, keeps track of all the accumulated changes
 /// since the last flush to the disk.
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct CanisterSnapshots {
     next_snapshot_id: SnapshotId,
     pub(crate) snapshots: BTreeMap<SnapshotId, Arc<CanisterSnapshot>>,
@@ -76,6 +81,18 @@ impl CanisterSnapshots {
         }
     }
 
+    /// Returns a reference to the snapshot identified by `snapshot_id`, if it exists.
+    pub fn get(&self, snapshot_id: SnapshotId) -> Option<&Arc<CanisterSnapshot>> {
+        self.snapshots.get(&snapshot_id)
+    }
+
+    /// Returns a mutable reference to the snapshot identified by `snapshot_id`, if it exists.
+    ///
+    /// Used to fill in the data of a snapshot that is being uploaded.
+    pub fn get_mut(&mut self, snapshot_id: SnapshotId) -> Option<&mut CanisterSnapshot> {
+        self.snapshots.get_mut(&snapshot_id).map(Arc::make_mut)
+    }
+
     /// Take the unflushed changes.
     pub fn take_unflushed_changes(&mut self) -> Vec<SnapshotOperation> {
         std::mem::take(&mut self.unflushed_changes)
@@ -85,10 +102,38 @@ impl CanisterSnapshots {
     pub fn is_unflushed_changes_empty(&self) -> bool {
         self.unflushed_changes.is_empty()
     }
+
+    /// Returns the number of snapshots belonging to the given canister.
+    pub fn number_of_snapshots_by_canister(&self, canister_id: CanisterId) -> usize {
+        self.snapshots
+            .values()
+            .filter(|snapshot| *snapshot.canister_id() == canister_id)
+            .count()
+    }
+
+    /// Returns the snapshots belonging to the given canister, ordered by ID.
+    pub fn list_snapshots(
+        &self,
+        canister_id: CanisterId,
+    ) -> impl Iterator<Item = (SnapshotId, &Arc<CanisterSnapshot>)> {
+        self.snapshots
+            .iter()
+            .filter(move |(_, snapshot)| *snapshot.canister_id() == canister_id)
+            .map(|(snapshot_id, snapshot)| (*snapshot_id, snapshot))
+    }
+
+    /// Returns the total memory used by all snapshots belonging to the given canister.
+    pub fn memory_usage_by_canister(&self, canister_id: CanisterId) -> NumBytes {
+        self.snapshots
+            .values()
+            .filter(|snapshot| *snapshot.canister_id() == canister_id)
+            .map(|snapshot| snapshot.memory_usage())
+            .fold(NumBytes::from(0), |total, size| total + size)
+    }
 }
 
 /// Contains all information related to a canister snapshot.
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct CanisterSnapshot {
     /// Identifies the canister to which this snapshot belongs.
     canister_id: CanisterId,
@@ -108,6 +153,19 @@ pub struct CanisterSnapshot {
     stable_memory: Option<PageMap>,
     /// Snapshot of wasm memory.
     wasm_memory: Option<PageMap>,
+    /// The size of the wasm memory in wasm pages.
+    wasm_memory_size: NumWasmPages,
+    /// The size of the stable memory in wasm pages.
+    stable_memory_size: NumWasmPages,
+    /// The values of the exported globals.
+    exported_globals: Vec<Global>,
+    /// The Wasm module while it is being uploaded, if the snapshot was
+    /// uploaded rather than taken.
+    wasm_module_upload: Option<WasmModuleUpload>,
+    /// The subnet memory reserved for the snapshot when it was created, which
+    /// is released when the snapshot is removed. Defaults to the memory used
+    /// by the snapshot when it is created.
+    reserved_memory: NumBytes,
 }
 
 impl CanisterSnapshot {
@@ -120,8 +178,11 @@ impl CanisterSnapshot {
         wasm_memory: Option<PageMap>,
         chunk_store: WasmChunkStore,
         wasm_binary: Option<CanisterModule>,
+        wasm_memory_size: NumWasmPages,
+        stable_memory_size: NumWasmPages,
+        exported_globals: Vec<Global>,
     ) -> CanisterSnapshot {
-        Self {
+        let mut snapshot = Self {
             canister_id,
             taken_at_timestamp,
             canister_version,
@@ -130,7 +191,14 @@ impl CanisterSnapshot {
             wasm_memory,
             chunk_store,
             wasm_binary,
-        }
+            wasm_memory_size,
+            stable_memory_size,
+            exported_globals,
+            wasm_module_upload: None,
+            reserved_memory: NumBytes::from(0),
+        };
+        snapshot.reserved_memory = snapshot.memory_usage();
+        snapshot
     }
 
     pub fn canister_id(&self) -> &CanisterId {
@@ -145,17 +213,153 @@ impl CanisterSnapshot {
         &self.taken_at_timestamp
     }
 
+    pub fn certified_data(&self) -> &Vec<u8> {
+        &self.certified_data
+    }
+
     pub fn stable_memory(&self) -> &Option<PageMap> {
         &self.stable_memory
     }
 
+    pub fn stable_memory_mut(&mut self) -> &mut Option<PageMap> {
+        &mut self.stable_memory
+    }
+
     pub fn wasm_memory(&self) -> &Option<PageMap> {
         &self.wasm_memory
     }
 
+    pub fn wasm_memory_mut(&mut self) -> &mut Option<PageMap> {
+        &mut self.wasm_memory
+    }
+
     pub fn chunk_store(&self) -> &WasmChunkStore {
         &self.chunk_store
     }
+
+    pub fn chunk_store_mut(&mut self) -> &mut WasmChunkStore {
+        &mut self.chunk_store
+    }
+
+    pub fn wasm_binary(&self) -> &Option<CanisterModule> {
+        &self.wasm_binary
+    }
+
+    pub fn wasm_binary_mut(&mut self) -> &mut Option<CanisterModule> {
+        &mut self.wasm_binary
+    }
+
+    pub fn wasm_module_upload_mut(&mut self) -> &mut Option<WasmModuleUpload> {
+        &mut self.wasm_module_upload
+    }
+
+    pub fn reserved_memory(&self) -> NumBytes {
+        self.reserved_memory
+    }
+
+    pub fn reserved_memory_mut(&mut self) -> &mut NumBytes {
+        &mut self.reserved_memory
+    }
+
+    pub fn wasm_memory_size(&self) -> NumWasmPages {
+        self.wasm_memory_size
+    }
+
+    pub fn stable_memory_size(&self) -> NumWasmPages {
+        self.stable_memory_size
+    }
+
+    pub fn exported_globals(&self) -> &Vec<Global> {
+        &self.exported_globals
+    }
+
+    /// Returns the memory used by the snapshot in bytes.
+    ///
+    /// Memories are accounted by the number of pages held in their `PageMap`s.
+    pub fn memory_usage(&self) -> NumBytes {
+        let page_map_size = |page_map: &Option<PageMap>| {
+            page_map
+                .as_ref()
+                .map_or(0, |page_map| (page_map.num_host_pages() * PAGE_SIZE) as u64)
+        };
+        NumBytes::from(
+            page_map_size(&self.wasm_memory)
+                + page_map_size(&self.stable_memory)
+                + self
+                    .wasm_binary
+                    .as_ref()
+                    .map_or(0, |binary| binary.len() as u64)
+                + self.certified_data.len() as u64,
+        ) + self.chunk_store.memory_usage()
+    }
+}
+
+/// A Wasm module that is uploaded to a snapshot in consecutive chunks.
+///
+/// The bytes are kept in a `PageMap` until the module is complete, so that
+/// every chunk only copies the pages it touches.
+#[derive(Clone, Debug, PartialEq)]
+pub struct WasmModuleUpload {
+    bytes: PageMap,
+    uploaded: u64,
+    size: u64,
+    module_hash: WasmHash,
+}
+
+impl WasmModuleUpload {
+    pub fn new(bytes: PageMap, size: u64, module_hash: WasmHash) -> Self {
+        Self {
+            bytes,
+            uploaded: 0,
+            size,
+            module_hash,
+        }
+    }
+
+    /// Appends `chunk` at `offset`, which must be the number of bytes uploaded
+    /// so far. Returns the number of bytes of dirtied pages.
+    pub fn append(&mut self, offset: u64, chunk: &[u8]) -> Result<NumBytes, String> {
+        if offset != self.uploaded {
+            return Err(format!(
+                "Expected the Wasm module chunk at offset {}, got offset {}",
+                self.uploaded, offset
+            ));
+        }
+        let end = offset + chunk.len() as u64;
+        if end > self.size {
+            return Err(format!(
+                "Wasm module chunk ends at {}, beyond the module size of {}",
+                end, self.size
+            ));
+        }
+        let mut buffer = Buffer::new(self.bytes.clone());
+        buffer.write(chunk, offset as usize);
+        let dirty_bytes = (buffer.dirty_pages().count() * PAGE_SIZE) as u64;
+        self.bytes = buffer.into_page_map();
+        self.uploaded = end;
+        Ok(NumBytes::from(dirty_bytes))
+    }
+
+    /// Returns true if all bytes of the module have been uploaded.
+    pub fn is_complete(&self) -> bool {
+        self.uploaded == self.size
+    }
+
+    /// Assembles the uploaded module and checks it against the expected hash.
+    ///
+    /// On a hash mismatch the upload starts over from offset zero.
+    pub fn finish(&mut self) -> Result<CanisterModule, String> {
+        let mut bytes = vec![0; self.uploaded as usize];
+        Buffer::new(self.bytes.clone()).read(&mut bytes, 0);
+        let module = CanisterModule::new(bytes);
+        if WasmHash::from(&module) != self.module_hash {
+            self.uploaded = 0;
+            return Err(
+                "The uploaded Wasm module does not match the expected module hash".to_string(),
+            );
+        }
+        Ok(module)
+    }
 }
 
 /// Describes the types of unflushed changes that can be stored by the `SnapshotManager`.
@@ -184,6 +388,9 @@ mod tests {
             Some(PageMap::new_for_testing()),
             WasmChunkStore::new_for_testing(NumBytes::from(20)),
             Some(CanisterModule::new(vec![1, 2, 3])),
+            NumWasmPages::new(0),
+            NumWasmPages::new(0),
+            vec![],
         );
         let mut snapshot_manager = CanisterSnapshots::default();
         assert_eq!(snapshot_manager.snapshots.len(), 0);
@@ -207,4 +414,71 @@ mod tests {
         assert_eq!(snapshot_manager.unflushed_changes.len(), 0);
         assert_eq!(unflushed_changes.len(), 1);
     }
+
+    #[test]
+    fn test_memory_usage_by_canister() {
+        let new_snapshot = |canister_id| {
+            Arc::new(CanisterSnapshot::new(
+                canister_id,
+                mock_time(),
+                0,
+                vec![1, 2, 3, 4],
+                None,
+                None,
+                WasmChunkStore::new_for_testing(NumBytes::from(20)),
+                Some(CanisterModule::new(vec![1, 2, 3])),
+                NumWasmPages::new(0),
+                NumWasmPages::new(0),
+                vec![],
+            ))
+        };
+        let mut snapshot_manager = CanisterSnapshots::default();
+        let snapshot = new_snapshot(canister_test_id(0));
+        let expected_usage = NumBytes::from(7) + snapshot.chunk_store().memory_usage();
+        assert_eq!(snapshot.memory_usage(), expected_usage);
+
+        snapshot_manager.push(snapshot.clone());
+        snapshot_manager.push(snapshot);
+        snapshot_manager.push(new_snapshot(canister_test_id(1)));
+
+        assert_eq!(
+            snapshot_manager.memory_usage_by_canister(canister_test_id(0)),
+            NumBytes::from(2 * expected_usage.get())
+        );
+        assert_eq!(
+            snapshot_manager.memory_usage_by_canister(canister_test_id(2)),
+            NumBytes::from(0)
+        );
+    }
+
+    #[test]
+    fn test_wasm_module_upload() {
+        let module = CanisterModule::new(vec![1, 2, 3, 4, 5]);
+        let mut upload =
+            WasmModuleUpload::new(PageMap::new_for_testing(), 5, WasmHash::from(&module));
+
+        upload.append(0, &[1, 2]).unwrap();
+        // Chunks must be uploaded in order and within the module size.
+        assert!(upload.append(3, &[4, 5]).is_err());
+        assert!(upload.append(2, &[3, 4, 5, 6]).is_err());
+        assert!(!upload.is_complete());
+
+        upload.append(2, &[3, 4, 5]).unwrap();
+        assert!(upload.is_complete());
+        assert_eq!(upload.finish().unwrap(), module);
+    }
+
+    #[test]
+    fn test_wasm_module_upload_restarts_on_hash_mismatch() {
+        let module = CanisterModule::new(vec![1, 2, 3]);
+        let mut upload =
+            WasmModuleUpload::new(PageMap::new_for_testing(), 3, WasmHash::from(&module));
+
+        upload.append(0, &[3, 2, 1]).unwrap();
+        assert!(upload.finish().is_err());
+        assert!(!upload.is_complete());
+
+        upload.append(0, &[1, 2, 3]).unwrap();
+        assert_eq!(upload.finish().unwrap(), module);
+    }
 }
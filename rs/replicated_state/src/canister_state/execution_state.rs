@@ -1,6 +1,7 @@
 use super::SessionNonce;
 use crate::hash::ic_hashtree_leaf_hash;
 use crate::{canister_state::WASM_PAGE_SIZE_IN_BYTES, num_bytes_try_from, NumWasmPages, PageMap};
+use ic_management_canister_types::SnapshotGlobal;
 use ic_protobuf::{
     proxy::{try_from_option_field, ProxyDecodeError},
     state::canister_state_bits::v1 as pb,
@@ -130,6 +131,28 @@ impl TryFrom<pb::Global> for Global {
     }
 }
 
+impl From<&Global> for SnapshotGlobal {
+    fn from(item: &Global) -> Self {
+        match item {
+            Global::I32(value) => Self::I32(*value),
+            Global::I64(value) => Self::I64(*value),
+            Global::F32(value) => Self::F32(*value),
+            Global::F64(value) => Self::F64(*value),
+        }
+    }
+}
+
+impl From<&SnapshotGlobal> for Global {
+    fn from(item: &SnapshotGlobal) -> Self {
+        match item {
+            SnapshotGlobal::I32(value) => Self::I32(*value),
+            SnapshotGlobal::I64(value) => Self::I64(*value),
+            SnapshotGlobal::F32(value) => Self::F32(*value),
+            SnapshotGlobal::F64(value) => Self::F64(*value),
+        }
+    }
+}
+
 /// A set of the functions that a Wasm module exports.
 ///
 /// Arc is used to make cheap clones of this during snapshots.
@@ -146,6 +169,11 @@ pub struct ExportedFunctions {
 
     /// Cached info about exporting a global timer method to skip expensive BTreeSet lookup.
     exports_global_timer: bool,
+
+    /// Cached info about exporting the low resource hooks to skip expensive
+    /// BTreeSet lookups when the scheduler checks them in every round.
+    exports_on_low_wasm_memory: bool,
+    exports_on_low_cycles: bool,
 }
 
 impl ExportedFunctions {
@@ -154,10 +182,16 @@ impl ExportedFunctions {
             exported_functions.contains(&WasmMethod::System(SystemMethod::CanisterHeartbeat));
         let exports_global_timer =
             exported_functions.contains(&WasmMethod::System(SystemMethod::CanisterGlobalTimer));
+        let exports_on_low_wasm_memory =
+            exported_functions.contains(&WasmMethod::System(SystemMethod::CanisterOnLowWasmMemory));
+        let exports_on_low_cycles =
+            exported_functions.contains(&WasmMethod::System(SystemMethod::CanisterOnLowCycles));
         Self {
             exported_functions: Arc::new(exported_functions),
             exports_heartbeat,
             exports_global_timer,
+            exports_on_low_wasm_memory,
+            exports_on_low_cycles,
         }
     }
 
@@ -166,6 +200,10 @@ impl ExportedFunctions {
             // Cached values.
             WasmMethod::System(SystemMethod::CanisterHeartbeat) => self.exports_heartbeat,
             WasmMethod::System(SystemMethod::CanisterGlobalTimer) => self.exports_global_timer,
+            WasmMethod::System(SystemMethod::CanisterOnLowWasmMemory) => {
+                self.exports_on_low_wasm_memory
+            }
+            WasmMethod::System(SystemMethod::CanisterOnLowCycles) => self.exports_on_low_cycles,
             // Expensive lookup.
             _ => self.exported_functions.contains(method),
         }
@@ -529,16 +567,34 @@ impl ExecutionState {
 
     /// Returns the memory currently used by the `ExecutionState`.
     pub fn memory_usage(&self) -> NumBytes {
-        // We use 8 bytes per global.
-        let globals_size_bytes = 8 * self.exported_globals.len() as u64;
-        let wasm_binary_size_bytes = self.wasm_binary.binary.len() as u64;
+        self.wasm_memory_usage()
+            + self.stable_memory_usage()
+            + self.globals_memory_usage()
+            + self.wasm_binary_memory_usage()
+            + self.metadata.memory_usage()
+    }
+
+    /// Returns the memory currently used by the Wasm heap.
+    pub fn wasm_memory_usage(&self) -> NumBytes {
         num_bytes_try_from(self.wasm_memory.size)
             .expect("could not convert from wasm memory number of pages to bytes")
-            + num_bytes_try_from(self.stable_memory.size)
-                .expect("could not convert from stable memory number of pages to bytes")
-            + NumBytes::from(globals_size_bytes)
-            + NumBytes::from(wasm_binary_size_bytes)
-            + self.metadata.memory_usage()
+    }
+
+    /// Returns the memory currently used by the stable memory.
+    pub fn stable_memory_usage(&self) -> NumBytes {
+        num_bytes_try_from(self.stable_memory.size)
+            .expect("could not convert from stable memory number of pages to bytes")
+    }
+
+    /// Returns the memory currently used by the exported globals.
+    pub fn globals_memory_usage(&self) -> NumBytes {
+        // We use 8 bytes per global.
+        NumBytes::from(8 * self.exported_globals.len() as u64)
+    }
+
+    /// Returns the memory currently used by the Wasm binary.
+    pub fn wasm_binary_memory_usage(&self) -> NumBytes {
+        NumBytes::from(self.wasm_binary.binary.len() as u64)
     }
 
     /// Returns the number of global variables in the Wasm module.
@@ -339,6 +339,74 @@ pub struct SystemState {
 
     /// Log records of the canister.
     pub canister_log_records: Vec<CanisterLogRecord>,
+
+    /// Status of the `canister_on_low_wasm_memory` hook.
+    pub on_low_wasm_memory_hook_status: LowResourceHookStatus,
+
+    /// Status of the `canister_on_low_cycles` hook.
+    pub on_low_cycles_hook_status: LowResourceHookStatus,
+
+    /// The `canister_on_low_wasm_memory` hook runs once the Wasm memory that
+    /// remains available to the canister falls below this threshold. Zero
+    /// disables the hook.
+    pub wasm_memory_threshold: NumBytes,
+}
+
+/// The status of a hook that is run once when a resource of the canister runs
+/// low, e.g., `canister_on_low_wasm_memory`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LowResourceHookStatus {
+    /// The resource is not low.
+    #[default]
+    ConditionNotSatisfied,
+    /// The resource is low and the hook is yet to be run.
+    Ready,
+    /// The hook has been run. It is run again only after the resource has
+    /// recovered and runs low again.
+    Executed,
+}
+
+impl LowResourceHookStatus {
+    /// Updates the status given whether the resource is currently low and
+    /// returns true if the hook should be run.
+    pub fn update(&mut self, is_low: bool) -> bool {
+        *self = match (*self, is_low) {
+            (_, false) => LowResourceHookStatus::ConditionNotSatisfied,
+            (LowResourceHookStatus::ConditionNotSatisfied, true) => LowResourceHookStatus::Ready,
+            (status, true) => status,
+        };
+        *self == LowResourceHookStatus::Ready
+    }
+}
+
+impl From<LowResourceHookStatus> for pb::LowResourceHookStatus {
+    fn from(item: LowResourceHookStatus) -> Self {
+        match item {
+            LowResourceHookStatus::ConditionNotSatisfied => Self::ConditionNotSatisfied,
+            LowResourceHookStatus::Ready => Self::Ready,
+            LowResourceHookStatus::Executed => Self::Executed,
+        }
+    }
+}
+
+impl TryFrom<i32> for LowResourceHookStatus {
+    type Error = ProxyDecodeError;
+
+    fn try_from(item: i32) -> Result<Self, Self::Error> {
+        let status = pb::LowResourceHookStatus::try_from(item).map_err(|_| {
+            ProxyDecodeError::ValueOutOfRange {
+                typ: "LowResourceHookStatus",
+                err: format!("Unexpected value of low resource hook status: {}", item),
+            }
+        })?;
+        Ok(match status {
+            // Checkpoints written before the hooks were introduced.
+            pb::LowResourceHookStatus::Unspecified
+            | pb::LowResourceHookStatus::ConditionNotSatisfied => Self::ConditionNotSatisfied,
+            pb::LowResourceHookStatus::Ready => Self::Ready,
+            pb::LowResourceHookStatus::Executed => Self::Executed,
+        })
+    }
 }
 
 /// A wrapper around the different canister statuses.
@@ -438,6 +506,14 @@ pub enum ExecutionTask {
     /// The task exists only within an execution round, it never gets serialized.
     GlobalTimer,
 
+    /// Task running the `canister_on_low_wasm_memory` hook.
+    /// The task exists only within an execution round, it never gets serialized.
+    OnLowWasmMemory,
+
+    /// Task running the `canister_on_low_cycles` hook.
+    /// The task exists only within an execution round, it never gets serialized.
+    OnLowCycles,
+
     // A paused execution task exists only within an epoch (between
     // checkpoints). It is never serialized, and it turns into `AbortedExecution`
     // before the checkpoint or when there are too many long-running executions.
@@ -479,6 +555,8 @@ impl From<&ExecutionTask> for pb::ExecutionTask {
         match item {
             ExecutionTask::Heartbeat
             | ExecutionTask::GlobalTimer
+            | ExecutionTask::OnLowWasmMemory
+            | ExecutionTask::OnLowCycles
             | ExecutionTask::PausedExecution(_)
             | ExecutionTask::PausedInstallCode(_) => {
                 panic!("Attempt to serialize ephemeral task: {:?}.", item);
@@ -506,6 +584,12 @@ impl From<&ExecutionTask> for pb::ExecutionTask {
                     CanisterMessageOrTask::Task(CanisterTask::GlobalTimer) => {
                         PbInput::Task(PbCanisterTask::Timer as i32)
                     }
+                    CanisterMessageOrTask::Task(CanisterTask::OnLowWasmMemory) => {
+                        PbInput::Task(PbCanisterTask::OnLowWasmMemory as i32)
+                    }
+                    CanisterMessageOrTask::Task(CanisterTask::OnLowCycles) => {
+                        PbInput::Task(PbCanisterTask::OnLowCycles as i32)
+                    }
                 };
                 Self {
                     task: Some(pb::execution_task::Task::AbortedExecution(
@@ -581,6 +665,8 @@ impl TryFrom<pb::ExecutionTask> for ExecutionTask {
                             }
                             PbCanisterTask::Heartbeat => CanisterTask::Heartbeat,
                             PbCanisterTask::Timer => CanisterTask::GlobalTimer,
+                            PbCanisterTask::OnLowWasmMemory => CanisterTask::OnLowWasmMemory,
+                            PbCanisterTask::OnLowCycles => CanisterTask::OnLowCycles,
                         };
                         CanisterMessageOrTask::Task(task)
                     }
@@ -710,6 +796,9 @@ impl SystemState {
             wasm_chunk_store,
             log_visibility: LogVisibility::default(),
             canister_log_records: Vec::new(),
+            on_low_wasm_memory_hook_status: LowResourceHookStatus::default(),
+            on_low_cycles_hook_status: LowResourceHookStatus::default(),
+            wasm_memory_threshold: NumBytes::from(0),
         }
     }
 
@@ -755,6 +844,9 @@ impl SystemState {
         wasm_chunk_store_metadata: WasmChunkStoreMetadata,
         log_visibility: LogVisibility,
         canister_log_records: Vec<CanisterLogRecord>,
+        on_low_wasm_memory_hook_status: LowResourceHookStatus,
+        on_low_cycles_hook_status: LowResourceHookStatus,
+        wasm_memory_threshold: NumBytes,
     ) -> Self {
         Self {
             controllers,
@@ -779,6 +871,9 @@ impl SystemState {
             ),
             log_visibility,
             canister_log_records,
+            on_low_wasm_memory_hook_status,
+            on_low_cycles_hook_status,
+            wasm_memory_threshold,
         }
     }
 
@@ -1,5 +1,8 @@
 use std::{collections::BTreeMap, sync::Arc};
 
+use ic_management_canister_types::{
+    WASM_CHUNK_STORE_CHUNK_SIZE_BYTES, WASM_CHUNK_STORE_MAX_SIZE_BYTES,
+};
 use ic_protobuf::{proxy::ProxyDecodeError, state::canister_state_bits::v1 as pb};
 use ic_sys::{PageBytes, PageIndex, PAGE_SIZE};
 use ic_types::{NumBytes, NumPages};
@@ -8,11 +11,12 @@ use crate::{page_map::PageAllocatorFileDescriptor, PageMap};
 
 const PAGES_PER_CHUNK: u64 = 256;
 const CHUNK_SIZE: u64 = PAGES_PER_CHUNK * (PAGE_SIZE as u64);
-pub const DEFAULT_MAX_SIZE: NumBytes = NumBytes::new(100 * 1024 * 1024); // 100 MiB
+pub const DEFAULT_MAX_SIZE: NumBytes = NumBytes::new(WASM_CHUNK_STORE_MAX_SIZE_BYTES);
 
 #[test]
 fn check_chunk_size() {
     assert_eq!(1024 * 1024, CHUNK_SIZE);
+    assert_eq!(WASM_CHUNK_STORE_CHUNK_SIZE_BYTES, CHUNK_SIZE);
 }
 
 pub type WasmChunkHash = [u8; 32];
@@ -41,7 +41,7 @@ use ic_types::{
     subnet_id_into_protobuf, subnet_id_try_from_protobuf,
     time::{Time, UNIX_EPOCH},
     xnet::{StreamHeader, StreamIndex, StreamIndexedQueue, StreamSlice},
-    CountBytes, CryptoHashOfPartialState, NodeId, NumBytes, PrincipalId, SubnetId,
+    CountBytes, CryptoHashOfPartialState, NodeId, NumBytes, PrincipalId, ReplicaVersion, SubnetId,
 };
 use ic_wasm_types::WasmHash;
 use serde::{Deserialize, Serialize};
@@ -356,6 +356,8 @@ pub struct SubnetTopology {
     /// a backup. An additional NNS proposal will be needed to allow the subnet
     /// holding the key as backup to actually produce signatures.
     pub ecdsa_keys_held: BTreeSet<EcdsaKeyId>,
+    /// The replica version the subnet is running, as recorded in the registry.
+    pub replica_version: ReplicaVersion,
 }
 
 impl From<&SubnetTopology> for pb_metadata::SubnetTopology {
@@ -372,6 +374,7 @@ impl From<&SubnetTopology> for pb_metadata::SubnetTopology {
             subnet_type: i32::from(item.subnet_type),
             subnet_features: Some(pb_subnet::SubnetFeatures::from(item.subnet_features)),
             ecdsa_keys_held: item.ecdsa_keys_held.iter().map(|k| k.into()).collect(),
+            replica_version: String::from(&item.replica_version),
         }
     }
 }
@@ -401,6 +404,8 @@ impl TryFrom<pb_metadata::SubnetTopology> for SubnetTopology {
                 .map(SubnetFeatures::from)
                 .unwrap_or_default(),
             ecdsa_keys_held,
+            // Like the subnet type above, the field is reset before it is used.
+            replica_version: ReplicaVersion::try_from(item.replica_version).unwrap_or_default(),
         })
     }
 }
@@ -421,6 +421,11 @@ fn handle_list_neurons(
     let args = ic_nns_governance::pb::v1::ListNeurons {
         neuron_ids: vec![],
         include_neurons_readable_by_caller: true,
+        page_number: None,
+        page_size: None,
+        neuron_states: vec![],
+        min_stake_e8s: None,
+        has_hot_keys: None,
     };
     let update = HttpCanisterUpdate {
         canister_id: Blob(ic_nns_constants::GOVERNANCE_CANISTER_ID.get().to_vec()),
@@ -48,7 +48,8 @@ use ic_sns_governance::{
         GetRunningSnsVersionRequest, GetRunningSnsVersionResponse,
         GetSnsInitializationParametersRequest, GetSnsInitializationParametersResponse,
         Governance as GovernanceProto, ListNervousSystemFunctionsResponse, ListNeurons,
-        ListNeuronsResponse, ListProposals, ListProposalsResponse, ManageNeuron,
+        ListNeuronsResponse, ListProposals, ListProposalsResponse,
+        ListTreasuryPaymentStreamsRequest, ListTreasuryPaymentStreamsResponse, ManageNeuron,
         ManageNeuronResponse, NervousSystemParameters, RewardEvent, SetMode, SetModeResponse,
     },
     types::{Environment, HeapGrowthPotential},
@@ -350,6 +351,21 @@ fn get_sns_initialization_parameters_(
     governance().get_sns_initialization_parameters(&request)
 }
 
+/// Returns the treasury payment streams created by CreateTreasuryPaymentStream proposals.
+#[export_name = "canister_query list_treasury_payment_streams"]
+fn list_treasury_payment_streams() {
+    log!(INFO, "list_treasury_payment_streams");
+    over(candid_one, list_treasury_payment_streams_)
+}
+
+/// Internal method for calling list_treasury_payment_streams.
+#[candid_method(query, rename = "list_treasury_payment_streams")]
+fn list_treasury_payment_streams_(
+    request: ListTreasuryPaymentStreamsRequest,
+) -> ListTreasuryPaymentStreamsResponse {
+    governance().list_treasury_payment_streams(&request)
+}
+
 /// Performs a command on a neuron if the caller is authorized to do so.
 /// The possible neuron commands are (for details, see the SNS's governance.proto):
 /// - configuring the neuron (increasing or setting its dissolve delay or changing the
@@ -137,6 +137,13 @@ pub struct Neuron {
     /// entry\[i\].timestamp_of_disbursement_seconds <= entry\[i+1\].timestamp_of_disbursement_seconds
     #[prost(message, repeated, tag = "18")]
     pub disburse_maturity_in_progress: ::prost::alloc::vec::Vec<DisburseMaturityInProgress>,
+    /// The neuron's followees per topic, specified as a map of topics to followees neuron IDs.
+    /// The map's keys are represented by integers as Protobuf does not support enum keys in maps.
+    ///
+    /// Followees for a specific function (see `followees`) take precedence over the followees
+    /// for the topic of that function.
+    #[prost(btree_map = "int32, message", tag = "19")]
+    pub topic_followees: ::prost::alloc::collections::BTreeMap<i32, neuron::Followees>,
     /// The neuron's dissolve state, specifying whether the neuron is dissolving,
     /// non-dissolving, or dissolved.
     ///
@@ -259,6 +266,11 @@ pub mod nervous_system_function {
         /// <method_name>(proposal_data: ProposalData) -> Result<String, String>
         #[prost(string, optional, tag = "5")]
         pub validator_method_name: ::core::option::Option<::prost::alloc::string::String>,
+        /// The topic of the proposals executing this function. If not specified,
+        /// TOPIC_APPLICATION_SPECIFIC is used. Generic functions cannot be in
+        /// TOPIC_TREASURY or TOPIC_CRITICAL.
+        #[prost(enumeration = "super::Topic", optional, tag = "6")]
+        pub topic: ::core::option::Option<i32>,
     }
     #[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
     #[allow(clippy::derive_partial_eq_without_eq)]
@@ -314,7 +326,8 @@ pub struct UpgradeSnsControlledCanister {
     /// The id of the canister that is upgraded.
     #[prost(message, optional, tag = "1")]
     pub canister_id: ::core::option::Option<::ic_base_types::PrincipalId>,
-    /// The new wasm module that the canister is upgraded to.
+    /// The new wasm module that the canister is upgraded to. Must be empty if
+    /// `chunked_canister_wasm` is set.
     #[prost(bytes = "vec", tag = "2")]
     pub new_canister_wasm: ::prost::alloc::vec::Vec<u8>,
     /// Arguments passed to the post-upgrade method of the new wasm module.
@@ -327,6 +340,33 @@ pub struct UpgradeSnsControlledCanister {
         tag = "4"
     )]
     pub mode: ::core::option::Option<i32>,
+    /// If the new wasm module is too large to fit into a proposal (i.e. into a
+    /// single message), it can be uploaded in chunks to the chunk store of a
+    /// canister instead, and referenced here. In that case, `new_canister_wasm`
+    /// must be empty.
+    #[prost(message, optional, tag = "5")]
+    pub chunked_canister_wasm: ::core::option::Option<ChunkedCanisterWasm>,
+}
+/// A wasm module that has been split into chunks, which were uploaded to the
+/// chunk store of a canister via the management canister's `upload_chunk` method.
+#[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ChunkedCanisterWasm {
+    /// The SHA-256 hash of the entire wasm module, i.e. of the concatenation of
+    /// all chunks. The assembled wasm module is checked against this hash before
+    /// it is installed.
+    #[prost(bytes = "vec", tag = "1")]
+    pub wasm_module_hash: ::prost::alloc::vec::Vec<u8>,
+    /// The canister whose chunk store contains the chunks. Must be a dapp
+    /// canister registered with SNS root, on the same subnet as the canister to
+    /// upgrade (it may be the canister to upgrade itself).
+    #[prost(message, optional, tag = "2")]
+    pub store_canister_id: ::core::option::Option<::ic_base_types::PrincipalId>,
+    /// The SHA-256 hashes of the chunks, in the order in which they are to be
+    /// assembled. Must not be empty.
+    #[prost(bytes = "vec", repeated, tag = "3")]
+    pub chunk_hashes_list: ::prost::alloc::vec::Vec<::prost::alloc::vec::Vec<u8>>,
 }
 /// A proposal to transfer SNS treasury funds to (optionally a Subaccount of) the
 /// target principal.
@@ -493,6 +533,167 @@ pub struct ManageDappCanisterSettings {
     #[prost(enumeration = "LogVisibility", optional, tag = "6")]
     pub log_visibility: ::core::option::Option<i32>,
 }
+/// Loads a snapshot of a dapp canister, replacing the canister's current state
+/// (i.e. its Wasm module, memory and stable memory) with that of the snapshot.
+///
+/// The snapshot must be the one that was taken automatically right before the
+/// latest upgrade of the canister by an UpgradeSnsControlledCanister proposal
+/// (see `Governance.pre_upgrade_snapshots`). While the proposal is open or
+/// adopted, the canister cannot be upgraded, so that the snapshot is not
+/// replaced.
+///
+/// Not supported yet, because the management canister does not implement
+/// canister snapshots yet.
+#[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RestoreDappCanisterSnapshot {
+    /// The canister ID of the dapp canister to be restored.
+    #[prost(message, optional, tag = "1")]
+    pub canister_id: ::core::option::Option<::ic_base_types::PrincipalId>,
+    /// The ID of the snapshot of `canister_id` to be loaded.
+    #[prost(bytes = "vec", optional, tag = "2")]
+    pub snapshot_id: ::core::option::Option<::prost::alloc::vec::Vec<u8>>,
+}
+/// Creates a payment stream, i.e. a schedule of transfers from one of the SNS
+/// treasuries to a recipient (e.g. to pay a contributor over time).
+///
+/// The total amount vests in `installment_count` equal installments, one every
+/// `period_seconds` after the start of the stream. Nothing is paid before the
+/// cliff has passed; at that point, all installments that vested in the
+/// meantime are paid at once. Vested amounts are transferred by governance's
+/// periodic tasks, until the stream is paid in full or cancelled by a
+/// CancelTreasuryPaymentStream proposal.
+#[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CreateTreasuryPaymentStream {
+    /// The treasury from which the installments are paid.
+    #[prost(enumeration = "transfer_sns_treasury_funds::TransferFrom", tag = "1")]
+    pub from_treasury: i32,
+    /// The total amount to pay over the lifetime of the stream, in e8s.
+    #[prost(uint64, tag = "2")]
+    pub total_amount_e8s: u64,
+    /// The principal to pay the installments to.
+    #[prost(message, optional, tag = "3")]
+    pub to_principal: ::core::option::Option<::ic_base_types::PrincipalId>,
+    /// An (optional) Subaccount of the principal to pay the installments to.
+    #[prost(message, optional, tag = "4")]
+    pub to_subaccount: ::core::option::Option<Subaccount>,
+    /// An optional memo to use for the transfers.
+    #[prost(uint64, optional, tag = "5")]
+    pub memo: ::core::option::Option<u64>,
+    /// When the stream starts, in seconds since the UNIX epoch. If not set, the
+    /// stream starts when the proposal is executed.
+    #[prost(uint64, optional, tag = "6")]
+    pub start_timestamp_seconds: ::core::option::Option<u64>,
+    /// For how long after the start of the stream no installments are paid.
+    #[prost(uint64, optional, tag = "7")]
+    pub cliff_duration_seconds: ::core::option::Option<u64>,
+    /// The time between two consecutive installments.
+    #[prost(uint64, optional, tag = "8")]
+    pub period_seconds: ::core::option::Option<u64>,
+    /// The number of installments into which the total amount is split.
+    #[prost(uint64, optional, tag = "9")]
+    pub installment_count: ::core::option::Option<u64>,
+}
+/// Stops a payment stream that was created by a CreateTreasuryPaymentStream
+/// proposal. Installments that were already paid are not affected, but no
+/// further installments are paid.
+#[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CancelTreasuryPaymentStream {
+    /// The ID of the payment stream to cancel.
+    #[prost(uint64, optional, tag = "1")]
+    pub payment_stream_id: ::core::option::Option<u64>,
+}
+/// A payment stream that was created by a CreateTreasuryPaymentStream proposal.
+#[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TreasuryPaymentStream {
+    /// The ID of the stream, which is the ID of the proposal that created it.
+    #[prost(uint64, optional, tag = "1")]
+    pub id: ::core::option::Option<u64>,
+    /// The terms of the stream, as given in the proposal.
+    #[prost(message, optional, tag = "2")]
+    pub terms: ::core::option::Option<CreateTreasuryPaymentStream>,
+    /// When the stream started, in seconds since the UNIX epoch.
+    #[prost(uint64, optional, tag = "3")]
+    pub start_timestamp_seconds: ::core::option::Option<u64>,
+    /// The total amount paid so far, in e8s. This includes the amount of a
+    /// pending payment.
+    #[prost(uint64, optional, tag = "4")]
+    pub paid_e8s: ::core::option::Option<u64>,
+    /// When an installment was last paid successfully.
+    #[prost(uint64, optional, tag = "5")]
+    pub last_payment_timestamp_seconds: ::core::option::Option<u64>,
+    /// When paying an installment last failed. Cleared when a payment succeeds.
+    #[prost(uint64, optional, tag = "6")]
+    pub last_failed_payment_timestamp_seconds: ::core::option::Option<u64>,
+    /// When the stream was cancelled, if it was.
+    #[prost(uint64, optional, tag = "7")]
+    pub cancelled_timestamp_seconds: ::core::option::Option<u64>,
+    /// The amount of a payment that is in progress, or that failed in a way that
+    /// does not tell whether the ledger recorded the transfer, in e8s. Such a
+    /// payment is retried with the same created_at_time, so that the ledger does
+    /// not record the transfer twice. Cleared once the payment succeeds.
+    #[prost(uint64, optional, tag = "8")]
+    pub pending_payment_e8s: ::core::option::Option<u64>,
+    /// The created_at_time of the transfer of the pending payment, in nanoseconds
+    /// since the UNIX epoch.
+    #[prost(uint64, optional, tag = "9")]
+    pub pending_payment_created_at_time_nanos: ::core::option::Option<u64>,
+}
+/// Opens a follow-on sale round in the SNS swap canister, in which SNS tokens
+/// from the SNS token treasury are sold for ICP. The round follows the same
+/// rules as the decentralization swap, except that the Neurons' Fund does not
+/// participate: if it is committed, the participants receive SNS neurons and the
+/// ICP goes to the ICP treasury; if it is aborted, the ICP is refunded and the
+/// SNS tokens are returned to the SNS token treasury.
+///
+/// A round can only be opened once the previous one has been finalized.
+#[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct OpenSaleRound {
+    /// The amount of SNS tokens to sell, in e8s. The tokens are transferred from
+    /// the SNS token treasury to the swap canister when the proposal is executed.
+    #[prost(uint64, optional, tag = "1")]
+    pub sns_token_e8s: ::core::option::Option<u64>,
+    /// The minimum number of direct participants for the round to succeed.
+    #[prost(uint32, optional, tag = "2")]
+    pub min_participants: ::core::option::Option<u32>,
+    /// The minimum amount of ICP (in e8s) that needs to be raised for the round
+    /// to succeed.
+    #[prost(uint64, optional, tag = "3")]
+    pub min_direct_participation_icp_e8s: ::core::option::Option<u64>,
+    /// The maximum amount of ICP (in e8s) that can be raised. The round is
+    /// committed as soon as this amount is reached.
+    #[prost(uint64, optional, tag = "4")]
+    pub max_direct_participation_icp_e8s: ::core::option::Option<u64>,
+    /// The minimum amount of ICP (in e8s) that each participant must contribute.
+    #[prost(uint64, optional, tag = "5")]
+    pub min_participant_icp_e8s: ::core::option::Option<u64>,
+    /// The maximum amount of ICP (in e8s) that each participant may contribute.
+    #[prost(uint64, optional, tag = "6")]
+    pub max_participant_icp_e8s: ::core::option::Option<u64>,
+    /// How long the round is open, in seconds.
+    #[prost(uint64, optional, tag = "7")]
+    pub duration_seconds: ::core::option::Option<u64>,
+    /// How long after the execution of the proposal the round opens, in seconds.
+    /// If not set, the round opens immediately.
+    #[prost(uint64, optional, tag = "8")]
+    pub start_delay_seconds: ::core::option::Option<u64>,
+    /// The number of neurons in the neuron basket of each participant.
+    #[prost(uint64, optional, tag = "9")]
+    pub neuron_basket_count: ::core::option::Option<u64>,
+    /// The amount of additional time it takes for the next neuron in the basket
+    /// to dissolve.
+    #[prost(uint64, optional, tag = "10")]
+    pub neuron_basket_dissolve_delay_interval_seconds: ::core::option::Option<u64>,
+}
 /// A proposal is the immutable input of a proposal submission.
 #[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
 #[compare_default]
@@ -522,7 +723,7 @@ pub struct Proposal {
     /// of this mapping.
     #[prost(
         oneof = "proposal::Action",
-        tags = "4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18"
+        tags = "4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22"
     )]
     pub action: ::core::option::Option<proposal::Action>,
 }
@@ -628,6 +829,26 @@ pub mod proposal {
         /// Id = 14.
         #[prost(message, tag = "18")]
         ManageDappCanisterSettings(super::ManageDappCanisterSettings),
+        /// Restore a dapp canister from one of its snapshots.
+        ///
+        /// Id = 15.
+        #[prost(message, tag = "19")]
+        RestoreDappCanisterSnapshot(super::RestoreDappCanisterSnapshot),
+        /// Create a stream of scheduled transfers from an SNS treasury.
+        ///
+        /// Id = 16.
+        #[prost(message, tag = "20")]
+        CreateTreasuryPaymentStream(super::CreateTreasuryPaymentStream),
+        /// Cancel a stream of scheduled transfers from an SNS treasury.
+        ///
+        /// Id = 17.
+        #[prost(message, tag = "21")]
+        CancelTreasuryPaymentStream(super::CancelTreasuryPaymentStream),
+        /// Open a follow-on sale round of SNS tokens from the SNS token treasury.
+        ///
+        /// Id = 18.
+        #[prost(message, tag = "22")]
+        OpenSaleRound(super::OpenSaleRound),
     }
 }
 #[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
@@ -959,6 +1180,22 @@ pub struct ProposalData {
     #[prost(message, optional, tag = "21")]
     pub minimum_yes_proportion_of_exercised:
         ::core::option::Option<::ic_nervous_system_proto::pb::v1::Percentage>,
+    /// The topic of the proposal, determined by its action when the proposal was
+    /// made.
+    #[prost(enumeration = "Topic", optional, tag = "22")]
+    pub topic: ::core::option::Option<i32>,
+    /// For UpgradeSnsControlledCanister proposals, the ID of the snapshot of the
+    /// target canister that was taken right before the upgrade was performed.
+    /// This can be used to roll back the upgrade via a RestoreDappCanisterSnapshot
+    /// proposal. Not set if canister snapshots are disabled. Only the latest
+    /// pre-upgrade snapshot of each canister is kept (see
+    /// `Governance.pre_upgrade_snapshots`), i.e. the snapshot is replaced when the
+    /// canister is upgraded again. While a RestoreDappCanisterSnapshot proposal
+    /// that targets it is open or adopted, upgrades of the canister fail.
+    ///
+    /// The ID is opaque, as assigned by the management canister.
+    #[prost(bytes = "vec", optional, tag = "23")]
+    pub pre_upgrade_snapshot_id: ::core::option::Option<::prost::alloc::vec::Vec<u8>>,
 }
 /// The nervous system's parameters, which are parameters that can be changed, via proposals,
 /// by each nervous system community.
@@ -1335,6 +1572,17 @@ pub struct Governance {
     pub is_finalizing_disburse_maturity: ::core::option::Option<bool>,
     #[prost(message, optional, tag = "26")]
     pub maturity_modulation: ::core::option::Option<governance::MaturityModulation>,
+    /// The payment streams created by CreateTreasuryPaymentStream proposals, as a
+    /// map from stream IDs to streams. Streams that have been paid in full or
+    /// cancelled are kept for a while, for the record, and then removed.
+    #[prost(btree_map = "uint64, message", tag = "27")]
+    pub treasury_payment_streams: ::prost::alloc::collections::BTreeMap<u64, TreasuryPaymentStream>,
+    /// The snapshot taken before the latest upgrade of each dapp canister, keyed
+    /// by the canister's principal (in text form). The next upgrade of the
+    /// canister replaces the snapshot, and RestoreDappCanisterSnapshot proposals
+    /// can only target it.
+    #[prost(btree_map = "string, message", tag = "28")]
+    pub pre_upgrade_snapshots: ::prost::alloc::collections::BTreeMap<::prost::alloc::string::String, governance::PreUpgradeSnapshot>,
 }
 /// Nested message and enum types in `Governance`.
 pub mod governance {
@@ -1553,6 +1801,19 @@ pub mod governance {
         #[prost(uint64, optional, tag = "2")]
         pub updated_at_timestamp_seconds: ::core::option::Option<u64>,
     }
+    /// A snapshot of a dapp canister that was taken right before the canister was
+    /// upgraded by an UpgradeSnsControlledCanister proposal.
+    #[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct PreUpgradeSnapshot {
+        /// The ID of the UpgradeSnsControlledCanister proposal.
+        #[prost(uint64, tag = "1")]
+        pub proposal_id: u64,
+        /// The ID of the snapshot, as assigned by the management canister.
+        #[prost(bytes = "vec", tag = "2")]
+        pub snapshot_id: ::prost::alloc::vec::Vec<u8>,
+    }
     #[derive(
         candid::CandidType,
         candid::Deserialize,
@@ -1601,6 +1862,31 @@ pub mod governance {
         }
     }
 }
+/// Request message for 'list_treasury_payment_streams'.
+#[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListTreasuryPaymentStreamsRequest {
+    /// Limit the number of streams returned in each page, from 1 to 100.
+    /// If a value outside of this range is provided, 100 will be used.
+    #[prost(uint32, tag = "1")]
+    pub limit: u32,
+    /// The stream ID specifying which streams to return. This should be set to
+    /// the last stream of the previously returned page and will not be included
+    /// in the current page. If this is specified, then only the streams that have
+    /// an ID strictly lower than the specified one are returned. If this is not
+    /// specified, then the list starts with the most recent stream.
+    #[prost(uint64, optional, tag = "2")]
+    pub before_payment_stream_id: ::core::option::Option<u64>,
+}
+/// Response message for 'list_treasury_payment_streams'.
+#[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListTreasuryPaymentStreamsResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub treasury_payment_streams: ::prost::alloc::vec::Vec<TreasuryPaymentStream>,
+}
 /// Request message for 'get_metadata'.
 #[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -1680,7 +1966,7 @@ pub struct ManageNeuron {
     pub subaccount: ::prost::alloc::vec::Vec<u8>,
     #[prost(
         oneof = "manage_neuron::Command",
-        tags = "2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13"
+        tags = "2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14"
     )]
     pub command: ::core::option::Option<manage_neuron::Command>,
 }
@@ -1891,6 +2177,22 @@ pub mod manage_neuron {
         #[prost(message, repeated, tag = "2")]
         pub followees: ::prost::alloc::vec::Vec<super::NeuronId>,
     }
+    /// The operation that sets the followees of a neuron for all the functions
+    /// of a topic. The neuron votes on proposals of the topic in the same way as
+    /// a majority of the followees (see `Follow`), unless the neuron has
+    /// followees for the specific function of the proposal.
+    /// If the list of followees is empty, the following on the topic is removed.
+    #[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct SetFollowing {
+        /// The topic for which this follow relation is relevant.
+        #[prost(enumeration = "super::Topic", tag = "1")]
+        pub topic: i32,
+        /// The list of followee neurons, specified by their neuron ID.
+        #[prost(message, repeated, tag = "2")]
+        pub followees: ::prost::alloc::vec::Vec<super::NeuronId>,
+    }
     /// The operation that registers a given vote from the neuron for a given
     /// proposal (a directly cast vote as opposed to a vote that is cast as
     /// a result of a follow relation).
@@ -2011,6 +2313,8 @@ pub mod manage_neuron {
         RemoveNeuronPermissions(RemoveNeuronPermissions),
         #[prost(message, tag = "13")]
         StakeMaturity(StakeMaturity),
+        #[prost(message, tag = "14")]
+        SetFollowing(SetFollowing),
     }
 }
 /// The response of a ManageNeuron command.
@@ -2021,7 +2325,7 @@ pub mod manage_neuron {
 pub struct ManageNeuronResponse {
     #[prost(
         oneof = "manage_neuron_response::Command",
-        tags = "1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13"
+        tags = "1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14"
     )]
     pub command: ::core::option::Option<manage_neuron_response::Command>,
 }
@@ -2083,6 +2387,11 @@ pub mod manage_neuron_response {
     #[allow(clippy::derive_partial_eq_without_eq)]
     #[derive(Clone, PartialEq, ::prost::Message)]
     pub struct FollowResponse {}
+    /// The response to the ManageNeuron command 'set_following'.
+    #[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct SetFollowingResponse {}
     /// The response to the ManageNeuron command 'make_proposal'.
     #[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
     #[allow(clippy::derive_partial_eq_without_eq)]
@@ -2156,6 +2465,8 @@ pub mod manage_neuron_response {
         RemoveNeuronPermission(RemoveNeuronPermissionsResponse),
         #[prost(message, tag = "13")]
         StakeMaturity(StakeMaturityResponse),
+        #[prost(message, tag = "14")]
+        SetFollowing(SetFollowingResponse),
     }
 }
 /// An operation that attempts to get a neuron by a given neuron ID.
@@ -2266,6 +2577,11 @@ pub struct ListProposals {
     /// If this list is empty, no restriction is applied.
     #[prost(enumeration = "ProposalDecisionStatus", repeated, tag = "5")]
     pub include_status: ::prost::alloc::vec::Vec<i32>,
+    /// A list of topics, specifying that only proposals that have one of the
+    /// given topics should be included in the list.
+    /// If this list is empty, no restriction is applied.
+    #[prost(enumeration = "Topic", repeated, tag = "6")]
+    pub include_topics: ::prost::alloc::vec::Vec<i32>,
 }
 /// A response to the ListProposals command.
 #[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
@@ -2679,6 +2995,72 @@ impl Vote {
         }
     }
 }
+/// The topic of a proposal. Each native and generic nervous system function
+/// belongs to exactly one topic, which allows neurons to follow other neurons
+/// on all the functions of a topic at once.
+#[derive(
+    candid::CandidType,
+    candid::Deserialize,
+    comparable::Comparable,
+    Clone,
+    Copy,
+    Debug,
+    PartialEq,
+    Eq,
+    Hash,
+    PartialOrd,
+    Ord,
+    ::prost::Enumeration,
+)]
+#[repr(i32)]
+pub enum Topic {
+    /// This exists because proto3 defaults to the 0 value on enums.
+    Unspecified = 0,
+    /// Proposals that change how the SNS is governed, e.g., its nervous system
+    /// parameters, its metadata, its ledger parameters, the set of generic
+    /// nervous system functions, or the version of the SNS framework.
+    Governance = 1,
+    /// Proposals that move or mint tokens of the SNS treasury.
+    Treasury = 2,
+    /// Proposals that register, upgrade or manage the dapp canisters controlled
+    /// by the SNS.
+    DappManagement = 3,
+    /// Proposals that are critical for the dapp, e.g., giving up control over
+    /// dapp canisters.
+    Critical = 4,
+    /// Generic nervous system functions that are specific to the application of
+    /// the SNS. This is the topic of generic nervous system functions that do
+    /// not specify a topic.
+    ApplicationSpecific = 5,
+}
+impl Topic {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            Topic::Unspecified => "TOPIC_UNSPECIFIED",
+            Topic::Governance => "TOPIC_GOVERNANCE",
+            Topic::Treasury => "TOPIC_TREASURY",
+            Topic::DappManagement => "TOPIC_DAPP_MANAGEMENT",
+            Topic::Critical => "TOPIC_CRITICAL",
+            Topic::ApplicationSpecific => "TOPIC_APPLICATION_SPECIFIC",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "TOPIC_UNSPECIFIED" => Some(Self::Unspecified),
+            "TOPIC_GOVERNANCE" => Some(Self::Governance),
+            "TOPIC_TREASURY" => Some(Self::Treasury),
+            "TOPIC_DAPP_MANAGEMENT" => Some(Self::DappManagement),
+            "TOPIC_CRITICAL" => Some(Self::Critical),
+            "TOPIC_APPLICATION_SPECIFIC" => Some(Self::ApplicationSpecific),
+            _ => None,
+        }
+    }
+}
 #[derive(
     candid::CandidType,
     candid::Deserialize,
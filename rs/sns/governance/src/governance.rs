@@ -15,9 +15,11 @@ use crate::{
     },
     pb::{
         sns_root_types::{
+            LoadDappCanisterSnapshotRequest, LoadDappCanisterSnapshotResponse,
             ManageDappCanisterSettingsRequest, ManageDappCanisterSettingsResponse,
             RegisterDappCanistersRequest, RegisterDappCanistersResponse, SetDappControllersRequest,
-            SetDappControllersResponse,
+            SetDappControllersResponse, TakeDappCanisterSnapshotRequest,
+            TakeDappCanisterSnapshotResponse,
         },
         v1::{
             claim_swap_neurons_response::SwapNeuron,
@@ -25,14 +27,14 @@ use crate::{
             governance::{
                 self, neuron_in_flight_command,
                 neuron_in_flight_command::Command as InFlightCommand, MaturityModulation,
-                NeuronInFlightCommand, SnsMetadata, UpgradeInProgress, Version,
+                NeuronInFlightCommand, PreUpgradeSnapshot, SnsMetadata, UpgradeInProgress, Version,
             },
             governance_error::ErrorType,
             manage_neuron::{
                 self,
                 claim_or_refresh::{By, MemoAndController},
                 AddNeuronPermissions, ClaimOrRefresh, DisburseMaturity, FinalizeDisburseMaturity,
-                RemoveNeuronPermissions,
+                RemoveNeuronPermissions, SetFollowing,
             },
             manage_neuron_response::{
                 DisburseMaturityResponse, MergeMaturityResponse, StakeMaturityResponse,
@@ -40,8 +42,9 @@ use crate::{
             neuron::{DissolveState, Followees},
             proposal::Action,
             transfer_sns_treasury_funds::TransferFrom,
-            Account as AccountProto, Ballot, ClaimSwapNeuronsError, ClaimSwapNeuronsRequest,
-            ClaimSwapNeuronsResponse, ClaimedSwapNeuronStatus, DefaultFollowees,
+            Account as AccountProto, Ballot, CancelTreasuryPaymentStream, ChunkedCanisterWasm,
+            ClaimSwapNeuronsError, ClaimSwapNeuronsRequest, ClaimSwapNeuronsResponse,
+            ClaimedSwapNeuronStatus, CreateTreasuryPaymentStream, DefaultFollowees,
             DeregisterDappCanisters, DisburseMaturityInProgress, Empty,
             ExecuteGenericNervousSystemFunction, FailStuckUpgradeInProgressRequest,
             FailStuckUpgradeInProgressResponse, GetMaturityModulationRequest,
@@ -50,26 +53,30 @@ use crate::{
             GetSnsInitializationParametersRequest, GetSnsInitializationParametersResponse,
             Governance as GovernanceProto, GovernanceError, ListNervousSystemFunctionsResponse,
             ListNeurons, ListNeuronsResponse, ListProposals, ListProposalsResponse,
+            ListTreasuryPaymentStreamsRequest, ListTreasuryPaymentStreamsResponse,
             ManageDappCanisterSettings, ManageLedgerParameters, ManageNeuron, ManageNeuronResponse,
             ManageSnsMetadata, MintSnsTokens, NervousSystemFunction, NervousSystemParameters,
             Neuron, NeuronId, NeuronPermission, NeuronPermissionList, NeuronPermissionType,
-            Proposal, ProposalData, ProposalDecisionStatus, ProposalId, ProposalRewardStatus,
-            RegisterDappCanisters, RewardEvent, Tally, TransferSnsTreasuryFunds,
+            OpenSaleRound, Proposal, ProposalData, ProposalDecisionStatus, ProposalId,
+            ProposalRewardStatus, RegisterDappCanisters, RestoreDappCanisterSnapshot, RewardEvent,
+            Tally, Topic, TransferSnsTreasuryFunds, TreasuryPaymentStream,
             UpgradeSnsControlledCanister, UpgradeSnsToNextVersion, Vote, VotingRewardsParameters,
             WaitForQuietState,
         },
     },
     proposal::{
-        validate_and_render_proposal, ValidGenericNervousSystemFunction, MAX_LIST_PROPOSAL_RESULTS,
-        MAX_NUMBER_OF_PROPOSALS_WITH_BALLOTS,
+        is_snapshot_targeted_by_restore_proposal, validate_and_render_proposal,
+        validate_and_render_restore_dapp_canister_snapshot, ValidGenericNervousSystemFunction,
+        ARE_DAPP_CANISTER_SNAPSHOTS_ENABLED, MAX_LIST_PROPOSAL_RESULTS,
+        MAX_LIST_TREASURY_PAYMENT_STREAMS_RESULTS, MAX_NUMBER_OF_PROPOSALS_WITH_BALLOTS,
     },
     sns_upgrade::{
         get_all_sns_canisters, get_running_version, get_upgrade_params, get_wasm, SnsCanisterType,
         UpgradeSnsParams,
     },
     types::{
-        function_id_to_proposal_criticality, is_registered_function_id, Environment,
-        HeapGrowthPotential, LedgerUpdateLock,
+        function_id_to_proposal_criticality, function_id_to_topic, is_registered_function_id,
+        Environment, HeapGrowthPotential, LedgerUpdateLock,
     },
 };
 use candid::{Decode, Encode};
@@ -86,15 +93,21 @@ use ic_nervous_system_collections_union_multi_map::UnionMultiMap;
 use ic_nervous_system_common::{
     cmc::CMC,
     i2d,
-    ledger::{self, compute_distribution_subaccount_bytes},
+    ledger::{self, compute_distribution_subaccount_bytes, TransferWithCreatedAtTimeError},
     NervousSystemError, SECONDS_PER_DAY,
 };
 use ic_nervous_system_governance::maturity_modulation::{
     apply_maturity_modulation, MIN_MATURITY_MODULATION_PERMYRIAD,
 };
-use ic_nervous_system_root::change_canister::ChangeCanisterRequest;
+use ic_nervous_system_root::change_canister::{
+    ChangeCanisterRequest, ChunkedCanisterWasm as RootChunkedCanisterWasm,
+};
 use ic_nns_constants::LEDGER_CANISTER_ID as NNS_LEDGER_CANISTER_ID;
 use ic_sns_governance_proposal_criticality::ProposalCriticality;
+use ic_sns_swap_proto_library::pb::v1::{
+    NeuronBasketConstructionParameters, OpenSaleRoundRequest, OpenSaleRoundResponse,
+    Params as SwapParams, RefundSaleRoundTokensRequest,
+};
 use icp_ledger::DEFAULT_TRANSFER_FEE as NNS_DEFAULT_TRANSFER_FEE;
 use icrc_ledger_types::icrc1::account::{Account, Subaccount};
 use lazy_static::lazy_static;
@@ -137,6 +150,18 @@ const WASM32_PAGE_SIZE_IN_KIB: usize = 64;
 pub const ONE_DAY_SECONDS: u64 = 24 * 60 * 60;
 pub const MATURITY_DISBURSEMENT_DELAY_SECONDS: u64 = 7 * 24 * 3600;
 
+/// How long to wait before retrying a treasury payment stream installment whose transfer failed.
+pub const TREASURY_PAYMENT_STREAM_RETRY_INTERVAL_SECONDS: u64 = 60 * 60;
+
+/// How long the transfer of a pending treasury payment stream payment is retried. The ledgers only
+/// deduplicate transfers within their transaction window of 24 hours, so the last retry must be
+/// made well before that.
+pub const TREASURY_PAYMENT_STREAM_PENDING_PAYMENT_EXPIRY_SECONDS: u64 =
+    ONE_DAY_SECONDS - TREASURY_PAYMENT_STREAM_RETRY_INTERVAL_SECONDS;
+
+/// How long a treasury payment stream is kept after it has been cancelled or paid in full.
+pub const FINISHED_TREASURY_PAYMENT_STREAM_RETENTION_DURATION_SECONDS: u64 = 30 * ONE_DAY_SECONDS;
+
 /// The max number of wasm32 pages for the heap after which we consider that there
 /// is a risk to the ability to grow the heap.
 ///
@@ -285,6 +310,73 @@ impl GovernanceProto {
         }
     }
 
+    /// Builds an index that maps topics to (followee) neuron IDs to these neuron's followers.
+    /// The resulting index is a map
+    /// Topic -> (followee's neuron ID) -> set of followers' neuron IDs.
+    ///
+    /// The index is built from the `neurons` in the `Governance` struct, which map followers
+    /// (the neuron ID) to a set of followees per topic.
+    pub fn build_topic_followee_index(
+        neurons: &BTreeMap<String, Neuron>,
+    ) -> BTreeMap<Topic, BTreeMap<String, BTreeSet<NeuronId>>> {
+        let mut topic_followee_index = BTreeMap::new();
+        for neuron in neurons.values() {
+            GovernanceProto::add_neuron_to_topic_followee_index(&mut topic_followee_index, neuron);
+        }
+        topic_followee_index
+    }
+
+    /// Adds a neuron to the topic_followee_index.
+    pub fn add_neuron_to_topic_followee_index(
+        index: &mut BTreeMap<Topic, BTreeMap<String, BTreeSet<NeuronId>>>,
+        neuron: &Neuron,
+    ) {
+        for (topic, followees) in neuron.topic_followees.iter() {
+            let topic = match Topic::try_from(*topic) {
+                Ok(Topic::Unspecified) | Err(_) => continue,
+                Ok(topic) => topic,
+            };
+
+            let followee_index = index.entry(topic).or_default();
+            for followee in followees.followees.iter() {
+                followee_index
+                    .entry(followee.to_string())
+                    .or_default()
+                    .insert(
+                        neuron
+                            .id
+                            .as_ref()
+                            .expect("Neuron must have a NeuronId")
+                            .clone(),
+                    );
+            }
+        }
+    }
+
+    /// Removes a neuron from the topic_followee_index.
+    pub fn remove_neuron_from_topic_followee_index(
+        index: &mut BTreeMap<Topic, BTreeMap<String, BTreeSet<NeuronId>>>,
+        neuron: &Neuron,
+    ) {
+        for (topic, followees) in neuron.topic_followees.iter() {
+            let Ok(topic) = Topic::try_from(*topic) else {
+                continue;
+            };
+            if let Some(followee_index) = index.get_mut(&topic) {
+                for followee in followees.followees.iter() {
+                    let nid = followee.to_string();
+                    if let Some(followee_set) = followee_index.get_mut(&nid) {
+                        followee_set
+                            .remove(neuron.id.as_ref().expect("Neuron must have a NeuronId"));
+                        if followee_set.is_empty() {
+                            followee_index.remove(&nid);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
     /// Iterate through one neuron and add all the principals that have some permission on this
     /// neuron to the index that maps principalIDs to a set of neurons for which the principal
     /// has some permissions.
@@ -667,6 +759,16 @@ pub struct Governance {
     /// Function ID -> (followee's neuron ID) -> set of followers' neuron IDs.
     pub function_followee_index: BTreeMap<u64, BTreeMap<String, BTreeSet<NeuronId>>>,
 
+    /// Cached data structure that (for each topic) maps a followee to the set
+    /// of its followers. It is the inverse of the mapping from follower to
+    /// followees per topic that is stored in each (follower) neuron.
+    ///
+    /// This is a cached index and will be removed and recreated when the state
+    /// is saved and restored.
+    ///
+    /// Topic -> (followee's neuron ID) -> set of followers' neuron IDs.
+    pub topic_followee_index: BTreeMap<Topic, BTreeMap<String, BTreeSet<NeuronId>>>,
+
     /// Maps Principals to the Neuron IDs of all Neurons for which this principal
     /// has some permissions, i.e., all neurons that have this principal associated
     /// with a NeuronPermissionType for the Neuron.
@@ -736,6 +838,7 @@ impl Governance {
             nns_ledger,
             cmc,
             function_followee_index: BTreeMap::new(),
+            topic_followee_index: BTreeMap::new(),
             principal_to_neuron_ids_index: BTreeMap::new(),
             closest_proposal_deadline_timestamp_seconds: 0,
             latest_gc_timestamp_seconds: 0,
@@ -794,6 +897,8 @@ impl Governance {
         self.function_followee_index = self
             .proto
             .build_function_followee_index(&self.proto.neurons);
+        self.topic_followee_index =
+            GovernanceProto::build_topic_followee_index(&self.proto.neurons);
         self.principal_to_neuron_ids_index = self
             .proto
             .build_principal_to_neuron_ids_index(&self.proto.neurons);
@@ -904,7 +1009,8 @@ impl Governance {
     }
 
     /// Adds a neuron to the list of neurons and updates the indices
-    /// `principal_to_neuron_ids_index` and `function_followee_index`.
+    /// `principal_to_neuron_ids_index`, `function_followee_index` and
+    /// `topic_followee_index`.
     ///
     /// Preconditions:
     /// - the heap can still grow
@@ -944,13 +1050,19 @@ impl Governance {
             &neuron,
         );
 
+        GovernanceProto::add_neuron_to_topic_followee_index(
+            &mut self.topic_followee_index,
+            &neuron,
+        );
+
         self.proto.neurons.insert(neuron_id.to_string(), neuron);
 
         Ok(())
     }
 
     /// Removes a neuron from the list of neurons and updates the indices
-    /// `principal_to_neuron_ids_index` and `function_followee_index`.
+    /// `principal_to_neuron_ids_index`, `function_followee_index` and
+    /// `topic_followee_index`.
     ///
     /// Preconditions:
     /// - the given `neuron_id` exists in `self.proto.neurons`
@@ -979,6 +1091,11 @@ impl Governance {
             &neuron,
         );
 
+        GovernanceProto::remove_neuron_from_topic_followee_index(
+            &mut self.topic_followee_index,
+            &neuron,
+        );
+
         self.proto.neurons.remove(&neuron_id.to_string());
 
         Ok(())
@@ -1313,6 +1430,7 @@ impl Governance {
             auto_stake_maturity: parent_neuron.auto_stake_maturity,
             vesting_period_seconds: None,
             disburse_maturity_in_progress: vec![],
+            topic_followees: parent_neuron.topic_followees.clone(),
         };
 
         // Add the child neuron's id to the set of neurons with ongoing operations.
@@ -1766,6 +1884,7 @@ impl Governance {
         let include_reward_status: HashSet<i32> =
             request.include_reward_status.iter().cloned().collect();
         let include_status: HashSet<i32> = request.include_status.iter().cloned().collect();
+        let include_topics: HashSet<i32> = request.include_topics.iter().cloned().collect();
         let now = self.env.now();
         let filter_all = |data: &ProposalData| -> bool {
             let action = data.action;
@@ -1783,6 +1902,14 @@ impl Governance {
             if !(include_status.is_empty() || include_status.contains(&(data.status() as i32))) {
                 return false;
             }
+            // Filter out proposals by topic.
+            if !(include_topics.is_empty()
+                || include_topics.contains(
+                    &(data.effective_topic(&self.proto.id_to_nervous_system_functions) as i32),
+                ))
+            {
+                return false;
+            }
 
             true
         };
@@ -2058,6 +2185,25 @@ impl Governance {
                 self.perform_manage_dapp_canister_settings(manage_dapp_canister_settings)
                     .await
             }
+            Action::RestoreDappCanisterSnapshot(restore_dapp_canister_snapshot) => {
+                self.perform_restore_dapp_canister_snapshot(
+                    proposal_id,
+                    restore_dapp_canister_snapshot,
+                )
+                .await
+            }
+            Action::CreateTreasuryPaymentStream(create_treasury_payment_stream) => self
+                .perform_create_treasury_payment_stream(
+                    proposal_id,
+                    create_treasury_payment_stream,
+                ),
+            Action::CancelTreasuryPaymentStream(cancel_treasury_payment_stream) => {
+                self.perform_cancel_treasury_payment_stream(cancel_treasury_payment_stream)
+            }
+            Action::OpenSaleRound(open_sale_round) => {
+                self.perform_open_sale_round(proposal_id, open_sale_round)
+                    .await
+            }
             // This should not be possible, because Proposal validation is performed when
             // a proposal is first made.
             Action::Unspecified(_) => Err(GovernanceError::new_with_message(
@@ -2411,9 +2557,43 @@ impl Governance {
             ));
         }
 
+        // Root can only install a chunked wasm from the chunk store of a canister that it
+        // controls, i.e. a registered dapp canister.
+        let chunked_canister_wasm = match upgrade.chunked_canister_wasm {
+            None => None,
+            Some(ChunkedCanisterWasm {
+                wasm_module_hash,
+                store_canister_id,
+                chunk_hashes_list,
+            }) => {
+                let store_canister_id = get_canister_id(&store_canister_id)?;
+                if !dapp_canisters.contains(&store_canister_id) {
+                    return Err(GovernanceError::new_with_message(
+                        ErrorType::InvalidCommand,
+                        format!(
+                            "The chunks of the new wasm must be stored in a dapp canister that is \
+                            registered with the SNS root. Valid store canisters are: {:?}",
+                            dapp_canisters
+                        ),
+                    ));
+                }
+                Some(RootChunkedCanisterWasm {
+                    wasm_module_hash,
+                    store_canister_id,
+                    chunk_hashes_list,
+                })
+            }
+        };
+
+        if ARE_DAPP_CANISTER_SNAPSHOTS_ENABLED {
+            self.take_pre_upgrade_snapshot(proposal_id, target_canister_id)
+                .await?;
+        }
+
         self.upgrade_non_root_canister(
             target_canister_id,
             upgrade.new_canister_wasm,
+            chunked_canister_wasm,
             upgrade
                 .canister_upgrade_arg
                 .unwrap_or_else(|| Encode!().unwrap()),
@@ -2425,10 +2605,124 @@ impl Governance {
         .await
     }
 
+    /// Takes a snapshot of the canister, so that the upgrade can be rolled back using a
+    /// RestoreDappCanisterSnapshot proposal, and records its ID in the upgrade proposal and in
+    /// `pre_upgrade_snapshots`.
+    ///
+    /// Since the number of snapshots per canister is limited, the new snapshot replaces the one
+    /// taken before the previous upgrade. If that snapshot is still targeted by a
+    /// RestoreDappCanisterSnapshot proposal, or if no snapshot can be taken, an error is returned
+    /// and the canister must not be upgraded, since the upgrade could not be rolled back.
+    async fn take_pre_upgrade_snapshot(
+        &mut self,
+        proposal_id: u64,
+        canister_id: CanisterId,
+    ) -> Result<(), GovernanceError> {
+        let canister_key = canister_id.get().to_string();
+        let latest_snapshot = self.proto.pre_upgrade_snapshots.get(&canister_key);
+        if let Some(latest_snapshot) = latest_snapshot {
+            if is_snapshot_targeted_by_restore_proposal(
+                &self.proto.proposals,
+                canister_id.get(),
+                latest_snapshot,
+            ) {
+                return Err(GovernanceError::new_with_message(
+                    ErrorType::PreconditionFailed,
+                    format!(
+                        "Not upgrading canister {canister_id}, because its latest snapshot {} is \
+                         targeted by a RestoreDappCanisterSnapshot proposal and cannot be \
+                         replaced.",
+                        hex::encode(&latest_snapshot.snapshot_id)
+                    ),
+                ));
+            }
+        }
+        let replace_snapshot = latest_snapshot.map(|snapshot| snapshot.snapshot_id.clone());
+
+        let snapshot_id = self
+            .take_dapp_canister_snapshot(canister_id, replace_snapshot)
+            .await
+            .map_err(|err| {
+                GovernanceError::new_with_message(
+                    ErrorType::External,
+                    format!(
+                        "Not upgrading canister {canister_id}, because no snapshot could be \
+                         taken to roll back the upgrade: {}",
+                        err.error_message
+                    ),
+                )
+            })?;
+
+        self.proto.pre_upgrade_snapshots.insert(
+            canister_key,
+            PreUpgradeSnapshot {
+                proposal_id,
+                snapshot_id: snapshot_id.clone(),
+            },
+        );
+        if let Some(proposal_data) = self.proto.proposals.get_mut(&proposal_id) {
+            proposal_data.pre_upgrade_snapshot_id = Some(snapshot_id);
+        }
+        Ok(())
+    }
+
+    /// Asks root to take a snapshot of the given dapp canister. Returns the ID of the new snapshot.
+    async fn take_dapp_canister_snapshot(
+        &self,
+        canister_id: CanisterId,
+        replace_snapshot: Option<Vec<u8>>,
+    ) -> Result<Vec<u8>, GovernanceError> {
+        let request = TakeDappCanisterSnapshotRequest {
+            canister_id: Some(canister_id.get()),
+            replace_snapshot,
+        };
+        let payload = candid::Encode!(&request).map_err(|err| {
+            GovernanceError::new_with_message(
+                ErrorType::InvalidProposal,
+                format!("Could not encode TakeDappCanisterSnapshotRequest: {err:?}"),
+            )
+        })?;
+        let reply = self
+            .env
+            .call_canister(
+                self.proto.root_canister_id_or_panic(),
+                "take_dapp_canister_snapshot",
+                payload,
+            )
+            .await
+            .map_err(|err| {
+                GovernanceError::new_with_message(
+                    ErrorType::External,
+                    format!("Canister method call failed: {err:?}"),
+                )
+            })?;
+
+        match candid::Decode!(&reply, TakeDappCanisterSnapshotResponse) {
+            Ok(TakeDappCanisterSnapshotResponse {
+                snapshot_id: Some(snapshot_id),
+                failure_reason: None,
+            }) => Ok(snapshot_id),
+            Ok(TakeDappCanisterSnapshotResponse { failure_reason, .. }) => {
+                Err(GovernanceError::new_with_message(
+                    ErrorType::External,
+                    format!(
+                        "Failed to take a snapshot of canister {canister_id}: {}",
+                        failure_reason.unwrap_or_else(|| "no snapshot ID returned".to_string())
+                    ),
+                ))
+            }
+            Err(error) => Err(GovernanceError::new_with_message(
+                ErrorType::External,
+                format!("Could not decode TakeDappCanisterSnapshotResponse: {error}"),
+            )),
+        }
+    }
+
     async fn upgrade_non_root_canister(
         &mut self,
         target_canister_id: CanisterId,
         wasm: Vec<u8>,
+        chunked_canister_wasm: Option<RootChunkedCanisterWasm>,
         arg: Vec<u8>,
         mode: CanisterInstallMode,
     ) -> Result<(), GovernanceError> {
@@ -2447,6 +2741,12 @@ impl Governance {
                     .with_wasm(wasm)
                     .with_arg(arg)
                     .with_mode(mode);
+            let change_canister_arg = match chunked_canister_wasm {
+                Some(chunked_canister_wasm) => {
+                    change_canister_arg.with_chunked_wasm(chunked_canister_wasm)
+                }
+                None => change_canister_arg,
+            };
 
             Encode!(&change_canister_arg).unwrap()
         };
@@ -2527,6 +2827,7 @@ impl Governance {
                 self.upgrade_non_root_canister(
                     target_canister_id,
                     target_wasm.clone(),
+                    None,
                     Encode!().unwrap(),
                     CanisterInstallMode::Upgrade,
                 )
@@ -2566,48 +2867,38 @@ impl Governance {
                     .expect("Couldn't transform transfer.subaccount to Subaccount")
             }),
         };
-        match transfer.from_treasury() {
-            TransferFrom::IcpTreasury => self
-                .nns_ledger
-                .transfer_funds(
-                    transfer.amount_e8s,
-                    NNS_DEFAULT_TRANSFER_FEE.get_e8s(),
-                    None,
-                    to,
-                    transfer.memo.unwrap_or(0),
-                )
-                .await
-                .map(|_| ())
-                .map_err(|e| {
-                    GovernanceError::new_with_message(
-                        ErrorType::External,
-                        format!("Error making ICP treasury transfer: {}", e),
-                    )
-                }),
-            TransferFrom::SnsTokenTreasury => {
-                let transaction_fee_e8s = self.transaction_fee_e8s_or_panic();
+        self.transfer_from_treasury(
+            transfer.from_treasury(),
+            transfer.amount_e8s,
+            to,
+            transfer.memo.unwrap_or(0),
+        )
+        .await
+    }
+
+    /// Returns the ledger, the transfer fee, the subaccount and the name of the given treasury
+    /// (i.e. the ICP treasury or the SNS token treasury).
+    fn treasury_ledger(
+        &self,
+        from_treasury: TransferFrom,
+    ) -> Result<(&dyn ICRC1Ledger, u64, Option<Subaccount>, &'static str), GovernanceError> {
+        match from_treasury {
+            TransferFrom::IcpTreasury => Ok((
+                self.nns_ledger.as_ref(),
+                NNS_DEFAULT_TRANSFER_FEE.get_e8s(),
+                None,
+                "ICP",
+            )),
+            TransferFrom::SnsTokenTreasury => Ok((
+                self.ledger.as_ref(),
+                self.transaction_fee_e8s_or_panic(),
                 // See ic_sns_init::distributions::FractionalDeveloperVotingPower.insert_treasury_accounts
-                let treasury_subaccount = compute_distribution_subaccount_bytes(
+                Some(compute_distribution_subaccount_bytes(
                     self.env.canister_id().get(),
                     TREASURY_SUBACCOUNT_NONCE,
-                );
-                self.ledger
-                    .transfer_funds(
-                        transfer.amount_e8s,
-                        transaction_fee_e8s,
-                        Some(treasury_subaccount),
-                        to,
-                        transfer.memo.unwrap_or(0),
-                    )
-                    .await
-                    .map(|_| ())
-                    .map_err(|e| {
-                        GovernanceError::new_with_message(
-                            ErrorType::External,
-                            format!("Error making SNS Token treasury transfer: {}", e),
-                        )
-                    })
-            }
+                )),
+                "SNS Token",
+            )),
             TransferFrom::Unspecified => Err(GovernanceError::new_with_message(
                 ErrorType::PreconditionFailed,
                 "Invalid 'from_treasury' in transfer.",
@@ -2615,48 +2906,327 @@ impl Governance {
         }
     }
 
-    async fn perform_mint_sns_tokens(
+    /// Transfers `amount_e8s` from the given treasury (i.e. the ICP treasury or the SNS token
+    /// treasury) to `to`. The transfer fee is paid by the treasury, on top of `amount_e8s`.
+    async fn transfer_from_treasury(
+        &self,
+        from_treasury: TransferFrom,
+        amount_e8s: u64,
+        to: Account,
+        memo: u64,
+    ) -> Result<(), GovernanceError> {
+        let (ledger, fee_e8s, from_subaccount, treasury_name) =
+            self.treasury_ledger(from_treasury)?;
+        ledger
+            .transfer_funds(amount_e8s, fee_e8s, from_subaccount, to, memo)
+            .await
+            .map(|_| ())
+            .map_err(|e| {
+                GovernanceError::new_with_message(
+                    ErrorType::External,
+                    format!("Error making {} treasury transfer: {}", treasury_name, e),
+                )
+            })
+    }
+
+    /// Starts a new treasury payment stream. The ID of the stream is the ID of the proposal that
+    /// created it. Installments are paid out by `maybe_pay_treasury_payment_streams`.
+    fn perform_create_treasury_payment_stream(
         &mut self,
-        mint: MintSnsTokens,
+        proposal_id: u64,
+        create_treasury_payment_stream: CreateTreasuryPaymentStream,
     ) -> Result<(), GovernanceError> {
-        let to = Account {
-            owner: mint
-                .to_principal
-                .ok_or(GovernanceError::new_with_message(
-                    ErrorType::InvalidProposal,
-                    "Expected mint to have a target principal",
-                ))?
-                .0,
-            subaccount: mint
-                .to_subaccount
-                .as_ref()
-                .map(|s| bytes_to_subaccount(&s.subaccount[..]))
-                .transpose()?,
-        };
-        let amount_e8s = mint.amount_e8s.ok_or(GovernanceError::new_with_message(
-            ErrorType::InvalidProposal,
-            "Expected MintSnsTokens to have an an amount_e8s",
-        ))?;
-        self.ledger
-            .transfer_funds(amount_e8s, 0, None, to, mint.memo())
-            .await?;
+        if self
+            .proto
+            .treasury_payment_streams
+            .contains_key(&proposal_id)
+        {
+            return Err(GovernanceError::new_with_message(
+                ErrorType::PreconditionFailed,
+                format!("Treasury payment stream {} already exists.", proposal_id),
+            ));
+        }
+
+        let start_timestamp_seconds = create_treasury_payment_stream
+            .start_timestamp_seconds
+            .unwrap_or_else(|| self.env.now());
+        self.proto.treasury_payment_streams.insert(
+            proposal_id,
+            TreasuryPaymentStream {
+                id: Some(proposal_id),
+                terms: Some(create_treasury_payment_stream),
+                start_timestamp_seconds: Some(start_timestamp_seconds),
+                paid_e8s: Some(0),
+                last_payment_timestamp_seconds: None,
+                last_failed_payment_timestamp_seconds: None,
+                cancelled_timestamp_seconds: None,
+                pending_payment_e8s: None,
+                pending_payment_created_at_time_nanos: None,
+            },
+        );
+
         Ok(())
     }
 
-    async fn perform_manage_ledger_parameters(
+    /// Cancels a treasury payment stream, so that no further installments are paid out. Amounts
+    /// that have been paid already are not affected.
+    fn perform_cancel_treasury_payment_stream(
         &mut self,
+        cancel_treasury_payment_stream: CancelTreasuryPaymentStream,
+    ) -> Result<(), GovernanceError> {
+        let payment_stream_id = cancel_treasury_payment_stream
+            .payment_stream_id
+            .ok_or_else(|| {
+                GovernanceError::new_with_message(
+                    ErrorType::InvalidProposal,
+                    "CancelTreasuryPaymentStream must specify a payment_stream_id.",
+                )
+            })?;
+        let now = self.env.now();
+        let payment_stream = self
+            .proto
+            .treasury_payment_streams
+            .get_mut(&payment_stream_id)
+            .ok_or_else(|| {
+                GovernanceError::new_with_message(
+                    ErrorType::NotFound,
+                    format!(
+                        "There is no treasury payment stream with ID {}.",
+                        payment_stream_id
+                    ),
+                )
+            })?;
+        if payment_stream.cancelled_timestamp_seconds.is_some() {
+            return Err(GovernanceError::new_with_message(
+                ErrorType::PreconditionFailed,
+                format!(
+                    "Treasury payment stream {} has already been cancelled.",
+                    payment_stream_id
+                ),
+            ));
+        }
+
+        payment_stream.cancelled_timestamp_seconds = Some(now);
+        Ok(())
+    }
+
+    /// Opens a follow-on sale round in the swap canister.
+    ///
+    /// First, makes sure that the swap canister holds the SNS tokens that are offered in the
+    /// round, by transferring the missing amount from the SNS token treasury. (The swap canister
+    /// might already hold some SNS tokens, e.g., the remainder of previous rounds.) Then, asks
+    /// the swap canister to open the round, which it only does if the previous round has been
+    /// finalized. If it refuses (or the call fails), the swap canister is asked to return the
+    /// transferred tokens to the SNS token treasury.
+    async fn perform_open_sale_round(
+        &self,
         proposal_id: u64,
-        manage_ledger_parameters: ManageLedgerParameters,
+        open_sale_round: OpenSaleRound,
     ) -> Result<(), GovernanceError> {
-        err_if_another_upgrade_is_in_progress(&self.proto.proposals, proposal_id)?;
+        let swap_canister_id = self.proto.swap_canister_id_or_panic();
+        let now_seconds = self.env.now();
 
-        let current_version = self.proto.deployed_version_or_panic();
-        let ledger_canister_id = self.proto.ledger_canister_id_or_panic();
+        let OpenSaleRound {
+            sns_token_e8s,
+            min_participants,
+            min_direct_participation_icp_e8s,
+            max_direct_participation_icp_e8s,
+            min_participant_icp_e8s,
+            max_participant_icp_e8s,
+            duration_seconds,
+            start_delay_seconds,
+            neuron_basket_count,
+            neuron_basket_dissolve_delay_interval_seconds,
+        } = open_sale_round;
+        let sns_token_e8s = sns_token_e8s.unwrap_or_default();
+        let min_direct_participation_icp_e8s = min_direct_participation_icp_e8s.unwrap_or_default();
+        let max_direct_participation_icp_e8s = max_direct_participation_icp_e8s.unwrap_or_default();
+        let start_delay_seconds = start_delay_seconds.unwrap_or_default();
+        let params = SwapParams {
+            min_participants: min_participants.unwrap_or_default(),
+            // Follow-on sale rounds have no Neurons' Fund participation.
+            min_icp_e8s: min_direct_participation_icp_e8s,
+            max_icp_e8s: max_direct_participation_icp_e8s,
+            min_direct_participation_icp_e8s: Some(min_direct_participation_icp_e8s),
+            max_direct_participation_icp_e8s: Some(max_direct_participation_icp_e8s),
+            min_participant_icp_e8s: min_participant_icp_e8s.unwrap_or_default(),
+            max_participant_icp_e8s: max_participant_icp_e8s.unwrap_or_default(),
+            swap_due_timestamp_seconds: now_seconds
+                .saturating_add(start_delay_seconds)
+                .saturating_add(duration_seconds.unwrap_or_default()),
+            sns_token_e8s,
+            neuron_basket_construction_parameters: Some(NeuronBasketConstructionParameters {
+                count: neuron_basket_count.unwrap_or_default(),
+                dissolve_delay_interval_seconds: neuron_basket_dissolve_delay_interval_seconds
+                    .unwrap_or_default(),
+            }),
+            sale_delay_seconds: Some(start_delay_seconds),
+        };
 
-        let ledger_canister_info = self.env
-            .call_canister(
-                CanisterId::ic_00(),
-                "canister_info",
+        // Encode the request first, so that nothing is transferred if that fails.
+        let payload = candid::Encode!(&OpenSaleRoundRequest {
+            params: Some(params),
+            sns_governance_proposal_id: Some(proposal_id),
+        })
+        .map_err(|err| {
+            GovernanceError::new_with_message(
+                ErrorType::InvalidProposal,
+                format!("Could not encode OpenSaleRoundRequest: {err:?}"),
+            )
+        })?;
+
+        // Top up the SNS token balance of the swap canister.
+        let swap_account = Account {
+            owner: swap_canister_id.get().0,
+            subaccount: None,
+        };
+        let swap_balance_e8s = self
+            .ledger
+            .account_balance(swap_account)
+            .await
+            .map_err(|e| {
+                GovernanceError::new_with_message(
+                    ErrorType::External,
+                    format!(
+                        "Error getting the SNS token balance of the swap canister: {}",
+                        e
+                    ),
+                )
+            })?
+            .get_e8s();
+        let missing_e8s = sns_token_e8s.saturating_sub(swap_balance_e8s);
+        if missing_e8s > 0 {
+            self.transfer_from_treasury(
+                TransferFrom::SnsTokenTreasury,
+                missing_e8s,
+                swap_account,
+                proposal_id,
+            )
+            .await?;
+        }
+
+        let open_result = self
+            .env
+            .call_canister(swap_canister_id, "open_sale_round", payload)
+            .await;
+        match open_result {
+            Ok(reply) => {
+                // This line is to ensure we handle the reply properly if it's ever
+                // changed to not be empty.
+                match candid::Decode!(&reply, OpenSaleRoundResponse) {
+                    Ok(OpenSaleRoundResponse {}) => {}
+                    Err(_) => log!(ERROR, "Could not decode OpenSaleRoundResponse!"),
+                };
+
+                log!(
+                    INFO,
+                    "Opened a sale round offering {} SNS e8s (proposal {}).",
+                    sns_token_e8s,
+                    proposal_id,
+                );
+                Ok(())
+            }
+            Err(err) => {
+                if missing_e8s > 0 {
+                    self.refund_sale_round_tokens(swap_canister_id, proposal_id, missing_e8s)
+                        .await;
+                }
+                Err(GovernanceError::new_with_message(
+                    ErrorType::External,
+                    format!("Canister method call failed: {err:?}"),
+                ))
+            }
+        }
+    }
+
+    /// Asks the swap canister to return `amount_e8s` SNS tokens, which were transferred to it for
+    /// the sale round of the given proposal, to the SNS token treasury. Failures are only logged,
+    /// as the execution of the proposal fails either way.
+    async fn refund_sale_round_tokens(
+        &self,
+        swap_canister_id: CanisterId,
+        proposal_id: u64,
+        amount_e8s: u64,
+    ) {
+        let payload = match candid::Encode!(&RefundSaleRoundTokensRequest {
+            sns_governance_proposal_id: Some(proposal_id),
+            amount_e8s: Some(amount_e8s),
+        }) {
+            Ok(payload) => payload,
+            Err(err) => {
+                log!(
+                    ERROR,
+                    "Could not encode RefundSaleRoundTokensRequest: {:?}",
+                    err
+                );
+                return;
+            }
+        };
+        match self
+            .env
+            .call_canister(swap_canister_id, "refund_sale_round_tokens", payload)
+            .await
+        {
+            Ok(_) => log!(
+                INFO,
+                "The swap canister returned the {} SNS e8s of the sale round of proposal {} to \
+                 the SNS token treasury.",
+                amount_e8s,
+                proposal_id,
+            ),
+            Err(err) => log!(
+                ERROR,
+                "The swap canister could not return the {} SNS e8s of the sale round of \
+                 proposal {} to the SNS token treasury: {:?}",
+                amount_e8s,
+                proposal_id,
+                err,
+            ),
+        }
+    }
+
+    async fn perform_mint_sns_tokens(
+        &mut self,
+        mint: MintSnsTokens,
+    ) -> Result<(), GovernanceError> {
+        let to = Account {
+            owner: mint
+                .to_principal
+                .ok_or(GovernanceError::new_with_message(
+                    ErrorType::InvalidProposal,
+                    "Expected mint to have a target principal",
+                ))?
+                .0,
+            subaccount: mint
+                .to_subaccount
+                .as_ref()
+                .map(|s| bytes_to_subaccount(&s.subaccount[..]))
+                .transpose()?,
+        };
+        let amount_e8s = mint.amount_e8s.ok_or(GovernanceError::new_with_message(
+            ErrorType::InvalidProposal,
+            "Expected MintSnsTokens to have an an amount_e8s",
+        ))?;
+        self.ledger
+            .transfer_funds(amount_e8s, 0, None, to, mint.memo())
+            .await?;
+        Ok(())
+    }
+
+    async fn perform_manage_ledger_parameters(
+        &mut self,
+        proposal_id: u64,
+        manage_ledger_parameters: ManageLedgerParameters,
+    ) -> Result<(), GovernanceError> {
+        err_if_another_upgrade_is_in_progress(&self.proto.proposals, proposal_id)?;
+
+        let current_version = self.proto.deployed_version_or_panic();
+        let ledger_canister_id = self.proto.ledger_canister_id_or_panic();
+
+        let ledger_canister_info = self.env
+            .call_canister(
+                CanisterId::ic_00(),
+                "canister_info",
                 candid::encode_one(
                     CanisterInfoRequest::new(
                         ledger_canister_id,
@@ -2705,6 +3275,7 @@ impl Governance {
         self.upgrade_non_root_canister(
             ledger_canister_id,
             ledger_wasm,
+            None,
             ledger_upgrade_arg,
             CanisterInstallMode::Upgrade,
         )
@@ -2820,6 +3391,64 @@ impl Governance {
             )
     }
 
+    async fn perform_restore_dapp_canister_snapshot(
+        &self,
+        proposal_id: u64,
+        restore_dapp_canister_snapshot: RestoreDappCanisterSnapshot,
+    ) -> Result<(), GovernanceError> {
+        // Restoring a snapshot is similar to an upgrade, in that it replaces the canister's code
+        // and state. Therefore, it must not interleave with upgrades.
+        err_if_another_upgrade_is_in_progress(&self.proto.proposals, proposal_id)?;
+
+        // Snapshots targeted by open or adopted restore proposals are not replaced (see
+        // `take_pre_upgrade_snapshot`). Still, check again that the snapshot is the one that was
+        // taken before the latest upgrade of the canister, as when the proposal was made.
+        validate_and_render_restore_dapp_canister_snapshot(
+            &restore_dapp_canister_snapshot,
+            &HashSet::new(),
+            &self.proto.pre_upgrade_snapshots,
+        )
+        .map_err(|err| GovernanceError::new_with_message(ErrorType::PreconditionFailed, err))?;
+
+        let request = LoadDappCanisterSnapshotRequest::from(restore_dapp_canister_snapshot);
+        let payload = candid::Encode!(&request).map_err(|err| {
+            GovernanceError::new_with_message(
+                ErrorType::InvalidProposal,
+                format!("Could not encode RestoreDappCanisterSnapshot: {err:?}"),
+            )
+        })?;
+        self.env
+            .call_canister(
+                self.proto.root_canister_id_or_panic(),
+                "load_dapp_canister_snapshot",
+                payload,
+            )
+            .await
+            .map_err(|err| {
+                GovernanceError::new_with_message(
+                    ErrorType::External,
+                    format!("Canister method call failed: {err:?}"),
+                )
+            })
+            .and_then(
+                |reply| match candid::Decode!(&reply, LoadDappCanisterSnapshotResponse) {
+                    Ok(LoadDappCanisterSnapshotResponse { failure_reason }) => failure_reason
+                        .map_or(Ok(()), |failure_reason| {
+                            Err(GovernanceError::new_with_message(
+                                ErrorType::External,
+                                format!(
+                                    "Failed to restore dapp canister snapshot: {failure_reason}"
+                                ),
+                            ))
+                        }),
+                    Err(error) => Err(GovernanceError::new_with_message(
+                        ErrorType::External,
+                        format!("Could not decode LoadDappCanisterSnapshotResponse: {error}"),
+                    )),
+                },
+            )
+    }
+
     // Returns an option with the NervousSystemParameters
     fn nervous_system_parameters(&self) -> Option<&NervousSystemParameters> {
         self.proto.parameters.as_ref()
@@ -3130,9 +3759,11 @@ impl Governance {
 
         // Compute whether the proposal is eligible for rewards
         let is_eligible_for_rewards = self.voting_rewards_parameters_or_panic().rewards_enabled();
+        let function_id = u64::from(action);
+        let topic = function_id_to_topic(function_id, &self.proto.id_to_nervous_system_functions);
         // Create the proposal.
         let mut proposal_data = ProposalData {
-            action: u64::from(action),
+            action: function_id,
             id: Some(proposal_id),
             proposer: Some(proposer_id.clone()),
             reject_cost_e8s,
@@ -3156,6 +3787,7 @@ impl Governance {
                 .reward_event_end_timestamp_seconds,
             minimum_yes_proportion_of_total: Some(minimum_yes_proportion_of_total),
             minimum_yes_proportion_of_exercised: Some(minimum_yes_proportion_of_exercised),
+            topic: Some(topic as i32),
         };
 
         proposal_data.wait_for_quiet_state = Some(WaitForQuietState {
@@ -3174,14 +3806,15 @@ impl Governance {
             .expect("Proposer not found.")
             .neuron_fees_e8s += proposal_data.reject_cost_e8s;
 
-        let function_id = u64::from(action);
         // Cast a 'yes'-vote for the proposer, including following.
         Governance::cast_vote_and_cascade_follow(
             &proposal_id,
             proposer_id,
             Vote::Yes,
             function_id,
+            topic,
             &self.function_followee_index,
+            &self.topic_followee_index,
             &self.proto.neurons,
             now_seconds,
             &mut proposal_data.ballots,
@@ -3196,19 +3829,23 @@ impl Governance {
     /// Registers the vote `vote_of_neuron` for the neuron `voting_neuron_id`
     /// and cascades voting according to the following relationship given in
     /// function_followee_index that (for each action) maps a followee to
-    /// the set of followers.
+    /// the set of followers, and in topic_followee_index that does the same
+    /// for each topic.
     ///
     /// This method should only be called with `vote_of_neuron` being `yes`
     /// or `no`.
     ///
     /// `function_id` must be a real function ID, not the "catch-all" (pseudo)
-    /// function ID, which is used for following.
+    /// function ID, which is used for following. `topic` must be the topic of
+    /// the proposal.
     fn cast_vote_and_cascade_follow(
         proposal_id: &ProposalId, // As of Nov, 2023 (a2095be), this is only used for logging.
         voting_neuron_id: &NeuronId,
         vote_of_neuron: Vote,
         function_id: u64,
+        topic: Topic,
         function_followee_index: &BTreeMap<u64, BTreeMap<String, BTreeSet<NeuronId>>>,
+        topic_followee_index: &BTreeMap<Topic, BTreeMap<String, BTreeSet<NeuronId>>>,
         neurons: &BTreeMap<String, Neuron>,
         // As of Dec, 2023 (52eec5c), the next parameter is only used to populate Ballots. In
         // particular, this has no impact on how the implications of following are deduced.
@@ -3222,9 +3859,9 @@ impl Governance {
         // filling in the current neuron's ballot.
         //
         // By default, followers on the specific function_id are reconsidered,
-        // as well as followers have have general "catch-all" following. As an
-        // optimization, catch-all followers are not considered when the
-        // proposal is not Critical.
+        // as well as followers on the topic of the proposal, and followers that
+        // have general "catch-all" following. As an optimization, catch-all
+        // followers are not considered when the proposal is not Critical.
         //
         // E.g. if Alice follows Bob on "catch-all", and Bob votes on a
         // TransferSnsTreasuryFunds proposal, then Alice will not be considered
@@ -3245,6 +3882,10 @@ impl Governance {
                 ProposalCriticality::Critical => (), // Do not use catch-all/fallback following.
             }
 
+            if let Some(member) = topic_followee_index.get(&topic) {
+                members.push(member);
+            }
+
             UnionMultiMap::new(members)
         };
 
@@ -3327,7 +3968,8 @@ impl Governance {
                     }
                 };
 
-                let follower_vote = follower_neuron.would_follow_ballots(function_id, ballots);
+                let follower_vote =
+                    follower_neuron.would_follow_ballots(function_id, topic, ballots);
                 if follower_vote != Vote::Unspecified {
                     // follower_neuron would be swayed by its followees!
                     //
@@ -3423,12 +4065,15 @@ impl Governance {
 
         // Update ballots.
         let function_id = u64::from(action);
+        let topic = proposal.effective_topic(&self.proto.id_to_nervous_system_functions);
         Governance::cast_vote_and_cascade_follow(
             proposal_id,
             neuron_id,
             vote,
             function_id,
+            topic,
             &self.function_followee_index,
+            &self.topic_followee_index,
             &self.proto.neurons,
             now_seconds,
             &mut proposal.ballots,
@@ -3546,6 +4191,109 @@ impl Governance {
         }
     }
 
+    /// Add or remove followees for a given neuron for all the functions of a topic.
+    ///
+    /// If the list of followees is empty, remove the followees for this topic.
+    /// If the list has at least one element, replace the current list of
+    /// followees for the given topic with the provided list. Note that the list
+    /// is replaced, not added to.
+    ///
+    /// Preconditions:
+    /// - the follower neuron exists
+    /// - the caller has the permission to change followers (same authorization
+    ///   as voting required, i.e., permission `Vote`)
+    /// - the topic is specified
+    /// - the list of followers is not too long (does not exceed max_followees_per_function
+    ///   as defined in the nervous system parameters)
+    fn set_following(
+        &mut self,
+        id: &NeuronId,
+        caller: &PrincipalId,
+        set_following: &SetFollowing,
+    ) -> Result<(), GovernanceError> {
+        // As for `follow`, the reverse index of all follow relationships on
+        // topics, i.e., the `topic_followee_index`, has to be maintained.
+        let neuron = self.proto.neurons.get_mut(&id.to_string()).ok_or_else(||
+            // The specified neuron is not present.
+            GovernanceError::new_with_message(ErrorType::NotFound, format!("Follower neuron not found: {}", id)))?;
+
+        // Check that the caller is authorized to change followers (same authorization
+        // as voting required).
+        neuron.check_authorized(caller, NeuronPermissionType::Vote)?;
+
+        let max_followees_per_function = self
+            .proto
+            .parameters
+            .as_ref()
+            .expect("NervousSystemParameters not present")
+            .max_followees_per_function
+            .expect("NervousSystemParameters must have max_followees_per_function");
+
+        // Check that the list of followees is not too long (see `follow`).
+        if set_following.followees.len() > max_followees_per_function as usize {
+            return Err(GovernanceError::new_with_message(
+                ErrorType::InvalidCommand,
+                "Too many followees.",
+            ));
+        }
+
+        let topic = match Topic::try_from(set_following.topic) {
+            Ok(Topic::Unspecified) | Err(_) => {
+                return Err(GovernanceError::new_with_message(
+                    ErrorType::InvalidCommand,
+                    format!("Invalid topic: {}", set_following.topic),
+                ));
+            }
+            Ok(topic) => topic,
+        };
+
+        // First, remove the current followees for this neuron and
+        // this topic from the topic followee index. Followees without any
+        // followers left are dropped from the index, so that it does not
+        // grow with every change of following.
+        if let Some(neuron_followees) = neuron.topic_followees.get(&(topic as i32)) {
+            if let Some(followee_index) = self.topic_followee_index.get_mut(&topic) {
+                for followee in &neuron_followees.followees {
+                    let followee = followee.to_string();
+                    if let Some(all_followers) = followee_index.get_mut(&followee) {
+                        all_followers.remove(id);
+                        if all_followers.is_empty() {
+                            followee_index.remove(&followee);
+                        }
+                    }
+                }
+                if followee_index.is_empty() {
+                    self.topic_followee_index.remove(&topic);
+                }
+            }
+        }
+
+        if set_following.followees.is_empty() {
+            // This operation clears the neuron's followees for the given topic.
+            neuron.topic_followees.remove(&(topic as i32));
+            return Ok(());
+        }
+
+        // Insert the new list of followees for this topic in the neuron's
+        // topic followees, replacing the old list, which has already been
+        // removed from the followee index above.
+        neuron.topic_followees.insert(
+            topic as i32,
+            Followees {
+                followees: set_following.followees.clone(),
+            },
+        );
+        let cache = self.topic_followee_index.entry(topic).or_default();
+        for followee in &set_following.followees {
+            cache
+                .entry(followee.to_string())
+                .or_default()
+                .insert(id.clone());
+        }
+
+        Ok(())
+    }
+
     /// Configures a given neuron (specified by the given neuron id).
     /// Specifically, this allows to stop and start dissolving a neuron
     /// as well as to increase a neuron's dissolve delay.
@@ -3739,6 +4487,7 @@ impl Governance {
             auto_stake_maturity: None,
             vesting_period_seconds: None,
             disburse_maturity_in_progress: vec![],
+            topic_followees: BTreeMap::new(),
         };
 
         // This also verifies that there are not too many neurons already.
@@ -3891,6 +4640,7 @@ impl Governance {
                 auto_stake_maturity: neuron_parameter.construct_auto_staking_maturity(),
                 vesting_period_seconds: None,
                 disburse_maturity_in_progress: vec![],
+                topic_followees: BTreeMap::new(),
             };
 
             // Add the neuron to the various data structures and indexes to support neurons. This
@@ -4184,6 +4934,9 @@ impl Governance {
             C::Follow(f) => self
                 .follow(&neuron_id, caller, f)
                 .map(|_| ManageNeuronResponse::follow_response()),
+            C::SetFollowing(s) => self
+                .set_following(&neuron_id, caller, s)
+                .map(|_| ManageNeuronResponse::set_following_response()),
             C::MakeProposal(p) => self
                 .make_proposal(&neuron_id, caller, p)
                 .await
@@ -4245,6 +4998,7 @@ impl Governance {
             Disburse(_) => err("Disburse"),
             Split(_) => err("Split"),
             Follow(_)
+            | SetFollowing(_)
             | MakeProposal(_)
             | RegisterVote(_)
             | ClaimOrRefresh(_)
@@ -4436,46 +5190,287 @@ impl Governance {
         self.proto.is_finalizing_disburse_maturity = None;
     }
 
-    /// When a neuron is finally dissolved, if there is any staked maturity it is moved to regular maturity
-    /// which can be spawned.
-    pub(crate) fn maybe_move_staked_maturity(&mut self) {
+    /// Removes the treasury payment streams that were cancelled or paid in full more than
+    /// FINISHED_TREASURY_PAYMENT_STREAM_RETENTION_DURATION_SECONDS ago.
+    fn prune_finished_treasury_payment_streams(&mut self) {
         let now_seconds = self.env.now();
-        // Filter all the neurons that are currently in "dissolved" state and have some staked maturity.
-        for neuron in self.proto.neurons.values_mut().filter(|n| {
-            n.state(now_seconds) == NeuronState::Dissolved
-                && n.staked_maturity_e8s_equivalent.unwrap_or(0) > 0
-        }) {
-            neuron.maturity_e8s_equivalent = neuron
-                .maturity_e8s_equivalent
-                .saturating_add(neuron.staked_maturity_e8s_equivalent.unwrap_or(0));
-            neuron.staked_maturity_e8s_equivalent = None;
-        }
+        self.proto
+            .treasury_payment_streams
+            .retain(|payment_stream_id, payment_stream| {
+                let Some(finished_timestamp_seconds) = payment_stream.finished_timestamp_seconds()
+                else {
+                    return true;
+                };
+                let retain = now_seconds
+                    < finished_timestamp_seconds.saturating_add(
+                        FINISHED_TREASURY_PAYMENT_STREAM_RETENTION_DURATION_SECONDS,
+                    );
+                if !retain {
+                    log!(
+                        INFO,
+                        "Removing treasury payment stream {}, which finished at {}.",
+                        payment_stream_id,
+                        finished_timestamp_seconds
+                    );
+                }
+                retain
+            });
     }
 
-    /// Garbage collect obsolete data from the governance canister.
+    /// Pays out whatever has vested, but has not been paid yet, for each treasury payment stream.
     ///
-    /// Current implementation only garbage collects proposals - not neurons.
-    ///
-    /// Returns true if GC was run and false otherwise.
-    pub fn maybe_gc(&mut self) -> bool {
+    /// The amount to pay is recorded as the pending payment of the stream, and added to its paid
+    /// amount, before the transfer is made, so that a concurrent call cannot pay the same
+    /// installments twice. If the ledger rejects the transfer, the payment is taken back out of
+    /// the paid amount, and the installments are paid by a new transfer later. If the outcome of
+    /// the transfer is not known, the payment stays pending, and is retried later with the same
+    /// created_at_time. That way, the ledger does not record the transfer twice.
+    async fn maybe_pay_treasury_payment_streams(&mut self) {
         let now_seconds = self.env.now();
-        // Run GC if either (a) more than 24 hours have passed since it
-        // was run last, or (b) more than 100 proposals have been
-        // added since it was run last.
-        if !(now_seconds > self.latest_gc_timestamp_seconds + 60 * 60 * 24
-            || self.proto.proposals.len() > self.latest_gc_num_proposals + 100)
-        {
-            // Condition to run was not met. Return false.
-            return false;
-        }
-        self.latest_gc_timestamp_seconds = self.env.now();
-        log!(
-            INFO,
-            "Running GC now at timestamp {} seconds",
-            self.latest_gc_timestamp_seconds
-        );
+        self.expire_pending_treasury_payment_stream_payments(now_seconds);
 
-        let max_proposals_to_keep_per_action = match self
+        let payment_stream_ids: Vec<u64> = self
+            .proto
+            .treasury_payment_streams
+            .iter()
+            .filter(|(_, payment_stream)| {
+                let retry_backoff_elapsed = payment_stream
+                    .last_failed_payment_timestamp_seconds
+                    .map_or(true, |last_failed_payment_timestamp_seconds| {
+                        now_seconds
+                            >= last_failed_payment_timestamp_seconds
+                                .saturating_add(TREASURY_PAYMENT_STREAM_RETRY_INTERVAL_SECONDS)
+                    });
+                let has_payment_to_make = match payment_stream.pending_payment_e8s {
+                    // A pending payment whose transfer has not failed is still in flight.
+                    Some(_) => payment_stream
+                        .last_failed_payment_timestamp_seconds
+                        .is_some(),
+                    None => payment_stream.due_e8s(now_seconds) > 0,
+                };
+                retry_backoff_elapsed && has_payment_to_make
+            })
+            .map(|(payment_stream_id, _)| *payment_stream_id)
+            .collect();
+
+        for payment_stream_id in payment_stream_ids {
+            // Look the stream up again, as it might have changed while a previous transfer
+            // was in flight.
+            let Some(payment_stream) = self
+                .proto
+                .treasury_payment_streams
+                .get_mut(&payment_stream_id)
+            else {
+                continue;
+            };
+            let Some(terms) = payment_stream.terms.clone() else {
+                log!(
+                    ERROR,
+                    "Treasury payment stream {} has no terms. Cannot pay.",
+                    payment_stream_id
+                );
+                continue;
+            };
+            let to = match (
+                terms.to_principal,
+                terms
+                    .to_subaccount
+                    .as_ref()
+                    .map(|s| bytes_to_subaccount(&s.subaccount[..]))
+                    .transpose(),
+            ) {
+                (Some(to_principal), Ok(to_subaccount)) => Account {
+                    owner: to_principal.0,
+                    subaccount: to_subaccount,
+                },
+                (to_principal, to_subaccount) => {
+                    log!(
+                        ERROR,
+                        "Treasury payment stream {} has an invalid recipient ({:?}, {:?}). \
+                         Cannot pay.",
+                        payment_stream_id,
+                        to_principal,
+                        to_subaccount,
+                    );
+                    continue;
+                }
+            };
+
+            let (amount_e8s, created_at_time_nanos) = match (
+                payment_stream.pending_payment_e8s,
+                payment_stream.pending_payment_created_at_time_nanos,
+            ) {
+                (Some(pending_payment_e8s), Some(pending_payment_created_at_time_nanos)) => {
+                    // A pending payment whose transfer has not failed is still in flight.
+                    if payment_stream
+                        .last_failed_payment_timestamp_seconds
+                        .is_none()
+                    {
+                        continue;
+                    }
+                    (pending_payment_e8s, pending_payment_created_at_time_nanos)
+                }
+                _ => {
+                    let due_e8s = payment_stream.due_e8s(now_seconds);
+                    if due_e8s == 0 {
+                        continue;
+                    }
+                    // The stream ID is added, so that payments of different streams that are
+                    // made at the same time, of the same amount, to the same recipient are not
+                    // taken for duplicates of one another by the ledger.
+                    let created_at_time_nanos = now_seconds
+                        .saturating_mul(1_000_000_000)
+                        .saturating_add(payment_stream_id % 1_000_000_000);
+                    let paid_e8s = payment_stream.paid_e8s.unwrap_or_default();
+                    payment_stream.paid_e8s = Some(paid_e8s.saturating_add(due_e8s));
+                    payment_stream.pending_payment_e8s = Some(due_e8s);
+                    payment_stream.pending_payment_created_at_time_nanos =
+                        Some(created_at_time_nanos);
+                    (due_e8s, created_at_time_nanos)
+                }
+            };
+            // Marks the pending payment as in flight.
+            payment_stream.last_failed_payment_timestamp_seconds = None;
+
+            let transfer_result = match self.treasury_ledger(terms.from_treasury()) {
+                Ok((ledger, fee_e8s, from_subaccount, _)) => {
+                    ledger
+                        .transfer_funds_with_created_at_time(
+                            amount_e8s,
+                            fee_e8s,
+                            from_subaccount,
+                            to,
+                            terms.memo.unwrap_or(0),
+                            created_at_time_nanos,
+                        )
+                        .await
+                }
+                Err(err) => Err(TransferWithCreatedAtTimeError::Rejected(
+                    NervousSystemError::new_with_message(err.error_message),
+                )),
+            };
+
+            let Some(payment_stream) = self
+                .proto
+                .treasury_payment_streams
+                .get_mut(&payment_stream_id)
+            else {
+                continue;
+            };
+            match transfer_result {
+                Ok(()) => {
+                    log!(
+                        INFO,
+                        "Paid {} e8s for treasury payment stream {}.",
+                        amount_e8s,
+                        payment_stream_id
+                    );
+                    payment_stream.pending_payment_e8s = None;
+                    payment_stream.pending_payment_created_at_time_nanos = None;
+                    payment_stream.last_payment_timestamp_seconds = Some(now_seconds);
+                    payment_stream.last_failed_payment_timestamp_seconds = None;
+                }
+                Err(TransferWithCreatedAtTimeError::Rejected(err)) => {
+                    log!(
+                        ERROR,
+                        "The ledger rejected the payment of {} e8s for treasury payment stream \
+                         {}, will make a new payment later: {}",
+                        amount_e8s,
+                        payment_stream_id,
+                        err
+                    );
+                    // The funds were not transferred, so the installments are due again.
+                    let paid_e8s = payment_stream.paid_e8s.unwrap_or_default();
+                    payment_stream.paid_e8s = Some(paid_e8s.saturating_sub(amount_e8s));
+                    payment_stream.pending_payment_e8s = None;
+                    payment_stream.pending_payment_created_at_time_nanos = None;
+                    payment_stream.last_failed_payment_timestamp_seconds = Some(now_seconds);
+                }
+                Err(TransferWithCreatedAtTimeError::UnknownOutcome(err)) => {
+                    log!(
+                        ERROR,
+                        "Failed to pay {} e8s for treasury payment stream {}, will retry: {}",
+                        amount_e8s,
+                        payment_stream_id,
+                        err
+                    );
+                    payment_stream.last_failed_payment_timestamp_seconds = Some(now_seconds);
+                }
+            }
+        }
+    }
+
+    /// Gives up on the pending payments that could not be confirmed within
+    /// TREASURY_PAYMENT_STREAM_PENDING_PAYMENT_EXPIRY_SECONDS. Only payments whose transfer had an
+    /// unknown outcome stay pending, as rejected payments are cleared right away. Retrying them
+    /// any longer would not be safe, as the ledger would no longer recognize a duplicate
+    /// transfer. As it is not known whether the transfer was recorded, the payment is still
+    /// counted as paid, so that it is paid at most once.
+    fn expire_pending_treasury_payment_stream_payments(&mut self, now_seconds: u64) {
+        for (payment_stream_id, payment_stream) in self.proto.treasury_payment_streams.iter_mut() {
+            let Some(pending_payment_created_at_time_nanos) =
+                payment_stream.pending_payment_created_at_time_nanos
+            else {
+                continue;
+            };
+            let expiry_timestamp_seconds = (pending_payment_created_at_time_nanos / 1_000_000_000)
+                .saturating_add(TREASURY_PAYMENT_STREAM_PENDING_PAYMENT_EXPIRY_SECONDS);
+            if now_seconds < expiry_timestamp_seconds {
+                continue;
+            }
+            log!(
+                ERROR,
+                "Giving up on the pending payment of {:?} e8s for treasury payment stream {}, \
+                 which could not be confirmed. It is still counted as paid, and might have to be \
+                 paid by a new proposal if it was not.",
+                payment_stream.pending_payment_e8s,
+                payment_stream_id,
+            );
+            payment_stream.pending_payment_e8s = None;
+            payment_stream.pending_payment_created_at_time_nanos = None;
+        }
+    }
+
+    /// When a neuron is finally dissolved, if there is any staked maturity it is moved to regular maturity
+    /// which can be spawned.
+    pub(crate) fn maybe_move_staked_maturity(&mut self) {
+        let now_seconds = self.env.now();
+        // Filter all the neurons that are currently in "dissolved" state and have some staked maturity.
+        for neuron in self.proto.neurons.values_mut().filter(|n| {
+            n.state(now_seconds) == NeuronState::Dissolved
+                && n.staked_maturity_e8s_equivalent.unwrap_or(0) > 0
+        }) {
+            neuron.maturity_e8s_equivalent = neuron
+                .maturity_e8s_equivalent
+                .saturating_add(neuron.staked_maturity_e8s_equivalent.unwrap_or(0));
+            neuron.staked_maturity_e8s_equivalent = None;
+        }
+    }
+
+    /// Garbage collect obsolete data from the governance canister.
+    ///
+    /// Current implementation only garbage collects proposals - not neurons.
+    ///
+    /// Returns true if GC was run and false otherwise.
+    pub fn maybe_gc(&mut self) -> bool {
+        let now_seconds = self.env.now();
+        // Run GC if either (a) more than 24 hours have passed since it
+        // was run last, or (b) more than 100 proposals have been
+        // added since it was run last.
+        if !(now_seconds > self.latest_gc_timestamp_seconds + 60 * 60 * 24
+            || self.proto.proposals.len() > self.latest_gc_num_proposals + 100)
+        {
+            // Condition to run was not met. Return false.
+            return false;
+        }
+        self.latest_gc_timestamp_seconds = self.env.now();
+        log!(
+            INFO,
+            "Running GC now at timestamp {} seconds",
+            self.latest_gc_timestamp_seconds
+        );
+
+        let max_proposals_to_keep_per_action = match self
             .nervous_system_parameters()
             .and_then(|params| params.max_proposals_to_keep_per_action)
         {
@@ -4566,6 +5561,10 @@ impl Governance {
 
         self.maybe_finalize_disburse_maturity().await;
 
+        self.maybe_pay_treasury_payment_streams().await;
+
+        self.prune_finished_treasury_payment_streams();
+
         self.maybe_move_staked_maturity();
 
         self.maybe_gc();
@@ -5410,6 +6409,37 @@ impl Governance {
         }
     }
 
+    /// Returns a page of treasury payment streams, including ones that have been cancelled or
+    /// paid in full recently, starting with the most recent stream.
+    ///
+    /// At most `request.limit` streams are returned. If `request.limit` is 0 or exceeds
+    /// MAX_LIST_TREASURY_PAYMENT_STREAMS_RESULTS, the latter is used.
+    pub fn list_treasury_payment_streams(
+        &self,
+        request: &ListTreasuryPaymentStreamsRequest,
+    ) -> ListTreasuryPaymentStreamsResponse {
+        let limit =
+            if request.limit == 0 || request.limit > MAX_LIST_TREASURY_PAYMENT_STREAMS_RESULTS {
+                MAX_LIST_TREASURY_PAYMENT_STREAMS_RESULTS
+            } else {
+                request.limit
+            } as usize;
+        let payment_streams = &self.proto.treasury_payment_streams;
+        let rng = if let Some(before_payment_stream_id) = request.before_payment_stream_id {
+            payment_streams.range(..before_payment_stream_id)
+        } else {
+            payment_streams.range(..)
+        };
+
+        ListTreasuryPaymentStreamsResponse {
+            treasury_payment_streams: rng
+                .rev()
+                .take(limit)
+                .map(|(_, payment_stream)| payment_stream.clone())
+                .collect(),
+        }
+    }
+
     pub fn get_maturity_modulation(
         &self,
         _: GetMaturityModulationRequest,
@@ -5489,10 +6519,11 @@ fn err_if_another_upgrade_is_in_progress(
     id_to_proposal_data: &BTreeMap</* proposal ID */ u64, ProposalData>,
     executing_proposal_id: u64,
 ) -> Result<(), GovernanceError> {
-    let upgrade_action_ids: [u64; 3] = [
+    let upgrade_action_ids: [u64; 4] = [
         (&Action::UpgradeSnsControlledCanister(UpgradeSnsControlledCanister::default())).into(),
         (&Action::UpgradeSnsToNextVersion(UpgradeSnsToNextVersion::default())).into(),
         (&Action::ManageLedgerParameters(ManageLedgerParameters::default())).into(),
+        (&Action::RestoreDappCanisterSnapshot(RestoreDappCanisterSnapshot::default())).into(),
     ];
 
     for (other_proposal_id, proposal_data) in id_to_proposal_data {
@@ -5604,6 +6635,7 @@ mod tests {
     };
     use ic_nervous_system_common_test_keys::{
         TEST_NEURON_1_OWNER_PRINCIPAL, TEST_NEURON_2_OWNER_PRINCIPAL, TEST_USER1_KEYPAIR,
+        TEST_USER1_PRINCIPAL,
     };
     use ic_nns_constants::SNS_WASM_CANISTER_ID;
     use ic_protobuf::types::v1::CanisterInstallMode as CanisterInstallModeProto;
@@ -5612,7 +6644,10 @@ mod tests {
     use maplit::{btreemap, btreeset};
     use pretty_assertions::assert_eq;
     use proptest::prelude::{prop_assert, proptest};
-    use std::sync::{Arc, Mutex};
+    use std::sync::{
+        atomic::{AtomicBool, Ordering as AtomicOrdering},
+        Arc, Mutex,
+    };
 
     mod fail_stuck_upgrade_in_progress_tests;
 
@@ -5631,6 +6666,18 @@ mod tests {
             unimplemented!();
         }
 
+        async fn transfer_funds_with_created_at_time(
+            &self,
+            _amount_e8s: u64,
+            _fee_e8s: u64,
+            _from_subaccount: Option<Subaccount>,
+            _to: Account,
+            _memo: u64,
+            _created_at_time_nanos: u64,
+        ) -> Result<u64, TransferWithCreatedAtTimeError> {
+            unimplemented!()
+        }
+
         async fn total_supply(&self) -> Result<Tokens, NervousSystemError> {
             unimplemented!()
         }
@@ -5659,6 +6706,18 @@ mod tests {
             Ok(0)
         }
 
+        async fn transfer_funds_with_created_at_time(
+            &self,
+            _amount_e8s: u64,
+            _fee_e8s: u64,
+            _from_subaccount: Option<Subaccount>,
+            _to: Account,
+            _memo: u64,
+            _created_at_time_nanos: u64,
+        ) -> Result<u64, TransferWithCreatedAtTimeError> {
+            Ok(0)
+        }
+
         async fn total_supply(&self) -> Result<Tokens, NervousSystemError> {
             Ok(Tokens::default())
         }
@@ -5792,6 +6851,18 @@ mod tests {
                 Ok(1)
             }
 
+            async fn transfer_funds_with_created_at_time(
+                &self,
+                _amount_e8s: u64,
+                _fee_e8s: u64,
+                _from_subaccount: Option<Subaccount>,
+                _to: Account,
+                _memo: u64,
+                _created_at_time_nanos: u64,
+            ) -> Result<u64, TransferWithCreatedAtTimeError> {
+                unimplemented!()
+            }
+
             async fn total_supply(&self) -> Result<Tokens, NervousSystemError> {
                 unimplemented!()
             }
@@ -5974,6 +7045,7 @@ mod tests {
                         target_method_name: Some("test_method".to_string()),
                         validator_canister_id: Some(CanisterId::from_u64(1).get()),
                         validator_method_name: Some("test_validator_method".to_string()),
+                        topic: None,
                     },
                 )),
             },
@@ -8339,6 +9411,7 @@ mod tests {
                 new_canister_wasm: vec![0, 0x61, 0x73, 0x6D, 2, 0, 0, 0],
                 canister_upgrade_arg: None,
                 mode: Some(CanisterInstallModeProto::Upgrade.into()),
+                chunked_canister_wasm: None,
             });
 
             // Upgrade Proposal
@@ -8385,6 +9458,20 @@ mod tests {
             .unwrap(),
             Ok(Encode!(&std_sns_canisters_summary_response()).unwrap()),
         );
+        env.set_call_canister_response(
+            root_canister_id,
+            "take_dapp_canister_snapshot",
+            Encode!(&TakeDappCanisterSnapshotRequest {
+                canister_id: Some(dapp_canisters[0].get()),
+                replace_snapshot: None,
+            })
+            .unwrap(),
+            Ok(Encode!(&TakeDappCanisterSnapshotResponse {
+                snapshot_id: Some(vec![1]),
+                failure_reason: None,
+            })
+            .unwrap()),
+        );
         // Make all of our proposals and initialize them in Governance
         let dapp_proposal = create_upgrade_proposal(1, dapp_canisters[0]);
         let root_proposal = create_upgrade_proposal(2, root_canister_id);
@@ -8454,48 +9541,519 @@ mod tests {
     }
 
     #[test]
-    fn test_allow_canister_upgrades_while_motion_proposal_execution_is_in_progress() {
-        // Step 1: Prepare the world.
+    fn test_sns_controlled_canister_upgrade_with_chunked_wasm() {
         use ProposalDecisionStatus as Status;
 
-        let motion_action_id: u64 = (&Action::Motion(Motion::default())).into();
+        let root_canister_id = *TEST_ROOT_CANISTER_ID;
+        let governance_canister_id = *TEST_GOVERNANCE_CANISTER_ID;
+        let ledger_canister_id = *TEST_LEDGER_CANISTER_ID;
+        let dapp_canister_id = TEST_DAPP_CANISTER_IDS[0];
 
-        let proposal_id = 1_u64;
-        let proposal = ProposalData {
-            action: motion_action_id,
-            id: Some(proposal_id.into()),
-            decided_timestamp_seconds: 1,
-            latest_tally: Some(Tally {
-                yes: 1,
-                no: 0,
-                total: 1,
-                timestamp_seconds: 1,
-            }),
-            ..Default::default()
+        // Helper to create open upgrade proposals, whose wasm is stored in `store_canister_id`.
+        let create_upgrade_proposal = |id: u64, store_canister_id: CanisterId| {
+            let action = Action::UpgradeSnsControlledCanister(UpgradeSnsControlledCanister {
+                canister_id: Some(dapp_canister_id.get()),
+                new_canister_wasm: vec![],
+                canister_upgrade_arg: None,
+                mode: Some(CanisterInstallModeProto::Upgrade.into()),
+                chunked_canister_wasm: Some(ChunkedCanisterWasm {
+                    wasm_module_hash: vec![0xaa; 32],
+                    store_canister_id: Some(store_canister_id.get()),
+                    chunk_hashes_list: vec![vec![0xbb; 32], vec![0xcc; 32]],
+                }),
+            });
+
+            let proposal = ProposalData {
+                action: (&action).into(),
+                id: Some(id.into()),
+                ballots: btreemap! {
+                    "neuron 1".to_string() => Ballot {
+                        vote: Vote::Yes as i32,
+                        voting_power: 9001,
+                        cast_timestamp_seconds: 1,
+                    },
+                },
+                wait_for_quiet_state: Some(WaitForQuietState::default()),
+                proposal: Some(Proposal {
+                    title: "Upgrade Proposal".to_string(),
+                    action: Some(action),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            };
+            assert_eq!(proposal.status(), Status::Open);
+
+            proposal
         };
-        assert_eq!(proposal.status(), Status::Adopted);
 
-        // Step 2: Run code under test.
-        let some_other_proposal_id = 99_u64;
-        let result = err_if_another_upgrade_is_in_progress(
-            &btreemap! {
-                proposal_id => proposal,
-            },
-            some_other_proposal_id,
+        let mut env = NativeEnvironment::new(Some(governance_canister_id));
+        env.set_call_canister_response(
+            root_canister_id,
+            "get_sns_canisters_summary",
+            Encode!(&GetSnsCanistersSummaryRequest {
+                update_canister_list: Some(true)
+            })
+            .unwrap(),
+            Ok(Encode!(&std_sns_canisters_summary_response()).unwrap()),
+        );
+        env.set_call_canister_response(
+            root_canister_id,
+            "take_dapp_canister_snapshot",
+            Encode!(&TakeDappCanisterSnapshotRequest {
+                canister_id: Some(dapp_canister_id.get()),
+                replace_snapshot: None,
+            })
+            .unwrap(),
+            Ok(Encode!(&TakeDappCanisterSnapshotResponse {
+                snapshot_id: Some(vec![1]),
+                failure_reason: None,
+            })
+            .unwrap()),
+        );
+        // Root is asked to install the wasm from the chunk store of the dapp canister.
+        env.require_call_canister_invocation(
+            root_canister_id,
+            "change_canister",
+            Encode!(&ChangeCanisterRequest::new(
+                true,
+                CanisterInstallMode::Upgrade,
+                dapp_canister_id
+            )
+            .with_arg(Encode!().unwrap())
+            .with_chunked_wasm(RootChunkedCanisterWasm {
+                wasm_module_hash: vec![0xaa; 32],
+                store_canister_id: dapp_canister_id,
+                chunk_hashes_list: vec![vec![0xbb; 32], vec![0xcc; 32]],
+            }))
+            .unwrap(),
+            Some(Ok(vec![])),
         );
 
-        // Step 3: Inspect result.
-        assert!(result.is_ok(), "{:#?}", result);
+        let mut governance = Governance::new(
+            GovernanceProto {
+                proposals: btreemap! {
+                    1 => create_upgrade_proposal(1, dapp_canister_id),
+                    2 => create_upgrade_proposal(2, ledger_canister_id),
+                },
+                root_canister_id: Some(root_canister_id.get()),
+                ledger_canister_id: Some(ledger_canister_id.get()),
+                ..basic_governance_proto()
+            }
+            .try_into()
+            .unwrap(),
+            Box::new(env),
+            Box::new(DoNothingLedger {}),
+            Box::new(DoNothingLedger {}),
+            Box::new(FakeCmc::new()),
+        );
+
+        let upgrade_proposal = execute_proposal(&mut governance, 1);
+        assert_eq!(
+            upgrade_proposal.status(),
+            Status::Executed,
+            "{upgrade_proposal:#?}"
+        );
+
+        // The chunks cannot be stored in a canister that is not a registered dapp canister.
+        let upgrade_proposal = execute_proposal(&mut governance, 2);
+        assert_eq!(upgrade_proposal.status(), Status::Failed);
+        assert_eq!(
+            upgrade_proposal.failure_reason.as_ref().unwrap().error_type,
+            ErrorType::InvalidCommand as i32,
+            "{upgrade_proposal:#?}"
+        );
     }
 
     #[test]
-    fn test_allow_canister_upgrades_while_another_upgrade_proposal_is_open() {
-        // Step 1: Prepare the world.
+    fn test_sns_controlled_canister_upgrade_takes_snapshot_that_can_be_restored() {
         use ProposalDecisionStatus as Status;
 
-        let upgrade_action_id: u64 =
-            (&Action::UpgradeSnsControlledCanister(UpgradeSnsControlledCanister::default())).into();
-
+        let root_canister_id = *TEST_ROOT_CANISTER_ID;
+        let governance_canister_id = *TEST_GOVERNANCE_CANISTER_ID;
+        let ledger_canister_id = *TEST_LEDGER_CANISTER_ID;
+        let dapp_canister_id = TEST_DAPP_CANISTER_IDS[0];
+
+        // Helper to create open proposals.
+        let create_proposal = |id: u64, action: Action| {
+            let proposal = ProposalData {
+                action: (&action).into(),
+                id: Some(id.into()),
+                ballots: btreemap! {
+                    "neuron 1".to_string() => Ballot {
+                        vote: Vote::Yes as i32,
+                        voting_power: 9001,
+                        cast_timestamp_seconds: 1,
+                    },
+                },
+                wait_for_quiet_state: Some(WaitForQuietState::default()),
+                proposal: Some(Proposal {
+                    title: "A proposal".to_string(),
+                    action: Some(action),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            };
+            assert_eq!(proposal.status(), Status::Open);
+
+            proposal
+        };
+        let upgrade_action = Action::UpgradeSnsControlledCanister(UpgradeSnsControlledCanister {
+            canister_id: Some(dapp_canister_id.get()),
+            // small valid wasm
+            new_canister_wasm: vec![0, 0x61, 0x73, 0x6D, 2, 0, 0, 0],
+            canister_upgrade_arg: None,
+            mode: Some(CanisterInstallModeProto::Upgrade.into()),
+            chunked_canister_wasm: None,
+        });
+
+        // A snapshot was taken when the canister was previously upgraded (by a proposal that has
+        // since been garbage collected). The new snapshot replaces it.
+        let upgrade_proposal = create_proposal(2, upgrade_action);
+        let restore_proposal = create_proposal(
+            3,
+            Action::RestoreDappCanisterSnapshot(RestoreDappCanisterSnapshot {
+                canister_id: Some(dapp_canister_id.get()),
+                snapshot_id: Some(vec![8]),
+            }),
+        );
+
+        let mut env = NativeEnvironment::new(Some(governance_canister_id));
+        env.set_call_canister_response(
+            root_canister_id,
+            "get_sns_canisters_summary",
+            Encode!(&GetSnsCanistersSummaryRequest {
+                update_canister_list: Some(true)
+            })
+            .unwrap(),
+            Ok(Encode!(&std_sns_canisters_summary_response()).unwrap()),
+        );
+        env.require_call_canister_invocation(
+            root_canister_id,
+            "take_dapp_canister_snapshot",
+            Encode!(&TakeDappCanisterSnapshotRequest {
+                canister_id: Some(dapp_canister_id.get()),
+                replace_snapshot: Some(vec![7]),
+            })
+            .unwrap(),
+            Some(Ok(Encode!(&TakeDappCanisterSnapshotResponse {
+                snapshot_id: Some(vec![8]),
+                failure_reason: None,
+            })
+            .unwrap())),
+        );
+        env.require_call_canister_invocation(
+            root_canister_id,
+            "load_dapp_canister_snapshot",
+            Encode!(&LoadDappCanisterSnapshotRequest {
+                canister_id: Some(dapp_canister_id.get()),
+                snapshot_id: Some(vec![8]),
+            })
+            .unwrap(),
+            Some(Ok(Encode!(&LoadDappCanisterSnapshotResponse {
+                failure_reason: None,
+            })
+            .unwrap())),
+        );
+
+        let mut governance = Governance::new(
+            GovernanceProto {
+                proposals: btreemap! {
+                    2 => upgrade_proposal,
+                    3 => restore_proposal,
+                },
+                pre_upgrade_snapshots: btreemap! {
+                    dapp_canister_id.get().to_string() => PreUpgradeSnapshot {
+                        proposal_id: 1,
+                        snapshot_id: vec![7],
+                    },
+                },
+                root_canister_id: Some(root_canister_id.get()),
+                ledger_canister_id: Some(ledger_canister_id.get()),
+                ..basic_governance_proto()
+            }
+            .try_into()
+            .unwrap(),
+            Box::new(env),
+            Box::new(DoNothingLedger {}),
+            Box::new(DoNothingLedger {}),
+            Box::new(FakeCmc::new()),
+        );
+
+        // The upgrade records the ID of the snapshot taken before upgrading.
+        let upgrade_proposal = execute_proposal(&mut governance, 2);
+        assert_eq!(upgrade_proposal.status(), Status::Executed);
+        assert_eq!(upgrade_proposal.pre_upgrade_snapshot_id, Some(vec![8]));
+        assert_eq!(
+            governance.proto.pre_upgrade_snapshots,
+            btreemap! {
+                dapp_canister_id.get().to_string() => PreUpgradeSnapshot {
+                    proposal_id: 2,
+                    snapshot_id: vec![8],
+                },
+            }
+        );
+
+        // The snapshot can be restored.
+        let restore_proposal = execute_proposal(&mut governance, 3);
+        assert_eq!(
+            restore_proposal.status(),
+            Status::Executed,
+            "{restore_proposal:#?}"
+        );
+    }
+
+    #[test]
+    fn test_sns_controlled_canister_upgrade_keeps_snapshot_targeted_by_restore_proposal() {
+        use ProposalDecisionStatus as Status;
+
+        let root_canister_id = *TEST_ROOT_CANISTER_ID;
+        let governance_canister_id = *TEST_GOVERNANCE_CANISTER_ID;
+        let ledger_canister_id = *TEST_LEDGER_CANISTER_ID;
+        let dapp_canister_id = TEST_DAPP_CANISTER_IDS[0];
+
+        let create_proposal = |id: u64, action: Action| ProposalData {
+            action: (&action).into(),
+            id: Some(id.into()),
+            ballots: btreemap! {
+                "neuron 1".to_string() => Ballot {
+                    vote: Vote::Yes as i32,
+                    voting_power: 9001,
+                    cast_timestamp_seconds: 1,
+                },
+            },
+            wait_for_quiet_state: Some(WaitForQuietState::default()),
+            proposal: Some(Proposal {
+                title: "A proposal".to_string(),
+                action: Some(action),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let upgrade_action = Action::UpgradeSnsControlledCanister(UpgradeSnsControlledCanister {
+            canister_id: Some(dapp_canister_id.get()),
+            // small valid wasm
+            new_canister_wasm: vec![0, 0x61, 0x73, 0x6D, 2, 0, 0, 0],
+            canister_upgrade_arg: None,
+            mode: Some(CanisterInstallModeProto::Upgrade.into()),
+            chunked_canister_wasm: None,
+        });
+
+        // An open proposal wants to restore the snapshot taken before the previous upgrade.
+        let previous_upgrade_proposal = ProposalData {
+            pre_upgrade_snapshot_id: Some(vec![7]),
+            ..create_proposal(1, upgrade_action.clone())
+        };
+        let pre_upgrade_snapshots = btreemap! {
+            dapp_canister_id.get().to_string() => PreUpgradeSnapshot {
+                proposal_id: 1,
+                snapshot_id: vec![7],
+            },
+        };
+        let restore_proposal = create_proposal(
+            2,
+            Action::RestoreDappCanisterSnapshot(RestoreDappCanisterSnapshot {
+                canister_id: Some(dapp_canister_id.get()),
+                snapshot_id: Some(vec![7]),
+            }),
+        );
+        let upgrade_proposal = create_proposal(3, upgrade_action);
+
+        let mut env = NativeEnvironment::new(Some(governance_canister_id));
+        env.set_call_canister_response(
+            root_canister_id,
+            "get_sns_canisters_summary",
+            Encode!(&GetSnsCanistersSummaryRequest {
+                update_canister_list: Some(true)
+            })
+            .unwrap(),
+            Ok(Encode!(&std_sns_canisters_summary_response()).unwrap()),
+        );
+        // Replacing the snapshot would succeed, but must not be attempted.
+        env.set_call_canister_response(
+            root_canister_id,
+            "take_dapp_canister_snapshot",
+            Encode!(&TakeDappCanisterSnapshotRequest {
+                canister_id: Some(dapp_canister_id.get()),
+                replace_snapshot: Some(vec![7]),
+            })
+            .unwrap(),
+            Ok(Encode!(&TakeDappCanisterSnapshotResponse {
+                snapshot_id: Some(vec![8]),
+                failure_reason: None,
+            })
+            .unwrap()),
+        );
+
+        let mut governance = Governance::new(
+            GovernanceProto {
+                proposals: btreemap! {
+                    1 => previous_upgrade_proposal,
+                    2 => restore_proposal,
+                    3 => upgrade_proposal,
+                },
+                pre_upgrade_snapshots: pre_upgrade_snapshots.clone(),
+                root_canister_id: Some(root_canister_id.get()),
+                ledger_canister_id: Some(ledger_canister_id.get()),
+                ..basic_governance_proto()
+            }
+            .try_into()
+            .unwrap(),
+            Box::new(env),
+            Box::new(DoNothingLedger {}),
+            Box::new(DoNothingLedger {}),
+            Box::new(FakeCmc::new()),
+        );
+
+        // The upgrade fails, since it could not be rolled back without replacing the snapshot.
+        let upgrade_proposal = execute_proposal(&mut governance, 3);
+        assert_eq!(upgrade_proposal.status(), Status::Failed);
+        assert_eq!(
+            upgrade_proposal.failure_reason.as_ref().unwrap().error_type,
+            ErrorType::PreconditionFailed as i32,
+            "{upgrade_proposal:#?}"
+        );
+        assert_eq!(upgrade_proposal.pre_upgrade_snapshot_id, None);
+        assert_eq!(
+            governance.proto.pre_upgrade_snapshots,
+            pre_upgrade_snapshots
+        );
+    }
+
+    #[test]
+    fn test_sns_controlled_canister_upgrade_fails_if_no_snapshot_can_be_taken() {
+        use ProposalDecisionStatus as Status;
+
+        let root_canister_id = *TEST_ROOT_CANISTER_ID;
+        let governance_canister_id = *TEST_GOVERNANCE_CANISTER_ID;
+        let ledger_canister_id = *TEST_LEDGER_CANISTER_ID;
+        let dapp_canister_id = TEST_DAPP_CANISTER_IDS[0];
+
+        let action = Action::UpgradeSnsControlledCanister(UpgradeSnsControlledCanister {
+            canister_id: Some(dapp_canister_id.get()),
+            // small valid wasm
+            new_canister_wasm: vec![0, 0x61, 0x73, 0x6D, 2, 0, 0, 0],
+            canister_upgrade_arg: None,
+            mode: Some(CanisterInstallModeProto::Upgrade.into()),
+            chunked_canister_wasm: None,
+        });
+        let upgrade_proposal = ProposalData {
+            action: (&action).into(),
+            id: Some(1.into()),
+            ballots: btreemap! {
+                "neuron 1".to_string() => Ballot {
+                    vote: Vote::Yes as i32,
+                    voting_power: 9001,
+                    cast_timestamp_seconds: 1,
+                },
+            },
+            wait_for_quiet_state: Some(WaitForQuietState::default()),
+            proposal: Some(Proposal {
+                title: "Upgrade Proposal".to_string(),
+                action: Some(action),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let mut env = NativeEnvironment::new(Some(governance_canister_id));
+        env.set_call_canister_response(
+            root_canister_id,
+            "get_sns_canisters_summary",
+            Encode!(&GetSnsCanistersSummaryRequest {
+                update_canister_list: Some(true)
+            })
+            .unwrap(),
+            Ok(Encode!(&std_sns_canisters_summary_response()).unwrap()),
+        );
+        env.require_call_canister_invocation(
+            root_canister_id,
+            "take_dapp_canister_snapshot",
+            Encode!(&TakeDappCanisterSnapshotRequest {
+                canister_id: Some(dapp_canister_id.get()),
+                replace_snapshot: None,
+            })
+            .unwrap(),
+            Some(Ok(Encode!(&TakeDappCanisterSnapshotResponse {
+                snapshot_id: None,
+                failure_reason: Some("Canister snapshots are not enabled".to_string()),
+            })
+            .unwrap())),
+        );
+
+        let mut governance = Governance::new(
+            GovernanceProto {
+                proposals: btreemap! {
+                    1 => upgrade_proposal,
+                },
+                root_canister_id: Some(root_canister_id.get()),
+                ledger_canister_id: Some(ledger_canister_id.get()),
+                ..basic_governance_proto()
+            }
+            .try_into()
+            .unwrap(),
+            Box::new(env),
+            Box::new(DoNothingLedger {}),
+            Box::new(DoNothingLedger {}),
+            Box::new(FakeCmc::new()),
+        );
+
+        // Root is not asked to upgrade the canister.
+        let upgrade_proposal = execute_proposal(&mut governance, 1);
+        assert_eq!(upgrade_proposal.status(), Status::Failed);
+        let failure_reason = upgrade_proposal.failure_reason.as_ref().unwrap();
+        assert_eq!(failure_reason.error_type, ErrorType::External as i32);
+        assert!(
+            failure_reason
+                .error_message
+                .contains("Canister snapshots are not enabled"),
+            "{failure_reason:#?}"
+        );
+        assert!(governance.proto.pre_upgrade_snapshots.is_empty());
+    }
+
+    #[test]
+    fn test_allow_canister_upgrades_while_motion_proposal_execution_is_in_progress() {
+        // Step 1: Prepare the world.
+        use ProposalDecisionStatus as Status;
+
+        let motion_action_id: u64 = (&Action::Motion(Motion::default())).into();
+
+        let proposal_id = 1_u64;
+        let proposal = ProposalData {
+            action: motion_action_id,
+            id: Some(proposal_id.into()),
+            decided_timestamp_seconds: 1,
+            latest_tally: Some(Tally {
+                yes: 1,
+                no: 0,
+                total: 1,
+                timestamp_seconds: 1,
+            }),
+            ..Default::default()
+        };
+        assert_eq!(proposal.status(), Status::Adopted);
+
+        // Step 2: Run code under test.
+        let some_other_proposal_id = 99_u64;
+        let result = err_if_another_upgrade_is_in_progress(
+            &btreemap! {
+                proposal_id => proposal,
+            },
+            some_other_proposal_id,
+        );
+
+        // Step 3: Inspect result.
+        assert!(result.is_ok(), "{:#?}", result);
+    }
+
+    #[test]
+    fn test_allow_canister_upgrades_while_another_upgrade_proposal_is_open() {
+        // Step 1: Prepare the world.
+        use ProposalDecisionStatus as Status;
+
+        let upgrade_action_id: u64 =
+            (&Action::UpgradeSnsControlledCanister(UpgradeSnsControlledCanister::default())).into();
+
         let proposal_id = 1_u64;
         let proposal = ProposalData {
             action: upgrade_action_id,
@@ -8638,6 +10196,7 @@ mod tests {
                     target_method_name: Some("test_method".to_string()),
                     validator_canister_id: Some(CanisterId::from(100).get()),
                     validator_method_name: Some("test_validator_method".to_string()),
+                    topic: None,
                 },
             )),
         };
@@ -9621,6 +11180,7 @@ mod tests {
                     target_method_name: Some("test_method".to_string()),
                     validator_canister_id: Some(CanisterId::from(1).get()),
                     validator_method_name: Some("test_validator_method".to_string()),
+                    topic: None,
                 },
             )),
         };
@@ -9643,6 +11203,7 @@ mod tests {
                     target_method_name: Some("test_method".to_string()),
                     validator_canister_id: Some(invalid_canister_target.get()),
                     validator_method_name: Some("test_validator_method".to_string()),
+                    topic: None,
                 },
             )),
         };
@@ -9866,7 +11427,9 @@ mod tests {
                     &voting_neuron_id,
                     vote_of_neuron,
                     function_id,
+                    function_id_to_topic(function_id, &BTreeMap::new()),
                     &function_followee_index,
+                    &BTreeMap::new(),
                     &neurons,
                     now_seconds,
                     &mut ballots,
@@ -9957,4 +11520,679 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_cast_vote_and_cascade_follow_on_topics() {
+        // Step 1: Prepare the world.
+
+        let proposal_id = ProposalId { id: 42 };
+
+        let voting_neuron_id = NeuronId { id: vec![1] };
+        let other_neuron_id = NeuronId { id: vec![2] };
+        let follows_on_topic_neuron_id = NeuronId { id: vec![3] };
+        let follows_on_topic_and_function_neuron_id = NeuronId { id: vec![4] };
+
+        let upgrade_function_id =
+            u64::from(&Action::UpgradeSnsControlledCanister(Default::default()));
+        let motion_function_id = u64::from(&Action::Motion(Default::default()));
+
+        let follows_on_topic = btreemap! {
+            Topic::DappManagement as i32 => Followees {
+                followees: vec![voting_neuron_id.clone()],
+            },
+        };
+        let neurons = btreemap! {
+            voting_neuron_id.to_string() => Neuron {
+                id: Some(voting_neuron_id.clone()),
+                cached_neuron_stake_e8s: E8, // voting power
+                ..Default::default()
+            },
+            other_neuron_id.to_string() => Neuron {
+                id: Some(other_neuron_id.clone()),
+                cached_neuron_stake_e8s: E8, // voting power
+                ..Default::default()
+            },
+            follows_on_topic_neuron_id.to_string() => Neuron {
+                id: Some(follows_on_topic_neuron_id.clone()),
+                cached_neuron_stake_e8s: E8, // voting power
+                topic_followees: follows_on_topic.clone(),
+                ..Default::default()
+            },
+            // Following on the specific function takes precedence over following on the topic.
+            follows_on_topic_and_function_neuron_id.to_string() => Neuron {
+                id: Some(follows_on_topic_and_function_neuron_id.clone()),
+                cached_neuron_stake_e8s: E8, // voting power
+                followees: btreemap! {
+                    upgrade_function_id => Followees {
+                        followees: vec![other_neuron_id.clone()],
+                    },
+                },
+                topic_followees: follows_on_topic,
+                ..Default::default()
+            },
+        };
+        let registered_functions = BTreeMap::new();
+        let proto = GovernanceProto::default();
+        let function_followee_index = proto.build_function_followee_index(&neurons);
+        let topic_followee_index = GovernanceProto::build_topic_followee_index(&neurons);
+
+        let now_seconds = 123_456_789;
+        let empty_ballot = Ballot {
+            vote: Vote::Unspecified as i32,
+            voting_power: E8,
+            cast_timestamp_seconds: now_seconds,
+        };
+        let yes_ballot = Ballot {
+            vote: Vote::Yes as i32,
+            ..empty_ballot.clone()
+        };
+
+        // Code under test.
+        let cast_vote_and_cascade_follow = |function_id| {
+            let mut ballots = neurons
+                .keys()
+                .map(|neuron_id| (neuron_id.clone(), empty_ballot.clone()))
+                .collect::<BTreeMap<String, Ballot>>();
+
+            Governance::cast_vote_and_cascade_follow(
+                &proposal_id,
+                &voting_neuron_id,
+                Vote::Yes,
+                function_id,
+                function_id_to_topic(function_id, &registered_functions),
+                &function_followee_index,
+                &topic_followee_index,
+                &neurons,
+                now_seconds,
+                &mut ballots,
+            );
+
+            ballots
+        };
+
+        // Step 2 & 3: Run code under test and inspect results.
+
+        // Step A: A proposal in the topic that is followed.
+        assert_eq!(
+            cast_vote_and_cascade_follow(upgrade_function_id),
+            btreemap! {
+                voting_neuron_id.to_string() => yes_ballot.clone(),
+                other_neuron_id.to_string() => empty_ballot.clone(),
+                // Thanks to following on the topic.
+                follows_on_topic_neuron_id.to_string() => yes_ballot.clone(),
+                // Because this follows another neuron on this specific function.
+                follows_on_topic_and_function_neuron_id.to_string() => empty_ballot.clone(),
+            }
+        );
+
+        // Step B: A proposal in another topic.
+        assert_eq!(
+            cast_vote_and_cascade_follow(motion_function_id),
+            btreemap! {
+                voting_neuron_id.to_string() => yes_ballot.clone(),
+                other_neuron_id.to_string() => empty_ballot.clone(),
+                follows_on_topic_neuron_id.to_string() => empty_ballot.clone(),
+                follows_on_topic_and_function_neuron_id.to_string() => empty_ballot.clone(),
+            }
+        );
+    }
+
+    /// A ledger that records the transfers it is asked to make, and that can be told to fail them.
+    /// Like a real ledger, it deduplicates transfers that have the same created_at_time.
+    #[derive(Default)]
+    struct RecordingLedger {
+        transfers: Arc<Mutex<Vec<(u64, u64, Option<Subaccount>, Account, u64)>>>,
+        created_at_times_nanos: Arc<Mutex<Vec<u64>>>,
+        /// The created_at_times of the deduplicated transfers that the ledger recorded.
+        recorded_created_at_times_nanos: Arc<Mutex<Vec<u64>>>,
+        /// Transfers fail without being recorded, and without a reply from the ledger.
+        fail_transfers: Arc<AtomicBool>,
+        /// The ledger rejects transfers.
+        reject_transfers: Arc<AtomicBool>,
+        /// The ledger records transfers, but its reply is lost.
+        lose_replies: Arc<AtomicBool>,
+        balance_e8s: u64,
+    }
+
+    #[async_trait]
+    impl ICRC1Ledger for RecordingLedger {
+        async fn transfer_funds(
+            &self,
+            amount_e8s: u64,
+            fee_e8s: u64,
+            from_subaccount: Option<Subaccount>,
+            to: Account,
+            memo: u64,
+        ) -> Result<u64, NervousSystemError> {
+            self.transfers
+                .lock()
+                .unwrap()
+                .push((amount_e8s, fee_e8s, from_subaccount, to, memo));
+            if self.fail_transfers.load(AtomicOrdering::SeqCst) {
+                return Err(NervousSystemError::new_with_message("Transfer failed."));
+            }
+            Ok(1)
+        }
+
+        async fn transfer_funds_with_created_at_time(
+            &self,
+            amount_e8s: u64,
+            fee_e8s: u64,
+            from_subaccount: Option<Subaccount>,
+            to: Account,
+            memo: u64,
+            created_at_time_nanos: u64,
+        ) -> Result<u64, TransferWithCreatedAtTimeError> {
+            self.transfers
+                .lock()
+                .unwrap()
+                .push((amount_e8s, fee_e8s, from_subaccount, to, memo));
+            self.created_at_times_nanos
+                .lock()
+                .unwrap()
+                .push(created_at_time_nanos);
+            if self.reject_transfers.load(AtomicOrdering::SeqCst) {
+                return Err(TransferWithCreatedAtTimeError::Rejected(
+                    NervousSystemError::new_with_message("Insufficient funds."),
+                ));
+            }
+            if self.fail_transfers.load(AtomicOrdering::SeqCst) {
+                return Err(TransferWithCreatedAtTimeError::UnknownOutcome(
+                    NervousSystemError::new_with_message("Transfer failed."),
+                ));
+            }
+            let mut recorded_created_at_times_nanos =
+                self.recorded_created_at_times_nanos.lock().unwrap();
+            if recorded_created_at_times_nanos.contains(&created_at_time_nanos) {
+                // A duplicate is reported as success.
+                return Ok(1);
+            }
+            recorded_created_at_times_nanos.push(created_at_time_nanos);
+            if self.lose_replies.load(AtomicOrdering::SeqCst) {
+                return Err(TransferWithCreatedAtTimeError::UnknownOutcome(
+                    NervousSystemError::new_with_message("No reply."),
+                ));
+            }
+            Ok(1)
+        }
+
+        async fn total_supply(&self) -> Result<Tokens, NervousSystemError> {
+            unimplemented!()
+        }
+
+        async fn account_balance(&self, _account: Account) -> Result<Tokens, NervousSystemError> {
+            Ok(Tokens::from_e8s(self.balance_e8s))
+        }
+
+        fn canister_id(&self) -> CanisterId {
+            unimplemented!()
+        }
+    }
+
+    #[test]
+    fn test_open_sale_round_returns_transferred_tokens_if_swap_refuses() {
+        let proposal_id = 42;
+        let sns_ledger = RecordingLedger {
+            balance_e8s: 100_000,
+            ..Default::default()
+        };
+        let transfers = sns_ledger.transfers.clone();
+        let governance_proto = basic_governance_proto();
+        let swap_canister_id = governance_proto.swap_canister_id_or_panic();
+        let mut env = NativeEnvironment::new(Some(*TEST_GOVERNANCE_CANISTER_ID));
+        // The swap canister refuses to open the round.
+        env.default_canister_call_response = Err((Some(5), "Refused.".to_string()));
+        env.require_call_canister_invocation(
+            swap_canister_id,
+            "refund_sale_round_tokens",
+            Encode!(&RefundSaleRoundTokensRequest {
+                sns_governance_proposal_id: Some(proposal_id),
+                amount_e8s: Some(400_000),
+            })
+            .unwrap(),
+            Some(Ok(Encode!(
+                &ic_sns_swap_proto_library::pb::v1::RefundSaleRoundTokensResponse {
+                    block_index: Some(2),
+                }
+            )
+            .unwrap())),
+        );
+        let governance = Governance::new(
+            governance_proto.try_into().unwrap(),
+            Box::new(env),
+            Box::new(sns_ledger),
+            Box::new(DoNothingLedger {}),
+            Box::new(FakeCmc::new()),
+        );
+
+        let err = governance
+            .perform_open_sale_round(
+                proposal_id,
+                OpenSaleRound {
+                    sns_token_e8s: Some(500_000),
+                    ..Default::default()
+                },
+            )
+            .now_or_never()
+            .unwrap()
+            .unwrap_err();
+
+        assert_eq!(err.error_type, ErrorType::External as i32);
+        // Only the amount that the swap canister was missing was transferred to it.
+        let transfers = transfers.lock().unwrap();
+        assert_eq!(transfers.len(), 1);
+        assert_eq!(transfers[0].0, 400_000);
+        assert_eq!(
+            transfers[0].3,
+            Account {
+                owner: swap_canister_id.get().0,
+                subaccount: None,
+            }
+        );
+    }
+
+    fn governance_with_treasury_payment_stream(
+        now: u64,
+        nns_ledger: RecordingLedger,
+    ) -> Governance {
+        let mut env = NativeEnvironment::new(Some(*TEST_GOVERNANCE_CANISTER_ID));
+        env.now = now;
+        let mut governance = Governance::new(
+            basic_governance_proto().try_into().unwrap(),
+            Box::new(env),
+            Box::new(DoNothingLedger {}),
+            Box::new(nns_ledger),
+            Box::new(FakeCmc::new()),
+        );
+
+        // Two of the four installments are due.
+        governance
+            .perform_create_treasury_payment_stream(
+                42,
+                CreateTreasuryPaymentStream {
+                    from_treasury: TransferFrom::IcpTreasury as i32,
+                    total_amount_e8s: 400_000,
+                    to_principal: Some(*TEST_USER1_PRINCIPAL),
+                    memo: Some(7),
+                    start_timestamp_seconds: Some(now - 2 * ONE_DAY_SECONDS - 1),
+                    period_seconds: Some(ONE_DAY_SECONDS),
+                    installment_count: Some(4),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+
+        governance
+    }
+
+    #[test]
+    fn test_treasury_payment_stream_pays_due_installments() {
+        let now = 1_000_000;
+        let nns_ledger = RecordingLedger::default();
+        let transfers = nns_ledger.transfers.clone();
+        let mut governance = governance_with_treasury_payment_stream(now, nns_ledger);
+
+        governance
+            .maybe_pay_treasury_payment_streams()
+            .now_or_never()
+            .unwrap();
+
+        assert_eq!(
+            *transfers.lock().unwrap(),
+            vec![(
+                200_000,
+                NNS_DEFAULT_TRANSFER_FEE.get_e8s(),
+                None,
+                Account {
+                    owner: TEST_USER1_PRINCIPAL.0,
+                    subaccount: None,
+                },
+                7,
+            )]
+        );
+        let payment_stream = &governance.proto.treasury_payment_streams[&42];
+        assert_eq!(payment_stream.paid_e8s, Some(200_000));
+        assert_eq!(payment_stream.last_payment_timestamp_seconds, Some(now));
+        assert_eq!(payment_stream.pending_payment_e8s, None);
+        assert_eq!(payment_stream.pending_payment_created_at_time_nanos, None);
+
+        // Installments are not paid twice.
+        governance
+            .maybe_pay_treasury_payment_streams()
+            .now_or_never()
+            .unwrap();
+        assert_eq!(transfers.lock().unwrap().len(), 1);
+
+        // Once cancelled, no further installments are paid.
+        governance
+            .perform_cancel_treasury_payment_stream(CancelTreasuryPaymentStream {
+                payment_stream_id: Some(42),
+            })
+            .unwrap();
+        let payment_stream = &governance.proto.treasury_payment_streams[&42];
+        assert_eq!(payment_stream.cancelled_timestamp_seconds, Some(now));
+        assert_eq!(payment_stream.outstanding_e8s(), 0);
+        assert_eq!(
+            governance
+                .perform_cancel_treasury_payment_stream(CancelTreasuryPaymentStream {
+                    payment_stream_id: Some(42),
+                })
+                .unwrap_err()
+                .error_type,
+            ErrorType::PreconditionFailed as i32
+        );
+        assert_eq!(
+            governance.list_treasury_payment_streams(&ListTreasuryPaymentStreamsRequest::default()),
+            ListTreasuryPaymentStreamsResponse {
+                treasury_payment_streams: vec![
+                    governance.proto.treasury_payment_streams[&42].clone()
+                ],
+            }
+        );
+
+        // Cancelled streams are kept for a while, and then removed.
+        governance.env.set_time_warp(TimeWarp {
+            delta_s: FINISHED_TREASURY_PAYMENT_STREAM_RETENTION_DURATION_SECONDS as i64 - 1,
+        });
+        governance.prune_finished_treasury_payment_streams();
+        assert!(governance.proto.treasury_payment_streams.contains_key(&42));
+        governance.env.set_time_warp(TimeWarp {
+            delta_s: FINISHED_TREASURY_PAYMENT_STREAM_RETENTION_DURATION_SECONDS as i64,
+        });
+        governance.prune_finished_treasury_payment_streams();
+        assert_eq!(governance.proto.treasury_payment_streams, btreemap! {});
+    }
+
+    #[test]
+    fn test_list_treasury_payment_streams_is_paginated() {
+        let mut governance = governance_with_treasury_payment_stream(1_000_000, Default::default());
+        let payment_stream = governance.proto.treasury_payment_streams[&42].clone();
+        for id in 1..=MAX_LIST_TREASURY_PAYMENT_STREAMS_RESULTS as u64 + 10 {
+            governance.proto.treasury_payment_streams.insert(
+                id,
+                TreasuryPaymentStream {
+                    id: Some(id),
+                    ..payment_stream.clone()
+                },
+            );
+        }
+        let list = |limit, before_payment_stream_id| -> Vec<u64> {
+            governance
+                .list_treasury_payment_streams(&ListTreasuryPaymentStreamsRequest {
+                    limit,
+                    before_payment_stream_id,
+                })
+                .treasury_payment_streams
+                .into_iter()
+                .map(|payment_stream| payment_stream.id.unwrap())
+                .collect()
+        };
+
+        // The most recent streams come first, and the page size is capped.
+        let first_page = list(0, None);
+        assert_eq!(
+            first_page.len(),
+            MAX_LIST_TREASURY_PAYMENT_STREAMS_RESULTS as usize
+        );
+        assert_eq!(first_page[..3], [110, 109, 108]);
+        assert_eq!(list(1_000, None), first_page);
+
+        assert_eq!(list(3, Some(42)), vec![41, 40, 39]);
+        assert_eq!(list(3, Some(3)), vec![2, 1]);
+    }
+
+    #[test]
+    fn test_treasury_payment_stream_failed_payment_is_retried_with_the_same_created_at_time() {
+        let now = 1_000_000;
+        let nns_ledger = RecordingLedger {
+            fail_transfers: Arc::new(AtomicBool::new(true)),
+            ..Default::default()
+        };
+        let transfers = nns_ledger.transfers.clone();
+        let created_at_times_nanos = nns_ledger.created_at_times_nanos.clone();
+        let fail_transfers = nns_ledger.fail_transfers.clone();
+        let mut governance = governance_with_treasury_payment_stream(now, nns_ledger);
+
+        governance
+            .maybe_pay_treasury_payment_streams()
+            .now_or_never()
+            .unwrap();
+
+        // It is not known whether the ledger recorded the transfer, so the payment stays pending,
+        // and is still counted as paid.
+        assert_eq!(transfers.lock().unwrap().len(), 1);
+        let payment_stream = &governance.proto.treasury_payment_streams[&42];
+        assert_eq!(payment_stream.paid_e8s, Some(200_000));
+        assert_eq!(payment_stream.pending_payment_e8s, Some(200_000));
+        assert_eq!(
+            payment_stream.pending_payment_created_at_time_nanos,
+            Some(now * 1_000_000_000 + 42)
+        );
+        assert_eq!(payment_stream.last_payment_timestamp_seconds, None);
+        assert_eq!(
+            payment_stream.last_failed_payment_timestamp_seconds,
+            Some(now)
+        );
+        assert_eq!(payment_stream.due_e8s(now), 0);
+        assert_eq!(payment_stream.finished_timestamp_seconds(), None);
+
+        // The payment is not retried before the retry interval has elapsed.
+        governance
+            .maybe_pay_treasury_payment_streams()
+            .now_or_never()
+            .unwrap();
+        assert_eq!(transfers.lock().unwrap().len(), 1);
+
+        // The retry makes the same transfer, with the same created_at_time, so that the ledger
+        // would deduplicate it if the first transfer had been recorded after all.
+        governance.env.set_time_warp(TimeWarp {
+            delta_s: TREASURY_PAYMENT_STREAM_RETRY_INTERVAL_SECONDS as i64,
+        });
+        fail_transfers.store(false, AtomicOrdering::SeqCst);
+        governance
+            .maybe_pay_treasury_payment_streams()
+            .now_or_never()
+            .unwrap();
+
+        let transfers = transfers.lock().unwrap();
+        assert_eq!(transfers.len(), 2);
+        assert_eq!(transfers[0], transfers[1]);
+        assert_eq!(
+            *created_at_times_nanos.lock().unwrap(),
+            vec![now * 1_000_000_000 + 42; 2]
+        );
+        let payment_stream = &governance.proto.treasury_payment_streams[&42];
+        assert_eq!(payment_stream.paid_e8s, Some(200_000));
+        assert_eq!(payment_stream.pending_payment_e8s, None);
+        assert_eq!(payment_stream.pending_payment_created_at_time_nanos, None);
+        assert_eq!(
+            payment_stream.last_payment_timestamp_seconds,
+            Some(now + TREASURY_PAYMENT_STREAM_RETRY_INTERVAL_SECONDS)
+        );
+        assert_eq!(payment_stream.last_failed_payment_timestamp_seconds, None);
+    }
+
+    #[test]
+    fn test_treasury_payment_stream_in_flight_payment_is_not_retried() {
+        let now = 1_000_000;
+        let nns_ledger = RecordingLedger::default();
+        let transfers = nns_ledger.transfers.clone();
+        let mut governance = governance_with_treasury_payment_stream(now, nns_ledger);
+        // A payment was started, but its transfer has not returned yet.
+        let payment_stream = governance
+            .proto
+            .treasury_payment_streams
+            .get_mut(&42)
+            .unwrap();
+        payment_stream.paid_e8s = Some(200_000);
+        payment_stream.pending_payment_e8s = Some(200_000);
+        payment_stream.pending_payment_created_at_time_nanos = Some(now * 1_000_000_000 + 42);
+
+        governance
+            .maybe_pay_treasury_payment_streams()
+            .now_or_never()
+            .unwrap();
+
+        assert_eq!(transfers.lock().unwrap().len(), 0);
+        let payment_stream = &governance.proto.treasury_payment_streams[&42];
+        assert_eq!(payment_stream.pending_payment_e8s, Some(200_000));
+    }
+
+    #[test]
+    fn test_treasury_payment_stream_gives_up_on_unconfirmed_pending_payment() {
+        let now = 1_000_000;
+        let nns_ledger = RecordingLedger {
+            fail_transfers: Arc::new(AtomicBool::new(true)),
+            ..Default::default()
+        };
+        let transfers = nns_ledger.transfers.clone();
+        let mut governance = governance_with_treasury_payment_stream(now, nns_ledger);
+
+        governance
+            .maybe_pay_treasury_payment_streams()
+            .now_or_never()
+            .unwrap();
+        assert_eq!(transfers.lock().unwrap().len(), 1);
+
+        // Just before the expiry, the payment is still retried.
+        governance.env.set_time_warp(TimeWarp {
+            delta_s: TREASURY_PAYMENT_STREAM_PENDING_PAYMENT_EXPIRY_SECONDS as i64 - 1,
+        });
+        governance
+            .maybe_pay_treasury_payment_streams()
+            .now_or_never()
+            .unwrap();
+        assert_eq!(transfers.lock().unwrap().len(), 2);
+        assert_eq!(
+            governance.proto.treasury_payment_streams[&42].pending_payment_e8s,
+            Some(200_000)
+        );
+
+        // Afterwards, the ledger might no longer deduplicate the transfer, so the payment is no
+        // longer retried, and it is still counted as paid, so that it is not paid twice.
+        governance.env.set_time_warp(TimeWarp {
+            delta_s: TREASURY_PAYMENT_STREAM_PENDING_PAYMENT_EXPIRY_SECONDS as i64,
+        });
+        governance
+            .maybe_pay_treasury_payment_streams()
+            .now_or_never()
+            .unwrap();
+        assert_eq!(transfers.lock().unwrap().len(), 2);
+        let payment_stream = &governance.proto.treasury_payment_streams[&42];
+        assert_eq!(payment_stream.paid_e8s, Some(200_000));
+        assert_eq!(payment_stream.pending_payment_e8s, None);
+        assert_eq!(payment_stream.pending_payment_created_at_time_nanos, None);
+    }
+
+    #[test]
+    fn test_treasury_payment_stream_rejected_payment_is_paid_again_later() {
+        let now = 1_000_000;
+        let nns_ledger = RecordingLedger {
+            reject_transfers: Arc::new(AtomicBool::new(true)),
+            ..Default::default()
+        };
+        let transfers = nns_ledger.transfers.clone();
+        let created_at_times_nanos = nns_ledger.created_at_times_nanos.clone();
+        let reject_transfers = nns_ledger.reject_transfers.clone();
+        let mut governance = governance_with_treasury_payment_stream(now, nns_ledger);
+
+        governance
+            .maybe_pay_treasury_payment_streams()
+            .now_or_never()
+            .unwrap();
+
+        // The ledger did not transfer the funds, so the installments are due again.
+        assert_eq!(transfers.lock().unwrap().len(), 1);
+        let payment_stream = &governance.proto.treasury_payment_streams[&42];
+        assert_eq!(payment_stream.paid_e8s, Some(0));
+        assert_eq!(payment_stream.pending_payment_e8s, None);
+        assert_eq!(payment_stream.pending_payment_created_at_time_nanos, None);
+        assert_eq!(
+            payment_stream.last_failed_payment_timestamp_seconds,
+            Some(now)
+        );
+        assert_eq!(payment_stream.due_e8s(now), 200_000);
+
+        // The payment is not made again before the retry interval has elapsed.
+        governance
+            .maybe_pay_treasury_payment_streams()
+            .now_or_never()
+            .unwrap();
+        assert_eq!(transfers.lock().unwrap().len(), 1);
+
+        // Afterwards, it is made by a new transfer, with a new created_at_time.
+        let retry_timestamp_seconds = now + TREASURY_PAYMENT_STREAM_RETRY_INTERVAL_SECONDS;
+        governance.env.set_time_warp(TimeWarp {
+            delta_s: TREASURY_PAYMENT_STREAM_RETRY_INTERVAL_SECONDS as i64,
+        });
+        reject_transfers.store(false, AtomicOrdering::SeqCst);
+        governance
+            .maybe_pay_treasury_payment_streams()
+            .now_or_never()
+            .unwrap();
+
+        assert_eq!(transfers.lock().unwrap().len(), 2);
+        assert_eq!(
+            *created_at_times_nanos.lock().unwrap(),
+            vec![
+                now * 1_000_000_000 + 42,
+                retry_timestamp_seconds * 1_000_000_000 + 42
+            ]
+        );
+        let payment_stream = &governance.proto.treasury_payment_streams[&42];
+        assert_eq!(payment_stream.paid_e8s, Some(200_000));
+        assert_eq!(payment_stream.pending_payment_e8s, None);
+        assert_eq!(
+            payment_stream.last_payment_timestamp_seconds,
+            Some(retry_timestamp_seconds)
+        );
+    }
+
+    #[test]
+    fn test_treasury_payment_stream_icp_payment_whose_reply_was_lost_is_confirmed_by_retry() {
+        let now = 1_000_000;
+        let nns_ledger = RecordingLedger {
+            lose_replies: Arc::new(AtomicBool::new(true)),
+            ..Default::default()
+        };
+        let transfers = nns_ledger.transfers.clone();
+        let recorded_created_at_times_nanos = nns_ledger.recorded_created_at_times_nanos.clone();
+        let lose_replies = nns_ledger.lose_replies.clone();
+        let mut governance = governance_with_treasury_payment_stream(now, nns_ledger);
+
+        // The ICP ledger records the transfer, but its reply is lost.
+        governance
+            .maybe_pay_treasury_payment_streams()
+            .now_or_never()
+            .unwrap();
+        let payment_stream = &governance.proto.treasury_payment_streams[&42];
+        assert_eq!(payment_stream.pending_payment_e8s, Some(200_000));
+        assert_eq!(payment_stream.last_payment_timestamp_seconds, None);
+
+        // The ledger reports the retry as a duplicate of the recorded transfer, which confirms
+        // the payment.
+        governance.env.set_time_warp(TimeWarp {
+            delta_s: TREASURY_PAYMENT_STREAM_RETRY_INTERVAL_SECONDS as i64,
+        });
+        lose_replies.store(false, AtomicOrdering::SeqCst);
+        governance
+            .maybe_pay_treasury_payment_streams()
+            .now_or_never()
+            .unwrap();
+
+        assert_eq!(transfers.lock().unwrap().len(), 2);
+        assert_eq!(
+            *recorded_created_at_times_nanos.lock().unwrap(),
+            vec![now * 1_000_000_000 + 42]
+        );
+        let payment_stream = &governance.proto.treasury_payment_streams[&42];
+        assert_eq!(payment_stream.paid_e8s, Some(200_000));
+        assert_eq!(payment_stream.pending_payment_e8s, None);
+        assert_eq!(payment_stream.pending_payment_created_at_time_nanos, None);
+        assert_eq!(
+            payment_stream.last_payment_timestamp_seconds,
+            Some(now + TREASURY_PAYMENT_STREAM_RETRY_INTERVAL_SECONDS)
+        );
+        assert_eq!(payment_stream.last_failed_payment_timestamp_seconds, None);
+    }
 }
@@ -5,11 +5,12 @@ use dfn_core::CanisterId;
 use ic_base_types::PrincipalId;
 use ic_ledger_core::{block::BlockIndex, Tokens};
 pub use ic_nervous_system_common::ledger::ICRC1Ledger;
+use ic_nervous_system_common::ledger::TransferWithCreatedAtTimeError;
 use ic_nervous_system_common::NervousSystemError;
 use icrc_ledger_client::{ICRC1Client, Runtime};
 use icrc_ledger_types::icrc1::{
     account::{Account, Subaccount},
-    transfer::{Memo, TransferArg},
+    transfer::{Memo, TransferArg, TransferError},
 };
 use num_traits::ToPrimitive;
 
@@ -84,6 +85,44 @@ impl ICRC1Ledger for LedgerCanister {
         .map(|n| n.0.to_u64().expect("nat does not fit into u64"))
     }
 
+    async fn transfer_funds_with_created_at_time(
+        &self,
+        amount_e8s: u64,
+        fee_e8s: u64,
+        from_subaccount: Option<Subaccount>,
+        to: Account,
+        memo: u64,
+        created_at_time_nanos: u64,
+    ) -> Result<BlockIndex, TransferWithCreatedAtTimeError> {
+        let args = TransferArg {
+            from_subaccount,
+            to,
+            fee: Some(Nat::from(fee_e8s)),
+            created_at_time: Some(created_at_time_nanos),
+            amount: Nat::from(amount_e8s),
+            memo: Some(Memo::from(memo)),
+        };
+        let res = self.client.transfer(args).await
+            .map_err(|(code, msg)| {
+                TransferWithCreatedAtTimeError::UnknownOutcome(NervousSystemError::new_with_message(format!(
+                    "Error calling method 'icrc1_transfer' of the icrc1 ledger canister. Code: {:?}. Message: {}",
+                    code, msg
+                )))
+            })?;
+        match res {
+            Ok(block_index) => Ok(block_index),
+            // The transfer was already recorded, by an earlier call with the same arguments.
+            Err(TransferError::Duplicate { duplicate_of }) => Ok(duplicate_of),
+            Err(err) => Err(TransferWithCreatedAtTimeError::Rejected(
+                NervousSystemError::new_with_message(format!(
+                    "'icrc1_transfer' of the icrc1 ledger canister failed. Error: {:?}",
+                    err
+                )),
+            )),
+        }
+        .map(|n| n.0.to_u64().expect("nat does not fit into u64"))
+    }
+
     async fn total_supply(&self) -> Result<Tokens, NervousSystemError> {
         self.client.total_supply().await
             .map(|n| Tokens::from_e8s(n.0.to_u64().expect("nat does not fit into u64")))
@@ -6,19 +6,21 @@ use crate::{
     },
     logs::{ERROR, INFO},
     pb::v1::{
-        governance::{SnsMetadata, Version},
+        governance::{PreUpgradeSnapshot, SnsMetadata, Version},
         nervous_system_function::{FunctionType, GenericNervousSystemFunction},
         proposal,
         proposal::Action,
         transfer_sns_treasury_funds::TransferFrom,
+        CancelTreasuryPaymentStream, ChunkedCanisterWasm, CreateTreasuryPaymentStream,
         DeregisterDappCanisters, ExecuteGenericNervousSystemFunction, Governance, LogVisibility,
         ManageDappCanisterSettings, ManageLedgerParameters, ManageSnsMetadata, MintSnsTokens,
-        Motion, NervousSystemFunction, NervousSystemParameters, Proposal, ProposalData,
-        ProposalDecisionStatus, ProposalRewardStatus, RegisterDappCanisters, Tally,
-        TransferSnsTreasuryFunds, UpgradeSnsControlledCanister, UpgradeSnsToNextVersion, Vote,
+        Motion, NervousSystemFunction, NervousSystemParameters, OpenSaleRound, Proposal,
+        ProposalData, ProposalDecisionStatus, ProposalRewardStatus, RegisterDappCanisters,
+        RestoreDappCanisterSnapshot, Tally, Topic, TransferSnsTreasuryFunds, TreasuryPaymentStream,
+        UpgradeSnsControlledCanister, UpgradeSnsToNextVersion, Vote,
     },
     sns_upgrade::{get_upgrade_params, UpgradeSnsParams},
-    types::{Environment, DEFAULT_TRANSFER_FEE},
+    types::{function_id_to_topic, Environment, DEFAULT_TRANSFER_FEE},
     validate_chars_count, validate_len, validate_required_field,
 };
 use candid::Principal;
@@ -26,6 +28,7 @@ use dfn_core::api::CanisterId;
 use ic_base_types::PrincipalId;
 use ic_canister_log::log;
 use ic_crypto_sha2::Sha256;
+use ic_management_canister_types::WASM_CHUNK_STORE_MAX_CHUNKS;
 use ic_nervous_system_common::{
     i2d, ledger::compute_distribution_subaccount_bytes, E8, SECONDS_PER_DAY,
 };
@@ -43,6 +46,12 @@ use std::{
     fmt::Write,
 };
 
+/// Whether dapp canisters are snapshotted before being upgraded, and can be restored from these
+/// snapshots via RestoreDappCanisterSnapshot proposals. While enabled, an upgrade fails if no
+/// snapshot can be taken, so this must stay disabled until the canister snapshot methods of the
+/// management canister are enabled on application subnets.
+pub const ARE_DAPP_CANISTER_SNAPSHOTS_ENABLED: bool = cfg!(test);
+
 /// The maximum number of bytes in an SNS proposal's title.
 pub const PROPOSAL_TITLE_BYTES_MAX: usize = 256;
 /// The maximum number of bytes in an SNS proposal's summary.
@@ -56,6 +65,10 @@ pub const PROPOSAL_MOTION_TEXT_BYTES_MAX: usize = 10000;
 /// which can be used to list all proposals in a paginated fashion.
 pub const MAX_LIST_PROPOSAL_RESULTS: u32 = 100;
 
+/// The maximum number of treasury payment streams returned by one call to the method
+/// `list_treasury_payment_streams`, which can be used to list all streams in a paginated fashion.
+pub const MAX_LIST_TREASURY_PAYMENT_STREAMS_RESULTS: u32 = 100;
+
 /// The maximum number of unsettled proposals (proposals for which ballots are still stored).
 pub const MAX_NUMBER_OF_PROPOSALS_WITH_BALLOTS: usize = 700;
 
@@ -77,6 +90,26 @@ pub const MAX_NUMBER_OF_BALLOTS_IN_LIST_PROPOSALS_RESPONSE: usize = 100;
 pub const EXECUTED_TRANSFER_SNS_TREASURY_FUNDS_PROPOSAL_RETENTION_DURATION_SECONDS: u64 =
     7 * SECONDS_PER_DAY;
 
+/// The minimum time between two installments of a treasury payment stream.
+pub const MIN_TREASURY_PAYMENT_STREAM_PERIOD_SECONDS: u64 = SECONDS_PER_DAY;
+
+/// The maximum number of installments of a treasury payment stream.
+pub const MAX_TREASURY_PAYMENT_STREAM_INSTALLMENT_COUNT: u64 = 1_000;
+
+/// The minimum duration of a follow-on sale round. This is enforced by the swap canister.
+pub const MIN_SALE_ROUND_DURATION_SECONDS: u64 = SECONDS_PER_DAY;
+
+/// The maximum duration of a follow-on sale round. This is enforced by the swap canister.
+pub const MAX_SALE_ROUND_DURATION_SECONDS: u64 = 14 * SECONDS_PER_DAY;
+
+/// The maximum number of neurons in the neuron basket of a participant of a follow-on sale
+/// round. This is enforced by the swap canister.
+pub const MAX_SALE_ROUND_NEURON_BASKET_COUNT: u64 = 10_000;
+
+/// The maximum amount of ICP that can be raised in a follow-on sale round. This is enforced by
+/// the swap canister.
+pub const MAX_SALE_ROUND_DIRECT_PARTICIPATION_ICP_E8S: u64 = 1_000_000_000 * E8;
+
 impl Proposal {
     /// Returns whether a proposal is allowed to be submitted when
     /// the heap growth potential is low.
@@ -261,6 +294,7 @@ pub async fn validate_and_render_action(
             let swap_canister_id = governance_proto.swap_canister_id_or_panic();
             let sns_ledger_canister_id = governance_proto.ledger_canister_id_or_panic();
             let proposals = governance_proto.proposals.values();
+            let treasury_payment_streams = governance_proto.treasury_payment_streams.values();
             validate_and_render_transfer_sns_treasury_funds(
                 transfer,
                 sns_transfer_fee_e8s,
@@ -268,6 +302,7 @@ pub async fn validate_and_render_action(
                 swap_canister_id,
                 sns_ledger_canister_id,
                 proposals,
+                treasury_payment_streams,
             )
             .await
         }
@@ -280,6 +315,53 @@ pub async fn validate_and_render_action(
         proposal::Action::ManageDappCanisterSettings(manage_dapp_canister_settings) => {
             validate_and_render_manage_dapp_canister_settings(manage_dapp_canister_settings)
         }
+        proposal::Action::RestoreDappCanisterSnapshot(restore_dapp_canister_snapshot) => {
+            validate_and_render_restore_dapp_canister_snapshot(
+                restore_dapp_canister_snapshot,
+                &disallowed_target_canister_ids,
+                &governance_proto.pre_upgrade_snapshots,
+            )
+        }
+        proposal::Action::CreateTreasuryPaymentStream(create) => {
+            let swap_canister_id = governance_proto.swap_canister_id_or_panic();
+            let sns_ledger_canister_id = governance_proto.ledger_canister_id_or_panic();
+            let proposals = governance_proto.proposals.values();
+            let treasury_payment_streams = governance_proto.treasury_payment_streams.values();
+            validate_and_render_create_treasury_payment_stream(
+                create,
+                sns_transfer_fee_e8s,
+                env,
+                swap_canister_id,
+                sns_ledger_canister_id,
+                proposals,
+                treasury_payment_streams,
+            )
+            .await
+        }
+        proposal::Action::CancelTreasuryPaymentStream(cancel) => {
+            validate_and_render_cancel_treasury_payment_stream(
+                cancel,
+                &governance_proto.treasury_payment_streams,
+            )
+        }
+        proposal::Action::OpenSaleRound(open_sale_round) => {
+            let neuron_minimum_stake_e8s = current_parameters.neuron_minimum_stake_e8s.unwrap_or(0);
+            let swap_canister_id = governance_proto.swap_canister_id_or_panic();
+            let sns_ledger_canister_id = governance_proto.ledger_canister_id_or_panic();
+            let proposals = governance_proto.proposals.values();
+            let treasury_payment_streams = governance_proto.treasury_payment_streams.values();
+            validate_and_render_open_sale_round(
+                open_sale_round,
+                sns_transfer_fee_e8s,
+                neuron_minimum_stake_e8s,
+                env,
+                swap_canister_id,
+                sns_ledger_canister_id,
+                proposals,
+                treasury_payment_streams,
+            )
+            .await
+        }
     }
 }
 
@@ -329,16 +411,19 @@ async fn validate_and_render_transfer_sns_treasury_funds(
     swap_canister_id: CanisterId,
     sns_ledger_canister_id: CanisterId,
     proposals: impl Iterator<Item = &ProposalData>,
+    treasury_payment_streams: impl Iterator<Item = &TreasuryPaymentStream>,
 ) -> Result<String, String> {
     let mut defects = vec![];
 
     // Validate amount. This requires calling CMC and the swap canister; hence, await.
-    let amount_result = transfer_sns_treasury_funds_amount_is_within_limits_or_err(
-        transfer,
+    let amount_result = treasury_amount_is_within_limits_or_err(
+        transfer.from_treasury(),
+        transfer.amount_e8s,
         env,
         swap_canister_id,
         sns_ledger_canister_id,
         proposals,
+        treasury_payment_streams,
     )
     .await;
     if let Err(err) = amount_result {
@@ -462,18 +547,30 @@ impl TransferFrom {
     }
 }
 
-async fn transfer_sns_treasury_funds_amount_is_within_limits_or_err(
-    transfer: &TransferSnsTreasuryFunds,
+/// Returns an error if transferring `amount_e8s` from the given treasury would exceed the upper
+/// bound on the total amount that can be transferred out of that treasury within 7 days.
+///
+/// The total amount of a treasury payment stream counts towards the 7 day total for 7 days after
+/// the stream is created. After that, the amount that the stream still has to pay is instead
+/// taken out of the treasury when its value is determined. That way, no amount is counted twice.
+async fn treasury_amount_is_within_limits_or_err(
+    from_treasury: TransferFrom,
+    amount_e8s: u64,
     env: &dyn Environment,
     swap_canister_id: CanisterId,
     sns_ledger_canister_id: CanisterId,
     proposals: impl Iterator<Item = &ProposalData>,
+    treasury_payment_streams: impl Iterator<Item = &TreasuryPaymentStream>,
 ) -> Result<(), String> {
+    let min_executed_timestamp_seconds = env.now() - 7 * SECONDS_PER_DAY;
+    // Both totals below need to look at the proposals.
+    let proposals: Vec<&ProposalData> = proposals.collect();
+
     let treasury_transfer_total_tokens = {
         let e8s = total_treasury_transfer_amount_e8s(
-            proposals,
-            transfer.from_treasury(),
-            env.now() - 7 * SECONDS_PER_DAY,
+            proposals.iter().copied(),
+            from_treasury,
+            min_executed_timestamp_seconds,
         )
         // An Err here most likely indicates a bug in our code, not something that the user did wrong.
         .ok_or_else(|| {
@@ -495,7 +592,6 @@ async fn transfer_sns_treasury_funds_amount_is_within_limits_or_err(
             })?
     };
 
-    let from_treasury = transfer.from_treasury();
     let treasury_account = from_treasury.treasury_account(env.canister_id())?;
 
     // Get valuation of the tokens in the treasury.
@@ -520,6 +616,28 @@ async fn transfer_sns_treasury_funds_amount_is_within_limits_or_err(
     // An Err here is most likely due to a failure to call another canister.
     .map_err(|valuation_error| format!("Unable to validate amount: {:?}", valuation_error,))?;
 
+    // Funds that payment streams still have to pay are already spoken for (unless they are
+    // counted in the 7 day total already).
+    let valuation = {
+        let mut valuation = valuation;
+        let committed_e8s = total_treasury_payment_stream_commitments_e8s(
+            treasury_payment_streams,
+            from_treasury,
+            proposals.iter().copied(),
+            min_executed_timestamp_seconds,
+        )
+        // An Err here most likely indicates a bug in our code.
+        .ok_or_else(|| {
+            "Unable to validate amount: Overflowed while calculating the total amount \
+                     that treasury payment streams still have to pay."
+                .to_string()
+        })?;
+        let committed_tokens = Decimal::from(committed_e8s) / Decimal::from(E8);
+        let balance_tokens = &mut valuation.valuation_factors.tokens;
+        *balance_tokens = (*balance_tokens - committed_tokens).max(Decimal::ZERO);
+        valuation
+    };
+
     // From valuation, determine limit on the total from the past 7 days.
     let max_tokens = TreasuryTransferTotalUpperBound::in_tokens(valuation)
         // Err is most likely a bug.
@@ -530,7 +648,7 @@ async fn transfer_sns_treasury_funds_amount_is_within_limits_or_err(
     // Finally, inspect amount: it must not exceed max - spent (remainder). Or if you prefer,
     // equivalently, amount + spent must be <= max.
     let allowance_remainder_tokens = max_tokens - treasury_transfer_total_tokens;
-    let transfer_amount_tokens = Decimal::from(transfer.amount_e8s) / Decimal::from(E8);
+    let transfer_amount_tokens = Decimal::from(amount_e8s) / Decimal::from(E8);
     if transfer_amount_tokens > allowance_remainder_tokens {
         // Although it might not be obvious to the user, their proposal is invalid, and we consider
         // it to be "their fault".
@@ -545,6 +663,391 @@ async fn transfer_sns_treasury_funds_amount_is_within_limits_or_err(
     Ok(())
 }
 
+/// Validates and renders a CreateTreasuryPaymentStream proposal.
+async fn validate_and_render_create_treasury_payment_stream(
+    create: &CreateTreasuryPaymentStream,
+    sns_transfer_fee_e8s: u64,
+    env: &dyn Environment,
+    swap_canister_id: CanisterId,
+    sns_ledger_canister_id: CanisterId,
+    proposals: impl Iterator<Item = &ProposalData>,
+    treasury_payment_streams: impl Iterator<Item = &TreasuryPaymentStream>,
+) -> Result<String, String> {
+    let mut defects = vec![];
+
+    // Validate the total amount. Although it is paid over time, the treasury commits to paying
+    // all of it. This requires calling CMC and the swap canister; hence, await.
+    let amount_result = treasury_amount_is_within_limits_or_err(
+        create.from_treasury(),
+        create.total_amount_e8s,
+        env,
+        swap_canister_id,
+        sns_ledger_canister_id,
+        proposals,
+        treasury_payment_streams,
+    )
+    .await;
+    if let Err(err) = amount_result {
+        defects.push(err);
+    }
+
+    // Validate all other aspects of the proposal action.
+    locally_validate_and_render_create_treasury_payment_stream(
+        create,
+        sns_transfer_fee_e8s,
+        env.now(),
+        defects,
+    )
+}
+
+/// Performs all the validation on a CreateTreasuryPaymentStream that does not require fetching
+/// information from other canisters.
+fn locally_validate_and_render_create_treasury_payment_stream(
+    create: &CreateTreasuryPaymentStream,
+    sns_transfer_fee_e8s: u64,
+    now_seconds: u64,
+    mut defects: Vec<String>,
+) -> Result<String, String> {
+    let (from, unit, minimum_transaction) = match create.from_treasury() {
+        TransferFrom::IcpTreasury => (
+            "ICP Treasury (ICP Ledger)",
+            "ICP",
+            NNS_DEFAULT_TRANSFER_FEE.get_e8s(),
+        ),
+        TransferFrom::SnsTokenTreasury => (
+            "SNS Token Treasury (SNS Ledger)",
+            "SNS Tokens",
+            sns_transfer_fee_e8s,
+        ),
+        TransferFrom::Unspecified => {
+            defects.push(
+                "Must specify a treasury from which to pay the installments (ICP/SNS Token)."
+                    .to_string(),
+            );
+            ("", "", 0)
+        }
+    };
+
+    // Inspect the schedule.
+    let installment_count = create.installment_count.unwrap_or_default();
+    if !(1..=MAX_TREASURY_PAYMENT_STREAM_INSTALLMENT_COUNT).contains(&installment_count) {
+        defects.push(format!(
+            "installment_count must be between 1 and {}.",
+            MAX_TREASURY_PAYMENT_STREAM_INSTALLMENT_COUNT
+        ));
+    } else if create.total_amount_e8s / installment_count < minimum_transaction {
+        // Make sure that installments are not too small.
+        defects.push(format!(
+            "Each installment must be at least the fee and minimum transaction for transactions \
+             from {}, which is {} e8s.",
+            from, minimum_transaction
+        ));
+    }
+    let period_seconds = create.period_seconds.unwrap_or_default();
+    if period_seconds < MIN_TREASURY_PAYMENT_STREAM_PERIOD_SECONDS {
+        defects.push(format!(
+            "period_seconds must be at least {}.",
+            MIN_TREASURY_PAYMENT_STREAM_PERIOD_SECONDS
+        ));
+    }
+    let start_timestamp_seconds = create.start_timestamp_seconds.unwrap_or(now_seconds);
+    let cliff_duration_seconds = create.cliff_duration_seconds.unwrap_or_default();
+    let end_timestamp_seconds = period_seconds
+        .checked_mul(installment_count)
+        .and_then(|duration_seconds| start_timestamp_seconds.checked_add(duration_seconds));
+    match end_timestamp_seconds {
+        None => defects.push("The payment stream would never end.".to_string()),
+        Some(end_timestamp_seconds) => {
+            if start_timestamp_seconds.saturating_add(cliff_duration_seconds)
+                > end_timestamp_seconds
+            {
+                defects.push(
+                    "cliff_duration_seconds must not extend beyond the last installment."
+                        .to_string(),
+                );
+            }
+        }
+    }
+
+    // Inspect the recipient, which must be a non-anonymous principal.
+    let to_principal = if let Some(to_principal) = create.to_principal {
+        if to_principal == PrincipalId::new_anonymous() {
+            defects.push("to_principal must not be anonymous.".to_string());
+        }
+        to_principal
+    } else {
+        defects.push("Must specify a principal to pay the installments to.".to_string());
+        PrincipalId::new_anonymous()
+    };
+    let subaccount = match &create.to_subaccount {
+        None => None,
+        Some(s) => match bytes_to_subaccount(&s.subaccount[..]) {
+            Ok(s) => Some(s),
+            Err(e) => {
+                defects.push(e.error_message);
+                None
+            }
+        },
+    };
+    let to_account = Account {
+        owner: to_principal.0,
+        subaccount,
+    };
+
+    // Generate final report.
+    if !defects.is_empty() {
+        return Err(format!(
+            "CreateTreasuryPaymentStream proposal was invalid for the following reason(s):\n{}",
+            defects.join("\n"),
+        ));
+    }
+
+    let display_amount_tokens = i2d(create.total_amount_e8s) / i2d(E8);
+    Ok(format!(
+        r"# Proposal to create a treasury payment stream:
+## Source treasury: {from}
+## Total amount: {display_amount_tokens:.8} {unit}
+## Total amount (e8s): {total_amount_e8s}
+## Target principal: {to_principal}
+## Target account: {to_account}
+## Memo: {memo}
+## Start (seconds since the UNIX epoch): {start}
+## Cliff duration (seconds): {cliff_duration_seconds}
+## Period (seconds): {period_seconds}
+## Number of installments: {installment_count}",
+        total_amount_e8s = create.total_amount_e8s,
+        memo = create.memo.unwrap_or(0),
+        start = create.start_timestamp_seconds.map_or_else(
+            || "when the proposal is executed".to_string(),
+            |start| start.to_string()
+        ),
+    ))
+}
+
+/// Validates and renders a CancelTreasuryPaymentStream proposal.
+fn validate_and_render_cancel_treasury_payment_stream(
+    cancel: &CancelTreasuryPaymentStream,
+    treasury_payment_streams: &BTreeMap<u64, TreasuryPaymentStream>,
+) -> Result<String, String> {
+    let payment_stream_id =
+        *validate_required_field("payment_stream_id", &cancel.payment_stream_id)?;
+
+    let payment_stream = treasury_payment_streams
+        .get(&payment_stream_id)
+        .ok_or_else(|| {
+            format!("There is no treasury payment stream with ID {payment_stream_id}.")
+        })?;
+    if payment_stream.cancelled_timestamp_seconds.is_some() {
+        return Err(format!(
+            "Treasury payment stream {payment_stream_id} has already been cancelled."
+        ));
+    }
+    if payment_stream.outstanding_e8s() == 0 {
+        return Err(format!(
+            "Treasury payment stream {payment_stream_id} has already been paid in full."
+        ));
+    }
+
+    Ok(format!(
+        r"# Proposal to cancel a treasury payment stream:
+## Payment stream id: {payment_stream_id}
+## Amount paid so far (e8s): {paid_e8s}
+## Amount that will no longer be paid (e8s): {outstanding_e8s}",
+        paid_e8s = payment_stream.paid_e8s.unwrap_or_default(),
+        outstanding_e8s = payment_stream.outstanding_e8s(),
+    ))
+}
+
+/// Validates and renders an OpenSaleRound proposal.
+#[allow(clippy::too_many_arguments)]
+async fn validate_and_render_open_sale_round(
+    open_sale_round: &OpenSaleRound,
+    sns_transfer_fee_e8s: u64,
+    neuron_minimum_stake_e8s: u64,
+    env: &dyn Environment,
+    swap_canister_id: CanisterId,
+    sns_ledger_canister_id: CanisterId,
+    proposals: impl Iterator<Item = &ProposalData>,
+    treasury_payment_streams: impl Iterator<Item = &TreasuryPaymentStream>,
+) -> Result<String, String> {
+    let mut defects = vec![];
+
+    // Validate the amount of SNS tokens that is offered. The tokens leave the treasury when the
+    // proposal is executed. This requires calling the swap canister; hence, await.
+    let amount_result = treasury_amount_is_within_limits_or_err(
+        TransferFrom::SnsTokenTreasury,
+        open_sale_round.sns_token_e8s.unwrap_or_default(),
+        env,
+        swap_canister_id,
+        sns_ledger_canister_id,
+        proposals,
+        treasury_payment_streams,
+    )
+    .await;
+    if let Err(err) = amount_result {
+        defects.push(err);
+    }
+
+    // Validate all other aspects of the proposal action.
+    locally_validate_and_render_open_sale_round(
+        open_sale_round,
+        sns_transfer_fee_e8s,
+        neuron_minimum_stake_e8s,
+        defects,
+    )
+}
+
+/// Performs all the validation on an OpenSaleRound that does not require fetching information
+/// from other canisters. These are the same checks that the swap canister performs on the
+/// parameters of a sale round, so that a proposal that is adopted does not fail to execute
+/// because of them.
+fn locally_validate_and_render_open_sale_round(
+    open_sale_round: &OpenSaleRound,
+    sns_transfer_fee_e8s: u64,
+    neuron_minimum_stake_e8s: u64,
+    mut defects: Vec<String>,
+) -> Result<String, String> {
+    let OpenSaleRound {
+        sns_token_e8s,
+        min_participants,
+        min_direct_participation_icp_e8s,
+        max_direct_participation_icp_e8s,
+        min_participant_icp_e8s,
+        max_participant_icp_e8s,
+        duration_seconds,
+        start_delay_seconds,
+        neuron_basket_count,
+        neuron_basket_dissolve_delay_interval_seconds,
+    } = open_sale_round;
+    let sns_token_e8s = sns_token_e8s.unwrap_or_default();
+    let min_participants = min_participants.unwrap_or_default();
+    let min_direct_participation_icp_e8s = min_direct_participation_icp_e8s.unwrap_or_default();
+    let max_direct_participation_icp_e8s = max_direct_participation_icp_e8s.unwrap_or_default();
+    let min_participant_icp_e8s = min_participant_icp_e8s.unwrap_or_default();
+    let max_participant_icp_e8s = max_participant_icp_e8s.unwrap_or_default();
+    let duration_seconds = duration_seconds.unwrap_or_default();
+    let start_delay_seconds = start_delay_seconds.unwrap_or_default();
+    let neuron_basket_count = neuron_basket_count.unwrap_or_default();
+    let neuron_basket_dissolve_delay_interval_seconds =
+        neuron_basket_dissolve_delay_interval_seconds.unwrap_or_default();
+
+    // Inspect the amounts.
+    if sns_token_e8s == 0 {
+        defects.push("sns_token_e8s must be > 0.".to_string());
+    }
+    if min_participants == 0 {
+        defects.push("min_participants must be > 0.".to_string());
+    }
+    if min_direct_participation_icp_e8s == 0 {
+        defects.push("min_direct_participation_icp_e8s must be > 0.".to_string());
+    }
+    if max_direct_participation_icp_e8s < min_direct_participation_icp_e8s {
+        defects.push(format!(
+            "max_direct_participation_icp_e8s ({}) must be >= \
+             min_direct_participation_icp_e8s ({}).",
+            max_direct_participation_icp_e8s, min_direct_participation_icp_e8s,
+        ));
+    }
+    if max_direct_participation_icp_e8s > MAX_SALE_ROUND_DIRECT_PARTICIPATION_ICP_E8S {
+        defects.push(format!(
+            "max_direct_participation_icp_e8s ({}) can be at most {}.",
+            max_direct_participation_icp_e8s, MAX_SALE_ROUND_DIRECT_PARTICIPATION_ICP_E8S,
+        ));
+    }
+    if max_participant_icp_e8s < min_participant_icp_e8s {
+        defects.push(format!(
+            "max_participant_icp_e8s ({}) must be >= min_participant_icp_e8s ({}).",
+            max_participant_icp_e8s, min_participant_icp_e8s,
+        ));
+    }
+    if max_participant_icp_e8s > max_direct_participation_icp_e8s {
+        defects.push(format!(
+            "max_participant_icp_e8s ({}) must be <= max_direct_participation_icp_e8s ({}).",
+            max_participant_icp_e8s, max_direct_participation_icp_e8s,
+        ));
+    }
+    if max_direct_participation_icp_e8s
+        < u64::from(min_participants).saturating_mul(min_participant_icp_e8s)
+    {
+        defects.push(format!(
+            "max_direct_participation_icp_e8s ({}) must be >= min_participants ({}) * \
+             min_participant_icp_e8s ({}).",
+            max_direct_participation_icp_e8s, min_participants, min_participant_icp_e8s,
+        ));
+    }
+
+    // Inspect the neuron basket.
+    if !(2..=MAX_SALE_ROUND_NEURON_BASKET_COUNT).contains(&neuron_basket_count) {
+        defects.push(format!(
+            "neuron_basket_count must be between 2 and {}.",
+            MAX_SALE_ROUND_NEURON_BASKET_COUNT
+        ));
+    }
+    if neuron_basket_dissolve_delay_interval_seconds == 0 {
+        defects.push("neuron_basket_dissolve_delay_interval_seconds must be > 0.".to_string());
+    }
+    let maximum_dissolve_delay_seconds = neuron_basket_count
+        .saturating_mul(neuron_basket_dissolve_delay_interval_seconds)
+        .saturating_add(1);
+    if maximum_dissolve_delay_seconds == u64::MAX {
+        defects.push("The neuron basket would result in u64 overflow.".to_string());
+    }
+
+    // Each participant must end up with enough SNS tokens to form all neurons of their basket.
+    if max_direct_participation_icp_e8s > 0 {
+        let min_participant_sns_e8s = u128::from(min_participant_icp_e8s)
+            * u128::from(sns_token_e8s)
+            / u128::from(max_direct_participation_icp_e8s);
+        let min_basket_sns_e8s = u128::from(neuron_basket_count)
+            * u128::from(neuron_minimum_stake_e8s.saturating_add(sns_transfer_fee_e8s));
+        if min_participant_sns_e8s < min_basket_sns_e8s {
+            defects.push(format!(
+                "min_participant_icp_e8s ({}) is too small. Each participant needs to receive \
+                 enough SNS tokens to form {} neurons, each of which requires at least {} SNS \
+                 e8s plus {} e8s in transaction fees.",
+                min_participant_icp_e8s,
+                neuron_basket_count,
+                neuron_minimum_stake_e8s,
+                sns_transfer_fee_e8s,
+            ));
+        }
+    }
+
+    // Inspect the schedule.
+    if !(MIN_SALE_ROUND_DURATION_SECONDS..=MAX_SALE_ROUND_DURATION_SECONDS)
+        .contains(&duration_seconds)
+    {
+        defects.push(format!(
+            "duration_seconds must be between {} and {}.",
+            MIN_SALE_ROUND_DURATION_SECONDS, MAX_SALE_ROUND_DURATION_SECONDS
+        ));
+    }
+
+    // Generate final report.
+    if !defects.is_empty() {
+        return Err(format!(
+            "OpenSaleRound proposal was invalid for the following reason(s):\n{}",
+            defects.join("\n"),
+        ));
+    }
+
+    let display_amount_tokens = i2d(sns_token_e8s) / i2d(E8);
+    Ok(format!(
+        r"# Proposal to open a follow-on sale round:
+## SNS tokens offered (from the SNS Token Treasury): {display_amount_tokens:.8} SNS Tokens
+## SNS tokens offered (e8s): {sns_token_e8s}
+## Minimum number of participants: {min_participants}
+## Minimum direct participation (ICP e8s): {min_direct_participation_icp_e8s}
+## Maximum direct participation (ICP e8s): {max_direct_participation_icp_e8s}
+## Minimum participation per participant (ICP e8s): {min_participant_icp_e8s}
+## Maximum participation per participant (ICP e8s): {max_participant_icp_e8s}
+## Start delay (seconds): {start_delay_seconds}
+## Duration (seconds): {duration_seconds}
+## Neurons per participant: {neuron_basket_count}
+## Neuron dissolve delay interval (seconds): {neuron_basket_dissolve_delay_interval_seconds}",
+    ))
+}
+
 /// Validates and render MintSnsTokens proposal
 fn validate_and_render_mint_sns_tokens(
     mint: &MintSnsTokens,
@@ -629,23 +1132,32 @@ fn validate_and_render_upgrade_sns_controlled_canister(
     }
 
     // Inspect wasm.
-    const RAW_WASM_HEADER: [u8; 4] = [0, 0x61, 0x73, 0x6d];
-    // see https://ic-interface-spec.netlify.app/#canister-module-format
-    const GZIPPED_WASM_HEADER: [u8; 3] = [0x1f, 0x8b, 0x08];
-    // Minimum length of raw WASM is 8 bytes (4 magic bytes and 4 bytes encoding version).
-    // Minimum length of gzipped WASM is 10 bytes (2 magic bytes, 1 byte encoding compression method, and 7 additional gzip header bytes).
-    const MIN_WASM_LEN: usize = 8;
-    if let Err(err) = validate_len(
-        "new_canister_wasm",
-        &upgrade.new_canister_wasm,
-        MIN_WASM_LEN,
-        usize::MAX,
-    ) {
-        defects.push(err);
-    } else if upgrade.new_canister_wasm[..4] != RAW_WASM_HEADER[..]
-        && upgrade.new_canister_wasm[..3] != GZIPPED_WASM_HEADER[..]
-    {
-        defects.push("new_canister_wasm lacks the magic value in its header.".into());
+    if let Some(chunked_canister_wasm) = &upgrade.chunked_canister_wasm {
+        if !upgrade.new_canister_wasm.is_empty() {
+            defects.push(
+                "new_canister_wasm must be empty if chunked_canister_wasm is set.".to_string(),
+            );
+        }
+        defects.extend(validate_chunked_canister_wasm(chunked_canister_wasm));
+    } else {
+        const RAW_WASM_HEADER: [u8; 4] = [0, 0x61, 0x73, 0x6d];
+        // see https://ic-interface-spec.netlify.app/#canister-module-format
+        const GZIPPED_WASM_HEADER: [u8; 3] = [0x1f, 0x8b, 0x08];
+        // Minimum length of raw WASM is 8 bytes (4 magic bytes and 4 bytes encoding version).
+        // Minimum length of gzipped WASM is 10 bytes (2 magic bytes, 1 byte encoding compression method, and 7 additional gzip header bytes).
+        const MIN_WASM_LEN: usize = 8;
+        if let Err(err) = validate_len(
+            "new_canister_wasm",
+            &upgrade.new_canister_wasm,
+            MIN_WASM_LEN,
+            usize::MAX,
+        ) {
+            defects.push(err);
+        } else if upgrade.new_canister_wasm[..4] != RAW_WASM_HEADER[..]
+            && upgrade.new_canister_wasm[..3] != GZIPPED_WASM_HEADER[..]
+        {
+            defects.push("new_canister_wasm lacks the magic value in its header.".into());
+        }
     }
 
     // Generate final report.
@@ -656,6 +1168,27 @@ fn validate_and_render_upgrade_sns_controlled_canister(
         ));
     }
 
+    if let Some(ChunkedCanisterWasm {
+        wasm_module_hash,
+        store_canister_id,
+        chunk_hashes_list,
+    }) = &upgrade.chunked_canister_wasm
+    {
+        return Ok(format!(
+            r"# Proposal to upgrade SNS controlled canister:
+
+## Canister id: {:?}
+
+## Canister wasm sha256: {}
+
+## Wasm chunks: {} chunk(s) stored in canister {}",
+            canister_id,
+            hex::encode(wasm_module_hash),
+            chunk_hashes_list.len(),
+            store_canister_id.unwrap_or_default(),
+        ));
+    }
+
     let mut state = Sha256::new();
     state.write(&upgrade.new_canister_wasm);
     let sha = state.finish();
@@ -671,6 +1204,50 @@ fn validate_and_render_upgrade_sns_controlled_canister(
     ))
 }
 
+/// Validates a reference to a wasm module that was uploaded in chunks. Returns the list of
+/// defects, which is empty if `chunked_canister_wasm` is valid.
+///
+/// Whether the chunks are actually present in the chunk store of the store canister can only
+/// be determined when the proposal is executed (chunks might get cleared or uploaded in the
+/// meantime), so this is checked by SNS root right before the wasm module is installed.
+fn validate_chunked_canister_wasm(chunked_canister_wasm: &ChunkedCanisterWasm) -> Vec<String> {
+    // Chunks and wasm modules are identified by their SHA-256 hash.
+    const HASH_LEN: usize = 32;
+    // The chunk store of a canister holds at most 100 chunks (100 MiB in chunks of 1 MiB), so
+    // a wasm module cannot consist of more chunks.
+    const MAX_CHUNKS: usize = WASM_CHUNK_STORE_MAX_CHUNKS as usize;
+
+    let ChunkedCanisterWasm {
+        wasm_module_hash,
+        store_canister_id,
+        chunk_hashes_list,
+    } = chunked_canister_wasm;
+
+    let mut defects = vec![];
+
+    if let Err(err) = validate_len("wasm_module_hash", wasm_module_hash, HASH_LEN, HASH_LEN) {
+        defects.push(err);
+    }
+    if let Err(err) = validate_required_field("store_canister_id", store_canister_id) {
+        defects.push(err);
+    }
+    if let Err(err) = validate_len("chunk_hashes_list", chunk_hashes_list, 1, MAX_CHUNKS) {
+        defects.push(err);
+    }
+    for (i, chunk_hash) in chunk_hashes_list.iter().enumerate() {
+        if let Err(err) = validate_len(
+            &format!("chunk_hashes_list[{}]", i),
+            chunk_hash,
+            HASH_LEN,
+            HASH_LEN,
+        ) {
+            defects.push(err);
+        }
+    }
+
+    defects
+}
+
 pub(crate) fn render_version(version: &Version) -> String {
     format!(
         r"Version {{
@@ -741,6 +1318,7 @@ pub(crate) struct ValidGenericNervousSystemFunction {
     pub target_method: String,
     pub validator_canister_id: CanisterId,
     pub validator_method: String,
+    pub topic: Topic,
 }
 
 /// Validates a given canister id and adds a defect to a given list of defects if the there was no
@@ -812,6 +1390,7 @@ impl TryFrom<&NervousSystemFunction> for ValidGenericNervousSystemFunction {
                 target_method_name,
                 validator_canister_id,
                 validator_method_name,
+                topic,
             })) => {
                 // Validate the target_canister_id field.
                 let target_canister_id =
@@ -835,6 +1414,25 @@ impl TryFrom<&NervousSystemFunction> for ValidGenericNervousSystemFunction {
                     defects.push("validator_method_name was empty.".to_string());
                 }
 
+                // Validate the topic field.
+                let topic = match topic {
+                    None => Some(Topic::ApplicationSpecific),
+                    Some(topic) => match Topic::try_from(*topic) {
+                        Ok(Topic::Unspecified) | Err(_) => {
+                            defects.push(format!("topic {} is not a valid topic.", topic));
+                            None
+                        }
+                        Ok(topic) if topic.is_critical() => {
+                            defects.push(format!(
+                                "topic {:?} is reserved for native functions.",
+                                topic
+                            ));
+                            None
+                        }
+                        Ok(topic) => Some(topic),
+                    },
+                };
+
                 if !defects.is_empty() {
                     return Err(format!(
                         "ExecuteNervousSystemFunction was invalid for the following reason(s):\n{}",
@@ -848,6 +1446,7 @@ impl TryFrom<&NervousSystemFunction> for ValidGenericNervousSystemFunction {
                     target_method: target_method_name.as_ref().unwrap().clone(),
                     validator_canister_id: validator_canister_id.unwrap(),
                     validator_method: validator_method_name.as_ref().unwrap().clone(),
+                    topic: topic.unwrap(),
                 })
             }
             _ => {
@@ -1207,6 +1806,92 @@ fn validate_and_render_manage_dapp_canister_settings(
     }
 }
 
+/// Validates and renders a proposal with action RestoreDappCanisterSnapshot.
+///
+/// Only the snapshot taken before the latest upgrade of the canister can be restored, so that
+/// proposals cannot target snapshots that governance does not know about (or that were already
+/// replaced). Whether `canister_id` is actually a registered dapp canister can only be checked
+/// (by SNS root) when the proposal is executed.
+pub(crate) fn validate_and_render_restore_dapp_canister_snapshot(
+    restore_dapp_canister_snapshot: &RestoreDappCanisterSnapshot,
+    disallowed_target_canister_ids: &HashSet<CanisterId>,
+    pre_upgrade_snapshots: &BTreeMap<String, PreUpgradeSnapshot>,
+) -> Result<String, String> {
+    if !ARE_DAPP_CANISTER_SNAPSHOTS_ENABLED {
+        return Err(
+            "RestoreDappCanisterSnapshot proposals are not supported yet, because the management \
+             canister does not implement canister snapshots yet."
+                .to_string(),
+        );
+    }
+
+    let RestoreDappCanisterSnapshot {
+        canister_id,
+        snapshot_id,
+    } = restore_dapp_canister_snapshot;
+
+    let canister_id = canister_id
+        .ok_or_else(|| "RestoreDappCanisterSnapshot must specify a canister_id".to_string())?;
+    let snapshot_id = snapshot_id
+        .as_ref()
+        .ok_or_else(|| "RestoreDappCanisterSnapshot must specify a snapshot_id".to_string())?;
+
+    if disallowed_target_canister_ids.contains(&CanisterId::unchecked_from_principal(canister_id)) {
+        return Err(format!(
+            "RestoreDappCanisterSnapshot cannot target canister {canister_id}, because it is \
+             not a dapp canister"
+        ));
+    }
+
+    let latest_snapshot_id = pre_upgrade_snapshots
+        .get(&canister_id.to_string())
+        .map(|snapshot| &snapshot.snapshot_id);
+    if latest_snapshot_id != Some(snapshot_id) {
+        return Err(format!(
+            "RestoreDappCanisterSnapshot can only restore the snapshot that was taken before the \
+             latest upgrade of canister {canister_id}, which is not {}",
+            hex::encode(snapshot_id)
+        ));
+    }
+
+    Ok(format!(
+        "# Proposal to restore a dapp canister from one of its snapshots:\n\
+         ## Canister id: {canister_id}\n\
+         ## Snapshot id: {}",
+        hex::encode(snapshot_id)
+    ))
+}
+
+/// Returns whether a RestoreDappCanisterSnapshot proposal that is open, or adopted but not yet
+/// executed, targets the given snapshot. Such a snapshot must not be replaced.
+///
+/// Only proposals made after the upgrade proposal that took the snapshot are considered, since
+/// RestoreDappCanisterSnapshot proposals can only target snapshots that already exist.
+pub(crate) fn is_snapshot_targeted_by_restore_proposal(
+    proposals: &BTreeMap<u64, ProposalData>,
+    canister_id: PrincipalId,
+    snapshot: &PreUpgradeSnapshot,
+) -> bool {
+    let PreUpgradeSnapshot {
+        proposal_id,
+        snapshot_id,
+    } = snapshot;
+    proposals
+        .range(proposal_id + 1..)
+        .any(|(_, proposal_data)| {
+            matches!(
+                proposal_data.status(),
+                ProposalDecisionStatus::Open | ProposalDecisionStatus::Adopted
+            ) && matches!(
+                proposal_data.proposal.as_ref().and_then(|proposal| proposal.action.as_ref()),
+                Some(Action::RestoreDappCanisterSnapshot(RestoreDappCanisterSnapshot {
+                    canister_id: Some(target_canister_id),
+                    snapshot_id: Some(target_snapshot_id),
+                })) if *target_canister_id == canister_id && target_snapshot_id == snapshot_id
+            )
+        })
+}
+
 impl ProposalData {
     /// Returns the proposal's decision status. See [ProposalDecisionStatus] in the SNS's
     /// proto for more information.
@@ -1226,6 +1911,18 @@ impl ProposalData {
         }
     }
 
+    /// Returns the proposal's topic. Proposals that were made before proposals had topics do not
+    /// store their topic, in which case it is derived from the proposal's action.
+    pub(crate) fn effective_topic(
+        &self,
+        registered_functions: &BTreeMap<u64, NervousSystemFunction>,
+    ) -> Topic {
+        match self.topic {
+            Some(_) => self.topic(),
+            None => function_id_to_topic(self.action, registered_functions),
+        }
+    }
+
     /// Returns the proposal's reward status. See [ProposalRewardStatus] in the SNS's
     /// proto for more information.
     pub fn reward_status(&self, now_seconds: u64) -> ProposalRewardStatus {
@@ -1634,17 +2331,20 @@ impl ProposalData {
             return false;
         }
 
-        // At this point, we can let go of most proposals. The only special case is
-        // TransferSnsTreasuryFunds. We want to hang onto those for at least 7 days after they have
-        // been successfully executed. This is because they are still needed for the purposes of
-        // limiting the total amount that is transferred out of the treasury in that time window.
+        // At this point, we can let go of most proposals. The only special cases are
+        // TransferSnsTreasuryFunds and CreateTreasuryPaymentStream. We want to hang onto those for
+        // at least 7 days after they have been successfully executed. This is because they are
+        // still needed for the purposes of limiting the total amount that is transferred out of
+        // the treasury in that time window.
 
         let Some(proposal) = &self.proposal else {
             log!(ERROR, "Proposal {:?} missing `proposal` field", self.id);
             return true;
         };
         match &proposal.action {
-            Some(Action::TransferSnsTreasuryFunds(_)) => (),
+            Some(Action::TransferSnsTreasuryFunds(_))
+            | Some(Action::CreateTreasuryPaymentStream(_))
+            | Some(Action::OpenSaleRound(_)) => (),
             _ => return true,
         };
 
@@ -1653,7 +2353,7 @@ impl ProposalData {
             return true;
         }
 
-        // Only hang onto TransferSnsTreasuryFunds that were executed recently enough.
+        // Only hang onto treasury proposals that were executed recently enough.
         let earliest_unpurgeable_executed_timestamp_seconds =
             now_seconds - EXECUTED_TRANSFER_SNS_TREASURY_FUNDS_PROPOSAL_RETENTION_DURATION_SECONDS;
         self.executed_timestamp_seconds < earliest_unpurgeable_executed_timestamp_seconds
@@ -1731,6 +2431,9 @@ impl ProposalRewardStatus {
 /// Returns the total amount (in e8s) that was transfered from the treasury via
 /// TransferSnsTreasuryFunds proposals, or None if there was an overflow.
 ///
+/// CreateTreasuryPaymentStream proposals count with the total amount of the stream, because that
+/// is what the treasury committed to paying when the proposal was executed.
+///
 /// Arguments:
 /// * `proposals` - Self-explanatory.
 /// * `filter_from_treasury` - Specify the token type (ICP or SNS). The name of this parameter is
@@ -1751,7 +2454,7 @@ fn total_treasury_transfer_amount_e8s<'a>(
             continue;
         }
 
-        // Skip non-TransferSnsTreasuryFunds proposals.
+        // Skip proposals that do not transfer funds out of the treasury.
         let proposal_id = proposal.id;
         let Some(proposal) = &proposal.proposal else {
             log!(
@@ -1761,21 +2464,31 @@ fn total_treasury_transfer_amount_e8s<'a>(
             );
             continue;
         };
-        let transfer_sns_treasury_funds = match &proposal.action {
-            Some(Action::TransferSnsTreasuryFunds(ok)) => ok,
+        let (from_treasury, amount_e8s) = match &proposal.action {
+            Some(Action::TransferSnsTreasuryFunds(transfer_sns_treasury_funds)) => (
+                transfer_sns_treasury_funds.from_treasury,
+                transfer_sns_treasury_funds.amount_e8s,
+            ),
+            Some(Action::CreateTreasuryPaymentStream(create_treasury_payment_stream)) => (
+                create_treasury_payment_stream.from_treasury,
+                create_treasury_payment_stream.total_amount_e8s,
+            ),
+            Some(Action::OpenSaleRound(open_sale_round)) => (
+                TransferFrom::SnsTokenTreasury as i32,
+                open_sale_round.sns_token_e8s.unwrap_or_default(),
+            ),
             _ => continue,
         };
 
         // Skip proposals that do not deal with the type of token that the caller asked for.
-        let observed_from_treasury =
-            TransferFrom::try_from(transfer_sns_treasury_funds.from_treasury);
+        let observed_from_treasury = TransferFrom::try_from(from_treasury);
         if observed_from_treasury != Ok(filter_from_treasury) {
             continue;
         }
 
         // At this point, the proposal was executed recently, and deals with the type of token that
         // the caller is interested in. Therefore, increment result by the amount in the proposal.
-        let total_e8s_or_none = total_e8s.checked_add(transfer_sns_treasury_funds.amount_e8s);
+        let total_e8s_or_none = total_e8s.checked_add(amount_e8s);
         total_e8s = match total_e8s_or_none {
             Some(ok) => ok,
 
@@ -1786,7 +2499,7 @@ fn total_treasury_transfer_amount_e8s<'a>(
                      {} + {} (last proposal ID = {:?})",
                     min_executed_timestamp_seconds,
                     total_e8s,
-                    transfer_sns_treasury_funds.amount_e8s,
+                    amount_e8s,
                     proposal_id,
                 );
 
@@ -1795,7 +2508,51 @@ fn total_treasury_transfer_amount_e8s<'a>(
         }
     }
 
-    Some(total_e8s)
+    Some(total_e8s)
+}
+
+/// Returns the total amount (in e8s) that active treasury payment streams still have to pay from
+/// the given treasury, or None if there was an overflow.
+///
+/// Streams that were created by a proposal that was executed at or after
+/// `min_executed_timestamp_seconds` are skipped, because `total_treasury_transfer_amount_e8s`
+/// already counts their total amount. (The ID of a stream is the ID of the proposal that created
+/// it.)
+#[must_use]
+fn total_treasury_payment_stream_commitments_e8s<'a>(
+    treasury_payment_streams: impl Iterator<Item = &'a TreasuryPaymentStream>,
+    filter_from_treasury: TransferFrom,
+    proposals: impl Iterator<Item = &'a ProposalData>,
+    min_executed_timestamp_seconds: u64,
+) -> Option<u64> {
+    let recently_created_payment_stream_ids: HashSet<u64> = proposals
+        .filter(|proposal| {
+            proposal.executed_timestamp_seconds >= min_executed_timestamp_seconds
+                && matches!(
+                    proposal
+                        .proposal
+                        .as_ref()
+                        .and_then(|proposal| proposal.action.as_ref()),
+                    Some(Action::CreateTreasuryPaymentStream(_))
+                )
+        })
+        .filter_map(|proposal| proposal.id.map(|proposal_id| proposal_id.id))
+        .collect();
+
+    treasury_payment_streams
+        .filter(|treasury_payment_stream| {
+            treasury_payment_stream
+                .terms
+                .as_ref()
+                .map(|terms| terms.from_treasury())
+                == Some(filter_from_treasury)
+                && !treasury_payment_stream
+                    .id
+                    .is_some_and(|id| recently_created_payment_stream_ids.contains(&id))
+        })
+        .try_fold(0_u64, |total_e8s, treasury_payment_stream| {
+            total_e8s.checked_add(treasury_payment_stream.outstanding_e8s())
+        })
 }
 
 #[cfg(test)]
@@ -1864,6 +2621,8 @@ mod tests {
             sns_initialization_parameters: "".to_string(),
             is_finalizing_disburse_maturity: None,
             maturity_modulation: None,
+            treasury_payment_streams: Default::default(),
+            pre_upgrade_snapshots: Default::default(),
         }
     }
 
@@ -2020,6 +2779,7 @@ mod tests {
             new_canister_wasm: vec![0, 0x61, 0x73, 0x6D, 1, 0, 0, 0],
             canister_upgrade_arg: None,
             mode: Some(CanisterInstallModeProto::Upgrade.into()),
+            chunked_canister_wasm: None,
         };
         assert_is_ok(validate_and_render_upgrade_sns_controlled_canister(
             &upgrade,
@@ -2118,6 +2878,106 @@ mod tests {
         assert_is_ok(validate_default_action(&proposal.action));
     }
 
+    fn basic_chunked_canister_wasm() -> ChunkedCanisterWasm {
+        ChunkedCanisterWasm {
+            wasm_module_hash: vec![0xaa; 32],
+            store_canister_id: Some(basic_principal_id()),
+            chunk_hashes_list: vec![vec![0xbb; 32], vec![0xcc; 32]],
+        }
+    }
+
+    #[test]
+    fn upgrade_wasm_can_be_chunked() {
+        let mut proposal = basic_upgrade_sns_controlled_canister_proposal();
+
+        match proposal.action.as_mut().unwrap() {
+            proposal::Action::UpgradeSnsControlledCanister(upgrade) => {
+                upgrade.new_canister_wasm = vec![];
+                upgrade.chunked_canister_wasm = Some(basic_chunked_canister_wasm());
+                let rendering =
+                    validate_and_render_upgrade_sns_controlled_canister(upgrade).unwrap();
+                assert!(
+                    rendering.contains(&format!("## Canister wasm sha256: {}", "aa".repeat(32))),
+                    "{}",
+                    rendering
+                );
+                assert!(
+                    rendering.contains(&format!(
+                        "## Wasm chunks: 2 chunk(s) stored in canister {}",
+                        basic_principal_id()
+                    )),
+                    "{}",
+                    rendering
+                );
+            }
+            _ => panic!("Proposal.action is not an UpgradeSnsControlledCanister."),
+        }
+
+        assert_is_ok(validate_default_proposal(&proposal));
+        assert_is_ok(validate_default_action(&proposal.action));
+    }
+
+    #[test]
+    fn upgrade_chunked_wasm_must_be_well_formed() {
+        let defective_chunked_canister_wasms = [
+            ChunkedCanisterWasm {
+                wasm_module_hash: vec![0xaa; 31],
+                ..basic_chunked_canister_wasm()
+            },
+            ChunkedCanisterWasm {
+                store_canister_id: None,
+                ..basic_chunked_canister_wasm()
+            },
+            ChunkedCanisterWasm {
+                chunk_hashes_list: vec![],
+                ..basic_chunked_canister_wasm()
+            },
+            ChunkedCanisterWasm {
+                chunk_hashes_list: vec![vec![0xbb; 32], vec![0xcc; 33]],
+                ..basic_chunked_canister_wasm()
+            },
+            // More chunks than fit into a chunk store.
+            ChunkedCanisterWasm {
+                chunk_hashes_list: vec![vec![0xbb; 32]; WASM_CHUNK_STORE_MAX_CHUNKS as usize + 1],
+                ..basic_chunked_canister_wasm()
+            },
+        ];
+
+        for chunked_canister_wasm in defective_chunked_canister_wasms {
+            let mut proposal = basic_upgrade_sns_controlled_canister_proposal();
+
+            // Create a defect.
+            match proposal.action.as_mut().unwrap() {
+                proposal::Action::UpgradeSnsControlledCanister(upgrade) => {
+                    upgrade.new_canister_wasm = vec![];
+                    upgrade.chunked_canister_wasm = Some(chunked_canister_wasm);
+                    assert_is_err(validate_and_render_upgrade_sns_controlled_canister(upgrade));
+                }
+                _ => panic!("Proposal.action is not an UpgradeSnsControlledCanister."),
+            }
+
+            assert_validate_upgrade_sns_controlled_canister_is_err(&proposal);
+        }
+    }
+
+    /// A proposal must not specify both an inline and a chunked wasm.
+    #[test]
+    fn upgrade_wasm_cannot_be_both_inline_and_chunked() {
+        let mut proposal = basic_upgrade_sns_controlled_canister_proposal();
+
+        // Create a defect.
+        match proposal.action.as_mut().unwrap() {
+            proposal::Action::UpgradeSnsControlledCanister(upgrade) => {
+                assert!(!upgrade.new_canister_wasm.is_empty());
+                upgrade.chunked_canister_wasm = Some(basic_chunked_canister_wasm());
+                assert_is_err(validate_and_render_upgrade_sns_controlled_canister(upgrade));
+            }
+            _ => panic!("Proposal.action is not an UpgradeSnsControlledCanister."),
+        }
+
+        assert_validate_upgrade_sns_controlled_canister_is_err(&proposal);
+    }
+
     fn basic_add_nervous_system_function_proposal() -> Proposal {
         let nervous_system_function = NervousSystemFunction {
             id: 1000,
@@ -2129,6 +2989,7 @@ mod tests {
                     target_method_name: Some("test_method".to_string()),
                     validator_canister_id: Some(CanisterId::from_u64(1).get()),
                     validator_method_name: Some("test_validator_method".to_string()),
+                    topic: None,
                 },
             )),
         };
@@ -2326,6 +3187,7 @@ mod tests {
                     target_method_name: Some("test_method".to_string()),
                     validator_canister_id: Some(CanisterId::from_u64(1).get()),
                     validator_method_name: Some("test_validator_method".to_string()),
+                    topic: None,
                 },
             )),
         };
@@ -2356,6 +3218,48 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn add_nervous_system_function_cant_use_critical_topics() {
+        let nervous_system_function_with_topic = |topic: Option<i32>| NervousSystemFunction {
+            id: 1000,
+            name: "a".to_string(),
+            description: None,
+            function_type: Some(FunctionType::GenericNervousSystemFunction(
+                GenericNervousSystemFunction {
+                    target_canister_id: Some(CanisterId::from_u64(1).get()),
+                    target_method_name: Some("test_method".to_string()),
+                    validator_canister_id: Some(CanisterId::from_u64(1).get()),
+                    validator_method_name: Some("test_validator_method".to_string()),
+                    topic,
+                },
+            )),
+        };
+
+        for topic in [None, Some(Topic::Governance), Some(Topic::DappManagement)] {
+            let nervous_system_function =
+                nervous_system_function_with_topic(topic.map(|topic| topic as i32));
+            assert_is_ok(validate_and_render_add_generic_nervous_system_function(
+                &hashset![FORBIDDEN_CANISTER],
+                &nervous_system_function,
+                &EMPTY_FUNCTIONS,
+            ));
+        }
+
+        for topic in [
+            Topic::Unspecified as i32,
+            Topic::Treasury as i32,
+            Topic::Critical as i32,
+            1_000,
+        ] {
+            let nervous_system_function = nervous_system_function_with_topic(Some(topic));
+            assert_is_err(validate_and_render_add_generic_nervous_system_function(
+                &hashset![FORBIDDEN_CANISTER],
+                &nervous_system_function,
+                &EMPTY_FUNCTIONS,
+            ));
+        }
+    }
+
     #[test]
     fn add_nervous_system_function_cant_exceed_maximum() {
         let mut functions_map = BTreeMap::new();
@@ -2372,6 +3276,7 @@ mod tests {
                         target_method_name: Some("test_method".to_string()),
                         validator_canister_id: Some(CanisterId::from_u64(i as u64).get()),
                         validator_method_name: Some("test_validator_method".to_string()),
+                        topic: None,
                     },
                 )),
             };
@@ -2388,6 +3293,7 @@ mod tests {
                     target_method_name: Some("test_method".to_string()),
                     validator_canister_id: Some(CanisterId::from_u64(u64::MAX).get()),
                     validator_method_name: Some("test_validator_method".to_string()),
+                    topic: None,
                 },
             )),
         };
@@ -2787,6 +3693,7 @@ Version {
                     target_method_name: Some("test_method".to_string()),
                     validator_canister_id: Some(CanisterId::from(1).get()),
                     validator_method_name: Some("test_validator_method".to_string()),
+                    topic: None,
                 },
             )),
         };
@@ -2808,6 +3715,7 @@ Version {
                     target_method_name: Some("test_method".to_string()),
                     validator_canister_id: Some(CanisterId::from(1).get()),
                     validator_method_name: Some("test_validator_method".to_string()),
+                    topic: None,
                 },
             )),
         };
@@ -2827,6 +3735,7 @@ Version {
                     target_method_name: Some("test_method".to_string()),
                     validator_canister_id: Some(CanisterId::ic_00().get()),
                     validator_method_name: Some("test_validator_method".to_string()),
+                    topic: None,
                 },
             )),
         };
@@ -3035,6 +3944,301 @@ Version {
         );
     }
 
+    fn create_treasury_payment_stream_for_test() -> CreateTreasuryPaymentStream {
+        CreateTreasuryPaymentStream {
+            from_treasury: TransferFrom::IcpTreasury.into(),
+            total_amount_e8s: 400_000,
+            to_principal: Some(basic_principal_id()),
+            to_subaccount: None,
+            memo: None,
+            start_timestamp_seconds: Some(1_000_000),
+            cliff_duration_seconds: None,
+            period_seconds: Some(SECONDS_PER_DAY),
+            installment_count: Some(4),
+        }
+    }
+
+    #[test]
+    fn validate_and_render_create_treasury_payment_stream_renders_for_valid_inputs() {
+        assert_eq!(
+            locally_validate_and_render_create_treasury_payment_stream(
+                &create_treasury_payment_stream_for_test(),
+                0,
+                1_000_000,
+                vec![],
+            )
+            .unwrap(),
+            r"# Proposal to create a treasury payment stream:
+## Source treasury: ICP Treasury (ICP Ledger)
+## Total amount: 0.00400000 ICP
+## Total amount (e8s): 400000
+## Target principal: bg4sm-wzk
+## Target account: bg4sm-wzk
+## Memo: 0
+## Start (seconds since the UNIX epoch): 1000000
+## Cliff duration (seconds): 0
+## Period (seconds): 86400
+## Number of installments: 4"
+        );
+
+        // Valid case without an explicit start.
+        assert_eq!(
+            locally_validate_and_render_create_treasury_payment_stream(
+                &CreateTreasuryPaymentStream {
+                    from_treasury: TransferFrom::SnsTokenTreasury.into(),
+                    start_timestamp_seconds: None,
+                    cliff_duration_seconds: Some(2 * SECONDS_PER_DAY),
+                    memo: Some(1000),
+                    ..create_treasury_payment_stream_for_test()
+                },
+                1000,
+                1_000_000,
+                vec![],
+            )
+            .unwrap(),
+            r"# Proposal to create a treasury payment stream:
+## Source treasury: SNS Token Treasury (SNS Ledger)
+## Total amount: 0.00400000 SNS Tokens
+## Total amount (e8s): 400000
+## Target principal: bg4sm-wzk
+## Target account: bg4sm-wzk
+## Memo: 1000
+## Start (seconds since the UNIX epoch): when the proposal is executed
+## Cliff duration (seconds): 172800
+## Period (seconds): 86400
+## Number of installments: 4"
+        );
+    }
+
+    #[test]
+    fn validate_and_render_create_treasury_payment_stream_invalid_schedule() {
+        assert_eq!(
+            locally_validate_and_render_create_treasury_payment_stream(
+                &CreateTreasuryPaymentStream {
+                    from_treasury: TransferFrom::SnsTokenTreasury.into(),
+                    total_amount_e8s: 3000,
+                    period_seconds: Some(3600),
+                    to_principal: None,
+                    ..create_treasury_payment_stream_for_test()
+                },
+                1000,
+                1_000_000,
+                vec![],
+            )
+            .unwrap_err(),
+            "CreateTreasuryPaymentStream proposal was invalid for the following reason(s):\n\
+             Each installment must be at least the fee and minimum transaction for transactions \
+             from SNS Token Treasury (SNS Ledger), which is 1000 e8s.\n\
+             period_seconds must be at least 86400.\n\
+             Must specify a principal to pay the installments to."
+        );
+
+        assert_eq!(
+            locally_validate_and_render_create_treasury_payment_stream(
+                &CreateTreasuryPaymentStream {
+                    installment_count: Some(0),
+                    ..create_treasury_payment_stream_for_test()
+                },
+                0,
+                1_000_000,
+                vec![],
+            )
+            .unwrap_err(),
+            "CreateTreasuryPaymentStream proposal was invalid for the following reason(s):\n\
+             installment_count must be between 1 and 1000."
+        );
+
+        assert_eq!(
+            locally_validate_and_render_create_treasury_payment_stream(
+                &CreateTreasuryPaymentStream {
+                    cliff_duration_seconds: Some(5 * SECONDS_PER_DAY),
+                    ..create_treasury_payment_stream_for_test()
+                },
+                0,
+                1_000_000,
+                vec![],
+            )
+            .unwrap_err(),
+            "CreateTreasuryPaymentStream proposal was invalid for the following reason(s):\n\
+             cliff_duration_seconds must not extend beyond the last installment."
+        );
+
+        assert_eq!(
+            locally_validate_and_render_create_treasury_payment_stream(
+                &CreateTreasuryPaymentStream {
+                    start_timestamp_seconds: Some(u64::MAX - SECONDS_PER_DAY),
+                    ..create_treasury_payment_stream_for_test()
+                },
+                0,
+                1_000_000,
+                vec![],
+            )
+            .unwrap_err(),
+            "CreateTreasuryPaymentStream proposal was invalid for the following reason(s):\n\
+             The payment stream would never end."
+        );
+    }
+
+    #[test]
+    fn validate_and_render_cancel_treasury_payment_stream_test() {
+        let payment_stream = TreasuryPaymentStream {
+            id: Some(1),
+            terms: Some(create_treasury_payment_stream_for_test()),
+            start_timestamp_seconds: Some(1_000_000),
+            paid_e8s: Some(100_000),
+            ..Default::default()
+        };
+        let treasury_payment_streams = btreemap! {
+            1 => payment_stream.clone(),
+            2 => TreasuryPaymentStream {
+                id: Some(2),
+                cancelled_timestamp_seconds: Some(1_100_000),
+                ..payment_stream.clone()
+            },
+            3 => TreasuryPaymentStream {
+                id: Some(3),
+                paid_e8s: Some(400_000),
+                ..payment_stream
+            },
+        };
+        let cancel = |payment_stream_id| {
+            validate_and_render_cancel_treasury_payment_stream(
+                &CancelTreasuryPaymentStream { payment_stream_id },
+                &treasury_payment_streams,
+            )
+        };
+
+        assert_eq!(
+            cancel(Some(1)).unwrap(),
+            r"# Proposal to cancel a treasury payment stream:
+## Payment stream id: 1
+## Amount paid so far (e8s): 100000
+## Amount that will no longer be paid (e8s): 300000"
+        );
+        assert_eq!(
+            cancel(Some(2)).unwrap_err(),
+            "Treasury payment stream 2 has already been cancelled."
+        );
+        assert_eq!(
+            cancel(Some(3)).unwrap_err(),
+            "Treasury payment stream 3 has already been paid in full."
+        );
+        assert_eq!(
+            cancel(Some(4)).unwrap_err(),
+            "There is no treasury payment stream with ID 4."
+        );
+        assert!(cancel(None).is_err());
+    }
+
+    fn open_sale_round_for_test() -> OpenSaleRound {
+        OpenSaleRound {
+            sns_token_e8s: Some(1_000_000 * E8),
+            min_participants: Some(2),
+            min_direct_participation_icp_e8s: Some(10 * E8),
+            max_direct_participation_icp_e8s: Some(100 * E8),
+            min_participant_icp_e8s: Some(E8),
+            max_participant_icp_e8s: Some(50 * E8),
+            duration_seconds: Some(7 * SECONDS_PER_DAY),
+            start_delay_seconds: Some(SECONDS_PER_DAY),
+            neuron_basket_count: Some(3),
+            neuron_basket_dissolve_delay_interval_seconds: Some(30 * SECONDS_PER_DAY),
+        }
+    }
+
+    #[test]
+    fn validate_and_render_open_sale_round_renders_for_valid_inputs() {
+        assert_eq!(
+            locally_validate_and_render_open_sale_round(
+                &open_sale_round_for_test(),
+                1000,
+                1_000_000,
+                vec![],
+            )
+            .unwrap(),
+            r"# Proposal to open a follow-on sale round:
+## SNS tokens offered (from the SNS Token Treasury): 1000000.00000000 SNS Tokens
+## SNS tokens offered (e8s): 100000000000000
+## Minimum number of participants: 2
+## Minimum direct participation (ICP e8s): 1000000000
+## Maximum direct participation (ICP e8s): 10000000000
+## Minimum participation per participant (ICP e8s): 100000000
+## Maximum participation per participant (ICP e8s): 5000000000
+## Start delay (seconds): 86400
+## Duration (seconds): 604800
+## Neurons per participant: 3
+## Neuron dissolve delay interval (seconds): 2592000"
+        );
+    }
+
+    #[test]
+    fn validate_and_render_open_sale_round_invalid_inputs() {
+        assert_eq!(
+            locally_validate_and_render_open_sale_round(
+                &OpenSaleRound {
+                    min_participants: None,
+                    neuron_basket_count: Some(1),
+                    duration_seconds: Some(3600),
+                    ..open_sale_round_for_test()
+                },
+                1000,
+                1_000_000,
+                vec![],
+            )
+            .unwrap_err(),
+            "OpenSaleRound proposal was invalid for the following reason(s):\n\
+             min_participants must be > 0.\n\
+             neuron_basket_count must be between 2 and 10000.\n\
+             duration_seconds must be between 86400 and 1209600."
+        );
+
+        assert_eq!(
+            locally_validate_and_render_open_sale_round(
+                &OpenSaleRound {
+                    max_participant_icp_e8s: Some(200 * E8),
+                    ..open_sale_round_for_test()
+                },
+                1000,
+                1_000_000,
+                vec![],
+            )
+            .unwrap_err(),
+            "OpenSaleRound proposal was invalid for the following reason(s):\n\
+             max_participant_icp_e8s (20000000000) must be <= \
+             max_direct_participation_icp_e8s (10000000000)."
+        );
+
+        // Each participant must receive enough SNS tokens for their neuron basket.
+        assert_eq!(
+            locally_validate_and_render_open_sale_round(
+                &OpenSaleRound {
+                    sns_token_e8s: Some(1_000_000),
+                    ..open_sale_round_for_test()
+                },
+                1000,
+                1_000_000,
+                vec![],
+            )
+            .unwrap_err(),
+            "OpenSaleRound proposal was invalid for the following reason(s):\n\
+             min_participant_icp_e8s (100000000) is too small. Each participant needs to \
+             receive enough SNS tokens to form 3 neurons, each of which requires at least \
+             1000000 SNS e8s plus 1000 e8s in transaction fees."
+        );
+
+        // Defects found by the caller are reported as well.
+        assert_eq!(
+            locally_validate_and_render_open_sale_round(
+                &open_sale_round_for_test(),
+                1000,
+                1_000_000,
+                vec!["Insufficient treasury balance.".to_string()],
+            )
+            .unwrap_err(),
+            "OpenSaleRound proposal was invalid for the following reason(s):\n\
+             Insufficient treasury balance."
+        );
+    }
+
     #[test]
     fn validate_and_render_mint_sns_tokens_renders_for_valid_inputs() {
         // Valid case
@@ -3418,6 +4622,7 @@ Version {
             ballots: btreemap!{},
             minimum_yes_proportion_of_total: None,
             minimum_yes_proportion_of_exercised: None,
+            topic: None,
             failed_timestamp_seconds: 0,
             proposal_creation_timestamp_seconds: 1670488610, // 2022-12-08T08:36:50Z (Thu)
             initial_voting_period_seconds: 345_600, // 4 days
@@ -3708,6 +4913,139 @@ Version {
         );
     }
 
+    #[test]
+    fn validate_and_render_restore_dapp_canister_snapshot_checks_fields() {
+        let canister_id = PrincipalId::new_user_test_id(1);
+        let disallowed_canister_ids: HashSet<CanisterId> =
+            hashset! {CanisterId::unchecked_from_principal(PrincipalId::new_user_test_id(2))};
+        // Snapshot 41 was replaced by snapshot 42 when the canister was upgraded again.
+        let pre_upgrade_snapshots = btreemap! {
+            canister_id.to_string() => PreUpgradeSnapshot {
+                proposal_id: 2,
+                snapshot_id: vec![42],
+            },
+        };
+
+        let render = validate_and_render_restore_dapp_canister_snapshot(
+            &RestoreDappCanisterSnapshot {
+                canister_id: Some(canister_id),
+                snapshot_id: Some(vec![42]),
+            },
+            &disallowed_canister_ids,
+            &pre_upgrade_snapshots,
+        )
+        .unwrap();
+        assert_eq!(
+            render,
+            format!(
+                "# Proposal to restore a dapp canister from one of its snapshots:\n\
+                 ## Canister id: {canister_id}\n\
+                 ## Snapshot id: 2a"
+            )
+        );
+
+        for (restore_dapp_canister_snapshot, expected_error) in [
+            (
+                RestoreDappCanisterSnapshot {
+                    canister_id: None,
+                    snapshot_id: Some(vec![42]),
+                },
+                "must specify a canister_id",
+            ),
+            (
+                RestoreDappCanisterSnapshot {
+                    canister_id: Some(canister_id),
+                    snapshot_id: None,
+                },
+                "must specify a snapshot_id",
+            ),
+            (
+                RestoreDappCanisterSnapshot {
+                    canister_id: Some(PrincipalId::new_user_test_id(2)),
+                    snapshot_id: Some(vec![42]),
+                },
+                "not a dapp canister",
+            ),
+            (
+                RestoreDappCanisterSnapshot {
+                    canister_id: Some(canister_id),
+                    snapshot_id: Some(vec![41]),
+                },
+                "only restore the snapshot that was taken before the latest upgrade",
+            ),
+            (
+                RestoreDappCanisterSnapshot {
+                    canister_id: Some(PrincipalId::new_user_test_id(3)),
+                    snapshot_id: Some(vec![42]),
+                },
+                "only restore the snapshot that was taken before the latest upgrade",
+            ),
+        ] {
+            let rendered_error = validate_and_render_restore_dapp_canister_snapshot(
+                &restore_dapp_canister_snapshot,
+                &disallowed_canister_ids,
+                &pre_upgrade_snapshots,
+            )
+            .unwrap_err();
+            assert!(
+                rendered_error.contains(expected_error),
+                "{rendered_error} does not contain {expected_error}"
+            );
+        }
+    }
+
+    #[test]
+    fn snapshots_targeted_by_open_or_adopted_restore_proposals_are_locked() {
+        let canister_id = PrincipalId::new_user_test_id(1);
+        let restore_proposal = |snapshot_id: Vec<u8>, decided_timestamp_seconds| ProposalData {
+            proposal: Some(Proposal {
+                action: Some(Action::RestoreDappCanisterSnapshot(
+                    RestoreDappCanisterSnapshot {
+                        canister_id: Some(canister_id),
+                        snapshot_id: Some(snapshot_id),
+                    },
+                )),
+                ..Default::default()
+            }),
+            decided_timestamp_seconds,
+            ..Default::default()
+        };
+        let snapshot = |proposal_id, snapshot_id: Vec<u8>| PreUpgradeSnapshot {
+            proposal_id,
+            snapshot_id,
+        };
+        // Proposal 2 is open, proposal 3 was rejected. Snapshots 1 and 2 were taken when
+        // executing proposal 1.
+        let proposals = btreemap! {
+            2 => restore_proposal(vec![1], 0),
+            3 => restore_proposal(vec![2], 1),
+        };
+        assert_eq!(proposals[&2].status(), ProposalDecisionStatus::Open);
+        assert_eq!(proposals[&3].status(), ProposalDecisionStatus::Rejected);
+
+        assert!(is_snapshot_targeted_by_restore_proposal(
+            &proposals,
+            canister_id,
+            &snapshot(1, vec![1])
+        ));
+        assert!(!is_snapshot_targeted_by_restore_proposal(
+            &proposals,
+            canister_id,
+            &snapshot(1, vec![2])
+        ));
+        assert!(!is_snapshot_targeted_by_restore_proposal(
+            &proposals,
+            PrincipalId::new_user_test_id(2),
+            &snapshot(1, vec![1])
+        ));
+        // Proposals made before the snapshot was taken cannot target it.
+        assert!(!is_snapshot_targeted_by_restore_proposal(
+            &proposals,
+            canister_id,
+            &snapshot(2, vec![1])
+        ));
+    }
+
     #[test]
     fn limited_proposal_data_for_list_proposals_retain_ballots_by_caller() {
         let original_proposal_data = ProposalData {
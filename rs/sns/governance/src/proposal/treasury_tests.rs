@@ -1,4 +1,5 @@
 use super::*;
+use crate::pb::v1::ProposalId;
 
 #[test]
 fn test_can_be_purged_retain_recent_transfer_sns_treasury_funds() {
@@ -286,3 +287,59 @@ fn test_total_treasury_transfer_amount_e8s() {
         None, // Quiet explosion.
     );
 }
+
+#[test]
+fn test_total_treasury_payment_stream_commitments_e8s_skips_recently_created_streams() {
+    let min_executed_timestamp_seconds = 123_456_789;
+
+    let new_payment_stream = |id: u64, from_treasury: TransferFrom| TreasuryPaymentStream {
+        id: Some(id),
+        terms: Some(CreateTreasuryPaymentStream {
+            from_treasury: from_treasury as i32,
+            total_amount_e8s: 1_000,
+            ..Default::default()
+        }),
+        paid_e8s: Some(id),
+        ..Default::default()
+    };
+    let new_proposal = |id: u64, executed_timestamp_seconds: u64| ProposalData {
+        id: Some(ProposalId { id }),
+        proposal: Some(Proposal {
+            action: Some(Action::CreateTreasuryPaymentStream(Default::default())),
+            ..Default::default()
+        }),
+        executed_timestamp_seconds,
+        ..Default::default()
+    };
+
+    let treasury_payment_streams = vec![
+        // Counted, because the proposal that created it is old.
+        new_payment_stream(1, TransferFrom::IcpTreasury),
+        // Counted, because the proposal that created it has been purged.
+        new_payment_stream(2, TransferFrom::IcpTreasury),
+        // Skipped, because the total amount is in the 7 day total already.
+        new_payment_stream(3, TransferFrom::IcpTreasury),
+        // Skipped, because it pays from the other treasury.
+        new_payment_stream(4, TransferFrom::SnsTokenTreasury),
+        // Skipped, because it has been cancelled.
+        TreasuryPaymentStream {
+            cancelled_timestamp_seconds: Some(min_executed_timestamp_seconds),
+            ..new_payment_stream(5, TransferFrom::IcpTreasury)
+        },
+    ];
+    let proposals = vec![
+        new_proposal(1, min_executed_timestamp_seconds - 1),
+        new_proposal(3, min_executed_timestamp_seconds),
+        new_proposal(4, min_executed_timestamp_seconds - 1),
+    ];
+
+    assert_eq!(
+        total_treasury_payment_stream_commitments_e8s(
+            treasury_payment_streams.iter(),
+            TransferFrom::IcpTreasury,
+            proposals.iter(),
+            min_executed_timestamp_seconds,
+        ),
+        Some(999 + 998),
+    );
+}
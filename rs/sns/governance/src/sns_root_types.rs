@@ -192,3 +192,49 @@ pub struct ManageDappCanisterSettingsResponse {
     #[prost(string, optional, tag = "1")]
     pub failure_reason: ::core::option::Option<::prost::alloc::string::String>,
 }
+
+#[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TakeDappCanisterSnapshotRequest {
+    /// The registered dapp canister to take a snapshot of.
+    #[prost(message, optional, tag = "1")]
+    pub canister_id: ::core::option::Option<::ic_base_types::PrincipalId>,
+    /// If set, the new snapshot replaces this (previously taken) snapshot.
+    #[prost(bytes = "vec", optional, tag = "2")]
+    pub replace_snapshot: ::core::option::Option<::prost::alloc::vec::Vec<u8>>,
+}
+
+#[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TakeDappCanisterSnapshotResponse {
+    /// The (opaque) ID of the new snapshot, as assigned by the management
+    /// canister. Set if and only if failure_reason is not set.
+    #[prost(bytes = "vec", optional, tag = "1")]
+    pub snapshot_id: ::core::option::Option<::prost::alloc::vec::Vec<u8>>,
+    /// Absense of failure_reason indicates success.
+    #[prost(string, optional, tag = "2")]
+    pub failure_reason: ::core::option::Option<::prost::alloc::string::String>,
+}
+
+#[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct LoadDappCanisterSnapshotRequest {
+    /// The registered dapp canister to load the snapshot into.
+    #[prost(message, optional, tag = "1")]
+    pub canister_id: ::core::option::Option<::ic_base_types::PrincipalId>,
+    /// The ID of a snapshot of canister_id.
+    #[prost(bytes = "vec", optional, tag = "2")]
+    pub snapshot_id: ::core::option::Option<::prost::alloc::vec::Vec<u8>>,
+}
+
+#[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct LoadDappCanisterSnapshotResponse {
+    /// Absense of failure_reason indicates success.
+    #[prost(string, optional, tag = "1")]
+    pub failure_reason: ::core::option::Option<::prost::alloc::string::String>,
+}
@@ -3,8 +3,9 @@ use crate::{
     logs::{ERROR, INFO},
     pb::{
         sns_root_types::{
-            set_dapp_controllers_request::CanisterIds, ManageDappCanisterSettingsRequest,
-            RegisterDappCanistersRequest, SetDappControllersRequest,
+            set_dapp_controllers_request::CanisterIds, LoadDappCanisterSnapshotRequest,
+            ManageDappCanisterSettingsRequest, RegisterDappCanistersRequest,
+            SetDappControllersRequest,
         },
         v1::{
             claim_swap_neurons_request::NeuronParameters,
@@ -18,7 +19,7 @@ use crate::{
             manage_neuron_response::{
                 DisburseMaturityResponse, MergeMaturityResponse, StakeMaturityResponse,
             },
-            nervous_system_function::FunctionType,
+            nervous_system_function::{FunctionType, GenericNervousSystemFunction},
             neuron::Followees,
             proposal::Action,
             ClaimSwapNeuronsError, ClaimSwapNeuronsResponse, ClaimedSwapNeuronStatus,
@@ -26,8 +27,9 @@ use crate::{
             GovernanceError, ManageDappCanisterSettings, ManageNeuronResponse, MintSnsTokens,
             Motion, NervousSystemFunction, NervousSystemParameters, Neuron, NeuronId,
             NeuronPermission, NeuronPermissionList, NeuronPermissionType, ProposalId,
-            RegisterDappCanisters, RewardEvent, TransferSnsTreasuryFunds,
-            UpgradeSnsControlledCanister, UpgradeSnsToNextVersion, Vote, VotingRewardsParameters,
+            RegisterDappCanisters, RestoreDappCanisterSnapshot, RewardEvent, Topic,
+            TransferSnsTreasuryFunds, TreasuryPaymentStream, UpgradeSnsControlledCanister,
+            UpgradeSnsToNextVersion, Vote, VotingRewardsParameters,
         },
     },
     proposal::ValidGenericNervousSystemFunction,
@@ -113,6 +115,18 @@ pub mod native_action_ids {
 
     /// ManageDappCanisterSettings Action.
     pub const MANAGE_DAPP_CANISTER_SETTINGS: u64 = 14;
+
+    /// RestoreDappCanisterSnapshot Action.
+    pub const RESTORE_DAPP_CANISTER_SNAPSHOT: u64 = 15;
+
+    /// CreateTreasuryPaymentStream Action.
+    pub const CREATE_TREASURY_PAYMENT_STREAM: u64 = 16;
+
+    /// CancelTreasuryPaymentStream Action.
+    pub const CANCEL_TREASURY_PAYMENT_STREAM: u64 = 17;
+
+    /// OpenSaleRound Action.
+    pub const OPEN_SALE_ROUND: u64 = 18;
 }
 
 impl governance::Mode {
@@ -141,6 +155,7 @@ impl governance::Mode {
         use manage_neuron::Command as C;
         let ok = match command {
             C::Follow(_)
+            | C::SetFollowing(_)
             | C::MakeProposal(_)
             | C::RegisterVote(_)
             | C::AddNeuronPermissions(_)
@@ -302,6 +317,7 @@ impl From<&manage_neuron::Command> for neuron_in_flight_command::Command {
             S::AddNeuronPermissions   (x) => D::AddNeuronPermissions   (x),
             S::RemoveNeuronPermissions(x) => D::RemoveNeuronPermissions(x),
             S::StakeMaturity          (_) => D::SyncCommand(SyncCommand{}),
+            S::SetFollowing           (_) => D::SyncCommand(SyncCommand{}),
         }
     }
 }
@@ -1108,6 +1124,46 @@ impl From<Action> for NervousSystemFunction {
                 ),
                 function_type: Some(FunctionType::NativeNervousSystemFunction(Empty {})),
             },
+            Action::RestoreDappCanisterSnapshot(_) => NervousSystemFunction {
+                id: native_action_ids::RESTORE_DAPP_CANISTER_SNAPSHOT,
+                name: "Restore dapp canister snapshot".to_string(),
+                description: Some(
+                    "Proposal to restore a dapp canister from one of its snapshots, e.g. to roll \
+                     back an upgrade."
+                        .to_string(),
+                ),
+                function_type: Some(FunctionType::NativeNervousSystemFunction(Empty {})),
+            },
+            Action::CreateTreasuryPaymentStream(_) => NervousSystemFunction {
+                id: native_action_ids::CREATE_TREASURY_PAYMENT_STREAM,
+                name: "Create treasury payment stream".to_string(),
+                description: Some(
+                    "Proposal to pay funds from an SNS Governance controlled treasury account \
+                     to a recipient in scheduled installments."
+                        .to_string(),
+                ),
+                function_type: Some(FunctionType::NativeNervousSystemFunction(Empty {})),
+            },
+            Action::CancelTreasuryPaymentStream(_) => NervousSystemFunction {
+                id: native_action_ids::CANCEL_TREASURY_PAYMENT_STREAM,
+                name: "Cancel treasury payment stream".to_string(),
+                description: Some(
+                    "Proposal to stop paying the remaining installments of a treasury payment \
+                     stream."
+                        .to_string(),
+                ),
+                function_type: Some(FunctionType::NativeNervousSystemFunction(Empty {})),
+            },
+            Action::OpenSaleRound(_) => NervousSystemFunction {
+                id: native_action_ids::OPEN_SALE_ROUND,
+                name: "Open sale round".to_string(),
+                description: Some(
+                    "Proposal to sell SNS tokens from the SNS token treasury for ICP in a \
+                     follow-on sale round of the SNS swap."
+                        .to_string(),
+                ),
+                function_type: Some(FunctionType::NativeNervousSystemFunction(Empty {})),
+            },
         }
     }
 }
@@ -1176,6 +1232,7 @@ impl manage_neuron::Command {
             manage_neuron::Command::AddNeuronPermissions(_) => "AddNeuronPermissions",
             manage_neuron::Command::RemoveNeuronPermissions(_) => "RemoveNeuronPermissions",
             manage_neuron::Command::StakeMaturity(_) => "StakeMaturity",
+            manage_neuron::Command::SetFollowing(_) => "SetFollowing",
         }
         .to_string()
     }
@@ -1264,6 +1321,14 @@ impl ManageNeuronResponse {
         }
     }
 
+    pub fn set_following_response() -> Self {
+        ManageNeuronResponse {
+            command: Some(manage_neuron_response::Command::SetFollowing(
+                manage_neuron_response::SetFollowingResponse {},
+            )),
+        }
+    }
+
     pub fn make_proposal_response(proposal_id: ProposalId) -> Self {
         let proposal_id = Some(proposal_id);
         ManageNeuronResponse {
@@ -1532,9 +1597,12 @@ impl Action {
     fn proposal_criticality(&self) -> ProposalCriticality {
         use Action::*;
         match self {
-            DeregisterDappCanisters(_) | TransferSnsTreasuryFunds(_) | MintSnsTokens(_) => {
-                ProposalCriticality::Critical
-            }
+            DeregisterDappCanisters(_)
+            | TransferSnsTreasuryFunds(_)
+            | MintSnsTokens(_)
+            | CreateTreasuryPaymentStream(_)
+            | CancelTreasuryPaymentStream(_)
+            | OpenSaleRound(_) => ProposalCriticality::Critical,
 
             Unspecified(_)
             | ManageNervousSystemParameters(_)
@@ -1547,7 +1615,42 @@ impl Action {
             | ManageSnsMetadata(_)
             | ManageLedgerParameters(_)
             | RegisterDappCanisters(_)
-            | ManageDappCanisterSettings(_) => ProposalCriticality::Normal,
+            | ManageDappCanisterSettings(_)
+            | RestoreDappCanisterSnapshot(_) => ProposalCriticality::Normal,
+        }
+    }
+
+    /// Returns the topic of proposals with this action.
+    ///
+    /// Proposals executing a generic nervous system function have the topic of that function,
+    /// which is not known here. Use `function_id_to_topic` for those.
+    fn topic(&self) -> Topic {
+        use Action::*;
+        match self {
+            Unspecified(_) => Topic::Unspecified,
+
+            Motion(_)
+            | ManageNervousSystemParameters(_)
+            | AddGenericNervousSystemFunction(_)
+            | RemoveGenericNervousSystemFunction(_)
+            | UpgradeSnsToNextVersion(_)
+            | ManageSnsMetadata(_)
+            | ManageLedgerParameters(_) => Topic::Governance,
+
+            TransferSnsTreasuryFunds(_)
+            | MintSnsTokens(_)
+            | CreateTreasuryPaymentStream(_)
+            | CancelTreasuryPaymentStream(_)
+            | OpenSaleRound(_) => Topic::Treasury,
+
+            UpgradeSnsControlledCanister(_)
+            | RegisterDappCanisters(_)
+            | ManageDappCanisterSettings(_)
+            | RestoreDappCanisterSnapshot(_) => Topic::DappManagement,
+
+            DeregisterDappCanisters(_) => Topic::Critical,
+
+            ExecuteGenericNervousSystemFunction(_) => Topic::ApplicationSpecific,
         }
     }
 }
@@ -1591,6 +1694,160 @@ pub(crate) fn function_id_to_proposal_criticality(function_id: u64) -> ProposalC
     ProposalCriticality::Normal
 }
 
+/// Returns the topic of proposals of the function with the given ID.
+///
+/// Native functions have a fixed topic. Generic functions have the topic that was given when they
+/// were added, and `Topic::ApplicationSpecific` if none was given (or if the function is not
+/// registered).
+pub(crate) fn function_id_to_topic(
+    function_id: u64,
+    registered_functions: &BTreeMap<u64, NervousSystemFunction>,
+) -> Topic {
+    lazy_static! {
+        static ref NATIVE_FUNCTION_ID_TO_TOPIC: HashMap</* function_id */ u64, Topic> = {
+            let mut result = HashMap::new();
+
+            for action in Action::iter() {
+                // Skip non-native, aka generic functions.
+                if let Action::ExecuteGenericNervousSystemFunction(_) = action {
+                    continue;
+                }
+
+                let function_id = u64::from(&action);
+                let previous_value = result.insert(function_id, action.topic());
+                debug_assert!(previous_value.is_none(), "{:#?}", previous_value);
+            }
+
+            result
+        };
+    }
+
+    if let Some(topic) = NATIVE_FUNCTION_ID_TO_TOPIC.get(&function_id) {
+        return *topic;
+    }
+
+    match registered_functions
+        .get(&function_id)
+        .and_then(|function| function.function_type.as_ref())
+    {
+        Some(FunctionType::GenericNervousSystemFunction(generic)) => generic.topic_or_default(),
+        _ => Topic::ApplicationSpecific,
+    }
+}
+
+impl Topic {
+    /// Returns whether proposals of this topic can have critical consequences for the SNS or its
+    /// dapp. Generic nervous system functions cannot be in such a topic.
+    pub fn is_critical(&self) -> bool {
+        match self {
+            Topic::Treasury | Topic::Critical => true,
+
+            Topic::Unspecified
+            | Topic::Governance
+            | Topic::DappManagement
+            | Topic::ApplicationSpecific => false,
+        }
+    }
+}
+
+impl GenericNervousSystemFunction {
+    /// Returns the topic of proposals executing this function, which is
+    /// `Topic::ApplicationSpecific` if the function does not specify a valid topic.
+    pub fn topic_or_default(&self) -> Topic {
+        self.topic
+            .and_then(|topic| Topic::try_from(topic).ok())
+            .filter(|topic| *topic != Topic::Unspecified)
+            .unwrap_or(Topic::ApplicationSpecific)
+    }
+}
+
+impl TreasuryPaymentStream {
+    /// Returns the amount (in e8s) that has vested by `now_seconds`, i.e. the total amount of all
+    /// installments that are due by then, whether they have been paid or not.
+    pub fn vested_e8s(&self, now_seconds: u64) -> u64 {
+        let Some(terms) = &self.terms else {
+            return 0;
+        };
+        let start_timestamp_seconds = self.start_timestamp_seconds.unwrap_or_default();
+        let cliff_timestamp_seconds = start_timestamp_seconds
+            .saturating_add(terms.cliff_duration_seconds.unwrap_or_default());
+        let installment_count = terms.installment_count.unwrap_or_default();
+        if now_seconds < start_timestamp_seconds
+            || now_seconds < cliff_timestamp_seconds
+            || installment_count == 0
+        {
+            return 0;
+        }
+
+        // One installment vests at the end of each period.
+        let vested_installment_count = match terms.period_seconds.unwrap_or_default() {
+            0 => installment_count,
+            period_seconds => {
+                ((now_seconds - start_timestamp_seconds) / period_seconds).min(installment_count)
+            }
+        };
+
+        // This cannot overflow, and the result is at most total_amount_e8s, because
+        // vested_installment_count <= installment_count.
+        (u128::from(terms.total_amount_e8s) * u128::from(vested_installment_count)
+            / u128::from(installment_count)) as u64
+    }
+
+    /// Returns the amount (in e8s) that has vested by `now_seconds`, but has not been paid yet.
+    /// Nothing is due once the stream has been cancelled.
+    pub fn due_e8s(&self, now_seconds: u64) -> u64 {
+        if self.cancelled_timestamp_seconds.is_some() {
+            return 0;
+        }
+        self.vested_e8s(now_seconds)
+            .saturating_sub(self.paid_e8s.unwrap_or_default())
+    }
+
+    /// Returns the amount (in e8s) that the stream still has to pay over its remaining lifetime.
+    /// Nothing is outstanding once the stream has been cancelled.
+    pub fn outstanding_e8s(&self) -> u64 {
+        if self.cancelled_timestamp_seconds.is_some() {
+            return 0;
+        }
+        self.terms
+            .as_ref()
+            .map_or(0, |terms| terms.total_amount_e8s)
+            .saturating_sub(self.paid_e8s.unwrap_or_default())
+    }
+
+    /// Returns when the stream finished, i.e. when it was cancelled or when its last installment
+    /// was paid, or None if it is still active. A stream with a pending payment is still active.
+    pub fn finished_timestamp_seconds(&self) -> Option<u64> {
+        if self.pending_payment_e8s.is_some() {
+            return None;
+        }
+        if let Some(cancelled_timestamp_seconds) = self.cancelled_timestamp_seconds {
+            return Some(cancelled_timestamp_seconds);
+        }
+        let terms = self.terms.as_ref()?;
+        if self.outstanding_e8s() > 0 {
+            return None;
+        }
+
+        // While the last installment is being transferred, paid_e8s already includes it, but
+        // last_payment_timestamp_seconds still refers to a payment from before the end of the
+        // stream. Whereas a successful payment after the end pays everything that is left.
+        let end_timestamp_seconds = self
+            .start_timestamp_seconds
+            .unwrap_or_default()
+            .saturating_add(
+                terms
+                    .period_seconds
+                    .unwrap_or_default()
+                    .saturating_mul(terms.installment_count.unwrap_or_default()),
+            );
+        self.last_payment_timestamp_seconds
+            .filter(|last_payment_timestamp_seconds| {
+                *last_payment_timestamp_seconds >= end_timestamp_seconds
+            })
+    }
+}
+
 impl UpgradeSnsControlledCanister {
     // Returns a clone of self, except that "large blob fields" are replaced
     // with a (UTF-8 encoded) textual summary of their contents. See
@@ -1687,6 +1944,16 @@ impl From<&Action> for u64 {
             Action::ManageDappCanisterSettings(_) => {
                 native_action_ids::MANAGE_DAPP_CANISTER_SETTINGS
             }
+            Action::RestoreDappCanisterSnapshot(_) => {
+                native_action_ids::RESTORE_DAPP_CANISTER_SNAPSHOT
+            }
+            Action::CreateTreasuryPaymentStream(_) => {
+                native_action_ids::CREATE_TREASURY_PAYMENT_STREAM
+            }
+            Action::CancelTreasuryPaymentStream(_) => {
+                native_action_ids::CANCEL_TREASURY_PAYMENT_STREAM
+            }
+            Action::OpenSaleRound(_) => native_action_ids::OPEN_SALE_ROUND,
         }
     }
 }
@@ -2106,6 +2373,20 @@ impl From<ManageDappCanisterSettings> for ManageDappCanisterSettingsRequest {
     }
 }
 
+impl From<RestoreDappCanisterSnapshot> for LoadDappCanisterSnapshotRequest {
+    fn from(restore_dapp_canister_snapshot: RestoreDappCanisterSnapshot) -> Self {
+        let RestoreDappCanisterSnapshot {
+            canister_id,
+            snapshot_id,
+        } = restore_dapp_canister_snapshot;
+
+        LoadDappCanisterSnapshotRequest {
+            canister_id,
+            snapshot_id,
+        }
+    }
+}
+
 impl Motion {
     pub fn new(text: &str) -> Self {
         Motion {
@@ -2413,7 +2694,8 @@ pub(crate) mod tests {
         governance::Mode::PreInitializationSwap,
         nervous_system_function::{FunctionType, GenericNervousSystemFunction},
         neuron::Followees,
-        ExecuteGenericNervousSystemFunction, Proposal, ProposalData, VotingRewardsParameters,
+        CreateTreasuryPaymentStream, ExecuteGenericNervousSystemFunction, Proposal, ProposalData,
+        VotingRewardsParameters,
     };
     use ic_base_types::PrincipalId;
     use ic_nervous_system_common_test_keys::{TEST_USER1_PRINCIPAL, TEST_USER2_PRINCIPAL};
@@ -2681,6 +2963,7 @@ pub(crate) mod tests {
             #[rustfmt::skip]
             let allowed_in_pre_initialization_swap = vec! [
                 Command::Follow                  (Default::default()),
+                Command::SetFollowing            (Default::default()),
                 Command::MakeProposal            (Default::default()),
                 Command::RegisterVote            (Default::default()),
                 Command::AddNeuronPermissions    (Default::default()),
@@ -2831,6 +3114,7 @@ pub(crate) mod tests {
                         target_method_name: Some("Foo".to_string()),
                         validator_canister_id: Some(*target_canister_id),
                         validator_method_name: Some("Bar".to_string()),
+                        topic: None,
                     })),
                 }
             }
@@ -3425,4 +3709,115 @@ pub(crate) mod tests {
             execute_generic_nervous_system_function_proposal,
         );
     }
+
+    fn treasury_payment_stream_for_test() -> TreasuryPaymentStream {
+        TreasuryPaymentStream {
+            id: Some(42),
+            terms: Some(CreateTreasuryPaymentStream {
+                total_amount_e8s: 1_000,
+                cliff_duration_seconds: Some(2 * SECONDS_PER_DAY),
+                period_seconds: Some(SECONDS_PER_DAY),
+                installment_count: Some(4),
+                ..Default::default()
+            }),
+            start_timestamp_seconds: Some(100),
+            paid_e8s: Some(0),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_treasury_payment_stream_vested_e8s() {
+        let payment_stream = treasury_payment_stream_for_test();
+
+        // Nothing vests before the start, nor before the cliff.
+        assert_eq!(payment_stream.vested_e8s(0), 0);
+        assert_eq!(payment_stream.vested_e8s(100), 0);
+        assert_eq!(payment_stream.vested_e8s(100 + SECONDS_PER_DAY), 0);
+        assert_eq!(payment_stream.vested_e8s(99 + 2 * SECONDS_PER_DAY), 0);
+
+        // At the cliff, the installments of all elapsed periods vest at once.
+        assert_eq!(payment_stream.vested_e8s(100 + 2 * SECONDS_PER_DAY), 500);
+        assert_eq!(payment_stream.vested_e8s(99 + 3 * SECONDS_PER_DAY), 500);
+        assert_eq!(payment_stream.vested_e8s(100 + 3 * SECONDS_PER_DAY), 750);
+
+        // Nothing more vests after the last installment.
+        assert_eq!(payment_stream.vested_e8s(100 + 4 * SECONDS_PER_DAY), 1_000);
+        assert_eq!(
+            payment_stream.vested_e8s(100 + 400 * SECONDS_PER_DAY),
+            1_000
+        );
+    }
+
+    #[test]
+    fn test_treasury_payment_stream_vested_e8s_rounds_down() {
+        let payment_stream = TreasuryPaymentStream {
+            terms: Some(CreateTreasuryPaymentStream {
+                total_amount_e8s: 1_000,
+                period_seconds: Some(10),
+                installment_count: Some(3),
+                ..Default::default()
+            }),
+            start_timestamp_seconds: Some(0),
+            ..Default::default()
+        };
+
+        assert_eq!(payment_stream.vested_e8s(10), 333);
+        assert_eq!(payment_stream.vested_e8s(20), 666);
+        // The last installment makes up for the rounding of the previous ones.
+        assert_eq!(payment_stream.vested_e8s(30), 1_000);
+    }
+
+    #[test]
+    fn test_treasury_payment_stream_due_and_outstanding_e8s() {
+        let now = 100 + 3 * SECONDS_PER_DAY;
+        let mut payment_stream = TreasuryPaymentStream {
+            paid_e8s: Some(500),
+            ..treasury_payment_stream_for_test()
+        };
+
+        assert_eq!(payment_stream.due_e8s(now), 250);
+        assert_eq!(payment_stream.outstanding_e8s(), 500);
+
+        payment_stream.paid_e8s = Some(750);
+        assert_eq!(payment_stream.due_e8s(now), 0);
+        assert_eq!(payment_stream.outstanding_e8s(), 250);
+
+        // Once cancelled, nothing is due or outstanding anymore.
+        payment_stream.paid_e8s = Some(500);
+        payment_stream.cancelled_timestamp_seconds = Some(now);
+        assert_eq!(payment_stream.due_e8s(now), 0);
+        assert_eq!(payment_stream.outstanding_e8s(), 0);
+    }
+
+    #[test]
+    fn test_treasury_payment_stream_finished_timestamp_seconds() {
+        let end = 100 + 4 * SECONDS_PER_DAY;
+        let mut payment_stream = TreasuryPaymentStream {
+            paid_e8s: Some(750),
+            last_payment_timestamp_seconds: Some(end - 1),
+            ..treasury_payment_stream_for_test()
+        };
+        assert_eq!(payment_stream.finished_timestamp_seconds(), None);
+
+        // The last installment is being transferred.
+        payment_stream.paid_e8s = Some(1_000);
+        assert_eq!(payment_stream.finished_timestamp_seconds(), None);
+
+        // The last installment has been paid.
+        payment_stream.last_payment_timestamp_seconds = Some(end + 10);
+        assert_eq!(payment_stream.finished_timestamp_seconds(), Some(end + 10));
+
+        // Cancelled streams are finished when they are cancelled.
+        let mut payment_stream = TreasuryPaymentStream {
+            cancelled_timestamp_seconds: Some(200),
+            ..treasury_payment_stream_for_test()
+        };
+        assert_eq!(payment_stream.finished_timestamp_seconds(), Some(200));
+
+        // Unless a payment is still pending.
+        payment_stream.pending_payment_e8s = Some(250);
+        payment_stream.pending_payment_created_at_time_nanos = Some(150_000_000_000);
+        assert_eq!(payment_stream.finished_timestamp_seconds(), None);
+    }
 }
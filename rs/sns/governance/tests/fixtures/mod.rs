@@ -3,7 +3,9 @@ use async_trait::async_trait;
 use futures::future::FutureExt;
 use ic_base_types::{CanisterId, PrincipalId};
 use ic_ledger_core::Tokens;
-use ic_nervous_system_common::{cmc::CMC, NervousSystemError, E8};
+use ic_nervous_system_common::{
+    cmc::CMC, ledger::TransferWithCreatedAtTimeError, NervousSystemError, E8,
+};
 use ic_sns_governance::{
     governance::{Governance, ValidGovernanceProto},
     ledger::ICRC1Ledger,
@@ -13,17 +15,18 @@ use ic_sns_governance::{
         manage_neuron,
         manage_neuron::{
             AddNeuronPermissions, MergeMaturity, RegisterVote, RemoveNeuronPermissions,
+            SetFollowing,
         },
         manage_neuron_response::{
             self, AddNeuronPermissionsResponse, FollowResponse, MergeMaturityResponse,
-            RegisterVoteResponse, RemoveNeuronPermissionsResponse,
+            RegisterVoteResponse, RemoveNeuronPermissionsResponse, SetFollowingResponse,
         },
         neuron::{DissolveState, Followees},
         proposal::Action,
         GetMaturityModulationRequest, GetMaturityModulationResponse, GetNeuron, GetProposal,
         Governance as GovernanceProto, GovernanceError, ManageNeuron, ManageNeuronResponse,
         NervousSystemParameters, Neuron, NeuronId, NeuronPermission, NeuronPermissionList,
-        NeuronPermissionType, Proposal, ProposalData, ProposalId, Vote,
+        NeuronPermissionType, Proposal, ProposalData, ProposalId, Topic, Vote,
     },
     types::Environment,
 };
@@ -122,6 +125,18 @@ impl ICRC1Ledger for LedgerFixture {
         Ok(ledger_fixture_state.block_height)
     }
 
+    async fn transfer_funds_with_created_at_time(
+        &self,
+        _amount_e8s: u64,
+        _fee_e8s: u64,
+        _from_subaccount: Option<Subaccount>,
+        _to: Account,
+        _memo: u64,
+        _created_at_time_nanos: u64,
+    ) -> Result<u64, TransferWithCreatedAtTimeError> {
+        unimplemented!()
+    }
+
     async fn total_supply(&self) -> Result<Tokens, NervousSystemError> {
         let accounts = &mut self.ledger_fixture_state.try_lock().unwrap().accounts;
 
@@ -770,6 +785,33 @@ impl GovernanceCanisterFixture {
         }
     }
 
+    pub fn set_following(
+        &mut self,
+        target_neuron: &NeuronId,
+        topic: Topic,
+        followees: Vec<NeuronId>,
+        caller: PrincipalId,
+    ) -> Result<SetFollowingResponse, GovernanceError> {
+        let response = self.manage_neuron(
+            target_neuron,
+            manage_neuron::Command::SetFollowing(SetFollowing {
+                topic: topic as i32,
+                followees,
+            }),
+            caller,
+        );
+
+        match response.command.unwrap() {
+            manage_neuron_response::Command::SetFollowing(set_following_response) => {
+                Ok(set_following_response)
+            }
+            manage_neuron_response::Command::Error(governance_error) => Err(governance_error),
+            _ => {
+                panic!("Unexpected command response when setting a follow relationship on a topic")
+            }
+        }
+    }
+
     pub fn vote(
         &mut self,
         target_neuron: &NeuronId,
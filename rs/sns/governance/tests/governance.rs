@@ -35,10 +35,11 @@ use ic_sns_governance::{
             proposal::Action,
             Account as AccountProto, AddMaturityRequest, Ballot, ClaimSwapNeuronsError,
             ClaimSwapNeuronsRequest, ClaimSwapNeuronsResponse, ClaimedSwapNeuronStatus,
-            DeregisterDappCanisters, Empty, GovernanceError, ManageNeuronResponse, MintSnsTokens,
-            MintTokensRequest, MintTokensResponse, Motion, NervousSystemParameters, Neuron,
-            NeuronId, NeuronPermission, NeuronPermissionList, NeuronPermissionType, Proposal,
-            ProposalData, ProposalId, RegisterDappCanisters, Vote, WaitForQuietState,
+            DeregisterDappCanisters, Empty, GovernanceError, ListProposals, ManageNeuronResponse,
+            MintSnsTokens, MintTokensRequest, MintTokensResponse, Motion, NervousSystemParameters,
+            Neuron, NeuronId, NeuronPermission, NeuronPermissionList, NeuronPermissionType,
+            Proposal, ProposalData, ProposalId, RegisterDappCanisters, Topic, Vote,
+            WaitForQuietState,
         },
     },
     types::{native_action_ids, ONE_DAY_SECONDS, ONE_MONTH_SECONDS},
@@ -2557,6 +2558,182 @@ fn test_neurons_can_follow_themselves() {
         .is_err());
 }
 
+/// Test that a neuron that follows another neuron on a topic votes like its followee on the
+/// proposals of that topic, and only on those.
+#[test]
+fn test_neurons_can_follow_on_topics() {
+    // Create the various neurons needed for this test
+    let followee_principal_id = PrincipalId::new_user_test_id(1000);
+    let followee_neuron_id = neuron_id(followee_principal_id, /*memo*/ 0);
+
+    let follower_principal_id = PrincipalId::new_user_test_id(1001);
+    let follower_neuron_id = neuron_id(follower_principal_id, /*memo*/ 0);
+
+    let proposer_principal_id = PrincipalId::new_user_test_id(1002);
+    let proposer_neuron_id = neuron_id(proposer_principal_id, /*memo*/ 0);
+
+    // Set up the test environment with neurons that can vote
+    let mut canister_fixture = GovernanceCanisterFixtureBuilder::new()
+        .add_neuron(
+            NeuronBuilder::new(
+                followee_neuron_id.clone(),
+                E8,
+                NeuronPermission::all(&followee_principal_id),
+            )
+            .set_dissolve_delay(15778801),
+        )
+        .add_neuron(
+            NeuronBuilder::new(
+                follower_neuron_id.clone(),
+                E8,
+                NeuronPermission::all(&follower_principal_id),
+            )
+            .set_dissolve_delay(15778801),
+        )
+        .add_neuron(
+            NeuronBuilder::new(
+                proposer_neuron_id.clone(),
+                E8,
+                NeuronPermission::all(&proposer_principal_id),
+            )
+            .set_dissolve_delay(15778801),
+        )
+        .create();
+
+    // Following on the unspecified topic is not allowed.
+    let error = canister_fixture
+        .set_following(
+            &follower_neuron_id,
+            Topic::Unspecified,
+            vec![followee_neuron_id.clone()],
+            follower_principal_id,
+        )
+        .unwrap_err();
+    assert_eq!(error.error_type, ErrorType::InvalidCommand as i32);
+
+    // The follower neuron will follow the followee neuron on the governance topic.
+    canister_fixture
+        .set_following(
+            &follower_neuron_id,
+            Topic::Governance,
+            vec![followee_neuron_id.clone()],
+            follower_principal_id,
+        )
+        .unwrap();
+
+    let follower_neuron = canister_fixture.get_neuron(&follower_neuron_id);
+    assert_eq!(
+        follower_neuron.topic_followees,
+        btreemap! {
+            Topic::Governance as i32 => Followees {
+                followees: vec![followee_neuron_id.clone()]
+            },
+        }
+    );
+
+    // Submit a proposal of the governance topic, and one of another topic.
+    let (governance_proposal_id, governance_proposal) = canister_fixture
+        .make_default_proposal(
+            &proposer_neuron_id,
+            Motion {
+                motion_text: "Test following on topics".to_string(),
+            },
+            proposer_principal_id,
+        )
+        .unwrap();
+    assert_eq!(governance_proposal.topic, Some(Topic::Governance as i32));
+
+    let (dapp_management_proposal_id, dapp_management_proposal) = canister_fixture
+        .make_default_proposal(
+            &proposer_neuron_id,
+            RegisterDappCanisters {
+                canister_ids: vec![PrincipalId::new_user_test_id(1)],
+            },
+            proposer_principal_id,
+        )
+        .unwrap();
+    assert_eq!(
+        dapp_management_proposal.topic,
+        Some(Topic::DappManagement as i32)
+    );
+
+    // Vote with the followee neuron on both proposals. The follower neuron only follows on the
+    // governance proposal.
+    for proposal_id in [governance_proposal_id, dapp_management_proposal_id] {
+        canister_fixture
+            .vote(
+                &followee_neuron_id,
+                proposal_id,
+                Vote::No,
+                followee_principal_id,
+            )
+            .unwrap();
+    }
+
+    let follower_vote = |canister_fixture: &mut GovernanceCanisterFixture, proposal_id| {
+        canister_fixture
+            .get_proposal_or_panic(proposal_id)
+            .ballots
+            .get(&follower_neuron_id.to_string())
+            .expect("Expected the follower neuron to have a ballot")
+            .vote
+    };
+    assert_eq!(
+        follower_vote(&mut canister_fixture, governance_proposal_id),
+        Vote::No as i32
+    );
+    assert_eq!(
+        follower_vote(&mut canister_fixture, dapp_management_proposal_id),
+        Vote::Unspecified as i32
+    );
+
+    // Proposals can be listed by topic.
+    let list_proposals_by_topic = |topics: Vec<Topic>| -> Vec<ProposalId> {
+        canister_fixture
+            .governance
+            .list_proposals(
+                &ListProposals {
+                    include_topics: topics.into_iter().map(|topic| topic as i32).collect(),
+                    ..Default::default()
+                },
+                &proposer_principal_id,
+            )
+            .proposals
+            .into_iter()
+            .map(|proposal| proposal.id.unwrap())
+            .collect()
+    };
+    assert_eq!(
+        list_proposals_by_topic(vec![Topic::DappManagement]),
+        vec![dapp_management_proposal_id]
+    );
+    assert_eq!(
+        list_proposals_by_topic(vec![Topic::Treasury, Topic::Governance]),
+        vec![governance_proposal_id]
+    );
+    assert_eq!(
+        list_proposals_by_topic(vec![]),
+        vec![dapp_management_proposal_id, governance_proposal_id]
+    );
+
+    // Following on a topic can be removed.
+    canister_fixture
+        .set_following(
+            &follower_neuron_id,
+            Topic::Governance,
+            vec![],
+            follower_principal_id,
+        )
+        .unwrap();
+    let follower_neuron = canister_fixture.get_neuron(&follower_neuron_id);
+    assert_eq!(follower_neuron.topic_followees, btreemap! {});
+    // The followee is removed from the followee index once it has no followers left.
+    assert_eq!(
+        canister_fixture.governance.topic_followee_index,
+        btreemap! {}
+    );
+}
+
 // Same as the previous test, but wait_for_quiet_state is None.
 #[test]
 fn test_register_vote_happy_no_wait_for_quiet() {
@@ -10,7 +10,9 @@ use ic_ledger_core::{
     tokens::{CheckedAdd, TOKEN_SUBDIVIDABLE_BY},
     Tokens,
 };
-use ic_nervous_system_common::{cmc::FakeCmc, i2d, NervousSystemError};
+use ic_nervous_system_common::{
+    cmc::FakeCmc, i2d, ledger::TransferWithCreatedAtTimeError, NervousSystemError,
+};
 use ic_nervous_system_common_test_keys::{
     TEST_USER1_KEYPAIR, TEST_USER2_KEYPAIR, TEST_USER3_KEYPAIR, TEST_USER4_KEYPAIR,
 };
@@ -1184,6 +1186,18 @@ async fn zero_total_reward_shares() {
             unimplemented!();
         }
 
+        async fn transfer_funds_with_created_at_time(
+            &self,
+            _amount_e8s: u64,
+            _fee_e8s: u64,
+            _from_subaccount: Option<Subaccount>,
+            _to: Account,
+            _memo: u64,
+            _created_at_time_nanos: u64,
+        ) -> Result<u64, TransferWithCreatedAtTimeError> {
+            unimplemented!()
+        }
+
         async fn total_supply(&self) -> Result<Tokens, NervousSystemError> {
             Ok(Tokens::from_e8s(0))
         }
@@ -1355,6 +1369,18 @@ async fn couple_of_neurons_who_voted_get_rewards() {
             unimplemented!();
         }
 
+        async fn transfer_funds_with_created_at_time(
+            &self,
+            _amount_e8s: u64,
+            _fee_e8s: u64,
+            _from_subaccount: Option<Subaccount>,
+            _to: Account,
+            _memo: u64,
+            _created_at_time_nanos: u64,
+        ) -> Result<u64, TransferWithCreatedAtTimeError> {
+            unimplemented!()
+        }
+
         async fn total_supply(&self) -> Result<Tokens, NervousSystemError> {
             Ok(Tokens::from_e8s(TOTAL_SUPPLY))
         }
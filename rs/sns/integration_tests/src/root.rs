@@ -318,6 +318,7 @@ fn test_root_restarts_governance_on_stop_canister_timeout() {
         compute_allocation: None,
         memory_allocation: None,
         query_allocation: None,
+        chunked_canister_wasm: None,
     };
 
     let _: () = update_with_sender(
@@ -122,6 +122,7 @@ fn test_upgrade_canister_proposal_is_successful() {
                 canister_upgrade_arg: Some(wasm().set_global_data(&[42]).build()),
                 // mode: None corresponds to CanisterInstallModeProto::Upgrade
                 mode: None,
+                chunked_canister_wasm: None,
             },
         )),
         ..Default::default()
@@ -248,6 +249,7 @@ fn test_upgrade_canister_proposal_reinstall() {
                     new_canister_wasm: new_dapp_wasm,
                     canister_upgrade_arg: Some(wasm().build()),
                     mode: Some(CanisterInstallModeProto::Reinstall.into()),
+                    chunked_canister_wasm: None,
                 },
             )),
             ..Default::default()
@@ -405,6 +407,7 @@ fn test_upgrade_canister_proposal_execution_fail() {
                     new_canister_wasm: new_dapp_wasm,
                     canister_upgrade_arg: None,
                     mode: Some(CanisterInstallModeProto::Upgrade.into()),
+                    chunked_canister_wasm: None,
                 },
             )),
             ..Default::default()
@@ -595,6 +598,7 @@ fn test_upgrade_after_state_shrink() {
                     new_canister_wasm: governance_wasm,
                     canister_upgrade_arg: None,
                     mode: Some(CanisterInstallModeProto::Upgrade.into()),
+                    chunked_canister_wasm: None,
                 },
             )),
             ..Default::default()
@@ -19,10 +19,11 @@ use ic_sns_root::{
     logs::{ERROR, INFO},
     pb::v1::{
         CanisterCallError, ListSnsCanistersRequest, ListSnsCanistersResponse,
+        LoadDappCanisterSnapshotRequest, LoadDappCanisterSnapshotResponse,
         ManageDappCanisterSettingsRequest, ManageDappCanisterSettingsResponse,
         RegisterDappCanisterRequest, RegisterDappCanisterResponse, RegisterDappCanistersRequest,
         RegisterDappCanistersResponse, SetDappControllersRequest, SetDappControllersResponse,
-        SnsRootCanister,
+        SnsRootCanister, TakeDappCanisterSnapshotRequest, TakeDappCanisterSnapshotResponse,
     },
     types::Environment,
     GetSnsCanistersSummaryRequest, GetSnsCanistersSummaryResponse, LedgerCanisterClient,
@@ -325,6 +326,44 @@ async fn manage_dapp_canister_settings(
     })
 }
 
+/// Takes a snapshot of a registered dapp canister. Caller must be the Governance canister.
+///
+/// Governance calls this before upgrading a dapp canister, so that the upgrade can be rolled back
+/// (see load_dapp_canister_snapshot).
+#[candid_method(update)]
+#[update]
+async fn take_dapp_canister_snapshot(
+    request: TakeDappCanisterSnapshotRequest,
+) -> TakeDappCanisterSnapshotResponse {
+    log!(INFO, "take_dapp_canister_snapshot");
+    assert_eq_governance_canister_id(PrincipalId(ic_cdk::api::caller()));
+
+    SnsRootCanister::take_dapp_canister_snapshot(
+        &STATE,
+        &ManagementCanisterClientImpl::<CanisterRuntime>::new(None),
+        request,
+    )
+    .await
+}
+
+/// Loads a snapshot into a registered dapp canister, replacing its current state. Caller must be
+/// the Governance canister.
+#[candid_method(update)]
+#[update]
+async fn load_dapp_canister_snapshot(
+    request: LoadDappCanisterSnapshotRequest,
+) -> LoadDappCanisterSnapshotResponse {
+    log!(INFO, "load_dapp_canister_snapshot");
+    assert_eq_governance_canister_id(PrincipalId(ic_cdk::api::caller()));
+
+    SnsRootCanister::load_dapp_canister_snapshot(
+        &STATE,
+        &ManagementCanisterClientImpl::<CanisterRuntime>::new(None),
+        request,
+    )
+    .await
+}
+
 fn assert_state_is_valid(state: &SnsRootCanister) {
     assert!(state.governance_canister_id.is_some());
     assert!(state.ledger_canister_id.is_some());
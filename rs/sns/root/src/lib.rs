@@ -2,9 +2,11 @@ use crate::{
     logs::{ERROR, INFO},
     pb::v1::{
         set_dapp_controllers_response, CanisterCallError, ListSnsCanistersResponse,
+        LoadDappCanisterSnapshotRequest, LoadDappCanisterSnapshotResponse,
         ManageDappCanisterSettingsRequest, ManageDappCanisterSettingsResponse,
         RegisterDappCanistersRequest, RegisterDappCanistersResponse, SetDappControllersRequest,
-        SetDappControllersResponse, SnsRootCanister,
+        SetDappControllersResponse, SnsRootCanister, TakeDappCanisterSnapshotRequest,
+        TakeDappCanisterSnapshotResponse,
     },
     types::Environment,
 };
@@ -16,6 +18,7 @@ use ic_canister_log::log;
 use ic_cdk::println;
 use ic_nervous_system_clients::{
     canister_id_record::CanisterIdRecord,
+    canister_snapshot::{LoadCanisterSnapshotArgs, TakeCanisterSnapshotArgs},
     canister_status::CanisterStatusResultV2,
     management_canister_client::ManagementCanisterClient,
     update_settings::{CanisterSettings, LogVisibility, UpdateSettings},
@@ -704,6 +707,127 @@ impl SnsRootCanister {
         }
     }
 
+    /// Takes a snapshot of a registered dapp canister.
+    ///
+    /// If `request.replace_snapshot` is set, the new snapshot replaces that one. This is needed
+    /// because the number of snapshots that a canister can have is limited.
+    pub async fn take_dapp_canister_snapshot(
+        self_ref: &'static LocalKey<RefCell<Self>>,
+        management_canister_client: &impl ManagementCanisterClient,
+        request: TakeDappCanisterSnapshotRequest,
+    ) -> TakeDappCanisterSnapshotResponse {
+        let TakeDappCanisterSnapshotRequest {
+            canister_id,
+            replace_snapshot,
+        } = request;
+
+        let canister_id = match Self::registered_dapp_canister_id(self_ref, canister_id) {
+            Ok(canister_id) => canister_id,
+            Err(failure_reason) => {
+                return TakeDappCanisterSnapshotResponse {
+                    snapshot_id: None,
+                    failure_reason: Some(failure_reason),
+                }
+            }
+        };
+
+        let result = management_canister_client
+            .take_canister_snapshot(TakeCanisterSnapshotArgs {
+                canister_id,
+                replace_snapshot,
+            })
+            .await;
+
+        match result {
+            Ok(snapshot) => {
+                log!(INFO, "Took a snapshot of canister {canister_id}");
+                TakeDappCanisterSnapshotResponse {
+                    snapshot_id: Some(snapshot.id),
+                    failure_reason: None,
+                }
+            }
+            Err(err) => {
+                let failure_reason =
+                    format!("Failed to take a snapshot of canister {canister_id}: {err:?}");
+                log!(ERROR, "{failure_reason}");
+                TakeDappCanisterSnapshotResponse {
+                    snapshot_id: None,
+                    failure_reason: Some(failure_reason),
+                }
+            }
+        }
+    }
+
+    /// Loads a snapshot into a registered dapp canister, replacing its current state.
+    pub async fn load_dapp_canister_snapshot(
+        self_ref: &'static LocalKey<RefCell<Self>>,
+        management_canister_client: &impl ManagementCanisterClient,
+        request: LoadDappCanisterSnapshotRequest,
+    ) -> LoadDappCanisterSnapshotResponse {
+        let LoadDappCanisterSnapshotRequest {
+            canister_id,
+            snapshot_id,
+        } = request;
+
+        let canister_id = match Self::registered_dapp_canister_id(self_ref, canister_id) {
+            Ok(canister_id) => canister_id,
+            Err(failure_reason) => {
+                return LoadDappCanisterSnapshotResponse {
+                    failure_reason: Some(failure_reason),
+                }
+            }
+        };
+        let Some(snapshot_id) = snapshot_id else {
+            return LoadDappCanisterSnapshotResponse {
+                failure_reason: Some("The snapshot_id field is required.".to_string()),
+            };
+        };
+
+        let result = management_canister_client
+            .load_canister_snapshot(LoadCanisterSnapshotArgs {
+                canister_id,
+                snapshot_id,
+                sender_canister_version: management_canister_client.canister_version(),
+            })
+            .await;
+
+        match result {
+            Ok(()) => {
+                log!(INFO, "Loaded a snapshot into canister {canister_id}");
+                LoadDappCanisterSnapshotResponse {
+                    failure_reason: None,
+                }
+            }
+            Err(err) => {
+                let failure_reason =
+                    format!("Failed to load a snapshot into canister {canister_id}: {err:?}");
+                log!(ERROR, "{failure_reason}");
+                LoadDappCanisterSnapshotResponse {
+                    failure_reason: Some(failure_reason),
+                }
+            }
+        }
+    }
+
+    /// Returns `canister_id` if it is set and refers to a registered dapp canister.
+    fn registered_dapp_canister_id(
+        self_ref: &'static LocalKey<RefCell<Self>>,
+        canister_id: Option<PrincipalId>,
+    ) -> Result<PrincipalId, String> {
+        let canister_id =
+            canister_id.ok_or_else(|| "The canister_id field is required.".to_string())?;
+
+        let is_registered =
+            self_ref.with(|self_ref| self_ref.borrow().dapp_canister_ids.contains(&canister_id));
+        if !is_registered {
+            return Err(format!(
+                "Canister {canister_id} is not a registered dapp canister."
+            ));
+        }
+
+        Ok(canister_id)
+    }
+
     /// Runs periodic tasks that are not directly triggered by user input.
     pub async fn heartbeat(
         self_ref: &'static LocalKey<RefCell<Self>>,
@@ -994,6 +1118,7 @@ mod tests {
     use super::*;
     use crate::pb::v1::{set_dapp_controllers_request::CanisterIds, ListSnsCanistersResponse};
     use ic_nervous_system_clients::{
+        canister_snapshot::CanisterSnapshot,
         canister_status::CanisterStatusResultFromManagementCanister,
         management_canister_client::{
             MockManagementCanisterClient, MockManagementCanisterClientCall,
@@ -2374,6 +2499,154 @@ mod tests {
         assert!(failure_reason.contains(&PrincipalId::new_user_test_id(4).to_string()));
     }
 
+    #[tokio::test]
+    async fn test_take_dapp_canister_snapshot() {
+        // Step 1: Prepare the world.
+        thread_local! {
+            static STATE: RefCell<SnsRootCanister> = RefCell::new(SnsRootCanister {
+                governance_canister_id: Some(PrincipalId::new_user_test_id(1)),
+                dapp_canister_ids: vec![PrincipalId::new_user_test_id(3)],
+                ..Default::default()
+            });
+        }
+        let dapp_canister_id = PrincipalId::new_user_test_id(3);
+
+        let management_canister_client = MockManagementCanisterClient::new(vec![
+            MockManagementCanisterClientReply::TakeCanisterSnapshot(Ok(CanisterSnapshot {
+                id: vec![42],
+                taken_at_timestamp: NOW,
+                total_size: 1 << 20,
+            })),
+        ]);
+
+        // Step 2: Run code under test.
+        let response = SnsRootCanister::take_dapp_canister_snapshot(
+            &STATE,
+            &management_canister_client,
+            TakeDappCanisterSnapshotRequest {
+                canister_id: Some(dapp_canister_id),
+                replace_snapshot: Some(vec![41]),
+            },
+        )
+        .await;
+
+        // Step 3: Inspect results.
+        assert_eq!(
+            response,
+            TakeDappCanisterSnapshotResponse {
+                snapshot_id: Some(vec![42]),
+                failure_reason: None,
+            }
+        );
+        assert_eq!(
+            management_canister_client.get_calls_snapshot(),
+            vec![MockManagementCanisterClientCall::TakeCanisterSnapshot(
+                TakeCanisterSnapshotArgs {
+                    canister_id: dapp_canister_id,
+                    replace_snapshot: Some(vec![41]),
+                }
+            )]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_take_dapp_canister_snapshot_rejects_unregistered_canister() {
+        // Step 1: Prepare the world.
+        thread_local! {
+            static STATE: RefCell<SnsRootCanister> = RefCell::new(SnsRootCanister {
+                governance_canister_id: Some(PrincipalId::new_user_test_id(1)),
+                dapp_canister_ids: vec![PrincipalId::new_user_test_id(3)],
+                ..Default::default()
+            });
+        }
+        let management_canister_client = MockManagementCanisterClient::new(vec![]);
+
+        // Step 2: Run code under test.
+        let response = SnsRootCanister::take_dapp_canister_snapshot(
+            &STATE,
+            &management_canister_client,
+            TakeDappCanisterSnapshotRequest {
+                canister_id: Some(PrincipalId::new_user_test_id(4)),
+                replace_snapshot: None,
+            },
+        )
+        .await;
+
+        // Step 3: Inspect results.
+        assert_eq!(response.snapshot_id, None);
+        let failure_reason = response.failure_reason.unwrap();
+        assert!(
+            failure_reason.contains("not a registered dapp canister"),
+            "{failure_reason}"
+        );
+        assert_eq!(management_canister_client.get_calls_snapshot(), vec![]);
+    }
+
+    #[tokio::test]
+    async fn test_load_dapp_canister_snapshot() {
+        // Step 1: Prepare the world.
+        thread_local! {
+            static STATE: RefCell<SnsRootCanister> = RefCell::new(SnsRootCanister {
+                governance_canister_id: Some(PrincipalId::new_user_test_id(1)),
+                dapp_canister_ids: vec![PrincipalId::new_user_test_id(3)],
+                ..Default::default()
+            });
+        }
+        let dapp_canister_id = PrincipalId::new_user_test_id(3);
+
+        let management_canister_client = MockManagementCanisterClient::new(vec![
+            MockManagementCanisterClientReply::LoadCanisterSnapshot(Err((
+                5,
+                "Snapshot not found".to_string(),
+            ))),
+            MockManagementCanisterClientReply::LoadCanisterSnapshot(Ok(())),
+        ]);
+
+        // Step 2 & 3: Run code under test and inspect results. The first call fails, the second
+        // succeeds.
+        let management_canister_client_ref = &management_canister_client;
+        let load = move |snapshot_id| {
+            SnsRootCanister::load_dapp_canister_snapshot(
+                &STATE,
+                management_canister_client_ref,
+                LoadDappCanisterSnapshotRequest {
+                    canister_id: Some(dapp_canister_id),
+                    snapshot_id,
+                },
+            )
+        };
+
+        let failure_reason = load(Some(vec![41])).await.failure_reason.unwrap();
+        assert!(
+            failure_reason.contains("Snapshot not found"),
+            "{failure_reason}"
+        );
+        assert_eq!(
+            load(Some(vec![42])).await,
+            LoadDappCanisterSnapshotResponse {
+                failure_reason: None
+            }
+        );
+        // Missing snapshot IDs are rejected without calling the management canister.
+        assert!(load(None).await.failure_reason.is_some());
+
+        assert_eq!(
+            management_canister_client.get_calls_snapshot(),
+            vec![
+                MockManagementCanisterClientCall::LoadCanisterSnapshot(LoadCanisterSnapshotArgs {
+                    canister_id: dapp_canister_id,
+                    snapshot_id: vec![41],
+                    sender_canister_version: None,
+                }),
+                MockManagementCanisterClientCall::LoadCanisterSnapshot(LoadCanisterSnapshotArgs {
+                    canister_id: dapp_canister_id,
+                    snapshot_id: vec![42],
+                    sender_canister_version: None,
+                }),
+            ]
+        );
+    }
+
     #[test]
     fn test_list_sns_canisters() {
         let state = SnsRootCanister {
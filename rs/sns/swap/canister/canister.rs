@@ -32,9 +32,10 @@ use ic_sns_swap::{
         ListCommunityFundParticipantsResponse, ListDirectParticipantsRequest,
         ListDirectParticipantsResponse, ListSnsNeuronRecipesRequest, ListSnsNeuronRecipesResponse,
         NewSaleTicketRequest, NewSaleTicketResponse, NotifyPaymentFailureRequest,
-        NotifyPaymentFailureResponse, OpenRequest, OpenResponse, RefreshBuyerTokensRequest,
-        RefreshBuyerTokensResponse, RestoreDappControllersRequest, RestoreDappControllersResponse,
-        Swap,
+        NotifyPaymentFailureResponse, OpenRequest, OpenResponse, OpenSaleRoundRequest,
+        OpenSaleRoundResponse, RefreshBuyerTokensRequest, RefreshBuyerTokensResponse,
+        RefundSaleRoundTokensRequest, RefundSaleRoundTokensResponse, RestoreDappControllersRequest,
+        RestoreDappControllersResponse, Swap,
     },
 };
 use ic_stable_structures::{writer::Writer, Memory};
@@ -157,6 +158,53 @@ async fn open_(req: OpenRequest) -> OpenResponse {
     }
 }
 
+/// Open a follow-on sale round. Only callable by SNS Governance.
+///
+/// See Swap.open_sale_round.
+#[export_name = "canister_update open_sale_round"]
+fn open_sale_round() {
+    over_async(candid_one, open_sale_round_)
+}
+
+/// See `open_sale_round`.
+#[candid_method(update, rename = "open_sale_round")]
+async fn open_sale_round_(req: OpenSaleRoundRequest) -> OpenSaleRoundResponse {
+    log!(INFO, "open_sale_round");
+    let sns_ledger = create_real_icrc1_ledger(swap().init_or_panic().sns_ledger_or_panic());
+    match swap_mut()
+        .open_sale_round(caller(), id(), &sns_ledger, now_seconds(), req)
+        .await
+    {
+        Ok(res) => res,
+        Err(msg) => panic!("{}", msg),
+    }
+}
+
+/// Return the SNS tokens of a follow-on sale round that was not opened to the
+/// SNS token treasury. Only callable by SNS Governance.
+///
+/// See Swap.refund_sale_round_tokens.
+#[export_name = "canister_update refund_sale_round_tokens"]
+fn refund_sale_round_tokens() {
+    over_async(candid_one, refund_sale_round_tokens_)
+}
+
+/// See `refund_sale_round_tokens`.
+#[candid_method(update, rename = "refund_sale_round_tokens")]
+async fn refund_sale_round_tokens_(
+    req: RefundSaleRoundTokensRequest,
+) -> RefundSaleRoundTokensResponse {
+    log!(INFO, "refund_sale_round_tokens");
+    let sns_ledger = create_real_icrc1_ledger(swap().init_or_panic().sns_ledger_or_panic());
+    match swap()
+        .refund_sale_round_tokens(caller(), &sns_ledger, req)
+        .await
+    {
+        Ok(res) => res,
+        Err(msg) => panic!("{}", msg),
+    }
+}
+
 /// See `Swap.refresh_buyer_token_e8`.
 #[export_name = "canister_update refresh_buyer_tokens"]
 fn refresh_buyer_tokens() {
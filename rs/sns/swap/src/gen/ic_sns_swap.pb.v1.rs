@@ -142,6 +142,26 @@
 /// - On reject of proposal to open decentralization swap:
 ///    - Assign the control of the dapp (now under the SNS control) back to the
 ///      specified principals.
+///
+/// Follow-on sale rounds.
+///
+/// Once the decentralization swap has been committed and finalized, the SNS
+/// can raise more funds by adopting an `OpenSaleRound` proposal. Its execution
+/// transfers the offered SNS tokens from the SNS token treasury to the swap
+/// canister and calls `open_sale_round`, which records a summary of the
+/// previous round in `previous_sale_rounds`, resets the per-round state
+/// (`params`, `buyers`, `neuron_recipes`, ...) and moves the swap back to
+/// ADOPTED. If the swap canister does not open the round, SNS governance asks
+/// it to return the transferred tokens via `refund_sale_round_tokens`. From there, the round goes through the same lifecycle as the
+/// decentralization swap, with the following differences:
+///
+/// - The Neurons' Fund does not participate.
+/// - If the round is aborted, the control of the dapp is not changed. Instead,
+///    the SNS tokens offered in the round are returned to the SNS token treasury.
+///
+/// A new round can only be opened once the previous one has been finalized.
+/// SNS neurons whose claims failed do not prevent this; claiming them is
+/// retried when the next round is finalized.
 #[derive(candid::CandidType, candid::Deserialize, serde::Serialize, comparable::Comparable)]
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -224,6 +244,58 @@ pub struct Swap {
     /// Amount of contributions from the Neurons' Fund committed to this SNS so far.
     #[prost(uint64, optional, tag = "20")]
     pub neurons_fund_participation_icp_e8s: ::core::option::Option<u64>,
+    /// The number of the current sale round. The decentralization swap is round 0
+    /// (or unset); follow-on sale rounds are numbered from 1.
+    #[prost(uint64, optional, tag = "22")]
+    pub sale_round_number: ::core::option::Option<u64>,
+    /// The ID of the SNS governance proposal that opened the current follow-on
+    /// sale round. Unset for the decentralization swap.
+    #[prost(uint64, optional, tag = "23")]
+    pub sale_round_sns_governance_proposal_id: ::core::option::Option<u64>,
+    /// Set when an aborted follow-on sale round is finalized, and tracks the
+    /// transfer of the SNS tokens offered in the round back to the SNS token
+    /// treasury.
+    #[prost(message, optional, tag = "24")]
+    pub sns_token_treasury_refund: ::core::option::Option<TransferableAmount>,
+    /// Summaries of all sale rounds that have ended before the current one, in
+    /// the order in which they took place.
+    #[prost(message, repeated, tag = "25")]
+    pub previous_sale_rounds: ::prost::alloc::vec::Vec<SaleRound>,
+}
+/// A summary of a sale round that has ended (i.e., was committed or aborted)
+/// and has been finalized.
+#[derive(candid::CandidType, candid::Deserialize, serde::Serialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SaleRound {
+    /// The number of the round. The decentralization swap is round 0.
+    #[prost(uint64, optional, tag = "1")]
+    pub round_number: ::core::option::Option<u64>,
+    /// The ID of the SNS governance proposal that opened the round. Unset for the
+    /// decentralization swap.
+    #[prost(uint64, optional, tag = "2")]
+    pub sns_governance_proposal_id: ::core::option::Option<u64>,
+    /// Either LIFECYCLE_COMMITTED or LIFECYCLE_ABORTED.
+    #[prost(enumeration = "Lifecycle", optional, tag = "3")]
+    pub lifecycle: ::core::option::Option<i32>,
+    /// The parameters of the round.
+    #[prost(message, optional, tag = "4")]
+    pub params: ::core::option::Option<Params>,
+    /// When the round opened.
+    #[prost(uint64, optional, tag = "5")]
+    pub open_timestamp_seconds: ::core::option::Option<u64>,
+    /// When the round was committed or aborted.
+    #[prost(uint64, optional, tag = "6")]
+    pub termination_timestamp_seconds: ::core::option::Option<u64>,
+    /// The number of direct participants in the round.
+    #[prost(uint64, optional, tag = "7")]
+    pub direct_participant_count: ::core::option::Option<u64>,
+    /// The amount of ICP contributed by direct participants.
+    #[prost(uint64, optional, tag = "8")]
+    pub direct_participation_icp_e8s: ::core::option::Option<u64>,
+    /// The amount of ICP contributed by the Neurons' Fund.
+    #[prost(uint64, optional, tag = "9")]
+    pub neurons_fund_participation_icp_e8s: ::core::option::Option<u64>,
 }
 /// The initialisation data of the canister. Always specified on
 /// canister creation, and cannot be modified afterwards.
@@ -829,6 +901,47 @@ pub struct OpenRequest {
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct OpenResponse {}
+/// Request to open a follow-on sale round. Can only be called by the SNS
+/// governance canister, once the previous round has been finalized.
+#[derive(candid::CandidType, candid::Deserialize, serde::Serialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct OpenSaleRoundRequest {
+    /// The parameters of the new round. The SNS tokens being offered must
+    /// already be held by the swap canister.
+    #[prost(message, optional, tag = "1")]
+    pub params: ::core::option::Option<Params>,
+    /// The ID of the SNS governance proposal that opens the round.
+    #[prost(uint64, optional, tag = "2")]
+    pub sns_governance_proposal_id: ::core::option::Option<u64>,
+}
+#[derive(candid::CandidType, candid::Deserialize, serde::Serialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct OpenSaleRoundResponse {}
+/// Request to return SNS tokens that the SNS governance canister transferred to
+/// the swap canister for a sale round that was not opened to the SNS token
+/// treasury. Can only be called by the SNS governance canister.
+#[derive(candid::CandidType, candid::Deserialize, serde::Serialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RefundSaleRoundTokensRequest {
+    /// The ID of the SNS governance proposal whose round was not opened.
+    #[prost(uint64, optional, tag = "1")]
+    pub sns_governance_proposal_id: ::core::option::Option<u64>,
+    /// The amount that was transferred to the swap canister. The transaction fee
+    /// is deducted from it.
+    #[prost(uint64, optional, tag = "2")]
+    pub amount_e8s: ::core::option::Option<u64>,
+}
+#[derive(candid::CandidType, candid::Deserialize, serde::Serialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RefundSaleRoundTokensResponse {
+    /// The index of the block of the transfer to the SNS token treasury.
+    #[prost(uint64, optional, tag = "1")]
+    pub block_index: ::core::option::Option<u64>,
+}
 #[derive(candid::CandidType, candid::Deserialize, serde::Serialize, comparable::Comparable)]
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -975,6 +1088,10 @@ pub struct FinalizeSwapResponse {
     #[prost(message, optional, tag = "9")]
     pub settle_neurons_fund_participation_result:
         ::core::option::Option<SettleNeuronsFundParticipationResult>,
+    /// Only set when finalizing an aborted follow-on sale round, in which case
+    /// the SNS tokens offered in the round are returned to the SNS token treasury.
+    #[prost(message, optional, tag = "10")]
+    pub refund_sns_token_treasury_result: ::core::option::Option<SweepResult>,
     /// Explains what (if anything) went wrong.
     #[prost(string, optional, tag = "7")]
     pub error_message: ::core::option::Option<::prost::alloc::string::String>,
@@ -22,12 +22,14 @@ use crate::{
         ListDirectParticipantsRequest, ListDirectParticipantsResponse, ListSnsNeuronRecipesRequest,
         ListSnsNeuronRecipesResponse, NeuronBasketConstructionParameters, NeuronId as SaleNeuronId,
         NewSaleTicketRequest, NewSaleTicketResponse, NotifyPaymentFailureResponse, OpenRequest,
-        OpenResponse, Participant, RefreshBuyerTokensResponse, RestoreDappControllersResponse,
-        SetDappControllersCallResult, SetDappControllersRequest, SetDappControllersResponse,
-        SetModeCallResult, SettleCommunityFundParticipation,
-        SettleCommunityFundParticipationResult, SettleNeuronsFundParticipationRequest,
-        SettleNeuronsFundParticipationResponse, SettleNeuronsFundParticipationResult,
-        SnsNeuronRecipe, Swap, SweepResult, Ticket, TransferableAmount,
+        OpenResponse, OpenSaleRoundRequest, OpenSaleRoundResponse, Participant,
+        RefreshBuyerTokensResponse, RefundSaleRoundTokensRequest, RefundSaleRoundTokensResponse,
+        RestoreDappControllersResponse, SaleRound, SetDappControllersCallResult,
+        SetDappControllersRequest, SetDappControllersResponse, SetModeCallResult,
+        SettleCommunityFundParticipation, SettleCommunityFundParticipationResult,
+        SettleNeuronsFundParticipationRequest, SettleNeuronsFundParticipationResponse,
+        SettleNeuronsFundParticipationResult, SnsNeuronRecipe, Swap, SweepResult, Ticket,
+        TransferableAmount,
     },
     types::{NeuronsFundNeuron, ScheduledVestingEvent, TransferResult},
 };
@@ -35,9 +37,13 @@ use dfn_core::CanisterId;
 use ic_base_types::PrincipalId;
 use ic_canister_log::log;
 use ic_ledger_core::Tokens;
-use ic_nervous_system_common::{i2d, ledger::compute_neuron_staking_subaccount_bytes};
+use ic_nervous_system_common::{
+    i2d,
+    ledger::{compute_distribution_subaccount_bytes, compute_neuron_staking_subaccount_bytes},
+};
 use ic_neurons_fund::{MatchedParticipationFunction, PolynomialNeuronsFundParticipation};
 use ic_sns_governance::{
+    governance::TREASURY_SUBACCOUNT_NONCE,
     ledger::ICRC1Ledger,
     pb::v1::{
         claim_swap_neurons_request::NeuronParameters,
@@ -89,6 +95,16 @@ const DEFAULT_LIST_SNS_NEURON_RECIPES_LIMIT: u32 = 10_000;
 pub const NEURON_BASKET_MEMO_RANGE_START: u64 = 1_000_000;
 pub const SALE_NEURON_MEMO_RANGE_END: u64 = 10_000_000;
 
+/// The size of the sub-range of the sale neuron memos that is reserved for the direct participants
+/// of each sale round. Round `n` uses the memos starting from
+/// `NEURON_BASKET_MEMO_RANGE_START + n * SALE_ROUND_MEMO_RANGE_SIZE`, so that the neurons of a
+/// principal participating in several rounds have distinct ids.
+pub const SALE_ROUND_MEMO_RANGE_SIZE: u64 = 10_000;
+
+/// The maximum number of open tickets, and of entries of the BUYERS_LIST_INDEX, of the previous
+/// sale round that are removed per heartbeat while a follow-on sale round waits to open.
+const MAX_SALE_ROUND_ENTRIES_TO_CLEAR_PER_HEARTBEAT: u64 = 10_000;
+
 /// The principal with all bytes set to zero. The main property
 /// of this principal is that for any principal p, the following condition holds:
 /// (p != FIRST_PRINCIPAL_BYTES) ==> FIRST_PRINCIPAL_BYTES.as_slice() < p.as_slice()
@@ -458,6 +474,10 @@ impl Swap {
             auto_finalize_swap_response: None,
             direct_participation_icp_e8s: None,
             neurons_fund_participation_icp_e8s: None,
+            sale_round_number: None,
+            sale_round_sns_governance_proposal_id: None,
+            sns_token_treasury_refund: None,
+            previous_sale_rounds: vec![],
         };
         if init.validate_swap_init_for_one_proposal_flow().is_ok() {
             // Automatically fill out the fields that the (legacy) open request
@@ -583,6 +603,12 @@ impl Swap {
             .fold(0_u64, |sum, v| sum.saturating_add(v));
         self.direct_participation_icp_e8s = Some(direct_participation_icp_e8s);
 
+        // The Neurons' Fund only participates in the decentralization swap.
+        if self.is_follow_on_sale_round() {
+            self.neurons_fund_participation_icp_e8s = Some(0);
+            return;
+        }
+
         let (neurons_fund_participation, neurons_fund_participation_constraints) =
             if let Some(init) = &self.init {
                 (
@@ -695,6 +721,22 @@ impl Swap {
             .count() as u64
     }
 
+    /// The number of the current sale round. The decentralization swap is round 0.
+    pub fn sale_round_number(&self) -> u64 {
+        self.sale_round_number.unwrap_or(0)
+    }
+
+    /// Whether the current sale round is a follow-on sale round, i.e., a round that
+    /// was opened by an SNS governance proposal after the decentralization swap.
+    pub fn is_follow_on_sale_round(&self) -> bool {
+        self.sale_round_number() > 0
+    }
+
+    /// The first memo of the neurons of direct participants in the current sale round.
+    fn sale_round_memo_offset(&self) -> Result<u64, String> {
+        sale_round_memo_range_start(self.sale_round_number())
+    }
+
     /// Determines if the Swap is in it's terminal state
     /// based on it's lifecycle.
     fn lifecycle_is_terminal(&self) -> bool {
@@ -779,6 +821,206 @@ impl Swap {
         )
     }
 
+    /// Opens a follow-on sale round. Only callable by SNS Governance, as part of
+    /// the execution of an `OpenSaleRound` proposal.
+    ///
+    /// The previous sale round (which might be the decentralization swap) must
+    /// have been finalized (see `can_open_sale_round`). A summary of it is
+    /// recorded in `previous_sale_rounds`, the per-round state is reset, and the
+    /// Swap transitions to `Lifecycle::Adopted`, so that the new round opens
+    /// after `params.sale_delay_seconds`. From there on, the round goes through
+    /// the same lifecycle as the decentralization swap.
+    ///
+    /// Neuron recipes whose SNS neurons could not be claimed yet are kept, so
+    /// that claiming them is retried when the new round is finalized. The open
+    /// tickets and the BUYERS_LIST_INDEX of the previous round are cleared in
+    /// batches by `heartbeat`, and the round only opens once they are empty.
+    ///
+    /// The SNS tokens offered in the round (`params.sns_token_e8s`) must already
+    /// be held by the Swap canister.
+    pub async fn open_sale_round(
+        &mut self,
+        caller: PrincipalId,
+        this_canister: CanisterId,
+        sns_ledger: &dyn ICRC1Ledger,
+        now_seconds: u64,
+        request: OpenSaleRoundRequest,
+    ) -> Result<OpenSaleRoundResponse, String> {
+        // Require authorization.
+        let init = self.init_and_validate()?;
+        let sns_governance = init.sns_governance_or_panic();
+        if caller != sns_governance.get() {
+            return Err(format!(
+                "This method can only be called by SNS Governance ({}). Current caller is {}.",
+                sns_governance, caller,
+            ));
+        }
+
+        request.validate(now_seconds, init)?;
+        let next_sale_round_number = self.sale_round_number().saturating_add(1);
+        sale_round_memo_range_start(next_sale_round_number)?;
+        self.can_open_sale_round()?;
+
+        let OpenSaleRoundRequest {
+            params,
+            sns_governance_proposal_id,
+        } = request;
+        // Safe to unwrap, as the request was validated above.
+        let params = params.expect("Expected params to be set");
+
+        // Check that the SNS tokens that are offered in the round are available.
+        let sns_token_balance_e8s = sns_ledger
+            .account_balance(Account {
+                owner: this_canister.get().0,
+                subaccount: None,
+            })
+            .await
+            .map_err(|err| {
+                format!("Unable to determine the SNS token balance of the Swap canister: {err}")
+            })?
+            .get_e8s();
+        if sns_token_balance_e8s < params.sns_token_e8s {
+            return Err(format!(
+                "The Swap canister holds {} SNS e8s, but the sale round offers {} SNS e8s.",
+                sns_token_balance_e8s, params.sns_token_e8s,
+            ));
+        }
+
+        // The state might have changed while awaiting the SNS ledger's response.
+        self.can_open_sale_round()?;
+
+        let previous_sale_round = SaleRound {
+            round_number: Some(self.sale_round_number()),
+            sns_governance_proposal_id: self.sale_round_sns_governance_proposal_id,
+            lifecycle: Some(self.lifecycle),
+            params: self.params.take(),
+            open_timestamp_seconds: self.decentralization_sale_open_timestamp_seconds,
+            termination_timestamp_seconds: self.decentralization_swap_termination_timestamp_seconds,
+            direct_participant_count: Some(self.buyers.len() as u64),
+            direct_participation_icp_e8s: Some(self.current_direct_participation_e8s()),
+            neurons_fund_participation_icp_e8s: Some(self.current_neurons_fund_participation_e8s()),
+        };
+        log!(
+            INFO,
+            "Opening sale round {} (proposal {:?}). Previous sale round: {:?}",
+            next_sale_round_number,
+            sns_governance_proposal_id,
+            previous_sale_round,
+        );
+        self.previous_sale_rounds.push(previous_sale_round);
+
+        // Reset the per-round state.
+        self.buyers.clear();
+        self.cf_participants.clear();
+        self.neuron_recipes
+            .retain(|recipe| recipe.claimed_status != Some(ClaimedStatus::Success as i32));
+        self.direct_participation_icp_e8s = None;
+        self.neurons_fund_participation_icp_e8s = None;
+        self.sns_token_treasury_refund = None;
+        self.already_tried_to_auto_finalize = Some(false);
+        self.auto_finalize_swap_response = None;
+        self.decentralization_swap_termination_timestamp_seconds = None;
+
+        self.decentralization_sale_open_timestamp_seconds =
+            Some(now_seconds.saturating_add(params.sale_delay_seconds.unwrap_or(0)));
+        self.params = Some(params);
+        self.sale_round_number = Some(next_sale_round_number);
+        self.sale_round_sns_governance_proposal_id = sns_governance_proposal_id;
+        self.set_lifecycle(Lifecycle::Adopted);
+
+        Ok(OpenSaleRoundResponse {})
+    }
+
+    /// Returns the SNS tokens that SNS Governance transferred to the Swap canister
+    /// for a follow-on sale round that was not opened (e.g., because
+    /// `open_sale_round` failed) to the SNS token treasury. Only callable by SNS
+    /// Governance, as part of the execution of an `OpenSaleRound` proposal.
+    ///
+    /// Nothing is returned for a round that was opened. The transaction fee is
+    /// deducted from `request.amount_e8s`.
+    pub async fn refund_sale_round_tokens(
+        &self,
+        caller: PrincipalId,
+        sns_ledger: &dyn ICRC1Ledger,
+        request: RefundSaleRoundTokensRequest,
+    ) -> Result<RefundSaleRoundTokensResponse, String> {
+        // Require authorization.
+        let init = self.init_and_validate()?;
+        let sns_governance = init.sns_governance_or_panic();
+        if caller != sns_governance.get() {
+            return Err(format!(
+                "This method can only be called by SNS Governance ({}). Current caller is {}.",
+                sns_governance, caller,
+            ));
+        }
+
+        let RefundSaleRoundTokensRequest {
+            sns_governance_proposal_id,
+            amount_e8s,
+        } = request;
+        let sns_governance_proposal_id = sns_governance_proposal_id
+            .ok_or_else(|| "The sns_governance_proposal_id field has no value.".to_string())?;
+        let amount_e8s = amount_e8s.unwrap_or_default();
+
+        let was_opened = self.sale_round_sns_governance_proposal_id
+            == Some(sns_governance_proposal_id)
+            || self.previous_sale_rounds.iter().any(|sale_round| {
+                sale_round.sns_governance_proposal_id == Some(sns_governance_proposal_id)
+            });
+        if was_opened {
+            return Err(format!(
+                "The sale round of proposal {} was opened, so its SNS tokens are not returned.",
+                sns_governance_proposal_id,
+            ));
+        }
+
+        let sns_transaction_fee_e8s = init.transaction_fee_e8s_or_panic();
+        let refund_e8s = amount_e8s
+            .checked_sub(sns_transaction_fee_e8s)
+            .filter(|refund_e8s| *refund_e8s > 0)
+            .ok_or_else(|| {
+                format!(
+                    "amount_e8s ({}) must exceed the transaction fee ({}).",
+                    amount_e8s, sns_transaction_fee_e8s,
+                )
+            })?;
+
+        let dst = Account {
+            owner: sns_governance.get().0,
+            subaccount: Some(compute_distribution_subaccount_bytes(
+                sns_governance.get(),
+                TREASURY_SUBACCOUNT_NONCE,
+            )),
+        };
+        let block_index = sns_ledger
+            .transfer_funds(
+                refund_e8s,
+                sns_transaction_fee_e8s,
+                /* from_subaccount= */ None,
+                dst,
+                sns_governance_proposal_id,
+            )
+            .await
+            .map_err(|err| {
+                format!(
+                    "Unable to return {} SNS e8s to the SNS token treasury: {}",
+                    refund_e8s, err,
+                )
+            })?;
+        log!(
+            INFO,
+            "Returned {} SNS e8s of the sale round of proposal {} to the SNS token treasury \
+            (block {}).",
+            refund_e8s,
+            sns_governance_proposal_id,
+            block_index,
+        );
+
+        Ok(RefundSaleRoundTokensResponse {
+            block_index: Some(block_index),
+        })
+    }
+
     /// Computes `amount_icp_e8s` scaled by (`total_sns_e8s` divided by
     /// `total_icp_e8s`), but perform the computation in integer space
     /// by computing `(amount_icp_e8s * total_sns_e8s) /
@@ -877,6 +1119,19 @@ impl Swap {
         // is correct at the end.
         let mut total_sns_tokens_sold_e8s: u64 = 0;
 
+        // Each sale round uses its own range of memos for the neurons of direct participants.
+        let direct_participant_memo_offset = match self.sale_round_memo_offset() {
+            Ok(memo_offset) => memo_offset,
+            Err(error_message) => {
+                log!(
+                    ERROR,
+                    "Halting create_sns_neuron_recipes(). {}",
+                    error_message
+                );
+                return SweepResult::new_with_global_failures(1);
+            }
+        };
+
         // =====================================================================
         // ===            This is where the actual swap happens              ===
         // =====================================================================
@@ -903,7 +1158,7 @@ impl Swap {
                 &parsed_principal,
                 amount_sns_e8s,
                 neuron_basket_construction_parameters,
-                NEURON_BASKET_MEMO_RANGE_START,
+                direct_participant_memo_offset,
             ) {
                 Ok(direct_participant_sns_neuron_recipes) => {
                     self.neuron_recipes
@@ -1058,6 +1313,13 @@ impl Swap {
             MAX_NUMBER_OF_PRINCIPALS_TO_INSPECT,
         );
 
+        // Clear the open tickets and the BUYERS_LIST_INDEX of the previous sale round
+        // before a follow-on sale round opens.
+        if self.lifecycle() == Lifecycle::Adopted && self.is_follow_on_sale_round() {
+            clear_open_tickets(MAX_SALE_ROUND_ENTRIES_TO_CLEAR_PER_HEARTBEAT);
+            clear_buyers_list_index(MAX_SALE_ROUND_ENTRIES_TO_CLEAR_PER_HEARTBEAT);
+        }
+
         // Automatically transition the state. Only one state transition per heartbeat.
 
         // Auto-open the swap
@@ -1389,8 +1651,17 @@ impl Swap {
     /// Determines if the conditions have been met in order to
     /// restore the dapp canisters to the fallback controller ids.
     /// The lifecycle MUST be set to Aborted via the commit method.
+    ///
+    /// Aborting a follow-on sale round does not affect the control of the dapp.
     pub fn should_restore_dapp_control(&self) -> bool {
-        self.lifecycle() == Lifecycle::Aborted
+        self.lifecycle() == Lifecycle::Aborted && !self.is_follow_on_sale_round()
+    }
+
+    /// Determines if the SNS tokens offered in the current sale round should be
+    /// returned to the SNS token treasury, which is the case when a follow-on
+    /// sale round is aborted.
+    pub fn should_refund_sns_token_treasury(&self) -> bool {
+        self.lifecycle() == Lifecycle::Aborted && self.is_follow_on_sale_round()
     }
 
     /// Calls SNS Root with the Swap canister's configured
@@ -1563,13 +1834,29 @@ impl Swap {
             return finalize_swap_response;
         }
 
-        // Settle the Neurons' Fund participation in the token swap.
-        self.settle_fund_participation(
-            environment.nns_governance_mut(),
-            &mut finalize_swap_response,
-        )
-        .await;
-        if finalize_swap_response.has_error_message() {
+        // Settle the Neurons' Fund participation in the token swap. The Neurons'
+        // Fund does not participate in follow-on sale rounds.
+        if !self.is_follow_on_sale_round() {
+            self.settle_fund_participation(
+                environment.nns_governance_mut(),
+                &mut finalize_swap_response,
+            )
+            .await;
+            if finalize_swap_response.has_error_message() {
+                return finalize_swap_response;
+            }
+        }
+
+        if self.should_refund_sns_token_treasury() {
+            // Return the SNS tokens that were offered in the aborted round to the
+            // SNS token treasury.
+            finalize_swap_response.set_refund_sns_token_treasury_result(
+                self.refund_sns_token_treasury(now_fn, environment.sns_ledger())
+                    .await,
+            );
+
+            // As with restoring the dapp controllers below, there is nothing more
+            // to do for an aborted round.
             return finalize_swap_response;
         }
 
@@ -2414,6 +2701,109 @@ impl Swap {
         sweep_result
     }
 
+    /// In state ABORTED of a follow-on sale round. Transfers the SNS tokens that
+    /// were offered in the round back to the SNS token treasury (a subaccount of
+    /// the SNS Governance canister).
+    ///
+    /// The transfer is recorded in `sns_token_treasury_refund`, so that the
+    /// tokens are returned at most once, no matter how often this is called.
+    pub async fn refund_sns_token_treasury(
+        &mut self,
+        now_fn: fn(bool) -> u64,
+        sns_ledger: &dyn ICRC1Ledger,
+    ) -> SweepResult {
+        if !self.should_refund_sns_token_treasury() {
+            log!(
+                ERROR,
+                "Halting refund_sns_token_treasury(). SNS tokens can only be returned to the \
+                treasury if a follow-on sale round is ABORTED. Current Lifecycle: {:?}, \
+                sale round: {}",
+                self.lifecycle(),
+                self.sale_round_number(),
+            );
+            return SweepResult::new_with_global_failures(1);
+        }
+
+        let init = match self.init_and_validate() {
+            Ok(init) => init,
+            Err(error_message) => {
+                log!(
+                    ERROR,
+                    "Halting refund_sns_token_treasury(). State is missing or corrupted: {:?}",
+                    error_message
+                );
+                return SweepResult::new_with_global_failures(1);
+            }
+        };
+
+        // The following methods are safe to call since we validated Init in the above block
+        let sns_governance = init.sns_governance_or_panic();
+        let sns_transaction_fee_tokens = Tokens::from_e8s(init.transaction_fee_e8s_or_panic());
+
+        let sns_token_e8s = match self.sns_token_e8s() {
+            Ok(sns_token_e8s) => sns_token_e8s,
+            Err(error_message) => {
+                log!(
+                    ERROR,
+                    "Halting refund_sns_token_treasury(). {}",
+                    error_message
+                );
+                return SweepResult::new_with_global_failures(1);
+            }
+        };
+
+        let dst = Account {
+            owner: sns_governance.get().0,
+            subaccount: Some(compute_distribution_subaccount_bytes(
+                sns_governance.get(),
+                TREASURY_SUBACCOUNT_NONCE,
+            )),
+        };
+
+        let refund = self
+            .sns_token_treasury_refund
+            .get_or_insert_with(|| TransferableAmount {
+                amount_e8s: sns_token_e8s,
+                transfer_start_timestamp_seconds: 0,
+                transfer_success_timestamp_seconds: 0,
+                amount_transferred_e8s: Some(0),
+                transfer_fee_paid_e8s: Some(0),
+            });
+
+        let mut sweep_result = SweepResult::default();
+
+        let result = refund
+            .transfer_helper(
+                now_fn,
+                sns_transaction_fee_tokens,
+                /* src_subaccount= */ None,
+                &dst,
+                sns_ledger,
+            )
+            .await;
+        match result {
+            // Sale rounds always offer more SNS tokens than the transaction fee.
+            TransferResult::AmountTooSmall => {
+                sweep_result.invalid += 1;
+            }
+            TransferResult::AlreadyStarted => {
+                sweep_result.skipped += 1;
+            }
+            TransferResult::Success(_) => {
+                let fee_e8s = sns_transaction_fee_tokens.get_e8s();
+                refund.transfer_fee_paid_e8s = Some(fee_e8s);
+                refund.amount_transferred_e8s = Some(refund.amount_e8s - fee_e8s);
+
+                sweep_result.success += 1;
+            }
+            TransferResult::Failure(_) => {
+                sweep_result.failure += 1;
+            }
+        }
+
+        sweep_result
+    }
+
     pub async fn settle_fund_participation(
         &mut self,
         nns_governance_client: &mut impl NnsGovernanceClient,
@@ -3026,6 +3416,11 @@ impl Swap {
             return false;
         }
 
+        // The state of the previous sale round must have been cleared (see `heartbeat`).
+        if self.is_follow_on_sale_round() && !is_previous_sale_round_stable_state_cleared() {
+            return false;
+        }
+
         let swap_open_timestamp_seconds = self
             .decentralization_sale_open_timestamp_seconds
             .unwrap_or(now_seconds);
@@ -3113,6 +3508,102 @@ impl Swap {
         }
     }
 
+    /// Returns Ok(()) if a follow-on sale round can be opened, and Err(reason) otherwise.
+    ///
+    /// Conditions:
+    /// 1. The lifecycle of Swap is `Lifecycle::Committed`, or `Lifecycle::Aborted` if the
+    ///    current round is a follow-on sale round (an aborted decentralization swap means
+    ///    that the SNS was not created).
+    /// 2. The current round has been finalized, i.e., finalization is not in progress,
+    ///    the ICP of all buyers has been transferred, and either the SNS tokens of all
+    ///    neuron recipes have been transferred (if committed) or the SNS tokens have been
+    ///    returned to the SNS token treasury (if aborted).
+    ///
+    /// SNS neurons that could not be claimed do not prevent opening a new round. Their
+    /// recipes are kept, and claiming them is retried when the new round is finalized.
+    pub fn can_open_sale_round(&self) -> Result<(), String> {
+        match self.lifecycle() {
+            Lifecycle::Committed => (),
+            Lifecycle::Aborted if self.is_follow_on_sale_round() => (),
+            lifecycle => {
+                return Err(format!(
+                    "A new sale round can only be opened after the previous one was committed \
+                    (or aborted, for follow-on sale rounds). Current state is {:?}",
+                    lifecycle
+                ));
+            }
+        }
+
+        if self.is_finalize_swap_locked() {
+            return Err("The previous sale round is being finalized.".to_string());
+        }
+
+        let buyers_with_pending_icp_transfers = self
+            .buyers
+            .values()
+            .filter(|buyer_state| {
+                buyer_state
+                    .icp
+                    .as_ref()
+                    .map(|icp| icp.transfer_success_timestamp_seconds == 0)
+                    .unwrap_or(true)
+            })
+            .count();
+        if buyers_with_pending_icp_transfers > 0 {
+            return Err(format!(
+                "The previous sale round has not been finalized: the ICP of {} buyers has \
+                not been transferred yet.",
+                buyers_with_pending_icp_transfers
+            ));
+        }
+
+        if self.lifecycle() == Lifecycle::Committed {
+            let all_recipes_created = self
+                .buyers
+                .values()
+                .all(|buyer_state| buyer_state.has_created_neuron_recipes == Some(true))
+                && self
+                    .cf_participants
+                    .iter()
+                    .flat_map(|cf_participant| &cf_participant.cf_neurons)
+                    .all(|cf_neuron| cf_neuron.has_created_neuron_recipes == Some(true));
+            let recipes_with_pending_sns_transfers = self
+                .neuron_recipes
+                .iter()
+                .filter(|recipe| {
+                    recipe
+                        .sns
+                        .as_ref()
+                        .map(|sns| sns.transfer_success_timestamp_seconds == 0)
+                        .unwrap_or(true)
+                })
+                .count();
+            if !all_recipes_created || recipes_with_pending_sns_transfers > 0 {
+                return Err(format!(
+                    "The previous sale round has not been finalized: not all SNS neuron \
+                    recipes have been created ({} neuron recipes have not received their SNS \
+                    tokens).",
+                    recipes_with_pending_sns_transfers
+                ));
+            }
+        } else {
+            let refunded = self
+                .sns_token_treasury_refund
+                .as_ref()
+                .map(|refund| refund.transfer_success_timestamp_seconds > 0)
+                .unwrap_or(false);
+            if !refunded {
+                return Err(
+                    "The previous sale round has not been finalized: the SNS tokens have \
+                    not been returned to the SNS token treasury yet."
+                        .to_string(),
+                );
+            }
+        }
+
+        Ok(())
+    }
+
     //
     // --- query methods on the state  -----------------------------------------
     //
@@ -3731,6 +4222,59 @@ fn insert_buyer_into_buyers_list_index(buyer_principal_id: PrincipalId) -> Resul
     memory::BUYERS_LIST_INDEX.with(|buyer_list| buyer_list.borrow_mut().push(&buyer_principal_id))
 }
 
+/// Removes up to `max_count` principals from the BUYERS_LIST_INDEX.
+fn clear_buyers_list_index(max_count: u64) {
+    memory::BUYERS_LIST_INDEX.with(|buyer_list| {
+        let buyer_list = buyer_list.borrow_mut();
+        for _ in 0..max_count {
+            if buyer_list.pop().is_none() {
+                break;
+            }
+        }
+    })
+}
+
+/// Removes up to `max_count` open tickets.
+fn clear_open_tickets(max_count: u64) {
+    memory::OPEN_TICKETS_MEMORY.with(|open_tickets| {
+        let mut open_tickets = open_tickets.borrow_mut();
+        let principals = open_tickets
+            .iter()
+            .take(max_count as usize)
+            .map(|(principal, _ticket)| principal)
+            .collect::<Vec<_>>();
+        for principal in principals {
+            open_tickets.remove(&principal);
+        }
+    })
+}
+
+/// Whether the open tickets and the BUYERS_LIST_INDEX are empty, which is required before a
+/// follow-on sale round opens.
+fn is_previous_sale_round_stable_state_cleared() -> bool {
+    memory::OPEN_TICKETS_MEMORY.with(|open_tickets| open_tickets.borrow().is_empty())
+        && memory::BUYERS_LIST_INDEX.with(|buyer_list| buyer_list.borrow().is_empty())
+}
+
+/// Returns the first memo of the neurons of direct participants in the given sale round, or
+/// an error if the memos of the round would not fit in the range of sale neuron memos.
+fn sale_round_memo_range_start(sale_round_number: u64) -> Result<u64, String> {
+    sale_round_number
+        .checked_mul(SALE_ROUND_MEMO_RANGE_SIZE)
+        .and_then(|offset| offset.checked_add(NEURON_BASKET_MEMO_RANGE_START))
+        .filter(|memo_range_start| {
+            memo_range_start.saturating_add(SALE_ROUND_MEMO_RANGE_SIZE)
+                <= SALE_NEURON_MEMO_RANGE_END
+        })
+        .ok_or_else(|| {
+            format!(
+                "The memos of the neurons of sale round {} would exceed \
+                SALE_NEURON_MEMO_RANGE_END ({}).",
+                sale_round_number, SALE_NEURON_MEMO_RANGE_END,
+            )
+        })
+}
+
 /// A version of Swap that implements a shorter version of Debug, suitable for
 /// logs. Potentially large collection fields are summarized and/or decimated.
 struct SwapDigest<'a> {
@@ -3758,6 +4302,9 @@ impl<'a> fmt::Debug for SwapDigest<'a> {
             purge_old_tickets_next_principal,
             already_tried_to_auto_finalize,
             auto_finalize_swap_response,
+            sale_round_number,
+            sale_round_sns_governance_proposal_id,
+            sns_token_treasury_refund,
 
             // These are (potentially large) collections. To avoid an
             // overwhelmingly large log message, we need summarize and/or
@@ -3767,6 +4314,7 @@ impl<'a> fmt::Debug for SwapDigest<'a> {
             neuron_recipes,
             direct_participation_icp_e8s,
             neurons_fund_participation_icp_e8s,
+            previous_sale_rounds,
         } = self.swap;
 
         formatter
@@ -3801,6 +4349,12 @@ impl<'a> fmt::Debug for SwapDigest<'a> {
                 already_tried_to_auto_finalize,
             )
             .field("auto_finalize_swap_response", auto_finalize_swap_response)
+            .field("sale_round_number", sale_round_number)
+            .field(
+                "sale_round_sns_governance_proposal_id",
+                sale_round_sns_governance_proposal_id,
+            )
+            .field("sns_token_treasury_refund", sns_token_treasury_refund)
             // Summarize and/or decimate (potentially large) collection fields.
             //
             // TODO: Include some samples? E.g. the first, and last element, and
@@ -3816,6 +4370,10 @@ impl<'a> fmt::Debug for SwapDigest<'a> {
                 "neurons_fund_participation_icp_e8s",
                 neurons_fund_participation_icp_e8s,
             )
+            .field(
+                "previous_sale_rounds",
+                &format!("<len={}>", previous_sale_rounds.len()),
+            )
             .finish()
     }
 }
@@ -4536,6 +5094,10 @@ mod tests {
                 auto_finalize_swap_response: None,
                 direct_participation_icp_e8s: None,
                 neurons_fund_participation_icp_e8s: None,
+                sale_round_number: None,
+                sale_round_sns_governance_proposal_id: None,
+                sns_token_treasury_refund: None,
+                previous_sale_rounds: vec![],
             };
             let mut ticket_ids = HashSet::new();
             for pid in pids {
@@ -4801,6 +5363,10 @@ mod tests {
             auto_finalize_swap_response: None,
             direct_participation_icp_e8s: None,
             neurons_fund_participation_icp_e8s: None,
+            sale_round_number: None,
+            sale_round_sns_governance_proposal_id: None,
+            sns_token_treasury_refund: None,
+            previous_sale_rounds: vec![],
         };
 
         let try_purge_old_tickets = |sale: &mut Swap, time: u64| loop {
@@ -5048,4 +5614,27 @@ mod tests {
 
         assert_eq!(7, swap.cf_neuron_count());
     }
+
+    #[test]
+    fn test_sale_round_memo_range_start() {
+        assert_eq!(
+            sale_round_memo_range_start(0),
+            Ok(NEURON_BASKET_MEMO_RANGE_START)
+        );
+        assert_eq!(
+            sale_round_memo_range_start(1),
+            Ok(NEURON_BASKET_MEMO_RANGE_START + SALE_ROUND_MEMO_RANGE_SIZE)
+        );
+
+        // The memos of the last possible sale round end exactly at SALE_NEURON_MEMO_RANGE_END.
+        let last_sale_round_number = (SALE_NEURON_MEMO_RANGE_END - NEURON_BASKET_MEMO_RANGE_START)
+            / SALE_ROUND_MEMO_RANGE_SIZE
+            - 1;
+        assert_eq!(
+            sale_round_memo_range_start(last_sale_round_number),
+            Ok(SALE_NEURON_MEMO_RANGE_END - SALE_ROUND_MEMO_RANGE_SIZE)
+        );
+        assert!(sale_round_memo_range_start(last_sale_round_number + 1).is_err());
+        assert!(sale_round_memo_range_start(u64::MAX).is_err());
+    }
 }
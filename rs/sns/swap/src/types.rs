@@ -9,11 +9,11 @@ use crate::{
         sns_neuron_recipe::{ClaimedStatus, Investor},
         BuyerState, CfInvestment, CfNeuron, CfParticipant, DirectInvestment,
         ErrorRefundIcpResponse, FinalizeSwapResponse, Init, Lifecycle, NeuronId as SaleNeuronId,
-        OpenRequest, Params, SetDappControllersCallResult, SetModeCallResult,
+        OpenRequest, OpenSaleRoundRequest, Params, SetDappControllersCallResult, SetModeCallResult,
         SettleCommunityFundParticipationResult, SettleNeuronsFundParticipationResult,
         SnsNeuronRecipe, SweepResult, TransferableAmount,
     },
-    swap::is_valid_principal,
+    swap::{is_valid_principal, SALE_ROUND_MEMO_RANGE_SIZE},
 };
 use ic_base_types::{CanisterId, PrincipalId};
 use ic_canister_log::log;
@@ -698,6 +698,63 @@ impl OpenRequest {
     }
 }
 
+impl OpenSaleRoundRequest {
+    pub fn validate(&self, current_timestamp_seconds: u64, init: &Init) -> Result<(), String> {
+        let mut defects = vec![];
+
+        // Inspect params.
+        match self.params.as_ref() {
+            None => {
+                defects.push("The parameters of the sale round are missing.".to_string());
+            }
+            Some(params) => {
+                // Fields that `Params::validate` relies on being set (or non-zero).
+                if params.max_icp_e8s == 0 {
+                    defects.push("max_icp_e8s must be > 0".to_string());
+                } else if params.neuron_basket_construction_parameters.is_none() {
+                    defects
+                        .push("neuron_basket_construction_parameters must be provided".to_string());
+                } else if let Err(err) = params.is_valid_if_initiated_at(current_timestamp_seconds)
+                {
+                    defects.push(err);
+                } else if let Err(err) = params.validate(init) {
+                    defects.push(err);
+                }
+
+                // Follow-on sale rounds have no Neurons' Fund participation, so
+                // the direct participation limits are the limits of the round.
+                if params.min_direct_participation_icp_e8s.is_none() {
+                    defects.push("min_direct_participation_icp_e8s is required.".to_string());
+                }
+                if params.max_direct_participation_icp_e8s.is_none() {
+                    defects.push("max_direct_participation_icp_e8s is required.".to_string());
+                }
+
+                if let Some(neuron_basket) = &params.neuron_basket_construction_parameters {
+                    if neuron_basket.count > SALE_ROUND_MEMO_RANGE_SIZE {
+                        defects.push(format!(
+                            "neuron_basket_construction_parameters.count ({}) must be <= {}",
+                            neuron_basket.count, SALE_ROUND_MEMO_RANGE_SIZE,
+                        ));
+                    }
+                }
+            }
+        }
+
+        // Inspect sns_governance_proposal_id.
+        if self.sns_governance_proposal_id.is_none() {
+            defects.push("The sns_governance_proposal_id field has no value.".to_string());
+        }
+
+        // Return result.
+        if defects.is_empty() {
+            Ok(())
+        } else {
+            Err(defects.join("\n"))
+        }
+    }
+}
+
 impl DirectInvestment {
     pub fn validate(&self) -> Result<(), String> {
         if !is_valid_principal(&self.buyer_principal) {
@@ -955,6 +1012,18 @@ impl FinalizeSwapResponse {
             Some(settle_neurons_fund_participation_result);
     }
 
+    pub fn set_refund_sns_token_treasury_result(
+        &mut self,
+        refund_sns_token_treasury_result: SweepResult,
+    ) {
+        if !refund_sns_token_treasury_result.is_successful_sweep() {
+            self.set_error_message(
+                "Returning the SNS tokens to the SNS token treasury did not complete fully. Halting swap finalization".to_string()
+            );
+        }
+        self.refund_sns_token_treasury_result = Some(refund_sns_token_treasury_result);
+    }
+
     pub fn has_error_message(&self) -> bool {
         self.error_message.is_some()
     }
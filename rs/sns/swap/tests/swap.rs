@@ -4,7 +4,7 @@ use crate::common::{
     create_generic_sns_neuron_recipes, create_successful_swap_neuron_basket_for_neurons_fund,
     create_successful_swap_neuron_basket_for_one_direct_participant,
     doubles::{
-        spy_clients, spy_clients_exploding_root, ExplodingSnsRootClient, LedgerExpect,
+        spy_clients, spy_clients_exploding_root, ExplodingSnsRootClient, LedgerExpect, MockLedger,
         NnsGovernanceClientCall, NnsGovernanceClientReply, SnsGovernanceClientCall,
         SnsGovernanceClientReply, SnsRootClientCall, SnsRootClientReply, SpyNnsGovernanceClient,
         SpySnsGovernanceClient, SpySnsRootClient,
@@ -23,7 +23,8 @@ use futures::{channel::mpsc, future::FutureExt, StreamExt};
 use ic_base_types::{CanisterId, PrincipalId};
 use ic_ledger_core::Tokens;
 use ic_nervous_system_common::{
-    assert_is_err, assert_is_ok, ledger::compute_neuron_staking_subaccount_bytes,
+    assert_is_err, assert_is_ok,
+    ledger::{compute_distribution_subaccount_bytes, compute_neuron_staking_subaccount_bytes},
     NervousSystemError, E8, SECONDS_PER_DAY, START_OF_2022_TIMESTAMP_SECONDS,
 };
 use ic_nervous_system_common_test_keys::{
@@ -38,6 +39,7 @@ use ic_neurons_fund::{
     InvertibleFunction, MatchingFunction, PolynomialMatchingFunction, SerializableFunction,
 };
 use ic_sns_governance::{
+    governance::TREASURY_SUBACCOUNT_NONCE,
     pb::v1::{
         claim_swap_neurons_request::NeuronParameters,
         claim_swap_neurons_response::ClaimSwapNeuronsResult, governance, ClaimSwapNeuronsRequest,
@@ -210,6 +212,10 @@ fn create_generic_committed_swap() -> Swap {
         auto_finalize_swap_response: None,
         direct_participation_icp_e8s: Some(50 * E8),
         neurons_fund_participation_icp_e8s: None,
+        sale_round_number: None,
+        sale_round_sns_governance_proposal_id: None,
+        sns_token_treasury_refund: None,
+        previous_sale_rounds: vec![],
     }
 }
 
@@ -1584,6 +1590,10 @@ async fn test_finalize_swap_ok_fixed_funding() {
         auto_finalize_swap_response: None,
         direct_participation_icp_e8s: None,
         neurons_fund_participation_icp_e8s: None,
+        sale_round_number: None,
+        sale_round_sns_governance_proposal_id: None,
+        sns_token_treasury_refund: None,
+        previous_sale_rounds: vec![],
     };
     swap.update_derived_fields();
 
@@ -2213,6 +2223,10 @@ async fn test_finalize_swap_abort_fixed_funding() {
         auto_finalize_swap_response: None,
         direct_participation_icp_e8s: None,
         neurons_fund_participation_icp_e8s: None,
+        sale_round_number: None,
+        sale_round_sns_governance_proposal_id: None,
+        sns_token_treasury_refund: None,
+        previous_sale_rounds: vec![],
     };
 
     // Step 1.5: Attempt to auto-finalize the swap. It should not work, since
@@ -6697,3 +6711,387 @@ async fn test_finalize_swap_abort_sets_amount_transferred_and_fees_correctly() {
         },
     );
 }
+
+/// Returns a committed swap whose finalization has completed, i.e., the ICP of all
+/// buyers has been transferred and all SNS neurons have received their SNS tokens
+/// and have been claimed.
+fn create_finalized_committed_swap() -> Swap {
+    let mut swap = create_generic_committed_swap();
+    for buyer_state in swap.buyers.values_mut() {
+        let icp = buyer_state.icp.as_mut().unwrap();
+        icp.transfer_start_timestamp_seconds = END_TIMESTAMP_SECONDS;
+        icp.transfer_success_timestamp_seconds = END_TIMESTAMP_SECONDS;
+        buyer_state.has_created_neuron_recipes = Some(true);
+    }
+    swap.neuron_recipes = create_generic_sns_neuron_recipes(1);
+    for recipe in swap.neuron_recipes.iter_mut() {
+        let sns = recipe.sns.as_mut().unwrap();
+        sns.transfer_start_timestamp_seconds = END_TIMESTAMP_SECONDS;
+        sns.transfer_success_timestamp_seconds = END_TIMESTAMP_SECONDS;
+        recipe.claimed_status = Some(ClaimedStatus::Success as i32);
+    }
+    swap.decentralization_sale_open_timestamp_seconds = Some(START_TIMESTAMP_SECONDS);
+    swap.decentralization_swap_termination_timestamp_seconds = Some(END_TIMESTAMP_SECONDS);
+    swap
+}
+
+const OPEN_SALE_ROUND_PROPOSAL_ID: u64 = 42;
+const SALE_ROUND_START_TIMESTAMP_SECONDS: u64 = END_TIMESTAMP_SECONDS + 30 * SECONDS_PER_DAY;
+
+fn open_sale_round_request() -> OpenSaleRoundRequest {
+    OpenSaleRoundRequest {
+        params: Some(Params {
+            min_participants: 1,
+            swap_due_timestamp_seconds: SALE_ROUND_START_TIMESTAMP_SECONDS + 7 * SECONDS_PER_DAY,
+            sns_token_e8s: 100_000 * E8,
+            sale_delay_seconds: Some(SECONDS_PER_DAY),
+            ..params()
+        }),
+        sns_governance_proposal_id: Some(OPEN_SALE_ROUND_PROPOSAL_ID),
+    }
+}
+
+fn swap_sns_token_balance_mock_ledger(balance_e8s: u64) -> Vec<LedgerExpect> {
+    vec![LedgerExpect::AccountBalance(
+        Account {
+            owner: SWAP_CANISTER_ID.get().into(),
+            subaccount: None,
+        },
+        Ok(Tokens::from_e8s(balance_e8s)),
+    )]
+}
+
+#[test]
+fn test_open_sale_round_can_only_be_called_by_sns_governance() {
+    let mut swap = create_finalized_committed_swap();
+
+    let result = swap
+        .open_sale_round(
+            *TEST_USER1_PRINCIPAL,
+            SWAP_CANISTER_ID,
+            &mock_stub(vec![]),
+            SALE_ROUND_START_TIMESTAMP_SECONDS,
+            open_sale_round_request(),
+        )
+        .now_or_never()
+        .unwrap();
+
+    let err = result.unwrap_err();
+    assert!(
+        err.contains("can only be called by SNS Governance"),
+        "{}",
+        err
+    );
+    assert_eq!(swap.lifecycle(), Committed);
+    assert!(swap.previous_sale_rounds.is_empty());
+}
+
+#[test]
+fn test_open_sale_round_requires_previous_round_to_be_finalized() {
+    // The ICP of the buyers has not been transferred yet.
+    let mut swap = create_generic_committed_swap();
+
+    let result = swap
+        .open_sale_round(
+            SNS_GOVERNANCE_CANISTER_ID.get(),
+            SWAP_CANISTER_ID,
+            &mock_stub(vec![]),
+            SALE_ROUND_START_TIMESTAMP_SECONDS,
+            open_sale_round_request(),
+        )
+        .now_or_never()
+        .unwrap();
+
+    let err = result.unwrap_err();
+    assert!(err.contains("has not been finalized"), "{}", err);
+    assert_eq!(swap.lifecycle(), Committed);
+    assert!(swap.previous_sale_rounds.is_empty());
+}
+
+#[test]
+fn test_open_sale_round_requires_sns_tokens_to_be_available() {
+    let mut swap = create_finalized_committed_swap();
+    let request = open_sale_round_request();
+    let sns_token_e8s = request.params.as_ref().unwrap().sns_token_e8s;
+
+    let result = swap
+        .open_sale_round(
+            SNS_GOVERNANCE_CANISTER_ID.get(),
+            SWAP_CANISTER_ID,
+            &mock_stub(swap_sns_token_balance_mock_ledger(sns_token_e8s - 1)),
+            SALE_ROUND_START_TIMESTAMP_SECONDS,
+            request,
+        )
+        .now_or_never()
+        .unwrap();
+
+    let err = result.unwrap_err();
+    assert!(err.contains("but the sale round offers"), "{}", err);
+    assert_eq!(swap.lifecycle(), Committed);
+    assert!(swap.previous_sale_rounds.is_empty());
+}
+
+#[test]
+fn test_open_sale_round_rejects_invalid_request() {
+    let mut swap = create_finalized_committed_swap();
+    let request = OpenSaleRoundRequest {
+        sns_governance_proposal_id: None,
+        ..open_sale_round_request()
+    };
+
+    let result = swap
+        .open_sale_round(
+            SNS_GOVERNANCE_CANISTER_ID.get(),
+            SWAP_CANISTER_ID,
+            &mock_stub(vec![]),
+            SALE_ROUND_START_TIMESTAMP_SECONDS,
+            request,
+        )
+        .now_or_never()
+        .unwrap();
+
+    let err = result.unwrap_err();
+    assert!(err.contains("sns_governance_proposal_id"), "{}", err);
+    assert_eq!(swap.lifecycle(), Committed);
+}
+
+#[test]
+fn test_open_sale_round_archives_previous_round_and_resets_state() {
+    let mut swap = create_finalized_committed_swap();
+    let request = open_sale_round_request();
+    let expected_params = request.params.clone().unwrap();
+    let previous_params = swap.params.clone();
+    let previous_direct_participation_icp_e8s = swap.current_direct_participation_e8s();
+
+    swap.open_sale_round(
+        SNS_GOVERNANCE_CANISTER_ID.get(),
+        SWAP_CANISTER_ID,
+        &mock_stub(swap_sns_token_balance_mock_ledger(
+            expected_params.sns_token_e8s,
+        )),
+        SALE_ROUND_START_TIMESTAMP_SECONDS,
+        request,
+    )
+    .now_or_never()
+    .unwrap()
+    .unwrap();
+
+    // The decentralization swap is recorded as round 0.
+    assert_eq!(
+        swap.previous_sale_rounds,
+        vec![SaleRound {
+            round_number: Some(0),
+            sns_governance_proposal_id: None,
+            lifecycle: Some(Committed as i32),
+            params: previous_params,
+            open_timestamp_seconds: Some(START_TIMESTAMP_SECONDS),
+            termination_timestamp_seconds: Some(END_TIMESTAMP_SECONDS),
+            direct_participant_count: Some(1),
+            direct_participation_icp_e8s: Some(previous_direct_participation_icp_e8s),
+            neurons_fund_participation_icp_e8s: Some(0),
+        }]
+    );
+
+    // The state of the new round.
+    assert_eq!(swap.lifecycle(), Lifecycle::Adopted);
+    assert_eq!(swap.sale_round_number, Some(1));
+    assert_eq!(
+        swap.sale_round_sns_governance_proposal_id,
+        Some(OPEN_SALE_ROUND_PROPOSAL_ID)
+    );
+    assert_eq!(swap.params, Some(expected_params));
+    assert_eq!(
+        swap.decentralization_sale_open_timestamp_seconds,
+        Some(SALE_ROUND_START_TIMESTAMP_SECONDS + SECONDS_PER_DAY)
+    );
+    assert_eq!(
+        swap.decentralization_swap_termination_timestamp_seconds,
+        None
+    );
+    assert!(swap.buyers.is_empty());
+    assert!(swap.cf_participants.is_empty());
+    assert!(swap.neuron_recipes.is_empty());
+    assert_eq!(swap.current_direct_participation_e8s(), 0);
+    assert_eq!(swap.already_tried_to_auto_finalize, Some(false));
+    assert_eq!(get_snapshot_of_buyers_index_list(), vec![]);
+
+    // A new round cannot be opened before the current one was finalized.
+    let err = swap.can_open_sale_round().unwrap_err();
+    assert!(err.contains("Adopted"), "{}", err);
+}
+
+#[test]
+fn test_can_open_sale_round_after_aborted_follow_on_round_requires_refund() {
+    let mut swap = create_finalized_committed_swap();
+    swap.lifecycle = Aborted as i32;
+    swap.buyers.clear();
+    swap.neuron_recipes.clear();
+
+    // An aborted decentralization swap means that the SNS was not created.
+    let err = swap.can_open_sale_round().unwrap_err();
+    assert!(err.contains("Aborted"), "{}", err);
+
+    // An aborted follow-on sale round must first return its SNS tokens to the treasury.
+    swap.sale_round_number = Some(1);
+    let err = swap.can_open_sale_round().unwrap_err();
+    assert!(err.contains("SNS token treasury"), "{}", err);
+
+    swap.sns_token_treasury_refund = Some(TransferableAmount {
+        amount_e8s: 100_000 * E8,
+        transfer_start_timestamp_seconds: END_TIMESTAMP_SECONDS,
+        transfer_success_timestamp_seconds: END_TIMESTAMP_SECONDS,
+        amount_transferred_e8s: Some(100_000 * E8),
+        transfer_fee_paid_e8s: Some(init().transaction_fee_e8s_or_panic()),
+    });
+    assert_eq!(swap.can_open_sale_round(), Ok(()));
+}
+
+#[test]
+fn test_failed_claims_do_not_prevent_opening_a_sale_round() {
+    let mut swap = create_finalized_committed_swap();
+    swap.neuron_recipes = create_generic_sns_neuron_recipes(2);
+    for recipe in swap.neuron_recipes.iter_mut() {
+        let sns = recipe.sns.as_mut().unwrap();
+        sns.transfer_start_timestamp_seconds = END_TIMESTAMP_SECONDS;
+        sns.transfer_success_timestamp_seconds = END_TIMESTAMP_SECONDS;
+        recipe.claimed_status = Some(ClaimedStatus::Success as i32);
+    }
+    swap.neuron_recipes[1].claimed_status = Some(ClaimedStatus::Failed as i32);
+    let failed_recipe = swap.neuron_recipes[1].clone();
+
+    // Recipes whose SNS tokens have not been transferred yet still block.
+    swap.neuron_recipes[1]
+        .sns
+        .as_mut()
+        .unwrap()
+        .transfer_success_timestamp_seconds = 0;
+    let err = swap.can_open_sale_round().unwrap_err();
+    assert!(
+        err.contains("have not received their SNS tokens"),
+        "{}",
+        err
+    );
+    swap.neuron_recipes[1] = failed_recipe.clone();
+    assert_eq!(swap.can_open_sale_round(), Ok(()));
+
+    let request = open_sale_round_request();
+    let sns_token_e8s = request.params.as_ref().unwrap().sns_token_e8s;
+    swap.open_sale_round(
+        SNS_GOVERNANCE_CANISTER_ID.get(),
+        SWAP_CANISTER_ID,
+        &mock_stub(swap_sns_token_balance_mock_ledger(sns_token_e8s)),
+        SALE_ROUND_START_TIMESTAMP_SECONDS,
+        request,
+    )
+    .now_or_never()
+    .unwrap()
+    .unwrap();
+
+    // The recipe whose neuron could not be claimed is kept, so that claiming it is
+    // retried when the new round is finalized.
+    assert_eq!(swap.neuron_recipes, vec![failed_recipe]);
+}
+
+#[test]
+fn test_follow_on_sale_round_opens_once_previous_round_state_is_cleared() {
+    let mut swap = create_finalized_committed_swap();
+    memory::BUYERS_LIST_INDEX
+        .with(|buyer_list| buyer_list.borrow_mut().push(&*TEST_USER1_PRINCIPAL))
+        .unwrap();
+    let request = open_sale_round_request();
+    let sns_token_e8s = request.params.as_ref().unwrap().sns_token_e8s;
+    swap.open_sale_round(
+        SNS_GOVERNANCE_CANISTER_ID.get(),
+        SWAP_CANISTER_ID,
+        &mock_stub(swap_sns_token_balance_mock_ledger(sns_token_e8s)),
+        SALE_ROUND_START_TIMESTAMP_SECONDS,
+        request,
+    )
+    .now_or_never()
+    .unwrap()
+    .unwrap();
+    let open_timestamp_seconds = swap.decentralization_sale_open_timestamp_seconds.unwrap();
+
+    // The BUYERS_LIST_INDEX of the previous round is not cleared all at once.
+    assert_eq!(
+        get_snapshot_of_buyers_index_list(),
+        vec![*TEST_USER1_PRINCIPAL]
+    );
+    assert!(!swap.can_open(open_timestamp_seconds));
+
+    memory::BUYERS_LIST_INDEX.with(|buyer_list| buyer_list.borrow_mut().pop());
+    assert!(swap.can_open(open_timestamp_seconds));
+}
+
+#[test]
+fn test_refund_sale_round_tokens() {
+    let mut swap = create_finalized_committed_swap();
+    swap.sale_round_number = Some(1);
+    swap.sale_round_sns_governance_proposal_id = Some(OPEN_SALE_ROUND_PROPOSAL_ID);
+    let fee_e8s = init().transaction_fee_e8s_or_panic();
+    let refund = |swap: &Swap, caller: PrincipalId, proposal_id, ledger: MockLedger| {
+        swap.refund_sale_round_tokens(
+            caller,
+            &ledger,
+            RefundSaleRoundTokensRequest {
+                sns_governance_proposal_id: Some(proposal_id),
+                amount_e8s: Some(10 * E8),
+            },
+        )
+        .now_or_never()
+        .unwrap()
+    };
+
+    // Only SNS Governance can ask for a refund.
+    let err = refund(
+        &swap,
+        *TEST_USER1_PRINCIPAL,
+        OPEN_SALE_ROUND_PROPOSAL_ID + 1,
+        mock_stub(vec![]),
+    )
+    .unwrap_err();
+    assert!(
+        err.contains("can only be called by SNS Governance"),
+        "{}",
+        err
+    );
+
+    // Nothing is returned for a round that was opened.
+    let err = refund(
+        &swap,
+        SNS_GOVERNANCE_CANISTER_ID.get(),
+        OPEN_SALE_ROUND_PROPOSAL_ID,
+        mock_stub(vec![]),
+    )
+    .unwrap_err();
+    assert!(err.contains("was opened"), "{}", err);
+
+    // The tokens of a round that was not opened are returned to the SNS token treasury.
+    let treasury_account = Account {
+        owner: SNS_GOVERNANCE_CANISTER_ID.get().0,
+        subaccount: Some(compute_distribution_subaccount_bytes(
+            SNS_GOVERNANCE_CANISTER_ID.get(),
+            TREASURY_SUBACCOUNT_NONCE,
+        )),
+    };
+    let response = refund(
+        &swap,
+        SNS_GOVERNANCE_CANISTER_ID.get(),
+        OPEN_SALE_ROUND_PROPOSAL_ID + 1,
+        mock_stub(vec![LedgerExpect::TransferFunds(
+            10 * E8 - fee_e8s,
+            fee_e8s,
+            None,
+            treasury_account,
+            OPEN_SALE_ROUND_PROPOSAL_ID + 1,
+            Ok(7),
+        )]),
+    )
+    .unwrap();
+    assert_eq!(
+        response,
+        RefundSaleRoundTokensResponse {
+            block_index: Some(7)
+        }
+    );
+}
@@ -18,7 +18,10 @@ use ic_replicated_state::{
     canister_snapshots::SnapshotId,
     canister_state::{
         execution_state::{NextScheduledMethod, WasmMetadata},
-        system_state::{wasm_chunk_store::WasmChunkStoreMetadata, CanisterHistory, CyclesUseCase},
+        system_state::{
+            wasm_chunk_store::WasmChunkStoreMetadata, CanisterHistory, CyclesUseCase,
+            LowResourceHookStatus,
+        },
     },
     CallContextManager, CanisterStatus, ExecutionTask, ExportedFunctions, Global, NumWasmPages,
 };
@@ -165,6 +168,9 @@ pub struct CanisterStateBits {
     pub total_query_stats: TotalQueryStats,
     pub log_visibility: LogVisibility,
     pub canister_log_records: Vec<CanisterLogRecord>,
+    pub on_low_wasm_memory_hook_status: LowResourceHookStatus,
+    pub on_low_cycles_hook_status: LowResourceHookStatus,
+    pub wasm_memory_threshold: NumBytes,
 }
 
 /// This struct contains bits of the `CanisterSnapshot` that are not already
@@ -1796,6 +1802,13 @@ impl From<CanisterStateBits> for pb_canister_state_bits::CanisterStateBits {
                 .into_iter()
                 .map(|record| record.into())
                 .collect(),
+            on_low_wasm_memory_hook_status: pb_canister_state_bits::LowResourceHookStatus::from(
+                item.on_low_wasm_memory_hook_status,
+            ) as i32,
+            on_low_cycles_hook_status: pb_canister_state_bits::LowResourceHookStatus::from(
+                item.on_low_cycles_hook_status,
+            ) as i32,
+            wasm_memory_threshold: item.wasm_memory_threshold.get(),
         }
     }
 }
@@ -1921,6 +1934,9 @@ impl TryFrom<pb_canister_state_bits::CanisterStateBits> for CanisterStateBits {
                 .into_iter()
                 .map(|record| record.into())
                 .collect(),
+            on_low_wasm_memory_hook_status: value.on_low_wasm_memory_hook_status.try_into()?,
+            on_low_cycles_hook_status: value.on_low_cycles_hook_status.try_into()?,
+            wasm_memory_threshold: NumBytes::from(value.wasm_memory_threshold),
         })
     }
 }
@@ -4,7 +4,7 @@ use ic_management_canister_types::{
     CanisterChange, CanisterChangeDetails, CanisterChangeOrigin, CanisterInstallMode,
     LogVisibility, IC_00,
 };
-use ic_replicated_state::canister_state::system_state::CanisterHistory;
+use ic_replicated_state::canister_state::system_state::{CanisterHistory, LowResourceHookStatus};
 use ic_replicated_state::metadata_state::subnet_call_context_manager::InstallCodeCallId;
 use ic_test_utilities::types::ids::user_test_id;
 use ic_test_utilities::types::{
@@ -14,7 +14,7 @@ use ic_test_utilities::types::{
 use ic_test_utilities_logger::with_test_replica_logger;
 use ic_test_utilities_time::mock_time;
 use ic_test_utilities_tmpdir::tmpdir;
-use ic_types::messages::{CanisterCall, CanisterMessage, CanisterMessageOrTask};
+use ic_types::messages::{CanisterCall, CanisterMessage, CanisterMessageOrTask, CanisterTask};
 use itertools::Itertools;
 use proptest::prelude::*;
 use std::fs::File;
@@ -54,6 +54,9 @@ fn default_canister_state_bits() -> CanisterStateBits {
         total_query_stats: TotalQueryStats::default(),
         log_visibility: LogVisibility::default(),
         canister_log_records: Vec::new(),
+        on_low_wasm_memory_hook_status: LowResourceHookStatus::default(),
+        on_low_cycles_hook_status: LowResourceHookStatus::default(),
+        wasm_memory_threshold: NumBytes::from(0),
     }
 }
 
@@ -181,6 +184,29 @@ fn test_encode_decode_non_empty_history() {
         CanisterChangeOrigin::from_canister(canister_test_id(123).get(), None),
         CanisterChangeDetails::controllers_change(vec![]),
     ));
+    canister_history.add_canister_change(CanisterChange::new(
+        555,
+        7,
+        CanisterChangeOrigin::from_user(user_test_id(42).get()),
+        CanisterChangeDetails::load_snapshot(3, 1, 222),
+    ));
+    canister_history.add_canister_change(CanisterChange::new(
+        666,
+        8,
+        CanisterChangeOrigin::from_user(user_test_id(42).get()),
+        CanisterChangeDetails::settings_change(
+            Some(2_592_000),
+            Some(10),
+            Some(1 << 30),
+            Some(LogVisibility::Public),
+        ),
+    ));
+    canister_history.add_canister_change(CanisterChange::new(
+        777,
+        9,
+        CanisterChangeOrigin::from_user(user_test_id(42).get()),
+        CanisterChangeDetails::settings_change(None, None, Some(0), None),
+    ));
 
     // A canister state with non-empty history.
     let canister_state_bits = CanisterStateBits {
@@ -244,6 +270,14 @@ fn test_encode_decode_task_queue() {
             input: CanisterMessageOrTask::Message(CanisterMessage::Ingress(Arc::clone(&ingress))),
             prepaid_execution_cycles: Cycles::new(5),
         },
+        ExecutionTask::AbortedExecution {
+            input: CanisterMessageOrTask::Task(CanisterTask::OnLowWasmMemory),
+            prepaid_execution_cycles: Cycles::new(6),
+        },
+        ExecutionTask::AbortedExecution {
+            input: CanisterMessageOrTask::Task(CanisterTask::OnLowCycles),
+            prepaid_execution_cycles: Cycles::new(7),
+        },
     ];
     let canister_state_bits = CanisterStateBits {
         task_queue: task_queue.clone(),
@@ -255,6 +289,37 @@ fn test_encode_decode_task_queue() {
     assert_eq!(canister_state_bits.task_queue, task_queue);
 }
 
+#[test]
+fn test_encode_decode_low_resource_hook_status() {
+    for (on_low_wasm_memory_hook_status, on_low_cycles_hook_status) in [
+        (
+            LowResourceHookStatus::ConditionNotSatisfied,
+            LowResourceHookStatus::Ready,
+        ),
+        (
+            LowResourceHookStatus::Executed,
+            LowResourceHookStatus::ConditionNotSatisfied,
+        ),
+    ] {
+        let canister_state_bits = CanisterStateBits {
+            on_low_wasm_memory_hook_status,
+            on_low_cycles_hook_status,
+            ..default_canister_state_bits()
+        };
+
+        let pb_bits = pb_canister_state_bits::CanisterStateBits::from(canister_state_bits);
+        let canister_state_bits = CanisterStateBits::try_from(pb_bits).unwrap();
+        assert_eq!(
+            canister_state_bits.on_low_wasm_memory_hook_status,
+            on_low_wasm_memory_hook_status
+        );
+        assert_eq!(
+            canister_state_bits.on_low_cycles_hook_status,
+            on_low_cycles_hook_status
+        );
+    }
+}
+
 #[test]
 fn test_removal_when_last_dropped() {
     with_test_replica_logger(|log| {
@@ -1128,6 +1128,7 @@ impl StateMachine {
                 Arc::clone(&cycles_account_manager),
                 Arc::clone(&state_manager) as Arc<_>,
                 Arc::clone(&state_manager.get_fd_factory()),
+                None,
             )
         });
 
@@ -419,6 +419,9 @@ pub fn load_canister_state<P: ReadPolicy>(
         canister_state_bits.wasm_chunk_store_metadata,
         canister_state_bits.log_visibility,
         canister_state_bits.canister_log_records,
+        canister_state_bits.on_low_wasm_memory_hook_status,
+        canister_state_bits.on_low_cycles_hook_status,
+        canister_state_bits.wasm_memory_threshold,
     );
 
     let canister_state = CanisterState {
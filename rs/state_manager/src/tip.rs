@@ -999,6 +999,11 @@ fn serialize_canister_to_tip(
             total_query_stats: canister_state.scheduler_state.total_query_stats.clone(),
             log_visibility: canister_state.system_state.log_visibility,
             canister_log_records: canister_state.system_state.canister_log_records.clone(),
+            on_low_wasm_memory_hook_status: canister_state
+                .system_state
+                .on_low_wasm_memory_hook_status,
+            on_low_cycles_hook_status: canister_state.system_state.on_low_cycles_hook_status,
+            wasm_memory_threshold: canister_state.system_state.wasm_memory_threshold,
         }
         .into(),
     )?;
@@ -59,6 +59,7 @@ use ic_types::{
     time::Time,
     xnet::{StreamIndex, StreamIndexedQueue},
     CanisterId, CryptoHashOfPartialState, CryptoHashOfState, Height, NodeId, NumBytes, PrincipalId,
+    ReplicaVersion,
 };
 use ic_types::{epoch_from_height, QueryStatsEpoch};
 use maplit::btreemap;
@@ -3560,6 +3561,7 @@ fn certified_read_can_certify_node_public_keys_since_v12() {
                 subnet_type: SubnetType::System,
                 subnet_features: SubnetFeatures::default(),
                 ecdsa_keys_held: BTreeSet::new(),
+                replica_version: ReplicaVersion::default(),
             },
         );
 
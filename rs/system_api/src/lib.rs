@@ -16,6 +16,7 @@ use ic_interfaces::execution_environment::{
     TrapCode::{self, CyclesAmountTooBigFor64Bit},
 };
 use ic_logger::{error, ReplicaLogger};
+use ic_management_canister_types::{EcdsaCurve, EcdsaKeyId};
 use ic_registry_subnet_type::SubnetType;
 use ic_replicated_state::{
     canister_state::WASM_PAGE_SIZE_IN_BYTES, memory_required_to_push_request, Memory, NumWasmPages,
@@ -42,6 +43,12 @@ use std::{
 pub const MULTIPLIER_MAX_SIZE_LOCAL_SUBNET: u64 = 5;
 const MAX_NON_REPLICATED_QUERY_REPLY_SIZE: NumBytes = NumBytes::new(3 << 20);
 const CERTIFIED_DATA_MAX_LENGTH: u32 = 32;
+/// The value of `ecdsa_curve` identifying secp256k1 in `ic0.cost_sign_with_ecdsa`.
+const ECDSA_CURVE_SECP256K1: u32 = 0;
+/// The return values of `ic0.cost_sign_with_ecdsa`.
+const COST_SIGN_WITH_ECDSA_SUCCESS: u32 = 0;
+const COST_SIGN_WITH_ECDSA_UNKNOWN_CURVE: u32 = 1;
+const COST_SIGN_WITH_ECDSA_UNKNOWN_KEY: u32 = 2;
 
 // Enables tracing of system calls for local debugging.
 const TRACE_SYSCALLS: bool = false;
@@ -327,11 +334,13 @@ pub enum ApiType {
         message_accepted: bool,
     },
 
-    // For executing the `canister_heartbeat` or `canister_global_timer` methods
+    // For executing the `canister_heartbeat`, `canister_global_timer`,
+    // `canister_on_low_wasm_memory` or `canister_on_low_cycles` methods
     SystemTask {
         caller: PrincipalId,
         /// System task to execute.
-        /// Only `canister_heartbeat` and `canister_global_timer` are allowed.
+        /// Only `canister_heartbeat`, `canister_global_timer`,
+        /// `canister_on_low_wasm_memory` and `canister_on_low_cycles` are allowed.
         system_task: SystemMethod,
         time: Time,
         call_context_id: CallContextId,
@@ -555,7 +564,12 @@ impl ApiType {
             ApiType::SystemTask { system_task, .. } => match system_task {
                 SystemMethod::CanisterHeartbeat => "heartbeat",
                 SystemMethod::CanisterGlobalTimer => "global timer",
-                _ => panic!("Only `canister_heartbeat` and `canister_global_timer` are allowed."),
+                SystemMethod::CanisterOnLowWasmMemory => "on low wasm memory",
+                SystemMethod::CanisterOnLowCycles => "on low cycles",
+                _ => panic!(
+                    "Only `canister_heartbeat`, `canister_global_timer`, \
+                    `canister_on_low_wasm_memory` and `canister_on_low_cycles` are allowed."
+                ),
             },
             ApiType::Update { .. } => "update",
             ApiType::ReplicatedQuery { .. } => "replicated query",
@@ -2968,6 +2982,102 @@ impl SystemApi for SystemApiImpl {
         trace_syscall!(self, CyclesBurn128, result, amount);
         result
     }
+
+    fn ic0_cost_call(
+        &self,
+        method_name_size: u64,
+        payload_size: u64,
+        dst: u32,
+        heap: &mut [u8],
+    ) -> HypervisorResult<()> {
+        let subnet_size = self.sandbox_safe_system_state.subnet_size;
+        let cycles_account_manager = &self.sandbox_safe_system_state.cycles_account_manager;
+        let cost = cycles_account_manager.xnet_call_performed_fee(subnet_size)
+            + cycles_account_manager.xnet_call_bytes_transmitted_fee(
+                NumBytes::from(method_name_size.saturating_add(payload_size)),
+                subnet_size,
+            )
+            + cycles_account_manager.prepayment_for_response_transmission(subnet_size)
+            + cycles_account_manager.prepayment_for_response_execution(subnet_size);
+        let result = copy_cycles_to_heap(cost, dst, heap, "ic0_cost_call");
+        trace_syscall!(self, CostCall, result, method_name_size, payload_size, cost);
+        result
+    }
+
+    fn ic0_cost_create_canister(&self, dst: u32, heap: &mut [u8]) -> HypervisorResult<()> {
+        let cost = self
+            .sandbox_safe_system_state
+            .cycles_account_manager
+            .canister_creation_fee(self.sandbox_safe_system_state.subnet_size);
+        let result = copy_cycles_to_heap(cost, dst, heap, "ic0_cost_create_canister");
+        trace_syscall!(self, CostCreateCanister, result, cost);
+        result
+    }
+
+    fn ic0_cost_http_request(
+        &self,
+        request_size: u64,
+        max_res_bytes: u64,
+        dst: u32,
+        heap: &mut [u8],
+    ) -> HypervisorResult<()> {
+        let cost = self
+            .sandbox_safe_system_state
+            .cycles_account_manager
+            .http_request_fee(
+                NumBytes::from(request_size),
+                Some(NumBytes::from(max_res_bytes)),
+                self.sandbox_safe_system_state.subnet_size,
+            );
+        let result = copy_cycles_to_heap(cost, dst, heap, "ic0_cost_http_request");
+        trace_syscall!(
+            self,
+            CostHttpRequest,
+            result,
+            request_size,
+            max_res_bytes,
+            cost
+        );
+        result
+    }
+
+    fn ic0_cost_sign_with_ecdsa(
+        &self,
+        src: u32,
+        size: u32,
+        ecdsa_curve: u32,
+        dst: u32,
+        heap: &mut [u8],
+    ) -> HypervisorResult<u32> {
+        let method_name = "ic0_cost_sign_with_ecdsa";
+        let result = {
+            let key_name = valid_subslice(method_name, src, size, heap)?;
+            match ecdsa_curve {
+                ECDSA_CURVE_SECP256K1 => {
+                    let fee = std::str::from_utf8(key_name).ok().and_then(|key_name| {
+                        let key_id = EcdsaKeyId {
+                            curve: EcdsaCurve::Secp256k1,
+                            name: key_name.to_string(),
+                        };
+                        self.sandbox_safe_system_state
+                            .ecdsa_signature_fees
+                            .get(&key_id)
+                            .copied()
+                    });
+                    match fee {
+                        Some(fee) => {
+                            copy_cycles_to_heap(fee, dst, heap, method_name)?;
+                            Ok(COST_SIGN_WITH_ECDSA_SUCCESS)
+                        }
+                        None => Ok(COST_SIGN_WITH_ECDSA_UNKNOWN_KEY),
+                    }
+                }
+                _ => Ok(COST_SIGN_WITH_ECDSA_UNKNOWN_CURVE),
+            }
+        };
+        trace_syscall!(self, CostSignWithEcdsa, result, src, size, ecdsa_curve);
+        result
+    }
 }
 
 /// The default implementation of the `OutOfInstructionHandler` trait.
@@ -10,8 +10,9 @@ use ic_error_types::{ErrorCode, RejectCode, UserError};
 use ic_interfaces::execution_environment::{HypervisorError, HypervisorResult};
 use ic_logger::{info, ReplicaLogger};
 use ic_management_canister_types::{
-    CreateCanisterArgs, InstallChunkedCodeArgs, InstallCodeArgsV2, Method as Ic00Method, Payload,
-    ProvisionalCreateCanisterWithCyclesArgs, UninstallCodeArgs, UpdateSettingsArgs, IC_00,
+    CreateCanisterArgs, EcdsaKeyId, InstallChunkedCodeArgs, InstallCodeArgsV2,
+    Method as Ic00Method, Payload, ProvisionalCreateCanisterWithCyclesArgs, UninstallCodeArgs,
+    UpdateSettingsArgs, IC_00,
 };
 use ic_nns_constants::CYCLES_MINTING_CANISTER_ID;
 use ic_registry_subnet_type::SubnetType;
@@ -243,6 +244,7 @@ impl SystemStateChanges {
             | Ok(Ic00Method::BitcoinSendTransaction)
             | Ok(Ic00Method::BitcoinGetCurrentFeePercentiles)
             | Ok(Ic00Method::NodeMetricsHistory)
+            | Ok(Ic00Method::SubnetInfo)
             | Ok(Ic00Method::FetchCanisterLogs)
             | Ok(Ic00Method::UploadChunk)
             | Ok(Ic00Method::StoredChunks)
@@ -251,7 +253,11 @@ impl SystemStateChanges {
             | Ok(Ic00Method::TakeCanisterSnapshot)
             | Ok(Ic00Method::ListCanisterSnapshots)
             | Ok(Ic00Method::DeleteCanisterSnapshot)
-            | Ok(Ic00Method::LoadCanisterSnapshot) => Ok(None),
+            | Ok(Ic00Method::LoadCanisterSnapshot)
+            | Ok(Ic00Method::ReadCanisterSnapshotMetadata)
+            | Ok(Ic00Method::ReadCanisterSnapshotData)
+            | Ok(Ic00Method::UploadCanisterSnapshotMetadata)
+            | Ok(Ic00Method::UploadCanisterSnapshotData) => Ok(None),
             Err(_) => Err(UserError::new(
                 ErrorCode::CanisterMethodNotFound,
                 format!("Management canister has no method '{}'", msg.method_name),
@@ -538,6 +544,9 @@ pub struct SandboxSafeSystemState {
     pub(super) status: CanisterStatusView,
     pub(super) subnet_type: SubnetType,
     pub(super) subnet_size: usize,
+    /// The fee charged for a threshold ECDSA signature with each key that some
+    /// subnet is enabled to sign with.
+    pub(super) ecdsa_signature_fees: BTreeMap<EcdsaKeyId, Cycles>,
     dirty_page_overhead: NumInstructions,
     freeze_threshold: NumSeconds,
     memory_allocation: MemoryAllocation,
@@ -546,7 +555,7 @@ pub struct SandboxSafeSystemState {
     initial_reserved_balance: Cycles,
     reserved_balance_limit: Option<Cycles>,
     call_context_balances: BTreeMap<CallContextId, Cycles>,
-    cycles_account_manager: CyclesAccountManager,
+    pub(super) cycles_account_manager: CyclesAccountManager,
     // None indicates that we are in a context where the canister cannot
     // register callbacks (e.g. running the `start` method when installing a
     // canister.)
@@ -580,6 +589,7 @@ impl SandboxSafeSystemState {
         ic00_available_request_slots: usize,
         ic00_aliases: BTreeSet<CanisterId>,
         subnet_size: usize,
+        ecdsa_signature_fees: BTreeMap<EcdsaKeyId, Cycles>,
         dirty_page_overhead: NumInstructions,
         global_timer: CanisterTimer,
         canister_version: u64,
@@ -591,6 +601,7 @@ impl SandboxSafeSystemState {
             status,
             subnet_type: cycles_account_manager.subnet_type(),
             subnet_size,
+            ecdsa_signature_fees,
             dirty_page_overhead,
             freeze_threshold,
             memory_allocation,
@@ -658,6 +669,28 @@ impl SandboxSafeSystemState {
         let subnet_size = network_topology
             .get_subnet_size(&cycles_account_manager.get_subnet_id())
             .unwrap_or(SMALL_APP_SUBNET_MAX_SIZE);
+        // A signature request is routed to the first subnet enabled to sign
+        // with the key, which charges the fee according to its own size. The
+        // fee is waived for canisters on the NNS subnet.
+        let is_nns_subnet =
+            cycles_account_manager.get_subnet_id() == network_topology.nns_subnet_id;
+        let ecdsa_signature_fees = network_topology
+            .ecdsa_signing_subnets
+            .iter()
+            .filter_map(|(key_id, signing_subnets)| {
+                let signing_subnet_id = signing_subnets.first()?;
+                let fee = if is_nns_subnet {
+                    Cycles::zero()
+                } else {
+                    cycles_account_manager.ecdsa_signature_fee(
+                        network_topology
+                            .get_subnet_size(signing_subnet_id)
+                            .unwrap_or(SMALL_APP_SUBNET_MAX_SIZE),
+                    )
+                };
+                Some((key_id.clone(), fee))
+            })
+            .collect();
 
         Self::new_internal(
             system_state.canister_id,
@@ -677,6 +710,7 @@ impl SandboxSafeSystemState {
             ic00_available_request_slots,
             ic00_aliases,
             subnet_size,
+            ecdsa_signature_fees,
             dirty_page_overhead,
             system_state.global_timer,
             system_state.canister_version,
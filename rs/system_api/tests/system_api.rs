@@ -9,9 +9,11 @@ use ic_interfaces::execution_environment::{
     SubnetAvailableMemory, SystemApi, TrapCode,
 };
 use ic_logger::replica_logger::no_op_logger;
+use ic_management_canister_types::{EcdsaCurve, EcdsaKeyId};
 use ic_registry_subnet_type::SubnetType;
 use ic_replicated_state::{
-    testing::CanisterQueuesTesting, CallOrigin, Memory, NetworkTopology, SystemState,
+    testing::CanisterQueuesTesting, CallOrigin, Memory, NetworkTopology, SubnetTopology,
+    SystemState,
 };
 use ic_system_api::{
     sandbox_safe_system_state::SandboxSafeSystemState, ApiType, DefaultOutOfInstructionsHandler,
@@ -21,7 +23,7 @@ use ic_test_utilities::{
     cycles_account_manager::CyclesAccountManagerBuilder,
     state::SystemStateBuilder,
     types::{
-        ids::{call_context_test_id, canister_test_id, subnet_test_id, user_test_id},
+        ids::{call_context_test_id, canister_test_id, node_test_id, subnet_test_id, user_test_id},
         messages::RequestBuilder,
     },
 };
@@ -31,7 +33,7 @@ use ic_types::{
         CallContextId, CallbackId, RejectContext, RequestMetadata, MAX_RESPONSE_COUNT_BYTES,
     },
     methods::{Callback, WasmClosure},
-    time, CanisterTimer, CountBytes, Cycles, NumInstructions, PrincipalId, Time,
+    time, CanisterTimer, CountBytes, Cycles, NumBytes, NumInstructions, PrincipalId, Time,
 };
 use std::{
     collections::BTreeSet,
@@ -1830,6 +1832,155 @@ fn ic0_is_controller_invalid_principal_id() {
     ));
 }
 
+#[test]
+fn ic0_cost_functions_return_fees_of_cycles_account_manager() {
+    let cycles_account_manager = CyclesAccountManagerBuilder::new().build();
+    let subnet_size = SMALL_APP_SUBNET_MAX_SIZE;
+    let api = get_system_api(
+        ApiTypeBuilder::build_update_api(),
+        &SystemStateBuilder::default().build(),
+        cycles_account_manager,
+    );
+    let read_cycles =
+        |heap: &[u8]| Cycles::new(u128::from_le_bytes(heap[..16].try_into().unwrap()));
+
+    let mut heap = vec![0; 16];
+    api.ic0_cost_call(10, 100, 0, &mut heap).unwrap();
+    assert_eq!(
+        read_cycles(&heap),
+        cycles_account_manager.xnet_call_performed_fee(subnet_size)
+            + cycles_account_manager
+                .xnet_call_bytes_transmitted_fee(NumBytes::from(110), subnet_size)
+            + cycles_account_manager.prepayment_for_response_transmission(subnet_size)
+            + cycles_account_manager.prepayment_for_response_execution(subnet_size)
+    );
+
+    api.ic0_cost_create_canister(0, &mut heap).unwrap();
+    assert_eq!(
+        read_cycles(&heap),
+        cycles_account_manager.canister_creation_fee(subnet_size)
+    );
+
+    api.ic0_cost_http_request(1_000, 2_000, 0, &mut heap)
+        .unwrap();
+    assert_eq!(
+        read_cycles(&heap),
+        cycles_account_manager.http_request_fee(
+            NumBytes::from(1_000),
+            Some(NumBytes::from(2_000)),
+            subnet_size
+        )
+    );
+}
+
+#[test]
+fn ic0_cost_sign_with_ecdsa_returns_fee_of_signing_subnet() {
+    let own_subnet_id = subnet_test_id(0);
+    let signing_subnet_id = subnet_test_id(1);
+    let nns_subnet_id = subnet_test_id(2);
+    let signing_subnet_size = 34;
+    let key_id = EcdsaKeyId {
+        curve: EcdsaCurve::Secp256k1,
+        name: "key_1".to_string(),
+    };
+    let mut network_topology = NetworkTopology {
+        nns_subnet_id,
+        ..NetworkTopology::default()
+    };
+    network_topology
+        .subnets
+        .insert(own_subnet_id, SubnetTopology::default());
+    network_topology.subnets.insert(
+        signing_subnet_id,
+        SubnetTopology {
+            nodes: (0..signing_subnet_size).map(node_test_id).collect(),
+            ..SubnetTopology::default()
+        },
+    );
+    network_topology
+        .ecdsa_signing_subnets
+        .insert(key_id.clone(), vec![signing_subnet_id]);
+    let read_cycles =
+        |heap: &[u8]| Cycles::new(u128::from_le_bytes(heap[..16].try_into().unwrap()));
+    let heap_with_key_name = |key_name: &str| [&[0; 16][..], key_name.as_bytes()].concat();
+
+    // The fee is the one charged by the signing subnet, according to its size.
+    let cycles_account_manager = CyclesAccountManagerBuilder::new()
+        .with_subnet_id(own_subnet_id)
+        .build();
+    let api = get_system_api_with_network_topology(
+        ApiTypeBuilder::build_update_api(),
+        &SystemStateBuilder::default().build(),
+        cycles_account_manager,
+        &network_topology,
+    );
+    let mut heap = heap_with_key_name(&key_id.name);
+    assert_eq!(
+        api.ic0_cost_sign_with_ecdsa(16, key_id.name.len() as u32, 0, 0, &mut heap)
+            .unwrap(),
+        0
+    );
+    assert_eq!(
+        read_cycles(&heap),
+        cycles_account_manager.ecdsa_signature_fee(signing_subnet_size as usize)
+    );
+    assert_ne!(
+        read_cycles(&heap),
+        cycles_account_manager.ecdsa_signature_fee(SMALL_APP_SUBNET_MAX_SIZE)
+    );
+
+    // Unknown curve.
+    assert_eq!(
+        api.ic0_cost_sign_with_ecdsa(16, key_id.name.len() as u32, 1, 0, &mut heap)
+            .unwrap(),
+        1
+    );
+
+    // Unknown key.
+    let mut heap = heap_with_key_name("key_2");
+    assert_eq!(
+        api.ic0_cost_sign_with_ecdsa(16, 5, 0, 0, &mut heap)
+            .unwrap(),
+        2
+    );
+    assert_eq!(read_cycles(&heap), Cycles::zero());
+
+    // The fee is waived for canisters on the NNS subnet.
+    let api = get_system_api_with_network_topology(
+        ApiTypeBuilder::build_update_api(),
+        &SystemStateBuilder::default().build(),
+        CyclesAccountManagerBuilder::new()
+            .with_subnet_id(nns_subnet_id)
+            .build(),
+        &network_topology,
+    );
+    let mut heap = [&[0xff; 16][..], key_id.name.as_bytes()].concat();
+    assert_eq!(
+        api.ic0_cost_sign_with_ecdsa(16, key_id.name.len() as u32, 0, 0, &mut heap)
+            .unwrap(),
+        0
+    );
+    assert_eq!(read_cycles(&heap), Cycles::zero());
+}
+
+#[test]
+fn ic0_cost_functions_fail_if_dst_is_out_of_bounds() {
+    let api = get_system_api(
+        ApiTypeBuilder::build_update_api(),
+        &SystemStateBuilder::default().build(),
+        CyclesAccountManagerBuilder::new().build(),
+    );
+    let mut heap = vec![0; 16];
+    assert!(matches!(
+        api.ic0_cost_create_canister(1, &mut heap),
+        Err(HypervisorError::ContractViolation(_))
+    ));
+    assert!(matches!(
+        api.ic0_cost_call(0, 0, 8, &mut heap),
+        Err(HypervisorError::ContractViolation(_))
+    ));
+}
+
 #[test]
 fn test_ic0_cycles_burn() {
     let initial_cycles = Cycles::new(5_000_000_000_000);
@@ -21,15 +21,15 @@ use ic_execution_environment::{
 };
 use ic_interfaces::execution_environment::{
     ExecutionMode, IngressHistoryWriter, QueryHandler, RegistryExecutionSettings,
-    SubnetAvailableMemory,
+    RemoteQueryClient, SubnetAvailableMemory,
 };
 use ic_interfaces_state_manager::Labeled;
 use ic_logger::{replica_logger::no_op_logger, ReplicaLogger};
 use ic_management_canister_types::{
     CanisterIdRecord, CanisterInstallMode, CanisterInstallModeV2, CanisterSettingsArgs,
-    CanisterSettingsArgsBuilder, CanisterStatusType, EcdsaKeyId, EmptyBlob, InstallCodeArgs,
-    InstallCodeArgsV2, LogVisibility, Method, Payload, ProvisionalCreateCanisterWithCyclesArgs,
-    SkipPreUpgrade, UpdateSettingsArgs,
+    CanisterSettingsArgsBuilder, CanisterStatusType, CanisterUpgradeOptions, EcdsaKeyId, EmptyBlob,
+    InstallCodeArgs, InstallCodeArgsV2, LogVisibility, Method, Payload,
+    ProvisionalCreateCanisterWithCyclesArgs, UpdateSettingsArgs,
 };
 use ic_metrics::MetricsRegistry;
 use ic_registry_provisional_whitelist::ProvisionalWhitelist;
@@ -55,7 +55,8 @@ use ic_types::{
         AnonymousQuery, CallbackId, CanisterCall, CanisterMessage, CanisterTask, MessageId,
         RequestOrResponse, Response, UserQuery, MAX_INTER_CANISTER_PAYLOAD_IN_BYTES,
     },
-    CanisterId, Cycles, Height, NumInstructions, NumPages, QueryStatsEpoch, Time, UserId,
+    CanisterId, Cycles, Height, NumInstructions, NumPages, QueryStatsEpoch, ReplicaVersion, Time,
+    UserId,
 };
 use ic_types_test_utils::ids::{node_test_id, subnet_test_id, user_test_id};
 use ic_universal_canister::UNIVERSAL_CANISTER_WASM;
@@ -100,6 +101,7 @@ pub fn generate_subnets(
                 subnet_type,
                 subnet_features: SubnetFeatures::default(),
                 ecdsa_keys_held: BTreeSet::new(),
+                replica_version: ReplicaVersion::default(),
             },
         );
     }
@@ -797,16 +799,16 @@ impl ExecutionTest {
         Ok(())
     }
 
-    /// Upgrades the given canister with the given Wasm binary,
-    /// in the mode specified by value of 'skip_pre_upgrade' field.
+    /// Upgrades the given canister with the given Wasm binary and the
+    /// given upgrade options.
     pub fn upgrade_canister_v2(
         &mut self,
         canister_id: CanisterId,
         wasm_binary: Vec<u8>,
-        skip_pre_upgrade: Option<SkipPreUpgrade>,
+        upgrade_options: Option<CanisterUpgradeOptions>,
     ) -> Result<(), UserError> {
         let args = InstallCodeArgsV2::new(
-            CanisterInstallModeV2::Upgrade(skip_pre_upgrade),
+            CanisterInstallModeV2::Upgrade(upgrade_options),
             canister_id,
             wasm_binary,
             vec![],
@@ -987,6 +989,18 @@ impl ExecutionTest {
                     .task_queue
                     .push_front(ExecutionTask::GlobalTimer);
             }
+            CanisterTask::OnLowWasmMemory => {
+                canister
+                    .system_state
+                    .task_queue
+                    .push_front(ExecutionTask::OnLowWasmMemory);
+            }
+            CanisterTask::OnLowCycles => {
+                canister
+                    .system_state
+                    .task_queue
+                    .push_front(ExecutionTask::OnLowCycles);
+            }
         }
         let result = execute_canister(
             &self.exec_env,
@@ -1594,6 +1608,7 @@ pub struct ExecutionTestBuilder {
     resource_saturation_scaling: usize,
     heap_delta_rate_limit: NumBytes,
     upload_wasm_chunk_instructions: NumInstructions,
+    remote_query_client: Option<Arc<dyn RemoteQueryClient>>,
 }
 
 impl Default for ExecutionTestBuilder {
@@ -1634,6 +1649,7 @@ impl Default for ExecutionTestBuilder {
             resource_saturation_scaling: 1,
             heap_delta_rate_limit: scheduler_config.heap_delta_rate_limit,
             upload_wasm_chunk_instructions: scheduler_config.upload_wasm_chunk_instructions,
+            remote_query_client: None,
         }
     }
 }
@@ -1682,6 +1698,16 @@ impl ExecutionTestBuilder {
         self
     }
 
+    /// Forwards query calls to canisters on other subnets through the given
+    /// client instead of rejecting them.
+    pub fn with_remote_query_client(
+        mut self,
+        remote_query_client: Arc<dyn RemoteQueryClient>,
+    ) -> Self {
+        self.remote_query_client = Some(remote_query_client);
+        self
+    }
+
     pub fn with_log(self, log: ReplicaLogger) -> Self {
         Self { log, ..self }
     }
@@ -1934,6 +1960,11 @@ impl ExecutionTestBuilder {
         self
     }
 
+    pub fn with_snapshot_data_transfer(mut self, status: FlagStatus) -> Self {
+        self.execution_config.canister_snapshot_data_transfer = status;
+        self
+    }
+
     pub fn with_canister_logging(mut self, status: FlagStatus) -> Self {
         self.execution_config.canister_logging = status;
         self
@@ -2110,6 +2141,7 @@ impl ExecutionTestBuilder {
             self.instruction_limit_without_dts,
             Arc::clone(&cycles_account_manager),
             query_stats_collector,
+            self.remote_query_client,
         );
         ExecutionTest {
             state: Some(state),
@@ -996,6 +996,7 @@ pub fn create_canister_on_specific_subnet_type(env: TestEnv) {
                 None,
                 Some(SubnetSelection::Filter(SubnetFilter {
                     subnet_type: Some(type1),
+                    ..Default::default()
                 })),
             )
             .await
@@ -19,7 +19,9 @@ use ic_protobuf::registry::subnet::v1::{InitialIDkgDealings, InitialNiDkgTranscr
 use ic_protobuf::state::canister_state_bits::v1::{self as pb_canister_state_bits};
 use ic_protobuf::types::v1::CanisterInstallModeV2 as CanisterInstallModeV2Proto;
 use ic_protobuf::types::v1::{
-    CanisterInstallMode as CanisterInstallModeProto, CanisterUpgradeOptions,
+    CanisterInstallMode as CanisterInstallModeProto,
+    CanisterUpgradeOptions as CanisterUpgradeOptionsProto,
+    WasmMemoryPersistence as WasmMemoryPersistenceProto,
 };
 use ic_protobuf::{proxy::ProxyDecodeError, registry::crypto::v1 as pb_registry_crypto};
 use num_traits::cast::ToPrimitive;
@@ -77,6 +79,8 @@ pub enum Method {
 
     NodeMetricsHistory,
 
+    SubnetInfo,
+
     FetchCanisterLogs,
 
     // These methods are only available on test IC instances where there is a
@@ -95,6 +99,10 @@ pub enum Method {
     LoadCanisterSnapshot,
     ListCanisterSnapshots,
     DeleteCanisterSnapshot,
+    ReadCanisterSnapshotMetadata,
+    ReadCanisterSnapshotData,
+    UploadCanisterSnapshotMetadata,
+    UploadCanisterSnapshotData,
 }
 
 fn candid_error_to_user_error(err: candid::Error) -> UserError {
@@ -269,6 +277,65 @@ impl CanisterControllersChangeRecord {
     }
 }
 
+/// `CandidType` for `CanisterLoadSnapshotRecord`
+/// ```text
+/// record {
+///   canister_version : nat64;
+///   snapshot_id : nat64;
+///   taken_at_timestamp : nat64;
+/// }
+/// ```
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct CanisterLoadSnapshotRecord {
+    canister_version: u64,
+    snapshot_id: u64,
+    taken_at_timestamp: u64,
+}
+
+impl CanisterLoadSnapshotRecord {
+    pub fn canister_version(&self) -> u64 {
+        self.canister_version
+    }
+    pub fn snapshot_id(&self) -> u64 {
+        self.snapshot_id
+    }
+    pub fn taken_at_timestamp(&self) -> u64 {
+        self.taken_at_timestamp
+    }
+}
+
+/// `CandidType` for `CanisterSettingsChangeRecord`
+/// ```text
+/// record {
+///   freezing_threshold : opt nat64;
+///   compute_allocation : opt nat64;
+///   memory_allocation : opt nat64;
+///   log_visibility : opt log_visibility;
+/// }
+/// ```
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct CanisterSettingsChangeRecord {
+    freezing_threshold: Option<u64>,
+    compute_allocation: Option<u64>,
+    memory_allocation: Option<u64>,
+    log_visibility: Option<LogVisibility>,
+}
+
+impl CanisterSettingsChangeRecord {
+    pub fn freezing_threshold(&self) -> Option<u64> {
+        self.freezing_threshold
+    }
+    pub fn compute_allocation(&self) -> Option<u64> {
+        self.compute_allocation
+    }
+    pub fn memory_allocation(&self) -> Option<u64> {
+        self.memory_allocation
+    }
+    pub fn log_visibility(&self) -> Option<LogVisibility> {
+        self.log_visibility
+    }
+}
+
 /// `CandidType` for `CanisterChangeDetails`
 /// ```text
 /// variant {
@@ -283,6 +350,17 @@ impl CanisterControllersChangeRecord {
 ///   controllers_change : record {
 ///     controllers : vec principal;
 ///   };
+///   load_snapshot : record {
+///     canister_version : nat64;
+///     snapshot_id : nat64;
+///     taken_at_timestamp : nat64;
+///   };
+///   settings_change : record {
+///     freezing_threshold : opt nat64;
+///     compute_allocation : opt nat64;
+///     memory_allocation : opt nat64;
+///     log_visibility : opt log_visibility;
+///   };
 /// }
 /// ```
 #[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
@@ -295,6 +373,10 @@ pub enum CanisterChangeDetails {
     CanisterCodeDeployment(CanisterCodeDeploymentRecord),
     #[serde(rename = "controllers_change")]
     CanisterControllersChange(CanisterControllersChangeRecord),
+    #[serde(rename = "load_snapshot")]
+    CanisterLoadSnapshot(CanisterLoadSnapshotRecord),
+    #[serde(rename = "settings_change")]
+    CanisterSettingsChange(CanisterSettingsChangeRecord),
 }
 
 impl CanisterChangeDetails {
@@ -317,9 +399,36 @@ impl CanisterChangeDetails {
             controllers,
         })
     }
+
+    pub fn load_snapshot(
+        canister_version: u64,
+        snapshot_id: u64,
+        taken_at_timestamp: u64,
+    ) -> CanisterChangeDetails {
+        CanisterChangeDetails::CanisterLoadSnapshot(CanisterLoadSnapshotRecord {
+            canister_version,
+            snapshot_id,
+            taken_at_timestamp,
+        })
+    }
+
+    pub fn settings_change(
+        freezing_threshold: Option<u64>,
+        compute_allocation: Option<u64>,
+        memory_allocation: Option<u64>,
+        log_visibility: Option<LogVisibility>,
+    ) -> CanisterChangeDetails {
+        CanisterChangeDetails::CanisterSettingsChange(CanisterSettingsChangeRecord {
+            freezing_threshold,
+            compute_allocation,
+            memory_allocation,
+            log_visibility,
+        })
+    }
 }
 
-/// Every canister change (canister creation, code uninstallation, code deployment, or controllers change) consists of
+/// Every canister change (canister creation, code uninstallation, code deployment, controllers change,
+/// snapshot load, or settings change) consists of
 ///
 /// 1. the system timestamp (in nanoseconds since Unix Epoch) at which the change was performed,
 /// 2. the canister version after performing the change,
@@ -335,6 +444,13 @@ impl CanisterChangeDetails {
 ///
 /// Controllers changes are described by the full new set of the canister controllers after the change.
 ///
+/// Snapshot loads are described by the ID of the loaded snapshot, the canister version at which
+/// the snapshot was taken, and the time at which it was taken.
+///
+/// Settings changes are described by the new values of the freezing threshold, compute allocation,
+/// memory allocation, and log visibility that were set by the change. Settings that were not set
+/// are omitted.
+///
 /// `CandidType` for `CanisterChange`
 /// ```text
 /// record {
@@ -380,7 +496,9 @@ impl CanisterChange {
                 std::mem::size_of_val(canister_controllers_change.controllers())
             }
             CanisterChangeDetails::CanisterCodeDeployment(_)
-            | CanisterChangeDetails::CanisterCodeUninstall => 0,
+            | CanisterChangeDetails::CanisterCodeUninstall
+            | CanisterChangeDetails::CanisterLoadSnapshot(_)
+            | CanisterChangeDetails::CanisterSettingsChange(_) => 0,
         };
         NumBytes::from((size_of::<CanisterChange>() + controllers_memory_size) as u64)
     }
@@ -560,6 +678,27 @@ impl From<&CanisterChangeDetails> for pb_canister_state_bits::canister_change::C
                     },
                 )
             }
+            CanisterChangeDetails::CanisterLoadSnapshot(canister_load_snapshot) => {
+                pb_canister_state_bits::canister_change::ChangeDetails::CanisterLoadSnapshot(
+                    pb_canister_state_bits::CanisterLoadSnapshot {
+                        canister_version: canister_load_snapshot.canister_version,
+                        snapshot_id: canister_load_snapshot.snapshot_id,
+                        taken_at_timestamp: canister_load_snapshot.taken_at_timestamp,
+                    },
+                )
+            }
+            CanisterChangeDetails::CanisterSettingsChange(canister_settings_change) => {
+                pb_canister_state_bits::canister_change::ChangeDetails::CanisterSettingsChange(
+                    pb_canister_state_bits::CanisterSettingsChange {
+                        freezing_threshold: canister_settings_change.freezing_threshold,
+                        compute_allocation: canister_settings_change.compute_allocation,
+                        memory_allocation: canister_settings_change.memory_allocation,
+                        log_visibility: canister_settings_change
+                            .log_visibility
+                            .map(|v| pb_canister_state_bits::LogVisibility::from(v) as i32),
+                    },
+                )
+            }
         }
     }
 }
@@ -603,6 +742,24 @@ impl TryFrom<pb_canister_state_bits::canister_change::ChangeDetails> for Caniste
                     .map(TryInto::try_into)
                     .collect::<Result<Vec<PrincipalId>, _>>()?,
             )),
+            pb_canister_state_bits::canister_change::ChangeDetails::CanisterLoadSnapshot(
+                canister_load_snapshot,
+            ) => Ok(CanisterChangeDetails::load_snapshot(
+                canister_load_snapshot.canister_version,
+                canister_load_snapshot.snapshot_id,
+                canister_load_snapshot.taken_at_timestamp,
+            )),
+            pb_canister_state_bits::canister_change::ChangeDetails::CanisterSettingsChange(
+                canister_settings_change,
+            ) => Ok(CanisterChangeDetails::settings_change(
+                canister_settings_change.freezing_threshold,
+                canister_settings_change.compute_allocation,
+                canister_settings_change.memory_allocation,
+                canister_settings_change
+                    .log_visibility
+                    .map(LogVisibility::try_from)
+                    .transpose()?,
+            )),
         }
     }
 }
@@ -731,6 +888,7 @@ impl From<pb_canister_state_bits::LogVisibility> for LogVisibility {
 ///     freezing_threshold: nat;
 ///     reserved_cycles_limit: nat;
 ///     log_visibility: log_visibility;
+///     wasm_memory_threshold: nat;
 /// })`
 #[derive(CandidType, Clone, Deserialize, Debug, Eq, PartialEq)]
 pub struct DefiniteCanisterSettingsArgs {
@@ -741,6 +899,7 @@ pub struct DefiniteCanisterSettingsArgs {
     freezing_threshold: candid::Nat,
     reserved_cycles_limit: candid::Nat,
     log_visibility: LogVisibility,
+    wasm_memory_threshold: candid::Nat,
 }
 
 impl DefiniteCanisterSettingsArgs {
@@ -752,6 +911,7 @@ impl DefiniteCanisterSettingsArgs {
         freezing_threshold: u64,
         reserved_cycles_limit: Option<u128>,
         log_visibility: LogVisibility,
+        wasm_memory_threshold: u64,
     ) -> Self {
         let memory_allocation = candid::Nat::from(memory_allocation.unwrap_or(0));
         let reserved_cycles_limit = candid::Nat::from(reserved_cycles_limit.unwrap_or(0));
@@ -763,6 +923,7 @@ impl DefiniteCanisterSettingsArgs {
             freezing_threshold: candid::Nat::from(freezing_threshold),
             reserved_cycles_limit,
             log_visibility,
+            wasm_memory_threshold: candid::Nat::from(wasm_memory_threshold),
         }
     }
 
@@ -777,6 +938,10 @@ impl DefiniteCanisterSettingsArgs {
     pub fn log_visibility(&self) -> LogVisibility {
         self.log_visibility
     }
+
+    pub fn wasm_memory_threshold(&self) -> candid::Nat {
+        self.wasm_memory_threshold.clone()
+    }
 }
 
 impl Payload<'_> for DefiniteCanisterSettingsArgs {}
@@ -845,6 +1010,94 @@ pub struct QueryStats {
     response_payload_bytes_total: candid::Nat,
 }
 
+/// Struct used for encoding/decoding
+/// `(record {
+///     wasm_memory_size: nat;
+///     stable_memory_size: nat;
+///     global_memory_size: nat;
+///     wasm_binary_size: nat;
+///     custom_sections_size: nat;
+///     canister_history_size: nat;
+///     wasm_chunk_store_size: nat;
+///     snapshots_size: nat;
+///     message_memory_size: nat;
+/// })`
+#[derive(CandidType, Debug, Default, Deserialize, Eq, PartialEq)]
+pub struct MemoryMetrics {
+    wasm_memory_size: candid::Nat,
+    stable_memory_size: candid::Nat,
+    global_memory_size: candid::Nat,
+    wasm_binary_size: candid::Nat,
+    custom_sections_size: candid::Nat,
+    canister_history_size: candid::Nat,
+    wasm_chunk_store_size: candid::Nat,
+    snapshots_size: candid::Nat,
+    message_memory_size: candid::Nat,
+}
+
+impl MemoryMetrics {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        wasm_memory_size: NumBytes,
+        stable_memory_size: NumBytes,
+        global_memory_size: NumBytes,
+        wasm_binary_size: NumBytes,
+        custom_sections_size: NumBytes,
+        canister_history_size: NumBytes,
+        wasm_chunk_store_size: NumBytes,
+        snapshots_size: NumBytes,
+        message_memory_size: NumBytes,
+    ) -> Self {
+        Self {
+            wasm_memory_size: candid::Nat::from(wasm_memory_size.get()),
+            stable_memory_size: candid::Nat::from(stable_memory_size.get()),
+            global_memory_size: candid::Nat::from(global_memory_size.get()),
+            wasm_binary_size: candid::Nat::from(wasm_binary_size.get()),
+            custom_sections_size: candid::Nat::from(custom_sections_size.get()),
+            canister_history_size: candid::Nat::from(canister_history_size.get()),
+            wasm_chunk_store_size: candid::Nat::from(wasm_chunk_store_size.get()),
+            snapshots_size: candid::Nat::from(snapshots_size.get()),
+            message_memory_size: candid::Nat::from(message_memory_size.get()),
+        }
+    }
+
+    pub fn wasm_memory_size(&self) -> NumBytes {
+        NumBytes::from(self.wasm_memory_size.0.to_u64().unwrap())
+    }
+
+    pub fn stable_memory_size(&self) -> NumBytes {
+        NumBytes::from(self.stable_memory_size.0.to_u64().unwrap())
+    }
+
+    pub fn global_memory_size(&self) -> NumBytes {
+        NumBytes::from(self.global_memory_size.0.to_u64().unwrap())
+    }
+
+    pub fn wasm_binary_size(&self) -> NumBytes {
+        NumBytes::from(self.wasm_binary_size.0.to_u64().unwrap())
+    }
+
+    pub fn custom_sections_size(&self) -> NumBytes {
+        NumBytes::from(self.custom_sections_size.0.to_u64().unwrap())
+    }
+
+    pub fn canister_history_size(&self) -> NumBytes {
+        NumBytes::from(self.canister_history_size.0.to_u64().unwrap())
+    }
+
+    pub fn wasm_chunk_store_size(&self) -> NumBytes {
+        NumBytes::from(self.wasm_chunk_store_size.0.to_u64().unwrap())
+    }
+
+    pub fn snapshots_size(&self) -> NumBytes {
+        NumBytes::from(self.snapshots_size.0.to_u64().unwrap())
+    }
+
+    pub fn message_memory_size(&self) -> NumBytes {
+        NumBytes::from(self.message_memory_size.0.to_u64().unwrap())
+    }
+}
+
 /// Struct used for encoding/decoding
 /// `(record {
 ///     status : variant { running; stopping; stopped };
@@ -852,6 +1105,7 @@ pub struct QueryStats {
 ///     module_hash: opt blob;
 ///     controller: principal;
 ///     memory_size: nat;
+///     memory_metrics: memory_metrics;
 ///     cycles: nat;
 ///     freezing_threshold: nat,
 ///     idle_cycles_burned_per_day: nat;
@@ -870,6 +1124,7 @@ pub struct CanisterStatusResultV2 {
     controller: candid::Principal,
     settings: DefiniteCanisterSettingsArgs,
     memory_size: candid::Nat,
+    memory_metrics: MemoryMetrics,
     cycles: candid::Nat,
     // this is for compat with Spec 0.12/0.13
     balance: Vec<(Vec<u8>, candid::Nat)>,
@@ -887,12 +1142,14 @@ impl CanisterStatusResultV2 {
         controller: PrincipalId,
         controllers: Vec<PrincipalId>,
         memory_size: NumBytes,
+        memory_metrics: MemoryMetrics,
         cycles: u128,
         compute_allocation: u64,
         memory_allocation: Option<u64>,
         freezing_threshold: u64,
         reserved_cycles_limit: Option<u128>,
         log_visibility: LogVisibility,
+        wasm_memory_threshold: u64,
         idle_cycles_burned_per_day: u128,
         reserved_cycles: u128,
         query_num_calls: u128,
@@ -905,6 +1162,7 @@ impl CanisterStatusResultV2 {
             module_hash,
             controller: candid::Principal::from_text(controller.to_string()).unwrap(),
             memory_size: candid::Nat::from(memory_size.get()),
+            memory_metrics,
             cycles: candid::Nat::from(cycles),
             // the following is spec 0.12/0.13 compat;
             // "\x00" denotes cycles
@@ -917,6 +1175,7 @@ impl CanisterStatusResultV2 {
                 freezing_threshold,
                 reserved_cycles_limit,
                 log_visibility,
+                wasm_memory_threshold,
             ),
             freezing_threshold: candid::Nat::from(freezing_threshold),
             idle_cycles_burned_per_day: candid::Nat::from(idle_cycles_burned_per_day),
@@ -950,6 +1209,10 @@ impl CanisterStatusResultV2 {
         NumBytes::from(self.memory_size.0.to_u64().unwrap())
     }
 
+    pub fn memory_metrics(&self) -> &MemoryMetrics {
+        &self.memory_metrics
+    }
+
     pub fn cycles(&self) -> u128 {
         self.cycles.0.to_u128().unwrap()
     }
@@ -1035,13 +1298,72 @@ impl CanisterInstallMode {
     }
 }
 
-#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, Eq, Hash, CandidType, Copy, Default)]
-pub struct SkipPreUpgrade(pub Option<bool>);
+/// Specifies what happens to the Wasm main memory of a canister on upgrade.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize, Eq, Hash, CandidType)]
+pub enum WasmMemoryPersistence {
+    /// Retain the main memory across the upgrade. Used for enhanced orthogonal
+    /// persistence, which requires the new Wasm module to declare support for it.
+    #[serde(rename = "keep")]
+    Keep,
+    /// Reinitialize the main memory on upgrade. This is the default behavior
+    /// without enhanced orthogonal persistence.
+    #[serde(rename = "replace")]
+    Replace,
+}
+
+impl WasmMemoryPersistence {
+    pub fn iter() -> Iter<'static, WasmMemoryPersistence> {
+        static MODES: [WasmMemoryPersistence; 2] =
+            [WasmMemoryPersistence::Keep, WasmMemoryPersistence::Replace];
+        MODES.iter()
+    }
+}
+
+impl From<&WasmMemoryPersistence> for WasmMemoryPersistenceProto {
+    fn from(item: &WasmMemoryPersistence) -> Self {
+        match item {
+            WasmMemoryPersistence::Keep => WasmMemoryPersistenceProto::Keep,
+            WasmMemoryPersistence::Replace => WasmMemoryPersistenceProto::Replace,
+        }
+    }
+}
+
+impl TryFrom<WasmMemoryPersistenceProto> for WasmMemoryPersistence {
+    type Error = ProxyDecodeError;
+
+    fn try_from(item: WasmMemoryPersistenceProto) -> Result<Self, Self::Error> {
+        match item {
+            WasmMemoryPersistenceProto::Keep => Ok(WasmMemoryPersistence::Keep),
+            WasmMemoryPersistenceProto::Replace => Ok(WasmMemoryPersistence::Replace),
+            WasmMemoryPersistenceProto::Unspecified => Err(ProxyDecodeError::ValueOutOfRange {
+                typ: "WasmMemoryPersistence",
+                err: format!("Unknown value for wasm memory persistence {:?}", item),
+            }),
+        }
+    }
+}
+
+/// The options of a canister upgrade.
+///
+/// `(record {
+///     skip_pre_upgrade: opt bool;
+///     wasm_memory_persistence: opt variant {
+///         keep;
+///         replace;
+///     };
+/// })`
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize, Eq, Hash, CandidType)]
+pub struct CanisterUpgradeOptions {
+    /// Whether to skip the execution of the `canister_pre_upgrade` method.
+    pub skip_pre_upgrade: Option<bool>,
+    /// Whether to retain the Wasm main memory across the upgrade.
+    pub wasm_memory_persistence: Option<WasmMemoryPersistence>,
+}
 
 /// The mode with which a canister is installed.
 ///
 /// This second version of the mode allows someone to specify the
-/// optional `SkipPreUpgrade` parameter in case of an upgrade
+/// optional `CanisterUpgradeOptions` in case of an upgrade
 #[derive(
     Clone, Debug, Deserialize, PartialEq, Serialize, Eq, EnumString, Hash, CandidType, Copy, Default,
 )]
@@ -1058,18 +1380,51 @@ pub enum CanisterInstallModeV2 {
     /// Upgrade an existing canister.
     #[serde(rename = "upgrade")]
     #[strum(serialize = "upgrade")]
-    Upgrade(Option<SkipPreUpgrade>),
+    Upgrade(Option<CanisterUpgradeOptions>),
 }
 
 impl CanisterInstallModeV2 {
     pub fn iter() -> Iter<'static, CanisterInstallModeV2> {
-        static MODES: [CanisterInstallModeV2; 6] = [
+        static MODES: [CanisterInstallModeV2; 12] = [
             CanisterInstallModeV2::Install,
             CanisterInstallModeV2::Reinstall,
             CanisterInstallModeV2::Upgrade(None),
-            CanisterInstallModeV2::Upgrade(Some(SkipPreUpgrade(None))),
-            CanisterInstallModeV2::Upgrade(Some(SkipPreUpgrade(Some(false)))),
-            CanisterInstallModeV2::Upgrade(Some(SkipPreUpgrade(Some(true)))),
+            CanisterInstallModeV2::Upgrade(Some(CanisterUpgradeOptions {
+                skip_pre_upgrade: None,
+                wasm_memory_persistence: None,
+            })),
+            CanisterInstallModeV2::Upgrade(Some(CanisterUpgradeOptions {
+                skip_pre_upgrade: Some(false),
+                wasm_memory_persistence: None,
+            })),
+            CanisterInstallModeV2::Upgrade(Some(CanisterUpgradeOptions {
+                skip_pre_upgrade: Some(true),
+                wasm_memory_persistence: None,
+            })),
+            CanisterInstallModeV2::Upgrade(Some(CanisterUpgradeOptions {
+                skip_pre_upgrade: None,
+                wasm_memory_persistence: Some(WasmMemoryPersistence::Keep),
+            })),
+            CanisterInstallModeV2::Upgrade(Some(CanisterUpgradeOptions {
+                skip_pre_upgrade: Some(false),
+                wasm_memory_persistence: Some(WasmMemoryPersistence::Keep),
+            })),
+            CanisterInstallModeV2::Upgrade(Some(CanisterUpgradeOptions {
+                skip_pre_upgrade: Some(true),
+                wasm_memory_persistence: Some(WasmMemoryPersistence::Keep),
+            })),
+            CanisterInstallModeV2::Upgrade(Some(CanisterUpgradeOptions {
+                skip_pre_upgrade: None,
+                wasm_memory_persistence: Some(WasmMemoryPersistence::Replace),
+            })),
+            CanisterInstallModeV2::Upgrade(Some(CanisterUpgradeOptions {
+                skip_pre_upgrade: Some(false),
+                wasm_memory_persistence: Some(WasmMemoryPersistence::Replace),
+            })),
+            CanisterInstallModeV2::Upgrade(Some(CanisterUpgradeOptions {
+                skip_pre_upgrade: Some(true),
+                wasm_memory_persistence: Some(WasmMemoryPersistence::Replace),
+            })),
         ];
         MODES.iter()
     }
@@ -1145,9 +1500,20 @@ impl TryFrom<CanisterInstallModeV2Proto> for CanisterInstallModeV2 {
 
             ic_protobuf::types::v1::canister_install_mode_v2::CanisterInstallModeV2::Mode2(
                 upgrade_mode,
-            ) => Ok(CanisterInstallModeV2::Upgrade(Some(SkipPreUpgrade(
-                upgrade_mode.skip_pre_upgrade,
-            )))),
+            ) => Ok(CanisterInstallModeV2::Upgrade(Some(
+                CanisterUpgradeOptions {
+                    skip_pre_upgrade: upgrade_mode.skip_pre_upgrade,
+                    wasm_memory_persistence: upgrade_mode
+                        .wasm_memory_persistence
+                        .map(|mode| {
+                            WasmMemoryPersistenceProto::try_from(mode)
+                                .ok()
+                                .and_then(|mode| WasmMemoryPersistence::try_from(mode).ok())
+                                .ok_or_else(|| CanisterInstallModeError(mode.to_string()))
+                        })
+                        .transpose()?,
+                },
+            ))),
         }
     }
 }
@@ -1191,10 +1557,13 @@ impl From<&CanisterInstallModeV2> for CanisterInstallModeV2Proto {
                         CanisterInstallModeProto::Upgrade.into(),
                     )
                 }
-                CanisterInstallModeV2::Upgrade(Some(skip_pre_upgrade)) => {
+                CanisterInstallModeV2::Upgrade(Some(upgrade_options)) => {
                     ic_protobuf::types::v1::canister_install_mode_v2::CanisterInstallModeV2::Mode2(
-                        CanisterUpgradeOptions {
-                            skip_pre_upgrade: skip_pre_upgrade.0,
+                        CanisterUpgradeOptionsProto {
+                            skip_pre_upgrade: upgrade_options.skip_pre_upgrade,
+                            wasm_memory_persistence: upgrade_options
+                                .wasm_memory_persistence
+                                .map(|mode| WasmMemoryPersistenceProto::from(&mode).into()),
                         },
                     )
                 }
@@ -1228,6 +1597,44 @@ fn canister_install_mode_round_trip() {
     canister_install_mode_round_trip_aux(CanisterInstallMode::Upgrade);
 }
 
+#[test]
+fn canister_install_mode_v2_round_trip() {
+    for mode in CanisterInstallModeV2::iter() {
+        let pb_mode = CanisterInstallModeV2Proto::from(mode);
+        let dec_mode = CanisterInstallModeV2::try_from(pb_mode).unwrap();
+        assert_eq!(*mode, dec_mode);
+    }
+}
+
+#[test]
+fn canister_install_mode_v2_candid_encoding() {
+    let mode = CanisterInstallModeV2::Upgrade(Some(CanisterUpgradeOptions {
+        skip_pre_upgrade: Some(true),
+        wasm_memory_persistence: Some(WasmMemoryPersistence::Keep),
+    }));
+    let bytes = Encode!(&mode).unwrap();
+    let decoded = Decode!(&bytes, CanisterInstallModeV2).unwrap();
+    assert_eq!(mode, decoded);
+
+    // The upgrade options are encoded as a record.
+    #[derive(CandidType, Deserialize)]
+    struct UpgradeOptions {
+        skip_pre_upgrade: Option<bool>,
+        wasm_memory_persistence: Option<WasmMemoryPersistence>,
+    }
+    #[derive(CandidType, Deserialize)]
+    enum Mode {
+        #[serde(rename = "upgrade")]
+        Upgrade(Option<UpgradeOptions>),
+    }
+    let bytes = Encode!(&Mode::Upgrade(Some(UpgradeOptions {
+        skip_pre_upgrade: Some(true),
+        wasm_memory_persistence: Some(WasmMemoryPersistence::Keep),
+    })))
+    .unwrap();
+    assert_eq!(Decode!(&bytes, CanisterInstallModeV2).unwrap(), mode);
+}
+
 impl Payload<'_> for CanisterStatusResultV2 {}
 
 /// Struct used for encoding/decoding
@@ -1524,6 +1931,7 @@ fn verify_max_bounded_controllers_length() {
 ///     freezing_threshold: opt nat;
 ///     reserved_cycles_limit: opt nat;
 ///     log_visibility : opt log_visibility;
+///     wasm_memory_threshold: opt nat;
 /// })`
 #[derive(Default, Clone, CandidType, Deserialize, Debug, PartialEq, Eq)]
 pub struct CanisterSettingsArgs {
@@ -1535,6 +1943,7 @@ pub struct CanisterSettingsArgs {
     pub freezing_threshold: Option<candid::Nat>,
     pub reserved_cycles_limit: Option<candid::Nat>,
     pub log_visibility: Option<LogVisibility>,
+    pub wasm_memory_threshold: Option<candid::Nat>,
 }
 
 impl Payload<'_> for CanisterSettingsArgs {}
@@ -1551,6 +1960,7 @@ impl CanisterSettingsArgs {
             freezing_threshold: None,
             reserved_cycles_limit: None,
             log_visibility: None,
+            wasm_memory_threshold: None,
         }
     }
 
@@ -1568,6 +1978,7 @@ pub struct CanisterSettingsArgsBuilder {
     freezing_threshold: Option<candid::Nat>,
     reserved_cycles_limit: Option<candid::Nat>,
     log_visibility: Option<LogVisibility>,
+    wasm_memory_threshold: Option<candid::Nat>,
 }
 
 #[allow(dead_code)]
@@ -1585,6 +1996,7 @@ impl CanisterSettingsArgsBuilder {
             freezing_threshold: self.freezing_threshold,
             reserved_cycles_limit: self.reserved_cycles_limit,
             log_visibility: self.log_visibility,
+            wasm_memory_threshold: self.wasm_memory_threshold,
         }
     }
 
@@ -1660,6 +2072,15 @@ impl CanisterSettingsArgsBuilder {
             ..self
         }
     }
+
+    /// Sets the Wasm memory threshold in bytes: the `canister_on_low_wasm_memory`
+    /// hook runs once the remaining Wasm memory falls below it.
+    pub fn with_wasm_memory_threshold(self, wasm_memory_threshold: u64) -> Self {
+        Self {
+            wasm_memory_threshold: Some(candid::Nat::from(wasm_memory_threshold)),
+            ..self
+        }
+    }
 }
 
 /// Struct used for encoding/decoding
@@ -2320,6 +2741,45 @@ pub struct NodeMetricsHistoryResponse {
 
 impl Payload<'_> for NodeMetricsHistoryResponse {}
 
+/// `CandidType` for `SubnetInfoArgs`
+/// ```text
+/// record {
+///     subnet_id: principal;
+/// }
+/// ```
+#[derive(Default, Clone, CandidType, Deserialize, Debug)]
+pub struct SubnetInfoArgs {
+    pub subnet_id: PrincipalId,
+}
+
+impl Payload<'_> for SubnetInfoArgs {}
+
+/// `CandidType` for `SubnetInfoResponse`
+/// ```text
+/// record {
+///     replica_version : text;
+///     subnet_type : text;
+///     subnet_size : nat64;
+///     ecdsa_key_ids : vec ecdsa_key_id;
+///     enabled_features : vec text;
+///     num_canisters : nat64;
+/// }
+/// ```
+#[derive(Default, Clone, CandidType, Deserialize, Debug, PartialEq, Eq)]
+pub struct SubnetInfoResponse {
+    pub replica_version: String,
+    pub subnet_type: String,
+    pub subnet_size: u64,
+    pub ecdsa_key_ids: Vec<EcdsaKeyId>,
+    pub enabled_features: Vec<String>,
+    /// Number of canisters currently hosted on the subnet, so that callers can tell how many more
+    /// canisters the subnet can host, e.g. the cycles minting canister when it selects a subnet
+    /// by remaining canister capacity.
+    pub num_canisters: u64,
+}
+
+impl Payload<'_> for SubnetInfoResponse {}
+
 /// `CandidType` for `FetchCanisterLogsRequest`
 /// ```text
 /// record {
@@ -2390,6 +2850,14 @@ pub struct FetchCanisterLogsResponse {
 
 impl Payload<'_> for FetchCanisterLogsResponse {}
 
+/// The maximum size of a chunk in a canister's wasm chunk store, in bytes.
+pub const WASM_CHUNK_STORE_CHUNK_SIZE_BYTES: u64 = 1024 * 1024; // 1 MiB
+/// The maximum total size of a canister's wasm chunk store, in bytes.
+pub const WASM_CHUNK_STORE_MAX_SIZE_BYTES: u64 = 100 * 1024 * 1024; // 100 MiB
+/// The maximum number of chunks a canister's wasm chunk store can hold.
+pub const WASM_CHUNK_STORE_MAX_CHUNKS: u64 =
+    WASM_CHUNK_STORE_MAX_SIZE_BYTES / WASM_CHUNK_STORE_CHUNK_SIZE_BYTES;
+
 /// Struct used for encoding/decoding
 /// `(record {
 ///     canister_id: principal;
@@ -2434,7 +2902,11 @@ pub type UploadChunkReply = ChunkHash;
 ///         install;
 ///         reinstall;
 ///         upgrade: opt record {
-///             skip_pre_upgrade: opt bool
+///             skip_pre_upgrade: opt bool;
+///             wasm_memory_persistence: opt variant {
+///                 keep;
+///                 replace;
+///             };
 ///         }
 ///     };
 ///     target_canister_id: principal;
@@ -2547,3 +3019,310 @@ impl StoredChunksArgs {
 pub struct StoredChunksReply(pub Vec<ChunkHash>);
 
 impl Payload<'_> for StoredChunksReply {}
+
+/// A value of an exported global of a canister.
+/// `(variant {
+///     i32: int32;
+///     i64: int64;
+///     f32: float32;
+///     f64: float64;
+/// })`
+#[derive(Clone, CandidType, Deserialize, Debug, PartialEq)]
+pub enum SnapshotGlobal {
+    #[serde(rename = "i32")]
+    I32(i32),
+    #[serde(rename = "i64")]
+    I64(i64),
+    #[serde(rename = "f32")]
+    F32(f32),
+    #[serde(rename = "f64")]
+    F64(f64),
+}
+
+/// Struct used for encoding/decoding
+/// `(record {
+///     canister_id: principal;
+///     replace_snapshot: opt nat64;
+/// })`
+#[derive(Clone, CandidType, Deserialize, Debug, PartialEq, Eq)]
+pub struct TakeCanisterSnapshotArgs {
+    pub canister_id: PrincipalId,
+    pub replace_snapshot: Option<u64>,
+}
+
+impl Payload<'_> for TakeCanisterSnapshotArgs {}
+
+impl TakeCanisterSnapshotArgs {
+    pub fn get_canister_id(&self) -> CanisterId {
+        CanisterId::unchecked_from_principal(self.canister_id)
+    }
+}
+
+/// Struct to be returned when taking a canister snapshot and, as a vector,
+/// when listing the snapshots of a canister.
+/// `(record {
+///     id: nat64;
+///     taken_at_timestamp: nat64;
+///     total_size: nat64;
+/// })`
+#[derive(Clone, CandidType, Deserialize, Debug, PartialEq, Eq)]
+pub struct CanisterSnapshotResponse {
+    pub id: u64,
+    pub taken_at_timestamp: u64,
+    pub total_size: u64,
+}
+
+impl Payload<'_> for CanisterSnapshotResponse {}
+
+/// Struct used for encoding/decoding
+/// `(record {
+///     canister_id: principal;
+/// })`
+#[derive(Clone, CandidType, Deserialize, Debug, PartialEq, Eq)]
+pub struct ListCanisterSnapshotArgs {
+    pub canister_id: PrincipalId,
+}
+
+impl Payload<'_> for ListCanisterSnapshotArgs {}
+
+impl ListCanisterSnapshotArgs {
+    pub fn get_canister_id(&self) -> CanisterId {
+        CanisterId::unchecked_from_principal(self.canister_id)
+    }
+}
+
+/// Struct to be returned when listing the snapshots of a canister.
+/// `(vec canister_snapshot)`
+#[derive(Clone, CandidType, Deserialize, Debug, PartialEq, Eq)]
+pub struct ListCanisterSnapshotsResponse(pub Vec<CanisterSnapshotResponse>);
+
+impl Payload<'_> for ListCanisterSnapshotsResponse {}
+
+/// Struct used for encoding/decoding
+/// `(record {
+///     canister_id: principal;
+///     snapshot_id: nat64;
+/// })`
+#[derive(Clone, CandidType, Deserialize, Debug, PartialEq, Eq)]
+pub struct DeleteCanisterSnapshotArgs {
+    pub canister_id: PrincipalId,
+    pub snapshot_id: u64,
+}
+
+impl Payload<'_> for DeleteCanisterSnapshotArgs {}
+
+impl DeleteCanisterSnapshotArgs {
+    pub fn get_canister_id(&self) -> CanisterId {
+        CanisterId::unchecked_from_principal(self.canister_id)
+    }
+}
+
+/// Struct used for encoding/decoding
+/// `(record {
+///     canister_id: principal;
+///     snapshot_id: nat64;
+///     sender_canister_version: opt nat64;
+/// })`
+#[derive(Clone, CandidType, Deserialize, Debug, PartialEq, Eq)]
+pub struct LoadCanisterSnapshotArgs {
+    pub canister_id: PrincipalId,
+    pub snapshot_id: u64,
+    pub sender_canister_version: Option<u64>,
+}
+
+impl Payload<'_> for LoadCanisterSnapshotArgs {}
+
+impl LoadCanisterSnapshotArgs {
+    pub fn get_canister_id(&self) -> CanisterId {
+        CanisterId::unchecked_from_principal(self.canister_id)
+    }
+
+    pub fn get_sender_canister_version(&self) -> Option<u64> {
+        self.sender_canister_version
+    }
+}
+
+/// Struct used for encoding/decoding
+/// `(record {
+///     canister_id: principal;
+///     snapshot_id: nat64;
+/// })`
+#[derive(Clone, CandidType, Deserialize, Debug, PartialEq, Eq)]
+pub struct ReadCanisterSnapshotMetadataArgs {
+    pub canister_id: PrincipalId,
+    pub snapshot_id: u64,
+}
+
+impl Payload<'_> for ReadCanisterSnapshotMetadataArgs {}
+
+impl ReadCanisterSnapshotMetadataArgs {
+    pub fn get_canister_id(&self) -> CanisterId {
+        CanisterId::unchecked_from_principal(self.canister_id)
+    }
+}
+
+/// Struct to be returned when reading the metadata of a canister snapshot.
+/// `(record {
+///     taken_at_timestamp: nat64;
+///     canister_version: nat64;
+///     certified_data: blob;
+///     wasm_module_size: nat64;
+///     exported_globals: vec global;
+///     wasm_memory_size: nat64;
+///     stable_memory_size: nat64;
+///     wasm_chunk_store: vec record { hash: blob };
+/// })`
+#[derive(Clone, CandidType, Deserialize, Debug, PartialEq)]
+pub struct ReadCanisterSnapshotMetadataResponse {
+    pub taken_at_timestamp: u64,
+    pub canister_version: u64,
+    #[serde(with = "serde_bytes")]
+    pub certified_data: Vec<u8>,
+    pub wasm_module_size: u64,
+    pub exported_globals: Vec<SnapshotGlobal>,
+    pub wasm_memory_size: u64,
+    pub stable_memory_size: u64,
+    pub wasm_chunk_store: Vec<ChunkHash>,
+}
+
+impl Payload<'_> for ReadCanisterSnapshotMetadataResponse {}
+
+/// Specifies which part of a canister snapshot is read.
+/// `(variant {
+///     wasm_module: record { offset: nat64; size: nat64 };
+///     main_memory: record { offset: nat64; size: nat64 };
+///     stable_memory: record { offset: nat64; size: nat64 };
+///     wasm_chunk: record { hash: blob };
+/// })`
+#[derive(Clone, CandidType, Deserialize, Debug, PartialEq, Eq)]
+pub enum CanisterSnapshotDataKind {
+    #[serde(rename = "wasm_module")]
+    WasmModule { offset: u64, size: u64 },
+    #[serde(rename = "main_memory")]
+    MainMemory { offset: u64, size: u64 },
+    #[serde(rename = "stable_memory")]
+    StableMemory { offset: u64, size: u64 },
+    #[serde(rename = "wasm_chunk")]
+    WasmChunk {
+        #[serde(with = "serde_bytes")]
+        hash: Vec<u8>,
+    },
+}
+
+/// Struct used for encoding/decoding
+/// `(record {
+///     canister_id: principal;
+///     snapshot_id: nat64;
+///     kind: canister_snapshot_data_kind;
+/// })`
+#[derive(Clone, CandidType, Deserialize, Debug, PartialEq, Eq)]
+pub struct ReadCanisterSnapshotDataArgs {
+    pub canister_id: PrincipalId,
+    pub snapshot_id: u64,
+    pub kind: CanisterSnapshotDataKind,
+}
+
+impl Payload<'_> for ReadCanisterSnapshotDataArgs {}
+
+impl ReadCanisterSnapshotDataArgs {
+    pub fn get_canister_id(&self) -> CanisterId {
+        CanisterId::unchecked_from_principal(self.canister_id)
+    }
+}
+
+/// Struct to be returned when reading a part of a canister snapshot.
+/// `(record {
+///     chunk: blob;
+/// })`
+#[derive(Clone, CandidType, Deserialize, Debug, PartialEq, Eq)]
+pub struct ReadCanisterSnapshotDataResponse {
+    #[serde(with = "serde_bytes")]
+    pub chunk: Vec<u8>,
+}
+
+impl Payload<'_> for ReadCanisterSnapshotDataResponse {}
+
+/// Struct used for encoding/decoding
+/// `(record {
+///     canister_id: principal;
+///     wasm_module_size: nat64;
+///     wasm_module_hash: blob;
+///     exported_globals: vec global;
+///     wasm_memory_size: nat64;
+///     stable_memory_size: nat64;
+///     certified_data: blob;
+/// })`
+#[derive(Clone, CandidType, Deserialize, Debug, PartialEq)]
+pub struct UploadCanisterSnapshotMetadataArgs {
+    pub canister_id: PrincipalId,
+    pub wasm_module_size: u64,
+    #[serde(with = "serde_bytes")]
+    pub wasm_module_hash: Vec<u8>,
+    pub exported_globals: Vec<SnapshotGlobal>,
+    pub wasm_memory_size: u64,
+    pub stable_memory_size: u64,
+    #[serde(with = "serde_bytes")]
+    pub certified_data: Vec<u8>,
+}
+
+impl Payload<'_> for UploadCanisterSnapshotMetadataArgs {}
+
+impl UploadCanisterSnapshotMetadataArgs {
+    pub fn get_canister_id(&self) -> CanisterId {
+        CanisterId::unchecked_from_principal(self.canister_id)
+    }
+}
+
+/// Struct to be returned when uploading the metadata of a canister snapshot.
+/// `(record {
+///     snapshot_id: nat64;
+/// })`
+#[derive(Clone, CandidType, Deserialize, Debug, PartialEq, Eq)]
+pub struct UploadCanisterSnapshotMetadataResponse {
+    pub snapshot_id: u64,
+}
+
+impl Payload<'_> for UploadCanisterSnapshotMetadataResponse {}
+
+/// Specifies which part of a canister snapshot is uploaded.
+/// `(variant {
+///     wasm_module: record { offset: nat64 };
+///     main_memory: record { offset: nat64 };
+///     stable_memory: record { offset: nat64 };
+///     wasm_chunk;
+/// })`
+#[derive(Clone, CandidType, Deserialize, Debug, PartialEq, Eq)]
+pub enum CanisterSnapshotDataOffset {
+    #[serde(rename = "wasm_module")]
+    WasmModule { offset: u64 },
+    #[serde(rename = "main_memory")]
+    MainMemory { offset: u64 },
+    #[serde(rename = "stable_memory")]
+    StableMemory { offset: u64 },
+    #[serde(rename = "wasm_chunk")]
+    WasmChunk,
+}
+
+/// Struct used for encoding/decoding
+/// `(record {
+///     canister_id: principal;
+///     snapshot_id: nat64;
+///     kind: canister_snapshot_data_offset;
+///     chunk: blob;
+/// })`
+#[derive(Clone, CandidType, Deserialize, Debug, PartialEq, Eq)]
+pub struct UploadCanisterSnapshotDataArgs {
+    pub canister_id: PrincipalId,
+    pub snapshot_id: u64,
+    pub kind: CanisterSnapshotDataOffset,
+    #[serde(with = "serde_bytes")]
+    pub chunk: Vec<u8>,
+}
+
+impl Payload<'_> for UploadCanisterSnapshotDataArgs {}
+
+impl UploadCanisterSnapshotDataArgs {
+    pub fn get_canister_id(&self) -> CanisterId {
+        CanisterId::unchecked_from_principal(self.canister_id)
+    }
+}
@@ -593,6 +593,19 @@ pub struct QueryResponseHash([u8; 32]);
 impl QueryResponseHash {
     /// Creates a [`QueryResponseHash`] from a given query response, request and timestamp.
     pub fn new(response: &HttpQueryResponse, request: &UserQuery, timestamp: Time) -> Self {
+        Self::from_request_id(response, &request.id(), timestamp)
+    }
+
+    /// Creates a [`QueryResponseHash`] from a given query response, the id of the
+    /// request it answers and timestamp.
+    ///
+    /// This allows parties that only see the raw request, such as boundary nodes,
+    /// to recompute the hash signed by the replica.
+    pub fn from_request_id(
+        response: &HttpQueryResponse,
+        request_id: &MessageId,
+        timestamp: Time,
+    ) -> Self {
         use RawHttpRequestVal::*;
 
         let self_map_representation = match response {
@@ -602,7 +615,7 @@ impl QueryResponseHash {
                 };
 
                 btreemap! {
-                    "request_id".to_string() => Bytes(request.id().as_bytes().to_vec()),
+                    "request_id".to_string() => Bytes(request_id.as_bytes().to_vec()),
                     "status".to_string() => String("replied".to_string()),
                     "timestamp".to_string() => U64(timestamp.as_nanos_since_unix_epoch()),
                     "reply".to_string() => Map(map_of_reply)
@@ -614,7 +627,7 @@ impl QueryResponseHash {
                 reject_message,
             } => {
                 btreemap! {
-                    "request_id".to_string() => Bytes(request.id().as_bytes().to_vec()),
+                    "request_id".to_string() => Bytes(request_id.as_bytes().to_vec()),
                     "status".to_string() => String("rejected".to_string()),
                     "timestamp".to_string() => U64(timestamp.as_nanos_since_unix_epoch()),
                     "reject_code".to_string() => U64(*reject_code),
@@ -598,6 +598,21 @@ mod hashing {
             &hex!("bd80f930dfd3eafdf2d5c03031da9b5ec62963701abcb217d31c84005bd8db87")
         );
     }
+
+    #[test]
+    fn hashing_query_response_from_request_id() {
+        let time = Time::from_nanos_since_unix_epoch(2614);
+        let query_response = HttpQueryResponse::Replied {
+            reply: HttpQueryResponseReply {
+                arg: Blob(b"some_bytes".to_vec()),
+            },
+        };
+        let user_query = query::default_user_query_content();
+        assert_eq!(
+            QueryResponseHash::from_request_id(&query_response, &user_query.id(), time).as_bytes(),
+            QueryResponseHash::new(&query_response, &user_query, time).as_bytes()
+        );
+    }
 }
 
 mod cbor_serialization {
@@ -70,7 +70,10 @@ pub fn create_canister(cycles: (u64, u64)) -> CandidCallBuilder<CreateCanisterAr
 /// // Upgrade a canister while skipping pre_upgrade hook with custom callbacks
 /// wasm().call(
 ///   management::install_code(canister_id, wasm_module)
-///      .with_mode(management::InstallMode::Upgrade(Some(management::SkipPreUpgrade(Some(true)))))
+///      .with_mode(management::InstallMode::Upgrade(Some(management::CanisterUpgradeOptions {
+///          skip_pre_upgrade: Some(true),
+///          wasm_memory_persistence: None,
+///      })))
 ///      .on_reply(wasm().noop()) // custom on_reply
 ///      .on_reject(wasm().noop()) // custom on_reject
 ///      .on_cleanup(wasm().noop())); // custom on_cleanup
@@ -285,7 +288,18 @@ impl<Args: CandidType> From<CandidCallBuilder<Args>> for Call {
 }
 
 #[derive(CandidType, Deserialize)]
-pub struct SkipPreUpgrade(pub Option<bool>);
+pub enum WasmMemoryPersistence {
+    #[serde(rename = "keep")]
+    Keep,
+    #[serde(rename = "replace")]
+    Replace,
+}
+
+#[derive(CandidType, Deserialize, Default)]
+pub struct CanisterUpgradeOptions {
+    pub skip_pre_upgrade: Option<bool>,
+    pub wasm_memory_persistence: Option<WasmMemoryPersistence>,
+}
 
 #[derive(CandidType, Deserialize)]
 pub enum InstallMode {
@@ -294,7 +308,7 @@ pub enum InstallMode {
     #[serde(rename = "reinstall")]
     Reinstall,
     #[serde(rename = "upgrade")]
-    Upgrade(Option<SkipPreUpgrade>),
+    Upgrade(Option<CanisterUpgradeOptions>),
 }
 
 #[derive(CandidType)]
@@ -3,8 +3,14 @@ mod config_tests;
 #[cfg(test)]
 mod tests;
 
-use hyper::{Body, Request, Response, StatusCode};
-use ic_crypto_tls_interfaces::TlsHandshake;
+use hyper::{
+    body::{Bytes, HttpBody},
+    Body, Method, Request, Response, StatusCode,
+};
+use ic_crypto_tls_interfaces::{AuthenticatedPeer, TlsHandshake};
+use ic_interfaces::execution_environment::{
+    RemoteQueryError, RemoteQueryHandler, RemoteQueryRequest,
+};
 use ic_interfaces_certified_stream_store::{CertifiedStreamStore, EncodeStreamError};
 use ic_interfaces_registry::RegistryClient;
 use ic_logger::{debug, info, warn, ReplicaLogger};
@@ -12,7 +18,10 @@ use ic_metrics::{buckets::decimal_buckets, MetricsRegistry};
 use ic_protobuf::messaging::xnet::v1 as pb;
 use ic_protobuf::proxy::ProtoProxy;
 use ic_registry_client_helpers::node::NodeRegistry;
-use ic_types::{xnet::StreamIndex, NodeId, PrincipalId, SubnetId};
+use ic_types::{
+    messages::MAX_INTER_CANISTER_PAYLOAD_IN_BYTES_U64, xnet::StreamIndex, NodeId, PrincipalId,
+    SubnetId,
+};
 use prometheus::{Histogram, HistogramVec};
 use serde::Serialize;
 use std::convert::Infallible;
@@ -42,12 +51,22 @@ const METRIC_SLICE_PAYLOAD_SIZE: &str = "xnet_endpoint_slice_payload_size_bytes"
 const METRIC_RESPONSE_SIZE: &str = "xnet_endpoint_response_size_bytes";
 
 const RESOURCE_ERROR: &str = "error";
+const RESOURCE_QUERY: &str = "query";
 const RESOURCE_STREAM: &str = "stream";
 const RESOURCE_STREAMS: &str = "streams";
 const RESOURCE_UNKNOWN: &str = "unknown";
 
 const XNET_ENDPOINT_NUM_WORKER_THREADS: usize = 4;
 
+/// Query calls from other subnets are handled by a separate set of workers, as
+/// they may take a lot longer than building stream slices.
+const XNET_ENDPOINT_NUM_QUERY_WORKER_THREADS: usize = 4;
+
+/// The maximum size of an encoded `RemoteQueryRequest`: the largest payload of
+/// an inter-canister request, plus some headroom for the remaining fields.
+const MAX_QUERY_REQUEST_SIZE_BYTES: usize =
+    MAX_INTER_CANISTER_PAYLOAD_IN_BYTES_U64 as usize + 64 * 1024;
+
 impl XNetEndpointMetrics {
     pub fn new(metrics_registry: &MetricsRegistry) -> Self {
         Self {
@@ -85,16 +104,28 @@ enum WorkerMessage {
         /// The channel that should be used to handle the reply to the user.
         response_sender: oneshot::Sender<Response<Body>>,
     },
+    /// Handle a query call from another subnet and send the result to the
+    /// reply channel.
+    HandleQuery {
+        /// The authenticated node that sent the query call.
+        peer: NodeId,
+        /// The encoded `RemoteQueryRequest`.
+        body: Bytes,
+        /// The channel that should be used to handle the reply to the peer.
+        response_sender: oneshot::Sender<Response<Body>>,
+    },
     /// Stop processing requests.
     Stop,
 }
 
-/// HTTPS endpoint for fetching XNet stream slices.
+/// HTTPS endpoint for fetching XNet stream slices and for executing query
+/// calls made by canisters on other subnets.
 ///
 /// Spawns a request handler thread, which holds a reference to the
 /// `StateManager`; and an async task that runs the HTTPS server and accepts
 /// incoming requests. The two are connected via a bounded `crossbeam::channel`,
-/// also used for signaling shutdown on drop.
+/// also used for signaling shutdown on drop. Query calls are handled by a
+/// separate pool of threads, connected via their own channel.
 ///
 /// Exposed APIs:
 /// * `/api/v1/streams`
@@ -104,11 +135,17 @@ enum WorkerMessage {
 ///   - Returns a stream slice for the given `SubnetId` with up to `msg_limit`
 ///     messages beginning at `msg_begin`, witness beginning at `witness_begin`
 ///     (`msg_begin` if missing), of up to `byte_limit` bytes.
+/// * `POST /api/v1/query`
+///   - Executes the CBOR-encoded `RemoteQueryRequest` in the body and returns
+///     the CBOR-encoded `RemoteQueryResponse`. Only accepted from nodes of the
+///     subnet hosting the calling canister.
 pub struct XNetEndpoint {
     server_address: SocketAddr,
     handler_thread_pool: threadpool::ThreadPool,
+    query_thread_pool: threadpool::ThreadPool,
     shutdown_notify: Arc<Notify>,
     request_sender: crossbeam_channel::Sender<WorkerMessage>,
+    query_sender: crossbeam_channel::Sender<WorkerMessage>,
     log: ReplicaLogger,
 }
 
@@ -126,9 +163,15 @@ impl Drop for XNetEndpoint {
                 .send(WorkerMessage::Stop)
                 .expect("failed to send stop signal!");
         }
+        for _ in 0..XNET_ENDPOINT_NUM_QUERY_WORKER_THREADS {
+            self.query_sender
+                .send(WorkerMessage::Stop)
+                .expect("failed to send stop signal!");
+        }
 
         // Join the background workers.
         self.handler_thread_pool.join();
+        self.query_thread_pool.join();
 
         info!(self.log, "XNet Endpoint shut down");
     }
@@ -136,12 +179,15 @@ impl Drop for XNetEndpoint {
 
 const API_URL_STREAMS: &str = "/api/v1/streams";
 const API_URL_STREAM_PREFIX: &str = "/api/v1/stream/";
+const API_URL_QUERY: &str = "/api/v1/query";
 
 impl XNetEndpoint {
     /// Creates and starts an `XNetEndpoint` to publish XNet `Streams`.
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         runtime_handle: runtime::Handle,
         certified_stream_store: Arc<dyn CertifiedStreamStore>,
+        remote_query_handler: Arc<dyn RemoteQueryHandler>,
         tls: Arc<dyn TlsHandshake + Send + Sync>,
         registry_client: Arc<dyn RegistryClient + Send + Sync>,
         config: XNetEndpointConfig,
@@ -169,12 +215,15 @@ impl XNetEndpoint {
         // only available in tokio ≥ 0.3.
         let (request_sender, request_receiver) =
             crossbeam_channel::bounded(XNET_ENDPOINT_NUM_WORKER_THREADS);
+        let (query_sender, query_receiver) =
+            crossbeam_channel::bounded(XNET_ENDPOINT_NUM_QUERY_WORKER_THREADS);
 
         let make_service = make_service_fn({
             #[derive(Clone)]
             struct Context {
                 log: ReplicaLogger,
                 request_sender: crossbeam_channel::Sender<WorkerMessage>,
+                query_sender: crossbeam_channel::Sender<WorkerMessage>,
                 metrics: Arc<XNetEndpointMetrics>,
             }
 
@@ -182,6 +231,7 @@ impl XNetEndpoint {
                 log: log.clone(),
                 metrics: Arc::clone(&metrics),
                 request_sender: request_sender.clone(),
+                query_sender: query_sender.clone(),
             };
 
             fn ok<T>(t: T) -> Result<T, Infallible> {
@@ -195,28 +245,51 @@ impl XNetEndpoint {
                     "Serving XNet streams to peer {:?}",
                     tls_conn.peer()
                 );
+                // The handshake is usually still in progress at this point, so
+                // keep a handle to the peer identity to authenticate query calls.
+                let peer_handle = tls_conn.peer_handle();
 
                 async move {
                     let ctx = ctx.clone();
                     ok(service_fn({
                         move |request: Request<Body>| {
                             let ctx = ctx.clone();
+                            let peer = match peer_handle.get() {
+                                Some(AuthenticatedPeer::Node(node_id)) => Some(*node_id),
+                                None => None,
+                            };
 
                             async move {
                                 let _ = &ctx;
                                 let (response_sender, response_receiver) = oneshot::channel();
-                                let task = WorkerMessage::HandleRequest {
-                                    request,
-                                    response_sender,
-                                };
+                                let (sender, resource, task) =
+                                    if request.uri().path() == API_URL_QUERY {
+                                        let (peer, body) =
+                                            match read_query_request(peer, request).await {
+                                                Ok(query) => query,
+                                                Err(response) => return ok(response),
+                                            };
+                                        let task = WorkerMessage::HandleQuery {
+                                            peer,
+                                            body,
+                                            response_sender,
+                                        };
+                                        (&ctx.query_sender, RESOURCE_QUERY, task)
+                                    } else {
+                                        let task = WorkerMessage::HandleRequest {
+                                            request,
+                                            response_sender,
+                                        };
+                                        (&ctx.request_sender, RESOURCE_UNKNOWN, task)
+                                    };
 
                                 // NOTE: we must use non-blocking send here, otherwise we might
                                 // delay the event thread.
-                                if ctx.request_sender.try_send(task).is_err() {
+                                if sender.try_send(task).is_err() {
                                     ctx.metrics
                                         .request_duration
                                         .with_label_values(&[
-                                            RESOURCE_UNKNOWN,
+                                            resource,
                                             StatusCode::SERVICE_UNAVAILABLE.as_str(),
                                         ])
                                         .observe(0.0);
@@ -318,11 +391,45 @@ impl XNetEndpoint {
             });
         }
 
+        // Spawn the query call handlers, which hold a reference to the remote
+        // query handler provided by execution.
+        let query_thread_pool = ThreadPool::with_name(
+            "XNet Endpoint Query Handler".to_string(),
+            XNET_ENDPOINT_NUM_QUERY_WORKER_THREADS,
+        );
+        for _ in 0..XNET_ENDPOINT_NUM_QUERY_WORKER_THREADS {
+            let query_receiver = query_receiver.clone();
+            let handler_log = handler_log.clone();
+            let metrics = Arc::clone(&metrics);
+            let remote_query_handler = Arc::clone(&remote_query_handler);
+            query_thread_pool.execute(move || {
+                while let Ok(WorkerMessage::HandleQuery {
+                    peer,
+                    body,
+                    response_sender,
+                }) = query_receiver.recv()
+                {
+                    let response =
+                        handle_query(peer, &body, remote_query_handler.as_ref(), &metrics);
+                    response_sender.send(response).unwrap_or_else(|res| {
+                        info!(
+                            handler_log,
+                            "Failed to respond with {:?}",
+                            res.into_parts().0
+                        )
+                    });
+                }
+                debug!(handler_log, "  ...XNet Endpoint Query Handler shut down");
+            });
+        }
+
         Self {
             server_address: address,
             shutdown_notify,
             handler_thread_pool,
+            query_thread_pool,
             request_sender,
+            query_sender,
             log,
         }
     }
@@ -485,6 +592,68 @@ fn handle_stream(
     }
 }
 
+/// Checks that an `/api/v1/query` request comes from an authenticated peer and
+/// reads its body, of at most `MAX_QUERY_REQUEST_SIZE_BYTES`.
+async fn read_query_request(
+    peer: Option<NodeId>,
+    request: Request<Body>,
+) -> Result<(NodeId, Bytes), Response<Body>> {
+    let peer = peer.ok_or_else(|| forbidden("Query calls require an authenticated peer"))?;
+    if request.method() != Method::POST {
+        return Err(method_not_allowed("Expected POST"));
+    }
+
+    let too_large = || {
+        payload_too_large(format!(
+            "Query requests are limited to {} bytes",
+            MAX_QUERY_REQUEST_SIZE_BYTES
+        ))
+    };
+    let mut body = request.into_body();
+    // Reject oversized requests up front if they declare their length.
+    if body.size_hint().lower() > MAX_QUERY_REQUEST_SIZE_BYTES as u64 {
+        return Err(too_large());
+    }
+    let mut buf = Vec::new();
+    while let Some(chunk) = body.data().await {
+        let chunk =
+            chunk.map_err(|e| bad_request(format!("Failed to read request body: {}", e)))?;
+        if buf.len() + chunk.len() > MAX_QUERY_REQUEST_SIZE_BYTES {
+            return Err(too_large());
+        }
+        buf.extend_from_slice(&chunk);
+    }
+    Ok((peer, Bytes::from(buf)))
+}
+
+/// Executes a query call made by a canister on the subnet of `peer` and
+/// returns the encoded response.
+fn handle_query(
+    peer: NodeId,
+    body: &[u8],
+    remote_query_handler: &dyn RemoteQueryHandler,
+    metrics: &XNetEndpointMetrics,
+) -> Response<Body> {
+    let since = Instant::now();
+    let response = match serde_cbor::from_slice::<RemoteQueryRequest>(body) {
+        Ok(request) => match remote_query_handler.query(peer, request) {
+            Ok(response) => {
+                observe_response_size(|| cbor_response(&response), RESOURCE_QUERY, metrics)
+            }
+            Err(e @ RemoteQueryError::Unauthorized(_)) => forbidden(e.to_string()),
+            Err(e @ RemoteQueryError::CertifiedStateUnavailable)
+            | Err(e @ RemoteQueryError::Transport(_)) => service_unavailable(e.to_string()),
+        },
+        Err(e) => bad_request(format!("Invalid query request: {}", e)),
+    };
+    metrics
+        .request_duration
+        .with_label_values(&[RESOURCE_QUERY, response.status().as_str()])
+        .observe(since.elapsed().as_secs_f64());
+
+    response
+}
+
 /// Calls through to one of the `*_response` functions and observes the size of
 /// the produced response.
 fn observe_response_size<F>(f: F, resource: &str, metrics: &XNetEndpointMetrics) -> Response<Body>
@@ -512,6 +681,19 @@ pub(crate) fn json_response<R: Serialize>(r: &R) -> (Response<Body>, usize) {
     (response, size_bytes)
 }
 
+/// Serializes the response as CBOR.
+pub(crate) fn cbor_response<R: Serialize>(r: &R) -> (Response<Body>, usize) {
+    let buf = serde_cbor::to_vec(r).expect("Could not serialize response");
+    let size_bytes = buf.len();
+
+    let response = Response::builder()
+        .header("Content-Type", "application/cbor")
+        .body(buf.into())
+        .unwrap();
+
+    (response, size_bytes)
+}
+
 /// Serializes the response as Protobuf.
 pub(crate) fn proto_response<R, M>(r: R) -> (Response<Body>, usize)
 where
@@ -548,6 +730,14 @@ fn bad_request<T: Into<Body>>(msg: T) -> Response<Body> {
         .unwrap()
 }
 
+/// Produces a 403 Forbidden response with the given content.
+fn forbidden<T: Into<Body>>(msg: T) -> Response<Body> {
+    Response::builder()
+        .status(StatusCode::FORBIDDEN)
+        .body(msg.into())
+        .unwrap()
+}
+
 /// Produces a 404 Not Found response with the given content.
 fn not_found<T: Into<Body>>(msg: T) -> Response<Body> {
     Response::builder()
@@ -556,6 +746,22 @@ fn not_found<T: Into<Body>>(msg: T) -> Response<Body> {
         .unwrap()
 }
 
+/// Produces a 405 Method Not Allowed response with the given content.
+fn method_not_allowed<T: Into<Body>>(msg: T) -> Response<Body> {
+    Response::builder()
+        .status(StatusCode::METHOD_NOT_ALLOWED)
+        .body(msg.into())
+        .unwrap()
+}
+
+/// Produces a 413 Payload Too Large response with the given content.
+fn payload_too_large<T: Into<Body>>(msg: T) -> Response<Body> {
+    Response::builder()
+        .status(StatusCode::PAYLOAD_TOO_LARGE)
+        .body(msg.into())
+        .unwrap()
+}
+
 /// Produces a 416 Range Not Satisfiable response with the given content.
 fn range_not_satisfiable<T: Into<Body>>(msg: T) -> Response<Body> {
     Response::builder()
@@ -564,6 +770,14 @@ fn range_not_satisfiable<T: Into<Body>>(msg: T) -> Response<Body> {
         .unwrap()
 }
 
+/// Produces a 503 Service Unavailable response with the given content.
+fn service_unavailable<T: Into<Body>>(msg: T) -> Response<Body> {
+    Response::builder()
+        .status(StatusCode::SERVICE_UNAVAILABLE)
+        .body(msg.into())
+        .unwrap()
+}
+
 /// The socket address for `XNetEndpoint` to listen on.
 #[derive(Debug, PartialEq, Eq)]
 pub struct XNetEndpointConfig {
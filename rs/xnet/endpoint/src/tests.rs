@@ -1,5 +1,6 @@
 use super::*;
 use bytes::Bytes;
+use ic_interfaces::execution_environment::RemoteQueryResponse;
 use ic_interfaces_registry_mocks::MockRegistryClient;
 use ic_interfaces_state_manager::{CertificationScope, StateManager};
 use ic_protobuf::{messaging::xnet::v1 as pb, proxy::ProtoProxy};
@@ -8,7 +9,7 @@ use ic_test_utilities::{
     crypto::fake_tls_handshake::FakeTlsHandshake,
     state_manager::FakeStateManager,
     types::{
-        ids::{canister_test_id, SUBNET_6, SUBNET_7},
+        ids::{canister_test_id, node_test_id, SUBNET_6, SUBNET_7},
         messages::RequestBuilder,
     },
 };
@@ -16,7 +17,11 @@ use ic_test_utilities_logger::with_test_replica_logger;
 use ic_test_utilities_metrics::{
     fetch_histogram_stats, fetch_histogram_vec_count, metric_vec, HistogramStats, MetricVec,
 };
-use ic_types::{messages::CallbackId, xnet::StreamIndexedQueue, Height, SubnetId};
+use ic_types::{
+    messages::{CallbackId, Payload},
+    xnet::StreamIndexedQueue,
+    Height, NumInstructions, SubnetId,
+};
 use maplit::btreemap;
 use std::sync::Barrier;
 use url::Url;
@@ -35,6 +40,7 @@ pub(crate) struct EndpointTestFixture {
     pub registry_client: Arc<MockRegistryClient>,
     pub metrics: MetricsRegistry,
     pub tls_handshake: Arc<dyn TlsHandshake + Send + Sync>,
+    pub remote_query_handler: Arc<FakeRemoteQueryHandler>,
 }
 
 impl EndpointTestFixture {
@@ -69,7 +75,44 @@ impl Default for EndpointTestFixture {
             state_manager: Arc::new(FakeStateManager::new()),
             registry_client: Arc::new(MockRegistryClient::new()),
             tls_handshake: Arc::new(FakeTlsHandshake::new()),
+            remote_query_handler: Arc::new(FakeRemoteQueryHandler),
+        }
+    }
+}
+
+/// The only node allowed to make query calls to `FakeRemoteQueryHandler`.
+const AUTHORIZED_NODE: u64 = 5;
+
+/// A `RemoteQueryHandler` that replies to query calls from `AUTHORIZED_NODE`
+/// with the request payload.
+pub(crate) struct FakeRemoteQueryHandler;
+
+impl RemoteQueryHandler for FakeRemoteQueryHandler {
+    fn query(
+        &self,
+        peer: NodeId,
+        request: RemoteQueryRequest,
+    ) -> Result<RemoteQueryResponse, RemoteQueryError> {
+        if peer != node_test_id(AUTHORIZED_NODE) {
+            return Err(RemoteQueryError::Unauthorized(peer));
         }
+        Ok(RemoteQueryResponse {
+            payload: Payload::Data(request.method_payload),
+            instructions_used: NumInstructions::from(1_000),
+        })
+    }
+}
+
+fn remote_query_request() -> RemoteQueryRequest {
+    RemoteQueryRequest {
+        sender: canister_test_id(SRC_CANISTER),
+        receiver: canister_test_id(DST_CANISTER),
+        method_name: "query".to_string(),
+        method_payload: b"ping".to_vec(),
+        instruction_limit: NumInstructions::from(1_000_000),
+        max_call_graph_depth: 6,
+        time_limit: std::time::Duration::from_secs(1),
+        call_stack: vec![canister_test_id(SRC_CANISTER)],
     }
 }
 
@@ -85,6 +128,7 @@ fn query_streams() {
         let xnet_endpoint = XNetEndpoint::new(
             rt.handle().clone(),
             fixture.state_manager.clone(),
+            fixture.remote_query_handler.clone(),
             fixture.tls_handshake.clone(),
             fixture.registry_client.clone(),
             Default::default(),
@@ -120,6 +164,7 @@ fn query_stream() {
         let xnet_endpoint = XNetEndpoint::new(
             rt.handle().clone(),
             fixture.state_manager.clone(),
+            fixture.remote_query_handler.clone(),
             fixture.tls_handshake.clone(),
             fixture.registry_client.clone(),
             Default::default(),
@@ -184,6 +229,7 @@ fn query_stream_parallel() {
         let xnet_endpoint = XNetEndpoint::new(
             endpoint_rt.handle().clone(),
             fixture.state_manager.clone(),
+            fixture.remote_query_handler.clone(),
             fixture.tls_handshake.clone(),
             fixture.registry_client.clone(),
             Default::default(),
@@ -586,6 +632,132 @@ fn get_stream_for_testing() -> Stream {
     stream
 }
 
+/// Tests executing a query call from an authorized node.
+#[tokio::test]
+async fn handle_query() {
+    let fixture = EndpointTestFixture::default();
+    let body = serde_cbor::to_vec(&remote_query_request()).unwrap();
+
+    let response = super::handle_query(
+        node_test_id(AUTHORIZED_NODE),
+        &body,
+        fixture.remote_query_handler.as_ref(),
+        &XNetEndpointMetrics::new(&fixture.metrics),
+    );
+    let (parsed_status, body) = parse_response(response).await;
+
+    assert_eq!(200, parsed_status);
+    assert_eq!(
+        RemoteQueryResponse {
+            payload: Payload::Data(b"ping".to_vec()),
+            instructions_used: NumInstructions::from(1_000),
+        },
+        serde_cbor::from_slice(&body).unwrap()
+    );
+    assert_eq!(
+        metric_vec(&[(&[("resource", "query"), ("status", "200")], 1)]),
+        fixture.request_counts()
+    );
+    assert_eq!(
+        metric_vec(&[(&[("resource", "query")], 1)]),
+        fixture.response_size_counts()
+    );
+}
+
+/// Tests that query calls from other nodes are rejected.
+#[tokio::test]
+async fn handle_query_unauthorized() {
+    let fixture = EndpointTestFixture::default();
+    let body = serde_cbor::to_vec(&remote_query_request()).unwrap();
+
+    let response = super::handle_query(
+        node_test_id(AUTHORIZED_NODE + 1),
+        &body,
+        fixture.remote_query_handler.as_ref(),
+        &XNetEndpointMetrics::new(&fixture.metrics),
+    );
+    let (parsed_status, _) = parse_response(response).await;
+
+    assert_eq!(403, parsed_status);
+    assert_eq!(
+        metric_vec(&[(&[("resource", "query"), ("status", "403")], 1)]),
+        fixture.request_counts()
+    );
+    assert_eq!(MetricVec::new(), fixture.response_size_counts());
+}
+
+/// Tests that malformed query calls are rejected.
+#[tokio::test]
+async fn handle_query_invalid_request() {
+    let fixture = EndpointTestFixture::default();
+
+    let response = super::handle_query(
+        node_test_id(AUTHORIZED_NODE),
+        b"garbage",
+        fixture.remote_query_handler.as_ref(),
+        &XNetEndpointMetrics::new(&fixture.metrics),
+    );
+    let (parsed_status, _) = parse_response(response).await;
+
+    assert_eq!(400, parsed_status);
+    assert_eq!(
+        metric_vec(&[(&[("resource", "query"), ("status", "400")], 1)]),
+        fixture.request_counts()
+    );
+}
+
+/// Tests that query calls over connections without an authenticated peer are
+/// rejected.
+///
+/// Heavyweight test that starts an `XNetEndpoint` and queries it over HTTP.
+#[test]
+fn query_without_authenticated_peer() {
+    with_test_replica_logger(|log| {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let fixture = EndpointTestFixture::with_replicated_state();
+
+        let xnet_endpoint = XNetEndpoint::new(
+            rt.handle().clone(),
+            fixture.state_manager.clone(),
+            fixture.remote_query_handler.clone(),
+            fixture.tls_handshake.clone(),
+            fixture.registry_client.clone(),
+            Default::default(),
+            &fixture.metrics,
+            log,
+        );
+
+        let url = http_url(API_URL_QUERY, &xnet_endpoint);
+        let body = serde_cbor::to_vec(&remote_query_request()).unwrap();
+        let status = rt.block_on(async move {
+            reqwest::Client::new()
+                .post(url)
+                .body(body)
+                .send()
+                .await
+                .expect("couldn't execute a POST request")
+                .status()
+        });
+
+        assert_eq!(reqwest::StatusCode::FORBIDDEN, status);
+    });
+}
+
+/// Tests that query requests larger than `MAX_QUERY_REQUEST_SIZE_BYTES` are
+/// rejected.
+#[tokio::test]
+async fn read_query_request_too_large() {
+    let request = Request::post(API_URL_QUERY)
+        .body(Body::from(vec![0; MAX_QUERY_REQUEST_SIZE_BYTES + 1]))
+        .unwrap();
+
+    let response = read_query_request(Some(node_test_id(AUTHORIZED_NODE)), request)
+        .await
+        .unwrap_err();
+
+    assert_eq!(StatusCode::PAYLOAD_TOO_LARGE, response.status());
+}
+
 /// Queries the given URL for the given path on a running `XNetEndpoint`.
 fn http_url(path: &str, xnet_endpoint: &XNetEndpoint) -> String {
     let url = format!("http://localhost:{}{}", xnet_endpoint.server_port(), path);
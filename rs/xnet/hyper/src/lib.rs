@@ -17,7 +17,7 @@ use std::convert::TryFrom;
 use std::future::Future;
 use std::net::SocketAddr;
 use std::pin::Pin;
-use std::sync::Arc;
+use std::sync::{Arc, OnceLock};
 use std::task::{Context, Poll};
 use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
 use tokio::net::{TcpListener, TcpStream};
@@ -76,9 +76,44 @@ fn io_err(e: impl std::error::Error + Send + Sync + 'static) -> std::io::Error {
 }
 
 /// A TLS connection.
-pub struct TlsConnection(ConnectionState);
+pub struct TlsConnection(ConnectionState, PeerHandle);
+
+/// A handle to the identity of the peer of a TLS connection, which is only
+/// known once the TLS handshake has completed.
+///
+/// Unlike [TlsConnection::peer], the handle can be obtained right after the
+/// connection was accepted, e.g. when setting up the service handling its
+/// requests.
+#[derive(Clone, Default)]
+pub struct PeerHandle(Arc<OnceLock<AuthenticatedPeer>>);
+
+impl PeerHandle {
+    /// Returns the identity of the connected peer if the TLS handshake
+    /// completed successfully.
+    pub fn get(&self) -> Option<&AuthenticatedPeer> {
+        self.0.get()
+    }
+
+    fn set(&self, peer: AuthenticatedPeer) {
+        // The handshake completes at most once per connection.
+        let _ = self.0.set(peer);
+    }
+}
 
 impl TlsConnection {
+    fn new(state: ConnectionState) -> Self {
+        let peer_handle = PeerHandle::default();
+        if let ConnectionState::Ready { peer, .. } = &state {
+            peer_handle.set(peer.clone());
+        }
+        Self(state, peer_handle)
+    }
+
+    /// Returns a handle to the identity of the connected peer.
+    pub fn peer_handle(&self) -> PeerHandle {
+        self.1.clone()
+    }
+
     /// Returns the identity of the connected peer if the TLS
     /// handshake completed successfully. Returns None if the handshake is not
     /// completed yet or failed.
@@ -107,6 +142,7 @@ impl TlsConnection {
                     // into TlsConnection and cause another poll on
                     // the `fut` future, which is not allowed for
                     // futures that returned `Ready`.
+                    self.1.set(peer.clone());
                     self.0 = ConnectionState::Ready { stream, peer };
                     if let ConnectionState::Ready { ref mut stream, .. } = self.0 {
                         f(Pin::new(stream), cx)
@@ -206,7 +242,7 @@ impl Accept for TlsAccept {
         Pin::new(&mut self.inner).poll_accept(cx).map(|opt_res| {
             opt_res.map(|res| match res {
                 Ok(conn) => match self.connection_type {
-                    ConnectionType::Raw => Ok(TlsConnection(ConnectionState::Unencrypted(
+                    ConnectionType::Raw => Ok(TlsConnection::new(ConnectionState::Unencrypted(
                         conn.into_inner(),
                     ))),
                     ConnectionType::Tls => {
@@ -220,7 +256,9 @@ impl Accept for TlsAccept {
                             )
                             .await
                         };
-                        Ok(TlsConnection(ConnectionState::Handshake(Box::pin(future))))
+                        Ok(TlsConnection::new(ConnectionState::Handshake(Box::pin(
+                            future,
+                        ))))
                     }
                 },
                 Err(err) => Err(Box::new(err) as Box<_>),
@@ -374,7 +412,9 @@ impl Service<Uri> for TlsConnector {
         let future = async move {
             let tcp_stream = connecting.await.map_err(box_err)?;
             match connection_type {
-                ConnectionType::Raw => Ok(TlsConnection(ConnectionState::Unencrypted(tcp_stream))),
+                ConnectionType::Raw => {
+                    Ok(TlsConnection::new(ConnectionState::Unencrypted(tcp_stream)))
+                }
                 ConnectionType::Tls => {
                     let tls_stream = tls
                         .perform_tls_client_handshake(
@@ -384,7 +424,7 @@ impl Service<Uri> for TlsConnector {
                         )
                         .await
                         .map_err(box_err)?;
-                    Ok(TlsConnection(ConnectionState::Ready {
+                    Ok(TlsConnection::new(ConnectionState::Ready {
                         stream: tls_stream,
                         peer: AuthenticatedPeer::Node(xnet_auth.node_id),
                     }))
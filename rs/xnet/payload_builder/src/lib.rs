@@ -1,5 +1,6 @@
 pub mod certified_slice_pool;
 mod proximity;
+mod remote_query;
 
 #[cfg(test)]
 mod impl_tests;
@@ -49,6 +50,7 @@ use ic_xnet_uri::XNetAuthority;
 use prometheus::{Histogram, HistogramVec, IntCounter, IntCounterVec, IntGauge};
 pub use proximity::{GenRangeFn, ProximityMap};
 use rand::{rngs::StdRng, thread_rng, Rng};
+pub use remote_query::XNetRemoteQueryClient;
 use std::{
     collections::{BTreeMap, VecDeque},
     net::SocketAddr,
@@ -0,0 +1,195 @@
+//! Forwarding of query calls to canisters hosted on other subnets, via the
+//! `/api/v1/query` API of their `XNetEndpoints`.
+
+use hyper::{client::Client, Body, Request, StatusCode, Uri};
+use ic_crypto_tls_interfaces::TlsHandshake;
+use ic_interfaces::execution_environment::{
+    RemoteQueryClient, RemoteQueryError, RemoteQueryFuture, RemoteQueryRequest, RemoteQueryResponse,
+};
+use ic_interfaces_registry::RegistryClient;
+use ic_metrics::{buckets::decimal_buckets, MetricsRegistry};
+use ic_registry_client_helpers::{node::NodeRegistry, subnet::SubnetRegistry};
+use ic_types::{NodeId, RegistryVersion, SubnetId};
+use ic_xnet_hyper::{ExecuteOnRuntime, TlsConnector};
+use ic_xnet_uri::XNetAuthority;
+use prometheus::HistogramVec;
+use rand::{thread_rng, Rng};
+use std::{net::SocketAddr, sync::Arc, time::Duration, time::Instant};
+use tokio::runtime;
+
+pub const METRIC_REMOTE_QUERY_DURATION: &str = "xnet_remote_query_duration_seconds";
+
+const STATUS_SUCCESS: &str = "success";
+const STATUS_ERROR: &str = "error";
+
+/// A `RemoteQueryClient` that sends query calls to a random node of the
+/// target subnet, over a TLS connection authenticated via the registry.
+///
+/// Query calls are driven by the provided runtime, so the returned futures
+/// make progress regardless of the executor they are polled on.
+pub struct XNetRemoteQueryClient {
+    /// An HTTP client to be used for querying.
+    http_client: Client<TlsConnector, Body>,
+
+    /// Used for looking up the nodes of the target subnet and their
+    /// `XNetEndpoint` addresses.
+    registry: Arc<dyn RegistryClient>,
+
+    /// The runtime driving the HTTP client.
+    runtime_handle: runtime::Handle,
+
+    /// Query call durations, by status.
+    query_duration: HistogramVec,
+}
+
+impl XNetRemoteQueryClient {
+    pub fn new(
+        registry: Arc<dyn RegistryClient>,
+        tls: Arc<dyn TlsHandshake + Send + Sync>,
+        runtime_handle: runtime::Handle,
+        metrics_registry: &MetricsRegistry,
+    ) -> Self {
+        let http_client: Client<TlsConnector, _> = Client::builder()
+            .pool_idle_timeout(Some(Duration::from_secs(600)))
+            .executor(ExecuteOnRuntime(runtime_handle.clone()))
+            .build(
+                #[cfg(not(test))]
+                TlsConnector::new(tls),
+                #[cfg(test)]
+                TlsConnector::new_for_tests(tls),
+            );
+        let query_duration = metrics_registry.histogram_vec(
+            METRIC_REMOTE_QUERY_DURATION,
+            "The time it took to execute a query call on another subnet, by status.",
+            // 0.1ms - 50s
+            decimal_buckets(-4, 1),
+            &["status"],
+        );
+
+        Self {
+            http_client,
+            registry,
+            runtime_handle,
+            query_duration,
+        }
+    }
+
+    /// Returns the `/api/v1/query` URL of a random node on `subnet_id`.
+    fn query_url(&self, subnet_id: SubnetId, version: RegistryVersion) -> Result<Uri, String> {
+        let nodes = self
+            .registry
+            .get_node_ids_on_subnet(subnet_id, version)
+            .map_err(|e| format!("Failed to get the nodes of subnet {}: {}", subnet_id, e))?
+            .filter(|nodes| !nodes.is_empty())
+            .ok_or_else(|| format!("Subnet {} has no nodes", subnet_id))?;
+        let node_id = nodes[thread_rng().gen_range(0..nodes.len())];
+
+        let authority = XNetAuthority {
+            node_id,
+            registry_version: version,
+            address: self.xnet_address(node_id, version)?,
+        };
+        let url = format!("http://{}/api/v1/query", authority);
+        url.parse::<Uri>()
+            .map_err(|e| format!("Invalid URL {}: {}", url, e))
+    }
+
+    /// Returns the address of the `XNetEndpoint` of the given node.
+    fn xnet_address(
+        &self,
+        node_id: NodeId,
+        version: RegistryVersion,
+    ) -> Result<SocketAddr, String> {
+        let xnet_endpoint = self
+            .registry
+            .get_node_record(node_id, version)
+            .map_err(|e| format!("Failed to get the record of node {}: {}", node_id, e))?
+            .and_then(|node_record| node_record.xnet)
+            .ok_or_else(|| format!("Node {} has no XNet endpoint", node_id))?;
+
+        let ip_addr = xnet_endpoint
+            .ip_addr
+            .parse()
+            .map_err(|_| format!("Node {}: bad ip addr {}", node_id, xnet_endpoint.ip_addr))?;
+        let port = u16::try_from(xnet_endpoint.port)
+            .map_err(|_| format!("Node {}: bad port {}", node_id, xnet_endpoint.port))?;
+        Ok(SocketAddr::new(ip_addr, port))
+    }
+}
+
+impl RemoteQueryClient for XNetRemoteQueryClient {
+    fn query(&self, subnet_id: SubnetId, request: RemoteQueryRequest) -> RemoteQueryFuture {
+        let version = self.registry.get_latest_version();
+        let url = self.query_url(subnet_id, version);
+        let http_client = self.http_client.clone();
+        let query_duration = self.query_duration.clone();
+
+        let task = self.runtime_handle.spawn(async move {
+            let since = Instant::now();
+            let result = match url {
+                Ok(url) => {
+                    tokio::time::timeout(request.time_limit, send(&http_client, url, &request))
+                        .await
+                        .unwrap_or_else(|_| {
+                            Err(RemoteQueryError::Transport(
+                                "The query call timed out".to_string(),
+                            ))
+                        })
+                }
+                Err(err) => Err(RemoteQueryError::Transport(err)),
+            };
+
+            let status = if result.is_ok() {
+                STATUS_SUCCESS
+            } else {
+                STATUS_ERROR
+            };
+            query_duration
+                .with_label_values(&[status])
+                .observe(since.elapsed().as_secs_f64());
+            result
+        });
+
+        Box::pin(async move {
+            task.await.unwrap_or_else(|err| {
+                Err(RemoteQueryError::Transport(format!(
+                    "The query call task failed: {}",
+                    err
+                )))
+            })
+        })
+    }
+}
+
+/// Sends the encoded query call to `url` and decodes the response.
+async fn send(
+    http_client: &Client<TlsConnector, Body>,
+    url: Uri,
+    request: &RemoteQueryRequest,
+) -> Result<RemoteQueryResponse, RemoteQueryError> {
+    let body = serde_cbor::to_vec(request)
+        .map_err(|e| RemoteQueryError::Transport(format!("Failed to encode request: {}", e)))?;
+    let http_request = Request::post(url)
+        .header("Content-Type", "application/cbor")
+        .body(Body::from(body))
+        .map_err(|e| RemoteQueryError::Transport(e.to_string()))?;
+
+    let response = http_client
+        .request(http_request)
+        .await
+        .map_err(|e| RemoteQueryError::Transport(e.to_string()))?;
+    let status = response.status();
+    let bytes = hyper::body::to_bytes(response.into_body())
+        .await
+        .map_err(|e| RemoteQueryError::Transport(e.to_string()))?;
+
+    match status {
+        StatusCode::OK => serde_cbor::from_slice(&bytes)
+            .map_err(|e| RemoteQueryError::Transport(format!("Failed to decode response: {}", e))),
+        _ => Err(RemoteQueryError::Transport(format!(
+            "{}: {}",
+            status,
+            String::from_utf8_lossy(&bytes)
+        ))),
+    }
+}